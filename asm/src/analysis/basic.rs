@@ -0,0 +1,150 @@
+use crate::analysis::{AnalyzedInsn, Interpreter, Value};
+use crate::impls::method_return_type;
+use crate::node::values::ConstValue;
+use crate::node::InsnNode;
+use crate::Opcodes;
+
+/// The value of [BasicInterpreter], all object types are just [BasicValue::Reference].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BasicValue {
+    /// An unassigned local, the second slot of a long or double, or merged incompatible values.
+    Uninitialized,
+    Int,
+    Float,
+    Long,
+    Double,
+    Reference,
+    ReturnAddress,
+}
+
+impl Value for BasicValue {
+    fn size(&self) -> usize {
+        match self {
+            BasicValue::Long | BasicValue::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl BasicValue {
+    /// The value of the field descriptor `desc`, [None] for `V`.
+    pub fn from_desc(desc: &str) -> Option<Self> {
+        let value = match desc.as_bytes().first()? {
+            b'Z' | b'B' | b'C' | b'S' | b'I' => BasicValue::Int,
+            b'F' => BasicValue::Float,
+            b'J' => BasicValue::Long,
+            b'D' => BasicValue::Double,
+            b'L' | b'[' => BasicValue::Reference,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// The value produced by `insn` which only depends on the opcode and the operands of it,
+    /// [None] if the instruction produces nothing.
+    pub fn of_insn(insn: &InsnNode) -> Option<Self> {
+        let opcode = insn.opcode();
+        let value = match opcode {
+            Opcodes::ACONST_NULL | Opcodes::AALOAD | Opcodes::NEW | Opcodes::NEWARRAY |
+            Opcodes::ANEWARRAY | Opcodes::CHECKCAST | Opcodes::MULTIANEWARRAY => BasicValue::Reference,
+            Opcodes::ICONST_M1..=Opcodes::ICONST_5 | Opcodes::BIPUSH | Opcodes::SIPUSH |
+            Opcodes::IINC | Opcodes::ARRAYLENGTH | Opcodes::INSTANCEOF |
+            Opcodes::LCMP..=Opcodes::DCMPG => BasicValue::Int,
+            Opcodes::LCONST_0 | Opcodes::LCONST_1 => BasicValue::Long,
+            Opcodes::FCONST_0..=Opcodes::FCONST_2 => BasicValue::Float,
+            Opcodes::DCONST_0 | Opcodes::DCONST_1 => BasicValue::Double,
+            Opcodes::JSR | Opcodes::JSR_W => BasicValue::ReturnAddress,
+            Opcodes::IALOAD | Opcodes::BALOAD | Opcodes::CALOAD | Opcodes::SALOAD => BasicValue::Int,
+            Opcodes::LALOAD => BasicValue::Long,
+            Opcodes::FALOAD => BasicValue::Float,
+            Opcodes::DALOAD => BasicValue::Double,
+            // arithmetic and negations are grouped by int, long, float and double.
+            Opcodes::IADD..=Opcodes::DNEG => match (opcode - Opcodes::IADD) % 4 {
+                0 => BasicValue::Int,
+                1 => BasicValue::Long,
+                2 => BasicValue::Float,
+                _ => BasicValue::Double,
+            },
+            Opcodes::ISHL..=Opcodes::LXOR => {
+                if (opcode - Opcodes::ISHL).is_multiple_of(2) { BasicValue::Int } else { BasicValue::Long }
+            }
+            Opcodes::L2I | Opcodes::F2I | Opcodes::D2I | Opcodes::I2B | Opcodes::I2C | Opcodes::I2S => BasicValue::Int,
+            Opcodes::I2L | Opcodes::F2L | Opcodes::D2L => BasicValue::Long,
+            Opcodes::I2F | Opcodes::L2F | Opcodes::D2F => BasicValue::Float,
+            Opcodes::I2D | Opcodes::L2D | Opcodes::F2D => BasicValue::Double,
+            _ => return match insn {
+                InsnNode::LdcInsnNode(value) => match &**value {
+                    ConstValue::Integer(_) => Some(BasicValue::Int),
+                    ConstValue::Float(_) => Some(BasicValue::Float),
+                    ConstValue::Long(_) => Some(BasicValue::Long),
+                    ConstValue::Double(_) => Some(BasicValue::Double),
+                    ConstValue::Dynamic { desc, .. } => BasicValue::from_desc(desc),
+                    _ => Some(BasicValue::Reference),
+                },
+                InsnNode::FieldInsnNode { opcode: Opcodes::GETSTATIC | Opcodes::GETFIELD, desc, .. } => {
+                    BasicValue::from_desc(desc)
+                }
+                InsnNode::MethodInsnNode { desc, .. } => BasicValue::from_desc(method_return_type(desc)),
+                InsnNode::InvokeDynamicInsnNode(const_dynamic) => {
+                    BasicValue::from_desc(method_return_type(&const_dynamic.desc))
+                }
+                _ => None,
+            },
+        };
+        Some(value)
+    }
+}
+
+/// Computes the basic kind of values without any checks, like ASM's `BasicInterpreter`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BasicInterpreter;
+
+impl Interpreter for BasicInterpreter {
+    type Value = BasicValue;
+
+    fn new_value(&self, desc: &str) -> Option<BasicValue> {
+        BasicValue::from_desc(desc)
+    }
+
+    fn new_empty_value(&self) -> BasicValue {
+        BasicValue::Uninitialized
+    }
+
+    fn new_operation(&self, insn: AnalyzedInsn) -> Result<BasicValue, String> {
+        BasicValue::of_insn(insn.node).ok_or_else(|| format!("unexpected instruction: {:?}", insn.node))
+    }
+
+    fn copy_operation(&self, _insn: AnalyzedInsn, value: &BasicValue) -> Result<BasicValue, String> {
+        Ok(*value)
+    }
+
+    fn unary_operation(&self, insn: AnalyzedInsn, _value: &BasicValue) -> Result<Option<BasicValue>, String> {
+        Ok(BasicValue::of_insn(insn.node))
+    }
+
+    fn binary_operation(
+        &self, insn: AnalyzedInsn, _value1: &BasicValue, _value2: &BasicValue,
+    ) -> Result<Option<BasicValue>, String> {
+        Ok(BasicValue::of_insn(insn.node))
+    }
+
+    fn ternary_operation(
+        &self, _insn: AnalyzedInsn, _value1: &BasicValue, _value2: &BasicValue, _value3: &BasicValue,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn nary_operation(&self, insn: AnalyzedInsn, _values: &[BasicValue]) -> Result<Option<BasicValue>, String> {
+        Ok(BasicValue::of_insn(insn.node))
+    }
+
+    fn return_operation(
+        &self, _insn: AnalyzedInsn, _value: &BasicValue, _expected: &BasicValue,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn merge(&self, value1: &BasicValue, value2: &BasicValue) -> BasicValue {
+        if value1 == value2 { *value1 } else { BasicValue::Uninitialized }
+    }
+}
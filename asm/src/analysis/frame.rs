@@ -0,0 +1,257 @@
+use crate::analysis::{AnalyzedInsn, Interpreter, Value};
+use crate::impls::{method_arg_types, method_return_type};
+use crate::node::InsnNode;
+use crate::Opcodes;
+
+/// The values of local variables and operand stack entries before an instruction.
+///
+/// Locals are indexed by slots, a long or double takes two slots and the second one holds
+/// the empty value of the [Interpreter]. The stack keeps one entry per value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame<V> {
+    pub locals: Vec<V>,
+    pub stack: Vec<V>,
+    max_stack: usize,
+    /// The value of the method's return type, [None] for `void` methods.
+    return_value: Option<V>,
+}
+
+impl<V: Value> Frame<V> {
+    pub fn new(locals: Vec<V>, max_stack: usize, return_value: Option<V>) -> Self {
+        Frame { locals, stack: vec![], max_stack, return_value }
+    }
+
+    /// A frame of the same method with other locals and stack.
+    pub(crate) fn with_stack(&self, locals: Vec<V>, stack: Vec<V>) -> Self {
+        Frame { locals, stack, max_stack: self.max_stack, return_value: self.return_value.clone() }
+    }
+
+    pub fn local(&self, index: usize) -> Option<&V> {
+        self.locals.get(index)
+    }
+
+    /// The value on the top of the operand stack.
+    pub fn top(&self) -> Option<&V> {
+        self.stack.last()
+    }
+
+    /// Slots taken by the operand stack.
+    pub fn stack_size(&self) -> usize {
+        self.stack.iter().map(Value::size).sum()
+    }
+
+    fn pop(&mut self) -> Result<V, String> {
+        self.stack.pop().ok_or_else(|| "operand stack underflow".to_string())
+    }
+
+    fn push(&mut self, value: V) -> Result<(), String> {
+        self.stack.push(value);
+        if self.stack_size() > self.max_stack {
+            return Err(format!("operand stack overflow, max stack: {}", self.max_stack));
+        }
+        Ok(())
+    }
+
+    fn push_result(&mut self, value: Option<V>) -> Result<(), String> {
+        let value = value.ok_or_else(|| "the interpreter produced no value".to_string())?;
+        self.push(value)
+    }
+
+    /// Pops values taking exactly `words` slots, in the order they were pushed.
+    fn pop_words(&mut self, words: usize) -> Result<Vec<V>, String> {
+        let mut values = vec![];
+        let mut size = 0;
+        while size < words {
+            let value = self.pop()?;
+            size += value.size();
+            values.push(value);
+        }
+        if size != words {
+            return Err("cannot split a long or double value on the operand stack".to_string());
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    fn get_local(&self, index: usize) -> Result<&V, String> {
+        self.locals.get(index).ok_or_else(|| format!("local variable {index} out of range"))
+    }
+
+    fn store<I: Interpreter<Value=V>>(&mut self, index: usize, value: V, interpreter: &I) -> Result<(), String> {
+        let size = value.size();
+        if index + size > self.locals.len() {
+            return Err(format!("local variable {index} out of range"));
+        }
+        // storing into the second half of a long or double invalidates it.
+        if index > 0 && self.locals[index - 1].size() == 2 {
+            self.locals[index - 1] = interpreter.new_empty_value();
+        }
+        self.locals[index] = value;
+        if size == 2 {
+            self.locals[index + 1] = interpreter.new_empty_value();
+        }
+        Ok(())
+    }
+
+    /// The `DUP` family, duplicates the top `words` slots and inserts the copies below the next `under` slots.
+    fn dup<I: Interpreter<Value=V>>(
+        &mut self, insn: AnalyzedInsn, words: usize, under: usize, interpreter: &I,
+    ) -> Result<(), String> {
+        let top = self.pop_words(words)?;
+        let below = self.pop_words(under)?;
+        for value in &top {
+            self.push(interpreter.copy_operation(insn, value)?)?;
+        }
+        for value in below.into_iter().chain(top) {
+            self.push(value)?;
+        }
+        Ok(())
+    }
+
+    /// Simulates the execution of `insn` on this frame with the `interpreter`.
+    pub fn execute<I: Interpreter<Value=V>>(&mut self, insn: AnalyzedInsn, interpreter: &I) -> Result<(), String> {
+        let opcode = insn.opcode();
+        match opcode {
+            Opcodes::NOP | Opcodes::GOTO | Opcodes::GOTO_W | Opcodes::RET => {}
+            Opcodes::ACONST_NULL..=Opcodes::LDC2_W | Opcodes::JSR | Opcodes::JSR_W |
+            Opcodes::GETSTATIC | Opcodes::NEW => {
+                let value = interpreter.new_operation(insn)?;
+                self.push(value)?;
+            }
+            Opcodes::ILOAD..=Opcodes::ALOAD_3 => {
+                let index = var_index(insn.node, Opcodes::ILOAD_0);
+                let value = interpreter.copy_operation(insn, self.get_local(index)?)?;
+                self.push(value)?;
+            }
+            Opcodes::ISTORE..=Opcodes::ASTORE_3 => {
+                let index = var_index(insn.node, Opcodes::ISTORE_0);
+                let value = self.pop()?;
+                let value = interpreter.copy_operation(insn, &value)?;
+                self.store(index, value, interpreter)?;
+            }
+            Opcodes::IALOAD..=Opcodes::SALOAD | Opcodes::IADD..=Opcodes::DREM |
+            Opcodes::ISHL..=Opcodes::LXOR | Opcodes::LCMP..=Opcodes::DCMPG => {
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                let result = interpreter.binary_operation(insn, &value1, &value2)?;
+                self.push_result(result)?;
+            }
+            Opcodes::IASTORE..=Opcodes::SASTORE => {
+                let value3 = self.pop()?;
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                interpreter.ternary_operation(insn, &value1, &value2, &value3)?;
+            }
+            Opcodes::POP => {
+                self.pop_words(1)?;
+            }
+            Opcodes::POP2 => {
+                self.pop_words(2)?;
+            }
+            Opcodes::DUP => self.dup(insn, 1, 0, interpreter)?,
+            Opcodes::DUP_X1 => self.dup(insn, 1, 1, interpreter)?,
+            Opcodes::DUP_X2 => self.dup(insn, 1, 2, interpreter)?,
+            Opcodes::DUP2 => self.dup(insn, 2, 0, interpreter)?,
+            Opcodes::DUP2_X1 => self.dup(insn, 2, 1, interpreter)?,
+            Opcodes::DUP2_X2 => self.dup(insn, 2, 2, interpreter)?,
+            Opcodes::SWAP => {
+                let value2 = self.pop_words(1)?.remove(0);
+                let value1 = self.pop_words(1)?.remove(0);
+                self.push(interpreter.copy_operation(insn, &value2)?)?;
+                self.push(interpreter.copy_operation(insn, &value1)?)?;
+            }
+            Opcodes::INEG..=Opcodes::DNEG | Opcodes::I2L..=Opcodes::I2S |
+            Opcodes::GETFIELD | Opcodes::NEWARRAY | Opcodes::ANEWARRAY | Opcodes::ARRAYLENGTH |
+            Opcodes::CHECKCAST | Opcodes::INSTANCEOF => {
+                let value = self.pop()?;
+                let result = interpreter.unary_operation(insn, &value)?;
+                self.push_result(result)?;
+            }
+            Opcodes::IINC => {
+                let index = match insn.node {
+                    InsnNode::IIncInsnNode { var, .. } => *var as usize,
+                    _ => 0,
+                };
+                let value = interpreter.unary_operation(insn, self.get_local(index)?)?
+                    .ok_or_else(|| "the interpreter produced no value".to_string())?;
+                self.store(index, value, interpreter)?;
+            }
+            Opcodes::IFEQ..=Opcodes::IFLE | Opcodes::IFNULL | Opcodes::IFNONNULL |
+            Opcodes::TABLESWITCH | Opcodes::LOOKUPSWITCH | Opcodes::PUTSTATIC |
+            Opcodes::ATHROW | Opcodes::MONITORENTER | Opcodes::MONITOREXIT => {
+                let value = self.pop()?;
+                interpreter.unary_operation(insn, &value)?;
+            }
+            Opcodes::IF_ICMPEQ..=Opcodes::IF_ACMPNE | Opcodes::PUTFIELD => {
+                let value2 = self.pop()?;
+                let value1 = self.pop()?;
+                interpreter.binary_operation(insn, &value1, &value2)?;
+            }
+            Opcodes::IRETURN..=Opcodes::ARETURN => {
+                let value = self.pop()?;
+                interpreter.unary_operation(insn, &value)?;
+                let Some(expected) = &self.return_value else {
+                    return Err("value returned from a void method".to_string());
+                };
+                interpreter.return_operation(insn, &value, expected)?;
+            }
+            Opcodes::RETURN => {
+                if self.return_value.is_some() {
+                    return Err("return without a value in a non-void method".to_string());
+                }
+            }
+            Opcodes::INVOKEVIRTUAL..=Opcodes::INVOKEDYNAMIC | Opcodes::MULTIANEWARRAY => {
+                let (count, returns_value) = match insn.node {
+                    InsnNode::MethodInsnNode { opcode, desc, .. } => {
+                        let receiver = usize::from(*opcode != Opcodes::INVOKESTATIC);
+                        (method_arg_types(desc).len() + receiver, method_return_type(desc) != "V")
+                    }
+                    InsnNode::InvokeDynamicInsnNode(const_dynamic) => {
+                        let desc = &const_dynamic.desc;
+                        (method_arg_types(desc).len(), method_return_type(desc) != "V")
+                    }
+                    InsnNode::MultiANewArrayInsnNode { dims, .. } => (*dims as usize, true),
+                    _ => (0, false),
+                };
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count {
+                    values.push(self.pop()?);
+                }
+                values.reverse();
+                let result = interpreter.nary_operation(insn, &values)?;
+                if returns_value {
+                    self.push_result(result)?;
+                }
+            }
+            other => return Err(format!("unexpected opcode: {other}")),
+        }
+        Ok(())
+    }
+
+    /// Merges `other` into this frame, returns true if this frame changed.
+    pub fn merge<I: Interpreter<Value=V>>(&mut self, other: &Frame<V>, interpreter: &I) -> Result<bool, String> {
+        if self.stack.len() != other.stack.len() {
+            return Err(format!("incompatible stack heights: {} and {}", self.stack.len(), other.stack.len()));
+        }
+        let mut changed = false;
+        let pairs = self.locals.iter_mut().zip(&other.locals)
+            .chain(self.stack.iter_mut().zip(&other.stack));
+        for (current, incoming) in pairs {
+            let merged = interpreter.merge(current, incoming);
+            if merged != *current {
+                *current = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// The local variable of `xLOAD` / `xSTORE`, `base` is `ILOAD_0` or `ISTORE_0` for the short forms.
+fn var_index(insn: &InsnNode, base: u8) -> usize {
+    match insn {
+        InsnNode::VarInsnNode { var_index, .. } => *var_index as usize,
+        InsnNode::NoOperand { opcode } => ((opcode - base) % 4) as usize,
+        _ => 0,
+    }
+}
@@ -0,0 +1,267 @@
+pub use basic::*;
+pub use frame::*;
+pub use source::*;
+pub use verifier::*;
+
+use crate::impls::{method_arg_types, method_return_type};
+use crate::node::element::{CodeBodyNode, LabelNode, MethodNode};
+use crate::node::InsnNode;
+use crate::{JavaMethodAccessFlags, Opcodes};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display, Formatter};
+
+mod basic;
+mod frame;
+mod source;
+mod verifier;
+
+/// A value held by a local variable or an operand stack entry of a [Frame].
+pub trait Value: Clone + Debug + PartialEq {
+    /// Slots taken by this value, 2 for long and double.
+    fn size(&self) -> usize;
+}
+
+/// An instruction being interpreted, with its index in [CodeBodyNode::instructions].
+#[derive(Copy, Clone, Debug)]
+pub struct AnalyzedInsn<'a> {
+    pub index: usize,
+    pub node: &'a InsnNode,
+}
+
+impl AnalyzedInsn<'_> {
+    pub fn opcode(&self) -> u8 {
+        self.node.opcode()
+    }
+}
+
+/// The semantics of instructions for the [Analyzer], just like ASM's `Interpreter`.
+///
+/// Operations return [None] when the instruction doesn't produce a value, an [Err] with
+/// a message makes the analysis fail at the instruction.
+pub trait Interpreter {
+    type Value: Value;
+
+    /// A value of the field descriptor `desc`, [None] for `V`.
+    fn new_value(&self, desc: &str) -> Option<Self::Value>;
+
+    /// The value of unassigned locals and the second slot of long and double locals.
+    fn new_empty_value(&self) -> Self::Value;
+
+    /// The value of a parameter stored at `local`, `this` uses the descriptor of the owner class.
+    fn new_parameter_value(&self, local: usize, desc: &str) -> Self::Value {
+        let _ = local;
+        self.new_value(desc).unwrap_or_else(|| self.new_empty_value())
+    }
+
+    /// The value pushed onto the stack of an exception handler.
+    fn new_exception_value(&self, catch_type: &str) -> Self::Value {
+        self.new_value(&format!("L{catch_type};")).unwrap_or_else(|| self.new_empty_value())
+    }
+
+    /// `ACONST_NULL`, `xCONST_n`, `BIPUSH`, `SIPUSH`, `LDC`, `JSR`, `GETSTATIC` and `NEW`.
+    fn new_operation(&self, insn: AnalyzedInsn) -> Result<Self::Value, String>;
+
+    /// `xLOAD`, `xSTORE`, `DUP` family and `SWAP`, returns the copied value.
+    fn copy_operation(&self, insn: AnalyzedInsn, value: &Self::Value) -> Result<Self::Value, String>;
+
+    /// Instructions with a single operand, e.g. `INEG`, `IFEQ`, `GETFIELD`, `CHECKCAST` or `IINC`.
+    fn unary_operation(
+        &self, insn: AnalyzedInsn, value: &Self::Value,
+    ) -> Result<Option<Self::Value>, String>;
+
+    /// Instructions with two operands, e.g. `IALOAD`, `IADD`, `IF_ICMPEQ` or `PUTFIELD`.
+    fn binary_operation(
+        &self, insn: AnalyzedInsn, value1: &Self::Value, value2: &Self::Value,
+    ) -> Result<Option<Self::Value>, String>;
+
+    /// `xASTORE`, operands are the array, the index and the value.
+    fn ternary_operation(
+        &self, insn: AnalyzedInsn, value1: &Self::Value, value2: &Self::Value, value3: &Self::Value,
+    ) -> Result<(), String>;
+
+    /// Invocations and `MULTIANEWARRAY`, `values` are in the order they were pushed.
+    fn nary_operation(
+        &self, insn: AnalyzedInsn, values: &[Self::Value],
+    ) -> Result<Option<Self::Value>, String>;
+
+    /// `xRETURN`, `expected` is the value of the method's return type.
+    fn return_operation(
+        &self, insn: AnalyzedInsn, value: &Self::Value, expected: &Self::Value,
+    ) -> Result<(), String>;
+
+    /// The value where two control flow paths meet.
+    fn merge(&self, value1: &Self::Value, value2: &Self::Value) -> Self::Value;
+}
+
+/// The frame before each instruction of a method, [None] for unreachable instructions.
+pub type Frames<V> = Vec<Option<Frame<V>>>;
+
+#[derive(Clone, Debug)]
+pub struct AnalyzerError {
+    /// The index of the failing instruction in [CodeBodyNode::instructions].
+    pub insn_index: usize,
+    pub insn: Option<InsnNode>,
+    pub message: String,
+}
+
+impl Display for AnalyzerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.insn_index)?;
+        if let Some(insn) = &self.insn {
+            write!(f, " {insn:?}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// A data-flow analyzer which computes the [Frame] before every instruction of a method,
+/// the port of ASM's `Analyzer`.
+pub struct Analyzer<I: Interpreter> {
+    interpreter: I,
+}
+
+impl<I: Interpreter> Analyzer<I> {
+    pub fn new(interpreter: I) -> Self {
+        Analyzer { interpreter }
+    }
+
+    pub fn interpreter(&self) -> &I {
+        &self.interpreter
+    }
+
+    /// Analyzes `method` of the class `owner`, the frame of each instruction is [None] if the
+    /// instruction is unreachable. Methods without code have no frames.
+    pub fn analyze(
+        &self, owner: &str, method: &MethodNode,
+    ) -> Result<Frames<I::Value>, Box<AnalyzerError>> {
+        let Some(code) = &method.code_body else { return Ok(vec![]) };
+        let code = code.get().map_err(|e| Box::new(AnalyzerError {
+            insn_index: 0, insn: None, message: format!("cannot read the code: {e:?}"),
+        }))?;
+        let instructions = &code.instructions;
+        let size = instructions.len();
+        let error = |insn_index: usize, message: String| Box::new(AnalyzerError {
+            insn_index, insn: instructions.get(insn_index).cloned(), message,
+        });
+        if size == 0 {
+            return Err(error(0, "empty code".to_string()));
+        }
+
+        let initial = self.initial_frame(owner, method, code).map_err(|message| error(0, message))?;
+        let jsr_returns: Vec<usize> = instructions.iter().enumerate()
+            .filter(|(_, insn)| matches!(insn.opcode(), Opcodes::JSR | Opcodes::JSR_W))
+            .map(|(index, _)| index + 1)
+            .filter(|index| *index < size)
+            .collect();
+
+        let mut frames: Frames<I::Value> = vec![None; size];
+        frames[0] = Some(initial);
+        let mut queued = vec![false; size];
+        let mut queue = VecDeque::from([0]);
+        queued[0] = true;
+        while let Some(index) = queue.pop_front() {
+            queued[index] = false;
+            let Some(frame_in) = frames[index].clone() else { continue };
+            let insn = AnalyzedInsn { index, node: &instructions[index] };
+            let mut frame = frame_in.clone();
+            let result = frame.execute(insn, &self.interpreter).and_then(|_| {
+                let mut successors = vec![];
+                for item in &code.exception_table {
+                    let label = label_of(code, index);
+                    if label < item.start || label >= item.end { continue; }
+                    let handler = index_of(code, item.handler)
+                        .ok_or_else(|| format!("handler {} is not an instruction", item.handler))?;
+                    let catch_type = item.catch_type.as_deref().unwrap_or("java/lang/Throwable");
+                    let exception = self.interpreter.new_exception_value(catch_type);
+                    for locals in [&frame_in.locals, &frame.locals] {
+                        let handler_frame = frame_in.with_stack(locals.clone(), vec![exception.clone()]);
+                        successors.push((handler, handler_frame));
+                    }
+                }
+                let (falls_through, targets) = flow_of(insn.node);
+                if falls_through {
+                    if index + 1 >= size { return Err("falling off the end of the code".to_string()); }
+                    successors.push((index + 1, frame.clone()));
+                }
+                for target in targets {
+                    let target_index = index_of(code, target)
+                        .ok_or_else(|| format!("{target} is not an instruction"))?;
+                    successors.push((target_index, frame.clone()));
+                }
+                if matches!(insn.node, InsnNode::VarInsnNode { opcode: Opcodes::RET, .. }) {
+                    successors.extend(jsr_returns.iter().map(|target| (*target, frame.clone())));
+                }
+                for (target, successor) in successors {
+                    let changed = match &mut frames[target] {
+                        Some(existing) => existing.merge(&successor, &self.interpreter)?,
+                        None => {
+                            frames[target] = Some(successor);
+                            true
+                        }
+                    };
+                    if changed && !queued[target] {
+                        queued[target] = true;
+                        queue.push_back(target);
+                    }
+                }
+                Ok(())
+            });
+            result.map_err(|message| error(index, message))?;
+        }
+        Ok(frames)
+    }
+
+    fn initial_frame(&self, owner: &str, method: &MethodNode, code: &CodeBodyNode) -> Result<Frame<I::Value>, String> {
+        let interpreter = &self.interpreter;
+        let mut locals = vec![];
+        if method.access & JavaMethodAccessFlags::ACC_STATIC == 0 {
+            locals.push(interpreter.new_parameter_value(0, &format!("L{owner};")));
+        }
+        for arg in method_arg_types(&method.desc) {
+            let value = interpreter.new_parameter_value(locals.len(), arg);
+            let size = value.size();
+            locals.push(value);
+            if size == 2 { locals.push(interpreter.new_empty_value()); }
+        }
+        let max_locals = code.max_locals as usize;
+        if locals.len() > max_locals {
+            return Err(format!("parameters take {} locals, more than {max_locals}", locals.len()));
+        }
+        locals.resize(max_locals, interpreter.new_empty_value());
+        let return_type = method_return_type(&method.desc);
+        let return_value = interpreter.new_value(return_type);
+        Ok(Frame::new(locals, code.max_stack as usize, return_value))
+    }
+}
+
+fn label_of(code: &CodeBodyNode, index: usize) -> LabelNode {
+    code.insn_offsets.get(index).copied().unwrap_or(index as LabelNode)
+}
+
+/// An empty [CodeBodyNode::insn_offsets] means labels are the indexes of instructions.
+fn index_of(code: &CodeBodyNode, label: LabelNode) -> Option<usize> {
+    if code.insn_offsets.is_empty() {
+        let index = label as usize;
+        (index < code.instructions.len()).then_some(index)
+    } else {
+        code.insn_offsets.binary_search(&label).ok()
+    }
+}
+
+/// Whether the instruction falls through to the next one, and its branch targets.
+/// `RET` has no targets here since it returns to the instruction after a `JSR`.
+pub(crate) fn flow_of(insn: &InsnNode) -> (bool, Vec<LabelNode>) {
+    match insn {
+        InsnNode::JumpInsnNode { opcode, label } => {
+            let unconditional = matches!(*opcode, Opcodes::GOTO | Opcodes::GOTO_W | Opcodes::JSR | Opcodes::JSR_W);
+            (!unconditional, vec![*label])
+        }
+        InsnNode::TableSwitchInsnNode { default, labels, .. } |
+        InsnNode::LookupSwitchInsnNode { default, labels, .. } => {
+            (false, std::iter::once(*default).chain(labels.iter().copied()).collect())
+        }
+        InsnNode::NoOperand { opcode: Opcodes::IRETURN..=Opcodes::RETURN | Opcodes::ATHROW } => (false, vec![]),
+        InsnNode::VarInsnNode { opcode: Opcodes::RET, .. } => (false, vec![]),
+        _ => (true, vec![]),
+    }
+}
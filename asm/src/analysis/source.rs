@@ -0,0 +1,88 @@
+use crate::analysis::{AnalyzedInsn, BasicValue, Interpreter, Value};
+use std::collections::BTreeSet;
+
+/// The value of [SourceInterpreter], records the instructions which may produce it.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SourceValue {
+    /// 2 for long and double, 1 otherwise.
+    pub size: usize,
+    /// The indexes of instructions which may produce this value, empty for parameters,
+    /// exceptions and unassigned locals.
+    pub insns: BTreeSet<usize>,
+}
+
+impl Value for SourceValue {
+    fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl SourceValue {
+    fn produced_by(insn: AnalyzedInsn, size: usize) -> Self {
+        SourceValue { size, insns: BTreeSet::from([insn.index]) }
+    }
+}
+
+/// Tracks where values come from (def-use chains), like ASM's `SourceInterpreter`.
+///
+/// Loads, stores and the `DUP` family are treated as the source of the copied values.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SourceInterpreter;
+
+/// The size of the value produced by `insn`.
+fn result_size(insn: AnalyzedInsn) -> usize {
+    BasicValue::of_insn(insn.node).map_or(1, |value| value.size())
+}
+
+impl Interpreter for SourceInterpreter {
+    type Value = SourceValue;
+
+    fn new_value(&self, desc: &str) -> Option<SourceValue> {
+        let size = BasicValue::from_desc(desc)?.size();
+        Some(SourceValue { size, insns: BTreeSet::new() })
+    }
+
+    fn new_empty_value(&self) -> SourceValue {
+        SourceValue { size: 1, insns: BTreeSet::new() }
+    }
+
+    fn new_operation(&self, insn: AnalyzedInsn) -> Result<SourceValue, String> {
+        Ok(SourceValue::produced_by(insn, result_size(insn)))
+    }
+
+    fn copy_operation(&self, insn: AnalyzedInsn, value: &SourceValue) -> Result<SourceValue, String> {
+        Ok(SourceValue::produced_by(insn, value.size))
+    }
+
+    fn unary_operation(&self, insn: AnalyzedInsn, _value: &SourceValue) -> Result<Option<SourceValue>, String> {
+        Ok(Some(SourceValue::produced_by(insn, result_size(insn))))
+    }
+
+    fn binary_operation(
+        &self, insn: AnalyzedInsn, _value1: &SourceValue, _value2: &SourceValue,
+    ) -> Result<Option<SourceValue>, String> {
+        Ok(Some(SourceValue::produced_by(insn, result_size(insn))))
+    }
+
+    fn ternary_operation(
+        &self, _insn: AnalyzedInsn, _value1: &SourceValue, _value2: &SourceValue, _value3: &SourceValue,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn nary_operation(&self, insn: AnalyzedInsn, _values: &[SourceValue]) -> Result<Option<SourceValue>, String> {
+        Ok(Some(SourceValue::produced_by(insn, result_size(insn))))
+    }
+
+    fn return_operation(
+        &self, _insn: AnalyzedInsn, _value: &SourceValue, _expected: &SourceValue,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn merge(&self, value1: &SourceValue, value2: &SourceValue) -> SourceValue {
+        if value1 == value2 { return value1.clone(); }
+        let insns = value1.insns.union(&value2.insns).copied().collect();
+        SourceValue { size: value1.size.min(value2.size), insns }
+    }
+}
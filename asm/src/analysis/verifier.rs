@@ -0,0 +1,322 @@
+use crate::analysis::{AnalyzedInsn, BasicValue, Interpreter, Value};
+use crate::hierarchy::{HierarchyResolver, OBJECT};
+use crate::impls::{method_arg_types, method_return_type};
+use crate::node::values::ConstValue;
+use crate::node::InsnNode;
+use crate::verify::{array_of, Verifier, VerifyType};
+use crate::{NewArrayTypeOperand, Opcodes};
+
+impl Value for VerifyType {
+    fn size(&self) -> usize {
+        VerifyType::size(self)
+    }
+}
+
+/// Checks the operands of every instruction, like ASM's `SimpleVerifier`.
+///
+/// Unlike [Verifier], objects created by `NEW` are treated as initialized and subroutines
+/// are not checked, class assignability is resolved by the [HierarchyResolver] in the same way.
+pub struct VerifierInterpreter<'a> {
+    verifier: Verifier<'a>,
+}
+
+impl<'a> VerifierInterpreter<'a> {
+    pub fn new(resolver: &'a dyn HierarchyResolver) -> Self {
+        VerifierInterpreter { verifier: Verifier::new(resolver) }
+    }
+
+    fn expect(&self, expected: &VerifyType, actual: &VerifyType) -> Result<(), String> {
+        if self.verifier.is_assignable(expected, actual) {
+            Ok(())
+        } else {
+            Err(format!("expected {expected}, but found {actual}"))
+        }
+    }
+
+    fn expect_reference(&self, actual: &VerifyType) -> Result<(), String> {
+        if actual.is_reference() {
+            Ok(())
+        } else {
+            Err(format!("expected a reference, but found {actual}"))
+        }
+    }
+
+    /// `null` or an array of one of the `arrays` types.
+    fn expect_array(&self, actual: &VerifyType, arrays: &[&str]) -> Result<(), String> {
+        let matched = match actual {
+            VerifyType::Null => true,
+            // any array of references is an `Object[]`.
+            VerifyType::Reference(name) if arrays[0] == "[Ljava/lang/Object;" => {
+                name.starts_with("[L") || name.starts_with("[[")
+            }
+            VerifyType::Reference(name) => arrays.contains(&name.as_ref()),
+            _ => false,
+        };
+        if matched {
+            Ok(())
+        } else {
+            Err(format!("expected {}, but found {actual}", arrays[0]))
+        }
+    }
+}
+
+/// `int`, `long`, `float`, `double` and reference in turn, the order of typed opcode groups.
+fn typed(kind: u8) -> VerifyType {
+    match kind {
+        0 => VerifyType::Integer,
+        1 => VerifyType::Long,
+        2 => VerifyType::Float,
+        3 => VerifyType::Double,
+        _ => VerifyType::reference(OBJECT),
+    }
+}
+
+/// The array types accepted by `xALOAD` and `xASTORE`, `kind` counts from `IALOAD` or `IASTORE`.
+fn array_types(kind: u8) -> &'static [&'static str] {
+    match kind {
+        0 => &["[I"],
+        1 => &["[J"],
+        2 => &["[F"],
+        3 => &["[D"],
+        4 => &["[Ljava/lang/Object;"],
+        5 => &["[B", "[Z"],
+        6 => &["[C"],
+        _ => &["[S"],
+    }
+}
+
+fn from_basic(value: Option<BasicValue>) -> Option<VerifyType> {
+    let ty = match value? {
+        BasicValue::Uninitialized => VerifyType::Top,
+        BasicValue::Int => VerifyType::Integer,
+        BasicValue::Float => VerifyType::Float,
+        BasicValue::Long => VerifyType::Long,
+        BasicValue::Double => VerifyType::Double,
+        BasicValue::Reference => VerifyType::reference(OBJECT),
+        BasicValue::ReturnAddress => VerifyType::ReturnAddress,
+    };
+    Some(ty)
+}
+
+impl Interpreter for VerifierInterpreter<'_> {
+    type Value = VerifyType;
+
+    fn new_value(&self, desc: &str) -> Option<VerifyType> {
+        VerifyType::from_desc(desc)
+    }
+
+    fn new_empty_value(&self) -> VerifyType {
+        VerifyType::Top
+    }
+
+    fn new_operation(&self, insn: AnalyzedInsn) -> Result<VerifyType, String> {
+        let ty = match insn.node {
+            InsnNode::NoOperand { opcode: Opcodes::ACONST_NULL } => Some(VerifyType::Null),
+            InsnNode::TypeInsnNode { type_name, .. } => Some(VerifyType::Reference(type_name.clone())),
+            InsnNode::FieldInsnNode { desc, .. } => VerifyType::from_desc(desc),
+            InsnNode::LdcInsnNode(value) => match &**value {
+                ConstValue::String(_) => Some(VerifyType::reference("java/lang/String")),
+                ConstValue::Class(_) => Some(VerifyType::reference("java/lang/Class")),
+                ConstValue::MethodType(_) => Some(VerifyType::reference("java/lang/invoke/MethodType")),
+                ConstValue::MethodHandle(_) => Some(VerifyType::reference("java/lang/invoke/MethodHandle")),
+                ConstValue::Dynamic { desc, .. } => VerifyType::from_desc(desc),
+                _ => from_basic(BasicValue::of_insn(insn.node)),
+            },
+            node => from_basic(BasicValue::of_insn(node)),
+        };
+        ty.ok_or_else(|| format!("unexpected instruction: {:?}", insn.node))
+    }
+
+    fn copy_operation(&self, insn: AnalyzedInsn, value: &VerifyType) -> Result<VerifyType, String> {
+        let opcode = insn.opcode();
+        let kind = match opcode {
+            Opcodes::ILOAD..=Opcodes::ALOAD => Some(opcode - Opcodes::ILOAD),
+            Opcodes::ILOAD_0..=Opcodes::ALOAD_3 => Some((opcode - Opcodes::ILOAD_0) / 4),
+            Opcodes::ISTORE..=Opcodes::ASTORE => Some(opcode - Opcodes::ISTORE),
+            Opcodes::ISTORE_0..=Opcodes::ASTORE_3 => Some((opcode - Opcodes::ISTORE_0) / 4),
+            // the DUP family and SWAP
+            _ => None,
+        };
+        match kind {
+            Some(4) => {
+                let is_store = opcode >= Opcodes::ISTORE;
+                if !(is_store && *value == VerifyType::ReturnAddress) {
+                    self.expect_reference(value)?;
+                }
+            }
+            Some(kind) => self.expect(&typed(kind), value)?,
+            None => {}
+        }
+        Ok(value.clone())
+    }
+
+    fn unary_operation(&self, insn: AnalyzedInsn, value: &VerifyType) -> Result<Option<VerifyType>, String> {
+        let opcode = insn.opcode();
+        match insn.node {
+            InsnNode::FieldInsnNode { opcode: Opcodes::GETFIELD, owner, desc, .. } => {
+                self.expect(&VerifyType::Reference(owner.clone()), value)?;
+                return Ok(VerifyType::from_desc(desc));
+            }
+            InsnNode::FieldInsnNode { desc, .. } => {
+                let expected = VerifyType::from_desc(desc).ok_or_else(|| format!("invalid field type: {desc}"))?;
+                self.expect(&expected, value)?;
+                return Ok(None);
+            }
+            InsnNode::NewArrayInsnNode { array_type } => {
+                self.expect(&VerifyType::Integer, value)?;
+                let array = match *array_type {
+                    NewArrayTypeOperand::T_BOOLEAN => "[Z",
+                    NewArrayTypeOperand::T_CHAR => "[C",
+                    NewArrayTypeOperand::T_FLOAT => "[F",
+                    NewArrayTypeOperand::T_DOUBLE => "[D",
+                    NewArrayTypeOperand::T_BYTE => "[B",
+                    NewArrayTypeOperand::T_SHORT => "[S",
+                    NewArrayTypeOperand::T_INT => "[I",
+                    NewArrayTypeOperand::T_LONG => "[J",
+                    other => return Err(format!("unknown newarray type: {other}")),
+                };
+                return Ok(Some(VerifyType::reference(array)));
+            }
+            InsnNode::TypeInsnNode { opcode, type_name } => {
+                return match *opcode {
+                    Opcodes::ANEWARRAY => {
+                        self.expect(&VerifyType::Integer, value)?;
+                        Ok(Some(VerifyType::Reference(array_of(type_name))))
+                    }
+                    Opcodes::CHECKCAST => {
+                        self.expect_reference(value)?;
+                        Ok(Some(VerifyType::Reference(type_name.clone())))
+                    }
+                    _ => {
+                        self.expect_reference(value)?;
+                        Ok(Some(VerifyType::Integer))
+                    }
+                };
+            }
+            _ => {}
+        }
+        match opcode {
+            Opcodes::INEG..=Opcodes::DNEG => self.expect(&typed(opcode - Opcodes::INEG), value)?,
+            Opcodes::I2L..=Opcodes::D2F => self.expect(&typed((opcode - Opcodes::I2L) / 3), value)?,
+            Opcodes::IRETURN..=Opcodes::DRETURN => self.expect(&typed(opcode - Opcodes::IRETURN), value)?,
+            Opcodes::IINC | Opcodes::I2B | Opcodes::I2C | Opcodes::I2S | Opcodes::IFEQ..=Opcodes::IFLE |
+            Opcodes::TABLESWITCH | Opcodes::LOOKUPSWITCH => self.expect(&VerifyType::Integer, value)?,
+            Opcodes::ARRAYLENGTH => {
+                let is_array = *value == VerifyType::Null ||
+                    matches!(value, VerifyType::Reference(name) if name.starts_with('['));
+                if !is_array {
+                    return Err(format!("expected an array, but found {value}"));
+                }
+            }
+            Opcodes::ATHROW => self.expect(&VerifyType::reference("java/lang/Throwable"), value)?,
+            Opcodes::ARETURN | Opcodes::MONITORENTER | Opcodes::MONITOREXIT |
+            Opcodes::IFNULL | Opcodes::IFNONNULL => self.expect_reference(value)?,
+            _ => {}
+        }
+        Ok(from_basic(BasicValue::of_insn(insn.node)))
+    }
+
+    fn binary_operation(
+        &self, insn: AnalyzedInsn, value1: &VerifyType, value2: &VerifyType,
+    ) -> Result<Option<VerifyType>, String> {
+        let opcode = insn.opcode();
+        match opcode {
+            Opcodes::IALOAD..=Opcodes::SALOAD => {
+                self.expect_array(value1, array_types(opcode - Opcodes::IALOAD))?;
+                self.expect(&VerifyType::Integer, value2)?;
+                if opcode == Opcodes::AALOAD {
+                    return Ok(Some(value1.array_element().unwrap_or(VerifyType::Null)));
+                }
+            }
+            Opcodes::IADD..=Opcodes::DREM | Opcodes::IAND..=Opcodes::LXOR => {
+                let expected = match opcode {
+                    Opcodes::IAND..=Opcodes::LXOR => typed((opcode - Opcodes::IAND) % 2),
+                    _ => typed((opcode - Opcodes::IADD) % 4),
+                };
+                self.expect(&expected, value1)?;
+                self.expect(&expected, value2)?;
+            }
+            Opcodes::ISHL..=Opcodes::LUSHR => {
+                self.expect(&typed((opcode - Opcodes::ISHL) % 2), value1)?;
+                self.expect(&VerifyType::Integer, value2)?;
+            }
+            Opcodes::LCMP..=Opcodes::DCMPG => {
+                let expected = match opcode {
+                    Opcodes::LCMP => VerifyType::Long,
+                    Opcodes::FCMPL | Opcodes::FCMPG => VerifyType::Float,
+                    _ => VerifyType::Double,
+                };
+                self.expect(&expected, value1)?;
+                self.expect(&expected, value2)?;
+            }
+            Opcodes::IF_ICMPEQ..=Opcodes::IF_ICMPLE => {
+                self.expect(&VerifyType::Integer, value1)?;
+                self.expect(&VerifyType::Integer, value2)?;
+            }
+            Opcodes::IF_ACMPEQ | Opcodes::IF_ACMPNE => {
+                self.expect_reference(value1)?;
+                self.expect_reference(value2)?;
+            }
+            Opcodes::PUTFIELD => {
+                if let InsnNode::FieldInsnNode { owner, desc, .. } = insn.node {
+                    self.expect(&VerifyType::Reference(owner.clone()), value1)?;
+                    let expected = VerifyType::from_desc(desc).ok_or_else(|| format!("invalid field type: {desc}"))?;
+                    self.expect(&expected, value2)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(from_basic(BasicValue::of_insn(insn.node)))
+    }
+
+    fn ternary_operation(
+        &self, insn: AnalyzedInsn, value1: &VerifyType, value2: &VerifyType, value3: &VerifyType,
+    ) -> Result<(), String> {
+        let kind = insn.opcode() - Opcodes::IASTORE;
+        self.expect_array(value1, array_types(kind))?;
+        self.expect(&VerifyType::Integer, value2)?;
+        match kind {
+            // the component type is only checked at runtime.
+            4 => self.expect_reference(value3),
+            5..=7 => self.expect(&VerifyType::Integer, value3),
+            _ => self.expect(&typed(kind), value3),
+        }
+    }
+
+    fn nary_operation(&self, insn: AnalyzedInsn, values: &[VerifyType]) -> Result<Option<VerifyType>, String> {
+        let desc = match insn.node {
+            InsnNode::MultiANewArrayInsnNode { array_type, .. } => {
+                for value in values {
+                    self.expect(&VerifyType::Integer, value)?;
+                }
+                return Ok(Some(VerifyType::Reference(array_type.clone())));
+            }
+            InsnNode::MethodInsnNode { opcode, owner, desc, .. } => {
+                if *opcode != Opcodes::INVOKESTATIC {
+                    let receiver = values.first().ok_or_else(|| "missing receiver".to_string())?;
+                    self.expect(&VerifyType::Reference(owner.clone()), receiver)?;
+                }
+                desc
+            }
+            InsnNode::InvokeDynamicInsnNode(const_dynamic) => &const_dynamic.desc,
+            node => return Err(format!("unexpected instruction: {node:?}")),
+        };
+        let arg_types = method_arg_types(desc);
+        let args = &values[values.len() - arg_types.len()..];
+        for (arg_type, arg) in arg_types.iter().zip(args) {
+            let expected = VerifyType::from_desc(arg_type).ok_or_else(|| format!("invalid argument type: {arg_type}"))?;
+            self.expect(&expected, arg)?;
+        }
+        Ok(VerifyType::from_desc(method_return_type(desc)))
+    }
+
+    fn return_operation(
+        &self, _insn: AnalyzedInsn, value: &VerifyType, expected: &VerifyType,
+    ) -> Result<(), String> {
+        self.expect(expected, value)
+    }
+
+    fn merge(&self, value1: &VerifyType, value2: &VerifyType) -> VerifyType {
+        self.verifier.merge(value1, value2)
+    }
+}
@@ -0,0 +1,327 @@
+use crate::android::chunk::*;
+use crate::android::ResValue;
+use crate::err::AsmResult;
+use crate::impls::ToStringRef;
+use crate::smali::{SmaliNode, SmaliToken};
+use crate::StrRef;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The value of a resource in one configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceValue {
+    Simple(ResValue),
+    /// A style, an array or plurals: the parent style and `(key, value)` items, keys are
+    /// resource ids of attributes or special ids for arrays and plurals.
+    Complex { parent: u32, items: Vec<(u32, ResValue)> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceEntry {
+    /// The resource id `0xPPTTEEEE` of package, type and entry.
+    pub id: u32,
+    pub name: StrRef,
+    /// Values by the qualifiers of their configuration, e.g. `de-rDE` or `xxhdpi-v21`,
+    /// the qualifiers of the default configuration are empty.
+    pub values: Vec<(StrRef, ResourceValue)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceType {
+    pub id: u8,
+    /// e.g. `string`, `layout` or `drawable`.
+    pub name: StrRef,
+    /// Sorted by id.
+    pub entries: Vec<ResourceEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourcePackage {
+    /// `0x7f` for applications, `0x01` for the android framework.
+    pub id: u8,
+    pub name: StrRef,
+    /// Sorted by id.
+    pub types: Vec<ResourceType>,
+}
+
+/// The compiled resources of an APK (`resources.arsc`), which map resource ids used in code
+/// to their names and values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceTable {
+    pub packages: Vec<ResourcePackage>,
+}
+
+const RES_TABLE_TYPE: u16 = 0x0002;
+const RES_TABLE_PACKAGE_TYPE: u16 = 0x0200;
+const RES_TABLE_TYPE_TYPE: u16 = 0x0201;
+const RES_TABLE_TYPE_SPEC_TYPE: u16 = 0x0202;
+
+const ENTRY_FLAG_COMPLEX: u16 = 0x0001;
+const ENTRY_FLAG_COMPACT: u16 = 0x0008;
+const TYPE_FLAG_SPARSE: u8 = 0x01;
+const TYPE_FLAG_OFFSET16: u8 = 0x02;
+const NO_ENTRY: u32 = 0xFFFF_FFFF;
+
+impl ResourceTable {
+    /// Whether the bytes start with the header of a resource table.
+    pub fn is_resource_table(bytes: &[u8]) -> bool {
+        bytes.get(..4) == Some(&[0x02, 0x00, 0x0C, 0x00])
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> AsmResult<ResourceTable> {
+        let bytes = Bytes(bytes);
+        let table = Chunk::read(bytes, 0)?;
+        if table.chunk_type != RES_TABLE_TYPE {
+            return Err(res_err("not a resource table"));
+        }
+        let mut strings = StringPool::default();
+        let mut packages = vec![];
+        for chunk in table.children(bytes, table.body())? {
+            match chunk.chunk_type {
+                RES_STRING_POOL_TYPE => strings = StringPool::read(bytes, &chunk)?,
+                RES_TABLE_PACKAGE_TYPE => packages.push(read_package(bytes, &chunk, &strings)?),
+                _ => {}
+            }
+        }
+        Ok(ResourceTable { packages })
+    }
+
+    pub fn entry(&self, id: u32) -> Option<(&ResourcePackage, &ResourceType, &ResourceEntry)> {
+        let package = self.packages.iter().find(|package| package.id as u32 == id >> 24)?;
+        let type_id = ((id >> 16) & 0xFF) as u8;
+        let resource_type = package.types.iter().find(|resource_type| resource_type.id == type_id)?;
+        let index = resource_type.entries.binary_search_by_key(&id, |entry| entry.id).ok()?;
+        Some((package, resource_type, &resource_type.entries[index]))
+    }
+
+    /// The name of a resource like it's referenced in XML, e.g. `@string/app_name`.
+    pub fn reference_name(&self, id: u32) -> Option<String> {
+        let (package, resource_type, entry) = self.entry(id)?;
+        if package.id == 0x01 {
+            Some(format!("@android:{}/{}", resource_type.name, entry.name))
+        } else {
+            Some(format!("@{}/{}", resource_type.name, entry.name))
+        }
+    }
+
+    /// Entries of a type with their values in all configurations, one entry per line.
+    pub fn describe_type(&self, resource_type: &ResourceType) -> String {
+        let mut result = String::new();
+        for entry in &resource_type.entries {
+            let _ = writeln!(result, "0x{:08x} @{}/{}", entry.id, resource_type.name, entry.name);
+            for (qualifiers, value) in &entry.values {
+                let config = if qualifiers.is_empty() { "default" } else { qualifiers };
+                match value {
+                    ResourceValue::Simple(ResValue::String(value)) => {
+                        let _ = writeln!(result, "    [{config}] {value:?}");
+                    }
+                    ResourceValue::Simple(value) => {
+                        let _ = writeln!(result, "    [{config}] {}", self.value_string(value));
+                    }
+                    ResourceValue::Complex { parent, items } => {
+                        let _ = write!(result, "    [{config}]");
+                        if *parent != 0 {
+                            let _ = write!(result, " parent {}", self.value_string(&ResValue::Reference(*parent)));
+                        }
+                        result.push('\n');
+                        for (key, value) in items {
+                            let key = self.reference_name(*key).unwrap_or_else(|| format!("0x{key:08x}"));
+                            let _ = writeln!(result, "        {key} = {}", self.value_string(value));
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// A value with the name of the resource it refers to, e.g. `@string/app_name` instead of
+    /// `@0x7f0f0001`.
+    pub fn value_string(&self, value: &ResValue) -> String {
+        match value {
+            ResValue::Reference(id) => self.reference_name(*id).unwrap_or_else(|| value.to_string()),
+            ResValue::Attribute(id) => self.reference_name(*id)
+                .map(|name| name.replacen('@', "?", 1))
+                .unwrap_or_else(|| value.to_string()),
+            value => value.to_string(),
+        }
+    }
+}
+
+/// `ResTable_package`: id, a utf-16 name of 128 chars, then the offsets of the pools of type names
+/// and entry names.
+fn read_package(bytes: Bytes, chunk: &Chunk, strings: &StringPool) -> AsmResult<ResourcePackage> {
+    let id = bytes.u32(chunk.start + 8)? as u8;
+    let name_units: Vec<u16> = bytes.slice(chunk.start + 12, 256)?
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    let name = String::from_utf16_lossy(&name_units).to_ref();
+    let type_strings = StringPool::read(bytes, &Chunk::read(bytes, chunk.start + bytes.u32(chunk.start + 268)? as usize)?)?;
+    let key_strings = StringPool::read(bytes, &Chunk::read(bytes, chunk.start + bytes.u32(chunk.start + 276)? as usize)?)?;
+    // entries by type id and entry index, the values of all configurations are merged
+    let mut types: BTreeMap<u8, BTreeMap<u16, ResourceEntry>> = BTreeMap::new();
+    for child in chunk.children(bytes, chunk.body())? {
+        match child.chunk_type {
+            RES_TABLE_TYPE_SPEC_TYPE => {
+                types.entry(bytes.u8(child.start + 8)?).or_default();
+            }
+            RES_TABLE_TYPE_TYPE => {
+                let type_id = bytes.u8(child.start + 8)?;
+                let entries = types.entry(type_id).or_default();
+                read_type(bytes, &child, id, strings, &key_strings, entries)?;
+            }
+            _ => {}
+        }
+    }
+    let types = types.into_iter()
+        .filter(|(type_id, _)| *type_id != 0)
+        .map(|(type_id, entries)| ResourceType {
+            id: type_id,
+            name: type_strings.get(type_id as u32 - 1).unwrap_or_else(|_| format!("type{type_id}").to_ref()),
+            entries: entries.into_values().collect(),
+        })
+        .collect();
+    Ok(ResourcePackage { id, name, types })
+}
+
+/// `ResTable_type`: the entries of a type in one configuration.
+fn read_type(
+    bytes: Bytes, chunk: &Chunk, package_id: u8, strings: &StringPool, key_strings: &StringPool,
+    entries: &mut BTreeMap<u16, ResourceEntry>,
+) -> AsmResult<()> {
+    let type_id = bytes.u8(chunk.start + 8)?;
+    let flags = bytes.u8(chunk.start + 9)?;
+    let entry_count = bytes.u32(chunk.start + 12)? as usize;
+    let entries_start = chunk.start + bytes.u32(chunk.start + 16)? as usize;
+    let qualifiers = config_qualifiers(bytes, chunk.start + 20)?;
+    let offsets_start = chunk.body();
+    // (entry index, offset of the entry from entries_start)
+    let mut offsets = Vec::with_capacity(entry_count);
+    for index in 0..entry_count {
+        if flags & TYPE_FLAG_SPARSE != 0 {
+            let position = offsets_start + index * 4;
+            offsets.push((bytes.u16(position)?, bytes.u16(position + 2)? as u32 * 4));
+        } else if flags & TYPE_FLAG_OFFSET16 != 0 {
+            let offset = bytes.u16(offsets_start + index * 2)?;
+            if offset != 0xFFFF { offsets.push((index as u16, offset as u32 * 4)); }
+        } else {
+            let offset = bytes.u32(offsets_start + index * 4)?;
+            if offset != NO_ENTRY { offsets.push((index as u16, offset)); }
+        }
+    }
+    for (index, offset) in offsets {
+        let position = entries_start + offset as usize;
+        let entry_flags = bytes.u16(position + 2)?;
+        let (key, value) = if entry_flags & ENTRY_FLAG_COMPACT != 0 {
+            // key, flags with the type of the value in the high byte, then the data
+            let key = bytes.u16(position)? as u32;
+            let value = ResValue::new((entry_flags >> 8) as u8, bytes.u32(position + 4)?, strings)?;
+            (key, ResourceValue::Simple(value))
+        } else if entry_flags & ENTRY_FLAG_COMPLEX != 0 {
+            let key = bytes.u32(position + 4)?;
+            let parent = bytes.u32(position + 8)?;
+            let count = bytes.u32(position + 12)? as usize;
+            let items_start = position + bytes.u16(position)? as usize;
+            let items = (0..count).map(|item| {
+                let item_position = items_start + item * 12;
+                Ok((bytes.u32(item_position)?, ResValue::read(bytes, item_position + 4, strings)?))
+            }).collect::<AsmResult<_>>()?;
+            (key, ResourceValue::Complex { parent, items })
+        } else {
+            let key = bytes.u32(position + 4)?;
+            let value = ResValue::read(bytes, position + bytes.u16(position)? as usize, strings)?;
+            (key, ResourceValue::Simple(value))
+        };
+        let id = ((package_id as u32) << 24) | ((type_id as u32) << 16) | index as u32;
+        let entry = entries.entry(index).or_insert_with(|| ResourceEntry {
+            id, name: key_strings.get(key).unwrap_or_else(|_| format!("0x{id:08x}").to_ref()), values: vec![],
+        });
+        entry.values.push((qualifiers.clone(), value));
+    }
+    Ok(())
+}
+
+/// Qualifiers of a `ResTable_config` in the order of resource directories, e.g. `de-rDE-land-xxhdpi-v21`.
+/// Only the common qualifiers are decoded.
+fn config_qualifiers(bytes: Bytes, start: usize) -> AsmResult<StrRef> {
+    let size = bytes.u32(start)? as usize;
+    let config = bytes.slice(start, size)?;
+    let u8_at = |offset: usize| config.get(offset).copied().unwrap_or(0);
+    let u16_at = |offset: usize| u16::from_le_bytes([u8_at(offset), u8_at(offset + 1)]);
+    let mut qualifiers = vec![];
+    let (mcc, mnc) = (u16_at(4), u16_at(6));
+    if mcc != 0 { qualifiers.push(format!("mcc{mcc}")); }
+    if mnc != 0 { qualifiers.push(format!("mnc{mnc:02}")); }
+    if let Some(language) = locale_part([u8_at(8), u8_at(9)], b'a') {
+        qualifiers.push(language);
+        if let Some(region) = locale_part([u8_at(10), u8_at(11)], b'0') {
+            qualifiers.push(format!("r{region}"));
+        }
+    }
+    let dp = |name: &str, value: u16| (value != 0).then(|| format!("{name}{value}dp"));
+    qualifiers.extend(dp("sw", u16_at(30)));
+    qualifiers.extend(dp("w", u16_at(32)));
+    qualifiers.extend(dp("h", u16_at(34)));
+    match u8_at(12) {
+        1 => qualifiers.push("port".to_string()),
+        2 => qualifiers.push("land".to_string()),
+        _ => {}
+    }
+    match u8_at(29) & 0x30 {
+        0x10 => qualifiers.push("notnight".to_string()),
+        0x20 => qualifiers.push("night".to_string()),
+        _ => {}
+    }
+    let density = match u16_at(14) {
+        0 => None,
+        120 => Some("ldpi".to_string()),
+        160 => Some("mdpi".to_string()),
+        213 => Some("tvdpi".to_string()),
+        240 => Some("hdpi".to_string()),
+        320 => Some("xhdpi".to_string()),
+        480 => Some("xxhdpi".to_string()),
+        640 => Some("xxxhdpi".to_string()),
+        0xFFFE => Some("anydpi".to_string()),
+        0xFFFF => Some("nodpi".to_string()),
+        density => Some(format!("{density}dpi")),
+    };
+    qualifiers.extend(density);
+    let sdk = u16_at(24);
+    if sdk != 0 { qualifiers.push(format!("v{sdk}")); }
+    Ok(qualifiers.join("-").to_ref())
+}
+
+/// A language or region of two chars, or three chars packed into two bytes.
+fn locale_part(bytes: [u8; 2], base: u8) -> Option<String> {
+    if bytes[0] == 0 { return None; }
+    if bytes[0] & 0x80 == 0 {
+        return Some(String::from_utf8_lossy(&bytes).to_string());
+    }
+    let first = bytes[1] & 0x1F;
+    let second = ((bytes[1] & 0xE0) >> 5) | ((bytes[0] & 0x03) << 3);
+    let third = (bytes[0] & 0x7C) >> 2;
+    Some([first, second, third].iter().map(|c| (c + base) as char).collect())
+}
+
+/// Adds the resource names of integer literals which are resource ids as comments,
+/// e.g. `const v0, 2131361810 # @id/button`. `name` is usually [ResourceTable::reference_name].
+pub fn annotate_resource_ids(node: &mut SmaliNode, name: &dyn Fn(u32) -> Option<String>) {
+    let mut annotation = None;
+    for token in &node.content {
+        let SmaliToken::Literal(literal) = token else { continue };
+        let Ok(value) = literal.parse::<i64>() else { continue };
+        let id = value as u32;
+        if value != id as i64 && value != id as i32 as i64 { continue; }
+        // resource ids have a package in the high byte, small numbers are never resource ids
+        if id >> 24 == 0 { continue; }
+        annotation = name(id);
+    }
+    if let Some(annotation) = annotation {
+        node.content.push(SmaliToken::Other(format!("# {annotation}").to_ref()));
+    }
+    for child in &mut node.children {
+        annotate_resource_ids(child, name);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::android::chunk::*;
+use crate::android::{ResValue, ResourceTable};
+use crate::err::AsmResult;
+use crate::impls::ToStringRef;
+use crate::StrRef;
+use std::fmt::Write;
+
+/// Namespace of the attributes of the android framework, usually with the prefix `android`.
+pub const ANDROID_NAMESPACE: &str = "http://schemas.android.com/apk/res/android";
+
+/// Names of framework attributes which are used by manifests, for binary XML files whose
+/// attribute names are stripped by shrinkers. Only the resource map keeps their ids then.
+const ANDROID_ATTRIBUTES: [(u32, &str); 26] = [
+    (0x01010000, "theme"), (0x01010001, "label"), (0x01010002, "icon"), (0x01010003, "name"),
+    (0x01010006, "permission"), (0x01010009, "protectionLevel"), (0x0101000e, "enabled"),
+    (0x0101000f, "debuggable"), (0x01010010, "exported"), (0x01010011, "process"),
+    (0x01010018, "authorities"), (0x01010024, "value"), (0x01010025, "resource"),
+    (0x0101020c, "minSdkVersion"), (0x0101021b, "versionCode"), (0x0101021c, "versionName"),
+    (0x01010270, "targetSdkVersion"), (0x01010271, "maxSdkVersion"),
+    (0x01010280, "allowBackup"), (0x0101028e, "required"), (0x010102d3, "largeHeap"),
+    (0x01010472, "supportsRtl"), (0x010104ea, "usesCleartextTraffic"),
+    (0x01010572, "compileSdkVersion"), (0x01010573, "compileSdkVersionCodename"),
+    (0x0101057a, "appComponentFactory"),
+];
+
+/// The name of a framework attribute by its resource id, see [ANDROID_ATTRIBUTES].
+fn android_attribute_name(id: u32) -> Option<&'static str> {
+    ANDROID_ATTRIBUTES.iter().find(|(attr_id, _)| *attr_id == id).map(|(_, name)| *name)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlAttribute {
+    /// The namespace uri, e.g. [ANDROID_NAMESPACE].
+    pub namespace: Option<StrRef>,
+    pub name: StrRef,
+    /// The id of the attribute resource from the resource map, e.g. `0x01010003` for `android:name`.
+    pub resource_id: Option<u32>,
+    pub value: ResValue,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(StrRef),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XmlElement {
+    pub namespace: Option<StrRef>,
+    pub name: StrRef,
+    /// Namespaces which are declared on this element as `(prefix, uri)`.
+    pub namespaces: Vec<(StrRef, StrRef)>,
+    pub attributes: Vec<XmlAttribute>,
+    pub children: Vec<XmlNode>,
+    pub line: u32,
+}
+
+impl XmlElement {
+    pub fn attribute(&self, namespace: Option<&str>, name: &str) -> Option<&ResValue> {
+        self.attributes.iter()
+            .find(|attr| attr.namespace.as_deref() == namespace && &*attr.name == name)
+            .map(|attr| &attr.value)
+    }
+
+    /// An attribute in the [ANDROID_NAMESPACE], e.g. `android_attribute("name")`.
+    pub fn android_attribute(&self, name: &str) -> Option<&ResValue> {
+        self.attribute(Some(ANDROID_NAMESPACE), name)
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item=&XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// Child elements with this name.
+    pub fn elements_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a XmlElement> {
+        self.elements().filter(move |element| &*element.name == name)
+    }
+}
+
+/// A decoded binary XML file (AXML), like the `AndroidManifest.xml` or layouts in an APK.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlDocument {
+    pub root: XmlElement,
+}
+
+impl XmlDocument {
+    /// Whether the bytes start with the header of a binary XML file.
+    pub fn is_binary_xml(bytes: &[u8]) -> bool {
+        bytes.get(..4) == Some(&[0x03, 0x00, 0x08, 0x00])
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> AsmResult<XmlDocument> {
+        let bytes = Bytes(bytes);
+        let document = Chunk::read(bytes, 0)?;
+        if document.chunk_type != RES_XML_TYPE {
+            return Err(res_err("not a binary xml file"));
+        }
+        let mut strings = StringPool::default();
+        let mut resource_ids: Vec<u32> = vec![];
+        let mut pending_namespaces = vec![];
+        // elements which are not closed yet, the root is the first one
+        let mut stack: Vec<XmlElement> = vec![];
+        let mut root = None;
+        for chunk in document.children(bytes, document.body())? {
+            match chunk.chunk_type {
+                RES_STRING_POOL_TYPE => strings = StringPool::read(bytes, &chunk)?,
+                RES_XML_RESOURCE_MAP_TYPE => {
+                    resource_ids = (chunk.body()..chunk.end()).step_by(4)
+                        .map(|offset| bytes.u32(offset))
+                        .collect::<AsmResult<_>>()?;
+                }
+                RES_XML_START_NAMESPACE_TYPE => {
+                    let prefix = strings.get_opt(bytes.u32(chunk.body())?)?.unwrap_or_default();
+                    let uri = strings.get(bytes.u32(chunk.body() + 4)?)?;
+                    pending_namespaces.push((prefix, uri));
+                }
+                RES_XML_START_ELEMENT_TYPE => {
+                    let mut element = Self::read_element(bytes, &chunk, &strings, &resource_ids)?;
+                    element.namespaces = std::mem::take(&mut pending_namespaces);
+                    stack.push(element);
+                }
+                RES_XML_END_ELEMENT_TYPE => {
+                    let element = stack.pop().ok_or_else(|| res_err("unexpected end of an element"))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(XmlNode::Element(element)),
+                        None if root.is_none() => root = Some(element),
+                        None => return Err(res_err("more than one root element")),
+                    }
+                }
+                RES_XML_CDATA_TYPE => {
+                    let text = strings.get(bytes.u32(chunk.body())?)?;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(XmlNode::Text(text));
+                    }
+                }
+                // namespaces are already known by the elements which declare them
+                RES_XML_END_NAMESPACE_TYPE => {}
+                _ => {}
+            }
+        }
+        if !stack.is_empty() {
+            return Err(res_err("unclosed elements"));
+        }
+        let root = root.ok_or_else(|| res_err("no root element"))?;
+        Ok(XmlDocument { root })
+    }
+
+    /// `ResXMLTree_node` header with the line number, then `ResXMLTree_attrExt`.
+    fn read_element(
+        bytes: Bytes, chunk: &Chunk, strings: &StringPool, resource_ids: &[u32],
+    ) -> AsmResult<XmlElement> {
+        let line = bytes.u32(chunk.start + 8)?;
+        let ext = chunk.body();
+        let namespace = strings.get_opt(bytes.u32(ext)?)?;
+        let name = strings.get(bytes.u32(ext + 4)?)?;
+        let attribute_start = bytes.u16(ext + 8)? as usize;
+        let attribute_size = bytes.u16(ext + 10)? as usize;
+        let attribute_count = bytes.u16(ext + 12)? as usize;
+        let mut attributes = Vec::with_capacity(attribute_count);
+        for index in 0..attribute_count {
+            let offset = ext + attribute_start + index * attribute_size;
+            let namespace = strings.get_opt(bytes.u32(offset)?)?;
+            let name_index = bytes.u32(offset + 4)?;
+            let raw_value = strings.get_opt(bytes.u32(offset + 8)?)?;
+            let resource_id = resource_ids.get(name_index as usize).copied();
+            let mut name = strings.get(name_index)?;
+            if name.is_empty() {
+                name = match resource_id {
+                    Some(id) => android_attribute_name(id).map(StrRef::from)
+                        .unwrap_or_else(|| format!("attr_0x{id:08x}").to_ref()),
+                    None => format!("attr{index}").to_ref(),
+                };
+            }
+            let mut value = ResValue::read(bytes, offset + 12, strings)?;
+            // aapt keeps the raw string of string values, the typed value may be empty
+            if let (ResValue::Null, Some(raw_value)) = (&value, raw_value) {
+                value = ResValue::String(raw_value);
+            }
+            attributes.push(XmlAttribute { namespace, name, resource_id, value });
+        }
+        Ok(XmlElement { namespace, name, attributes, line, ..Default::default() })
+    }
+
+    /// The document as text XML, references are written as resource ids.
+    pub fn to_xml(&self) -> String {
+        self.to_xml_with(&ResourceTable::default())
+    }
+
+    /// The document as text XML, references to resources of the table are written with their names.
+    pub fn to_xml_with(&self, table: &ResourceTable) -> String {
+        let mut result = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        let mut prefixes = vec![];
+        write_element(&mut result, &self.root, 0, &mut prefixes, table);
+        result
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `prefix:name`, `prefixes` are the `(prefix, uri)` of all declarations in scope.
+fn qualified_name(namespace: &Option<StrRef>, name: &str, prefixes: &[(StrRef, StrRef)]) -> String {
+    let prefix = namespace.as_ref().and_then(|uri| {
+        prefixes.iter().rev().find(|(_, declared)| declared == uri).map(|(prefix, _)| prefix)
+    });
+    match prefix {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}:{name}"),
+        _ => name.to_string(),
+    }
+}
+
+fn write_element(
+    result: &mut String, element: &XmlElement, depth: usize, prefixes: &mut Vec<(StrRef, StrRef)>, table: &ResourceTable,
+) {
+    let indent = "    ".repeat(depth);
+    let scope = prefixes.len();
+    prefixes.extend(element.namespaces.iter().cloned());
+    let name = qualified_name(&element.namespace, &element.name, prefixes);
+    let _ = write!(result, "{indent}<{name}");
+    for (prefix, uri) in &element.namespaces {
+        let _ = write!(result, " xmlns:{prefix}=\"{}\"", escape(uri));
+    }
+    for attribute in &element.attributes {
+        let attribute_name = qualified_name(&attribute.namespace, &attribute.name, prefixes);
+        let _ = write!(result, " {attribute_name}=\"{}\"", escape(&table.value_string(&attribute.value)));
+    }
+    if element.children.is_empty() {
+        result.push_str("/>\n");
+    } else {
+        result.push_str(">\n");
+        for child in &element.children {
+            match child {
+                XmlNode::Element(child) => write_element(result, child, depth + 1, prefixes, table),
+                XmlNode::Text(text) => {
+                    let _ = writeln!(result, "{indent}    {}", escape(text.trim()));
+                }
+            }
+        }
+        let _ = writeln!(result, "{indent}</{name}>");
+    }
+    prefixes.truncate(scope);
+}
@@ -0,0 +1,167 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::ToStringRef;
+use crate::StrRef;
+
+pub(crate) const RES_STRING_POOL_TYPE: u16 = 0x0001;
+pub(crate) const RES_XML_TYPE: u16 = 0x0003;
+pub(crate) const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+pub(crate) const RES_XML_END_NAMESPACE_TYPE: u16 = 0x0101;
+pub(crate) const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+pub(crate) const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+pub(crate) const RES_XML_CDATA_TYPE: u16 = 0x0104;
+pub(crate) const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+
+/// Index of a missing string, e.g. an element without namespace.
+pub(crate) const NO_INDEX: u32 = 0xFFFF_FFFF;
+
+pub(crate) fn res_err(message: impl AsRef<str>) -> AsmErr {
+    AsmErr::IllegalFormat(format!("android resource: {}", message.as_ref()))
+}
+
+/// Little endian reads at absolute offsets, every read is bounds checked.
+#[derive(Clone, Copy)]
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Bytes<'a> {
+    pub(crate) fn slice(&self, start: usize, len: usize) -> AsmResult<&'a [u8]> {
+        start.checked_add(len)
+            .and_then(|end| self.0.get(start..end))
+            .ok_or_else(|| res_err(format!("{len} bytes at {start} are out of bounds")))
+    }
+
+    pub(crate) fn u8(&self, offset: usize) -> AsmResult<u8> {
+        Ok(self.slice(offset, 1)?[0])
+    }
+
+    pub(crate) fn u16(&self, offset: usize) -> AsmResult<u16> {
+        let bytes = self.slice(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&self, offset: usize) -> AsmResult<u32> {
+        let bytes = self.slice(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// `ResChunk_header`, the start of every chunk.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Chunk {
+    pub(crate) chunk_type: u16,
+    /// Offset of the chunk in the bytes which are read.
+    pub(crate) start: usize,
+    pub(crate) header_size: usize,
+    pub(crate) size: usize,
+}
+
+impl Chunk {
+    pub(crate) fn read(bytes: Bytes, start: usize) -> AsmResult<Chunk> {
+        let chunk_type = bytes.u16(start)?;
+        let header_size = bytes.u16(start + 2)? as usize;
+        let size = bytes.u32(start + 4)? as usize;
+        if header_size < 8 || size < header_size || bytes.slice(start, size).is_err() {
+            return Err(res_err(format!("illegal chunk 0x{chunk_type:04x} at {start}")));
+        }
+        Ok(Chunk { chunk_type, start, header_size, size })
+    }
+
+    pub(crate) fn end(&self) -> usize {
+        self.start + self.size
+    }
+
+    /// The offset of the data behind the header.
+    pub(crate) fn body(&self) -> usize {
+        self.start + self.header_size
+    }
+
+    /// The chunks inside of this chunk, starting at `offset`.
+    pub(crate) fn children(&self, bytes: Bytes, offset: usize) -> AsmResult<Vec<Chunk>> {
+        let mut children = Vec::new();
+        let mut current = offset;
+        // trailing bytes which are too short for a header are padding
+        while current + 8 <= self.end() {
+            let child = Chunk::read(bytes, current)?;
+            if child.end() > self.end() {
+                return Err(res_err(format!("chunk at {current} exceeds its parent")));
+            }
+            current = child.end();
+            children.push(child);
+        }
+        Ok(children)
+    }
+}
+
+/// `ResStringPool`, strings are decoded eagerly since pools are referenced by index everywhere.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StringPool {
+    pub(crate) strings: Vec<StrRef>,
+}
+
+impl StringPool {
+    const UTF8_FLAG: u32 = 0x100;
+
+    pub(crate) fn read(bytes: Bytes, chunk: &Chunk) -> AsmResult<StringPool> {
+        if chunk.chunk_type != RES_STRING_POOL_TYPE {
+            return Err(res_err(format!("expected a string pool at {}", chunk.start)));
+        }
+        let string_count = bytes.u32(chunk.start + 8)? as usize;
+        let flags = bytes.u32(chunk.start + 16)?;
+        let strings_start = chunk.start + bytes.u32(chunk.start + 20)? as usize;
+        let chunk_bytes = Bytes(bytes.slice(chunk.start, chunk.size)?);
+        let mut strings = Vec::with_capacity(string_count.min(chunk.size / 4));
+        for index in 0..string_count {
+            let offset = bytes.u32(chunk.body() + index * 4)? as usize;
+            let position = strings_start - chunk.start + offset;
+            let string = if flags & Self::UTF8_FLAG != 0 {
+                Self::read_utf8(chunk_bytes, position)?
+            } else {
+                Self::read_utf16(chunk_bytes, position)?
+            };
+            strings.push(string);
+        }
+        Ok(StringPool { strings })
+    }
+
+    /// A length of one or two bytes, the high bit of the first byte marks the long form.
+    fn utf8_len(bytes: Bytes, position: &mut usize) -> AsmResult<usize> {
+        let first = bytes.u8(*position)? as usize;
+        *position += 1;
+        if first & 0x80 == 0 { return Ok(first); }
+        let second = bytes.u8(*position)? as usize;
+        *position += 1;
+        Ok(((first & 0x7F) << 8) | second)
+    }
+
+    fn read_utf8(bytes: Bytes, position: usize) -> AsmResult<StrRef> {
+        let mut position = position;
+        // the length in utf-16 units is only needed by the framework
+        Self::utf8_len(bytes, &mut position)?;
+        let len = Self::utf8_len(bytes, &mut position)?;
+        Ok(String::from_utf8_lossy(bytes.slice(position, len)?).to_ref())
+    }
+
+    fn read_utf16(bytes: Bytes, position: usize) -> AsmResult<StrRef> {
+        let first = bytes.u16(position)? as usize;
+        let (len, start) = if first & 0x8000 == 0 {
+            (first, position + 2)
+        } else {
+            (((first & 0x7FFF) << 16) | bytes.u16(position + 2)? as usize, position + 4)
+        };
+        let units: Vec<u16> = bytes.slice(start, len * 2)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        Ok(String::from_utf16_lossy(&units).to_ref())
+    }
+
+    pub(crate) fn get(&self, index: u32) -> AsmResult<StrRef> {
+        self.strings.get(index as usize).cloned()
+            .ok_or_else(|| res_err(format!("string index {index} is out of bounds")))
+    }
+
+    /// [None] for [NO_INDEX].
+    pub(crate) fn get_opt(&self, index: u32) -> AsmResult<Option<StrRef>> {
+        if index == NO_INDEX { return Ok(None); }
+        self.get(index).map(Some)
+    }
+}
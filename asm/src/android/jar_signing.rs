@@ -0,0 +1,229 @@
+use crate::android::sha256::Sha256;
+use crate::android::x509::{DerReader, TAG_CONTEXT_0, TAG_INTEGER, TAG_OID, TAG_SEQUENCE, TAG_SET};
+use crate::android::{ApkSigner, Certificate, SignatureScheme, SignedDigest};
+use crate::err::AsmResult;
+use crate::impls::ToStringRef;
+use crate::StrRef;
+use std::collections::BTreeMap;
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+const SHA256_DIGEST: &str = "SHA-256-Digest";
+const SHA256_MANIFEST_DIGEST: &str = "SHA-256-Digest-Manifest";
+
+/// A section of a JAR manifest or a signature file with its raw bytes, which are digested by
+/// the signature files.
+struct Section<'a> {
+    raw: &'a [u8],
+    attributes: Vec<(String, String)>,
+}
+
+impl Section<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sections are separated by empty lines, lines which start with a space continue the
+/// previous line.
+fn parse_sections(bytes: &[u8]) -> Vec<Section<'_>> {
+    let mut sections = vec![];
+    let mut section_start = 0;
+    let mut attributes: Vec<(String, String)> = vec![];
+    let mut position = 0;
+    while position < bytes.len() {
+        let line_end = bytes[position..].iter()
+            .position(|byte| *byte == b'\n' || *byte == b'\r')
+            .map_or(bytes.len(), |offset| position + offset);
+        let mut next = line_end;
+        if bytes.get(next) == Some(&b'\r') { next += 1; }
+        if bytes.get(next) == Some(&b'\n') && (next == line_end || bytes[line_end] == b'\r') { next += 1; }
+        let line = String::from_utf8_lossy(&bytes[position..line_end]);
+        if line.is_empty() {
+            if !attributes.is_empty() {
+                sections.push(Section { raw: &bytes[section_start..next], attributes: std::mem::take(&mut attributes) });
+            }
+            section_start = next;
+        } else if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = attributes.last_mut() {
+                value.push_str(continuation);
+            }
+        } else if let Some((name, value)) = line.split_once(": ") {
+            attributes.push((name.to_string(), value.to_string()));
+        }
+        position = next;
+    }
+    if !attributes.is_empty() {
+        sections.push(Section { raw: &bytes[section_start..], attributes });
+    }
+    sections
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Decodes base64 with padding, [None] for illegal chars.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(text.len() / 4 * 3);
+    let mut value = 0u32;
+    let mut bits = 0;
+    for byte in text.trim().trim_end_matches('=').bytes() {
+        let digit = BASE64_ALPHABET.iter().position(|candidate| *candidate == byte)?;
+        value = (value << 6) | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((value >> bits) as u8);
+        }
+    }
+    Some(result)
+}
+
+/// Whether the base64 `expected` digest is the SHA-256 of `bytes`.
+fn matches_sha256(expected: &str, bytes: &[u8]) -> bool {
+    base64_decode(expected).is_some_and(|expected| expected == Sha256::digest(bytes))
+}
+
+/// Entries which don't need a digest in the manifest: directories and the signature files.
+fn is_signature_entry(name: &str) -> bool {
+    if name.ends_with('/') { return true; }
+    let Some(file_name) = name.strip_prefix("META-INF/") else { return false };
+    if file_name.contains('/') { return false; }
+    let upper = file_name.to_ascii_uppercase();
+    upper == "MANIFEST.MF" || upper.starts_with("SIG-")
+        || [".SF", ".RSA", ".DSA", ".EC"].iter().any(|extension| upper.ends_with(extension))
+}
+
+/// Verifies the JAR signatures (`META-INF/*.SF` and their signature blocks) of the
+/// decompressed `entries`, every signature file is a signer.
+pub(crate) fn verify_jar_signers(entries: &BTreeMap<String, Vec<u8>>) -> Vec<ApkSigner> {
+    let signature_files: Vec<(&str, &[u8])> = entries.iter()
+        .filter_map(|(name, bytes)| {
+            let file_name = name.strip_prefix("META-INF/")?;
+            let signer_name = file_name.strip_suffix(".SF")?;
+            (!signer_name.contains('/')).then_some((signer_name, bytes.as_slice()))
+        })
+        .collect();
+    if signature_files.is_empty() { return vec![]; }
+    let Some(manifest) = entries.get(MANIFEST) else {
+        return signature_files.iter().map(|(name, _)| {
+            let mut signer = ApkSigner::new(SignatureScheme::V1);
+            signer.name = Some(StrRef::from(*name));
+            signer.errors.push(format!("{MANIFEST} is missing"));
+            signer
+        }).collect();
+    };
+    let manifest_sections = parse_sections(manifest);
+    let entry_errors = verify_entry_digests(entries, &manifest_sections);
+    signature_files.into_iter()
+        .map(|(name, signature_file)| {
+            let mut signer = ApkSigner::new(SignatureScheme::V1);
+            signer.name = Some(StrRef::from(name));
+            verify_signature_file(&mut signer, entries, manifest, &manifest_sections, signature_file);
+            signer.errors.extend(entry_errors.iter().cloned());
+            signer
+        })
+        .collect()
+}
+
+/// Compares the digests in the manifest with the entries, all entries except the signature
+/// files must have a digest.
+fn verify_entry_digests(entries: &BTreeMap<String, Vec<u8>>, manifest_sections: &[Section]) -> Vec<String> {
+    let mut errors = vec![];
+    let mut digested = vec![];
+    for section in manifest_sections.iter().skip(1) {
+        let Some(name) = section.get("Name") else { continue };
+        digested.push(name);
+        let Some(expected) = section.get(SHA256_DIGEST) else {
+            errors.push(format!("{name} has no {SHA256_DIGEST} in the manifest, other digests are not verified"));
+            continue;
+        };
+        match entries.get(name) {
+            None => errors.push(format!("{name} is in the manifest but not in the APK")),
+            Some(bytes) if !matches_sha256(expected, bytes) => {
+                errors.push(format!("the digest of {name} doesn't match the manifest"));
+            }
+            Some(_) => {}
+        }
+    }
+    for name in entries.keys() {
+        if !is_signature_entry(name) && !digested.contains(&name.as_str()) {
+            errors.push(format!("{name} is not signed"));
+        }
+    }
+    errors
+}
+
+/// Checks that the signature file digests the manifest and reads the certificates of its
+/// signature block.
+fn verify_signature_file(
+    signer: &mut ApkSigner, entries: &BTreeMap<String, Vec<u8>>, manifest: &[u8],
+    manifest_sections: &[Section], signature_file: &[u8],
+) {
+    let name = signer.name.clone().unwrap_or_default();
+    let sections = parse_sections(signature_file);
+    let Some(main) = sections.first() else {
+        signer.errors.push(format!("META-INF/{name}.SF is empty"));
+        return;
+    };
+    if let Some(schemes) = main.get("X-Android-APK-Signed") {
+        signer.declared_schemes = schemes.split(',')
+            .filter_map(|scheme| match scheme.trim() {
+                "2" => Some(SignatureScheme::V2),
+                "3" => Some(SignatureScheme::V3),
+                _ => None,
+            })
+            .collect();
+    }
+    if let Some(expected) = main.get(SHA256_MANIFEST_DIGEST) {
+        signer.digests.push(SignedDigest {
+            algorithm: SHA256_MANIFEST_DIGEST.to_ref(),
+            digest: base64_decode(expected).unwrap_or_default(),
+            verified: Some(matches_sha256(expected, manifest)),
+        });
+    }
+    // without a matching digest of the whole manifest, every section must match
+    if !signer.digests.iter().any(|digest| digest.verified == Some(true)) {
+        let mut verified = true;
+        for manifest_section in manifest_sections.iter().skip(1) {
+            let Some(entry_name) = manifest_section.get("Name") else { continue };
+            let expected = sections.iter()
+                .find(|section| section.get("Name") == Some(entry_name))
+                .and_then(|section| section.get(SHA256_DIGEST));
+            if !expected.is_some_and(|expected| matches_sha256(expected, manifest_section.raw)) {
+                verified = false;
+                signer.errors.push(format!("the manifest section of {entry_name} doesn't match META-INF/{name}.SF"));
+            }
+        }
+        signer.digests.push(SignedDigest { algorithm: SHA256_DIGEST.to_ref(), digest: vec![], verified: Some(verified) });
+    }
+    let block = ["RSA", "DSA", "EC"].iter()
+        .find_map(|extension| entries.get(&format!("META-INF/{name}.{extension}")));
+    match block.map(|block| pkcs7_certificates(block)) {
+        None => signer.errors.push(format!("META-INF/{name}.SF has no signature block")),
+        Some(Ok(certificates)) if certificates.is_empty() => {
+            signer.errors.push(format!("the signature block of {name} has no certificate"));
+        }
+        Some(Ok(certificates)) => signer.certificates = certificates,
+        Some(Err(err)) => signer.errors.push(format!("the signature block of {name} is illegal: {err:?}")),
+    }
+}
+
+/// The certificates of a PKCS #7 `SignedData`: a content type and the signed data with a
+/// version, digest algorithms, the content and the certificates.
+fn pkcs7_certificates(bytes: &[u8]) -> AsmResult<Vec<Certificate>> {
+    let mut content_info = DerReader::new(bytes).expect(TAG_SEQUENCE)?.reader();
+    content_info.expect(TAG_OID)?;
+    let signed_data = content_info.expect(TAG_CONTEXT_0)?.reader().expect(TAG_SEQUENCE)?;
+    let mut signed_data = signed_data.reader();
+    signed_data.expect(TAG_INTEGER)?;
+    signed_data.expect(TAG_SET)?;
+    signed_data.expect(TAG_SEQUENCE)?;
+    let Some(certificates) = signed_data.optional(TAG_CONTEXT_0)? else { return Ok(vec![]) };
+    let mut reader = certificates.reader();
+    let mut certificates = vec![];
+    while !reader.is_empty() {
+        certificates.push(Certificate::from_der(reader.expect(TAG_SEQUENCE)?.encoded)?);
+    }
+    Ok(certificates)
+}
@@ -0,0 +1,181 @@
+use crate::android::{ResValue, XmlDocument, XmlElement};
+use crate::err::AsmResult;
+use crate::impls::ToStringRef;
+use crate::StrRef;
+use std::fmt::{Display, Formatter};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ComponentKind {
+    Activity,
+    /// An `<activity-alias>`, its name is the alias and not a class.
+    ActivityAlias,
+    Service,
+    Receiver,
+    Provider,
+}
+
+impl ComponentKind {
+    pub fn from_tag(tag: &str) -> Option<ComponentKind> {
+        match tag {
+            "activity" => Some(ComponentKind::Activity),
+            "activity-alias" => Some(ComponentKind::ActivityAlias),
+            "service" => Some(ComponentKind::Service),
+            "receiver" => Some(ComponentKind::Receiver),
+            "provider" => Some(ComponentKind::Provider),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            ComponentKind::Activity => "activity",
+            ComponentKind::ActivityAlias => "activity-alias",
+            ComponentKind::Service => "service",
+            ComponentKind::Receiver => "receiver",
+            ComponentKind::Provider => "provider",
+        }
+    }
+}
+
+/// An activity, service, broadcast receiver or content provider declared by the application.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestComponent {
+    pub kind: ComponentKind,
+    /// The fully qualified class name, names starting with `.` are resolved with the package.
+    pub name: StrRef,
+    /// [None] if `android:exported` isn't declared, the default depends on the intent filters
+    /// and the target sdk.
+    pub exported: Option<bool>,
+    /// The required permission, e.g. `android.permission.BIND_JOB_SERVICE`.
+    pub permission: Option<StrRef>,
+    /// Actions of all intent filters, e.g. `android.intent.action.MAIN`.
+    pub actions: Vec<StrRef>,
+}
+
+/// The parts of an `AndroidManifest.xml` which identify an application.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AndroidManifest {
+    pub package: Option<StrRef>,
+    pub version_code: Option<u32>,
+    /// A string or a reference to a string resource.
+    pub version_name: Option<StrRef>,
+    pub min_sdk: Option<u32>,
+    pub target_sdk: Option<u32>,
+    pub compile_sdk: Option<u32>,
+    /// The `android:name` of the `<application>`, a subclass of `android.app.Application`.
+    pub application: Option<StrRef>,
+    /// Permissions which are requested by `<uses-permission>`.
+    pub permissions: Vec<StrRef>,
+    /// Permissions which are defined by `<permission>`.
+    pub declared_permissions: Vec<StrRef>,
+    pub components: Vec<ManifestComponent>,
+}
+
+fn string_value(value: Option<&ResValue>) -> Option<StrRef> {
+    value.map(|value| match value {
+        ResValue::String(value) => value.clone(),
+        value => value.to_string().to_ref(),
+    })
+}
+
+impl AndroidManifest {
+    pub fn from_bytes(bytes: &[u8]) -> AsmResult<AndroidManifest> {
+        Ok(Self::from_document(&XmlDocument::from_bytes(bytes)?))
+    }
+
+    pub fn from_document(document: &XmlDocument) -> AndroidManifest {
+        let root = &document.root;
+        let package = string_value(root.attribute(None, "package"));
+        let android_u32 = |element: &XmlElement, name: &str| {
+            element.android_attribute(name).and_then(ResValue::as_u32)
+        };
+        let mut manifest = AndroidManifest {
+            version_code: android_u32(root, "versionCode"),
+            version_name: string_value(root.android_attribute("versionName")),
+            compile_sdk: android_u32(root, "compileSdkVersion"),
+            ..Default::default()
+        };
+        if let Some(uses_sdk) = root.elements_named("uses-sdk").next() {
+            manifest.min_sdk = android_u32(uses_sdk, "minSdkVersion");
+            // the target defaults to the min sdk
+            manifest.target_sdk = android_u32(uses_sdk, "targetSdkVersion").or(manifest.min_sdk);
+        }
+        let names = |tag: &str| -> Vec<StrRef> {
+            root.elements_named(tag)
+                .filter_map(|element| string_value(element.android_attribute("name")))
+                .collect()
+        };
+        manifest.permissions = names("uses-permission");
+        manifest.permissions.extend(names("uses-permission-sdk-23"));
+        manifest.declared_permissions = names("permission");
+        if let Some(application) = root.elements_named("application").next() {
+            let class_name = |name: StrRef| qualified_class_name(package.as_deref(), name);
+            manifest.application = string_value(application.android_attribute("name")).map(class_name);
+            for element in application.elements() {
+                let Some(kind) = ComponentKind::from_tag(&element.name) else { continue };
+                let Some(name) = string_value(element.android_attribute("name")) else { continue };
+                let actions = element.elements_named("intent-filter")
+                    .flat_map(|filter| filter.elements_named("action"))
+                    .filter_map(|action| string_value(action.android_attribute("name")))
+                    .collect();
+                manifest.components.push(ManifestComponent {
+                    kind,
+                    name: class_name(name),
+                    exported: element.android_attribute("exported").and_then(ResValue::as_bool),
+                    permission: string_value(element.android_attribute("permission")),
+                    actions,
+                });
+            }
+        }
+        manifest.package = package;
+        manifest
+    }
+}
+
+/// Resolves `.Main` and `Main` to `com.example.Main` like the framework does.
+fn qualified_class_name(package: Option<&str>, name: StrRef) -> StrRef {
+    let Some(package) = package else { return name };
+    if name.starts_with('.') {
+        format!("{package}{name}").to_ref()
+    } else if !name.contains('.') {
+        format!("{package}.{name}").to_ref()
+    } else {
+        name
+    }
+}
+
+impl Display for AndroidManifest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: &Option<StrRef>| value.as_deref().unwrap_or("-").to_string();
+        let number = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_else(|| "-".to_string());
+        writeln!(f, "package {}", optional(&self.package))?;
+        writeln!(f, "version {} ({})", optional(&self.version_name), number(self.version_code))?;
+        writeln!(
+            f, "sdk min {} target {} compile {}",
+            number(self.min_sdk), number(self.target_sdk), number(self.compile_sdk),
+        )?;
+        if let Some(application) = &self.application {
+            writeln!(f, "application {application}")?;
+        }
+        for permission in &self.permissions {
+            writeln!(f, "uses-permission {permission}")?;
+        }
+        for permission in &self.declared_permissions {
+            writeln!(f, "permission {permission}")?;
+        }
+        for component in &self.components {
+            write!(f, "{} {}", component.kind.tag(), component.name)?;
+            if component.exported == Some(true) {
+                write!(f, " exported")?;
+            }
+            if let Some(permission) = &component.permission {
+                write!(f, " permission {permission}")?;
+            }
+            for action in &component.actions {
+                write!(f, " {action}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
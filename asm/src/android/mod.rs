@@ -0,0 +1,19 @@
+pub use arsc::*;
+pub use axml::*;
+pub use manifest::*;
+pub use odex::*;
+pub use sha256::*;
+pub use signing::*;
+pub use value::*;
+pub use x509::Certificate;
+
+mod arsc;
+mod axml;
+mod chunk;
+mod jar_signing;
+mod manifest;
+mod odex;
+mod sha256;
+mod signing;
+mod value;
+mod x509;
@@ -0,0 +1,204 @@
+use crate::err::{AsmErr, AsmResult};
+
+fn odex_err(message: impl AsRef<str>) -> AsmErr {
+    AsmErr::IllegalFormat(format!("odex: {}", message.as_ref()))
+}
+
+const VDEX_MAGIC: &[u8; 4] = b"vdex";
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const OAT_MAGIC: &[u8; 4] = b"oat\n";
+/// Since VDEX 021 (Android 11) the header is followed by a table of sections.
+const VDEX_SECTIONED_VERSION: u32 = 21;
+const VDEX_DEX_FILE_SECTION: u32 = 1;
+/// The section of an OAT file which starts with the OAT header, the `oatdata` symbol.
+const OAT_DATA_SECTION: &str = ".rodata";
+
+const DEX_MAGIC: &[u8; 4] = b"dex\n";
+const COMPACT_DEX_MAGIC: &[u8; 4] = b"cdex";
+const DEX_HEADER_SIZE: usize = 0x70;
+const ENDIAN_CONSTANT: u32 = 0x12345678;
+
+/// Whether `bytes` start like a VDEX file, which holds the verification data and, before
+/// Android 12, the dex files of an app.
+pub fn is_vdex_bytes(bytes: &[u8]) -> bool {
+    bytes.get(..4) == Some(VDEX_MAGIC)
+}
+
+/// Whether `bytes` start like an ELF file, which is an OAT file (`.odex` or `.oat`) when
+/// its `.rodata` section starts with the OAT header.
+pub fn is_oat_bytes(bytes: &[u8]) -> bool {
+    bytes.get(..4) == Some(ELF_MAGIC)
+}
+
+/// A dex file found in a VDEX or an OAT file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmbeddedDex {
+    /// The offset of the dex file in its container.
+    pub offset: usize,
+    /// Compact dex (`cdex`) files share a data section with other dex files of the container,
+    /// their bytes are incomplete and they can't be read by [crate::dex::DexFile].
+    pub compact: bool,
+    pub bytes: Vec<u8>,
+}
+
+impl EmbeddedDex {
+    /// `classes.dex`, `classes2.dex`... like the entries of the APK which the dex files are
+    /// compiled from, `.cdex` for compact dex files.
+    pub fn name(&self, index: usize) -> String {
+        let extension = if self.compact { "cdex" } else { "dex" };
+        match index {
+            0 => format!("classes.{extension}"),
+            index => format!("classes{}.{extension}", index + 1),
+        }
+    }
+}
+
+/// Extracts the dex files of a VDEX or an OAT file. Containers since Android 12 usually
+/// keep no dex files, ART reads them from the APK, an empty list is returned for them.
+pub fn extract_dex_files(bytes: &[u8]) -> AsmResult<Vec<EmbeddedDex>> {
+    let (start, end) = if is_vdex_bytes(bytes) {
+        vdex_dex_range(bytes)?
+    } else if is_oat_bytes(bytes) {
+        oat_dex_range(bytes)?
+    } else {
+        return Err(odex_err("neither a VDEX nor an ELF file"));
+    };
+    Ok(scan_dex_files(bytes, start, end))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> AsmResult<u16> {
+    offset.checked_add(2).and_then(|end| bytes.get(offset..end))
+        .map(|value| u16::from_le_bytes([value[0], value[1]]))
+        .ok_or_else(|| odex_err(format!("2 bytes at {offset} are out of bounds")))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> AsmResult<u32> {
+    offset.checked_add(4).and_then(|end| bytes.get(offset..end))
+        .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+        .ok_or_else(|| odex_err(format!("4 bytes at {offset} are out of bounds")))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> AsmResult<u64> {
+    Ok(u32_at(bytes, offset)? as u64 | (u32_at(bytes, offset_at(offset, 4)?)? as u64) << 32)
+}
+
+/// `base + offset` for offsets read from the file, which may overflow.
+fn offset_at(base: usize, offset: usize) -> AsmResult<usize> {
+    base.checked_add(offset)
+        .ok_or_else(|| odex_err(format!("offset {offset} from {base} is out of bounds")))
+}
+
+/// The version of a VDEX or OAT header, three digits and a zero like `021\0`.
+fn header_version(bytes: &[u8], offset: usize) -> AsmResult<u32> {
+    let version = offset.checked_add(4).and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| odex_err("the header is truncated"))?;
+    std::str::from_utf8(&version[..3]).ok()
+        .filter(|_| version[3] == 0)
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| odex_err(format!("illegal version {version:?}")))
+}
+
+/// Older VDEX files have version specific headers in front of the dex files, so the whole
+/// file is searched. Newer ones have a section table of `(kind, offset, size)`.
+fn vdex_dex_range(bytes: &[u8]) -> AsmResult<(usize, usize)> {
+    let version = header_version(bytes, 4)?;
+    if version < VDEX_SECTIONED_VERSION {
+        return Ok((8, bytes.len()));
+    }
+    let section_count = u32_at(bytes, 8)? as usize;
+    for index in 0..section_count {
+        let section = index.checked_mul(12).and_then(|offset| offset.checked_add(12))
+            .ok_or_else(|| odex_err(format!("section {index} is out of bounds")))?;
+        if u32_at(bytes, section)? != VDEX_DEX_FILE_SECTION { continue; }
+        let offset = u32_at(bytes, offset_at(section, 4)?)? as usize;
+        let size = u32_at(bytes, offset_at(section, 8)?)? as usize;
+        if offset.checked_add(size).is_none_or(|end| end > bytes.len()) {
+            return Err(odex_err(format!("the dex section at {offset} exceeds the file")));
+        }
+        return Ok((offset, offset + size));
+    }
+    Ok((0, 0))
+}
+
+/// Before Android 8 the dex files are in the OAT data, behind the OAT header and the
+/// `OatDexFile` records. Later OAT files reference the dex files in the VDEX file instead.
+fn oat_dex_range(bytes: &[u8]) -> AsmResult<(usize, usize)> {
+    let is_64 = match bytes.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err(odex_err("unknown ELF class")),
+    };
+    if bytes.get(5) != Some(&1) {
+        return Err(odex_err("only little endian ELF files are supported"));
+    }
+    let (section_offset, entry_size, section_count, names_index) = if is_64 {
+        (u64_at(bytes, 0x28)? as usize, u16_at(bytes, 0x3A)?, u16_at(bytes, 0x3C)?, u16_at(bytes, 0x3E)?)
+    } else {
+        (u32_at(bytes, 0x20)? as usize, u16_at(bytes, 0x2E)?, u16_at(bytes, 0x30)?, u16_at(bytes, 0x32)?)
+    };
+    // (name, offset, size) of a section header
+    let section = |index: u16| -> AsmResult<(u32, usize, usize)> {
+        let header = (index as usize).checked_mul(entry_size as usize)
+            .and_then(|offset| offset.checked_add(section_offset))
+            .ok_or_else(|| odex_err(format!("section header {index} is out of bounds")))?;
+        let name = u32_at(bytes, header)?;
+        Ok(if is_64 {
+            (name, u64_at(bytes, offset_at(header, 24)?)? as usize, u64_at(bytes, offset_at(header, 32)?)? as usize)
+        } else {
+            (name, u32_at(bytes, offset_at(header, 16)?)? as usize, u32_at(bytes, offset_at(header, 20)?)? as usize)
+        })
+    };
+    let (_, names_offset, _) = section(names_index)?;
+    for index in 0..section_count {
+        let (name, offset, size) = section(index)?;
+        let name_start = offset_at(names_offset, name as usize)?;
+        let name_end = bytes.get(name_start..).and_then(|names| names.iter().position(|byte| *byte == 0));
+        let Some(name_end) = name_end else { continue };
+        if &bytes[name_start..name_start + name_end] != OAT_DATA_SECTION.as_bytes() { continue; }
+        if offset.checked_add(size).is_none_or(|end| end > bytes.len()) {
+            return Err(odex_err(format!("{OAT_DATA_SECTION} exceeds the file")));
+        }
+        if bytes.get(offset..offset + 4) != Some(OAT_MAGIC) {
+            return Err(odex_err(format!("{OAT_DATA_SECTION} doesn't start with an OAT header")));
+        }
+        header_version(bytes, offset + 4)?;
+        return Ok((offset, offset + size));
+    }
+    Err(odex_err(format!("no {OAT_DATA_SECTION} section, this is not an OAT file")))
+}
+
+/// The size of the dex file at `offset` if a valid dex or compact dex header is there.
+fn dex_file_size(bytes: &[u8], offset: usize, end: usize) -> Option<(usize, bool)> {
+    let header = bytes.get(offset..offset.checked_add(DEX_HEADER_SIZE)?.min(end))?;
+    if header.len() < DEX_HEADER_SIZE { return None; }
+    let compact = match &header[..4] {
+        magic if magic == DEX_MAGIC => false,
+        magic if magic == COMPACT_DEX_MAGIC => true,
+        _ => return None,
+    };
+    if !header[4..7].iter().all(u8::is_ascii_digit) || header[7] != 0 { return None; }
+    let u32_in_header = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+    let file_size = u32_in_header(32) as usize;
+    let header_size = u32_in_header(36) as usize;
+    let valid = u32_in_header(40) == ENDIAN_CONSTANT
+        && header_size >= DEX_HEADER_SIZE && file_size >= header_size
+        && offset.checked_add(file_size).is_some_and(|file_end| file_end <= end);
+    valid.then_some((file_size, compact))
+}
+
+/// Dex files are aligned to 4 bytes and follow each other, every 4 bytes are checked for a
+/// header so that the headers of the different container versions needn't be decoded.
+fn scan_dex_files(bytes: &[u8], start: usize, end: usize) -> Vec<EmbeddedDex> {
+    let mut dex_files = vec![];
+    let mut offset = start.next_multiple_of(4);
+    while offset + DEX_HEADER_SIZE <= end {
+        match dex_file_size(bytes, offset, end) {
+            Some((file_size, compact)) => {
+                dex_files.push(EmbeddedDex { offset, compact, bytes: bytes[offset..offset + file_size].to_vec() });
+                offset = (offset + file_size).next_multiple_of(4);
+            }
+            None => offset += 4,
+        }
+    }
+    dex_files
+}
@@ -0,0 +1,404 @@
+use crate::android::jar_signing::verify_jar_signers;
+use crate::android::sha256::Sha256;
+use crate::android::Certificate;
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::ToStringRef;
+use crate::StrRef;
+use std::collections::BTreeMap;
+
+pub(crate) fn sign_err(message: impl AsRef<str>) -> AsmErr {
+    AsmErr::IllegalFormat(format!("apk signature: {}", message.as_ref()))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum SignatureScheme {
+    /// JAR signatures in `META-INF`.
+    V1,
+    /// APK Signature Scheme v2, in the APK Signing Block.
+    V2,
+    /// APK Signature Scheme v3, which adds key rotation and sdk ranges.
+    V3,
+    /// APK Signature Scheme v3.1, for rotated keys on newer platforms.
+    V31,
+    /// APK Signature Scheme v4, in a separate `.idsig` file.
+    V4,
+}
+
+impl SignatureScheme {
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureScheme::V1 => "v1",
+            SignatureScheme::V2 => "v2",
+            SignatureScheme::V3 => "v3",
+            SignatureScheme::V31 => "v3.1",
+            SignatureScheme::V4 => "v4",
+        }
+    }
+}
+
+pub const APK_SIGNATURE_SCHEME_V2_BLOCK_ID: u32 = 0x7109871a;
+pub const APK_SIGNATURE_SCHEME_V3_BLOCK_ID: u32 = 0xf05368c0;
+pub const APK_SIGNATURE_SCHEME_V31_BLOCK_ID: u32 = 0x1b93ad61;
+
+/// The name of a block in the APK Signing Block, other tools than signers add blocks too.
+pub fn signing_block_name(id: u32) -> Option<&'static str> {
+    match id {
+        APK_SIGNATURE_SCHEME_V2_BLOCK_ID => Some("APK Signature Scheme v2"),
+        APK_SIGNATURE_SCHEME_V3_BLOCK_ID => Some("APK Signature Scheme v3"),
+        APK_SIGNATURE_SCHEME_V31_BLOCK_ID => Some("APK Signature Scheme v3.1"),
+        0x42726577 => Some("verity padding"),
+        0x6dff800d => Some("source stamp v2"),
+        0x2b09189e => Some("source stamp v1"),
+        0x504b4453 => Some("dependency info"),
+        0x2146444e => Some("Google Play frosting"),
+        _ => None,
+    }
+}
+
+/// The name of a signature algorithm of the v2, v3 and v4 schemes.
+pub fn signature_algorithm_name(id: u32) -> Option<&'static str> {
+    match id {
+        0x0101 => Some("RSASSA-PSS with SHA2-256"),
+        0x0102 => Some("RSASSA-PSS with SHA2-512"),
+        0x0103 => Some("RSASSA-PKCS1-v1_5 with SHA2-256"),
+        0x0104 => Some("RSASSA-PKCS1-v1_5 with SHA2-512"),
+        0x0201 => Some("ECDSA with SHA2-256"),
+        0x0202 => Some("ECDSA with SHA2-512"),
+        0x0301 => Some("DSA with SHA2-256"),
+        0x0421 => Some("RSASSA-PKCS1-v1_5 with SHA2-256 verity"),
+        0x0423 => Some("ECDSA with SHA2-256 verity"),
+        0x0425 => Some("DSA with SHA2-256 verity"),
+        _ => None,
+    }
+}
+
+/// Algorithms whose content digest is the chunked SHA-256, the only digest which is verified.
+fn is_chunked_sha256(algorithm: u32) -> bool {
+    matches!(algorithm, 0x0101 | 0x0103 | 0x0201 | 0x0301)
+}
+
+/// A digest which is signed by a signer, and whether it matches the signed content.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedDigest {
+    /// e.g. `RSASSA-PKCS1-v1_5 with SHA2-256` or `SHA-256-Digest-Manifest` for v1.
+    pub algorithm: StrRef,
+    pub digest: Vec<u8>,
+    /// [None] if the digest algorithm isn't supported.
+    pub verified: Option<bool>,
+}
+
+/// A signer of one scheme. The digests of the content are verified, the signatures over them
+/// are not: the signer is only trustworthy if the signature was verified by the platform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApkSigner {
+    pub scheme: SignatureScheme,
+    /// The name of the signature files of v1 signers, e.g. `CERT` for `META-INF/CERT.SF`.
+    pub name: Option<StrRef>,
+    /// The certificate of the signer comes first.
+    pub certificates: Vec<Certificate>,
+    /// The platform versions which use this signer, only set for v3 and v3.1 signers.
+    pub min_sdk: Option<u32>,
+    pub max_sdk: Option<u32>,
+    pub digests: Vec<SignedDigest>,
+    /// Schemes which a v1 signer declares with `X-Android-APK-Signed`, the APK is invalid if
+    /// their signatures were stripped.
+    pub declared_schemes: Vec<SignatureScheme>,
+    /// Digests which don't match, a public key which doesn't belong to the certificate and
+    /// other problems which make the signature invalid.
+    pub errors: Vec<String>,
+}
+
+impl ApkSigner {
+    pub(crate) fn new(scheme: SignatureScheme) -> ApkSigner {
+        ApkSigner {
+            scheme, name: None, certificates: vec![], min_sdk: None, max_sdk: None, digests: vec![],
+            declared_schemes: vec![], errors: vec![],
+        }
+    }
+
+    pub fn certificate(&self) -> Option<&Certificate> {
+        self.certificates.first()
+    }
+
+    /// Whether there are no errors and at least one digest matches.
+    pub fn is_verified(&self) -> bool {
+        self.errors.is_empty() && self.digests.iter().any(|digest| digest.verified == Some(true))
+    }
+}
+
+/// All signatures of an APK.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ApkSignatures {
+    pub signers: Vec<ApkSigner>,
+    /// Ids of all blocks in the APK Signing Block, see [signing_block_name].
+    pub block_ids: Vec<u32>,
+}
+
+impl ApkSignatures {
+    /// Verifies the digests of all signatures. `entries` are the decompressed ZIP entries by
+    /// their names, they are needed for v1 signatures. `idsig` is the v4 signature file.
+    pub fn verify(apk: &[u8], entries: &BTreeMap<String, Vec<u8>>, idsig: Option<&[u8]>) -> AsmResult<ApkSignatures> {
+        let sections = ZipSections::find(apk)?;
+        let mut signatures = ApkSignatures::default();
+        let mut signers = verify_jar_signers(entries);
+        if let Some(block) = find_signing_block(apk, &sections)? {
+            let mut content_digest = None;
+            for (id, value) in block.pairs {
+                signatures.block_ids.push(id);
+                let scheme = match id {
+                    APK_SIGNATURE_SCHEME_V2_BLOCK_ID => SignatureScheme::V2,
+                    APK_SIGNATURE_SCHEME_V3_BLOCK_ID => SignatureScheme::V3,
+                    APK_SIGNATURE_SCHEME_V31_BLOCK_ID => SignatureScheme::V31,
+                    _ => continue,
+                };
+                let content_digest = content_digest
+                    .get_or_insert_with(|| chunked_sha256(apk, &sections, block.offset));
+                signers.extend(read_block_signers(scheme, value, content_digest)?);
+            }
+        }
+        // v1 signatures declare the newer schemes they were signed with, so that
+        // stripping the APK Signing Block can be detected
+        let schemes: Vec<SignatureScheme> = signers.iter().map(|signer| signer.scheme).collect();
+        for signer in &mut signers {
+            let missing: Vec<SignatureScheme> = signer.declared_schemes.iter()
+                .filter(|scheme| !schemes.contains(scheme))
+                .copied()
+                .collect();
+            for scheme in missing {
+                signer.errors.push(format!("the APK declares a {} signature which is missing", scheme.name()));
+            }
+        }
+        if let Some(idsig) = idsig {
+            let signer = read_v4_signer(idsig, &signers)?;
+            signers.push(signer);
+        }
+        signatures.signers = signers;
+        Ok(signatures)
+    }
+
+    /// Whether the APK is signed and all signers are verified.
+    pub fn is_verified(&self) -> bool {
+        !self.signers.is_empty() && self.signers.iter().all(ApkSigner::is_verified)
+    }
+}
+
+/// Reads values which have a little endian `u32` length in front.
+pub(crate) struct LengthPrefixed<'a>(pub(crate) &'a [u8]);
+
+impl<'a> LengthPrefixed<'a> {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn u32(&mut self) -> AsmResult<u32> {
+        let Some((value, rest)) = self.0.split_first_chunk::<4>() else {
+            return Err(sign_err("value is truncated"));
+        };
+        self.0 = rest;
+        Ok(u32::from_le_bytes(*value))
+    }
+
+    pub(crate) fn bytes(&mut self) -> AsmResult<&'a [u8]> {
+        let len = self.u32()? as usize;
+        if len > self.0.len() {
+            return Err(sign_err(format!("length {len} exceeds its parent")));
+        }
+        let (value, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    pub(crate) fn nested(&mut self) -> AsmResult<LengthPrefixed<'a>> {
+        self.bytes().map(LengthPrefixed)
+    }
+}
+
+/// The central directory and the end of central directory record of a ZIP file.
+pub(crate) struct ZipSections {
+    pub(crate) central_directory_offset: usize,
+    pub(crate) eocd_offset: usize,
+}
+
+const EOCD_SIGNATURE: u32 = 0x06054b50;
+const EOCD_SIZE: usize = 22;
+
+impl ZipSections {
+    /// The EOCD is at the end, behind it there's only a comment of up to 64 KiB.
+    pub(crate) fn find(apk: &[u8]) -> AsmResult<ZipSections> {
+        if apk.len() < EOCD_SIZE { return Err(sign_err("file is too short for a ZIP file")); }
+        let last = apk.len() - EOCD_SIZE;
+        let first = last.saturating_sub(u16::MAX as usize);
+        let u16_at = |offset: usize| u16::from_le_bytes([apk[offset], apk[offset + 1]]) as usize;
+        let u32_at = |offset: usize| u32::from_le_bytes([apk[offset], apk[offset + 1], apk[offset + 2], apk[offset + 3]]);
+        let eocd_offset = (first..=last).rev()
+            .find(|offset| u32_at(*offset) == EOCD_SIGNATURE && offset + EOCD_SIZE + u16_at(offset + 20) == apk.len())
+            .ok_or_else(|| sign_err("no end of central directory record"))?;
+        let central_directory_size = u32_at(eocd_offset + 12) as usize;
+        let central_directory_offset = u32_at(eocd_offset + 16) as usize;
+        if central_directory_offset == u32::MAX as usize {
+            return Err(sign_err("ZIP64 files are not supported"));
+        }
+        if central_directory_offset + central_directory_size != eocd_offset {
+            return Err(sign_err("the central directory doesn't end at the end of central directory record"));
+        }
+        Ok(ZipSections { central_directory_offset, eocd_offset })
+    }
+}
+
+const SIGNING_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+
+/// The APK Signing Block, which is right in front of the central directory: its size,
+/// `(id, value)` pairs, its size again and the magic.
+struct SigningBlock<'a> {
+    offset: usize,
+    pairs: Vec<(u32, &'a [u8])>,
+}
+
+fn find_signing_block<'a>(apk: &'a [u8], sections: &ZipSections) -> AsmResult<Option<SigningBlock<'a>>> {
+    let end = sections.central_directory_offset;
+    if end < 32 || &apk[end - 16..end] != SIGNING_BLOCK_MAGIC { return Ok(None); }
+    let size = u64::from_le_bytes(apk[end - 24..end - 16].try_into().unwrap());
+    if size < 24 { return Err(sign_err("the APK Signing Block is too short")); }
+    let offset = (end as u64).checked_sub(size.saturating_add(8))
+        .ok_or_else(|| sign_err("the size of the APK Signing Block is out of bounds"))? as usize;
+    if u64::from_le_bytes(apk[offset..offset + 8].try_into().unwrap()) != size {
+        return Err(sign_err("the sizes of the APK Signing Block don't match"));
+    }
+    let mut pairs = vec![];
+    let mut rest = &apk[offset + 8..end - 24];
+    while !rest.is_empty() {
+        let Some((len, tail)) = rest.split_first_chunk::<8>() else {
+            return Err(sign_err("truncated pair in the APK Signing Block"));
+        };
+        let len = u64::from_le_bytes(*len);
+        if len < 4 || len > tail.len() as u64 {
+            return Err(sign_err("illegal pair length in the APK Signing Block"));
+        }
+        let (pair, tail) = tail.split_at(len as usize);
+        pairs.push((u32::from_le_bytes(pair[..4].try_into().unwrap()), &pair[4..]));
+        rest = tail;
+    }
+    Ok(Some(SigningBlock { offset, pairs }))
+}
+
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// The digest of the v2 and v3 schemes: the SHA-256 of the digests of 1 MiB chunks of the
+/// entries, the central directory and the EOCD, whose central directory offset points to the
+/// APK Signing Block like it does in an unsigned APK.
+fn chunked_sha256(apk: &[u8], sections: &ZipSections, block_offset: usize) -> [u8; 32] {
+    let mut eocd = apk[sections.eocd_offset..].to_vec();
+    eocd[16..20].copy_from_slice(&(block_offset as u32).to_le_bytes());
+    let parts = [&apk[..block_offset], &apk[sections.central_directory_offset..sections.eocd_offset], &eocd[..]];
+    let mut chunk_digests = vec![];
+    for chunk in parts.iter().flat_map(|part| part.chunks(CHUNK_SIZE)) {
+        let mut sha256 = Sha256::new();
+        sha256.update(&[0xa5]);
+        sha256.update(&(chunk.len() as u32).to_le_bytes());
+        sha256.update(chunk);
+        chunk_digests.push(sha256.finish());
+    }
+    let mut sha256 = Sha256::new();
+    sha256.update(&[0x5a]);
+    sha256.update(&(chunk_digests.len() as u32).to_le_bytes());
+    for digest in &chunk_digests {
+        sha256.update(digest);
+    }
+    sha256.finish()
+}
+
+fn algorithm_name(id: u32) -> StrRef {
+    signature_algorithm_name(id).map(ToStringRef::to_ref).unwrap_or_else(|| format!("0x{id:04x}").to_ref())
+}
+
+/// Signers of a v2, v3 or v3.1 block, which are a length prefixed sequence.
+fn read_block_signers(scheme: SignatureScheme, block: &[u8], content_digest: &[u8; 32]) -> AsmResult<Vec<ApkSigner>> {
+    let has_sdk_range = scheme != SignatureScheme::V2;
+    let mut signers = vec![];
+    let mut block = LengthPrefixed(block).nested()?;
+    while !block.is_empty() {
+        let mut signer_bytes = block.nested()?;
+        let mut signed_data = signer_bytes.nested()?;
+        let mut signer = ApkSigner::new(scheme);
+        if has_sdk_range {
+            signer.min_sdk = Some(signer_bytes.u32()?);
+            signer.max_sdk = Some(signer_bytes.u32()?);
+        }
+        let mut signatures = signer_bytes.nested()?;
+        let public_key = signer_bytes.bytes()?;
+
+        let mut digest_algorithms = vec![];
+        let mut digests = signed_data.nested()?;
+        while !digests.is_empty() {
+            let mut digest = digests.nested()?;
+            let algorithm = digest.u32()?;
+            let digest = digest.bytes()?;
+            digest_algorithms.push(algorithm);
+            let verified = is_chunked_sha256(algorithm).then(|| digest == content_digest);
+            if verified == Some(false) {
+                signer.errors.push(format!("the {} digest doesn't match the content", algorithm_name(algorithm)));
+            }
+            signer.digests.push(SignedDigest { algorithm: algorithm_name(algorithm), digest: digest.to_vec(), verified });
+        }
+        let mut certificates = signed_data.nested()?;
+        while !certificates.is_empty() {
+            signer.certificates.push(Certificate::from_der(certificates.bytes()?)?);
+        }
+        signed_data.nested()?;
+        if has_sdk_range && (Some(signed_data.u32()?) != signer.min_sdk || Some(signed_data.u32()?) != signer.max_sdk) {
+            signer.errors.push("the signed sdk range doesn't match the sdk range of the signer".to_string());
+        }
+
+        let mut signature_algorithms = vec![];
+        while !signatures.is_empty() {
+            signature_algorithms.push(signatures.nested()?.u32()?);
+        }
+        if signature_algorithms != digest_algorithms {
+            signer.errors.push("the algorithms of the signatures and the digests differ".to_string());
+        }
+        match signer.certificate() {
+            None => signer.errors.push("the signer has no certificate".to_string()),
+            Some(certificate) if certificate.public_key != public_key => {
+                signer.errors.push("the public key doesn't match the certificate".to_string());
+            }
+            Some(_) => {}
+        }
+        signers.push(signer);
+    }
+    if signers.is_empty() {
+        return Err(sign_err(format!("the {} block has no signers", scheme.name())));
+    }
+    Ok(signers)
+}
+
+/// A v4 signature file: a version, the hashing info with the merkle tree root and the signing
+/// info with the digest of the v2 or v3 signature, which binds the v4 signature to the APK.
+fn read_v4_signer(idsig: &[u8], signers: &[ApkSigner]) -> AsmResult<ApkSigner> {
+    let mut reader = LengthPrefixed(idsig);
+    let version = reader.u32()?;
+    if version != 2 && version != 3 {
+        return Err(sign_err(format!("unsupported v4 signature version {version}")));
+    }
+    reader.bytes()?;
+    let mut signing_info = reader.nested()?;
+    let apk_digest = signing_info.bytes()?;
+    let certificate = Certificate::from_der(signing_info.bytes()?)?;
+    signing_info.bytes()?;
+    let public_key = signing_info.bytes()?;
+    let algorithm = signing_info.u32()?;
+
+    let mut signer = ApkSigner::new(SignatureScheme::V4);
+    let verified = signers.iter()
+        .filter(|signer| matches!(signer.scheme, SignatureScheme::V2 | SignatureScheme::V3 | SignatureScheme::V31))
+        .flat_map(|signer| &signer.digests)
+        .any(|digest| digest.verified == Some(true) && digest.digest == apk_digest);
+    if !verified {
+        signer.errors.push("the APK digest doesn't match a verified v2 or v3 digest".to_string());
+    }
+    if certificate.public_key != public_key {
+        signer.errors.push("the public key doesn't match the certificate".to_string());
+    }
+    signer.digests.push(SignedDigest { algorithm: algorithm_name(algorithm), digest: apk_digest.to_vec(), verified: Some(verified) });
+    signer.certificates.push(certificate);
+    Ok(signer)
+}
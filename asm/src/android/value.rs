@@ -0,0 +1,130 @@
+use crate::android::chunk::{Bytes, StringPool};
+use crate::err::AsmResult;
+use crate::StrRef;
+use std::fmt::{Display, Formatter};
+
+/// A typed value (`Res_value`) of an attribute or a resource entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResValue {
+    Null,
+    /// A resource id like `0x7f0a0012`, written as `@0x7f0a0012`.
+    Reference(u32),
+    /// The id of a theme attribute, written as `?0x7f040001`.
+    Attribute(u32),
+    String(StrRef),
+    Float(f32),
+    /// A complex value with a unit, e.g. `16.0dp`.
+    Dimension(u32),
+    /// A complex value which is a fraction, e.g. `50.0%`.
+    Fraction(u32),
+    Int(i32),
+    Hex(u32),
+    Bool(bool),
+    /// A color in `#AARRGGBB` form, whatever format it was declared with.
+    Color(u32),
+    /// A type which isn't known, with its raw data.
+    Other { data_type: u8, data: u32 },
+}
+
+impl ResValue {
+    pub const TYPE_NULL: u8 = 0x00;
+    pub const TYPE_REFERENCE: u8 = 0x01;
+    pub const TYPE_ATTRIBUTE: u8 = 0x02;
+    pub const TYPE_STRING: u8 = 0x03;
+    pub const TYPE_FLOAT: u8 = 0x04;
+    pub const TYPE_DIMENSION: u8 = 0x05;
+    pub const TYPE_FRACTION: u8 = 0x06;
+    pub const TYPE_DYNAMIC_REFERENCE: u8 = 0x07;
+    pub const TYPE_DYNAMIC_ATTRIBUTE: u8 = 0x08;
+    pub const TYPE_INT_DEC: u8 = 0x10;
+    pub const TYPE_INT_HEX: u8 = 0x11;
+    pub const TYPE_INT_BOOLEAN: u8 = 0x12;
+    pub const TYPE_FIRST_COLOR_INT: u8 = 0x1C;
+    pub const TYPE_LAST_COLOR_INT: u8 = 0x1F;
+
+    pub(crate) fn new(data_type: u8, data: u32, strings: &StringPool) -> AsmResult<ResValue> {
+        let value = match data_type {
+            Self::TYPE_NULL => ResValue::Null,
+            Self::TYPE_REFERENCE | Self::TYPE_DYNAMIC_REFERENCE => ResValue::Reference(data),
+            Self::TYPE_ATTRIBUTE | Self::TYPE_DYNAMIC_ATTRIBUTE => ResValue::Attribute(data),
+            Self::TYPE_STRING => ResValue::String(strings.get(data)?),
+            Self::TYPE_FLOAT => ResValue::Float(f32::from_bits(data)),
+            Self::TYPE_DIMENSION => ResValue::Dimension(data),
+            Self::TYPE_FRACTION => ResValue::Fraction(data),
+            Self::TYPE_INT_DEC => ResValue::Int(data as i32),
+            Self::TYPE_INT_HEX => ResValue::Hex(data),
+            Self::TYPE_INT_BOOLEAN => ResValue::Bool(data != 0),
+            Self::TYPE_FIRST_COLOR_INT..=Self::TYPE_LAST_COLOR_INT => ResValue::Color(data),
+            data_type => ResValue::Other { data_type, data },
+        };
+        Ok(value)
+    }
+
+    /// Reads a `Res_value` at `offset`: size, a reserved byte, type and data.
+    pub(crate) fn read(bytes: Bytes, offset: usize, strings: &StringPool) -> AsmResult<ResValue> {
+        let data_type = bytes.u8(offset + 3)?;
+        let data = bytes.u32(offset + 4)?;
+        ResValue::new(data_type, data, strings)
+    }
+
+    /// Integers, and strings which contain an integer, e.g. a `versionCode` or an sdk level.
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            ResValue::Int(value) => u32::try_from(*value).ok(),
+            ResValue::Hex(value) => Some(*value),
+            ResValue::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ResValue::Bool(value) => Some(*value),
+            ResValue::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The resource id if this value refers to a resource.
+    pub fn reference(&self) -> Option<u32> {
+        match self {
+            ResValue::Reference(id) | ResValue::Attribute(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+const RADIX_MULTIPLIERS: [f32; 4] = [1.0 / 256.0, 1.0 / 32768.0, 1.0 / 8388608.0, 1.0 / 2147483648.0];
+
+/// The number of a complex value, the mantissa is stored in the high 24 bits.
+fn complex_to_float(complex: u32) -> f32 {
+    let mantissa = (complex & 0xFFFF_FF00) as i32;
+    mantissa as f32 * RADIX_MULTIPLIERS[((complex >> 4) & 0x3) as usize]
+}
+
+impl Display for ResValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResValue::Null => write!(f, "@null"),
+            ResValue::Reference(0) => write!(f, "@null"),
+            ResValue::Reference(id) => write!(f, "@0x{id:08x}"),
+            ResValue::Attribute(id) => write!(f, "?0x{id:08x}"),
+            ResValue::String(value) => write!(f, "{value}"),
+            ResValue::Float(value) => write!(f, "{value:?}"),
+            ResValue::Dimension(complex) => {
+                const UNITS: [&str; 6] = ["px", "dp", "sp", "pt", "in", "mm"];
+                let unit = UNITS.get((complex & 0xF) as usize).copied().unwrap_or("");
+                write!(f, "{:?}{unit}", complex_to_float(*complex))
+            }
+            ResValue::Fraction(complex) => {
+                let unit = if complex & 0xF == 0 { "%" } else { "%p" };
+                write!(f, "{:?}{unit}", complex_to_float(*complex) * 100.0)
+            }
+            ResValue::Int(value) => write!(f, "{value}"),
+            ResValue::Hex(value) => write!(f, "0x{value:08x}"),
+            ResValue::Bool(value) => write!(f, "{value}"),
+            ResValue::Color(argb) => write!(f, "#{argb:08x}"),
+            ResValue::Other { data_type, data } => write!(f, "(type 0x{data_type:02x})0x{data:08x}"),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::android::sha256::{to_hex, Sha256};
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::ToStringRef;
+use crate::StrRef;
+
+fn der_err(message: impl AsRef<str>) -> AsmErr {
+    AsmErr::IllegalFormat(format!("der: {}", message.as_ref()))
+}
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+const TAG_UTF8_STRING: u8 = 0x0C;
+const TAG_PRINTABLE_STRING: u8 = 0x13;
+const TAG_T61_STRING: u8 = 0x14;
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_BMP_STRING: u8 = 0x1E;
+/// `[0]` of a constructed context specific value, e.g. the version of a certificate.
+pub(crate) const TAG_CONTEXT_0: u8 = 0xA0;
+
+/// A DER value: its tag, the content and the whole encoding with tag and length.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DerValue<'a> {
+    pub(crate) tag: u8,
+    pub(crate) content: &'a [u8],
+    pub(crate) encoded: &'a [u8],
+}
+
+/// Reads DER values one after another, only definite lengths are allowed in DER.
+pub(crate) struct DerReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> DerReader<'a> {
+        DerReader { bytes, position: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    pub(crate) fn read(&mut self) -> AsmResult<DerValue<'a>> {
+        let start = self.position;
+        let byte_at = |position: usize| self.bytes.get(position).copied()
+            .ok_or_else(|| der_err(format!("value at {start} is truncated")));
+        let tag = byte_at(start)?;
+        let first = byte_at(start + 1)? as usize;
+        let (len, header_len) = if first & 0x80 == 0 {
+            (first, 2)
+        } else {
+            let len_bytes = first & 0x7F;
+            if len_bytes == 0 || len_bytes > 4 {
+                return Err(der_err(format!("unsupported length at {start}")));
+            }
+            let mut len = 0usize;
+            for index in 0..len_bytes {
+                len = (len << 8) | byte_at(start + 2 + index)? as usize;
+            }
+            (len, 2 + len_bytes)
+        };
+        let end = (start + header_len).checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| der_err(format!("value at {start} exceeds its parent")))?;
+        self.position = end;
+        Ok(DerValue { tag, content: &self.bytes[start + header_len..end], encoded: &self.bytes[start..end] })
+    }
+
+    /// Reads a value which must have `tag`.
+    pub(crate) fn expect(&mut self, tag: u8) -> AsmResult<DerValue<'a>> {
+        let value = self.read()?;
+        if value.tag != tag {
+            return Err(der_err(format!("expected tag 0x{tag:02x} but found 0x{:02x}", value.tag)));
+        }
+        Ok(value)
+    }
+
+    /// Reads the next value if it has `tag`.
+    pub(crate) fn optional(&mut self, tag: u8) -> AsmResult<Option<DerValue<'a>>> {
+        if self.bytes.get(self.position) != Some(&tag) { return Ok(None); }
+        self.read().map(Some)
+    }
+}
+
+impl<'a> DerValue<'a> {
+    pub(crate) fn reader(&self) -> DerReader<'a> {
+        DerReader::new(self.content)
+    }
+}
+
+/// The dotted form of an object identifier, e.g. `2.5.4.3`.
+pub(crate) fn oid_to_string(content: &[u8]) -> String {
+    let mut parts = vec![];
+    let mut value = 0u64;
+    for byte in content {
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 != 0 { continue; }
+        if parts.is_empty() {
+            let first = (value / 40).min(2);
+            parts.push(first);
+            parts.push(value - first * 40);
+        } else {
+            parts.push(value);
+        }
+        value = 0;
+    }
+    parts.iter().map(u64::to_string).collect::<Vec<_>>().join(".")
+}
+
+/// An X.509 certificate of a signer, only the parts which identify the signer are decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Certificate {
+    /// e.g. `CN=Android Debug, O=Android, C=US`, in the order printed by `keytool`.
+    pub subject: StrRef,
+    pub issuer: StrRef,
+    /// The serial number in hex.
+    pub serial: StrRef,
+    /// e.g. `2024-01-31T12:00:00Z`.
+    pub not_before: StrRef,
+    pub not_after: StrRef,
+    /// `RSA`, `EC`, `DSA` or the OID of other algorithms.
+    pub public_key_algorithm: StrRef,
+    /// The DER encoded `SubjectPublicKeyInfo`.
+    pub public_key: Vec<u8>,
+    /// The whole DER encoded certificate.
+    pub encoded: Vec<u8>,
+}
+
+impl Certificate {
+    pub fn from_der(bytes: &[u8]) -> AsmResult<Certificate> {
+        let certificate = DerReader::new(bytes).expect(TAG_SEQUENCE)?;
+        let tbs = certificate.reader().expect(TAG_SEQUENCE)?;
+        let mut tbs = tbs.reader();
+        tbs.optional(TAG_CONTEXT_0)?;
+        let serial = tbs.expect(TAG_INTEGER)?;
+        tbs.expect(TAG_SEQUENCE)?;
+        let issuer = tbs.expect(TAG_SEQUENCE)?;
+        let mut validity = tbs.expect(TAG_SEQUENCE)?.reader();
+        let not_before = validity.read()?;
+        let not_after = validity.read()?;
+        let subject = tbs.expect(TAG_SEQUENCE)?;
+        let public_key = tbs.expect(TAG_SEQUENCE)?;
+        let algorithm = public_key.reader().expect(TAG_SEQUENCE)?.reader().expect(TAG_OID)?;
+        let public_key_algorithm = match oid_to_string(algorithm.content).as_str() {
+            "1.2.840.113549.1.1.1" => "RSA".to_string(),
+            "1.2.840.10045.2.1" => "EC".to_string(),
+            "1.2.840.10040.4.1" => "DSA".to_string(),
+            "1.3.101.112" => "Ed25519".to_string(),
+            oid => oid.to_string(),
+        };
+        // leading zeros only keep positive integers positive
+        let serial = match serial.content {
+            [0, rest @ ..] if !rest.is_empty() => rest,
+            serial => serial,
+        };
+        Ok(Certificate {
+            subject: name_to_string(subject)?.to_ref(),
+            issuer: name_to_string(issuer)?.to_ref(),
+            serial: to_hex(serial).to_ref(),
+            not_before: time_to_string(not_before)?.to_ref(),
+            not_after: time_to_string(not_after)?.to_ref(),
+            public_key_algorithm: public_key_algorithm.to_ref(),
+            public_key: public_key.encoded.to_vec(),
+            encoded: certificate.encoded.to_vec(),
+        })
+    }
+
+    /// The SHA-256 digest of the encoded certificate in hex, which identifies a signing key.
+    pub fn sha256_fingerprint(&self) -> String {
+        to_hex(&Sha256::digest(&self.encoded))
+    }
+}
+
+/// An X.500 name like `CN=Android Debug, O=Android, C=US`.
+fn name_to_string(name: DerValue) -> AsmResult<String> {
+    let mut parts = vec![];
+    let mut rdns = name.reader();
+    while !rdns.is_empty() {
+        let mut attributes = rdns.expect(TAG_SET)?.reader();
+        while !attributes.is_empty() {
+            let mut attribute = attributes.expect(TAG_SEQUENCE)?.reader();
+            let oid = oid_to_string(attribute.expect(TAG_OID)?.content);
+            let value = attribute.read()?;
+            let key = match oid.as_str() {
+                "2.5.4.3" => "CN",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "ST",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "EMAILADDRESS",
+                oid => oid,
+            };
+            parts.push(format!("{key}={}", string_value(value)));
+        }
+    }
+    // certificates store the most general part first
+    parts.reverse();
+    Ok(parts.join(", "))
+}
+
+fn string_value(value: DerValue) -> String {
+    match value.tag {
+        TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING | TAG_T61_STRING => {
+            String::from_utf8_lossy(value.content).to_string()
+        }
+        TAG_BMP_STRING => {
+            let units: Vec<u16> = value.content.chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => format!("#{}", to_hex(value.encoded)),
+    }
+}
+
+/// `UTCTime` or `GeneralizedTime` in ISO 8601 form.
+fn time_to_string(time: DerValue) -> AsmResult<String> {
+    let text = String::from_utf8_lossy(time.content);
+    let digits = text.trim_end_matches('Z');
+    let full = match time.tag {
+        // two digit years from 1950 to 2049
+        TAG_UTC_TIME if digits.len() >= 12 => {
+            let century = if digits.as_bytes()[..2] < b"50"[..] { "20" } else { "19" };
+            format!("{century}{digits}")
+        }
+        TAG_GENERALIZED_TIME if digits.len() >= 14 => digits.to_string(),
+        _ => return Err(der_err(format!("illegal time {text}"))),
+    };
+    if !full.is_char_boundary(14) || !full[..14].bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(der_err(format!("illegal time {text}")));
+    }
+    Ok(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &full[..4], &full[4..6], &full[6..8], &full[8..10], &full[10..12], &full[12..14],
+    ))
+}
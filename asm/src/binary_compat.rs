@@ -0,0 +1,423 @@
+use crate::diff::value_string;
+use crate::node::element::{ClassNode, FieldNode, MethodNode};
+use crate::xref::MemberRef;
+use crate::{DescriptorRef, InternalNameRef, JavaAccessFlags, JavaClassAccessFlags, StrRef};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// How a change affects code which is compiled against the old version.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Severity {
+    /// Existing binaries fail to link or run, e.g. with `NoSuchMethodError`,
+    /// `IllegalAccessError` or `IncompatibleClassChangeError`.
+    Breaking,
+    /// Existing binaries still link, but behave differently until they are recompiled.
+    Behavioral,
+}
+
+/// The access level of a class or member, ordered from the most restrictive one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Visibility {
+    Private,
+    Package,
+    Protected,
+    Public,
+}
+
+impl Visibility {
+    pub fn of(access: u16) -> Visibility {
+        if access & JavaAccessFlags::ACC_PUBLIC != 0 {
+            Visibility::Public
+        } else if access & JavaAccessFlags::ACC_PROTECTED != 0 {
+            Visibility::Protected
+        } else if access & JavaAccessFlags::ACC_PRIVATE != 0 {
+            Visibility::Private
+        } else {
+            Visibility::Package
+        }
+    }
+
+    /// Whether code of other packages can use it, only such classes and members are checked.
+    pub fn is_api(self) -> bool {
+        self >= Visibility::Protected
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Package => "package-private",
+            Visibility::Protected => "protected",
+            Visibility::Public => "public",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ApiChangeKind {
+    ClassRemoved,
+    /// The class or interface isn't public anymore.
+    ClassLessAccessible,
+    ClassMadeFinal,
+    ClassMadeAbstract,
+    ClassMadeSealed,
+    /// A class became an interface, or an interface became a class.
+    ClassKindChanged { interface: bool },
+    /// A class or interface isn't a super type anymore, also if it was an indirect one.
+    SupertypeRemoved(InternalNameRef),
+    /// The member is neither declared by the class nor inherited from a super type of the new classes.
+    MemberRemoved,
+    /// The member is removed, but members with the same name and other descriptors exist.
+    DescriptorChanged { new_descs: Vec<DescriptorRef> },
+    MemberLessAccessible { old: Visibility, new: Visibility },
+    /// A field became final, or an instance method which could be overridden became final.
+    MemberMadeFinal,
+    MemberStaticChanged { now_static: bool },
+    MethodMadeAbstract,
+    /// An abstract method is added to an interface or an abstract class, existing implementations
+    /// don't define it. The JLS treats it as binary compatible, but calling the method on such an
+    /// implementation throws `AbstractMethodError` and the implementations don't compile anymore.
+    AbstractMethodAdded,
+    /// The value of a constant variable changed or it isn't a constant anymore, callers still
+    /// use the value which javac inlined into them.
+    ConstantChanged { old: StrRef, new: Option<StrRef> },
+}
+
+/// A change of a class or member which is visible to other packages.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApiChange {
+    pub class: InternalNameRef,
+    /// [None] for changes of the class itself.
+    pub member: Option<MemberRef>,
+    /// Whether the class (the old one if it changed) is an interface.
+    pub interface: bool,
+    pub kind: ApiChangeKind,
+}
+
+impl ApiChange {
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            ApiChangeKind::ConstantChanged { .. } => Severity::Behavioral,
+            _ => Severity::Breaking,
+        }
+    }
+
+    /// The section of JLS chapter 13 (Binary Compatibility) which describes this change.
+    pub fn jls_section(&self) -> &'static str {
+        let is_method = self.member.as_ref().is_some_and(|member| member.is_method());
+        match (&self.kind, self.interface) {
+            (ApiChangeKind::ClassRemoved, _) => "13.3",
+            (ApiChangeKind::ClassKindChanged { .. }, _) => "13.4",
+            (ApiChangeKind::ClassMadeAbstract, _) => "13.4.1",
+            (ApiChangeKind::ClassMadeSealed, _) => "13.4.2.1",
+            (ApiChangeKind::ClassMadeFinal, _) => "13.4.2.3",
+            (ApiChangeKind::ClassLessAccessible, false) => "13.4.3",
+            (ApiChangeKind::ClassLessAccessible, true) => "13.5.1",
+            (ApiChangeKind::SupertypeRemoved(_), false) => "13.4.4",
+            (ApiChangeKind::SupertypeRemoved(_), true) => "13.5.2",
+            (ApiChangeKind::MemberLessAccessible { .. }, _) => "13.4.7",
+            (ApiChangeKind::MemberRemoved, true) => "13.5.3",
+            (ApiChangeKind::MemberRemoved, false) if is_method => "13.4.12",
+            (ApiChangeKind::MemberRemoved, false) => "13.4.8",
+            (ApiChangeKind::DescriptorChanged { .. }, _) if is_method => "13.4.14",
+            (ApiChangeKind::DescriptorChanged { .. }, _) => "13.4.8",
+            (ApiChangeKind::MemberMadeFinal, _) if is_method => "13.4.17",
+            (ApiChangeKind::MemberMadeFinal, _) | (ApiChangeKind::ConstantChanged { .. }, _) => "13.4.9",
+            (ApiChangeKind::MemberStaticChanged { .. }, _) if is_method => "13.4.19",
+            (ApiChangeKind::MemberStaticChanged { .. }, _) => "13.4.10",
+            (ApiChangeKind::MethodMadeAbstract, _) => "13.4.16",
+            (ApiChangeKind::AbstractMethodAdded, true) => "13.5.3",
+            (ApiChangeKind::AbstractMethodAdded, false) => "13.4.16",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match &self.kind {
+            ApiChangeKind::ClassRemoved => "removed".to_string(),
+            ApiChangeKind::ClassLessAccessible => "not public anymore".to_string(),
+            ApiChangeKind::ClassMadeFinal => "final now".to_string(),
+            ApiChangeKind::ClassMadeAbstract => "abstract now".to_string(),
+            ApiChangeKind::ClassMadeSealed => "sealed now".to_string(),
+            ApiChangeKind::ClassKindChanged { interface: true } => "changed to an interface".to_string(),
+            ApiChangeKind::ClassKindChanged { interface: false } => "changed to a class".to_string(),
+            ApiChangeKind::SupertypeRemoved(name) => format!("super type {name} removed"),
+            ApiChangeKind::MemberRemoved => "removed".to_string(),
+            ApiChangeKind::DescriptorChanged { new_descs } => {
+                let new_descs: Vec<&str> = new_descs.iter().map(|desc| &**desc).collect();
+                format!("descriptor changed to {}", new_descs.join(", "))
+            }
+            ApiChangeKind::MemberLessAccessible { old, new } => {
+                format!("less accessible, {} -> {}", old.name(), new.name())
+            }
+            ApiChangeKind::MemberMadeFinal => "final now".to_string(),
+            ApiChangeKind::MemberStaticChanged { now_static: true } => "static now".to_string(),
+            ApiChangeKind::MemberStaticChanged { now_static: false } => "not static anymore".to_string(),
+            ApiChangeKind::MethodMadeAbstract => "abstract now".to_string(),
+            ApiChangeKind::AbstractMethodAdded => "abstract method added".to_string(),
+            ApiChangeKind::ConstantChanged { old, new: Some(new) } => {
+                format!("constant value changed from {old} to {new}, callers keep the inlined {old}")
+            }
+            ApiChangeKind::ConstantChanged { old, new: None } => {
+                format!("not a constant anymore, callers keep the inlined {old}")
+            }
+        }
+    }
+}
+
+impl Display for ApiChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity() {
+            Severity::Breaking => "BREAKING",
+            Severity::Behavioral => "BEHAVIORAL",
+        };
+        match &self.member {
+            Some(member) => write!(f, "{severity} {member}: ")?,
+            None => write!(f, "{severity} {}: ", self.class)?,
+        }
+        write!(f, "{} (JLS {})", self.description(), self.jls_section())
+    }
+}
+
+/// Changes between two versions of a library which affect its users, classified by the rules
+/// of JLS chapter 13. Only public and protected classes and members are checked, synthetic
+/// ones are skipped.
+#[derive(Clone, Debug, Default)]
+pub struct ApiReport {
+    /// Sorted by class name, class changes come before member changes.
+    pub changes: Vec<ApiChange>,
+    /// Count of the old classes which are part of the api.
+    pub api_class_count: usize,
+}
+
+impl Display for ApiReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+fn by_name<'a>(classes: impl IntoIterator<Item=&'a ClassNode>) -> BTreeMap<&'a str, &'a ClassNode> {
+    let mut map = BTreeMap::new();
+    for node in classes {
+        map.entry(&*node.name).or_insert(node);
+    }
+    map
+}
+
+fn is_synthetic(access: u16) -> bool {
+    access & JavaAccessFlags::ACC_SYNTHETIC != 0
+}
+
+fn is_api(access: u16) -> bool {
+    !is_synthetic(access) && Visibility::of(access).is_api()
+}
+
+fn has(access: u16, flag: u16) -> bool {
+    access & flag != 0
+}
+
+/// Classes of a version which are looked up for super types and inherited members.
+struct ClassSet<'a>(BTreeMap<&'a str, &'a ClassNode>);
+
+impl<'a> ClassSet<'a> {
+    /// All super classes and super interfaces, types outside of this set end the search.
+    fn supertypes(&self, node: &'a ClassNode) -> HashSet<&'a str> {
+        let mut supertypes = HashSet::new();
+        let mut pending: Vec<&ClassNode> = vec![node];
+        while let Some(current) = pending.pop() {
+            for supertype in current.super_name.iter().chain(&current.interfaces) {
+                if !supertypes.insert(&**supertype) { continue; }
+                if let Some(super_node) = self.0.get(&**supertype) {
+                    pending.push(super_node);
+                }
+            }
+        }
+        supertypes.remove("java/lang/Object");
+        supertypes
+    }
+
+    /// Whether a super type declares an accessible member with this name and descriptor.
+    fn inherits(&self, node: &'a ClassNode, name: &str, desc: &str, is_method: bool) -> bool {
+        self.supertypes(node).iter()
+            .filter_map(|supertype| self.0.get(supertype))
+            .any(|super_node| if is_method {
+                super_node.methods.iter().any(|m| &*m.name == name && &*m.desc == desc && is_api(m.access))
+            } else {
+                super_node.fields.iter().any(|f| &*f.name == name && &*f.desc == desc && is_api(f.access))
+            })
+    }
+}
+
+impl ApiReport {
+    pub fn new<'a>(
+        old: impl IntoIterator<Item=&'a ClassNode>, new: impl IntoIterator<Item=&'a ClassNode>,
+    ) -> ApiReport {
+        let old = ClassSet(by_name(old));
+        let new = ClassSet(by_name(new));
+        let mut report = ApiReport::default();
+        for (name, old_node) in &old.0 {
+            if !is_api(old_node.access) { continue; }
+            report.api_class_count += 1;
+            let mut checker = ClassChecker { old: &old, new: &new, old_node, changes: &mut report.changes };
+            match new.0.get(name) {
+                None => checker.class_change(ApiChangeKind::ClassRemoved),
+                Some(new_node) => checker.check(new_node),
+            }
+        }
+        report
+    }
+
+    pub fn breaking_count(&self) -> usize {
+        self.changes.iter().filter(|change| change.severity() == Severity::Breaking).count()
+    }
+
+    /// Whether code compiled against the old classes links with the new classes.
+    pub fn is_compatible(&self) -> bool {
+        self.breaking_count() == 0
+    }
+}
+
+struct ClassChecker<'a, 'r> {
+    old: &'r ClassSet<'a>,
+    new: &'r ClassSet<'a>,
+    old_node: &'a ClassNode,
+    changes: &'r mut Vec<ApiChange>,
+}
+
+impl<'a> ClassChecker<'a, '_> {
+    fn is_interface(&self) -> bool {
+        has(self.old_node.access, JavaClassAccessFlags::ACC_INTERFACE)
+    }
+
+    fn class_change(&mut self, kind: ApiChangeKind) {
+        let change = ApiChange {
+            class: self.old_node.name.clone(), member: None, interface: self.is_interface(), kind,
+        };
+        self.changes.push(change);
+    }
+
+    fn member_change(&mut self, name: &StrRef, desc: &DescriptorRef, kind: ApiChangeKind) {
+        let member = MemberRef { owner: self.old_node.name.clone(), name: name.clone(), desc: desc.clone() };
+        let change = ApiChange {
+            class: self.old_node.name.clone(), member: Some(member), interface: self.is_interface(), kind,
+        };
+        self.changes.push(change);
+    }
+
+    fn check(&mut self, new_node: &'a ClassNode) {
+        let (old_access, new_access) = (self.old_node.access, new_node.access);
+        if !is_api(new_access) {
+            // members can't be used anymore, so their changes don't matter
+            self.class_change(ApiChangeKind::ClassLessAccessible);
+            return;
+        }
+        let interface = has(new_access, JavaClassAccessFlags::ACC_INTERFACE);
+        if interface != self.is_interface() {
+            self.class_change(ApiChangeKind::ClassKindChanged { interface });
+            return;
+        }
+        let old_final = has(old_access, JavaClassAccessFlags::ACC_FINAL);
+        if !old_final && has(new_access, JavaClassAccessFlags::ACC_FINAL) {
+            self.class_change(ApiChangeKind::ClassMadeFinal);
+        }
+        if !interface && !has(old_access, JavaClassAccessFlags::ACC_ABSTRACT)
+            && has(new_access, JavaClassAccessFlags::ACC_ABSTRACT) {
+            self.class_change(ApiChangeKind::ClassMadeAbstract);
+        }
+        if !old_final && self.old_node.permitted_subclasses.is_empty() && !new_node.permitted_subclasses.is_empty() {
+            self.class_change(ApiChangeKind::ClassMadeSealed);
+        }
+        let new_supertypes = self.new.supertypes(new_node);
+        let mut removed: Vec<_> = self.old.supertypes(self.old_node).into_iter()
+            .filter(|supertype| !new_supertypes.contains(supertype))
+            .collect();
+        removed.sort_unstable();
+        for supertype in removed {
+            self.class_change(ApiChangeKind::SupertypeRemoved(supertype.into()));
+        }
+
+        for old_field in &self.old_node.fields {
+            if !is_api(old_field.access) { continue; }
+            match new_node.fields.iter().find(|f| f.name == old_field.name && f.desc == old_field.desc) {
+                Some(new_field) => self.check_field(old_field, new_field),
+                None => self.removed_member(new_node, &old_field.name, &old_field.desc, false),
+            }
+        }
+        let class_final = old_final || !self.old_node.permitted_subclasses.is_empty();
+        for old_method in &self.old_node.methods {
+            if !is_api(old_method.access) || &*old_method.name == "<clinit>" { continue; }
+            match new_node.methods.iter().find(|m| m.name == old_method.name && m.desc == old_method.desc) {
+                Some(new_method) => self.check_method(old_method, new_method, class_final),
+                None => self.removed_member(new_node, &old_method.name, &old_method.desc, true),
+            }
+        }
+        // new abstract methods break implementations which can't be changed by the library
+        if !class_final && (interface || has(new_access, JavaClassAccessFlags::ACC_ABSTRACT)) {
+            for new_method in &new_node.methods {
+                let abstract_api = is_api(new_method.access) && has(new_method.access, JavaAccessFlags::ACC_ABSTRACT);
+                let existed = self.old_node.methods.iter().any(|m| m.name == new_method.name && m.desc == new_method.desc)
+                    || self.old.inherits(self.old_node, &new_method.name, &new_method.desc, true);
+                if abstract_api && !existed {
+                    self.member_change(&new_method.name, &new_method.desc, ApiChangeKind::AbstractMethodAdded);
+                }
+            }
+        }
+    }
+
+    fn removed_member(&mut self, new_node: &'a ClassNode, name: &StrRef, desc: &DescriptorRef, is_method: bool) {
+        if &**name != "<init>" && self.new.inherits(new_node, name, desc, is_method) { return; }
+        let new_descs: Vec<DescriptorRef> = if is_method {
+            new_node.methods.iter().filter(|m| m.name == *name && is_api(m.access)).map(|m| m.desc.clone()).collect()
+        } else {
+            new_node.fields.iter().filter(|f| f.name == *name && is_api(f.access)).map(|f| f.desc.clone()).collect()
+        };
+        let kind = if new_descs.is_empty() {
+            ApiChangeKind::MemberRemoved
+        } else {
+            ApiChangeKind::DescriptorChanged { new_descs }
+        };
+        self.member_change(name, desc, kind);
+    }
+
+    fn check_access(&mut self, name: &StrRef, desc: &DescriptorRef, old_access: u16, new_access: u16) {
+        let (old, new) = (Visibility::of(old_access), Visibility::of(new_access));
+        if new < old {
+            self.member_change(name, desc, ApiChangeKind::MemberLessAccessible { old, new });
+        }
+        let now_static = has(new_access, JavaAccessFlags::ACC_STATIC);
+        if has(old_access, JavaAccessFlags::ACC_STATIC) != now_static {
+            self.member_change(name, desc, ApiChangeKind::MemberStaticChanged { now_static });
+        }
+    }
+
+    fn check_field(&mut self, old: &FieldNode, new: &FieldNode) {
+        self.check_access(&old.name, &old.desc, old.access, new.access);
+        if !has(old.access, JavaAccessFlags::ACC_FINAL) && has(new.access, JavaAccessFlags::ACC_FINAL) {
+            self.member_change(&old.name, &old.desc, ApiChangeKind::MemberMadeFinal);
+        }
+        // only static final fields with a `ConstantValue` are inlined
+        let constant = |field: &FieldNode| {
+            let constant_flags = JavaAccessFlags::ACC_STATIC | JavaAccessFlags::ACC_FINAL;
+            if field.access & constant_flags != constant_flags { return None; }
+            field.value.as_ref().map(value_string)
+        };
+        if let Some(old_value) = constant(old) {
+            let new_value = constant(new);
+            if new_value.as_ref() != Some(&old_value) {
+                self.member_change(&old.name, &old.desc, ApiChangeKind::ConstantChanged { old: old_value, new: new_value });
+            }
+        }
+    }
+
+    fn check_method(&mut self, old: &MethodNode, new: &MethodNode, class_final: bool) {
+        self.check_access(&old.name, &old.desc, old.access, new.access);
+        let overridable = !class_final && !has(old.access, JavaAccessFlags::ACC_STATIC) && &*old.name != "<init>";
+        if overridable && !has(old.access, JavaAccessFlags::ACC_FINAL) && has(new.access, JavaAccessFlags::ACC_FINAL) {
+            self.member_change(&old.name, &old.desc, ApiChangeKind::MemberMadeFinal);
+        }
+        if !has(old.access, JavaAccessFlags::ACC_ABSTRACT) && has(new.access, JavaAccessFlags::ACC_ABSTRACT) {
+            self.member_change(&old.name, &old.desc, ApiChangeKind::MethodMadeAbstract);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::impls::{desc_to_internal, method_arg_types, method_return_type, ToStringRef};
+use crate::node::values::{BootstrapMethodArgument, ConstDynamic, Handle};
+use crate::smali::{stb, SmaliNode};
+use crate::{ConstContainer, DescriptorRef, InternalNameRef, MethodHandleKind, StrRef};
+use std::fmt::{Display, Formatter};
+
+/// An `invokedynamic` (or dex `invoke-custom`) call site of a well-known bootstrap method.
+#[derive(Clone, Debug)]
+pub enum BootstrapCall {
+    /// `LambdaMetafactory.metafactory` and `LambdaMetafactory.altMetafactory`.
+    Lambda(LambdaCall),
+    /// `StringConcatFactory.makeConcatWithConstants` and `StringConcatFactory.makeConcat`.
+    StringConcat(StringConcatCall),
+    /// `ObjectMethods.bootstrap`, which implements `toString`, `hashCode` and `equals` of records.
+    ObjectMethods(ObjectMethodsCall),
+    /// `SwitchBootstraps.typeSwitch` and `SwitchBootstraps.enumSwitch` of pattern matching switches.
+    Switch(SwitchCall),
+}
+
+#[derive(Clone, Debug)]
+pub struct LambdaCall {
+    /// The internal name of the functional interface, e.g. `java/lang/Runnable`.
+    pub interface: InternalNameRef,
+    /// The name of the implemented interface method, e.g. `run`.
+    pub method_name: StrRef,
+    /// The erased descriptor of the interface method, e.g. `(Ljava/lang/Object;)V`.
+    pub method_desc: DescriptorRef,
+    /// The descriptor of the interface method with instantiated generic types.
+    pub instantiated_desc: DescriptorRef,
+    /// The method which implements the lambda, e.g. a `lambda$main$0` or a method reference.
+    pub implementation: Handle,
+    /// Descriptors of the values captured by the lambda, they are the arguments of the call site.
+    pub captured: Vec<DescriptorRef>,
+    pub serializable: bool,
+    /// Other interfaces implemented by the lambda, only set by `altMetafactory`.
+    pub marker_interfaces: Vec<InternalNameRef>,
+    /// Descriptors of bridge methods, only set by `altMetafactory`.
+    pub bridges: Vec<DescriptorRef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StringConcatCall {
+    pub parts: Vec<ConcatPart>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ConcatPart {
+    /// Constant text of the recipe.
+    Literal(StrRef),
+    /// The argument at the index of the call site and its descriptor.
+    Argument(usize, DescriptorRef),
+    /// A constant of the bootstrap arguments, marked by `\u0002` in the recipe.
+    Constant(BootstrapMethodArgument),
+}
+
+#[derive(Clone, Debug)]
+pub struct ObjectMethodsCall {
+    /// `toString`, `hashCode` or `equals`.
+    pub method_name: StrRef,
+    pub record: InternalNameRef,
+    /// Names of the record components, in the order of their declaration.
+    pub components: Vec<StrRef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SwitchCall {
+    /// `typeSwitch` or `enumSwitch`.
+    pub method_name: StrRef,
+    /// Class, string or integer labels of the cases, the index of a matched label is returned.
+    pub labels: Vec<BootstrapMethodArgument>,
+}
+
+impl LambdaCall {
+    pub const FLAG_SERIALIZABLE: i32 = 1;
+    pub const FLAG_MARKERS: i32 = 2;
+    pub const FLAG_BRIDGES: i32 = 4;
+}
+
+fn is_bootstrap(bsm: &Handle, owner: &str, names: &[&str]) -> bool {
+    &*bsm.owner == owner && names.contains(&&*bsm.name)
+}
+
+impl BootstrapCall {
+    /// Recognizes the bootstrap method of a call site, [None] for other or malformed call sites.
+    pub fn resolve(call_site: &ConstDynamic) -> Option<BootstrapCall> {
+        let ConstDynamic { name, desc, bsm, bsm_args } = call_site;
+        let call = if is_bootstrap(bsm, "java/lang/invoke/LambdaMetafactory", &["metafactory", "altMetafactory"]) {
+            BootstrapCall::Lambda(lambda_call(name, desc, bsm_args)?)
+        } else if is_bootstrap(bsm, "java/lang/invoke/StringConcatFactory", &["makeConcatWithConstants", "makeConcat"]) {
+            BootstrapCall::StringConcat(concat_call(desc, bsm_args)?)
+        } else if is_bootstrap(bsm, "java/lang/runtime/ObjectMethods", &["bootstrap"]) {
+            let [BootstrapMethodArgument::Class(record), BootstrapMethodArgument::String(names), ..] = &bsm_args[..] else {
+                return None;
+            };
+            let components = names.split(';').filter(|name| !name.is_empty()).map(|name| name.to_ref()).collect();
+            BootstrapCall::ObjectMethods(ObjectMethodsCall { method_name: name.clone(), record: record.clone(), components })
+        } else if is_bootstrap(bsm, "java/lang/runtime/SwitchBootstraps", &["typeSwitch", "enumSwitch"]) {
+            BootstrapCall::Switch(SwitchCall { method_name: bsm.name.clone(), labels: bsm_args.clone() })
+        } else {
+            return None;
+        };
+        Some(call)
+    }
+
+    /// A comment which describes this call, it's shown above the instruction in smali output.
+    pub fn to_smali(&self) -> SmaliNode {
+        stb().other(format!("# {self}").to_ref()).s()
+    }
+}
+
+/// `metafactory(samMethodType, implMethod, instantiatedMethodType)`, `altMetafactory` appends
+/// flags, markers and bridges which are prefixed by their counts.
+fn lambda_call(name: &StrRef, desc: &str, bsm_args: &[BootstrapMethodArgument]) -> Option<LambdaCall> {
+    let [
+        BootstrapMethodArgument::MethodType(method_desc),
+        BootstrapMethodArgument::Handle(implementation),
+        BootstrapMethodArgument::MethodType(instantiated_desc),
+        rest @ ..
+    ] = bsm_args else { return None };
+    let mut call = LambdaCall {
+        interface: desc_to_internal(method_return_type(desc)).to_ref(),
+        method_name: name.clone(),
+        method_desc: method_desc.clone(),
+        instantiated_desc: instantiated_desc.clone(),
+        implementation: implementation.clone(),
+        captured: method_arg_types(desc).into_iter().map(|arg| arg.to_ref()).collect(),
+        serializable: false,
+        marker_interfaces: vec![],
+        bridges: vec![],
+    };
+    let mut rest = rest.iter();
+    let Some(flags) = next_int(&mut rest) else { return Some(call) };
+    call.serializable = flags & LambdaCall::FLAG_SERIALIZABLE != 0;
+    if flags & LambdaCall::FLAG_MARKERS != 0 {
+        for _ in 0..next_int(&mut rest)? {
+            let Some(BootstrapMethodArgument::Class(marker)) = rest.next() else { return None };
+            call.marker_interfaces.push(marker.clone());
+        }
+    }
+    if flags & LambdaCall::FLAG_BRIDGES != 0 {
+        for _ in 0..next_int(&mut rest)? {
+            let Some(BootstrapMethodArgument::MethodType(bridge)) = rest.next() else { return None };
+            call.bridges.push(bridge.clone());
+        }
+    }
+    Some(call)
+}
+
+fn next_int<'a>(args: &mut impl Iterator<Item=&'a BootstrapMethodArgument>) -> Option<i32> {
+    match args.next() {
+        Some(BootstrapMethodArgument::Integer(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+/// In the recipe of `makeConcatWithConstants`, `\u0001` is the next argument and `\u0002` is
+/// the next constant. `makeConcat` has no recipe, all arguments are concatenated.
+fn concat_call(desc: &str, bsm_args: &[BootstrapMethodArgument]) -> Option<StringConcatCall> {
+    let mut args = method_arg_types(desc).into_iter().enumerate().map(|(index, arg)| ConcatPart::Argument(index, arg.to_ref()));
+    let Some((recipe, constants)) = bsm_args.split_first() else {
+        return Some(StringConcatCall { parts: args.collect() });
+    };
+    let BootstrapMethodArgument::String(recipe) = recipe else { return None };
+    let mut constants = constants.iter();
+    let mut parts = vec![];
+    let mut literal = String::new();
+    for c in recipe.chars() {
+        let part = match c {
+            '\u{1}' => args.next()?,
+            '\u{2}' => ConcatPart::Constant(constants.next()?.clone()),
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            parts.push(ConcatPart::Literal(std::mem::take(&mut literal).to_ref()));
+        }
+        parts.push(part);
+    }
+    if !literal.is_empty() {
+        parts.push(ConcatPart::Literal(literal.to_ref()));
+    }
+    Some(StringConcatCall { parts })
+}
+
+/// `invokestatic com/example/Main.lambda$main$0(Ljava/lang/String;)V`
+fn handle_member(f: &mut Formatter<'_>, handle: &Handle) -> std::fmt::Result {
+    let kind = MethodHandleKind::const_name(handle.reference_kind)
+        .map(|name| name.to_lowercase().trim_start_matches("h_").to_string())
+        .unwrap_or_else(|| format!("ref_{}", handle.reference_kind));
+    write!(f, "{kind} {}.{}{}", handle.owner, handle.name, handle.desc)
+}
+
+/// Constants are written like java literals, other arguments like smali.
+fn bootstrap_argument(f: &mut Formatter<'_>, argument: &BootstrapMethodArgument) -> std::fmt::Result {
+    match argument {
+        BootstrapMethodArgument::String(value) => write!(f, "{value:?}"),
+        BootstrapMethodArgument::Class(name) => write!(f, "{name}"),
+        BootstrapMethodArgument::Handle(handle) => handle_member(f, handle),
+        BootstrapMethodArgument::MethodType(desc) => write!(f, "{desc}"),
+        BootstrapMethodArgument::Integer(value) => write!(f, "{value}"),
+        BootstrapMethodArgument::Float(value) => write!(f, "{value}f"),
+        BootstrapMethodArgument::Long(value) => write!(f, "{value}L"),
+        BootstrapMethodArgument::Double(value) => write!(f, "{value}d"),
+    }
+}
+
+impl Display for BootstrapCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BootstrapCall::Lambda(call) => call.fmt(f),
+            BootstrapCall::StringConcat(call) => call.fmt(f),
+            BootstrapCall::ObjectMethods(call) => call.fmt(f),
+            BootstrapCall::Switch(call) => call.fmt(f),
+        }
+    }
+}
+
+/// `lambda java/lang/Runnable.run()V -> invokestatic com/example/Main.lambda$main$0()V`
+impl Display for LambdaCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lambda {}.{}{} -> ", self.interface, self.method_name, self.instantiated_desc)?;
+        handle_member(f, &self.implementation)?;
+        if !self.captured.is_empty() {
+            write!(f, " captures {}", self.captured.join(", "))?;
+        }
+        if self.serializable {
+            f.write_str(" serializable")?;
+        }
+        for marker in &self.marker_interfaces {
+            write!(f, " & {marker}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `concat "Hello, " + arg0 + "!"`
+impl Display for StringConcatCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("concat ")?;
+        if self.parts.is_empty() {
+            return f.write_str("\"\"");
+        }
+        for (index, part) in self.parts.iter().enumerate() {
+            if index > 0 { f.write_str(" + ")?; }
+            match part {
+                ConcatPart::Literal(text) => write!(f, "{text:?}")?,
+                ConcatPart::Argument(index, _) => write!(f, "arg{index}")?,
+                ConcatPart::Constant(constant) => bootstrap_argument(f, constant)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `record com/example/Point.toString [x, y]`
+impl Display for ObjectMethodsCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "record {}.{} [{}]", self.record, self.method_name, self.components.join(", "))
+    }
+}
+
+/// `typeSwitch [java/lang/String, java/lang/Integer]`
+impl Display for SwitchCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [", self.method_name)?;
+        for (index, label) in self.labels.iter().enumerate() {
+            if index > 0 { f.write_str(", ")?; }
+            bootstrap_argument(f, label)?;
+        }
+        f.write_str("]")
+    }
+}
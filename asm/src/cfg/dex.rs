@@ -0,0 +1,75 @@
+use crate::cfg::{ControlFlowGraph, EdgeKind, Handler, InsnFlow};
+use crate::dex::insn::{DexInsn, PackedSwitchPayload, SparseSwitchPayload};
+use crate::dex::insn_syntax::*;
+use crate::dex::{CodeItem, DUInt, DexFileAccessor};
+use crate::impls::dex::convert::code::{can_throw, falls_through, read_try_catches};
+use crate::impls::{desc_to_internal, ToStringRef};
+use crate::{AsmErr, AsmResult};
+use std::collections::HashMap;
+
+impl ControlFlowGraph {
+    /// Builds the graph of dalvik code, `code_off` is the offset of `code_item` in the dex file.
+    ///
+    /// Only blocks with a throwing instruction get exception edges, like the dalvik verifier.
+    /// Catch types are internal names as in jvm code.
+    pub fn from_dex(accessor: &DexFileAccessor, code_off: DUInt, code_item: &CodeItem) -> AsmResult<Self> {
+        let insns = &code_item.insn_container.insns;
+        let addressed: Vec<(DUInt, &DexInsn)> = code_item.insn_container.addressed_insns().collect();
+        let index_of_addr: HashMap<DUInt, usize> = addressed.iter().enumerate()
+            .map(|(index, (addr, _))| (*addr, index))
+            .collect();
+        let index_of = |addr: DUInt| index_of_addr.get(&addr).copied()
+            .filter(|index| !insns[*index].is_payload())
+            .ok_or_else(|| AsmErr::IllegalFormat(format!("no instruction at address {addr}")));
+
+        let mut flows = Vec::with_capacity(insns.len());
+        for (addr, insn) in &addressed {
+            if insn.is_payload() {
+                flows.push(None);
+                continue;
+            }
+            let relative = |offset: i32| addr.wrapping_add(offset as u32);
+            let (kind, targets) = match insn {
+                DexInsn::Goto(F10t { offsetA, .. }) => (EdgeKind::Jump, vec![relative(*offsetA as i32)]),
+                DexInsn::Goto16(F20t { offsetA, .. }) => (EdgeKind::Jump, vec![relative(*offsetA as i32)]),
+                DexInsn::Goto32(F30t { offsetA, .. }) => (EdgeKind::Jump, vec![relative(*offsetA)]),
+                DexInsn::IfTest(F22t { offsetC, .. }) => (EdgeKind::Jump, vec![relative(*offsetC as i32)]),
+                DexInsn::IfTestz(F21t { offsetB, .. }) => (EdgeKind::Jump, vec![relative(*offsetB as i32)]),
+                DexInsn::PackedSwitch(F31t { offsetB, .. }) |
+                DexInsn::SparseSwitch(F31t { offsetB, .. }) => {
+                    let payload = index_of_addr.get(&relative(*offsetB)).map(|index| &insns[*index]);
+                    let targets = match payload {
+                        Some(DexInsn::PackedSwitchPayload(PackedSwitchPayload { targets, .. })) |
+                        Some(DexInsn::SparseSwitchPayload(SparseSwitchPayload { targets, .. })) => targets,
+                        _ => return Err(AsmErr::IllegalFormat(format!("bad switch payload at {addr}"))),
+                    };
+                    (EdgeKind::Switch, targets.iter().map(|t| relative(*t)).collect())
+                }
+                _ => (EdgeKind::Jump, vec![]),
+            };
+            let targets = targets.into_iter()
+                .map(|target| Ok((index_of(target)?, kind.clone())))
+                .collect::<AsmResult<Vec<_>>>()?;
+            let exits = matches!(
+                insn,
+                DexInsn::ReturnVoid(_) | DexInsn::Return(_) | DexInsn::ReturnWide(_) |
+                DexInsn::ReturnObject(_) | DexInsn::Throw(_)
+            );
+            flows.push(Some(InsnFlow { falls_through: falls_through(insn), targets, exits }));
+        }
+
+        let mut handlers = vec![];
+        for try_catch in read_try_catches(accessor, code_off, code_item)? {
+            let start = addressed.partition_point(|(addr, _)| *addr < try_catch.start_addr);
+            let end = addressed.partition_point(|(addr, _)| *addr < try_catch.end_addr);
+            for (catch_type, handler_addr) in try_catch.handlers {
+                handlers.push(Handler {
+                    start, end,
+                    handler: index_of(handler_addr)?,
+                    catch_type: catch_type.map(|desc| desc_to_internal(&desc).to_ref()),
+                });
+            }
+        }
+        Ok(Self::build(&flows, &handlers, |index| can_throw(&insns[index])))
+    }
+}
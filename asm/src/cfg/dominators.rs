@@ -0,0 +1,95 @@
+use crate::cfg::reverse_postorder;
+
+/// The dominator tree of a [crate::cfg::ControlFlowGraph], computed with the iterative
+/// algorithm of Cooper, Harvey and Kennedy.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Dominators {
+    /// The immediate dominator of each block, [None] for the root and unreachable blocks.
+    idom: Vec<Option<usize>>,
+    reachable: Vec<bool>,
+}
+
+impl Dominators {
+    pub(crate) fn compute(root: usize, succs: &[Vec<usize>], preds: &[Vec<usize>]) -> Self {
+        let count = succs.len();
+        let order = reverse_postorder(root, succs);
+        let mut number = vec![usize::MAX; count];
+        for (index, node) in order.iter().enumerate() {
+            number[*node] = index;
+        }
+        let mut idom = vec![usize::MAX; count];
+        idom[root] = root;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in order.iter().skip(1).copied() {
+                let mut new_idom = usize::MAX;
+                for pred in preds[node].iter().copied() {
+                    if idom[pred] == usize::MAX { continue; }
+                    new_idom = if new_idom == usize::MAX { pred } else {
+                        intersect(&idom, &number, pred, new_idom)
+                    };
+                }
+                if idom[node] != new_idom {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        let reachable = number.iter().map(|n| *n != usize::MAX).collect();
+        let idom = idom.iter().enumerate()
+            .map(|(node, dom)| (node != root && *dom != usize::MAX).then_some(*dom))
+            .collect();
+        Dominators { idom, reachable }
+    }
+
+    /// Drops the last node, which is the virtual root of post-dominators.
+    pub(crate) fn remove_root(&mut self) {
+        let root = self.idom.len() - 1;
+        self.idom.pop();
+        self.reachable.pop();
+        for dom in &mut self.idom {
+            if *dom == Some(root) { *dom = None; }
+        }
+    }
+
+    /// The immediate dominator of `block`, [None] for the root and unreachable blocks.
+    pub fn immediate(&self, block: usize) -> Option<usize> {
+        self.idom.get(block).copied().flatten()
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.reachable.get(block).copied().unwrap_or(false)
+    }
+
+    /// Whether every path from the root to `block` passes `dominator`, a block dominates itself.
+    pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+        if !self.is_reachable(block) { return false; }
+        let mut current = block;
+        loop {
+            if current == dominator { return true; }
+            match self.immediate(current) {
+                Some(dom) => current = dom,
+                None => return false,
+            }
+        }
+    }
+
+    /// All dominators of `block` from itself up to the root of the tree.
+    pub fn dominators_of(&self, block: usize) -> Vec<usize> {
+        if !self.is_reachable(block) { return vec![]; }
+        let mut dominators = vec![block];
+        while let Some(dom) = self.immediate(*dominators.last().unwrap()) {
+            dominators.push(dom);
+        }
+        dominators
+    }
+}
+
+fn intersect(idom: &[usize], number: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while number[a] > number[b] { a = idom[a]; }
+        while number[b] > number[a] { b = idom[b]; }
+    }
+    a
+}
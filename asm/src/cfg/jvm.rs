@@ -0,0 +1,60 @@
+use crate::cfg::{ControlFlowGraph, EdgeKind, Handler, InsnFlow};
+use crate::node::element::{CodeBodyNode, LabelNode};
+use crate::node::InsnNode;
+use crate::{AsmErr, AsmResult, Opcodes};
+
+impl ControlFlowGraph {
+    /// Builds the graph of jvm code, instructions covered by an [CodeBodyNode::exception_table]
+    /// item may all throw.
+    pub fn from_code(code: &CodeBodyNode) -> AsmResult<Self> {
+        let instructions = &code.instructions;
+        let labels: Vec<LabelNode> = if code.insn_offsets.is_empty() {
+            (0..instructions.len()).map(|index| index as LabelNode).collect()
+        } else {
+            code.insn_offsets.clone()
+        };
+        let index_of = |label: LabelNode| labels.binary_search(&label)
+            .map_err(|_| AsmErr::IllegalFormat(format!("label {label} is not an instruction")));
+
+        let jsr_returns: Vec<usize> = instructions.iter().enumerate()
+            .filter(|(index, insn)| matches!(insn.opcode(), Opcodes::JSR | Opcodes::JSR_W) && index + 1 < instructions.len())
+            .map(|(index, _)| index + 1)
+            .collect();
+        let mut flows = Vec::with_capacity(instructions.len());
+        for insn in instructions {
+            let flow = match insn {
+                InsnNode::JumpInsnNode { opcode, label } => {
+                    let unconditional = matches!(*opcode, Opcodes::GOTO | Opcodes::GOTO_W | Opcodes::JSR | Opcodes::JSR_W);
+                    InsnFlow { falls_through: !unconditional, targets: vec![(index_of(*label)?, EdgeKind::Jump)], exits: false }
+                }
+                InsnNode::TableSwitchInsnNode { default, labels, .. } |
+                InsnNode::LookupSwitchInsnNode { default, labels, .. } => {
+                    let targets = std::iter::once(default).chain(labels.iter())
+                        .map(|label| Ok((index_of(*label)?, EdgeKind::Switch)))
+                        .collect::<AsmResult<Vec<_>>>()?;
+                    InsnFlow { falls_through: false, targets, exits: false }
+                }
+                InsnNode::VarInsnNode { opcode: Opcodes::RET, .. } => {
+                    let targets = jsr_returns.iter().map(|index| (*index, EdgeKind::SubroutineReturn)).collect();
+                    InsnFlow { falls_through: false, targets, exits: false }
+                }
+                InsnNode::NoOperand { opcode: Opcodes::IRETURN..=Opcodes::RETURN | Opcodes::ATHROW } => {
+                    InsnFlow { falls_through: false, targets: vec![], exits: true }
+                }
+                _ => InsnFlow { falls_through: true, targets: vec![], exits: false },
+            };
+            flows.push(Some(flow));
+        }
+
+        let mut handlers = Vec::with_capacity(code.exception_table.len());
+        for item in &code.exception_table {
+            handlers.push(Handler {
+                start: labels.partition_point(|label| *label < item.start),
+                end: labels.partition_point(|label| *label < item.end),
+                handler: index_of(item.handler)?,
+                catch_type: item.catch_type.clone(),
+            });
+        }
+        Ok(Self::build(&flows, &handlers, |_| true))
+    }
+}
@@ -0,0 +1,282 @@
+pub use dominators::*;
+
+use crate::StrRef;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+mod dex;
+mod dominators;
+mod jvm;
+
+/// How control reaches the target of an [Edge].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EdgeKind {
+    /// To the next instruction.
+    FallThrough,
+    /// To the target of a `goto`, an `if` or a `jsr`.
+    Jump,
+    /// To a case or the default target of a switch.
+    Switch,
+    /// From a `ret` to the instruction after a `jsr`.
+    SubroutineReturn,
+    /// To an exception handler, with the caught type, [None] for catch-all handlers (`finally`).
+    Exception(Option<StrRef>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Edge {
+    /// The index of the target block in [ControlFlowGraph::blocks].
+    pub target: usize,
+    pub kind: EdgeKind,
+}
+
+/// A straight-line sequence of instructions, only the first one is a jump target
+/// and only the last one branches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasicBlock {
+    /// The first instruction index of this block.
+    pub start: usize,
+    /// The instruction index after the last one of this block.
+    pub end: usize,
+    pub succs: Vec<Edge>,
+    /// Indexes of blocks with an edge to this block.
+    pub preds: Vec<usize>,
+    /// Whether the block leaves the method with a return or a throw.
+    pub exits: bool,
+}
+
+impl BasicBlock {
+    pub fn insns(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// A natural loop, which is found by a back edge to a block dominating its source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Loop {
+    pub header: usize,
+    /// Blocks jumping back to the header.
+    pub latches: Vec<usize>,
+    /// All blocks of this loop, including the header.
+    pub blocks: BTreeSet<usize>,
+}
+
+/// The control-flow graph of a method, built from [crate::node::element::CodeBodyNode::instructions]
+/// or [crate::dex::InsnContainer::insns]. Instructions are referred by their index in those lists.
+///
+/// The entry block is always the first block, dex payloads don't belong to any block.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    /// The block of each instruction.
+    block_of: Vec<Option<usize>>,
+}
+
+/// How control leaves an instruction, collected by the jvm and dex builders.
+struct InsnFlow {
+    falls_through: bool,
+    /// Targets in instruction indexes.
+    targets: Vec<(usize, EdgeKind)>,
+    exits: bool,
+}
+
+/// An exception handler covering `[start, end)` instruction indexes.
+struct Handler {
+    start: usize,
+    end: usize,
+    handler: usize,
+    catch_type: Option<StrRef>,
+}
+
+impl ControlFlowGraph {
+    /// Splits instructions into blocks, `flows` is [None] for instructions which are not code
+    /// (dex payloads). Blocks covered by a handler get an exception edge when `throws` is true
+    /// for any of their instructions.
+    fn build(flows: &[Option<InsnFlow>], handlers: &[Handler], throws: impl Fn(usize) -> bool) -> Self {
+        let count = flows.len();
+        let mut leaders = vec![false; count + 1];
+        leaders[0] = true;
+        for (index, flow) in flows.iter().enumerate() {
+            let Some(flow) = flow else {
+                leaders[index] = true;
+                leaders[index + 1] = true;
+                continue;
+            };
+            if !flow.falls_through || flow.exits || !flow.targets.is_empty() {
+                leaders[index + 1] = true;
+            }
+            for (target, _) in &flow.targets {
+                leaders[*target] = true;
+            }
+        }
+        for handler in handlers {
+            leaders[handler.start] = true;
+            leaders[handler.end] = true;
+            leaders[handler.handler] = true;
+        }
+
+        let mut blocks = vec![];
+        let mut block_of = vec![None; count];
+        let mut index = 0;
+        while index < count {
+            if flows[index].is_none() {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            index += 1;
+            while index < count && !leaders[index] {
+                index += 1;
+            }
+            block_of[start..index].fill(Some(blocks.len()));
+            let exits = flows[index - 1].as_ref().is_some_and(|flow| flow.exits);
+            blocks.push(BasicBlock { start, end: index, succs: vec![], preds: vec![], exits });
+        }
+
+        for block in &mut blocks {
+            let (start, end) = (block.start, block.end);
+            let mut succs = vec![];
+            if let Some(flow) = &flows[end - 1] {
+                if flow.falls_through && let Some(Some(next)) = block_of.get(end) {
+                    succs.push(Edge { target: *next, kind: EdgeKind::FallThrough });
+                }
+                for (target, kind) in &flow.targets {
+                    if let Some(target) = block_of[*target] {
+                        succs.push(Edge { target, kind: kind.clone() });
+                    }
+                }
+            }
+            for handler in handlers {
+                let covered = handler.start < end && start < handler.end;
+                if !covered || !(start..end).any(&throws) { continue; }
+                if let Some(target) = block_of[handler.handler] {
+                    succs.push(Edge { target, kind: EdgeKind::Exception(handler.catch_type.clone()) });
+                }
+            }
+            let mut unique: Vec<Edge> = Vec::with_capacity(succs.len());
+            for edge in succs {
+                if !unique.contains(&edge) { unique.push(edge); }
+            }
+            block.succs = unique;
+        }
+        for block_index in 0..blocks.len() {
+            let targets: Vec<usize> = blocks[block_index].succs.iter().map(|edge| edge.target).collect();
+            for target in targets {
+                let preds = &mut blocks[target].preds;
+                if !preds.contains(&block_index) { preds.push(block_index); }
+            }
+        }
+        ControlFlowGraph { blocks, block_of }
+    }
+
+    /// The block containing the instruction at `insn_index`.
+    pub fn block_of(&self, insn_index: usize) -> Option<usize> {
+        self.block_of.get(insn_index).copied().flatten()
+    }
+
+    fn succ_lists(&self) -> Vec<Vec<usize>> {
+        self.blocks.iter().map(|block| block.succs.iter().map(|edge| edge.target).collect()).collect()
+    }
+
+    fn pred_lists(&self) -> Vec<Vec<usize>> {
+        self.blocks.iter().map(|block| block.preds.clone()).collect()
+    }
+
+    /// Whether each block is reachable from the entry block.
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        for block in self.reverse_postorder() {
+            reachable[block] = true;
+        }
+        reachable
+    }
+
+    /// Blocks reachable from the entry in reverse postorder, a block comes before its
+    /// successors except along back edges.
+    pub fn reverse_postorder(&self) -> Vec<usize> {
+        if self.blocks.is_empty() { return vec![]; }
+        reverse_postorder(0, &self.succ_lists())
+    }
+
+    pub fn dominators(&self) -> Dominators {
+        if self.blocks.is_empty() { return Dominators::default(); }
+        Dominators::compute(0, &self.succ_lists(), &self.pred_lists())
+    }
+
+    /// Post-dominators, computed on the reversed graph from a virtual exit which every
+    /// [BasicBlock::exits] block and every block without successors flows to.
+    /// [Dominators::immediate] is [None] for blocks only post-dominated by the virtual exit,
+    /// and blocks which never leave the method (infinite loops) are unreachable.
+    pub fn post_dominators(&self) -> Dominators {
+        let count = self.blocks.len();
+        if count == 0 { return Dominators::default(); }
+        let exit = count;
+        let mut succs = self.pred_lists();
+        let mut preds = self.succ_lists();
+        let exit_succs: Vec<usize> = self.blocks.iter().enumerate()
+            .filter(|(_, block)| block.exits || block.succs.is_empty())
+            .map(|(index, _)| index)
+            .collect();
+        for block in &exit_succs {
+            preds[*block].push(exit);
+        }
+        succs.push(exit_succs);
+        preds.push(vec![]);
+        let mut dominators = Dominators::compute(exit, &succs, &preds);
+        dominators.remove_root();
+        dominators
+    }
+
+    /// Natural loops ordered by their headers, loops sharing a header are merged.
+    pub fn loops(&self) -> Vec<Loop> {
+        let dominators = self.dominators();
+        let mut loops: Vec<Loop> = vec![];
+        for (latch, block) in self.blocks.iter().enumerate() {
+            if !dominators.is_reachable(latch) { continue; }
+            for edge in &block.succs {
+                let header = edge.target;
+                if !dominators.dominates(header, latch) { continue; }
+                let index = match loops.iter().position(|l| l.header == header) {
+                    Some(index) => index,
+                    None => {
+                        loops.push(Loop { header, latches: vec![], blocks: BTreeSet::from([header]) });
+                        loops.len() - 1
+                    }
+                };
+                let current = &mut loops[index];
+                if !current.latches.contains(&latch) { current.latches.push(latch); }
+                // blocks reaching the latch without passing the header.
+                let mut stack = vec![latch];
+                while let Some(node) = stack.pop() {
+                    if !current.blocks.insert(node) { continue; }
+                    stack.extend(self.blocks[node].preds.iter().filter(|p| dominators.is_reachable(**p)));
+                }
+            }
+        }
+        loops.sort_by_key(|l| l.header);
+        loops
+    }
+}
+
+fn reverse_postorder(root: usize, succs: &[Vec<usize>]) -> Vec<usize> {
+    let mut visited = vec![false; succs.len()];
+    let mut order = Vec::with_capacity(succs.len());
+    // (node, index of the next successor to visit)
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+    while let Some((node, next)) = stack.last_mut() {
+        let node = *node;
+        if let Some(succ) = succs[node].get(*next).copied() {
+            *next += 1;
+            if !visited[succ] {
+                visited[succ] = true;
+                stack.push((succ, 0));
+            }
+        } else {
+            stack.pop();
+            order.push(node);
+        }
+    }
+    order.reverse();
+    order
+}
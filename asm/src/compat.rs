@@ -0,0 +1,270 @@
+use crate::impls::ToStringRef;
+use crate::node::element::ClassNode;
+use crate::node::values::ConstValue;
+use crate::node::InsnNode;
+use crate::xref::{MemberRef, Xref, XrefTarget};
+use crate::{AsmErr, AsmResult, InternalNameRef, StrRef};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// A class file feature which requires a minimum java release.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum CompatFeature {
+    /// `invokedynamic` instructions.
+    InvokeDynamic,
+    /// `module-info` classes.
+    Module,
+    /// `NestHost` and `NestMembers` attributes.
+    NestMates,
+    /// `CONSTANT_Dynamic` loaded by `ldc`.
+    ConstantDynamic,
+    Records,
+    /// `PermittedSubclasses` attributes.
+    SealedClasses,
+}
+
+impl CompatFeature {
+    /// The first java release which supports this feature.
+    pub fn min_release(self) -> u16 {
+        match self {
+            CompatFeature::InvokeDynamic => 7,
+            CompatFeature::Module => 9,
+            CompatFeature::NestMates | CompatFeature::ConstantDynamic => 11,
+            CompatFeature::Records => 16,
+            CompatFeature::SealedClasses => 17,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CompatFeature::InvokeDynamic => "invokedynamic",
+            CompatFeature::Module => "module",
+            CompatFeature::NestMates => "nestmates",
+            CompatFeature::ConstantDynamic => "condy",
+            CompatFeature::Records => "record",
+            CompatFeature::SealedClasses => "sealed",
+        }
+    }
+}
+
+/// The version and the version dependent features of a class file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassCompat {
+    pub name: InternalNameRef,
+    pub major_version: u16,
+    pub minor_version: u16,
+    /// Used features, sorted by [CompatFeature::min_release].
+    pub features: Vec<CompatFeature>,
+}
+
+/// A reason why a class doesn't run on a java release.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CompatIssue {
+    /// The class file targets a newer release.
+    ClassVersion { release: u16 },
+    /// The minor version is `0xFFFF`, the class only runs with `--enable-preview` on its release.
+    Preview,
+    Feature(CompatFeature),
+    /// A referenced class which is missing in the api signatures.
+    MissingClass(InternalNameRef),
+    /// A referenced field or method which is missing in the api signatures.
+    MissingMember(MemberRef),
+}
+
+impl Display for CompatIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompatIssue::ClassVersion { release } => write!(f, "class file targets java {release}"),
+            CompatIssue::Preview => write!(f, "class file uses preview features"),
+            CompatIssue::Feature(feature) => write!(f, "{} requires java {}", feature.name(), feature.min_release()),
+            CompatIssue::MissingClass(name) => write!(f, "missing class {name}"),
+            CompatIssue::MissingMember(member) => write!(f, "missing member {member}"),
+        }
+    }
+}
+
+impl ClassCompat {
+    pub const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+
+    pub fn from_class_node(node: &ClassNode) -> AsmResult<ClassCompat> {
+        let mut features = HashSet::new();
+        if node.module.is_some() { features.insert(CompatFeature::Module); }
+        if node.nest_host_class.is_some() || !node.nest_members.is_empty() {
+            features.insert(CompatFeature::NestMates);
+        }
+        if !node.record_components.is_empty() || node.super_name.as_deref() == Some("java/lang/Record") {
+            features.insert(CompatFeature::Records);
+        }
+        if !node.permitted_subclasses.is_empty() { features.insert(CompatFeature::SealedClasses); }
+        for method in &node.methods {
+            let Some(code_body) = method.code()? else { continue };
+            for insn in &code_body.instructions {
+                match insn {
+                    InsnNode::InvokeDynamicInsnNode(_) => { features.insert(CompatFeature::InvokeDynamic); }
+                    InsnNode::LdcInsnNode(value) if matches!(**value, ConstValue::Dynamic { .. }) => {
+                        features.insert(CompatFeature::ConstantDynamic);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut features: Vec<_> = features.into_iter().collect();
+        features.sort_by_key(|feature| (feature.min_release(), *feature));
+        Ok(ClassCompat {
+            name: node.name.clone(),
+            major_version: node.major_version,
+            minor_version: node.minor_version,
+            features,
+        })
+    }
+
+    /// The java release of the class file version, e.g. 8 for major version 52.
+    pub fn release(&self) -> u16 {
+        self.major_version.saturating_sub(44)
+    }
+
+    pub fn is_preview(&self) -> bool {
+        self.minor_version == Self::PREVIEW_MINOR_VERSION
+    }
+
+    /// The version like it is written in the class file, e.g. `61.0`.
+    pub fn version_string(&self) -> String {
+        format!("{}.{}", self.major_version, self.minor_version)
+    }
+
+    /// Issues which stop this class from running on `release`.
+    pub fn check_release(&self, release: u16) -> Vec<CompatIssue> {
+        let mut issues = vec![];
+        if self.release() > release {
+            issues.push(CompatIssue::ClassVersion { release: self.release() });
+        }
+        if self.is_preview() {
+            issues.push(CompatIssue::Preview);
+        }
+        for feature in &self.features {
+            if feature.min_release() > release {
+                issues.push(CompatIssue::Feature(*feature));
+            }
+        }
+        issues
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ApiClass {
+    supertypes: Vec<InternalNameRef>,
+    /// Names and descriptors of the declared fields and methods.
+    members: HashSet<(StrRef, StrRef)>,
+}
+
+/// Classes and members of an api, e.g. the java class library of a release or an android sdk.
+///
+/// The signature file lists one class or member per line, `#` starts a comment:
+/// ```text
+/// java/util/ArrayList : java/util/AbstractList java/util/List
+/// java/util/ArrayList.<init>()V
+/// java/lang/String.CASE_INSENSITIVE_ORDER:Ljava/util/Comparator;
+/// ```
+/// Super types of a class are optional, `java/lang/Object` is used if they are omitted.
+/// Members can be declared by the class or any of its listed super types.
+#[derive(Clone, Debug, Default)]
+pub struct ApiSignatures {
+    classes: HashMap<InternalNameRef, ApiClass>,
+    packages: HashSet<StrRef>,
+}
+
+fn package_name(internal_name: &str) -> &str {
+    internal_name.rsplit_once('/').map(|(package, _)| package).unwrap_or_default()
+}
+
+impl ApiSignatures {
+    const OBJECT: &'static str = "java/lang/Object";
+
+    pub fn parse(content: &str) -> AsmResult<ApiSignatures> {
+        let mut signatures = ApiSignatures::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() { continue; }
+            let illegal = || AsmErr::IllegalFormat(format!("illegal api signature at line {}: {line}", index + 1));
+            let (class, supertypes) = match line.split_once(" : ") {
+                Some((class, supertypes)) => (class.trim(), Some(supertypes)),
+                None => (line, None),
+            };
+            // `owner.name(desc)` or `owner.name:desc`, inner classes may contain `$` but no `.`
+            let member = class.split_once('.').map(|(owner, member)| {
+                match member.find('(') {
+                    Some(desc_start) => (owner, &member[..desc_start], &member[desc_start..]),
+                    None => {
+                        let (name, desc) = member.split_once(':').unwrap_or((member, ""));
+                        (owner, name, desc)
+                    }
+                }
+            });
+            match member {
+                Some((owner, name, desc)) => {
+                    if supertypes.is_some() || name.is_empty() || desc.is_empty() { return Err(illegal()); }
+                    signatures.class_mut(owner).members.insert((name.to_ref(), desc.to_ref()));
+                }
+                None => {
+                    let supertypes = supertypes.map(|s| s.split_whitespace().map(|s| s.to_ref()).collect());
+                    let class = signatures.class_mut(class);
+                    if let Some(supertypes) = supertypes {
+                        class.supertypes = supertypes;
+                    }
+                }
+            }
+        }
+        Ok(signatures)
+    }
+
+    fn class_mut(&mut self, name: &str) -> &mut ApiClass {
+        self.packages.insert(package_name(name).to_ref());
+        self.classes.entry(name.to_ref()).or_insert_with(|| ApiClass {
+            supertypes: if name == Self::OBJECT { vec![] } else { vec![Self::OBJECT.to_ref()] },
+            members: HashSet::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize { self.classes.len() }
+
+    pub fn is_empty(&self) -> bool { self.classes.is_empty() }
+
+    pub fn contains_class(&self, name: &str) -> bool {
+        self.classes.contains_key(name)
+    }
+
+    /// Whether classes of this package are listed, only references into them are checked.
+    pub fn covers(&self, name: &str) -> bool {
+        !name.starts_with('[') && self.packages.contains(package_name(name))
+    }
+
+    /// Whether the member is declared by `owner` or one of its super types. Super types which
+    /// are not listed may declare anything, so the member is assumed to exist.
+    pub fn contains_member(&self, owner: &str, name: &str, desc: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![owner];
+        while let Some(current) = pending.pop() {
+            if !visited.insert(current) { continue; }
+            let Some(class) = self.classes.get(current) else { return true };
+            if class.members.contains(&(name.to_ref(), desc.to_ref())) { return true; }
+            pending.extend(class.supertypes.iter().map(|s| &**s));
+        }
+        false
+    }
+
+    /// Checks the target of a reference, [None] if it exists or isn't covered by this api.
+    pub fn check(&self, xref: &Xref) -> Option<CompatIssue> {
+        let owner = xref.target.class()?;
+        if !self.covers(owner) { return None; }
+        if !self.contains_class(owner) {
+            return Some(CompatIssue::MissingClass(owner.clone()));
+        }
+        match &xref.target {
+            XrefTarget::Method(member) | XrefTarget::Field(member)
+            if !self.contains_member(&member.owner, &member.name, &member.desc) => {
+                Some(CompatIssue::MissingMember(member.clone()))
+            }
+            _ => None,
+        }
+    }
+}
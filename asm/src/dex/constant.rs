@@ -168,3 +168,12 @@ impl MethodHandleType {
     pub const H_INVOKE_DIRECT: DUShort = 0x07;
     pub const H_INVOKE_INTERFACE: DUShort = 0x08;
 }
+
+pub struct AnnotationVisibility;
+
+#[const_container(DUByte)]
+impl AnnotationVisibility {
+    pub const VISIBILITY_BUILD: DUByte = 0x00;
+    pub const VISIBILITY_RUNTIME: DUByte = 0x01;
+    pub const VISIBILITY_SYSTEM: DUByte = 0x02;
+}
@@ -115,6 +115,27 @@ insn_width_impl! {
     0, [PackedSwitchPayload, SparseSwitchPayload, FillArrayDataPayload],
 }
 
+impl DexInsn {
+    /// The real size in code units, which is the same as [DexInsn::insn_width] except
+    /// payloads, whose size depends on their content.
+    pub fn code_units(&self) -> usize {
+        match self {
+            DexInsn::PackedSwitchPayload(p) => 4 + p.size as usize * 2,
+            DexInsn::SparseSwitchPayload(p) => 2 + p.size as usize * 4,
+            DexInsn::FillArrayDataPayload(p) =>
+                4 + (p.element_width as usize * p.size.0 as usize).div_ceil(2),
+            _ => self.insn_width(),
+        }
+    }
+
+    pub fn is_payload(&self) -> bool {
+        matches!(
+            self,
+            DexInsn::PackedSwitchPayload(_) | DexInsn::SparseSwitchPayload(_) | DexInsn::FillArrayDataPayload(_)
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, ReadFrom)]
 pub struct PackedSwitchPayload {
     pub ident: DUShort, // should always be 0x0100
@@ -134,12 +155,12 @@ pub struct SparseSwitchPayload {
     pub targets: Vec<DInt>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, ReadFrom)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FillArrayDataPayload {
     pub ident: DUShort, // should always be 0x0300
     pub element_width: DUShort,
     pub size: U32BasedSize,
-    #[index(size)]
+    /// `size * element_width` bytes, the trailing padding byte is not included.
     pub data: Vec<u8>,
 }
 
@@ -2,13 +2,19 @@ pub use raw::*;
 pub mod element;
 
 use crate::dex::element::ClassContentElement;
+use crate::dex::insn::DexInsn;
+use crate::hierarchy::{ClassHierarchy, HierarchyResolver};
 use crate::impls::jvms::r::{ReadContext, U32BasedSize};
 use crate::impls::ToArc;
+use crate::node::element::ClassNode;
 use crate::smali::SmaliNode;
-use crate::{AsmErr, AsmResult, StrRef};
+use crate::{AsmErr, AsmResult, DescriptorRef, StrRef};
+use std::collections::HashMap;
 pub use constant::*;
 use std::io::Read;
+use std::sync::OnceLock;
 pub use util::*;
+pub use register_types::*;
 
 pub mod insn;
 pub mod insn_syntax;
@@ -16,6 +22,7 @@ pub mod insn_syntax;
 mod raw;
 mod constant;
 mod util;
+mod register_types;
 
 pub type Opcode = u8;
 
@@ -40,6 +47,10 @@ pub struct DexFileAccessor {
     pub endian: bool,
     pub call_site_ids: Vec<CallSiteId>,
     pub method_handles: Vec<MethodHandle>,
+    /// index of [DexFile::class_defs] by the type descriptor of the class, built on first use.
+    class_def_indexes: OnceLock<HashMap<DescriptorRef, usize>>,
+    /// hierarchy of the classes in this dex file, built on first use to compute stack map frames.
+    hierarchy: OnceLock<ClassHierarchy>,
 }
 
 impl DexFileAccessor {
@@ -66,7 +77,11 @@ impl DexFileAccessor {
         }
         let call_site_ids = Self::get_call_site_ids(&bytes, call_site_off, call_site_size, endian);
         let method_handles = Self::get_method_handles(&bytes, method_handle_off, method_handle_size, endian);
-        Self { file, bytes, endian, call_site_ids, method_handles, file_name }
+        Self {
+            file, bytes, endian, call_site_ids, method_handles, file_name,
+            class_def_indexes: OnceLock::new(),
+            hierarchy: OnceLock::new(),
+        }
     }
 
     pub fn get_class_element(&self, class_data_off: DUInt) -> AsmResult<ClassContentElement> {
@@ -77,8 +92,51 @@ impl DexFileAccessor {
         class_def.to_smali(&self)
     }
 
+    pub fn get_class_node(&self, class_def: ClassDef) -> AsmResult<ClassNode> {
+        class_def.to_class_node(self)
+    }
+
+    /// Like [DexFileAccessor::get_class_node], stack map frames merge the types of classes
+    /// found by `resolver`, e.g. one which covers all dex files of an apk.
+    pub fn get_class_node_with(&self, class_def: ClassDef, resolver: &dyn HierarchyResolver) -> AsmResult<ClassNode> {
+        class_def.to_class_node_with(self, resolver)
+    }
+
+    /// The hierarchy of the classes in this dex file, classes which can't be read are left out.
+    pub(crate) fn hierarchy(&self) -> &ClassHierarchy {
+        self.hierarchy.get_or_init(|| {
+            let mut hierarchy = ClassHierarchy::new();
+            for class_def in &self.file.class_defs {
+                let _ = hierarchy.add_class_def(self, class_def);
+            }
+            hierarchy
+        })
+    }
+
+    /// Finds the [ClassDef] of the class with the given type descriptor in this dex file.
+    pub fn find_class_def(&self, class_type: &str) -> Option<&ClassDef> {
+        let class_def_indexes = self.class_def_indexes.get_or_init(|| {
+            self.file.class_defs.iter().enumerate()
+                .filter_map(|(index, def)| Some((self.get_type(def.class_idx).ok()?, index)))
+                .collect()
+        });
+        let index = class_def_indexes.get(class_type)?;
+        self.file.class_defs.get(*index)
+    }
+
     pub fn get_code_item(&self, code_off: DUInt) -> AsmResult<Option<CodeItem>> {
         if code_off == 0 { return Ok(None); }
         self.get_data_impl(code_off).map(Some)
     }
 }
+
+impl InsnContainer {
+    /// All instructions with their addresses in code units, payloads are included.
+    pub fn addressed_insns(&self) -> impl Iterator<Item=(DUInt, &DexInsn)> {
+        self.insns.iter().scan(0 as DUInt, |addr, insn| {
+            let current = *addr;
+            *addr += insn.code_units() as DUInt;
+            Some((current, insn))
+        })
+    }
+}
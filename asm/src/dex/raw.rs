@@ -290,8 +290,8 @@ pub enum EncodedValue {
     Char(DUShort),
     Int(DInt),
     Long(DLong),
-    Float([DUByte; 4]),                 // IEEE754 32-bit
-    Double([DUByte; 8]),                // IEEE754 64-bit
+    Float([DUByte; 4]),                 // IEEE754 32-bit, little-endian
+    Double([DUByte; 8]),                // IEEE754 64-bit, little-endian
     MethodType(U32BasedSize),           // index into `proto_ids`
     MethodHandle(U32BasedSize),         // index into `method_handles`
     String(U32BasedSize),               // index into `string_ids`
@@ -326,6 +326,69 @@ pub struct EncodedAnnotationAttribute {
     pub value: EncodedValue,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, ReadFrom)]
+#[align(4)]
+pub struct AnnotationsDirectoryItem {
+    /// offset from the start of the file to the [AnnotationSetItem] of the class, or 0
+    pub class_annotations_off: DUInt,
+    pub fields_size: U32BasedSize,
+    pub annotated_methods_size: U32BasedSize,
+    pub annotated_parameters_size: U32BasedSize,
+    #[index(fields_size)]
+    pub field_annotations: Vec<FieldAnnotation>,
+    #[index(annotated_methods_size)]
+    pub method_annotations: Vec<MethodAnnotation>,
+    #[index(annotated_parameters_size)]
+    pub parameter_annotations: Vec<ParameterAnnotation>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ReadFrom)]
+pub struct FieldAnnotation {
+    pub field_idx: DUInt, // index into `field_ids`
+    /// offset from the start of the file to the [AnnotationSetItem]
+    pub annotations_off: DUInt,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ReadFrom)]
+pub struct MethodAnnotation {
+    pub method_idx: DUInt, // index into `method_ids`
+    /// offset from the start of the file to the [AnnotationSetItem]
+    pub annotations_off: DUInt,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ReadFrom)]
+pub struct ParameterAnnotation {
+    pub method_idx: DUInt, // index into `method_ids`
+    /// offset from the start of the file to the [AnnotationSetRefList]
+    pub annotations_off: DUInt,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, ReadFrom)]
+#[align(4)]
+pub struct AnnotationSetRefList {
+    pub size: U32BasedSize,
+    /// offsets from the start of the file to the [AnnotationSetItem]s, 0 if no annotations
+    /// for the parameter.
+    #[index(size)]
+    pub list: Vec<DUInt>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, ReadFrom)]
+#[align(4)]
+pub struct AnnotationSetItem {
+    pub size: U32BasedSize,
+    /// offsets from the start of the file to the [AnnotationItem]s, sorted by type_idx.
+    #[index(size)]
+    pub entries: Vec<DUInt>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, ReadFrom)]
+pub struct AnnotationItem {
+    /// see [crate::dex::AnnotationVisibility]
+    pub visibility: DUByte,
+    pub annotation: EncodedAnnotation,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ReadFrom)]
 #[align(4)]
 pub struct MethodHandle {
@@ -0,0 +1,363 @@
+use crate::dex::element::MethodElement;
+use crate::dex::insn::DexInsn;
+use crate::dex::insn_syntax::*;
+use crate::dex::{DUInt, DexFileAccessor, MethodAccessFlags};
+use crate::impls::dex::convert::code::DexCode;
+use crate::impls::dex::convert::types::{invoke_regs, Kind, RegisterTypes, Ty};
+use crate::{AsmResult, DescriptorRef, StrRef};
+use std::fmt::{Display, Formatter};
+
+/// The type of a value held by a dalvik register, like the register types of the dalvik verifier.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RegisterType {
+    /// Unassigned, or merged from incompatible values.
+    Conflict,
+    /// The constant `0` which is never used as a specific type, it could be an int,
+    /// a float, a boolean or `null`.
+    Zero,
+    /// A non-zero 32-bit constant which is never used as a specific type, or used as
+    /// both int and float.
+    Constant,
+    /// A 64-bit constant which is never used as a specific type, or used as both long and double.
+    WideConstant,
+    Null,
+    Boolean,
+    Byte,
+    Short,
+    Char,
+    Int,
+    Float,
+    Long,
+    Double,
+    /// The high half of a long or double, kept in the register after the low half.
+    WideHigh,
+    Reference(DescriptorRef),
+    /// `this` of a constructor before the super (or another) constructor is called.
+    UninitializedThis(DescriptorRef),
+    /// An instance created by `new-instance` at `address`, before its constructor is called.
+    Uninitialized { address: DUInt, type_desc: DescriptorRef },
+}
+
+const OBJECT: &str = "Ljava/lang/Object;";
+
+impl RegisterType {
+    /// The type of a field descriptor, [None] for `V`.
+    pub fn from_desc(desc: &str) -> Option<Self> {
+        let ty = match desc.as_bytes().first()? {
+            b'Z' => RegisterType::Boolean,
+            b'B' => RegisterType::Byte,
+            b'S' => RegisterType::Short,
+            b'C' => RegisterType::Char,
+            b'I' => RegisterType::Int,
+            b'F' => RegisterType::Float,
+            b'J' => RegisterType::Long,
+            b'D' => RegisterType::Double,
+            b'L' | b'[' => RegisterType::Reference(StrRef::from(desc)),
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// Whether the value takes two registers.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, RegisterType::Long | RegisterType::Double | RegisterType::WideConstant)
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            RegisterType::Null | RegisterType::Reference(_) |
+            RegisterType::UninitializedThis(_) | RegisterType::Uninitialized { .. }
+        )
+    }
+
+    fn is_int_like(&self) -> bool {
+        matches!(
+            self,
+            RegisterType::Boolean | RegisterType::Byte | RegisterType::Short | RegisterType::Char | RegisterType::Int
+        )
+    }
+
+    /// The type where two control flow paths meet. Different reference types are merged
+    /// into `java.lang.Object` since the class hierarchy is not known here.
+    pub fn merge(&self, other: &RegisterType) -> RegisterType {
+        use RegisterType::*;
+        if self == other { return self.clone(); }
+        match (self, other) {
+            (Zero, Null) | (Null, Zero) => Null,
+            (Zero | Null, Reference(ty)) | (Reference(ty), Zero | Null) => Reference(ty.clone()),
+            (Zero, Constant) | (Constant, Zero) => Constant,
+            (Zero | Constant, Float) | (Float, Zero | Constant) => Float,
+            (Zero | Constant, ty) | (ty, Zero | Constant) if ty.is_int_like() => ty.clone(),
+            (a, b) if a.is_int_like() && b.is_int_like() => Int,
+            (WideConstant, Long) | (Long, WideConstant) => Long,
+            (WideConstant, Double) | (Double, WideConstant) => Double,
+            (Reference(_), Reference(_)) => Reference(StrRef::from(OBJECT)),
+            _ => Conflict,
+        }
+    }
+
+    fn from_kind(kind: Kind) -> Self {
+        match kind {
+            Kind::Int => RegisterType::Int,
+            Kind::Float => RegisterType::Float,
+            Kind::Long => RegisterType::Long,
+            Kind::Double => RegisterType::Double,
+            Kind::Ref => RegisterType::Reference(StrRef::from(OBJECT)),
+        }
+    }
+}
+
+impl Display for RegisterType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterType::Conflict => write!(f, "conflict"),
+            RegisterType::Zero => write!(f, "zero"),
+            RegisterType::Constant => write!(f, "const"),
+            RegisterType::WideConstant => write!(f, "const-wide"),
+            RegisterType::Null => write!(f, "null"),
+            RegisterType::Boolean => write!(f, "Z"),
+            RegisterType::Byte => write!(f, "B"),
+            RegisterType::Short => write!(f, "S"),
+            RegisterType::Char => write!(f, "C"),
+            RegisterType::Int => write!(f, "I"),
+            RegisterType::Float => write!(f, "F"),
+            RegisterType::Long => write!(f, "J"),
+            RegisterType::Double => write!(f, "D"),
+            RegisterType::WideHigh => write!(f, "wide-high"),
+            RegisterType::Reference(ty) => write!(f, "{ty}"),
+            RegisterType::UninitializedThis(ty) => write!(f, "uninit-this {ty}"),
+            RegisterType::Uninitialized { address, type_desc } => write!(f, "uninit@{address} {type_desc}"),
+        }
+    }
+}
+
+/// The types of all registers before each instruction of a method.
+///
+/// Types flow forward from the parameters of the proto, invoke return types (through `move-result`),
+/// field types, array element types and exception handlers. Constants take the type decided by
+/// their usages, e.g. `const/4 v0, 0` passed as an object argument is [RegisterType::Null].
+#[derive(Clone, Debug, Default)]
+pub struct RegisterTypeMap {
+    /// Addresses of the instructions in code units, payloads are excluded.
+    pub addresses: Vec<DUInt>,
+    /// Register types before each instruction of [RegisterTypeMap::addresses], [None]
+    /// for unreachable instructions.
+    pub frames: Vec<Option<Vec<RegisterType>>>,
+}
+
+impl RegisterTypeMap {
+    /// Infers register types of `method` declared in the class `class_type` (a descriptor),
+    /// [None] for methods without code.
+    pub fn analyze(
+        accessor: &DexFileAccessor, class_type: &str, method: &MethodElement,
+    ) -> AsmResult<Option<Self>> {
+        let Some(code_item) = accessor.get_code_item(method.code_off)? else { return Ok(None) };
+        let desc = format!("({}){}", method.parameters.concat(), method.return_type);
+        let is_static = method.access_flags & MethodAccessFlags::ACC_STATIC != 0;
+        let code = DexCode::new(
+            accessor, method.code_off, &code_item, StrRef::from(class_type), StrRef::from(desc), is_static,
+        )?;
+        let is_constructor = method.name.as_ref() == "<init>" && class_type != OBJECT;
+        Inference::new(&code)?.run(is_constructor).map(Some)
+    }
+
+    /// Register types before the instruction at `address`.
+    pub fn types_at(&self, address: DUInt) -> Option<&[RegisterType]> {
+        let index = self.addresses.binary_search(&address).ok()?;
+        self.frames[index].as_deref()
+    }
+
+    pub fn type_at(&self, address: DUInt, register: u16) -> Option<&RegisterType> {
+        self.types_at(address)?.get(register as usize)
+    }
+}
+
+struct Inference<'a> {
+    code: &'a DexCode<'a>,
+    types: RegisterTypes,
+}
+
+impl<'a> Inference<'a> {
+    fn new(code: &'a DexCode<'a>) -> AsmResult<Self> {
+        Ok(Inference { code, types: RegisterTypes::analyze(code)? })
+    }
+
+    fn run(&self, is_constructor: bool) -> AsmResult<RegisterTypeMap> {
+        let code = self.code;
+        let count = code.insns.len();
+        let addresses: Vec<DUInt> = code.insns.iter().map(|(addr, _)| *addr).collect();
+        let mut frames: Vec<Option<Vec<RegisterType>>> = vec![None; count];
+        if count == 0 { return Ok(RegisterTypeMap { addresses, frames }); }
+
+        let mut entry = vec![RegisterType::Conflict; code.registers as usize];
+        for (position, (reg, desc)) in code.param_regs().into_iter().enumerate() {
+            let ty = if position == 0 && !code.is_static && is_constructor {
+                RegisterType::UninitializedThis(desc)
+            } else {
+                RegisterType::from_desc(&desc).unwrap_or(RegisterType::Conflict)
+            };
+            set(&mut entry, reg as usize, ty);
+        }
+
+        let blocks = code.blocks();
+        let mut block_in: Vec<Option<Vec<RegisterType>>> = vec![None; blocks.ranges.len()];
+        block_in[0] = Some(entry);
+        let mut worklist = vec![0];
+        let mut queued = vec![false; blocks.ranges.len()];
+        queued[0] = true;
+        while let Some(block) = worklist.pop() {
+            queued[block] = false;
+            let Some(state_in) = block_in[block].clone() else { continue };
+            let (start, end) = blocks.ranges[block];
+            let mut state = state_in.clone();
+            for (index, frame) in frames.iter_mut().enumerate().take(end).skip(start) {
+                *frame = Some(state.clone());
+                self.execute(index, &mut state)?;
+            }
+            let normal = blocks.succs[block].iter().map(|succ| (*succ, &state));
+            // a throwing instruction doesn't write its destination register.
+            let exceptional = blocks.handler_succs[block].iter().map(|succ| (*succ, &state_in));
+            for (succ, incoming) in normal.chain(exceptional) {
+                let changed = match &mut block_in[succ] {
+                    Some(existing) => {
+                        let mut changed = false;
+                        for (current, other) in existing.iter_mut().zip(incoming) {
+                            let merged = current.merge(other);
+                            if merged != *current {
+                                *current = merged;
+                                changed = true;
+                            }
+                        }
+                        changed
+                    }
+                    None => {
+                        block_in[succ] = Some(incoming.clone());
+                        true
+                    }
+                };
+                if changed && !queued[succ] {
+                    queued[succ] = true;
+                    worklist.push(succ);
+                }
+            }
+        }
+        Ok(RegisterTypeMap { addresses, frames })
+    }
+
+    fn execute(&self, index: usize, regs: &mut [RegisterType]) -> AsmResult<()> {
+        let code = self.code;
+        let (address, insn) = code.insns[index];
+        let ops = &self.types.ops[index];
+        if let Some(receiver) = self.constructed(insn)? {
+            // all copies of the instance are initialized by the constructor.
+            let uninitialized = regs.get(receiver as usize).cloned();
+            let initialized = match &uninitialized {
+                Some(RegisterType::UninitializedThis(ty)) |
+                Some(RegisterType::Uninitialized { type_desc: ty, .. }) => Some(RegisterType::Reference(ty.clone())),
+                _ => None,
+            };
+            if let (Some(uninitialized), Some(initialized)) = (uninitialized, initialized) {
+                for reg in regs.iter_mut().filter(|reg| **reg == uninitialized) {
+                    *reg = initialized.clone();
+                }
+            }
+        }
+        let Some((reg, ty)) = ops.def else { return Ok(()) };
+        let value = if ops.is_move {
+            let source = ops.uses.first().and_then(|(src, _)| regs.get(*src as usize));
+            source.cloned().unwrap_or(RegisterType::Conflict)
+        } else if let Some(literal) = ops.literal {
+            match self.types.def_kind(index) {
+                Some(Kind::Ref) if literal == 0 => RegisterType::Null,
+                Some(Kind::Ref) | None => match ty {
+                    Ty::Wide => RegisterType::WideConstant,
+                    _ if literal == 0 => RegisterType::Zero,
+                    _ => RegisterType::Constant,
+                },
+                Some(kind) => RegisterType::from_kind(kind),
+            }
+        } else {
+            match insn {
+                DexInsn::NewInstance(_) => {
+                    let type_desc = ops.def_ref.clone().unwrap_or_else(|| StrRef::from(OBJECT));
+                    RegisterType::Uninitialized { address, type_desc }
+                }
+                DexInsn::MoveResult(_) | DexInsn::MoveResultWide(_) | DexInsn::MoveResultObject(_) => {
+                    RegisterType::from_desc(&code.result_type(index)?).unwrap_or(RegisterType::Conflict)
+                }
+                DexInsn::MoveException(_) => {
+                    let catch_types = code.catch_types(index);
+                    let first = catch_types.first().cloned().flatten();
+                    match first {
+                        Some(ty) if catch_types.iter().all(|t| t.as_ref() == Some(&ty)) => RegisterType::Reference(ty),
+                        _ => RegisterType::Reference(StrRef::from("Ljava/lang/Throwable;")),
+                    }
+                }
+                DexInsn::IInstanceOp(F22c { constC: field, .. }) |
+                DexInsn::SStaticOp(F21c { constB: field, .. }) => {
+                    let field_type = code.accessor.get_field(*field)?.field_type;
+                    RegisterType::from_desc(&field_type).unwrap_or(RegisterType::Conflict)
+                }
+                DexInsn::ArrayOp(_) => {
+                    let array = ops.uses.first().and_then(|(array, _)| regs.get(*array as usize));
+                    let element = match array {
+                        Some(RegisterType::Reference(array_type)) => {
+                            array_type.strip_prefix('[').and_then(RegisterType::from_desc)
+                        }
+                        _ => None,
+                    };
+                    element.unwrap_or_else(|| self.kind_type(index, ty))
+                }
+                _ => match &ops.def_ref {
+                    Some(ref_type) => RegisterType::Reference(ref_type.clone()),
+                    None => self.kind_type(index, ty),
+                },
+            }
+        };
+        set(regs, reg as usize, value);
+        Ok(())
+    }
+
+    /// The type of a definition which only has a kind.
+    fn kind_type(&self, index: usize, ty: Ty) -> RegisterType {
+        match ty {
+            Ty::Exact(kind) => RegisterType::from_kind(kind),
+            _ => match self.types.def_kind(index) {
+                Some(kind) => RegisterType::from_kind(kind),
+                None if ty.is_wide() => RegisterType::Long,
+                None => RegisterType::Int,
+            },
+        }
+    }
+
+    /// The receiver register of a constructor call.
+    fn constructed(&self, insn: &DexInsn) -> AsmResult<Option<u16>> {
+        let method_idx = match insn {
+            DexInsn::InvokeKind(F35c { opcode: 0x70, constB, .. }) |
+            DexInsn::InvokeKindRange(F3rc { opcode: 0x76, constB, .. }) => *constB,
+            _ => return Ok(None),
+        };
+        let method = self.code.accessor.get_method(method_idx)?;
+        if method.method_name.as_ref() != "<init>" { return Ok(None); }
+        Ok(invoke_regs(insn).first().copied())
+    }
+}
+
+/// Writes `ty` into `reg`, a wide value takes the next register as well.
+/// Overwriting either half of a wide value breaks it.
+fn set(regs: &mut [RegisterType], reg: usize, ty: RegisterType) {
+    let len = regs.len();
+    if reg >= len { return; }
+    let break_wide = |regs: &mut [RegisterType], reg: usize| {
+        if reg > 0 && regs[reg - 1].is_wide() { regs[reg - 1] = RegisterType::Conflict; }
+        if regs[reg].is_wide() && reg + 1 < len { regs[reg + 1] = RegisterType::Conflict; }
+    };
+    break_wide(regs, reg);
+    let wide = ty.is_wide();
+    regs[reg] = ty;
+    if wide && reg + 1 < len {
+        if reg + 2 < len && regs[reg + 1].is_wide() { regs[reg + 2] = RegisterType::Conflict; }
+        regs[reg + 1] = RegisterType::WideHigh;
+    }
+}
@@ -26,6 +26,7 @@ impl Display for I4 {
 
 impl I4 {
     pub(crate) fn from_u4(v: U4) -> Self {
-        I4((v.0 | 0xF0) as i8)
+        // sign-extend the 4-bit value
+        I4(((v.0 << 4) as i8) >> 4)
     }
 }
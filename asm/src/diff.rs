@@ -0,0 +1,424 @@
+use crate::impls::ToStringRef;
+use crate::node::element::{ClassNode, CodeBodyNode, FieldNode, LabelNode, MethodNode};
+use crate::node::values::FieldInitialValue;
+use crate::node::InsnNode;
+use crate::smali::ToSmali;
+use crate::{AsmResult, ConstContainer, DescriptorRef, InternalNameRef, Opcodes, StrRef};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// The differences between two sets of classes, e.g. two builds of an app or two versions of a library.
+/// Classes are matched by their internal names, members by their names and descriptors.
+#[derive(Clone, Debug, Default)]
+pub struct ClassSetDiff {
+    /// Classes which only exist in the new set, sorted by name.
+    pub added: Vec<InternalNameRef>,
+    /// Classes which only exist in the old set, sorted by name.
+    pub removed: Vec<InternalNameRef>,
+    /// Classes of both sets which differ, sorted by name.
+    pub changed: Vec<ClassDiff>,
+    pub unchanged_count: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassDiff {
+    pub name: InternalNameRef,
+    pub changes: Vec<ClassChange>,
+    pub fields: Vec<MemberDiff>,
+    pub methods: Vec<MemberDiff>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClassChange {
+    /// Major and minor versions of the class file.
+    Version { old: (u16, u16), new: (u16, u16) },
+    Access { old: u16, new: u16 },
+    SuperName { old: Option<InternalNameRef>, new: Option<InternalNameRef> },
+    Interfaces { added: Vec<InternalNameRef>, removed: Vec<InternalNameRef> },
+    Signature { old: Option<StrRef>, new: Option<StrRef> },
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A field or method which is added, removed or changed. A changed descriptor is a removed
+/// member and an added member, like it is for the callers.
+#[derive(Clone, Debug)]
+pub struct MemberDiff {
+    pub name: StrRef,
+    pub desc: DescriptorRef,
+    pub kind: DiffKind,
+    /// The access of the new member, or of the old one if it is removed.
+    pub access: u16,
+    /// Empty unless the member is [DiffKind::Changed].
+    pub changes: Vec<MemberChange>,
+}
+
+#[derive(Clone, Debug)]
+pub enum MemberChange {
+    Access { old: u16, new: u16 },
+    Signature { old: Option<StrRef>, new: Option<StrRef> },
+    /// The constant value of a field, see [value_string].
+    Value { old: Option<StrRef>, new: Option<StrRef> },
+    /// Checked exceptions of a method.
+    Exceptions { old: Vec<InternalNameRef>, new: Vec<InternalNameRef> },
+    Code(CodeDiff),
+}
+
+/// A line of the normalized instructions, see [normalize_code].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiffLine {
+    Same(StrRef),
+    Removed(StrRef),
+    Added(StrRef),
+}
+
+impl Display for DiffLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, "  {line}"),
+            DiffLine::Removed(line) => write!(f, "- {line}"),
+            DiffLine::Added(line) => write!(f, "+ {line}"),
+        }
+    }
+}
+
+/// The line diff of the normalized code of a method.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CodeDiff {
+    pub lines: Vec<DiffLine>,
+}
+
+impl ClassSetDiff {
+    pub fn new<'a>(
+        old: impl IntoIterator<Item=&'a ClassNode>, new: impl IntoIterator<Item=&'a ClassNode>,
+    ) -> AsmResult<ClassSetDiff> {
+        let old = by_name(old);
+        let new = by_name(new);
+        let mut diff = ClassSetDiff::default();
+        for (name, old_node) in &old {
+            let Some(new_node) = new.get(name) else {
+                diff.removed.push(old_node.name.clone());
+                continue;
+            };
+            let class_diff = ClassDiff::new(old_node, new_node)?;
+            if class_diff.is_empty() {
+                diff.unchanged_count += 1;
+            } else {
+                diff.changed.push(class_diff);
+            }
+        }
+        diff.added = new.iter()
+            .filter(|(name, _)| !old.contains_key(*name))
+            .map(|(_, node)| node.name.clone())
+            .collect();
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Classes by their names, the first class wins if a name is defined more than once.
+fn by_name<'a>(classes: impl IntoIterator<Item=&'a ClassNode>) -> BTreeMap<&'a str, &'a ClassNode> {
+    let mut map = BTreeMap::new();
+    for node in classes {
+        map.entry(&*node.name).or_insert(node);
+    }
+    map
+}
+
+impl ClassDiff {
+    pub fn new(old: &ClassNode, new: &ClassNode) -> AsmResult<ClassDiff> {
+        let mut changes = vec![];
+        let (old_version, new_version) = ((old.major_version, old.minor_version), (new.major_version, new.minor_version));
+        if old_version != new_version {
+            changes.push(ClassChange::Version { old: old_version, new: new_version });
+        }
+        if old.access != new.access {
+            changes.push(ClassChange::Access { old: old.access, new: new.access });
+        }
+        if old.super_name != new.super_name {
+            changes.push(ClassChange::SuperName { old: old.super_name.clone(), new: new.super_name.clone() });
+        }
+        let added: Vec<_> = new.interfaces.iter().filter(|i| !old.interfaces.contains(i)).cloned().collect();
+        let removed: Vec<_> = old.interfaces.iter().filter(|i| !new.interfaces.contains(i)).cloned().collect();
+        if !added.is_empty() || !removed.is_empty() {
+            changes.push(ClassChange::Interfaces { added, removed });
+        }
+        if old.signature != new.signature {
+            changes.push(ClassChange::Signature { old: old.signature.clone(), new: new.signature.clone() });
+        }
+        let fields = diff_members(&old.fields, &new.fields, |f| (&f.name, &f.desc, f.access), diff_field)?;
+        let methods = diff_members(&old.methods, &new.methods, |m| (&m.name, &m.desc, m.access), diff_method)?;
+        Ok(ClassDiff { name: new.name.clone(), changes, fields, methods })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.fields.is_empty() && self.methods.is_empty()
+    }
+}
+
+/// Matches members by name and descriptor, removed and changed members keep the old order,
+/// added members follow them in the new order.
+fn diff_members<T>(
+    old: &[T], new: &[T],
+    key: impl Fn(&T) -> (&StrRef, &DescriptorRef, u16),
+    diff: impl Fn(&T, &T) -> AsmResult<Vec<MemberChange>>,
+) -> AsmResult<Vec<MemberDiff>> {
+    let new_members: HashMap<(&StrRef, &DescriptorRef), &T> = new.iter()
+        .map(|member| { let (name, desc, _) = key(member); ((name, desc), member) })
+        .collect();
+    let mut diffs = vec![];
+    let mut matched = HashSet::new();
+    for old_member in old {
+        let (name, desc, access) = key(old_member);
+        let member_diff = |kind, access, changes| MemberDiff {
+            name: name.clone(), desc: desc.clone(), kind, access, changes,
+        };
+        match new_members.get(&(name, desc)) {
+            None => diffs.push(member_diff(DiffKind::Removed, access, vec![])),
+            Some(new_member) => {
+                matched.insert((name, desc));
+                let changes = diff(old_member, new_member)?;
+                if !changes.is_empty() {
+                    diffs.push(member_diff(DiffKind::Changed, key(new_member).2, changes));
+                }
+            }
+        }
+    }
+    for new_member in new {
+        let (name, desc, access) = key(new_member);
+        if matched.contains(&(name, desc)) { continue; }
+        diffs.push(MemberDiff { name: name.clone(), desc: desc.clone(), kind: DiffKind::Added, access, changes: vec![] });
+    }
+    Ok(diffs)
+}
+
+fn diff_field(old: &FieldNode, new: &FieldNode) -> AsmResult<Vec<MemberChange>> {
+    let mut changes = vec![];
+    if old.access != new.access {
+        changes.push(MemberChange::Access { old: old.access, new: new.access });
+    }
+    if old.signature != new.signature {
+        changes.push(MemberChange::Signature { old: old.signature.clone(), new: new.signature.clone() });
+    }
+    let (old_value, new_value) = (old.value.as_ref().map(value_string), new.value.as_ref().map(value_string));
+    if old_value != new_value {
+        changes.push(MemberChange::Value { old: old_value, new: new_value });
+    }
+    Ok(changes)
+}
+
+fn diff_method(old: &MethodNode, new: &MethodNode) -> AsmResult<Vec<MemberChange>> {
+    let mut changes = vec![];
+    if old.access != new.access {
+        changes.push(MemberChange::Access { old: old.access, new: new.access });
+    }
+    if old.signature != new.signature {
+        changes.push(MemberChange::Signature { old: old.signature.clone(), new: new.signature.clone() });
+    }
+    if old.exceptions != new.exceptions {
+        changes.push(MemberChange::Exceptions { old: old.exceptions.clone(), new: new.exceptions.clone() });
+    }
+    let old_code = method_code(old)?;
+    let new_code = method_code(new)?;
+    if old_code != new_code {
+        changes.push(MemberChange::Code(CodeDiff::new(&old_code, &new_code)));
+    }
+    Ok(changes)
+}
+
+fn method_code(method: &MethodNode) -> AsmResult<Vec<StrRef>> {
+    match method.code()? {
+        Some(code_body) => Ok(normalize_code(code_body)),
+        None => Ok(vec![]),
+    }
+}
+
+/// The constant value of a field like it is written in java, e.g. `1L` or `"text"`.
+pub fn value_string(value: &FieldInitialValue) -> StrRef {
+    match value {
+        FieldInitialValue::Integer(v) => v.to_string(),
+        FieldInitialValue::Float(v) => format!("{v}f"),
+        FieldInitialValue::Long(v) => format!("{v}L"),
+        FieldInitialValue::Double(v) => format!("{v}d"),
+        FieldInitialValue::String(v) => format!("{v:?}"),
+    }.to_ref()
+}
+
+/// One line per instruction, with the details which don't change the behavior removed:
+/// - bytecode offsets are replaced by labels like `L0:`, numbered by their position in the code.
+/// - line numbers, local variable names and stack map frames are left out.
+///
+/// Exception handlers follow the instructions, e.g. `.catch java/io/IOException L0 L1 -> L2`.
+pub fn normalize_code(body: &CodeBodyNode) -> Vec<StrRef> {
+    let mut targets: Vec<LabelNode> = vec![];
+    for insn in &body.instructions {
+        match insn {
+            InsnNode::JumpInsnNode { label, .. } => targets.push(*label),
+            InsnNode::TableSwitchInsnNode { default, labels, .. } |
+            InsnNode::LookupSwitchInsnNode { default, labels, .. } => {
+                targets.push(*default);
+                targets.extend(labels);
+            }
+            _ => {}
+        }
+    }
+    for handler in &body.exception_table {
+        targets.extend([handler.start, handler.end, handler.handler]);
+    }
+    targets.sort_unstable();
+    targets.dedup();
+    let label = |offset: &LabelNode| match targets.binary_search(offset) {
+        Ok(index) => format!("L{index}"),
+        Err(_) => format!("@{offset}"),
+    };
+
+    let mut lines = vec![];
+    let mut next_target = targets.iter().peekable();
+    for (insn, offset) in body.instructions.iter().zip(&body.insn_offsets) {
+        while let Some(target) = next_target.next_if(|target| *target <= offset) {
+            lines.push(format!("{}:", label(target)).to_ref());
+        }
+        let line = match insn {
+            InsnNode::JumpInsnNode { opcode, label: target } => {
+                format!("{} {}", Opcodes::const_name_or_default(*opcode, "insn"), label(target))
+            }
+            InsnNode::TableSwitchInsnNode { default, min, max, labels } => {
+                let labels: Vec<_> = labels.iter().map(label).collect();
+                format!("tableswitch {min}..{max} [{}] default {}", labels.join(", "), label(default))
+            }
+            InsnNode::LookupSwitchInsnNode { default, keys, labels } => {
+                let cases: Vec<_> = keys.iter().zip(labels)
+                    .map(|(key, target)| format!("{key} -> {}", label(target)))
+                    .collect();
+                format!("lookupswitch [{}] default {}", cases.join(", "), label(default))
+            }
+            // nested lines are the operands of the instruction, comments only describe it
+            _ => insn.to_smali().render(0).lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        lines.push(line.to_ref());
+    }
+    // the end of a try block may be the end of the code
+    for target in next_target {
+        lines.push(format!("{}:", label(target)).to_ref());
+    }
+    for handler in &body.exception_table {
+        let catch_type = handler.catch_type.as_deref().unwrap_or("any");
+        let line = format!(
+            ".catch {catch_type} {} {} -> {}", label(&handler.start), label(&handler.end), label(&handler.handler),
+        );
+        lines.push(line.to_ref());
+    }
+    lines
+}
+
+impl CodeDiff {
+    /// Larger differences are shown as all lines removed and added instead of
+    /// finding their longest common subsequence.
+    const MAX_LCS_CELLS: usize = 4_000_000;
+
+    pub fn new(old: &[StrRef], new: &[StrRef]) -> CodeDiff {
+        let prefix = old.iter().zip(new).take_while(|(o, n)| o == n).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev())
+            .take_while(|(o, n)| o == n).count();
+        let old_middle = &old[prefix..old.len() - suffix];
+        let new_middle = &new[prefix..new.len() - suffix];
+
+        let mut lines: Vec<_> = old[..prefix].iter().cloned().map(DiffLine::Same).collect();
+        if old_middle.len().saturating_mul(new_middle.len()) > Self::MAX_LCS_CELLS {
+            lines.extend(old_middle.iter().cloned().map(DiffLine::Removed));
+            lines.extend(new_middle.iter().cloned().map(DiffLine::Added));
+        } else {
+            lines.extend(lcs_diff(old_middle, new_middle));
+        }
+        lines.extend(old[old.len() - suffix..].iter().cloned().map(DiffLine::Same));
+        CodeDiff { lines }
+    }
+
+    pub fn added_count(&self) -> usize {
+        self.lines.iter().filter(|line| matches!(line, DiffLine::Added(_))).count()
+    }
+
+    pub fn removed_count(&self) -> usize {
+        self.lines.iter().filter(|line| matches!(line, DiffLine::Removed(_))).count()
+    }
+
+    /// Rows of old and new lines, removed lines are paired with the added lines of the same change.
+    pub fn side_by_side(&self) -> Vec<(Option<StrRef>, Option<StrRef>)> {
+        let mut rows = vec![];
+        let (mut removed, mut added) = (vec![], vec![]);
+        let flush = |rows: &mut Vec<_>, removed: &mut Vec<StrRef>, added: &mut Vec<StrRef>| {
+            let count = removed.len().max(added.len());
+            let mut removed = removed.drain(..);
+            let mut added = added.drain(..);
+            for _ in 0..count {
+                rows.push((removed.next(), added.next()));
+            }
+        };
+        for line in &self.lines {
+            match line {
+                DiffLine::Same(line) => {
+                    flush(&mut rows, &mut removed, &mut added);
+                    rows.push((Some(line.clone()), Some(line.clone())));
+                }
+                DiffLine::Removed(line) => removed.push(line.clone()),
+                DiffLine::Added(line) => added.push(line.clone()),
+            }
+        }
+        flush(&mut rows, &mut removed, &mut added);
+        rows
+    }
+}
+
+impl Display for CodeDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+fn lcs_diff(old: &[StrRef], new: &[StrRef]) -> Vec<DiffLine> {
+    // lengths[i * width + j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            lines.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().cloned().map(DiffLine::Removed));
+    lines.extend(new[j..].iter().cloned().map(DiffLine::Added));
+    lines
+}
@@ -0,0 +1,226 @@
+use crate::dex::{ClassDef, DexFileAccessor, NO_INDEX};
+use crate::impls::{desc_to_internal, ToStringRef};
+use crate::node::element::ClassNode;
+use crate::{AsmResult, InternalNameRef, JavaClassAccessFlags};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const OBJECT: &str = "java/lang/Object";
+
+/// The type information of a single class which is needed to build the hierarchy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HierarchyClass {
+    /// The internal name of this class, e.g. `java/lang/String`.
+    pub name: InternalNameRef,
+    /// The class's access flags (see [JavaClassAccessFlags]), only [JavaClassAccessFlags::ACC_INTERFACE] is used here.
+    pub access: u16,
+    /// The internal name of the super class, [None] for `java/lang/Object`.
+    pub super_name: Option<InternalNameRef>,
+    /// The internal names of the interfaces directly implemented by this class.
+    pub interfaces: Vec<InternalNameRef>,
+}
+
+impl HierarchyClass {
+    pub fn is_interface(&self) -> bool {
+        self.access & JavaClassAccessFlags::ACC_INTERFACE != 0
+    }
+
+    pub fn from_class_node(node: &ClassNode) -> Self {
+        HierarchyClass {
+            name: node.name.clone(),
+            access: node.access,
+            super_name: node.super_name.clone(),
+            interfaces: node.interfaces.clone(),
+        }
+    }
+
+    pub fn from_class_def(accessor: &DexFileAccessor, class_def: &ClassDef) -> AsmResult<Self> {
+        let internal_name = |desc: &str| desc_to_internal(desc).to_ref();
+        let name = internal_name(&accessor.get_type(class_def.class_idx)?);
+        let super_name = if class_def.superclass_idx.0 != NO_INDEX {
+            Some(internal_name(&accessor.get_type(class_def.superclass_idx)?))
+        } else {
+            None
+        };
+        let interfaces = accessor.get_type_list(class_def.interfaces_off)?
+            .iter().map(|t| internal_name(t)).collect();
+        // dex access flags share the same bits with jvm for interfaces.
+        let access = class_def.access_flags as u16;
+        Ok(HierarchyClass { name, access, super_name, interfaces })
+    }
+}
+
+/// Resolves the direct super types of classes, all type queries which are needed by
+/// analyses (e.g. frame computation) are based on this.
+///
+/// Names are internal names, array types use their descriptors, e.g. `[Ljava/lang/String;`.
+pub trait HierarchyResolver {
+    /// Returns [None] if the class is unknown for this resolver.
+    fn resolve(&self, name: &str) -> Option<&HierarchyClass>;
+
+    fn is_interface(&self, name: &str) -> bool {
+        self.resolve(name).is_some_and(|c| c.is_interface())
+    }
+
+    /// All super classes and interfaces of `name`, not including itself, nearest first.
+    /// Unknown classes are included but their super types are not.
+    fn all_supertypes(&self, name: &str) -> Vec<InternalNameRef> {
+        let mut res = vec![];
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(name.to_ref());
+        while let Some(current) = queue.pop_front() {
+            let Some(class) = self.resolve(&current) else { continue };
+            for super_type in class.super_name.iter().chain(class.interfaces.iter()) {
+                if visited.insert(super_type.clone()) {
+                    res.push(super_type.clone());
+                    queue.push_back(super_type.clone());
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns true if a value of type `from` can be assigned to the type `to`,
+    /// e.g. `is_assignable("java/util/List", "java/util/ArrayList")` is true.
+    fn is_assignable(&self, to: &str, from: &str) -> bool {
+        if to == from || to == OBJECT { return true; }
+        if let Some(from_element) = from.strip_prefix('[') {
+            let Some(to_element) = to.strip_prefix('[') else {
+                return matches!(to, "java/lang/Cloneable" | "java/io/Serializable");
+            };
+            return match (element_name(to_element), element_name(from_element)) {
+                (Some(to_element), Some(from_element)) => self.is_assignable(to_element, from_element),
+                // primitive arrays are only assignable to the same type.
+                _ => false,
+            };
+        }
+        if to.starts_with('[') { return false; }
+        self.all_supertypes(from).iter().any(|s| s.as_ref() == to)
+    }
+
+    /// The nearest common super class of two classes, interfaces are treated as
+    /// `java/lang/Object` just like ASM's `ClassWriter::getCommonSuperClass`.
+    fn common_super_class(&self, a: &str, b: &str) -> InternalNameRef {
+        if self.is_assignable(a, b) { return a.to_ref(); }
+        if self.is_assignable(b, a) { return b.to_ref(); }
+        if a.starts_with('[') || b.starts_with('[') { return OBJECT.to_ref(); }
+        if self.is_interface(a) || self.is_interface(b) { return OBJECT.to_ref(); }
+        let mut visited = HashSet::new();
+        let mut current = self.resolve(a).and_then(|c| c.super_name.clone());
+        while let Some(super_name) = current {
+            // stop at cyclic super classes, which only exist in broken inputs.
+            if !visited.insert(super_name.clone()) { break; }
+            if self.is_assignable(&super_name, b) { return super_name; }
+            current = self.resolve(&super_name).and_then(|c| c.super_name.clone());
+        }
+        OBJECT.to_ref()
+    }
+}
+
+/// The internal name of an array element descriptor, or [None] for primitive types.
+fn element_name(desc: &str) -> Option<&str> {
+    match desc.as_bytes().first() {
+        Some(b'L') => Some(desc_to_internal(desc)),
+        Some(b'[') => Some(desc),
+        _ => None,
+    }
+}
+
+/// An index of super types and sub types for all loaded classes, no matter they come from
+/// dex files ([ClassDef]) or class files ([ClassNode]).
+///
+/// If a class is added more than once, the first one wins.
+#[derive(Clone, Debug, Default)]
+pub struct ClassHierarchy {
+    classes: HashMap<InternalNameRef, HierarchyClass>,
+    subclasses: HashMap<InternalNameRef, Vec<InternalNameRef>>,
+    implementors: HashMap<InternalNameRef, Vec<InternalNameRef>>,
+}
+
+impl HierarchyResolver for ClassHierarchy {
+    fn resolve(&self, name: &str) -> Option<&HierarchyClass> {
+        self.classes.get(name)
+    }
+}
+
+impl ClassHierarchy {
+    pub fn new() -> Self { Default::default() }
+
+    pub fn len(&self) -> usize { self.classes.len() }
+
+    pub fn is_empty(&self) -> bool { self.classes.is_empty() }
+
+    pub fn contains(&self, name: &str) -> bool { self.classes.contains_key(name) }
+
+    pub fn classes(&self) -> impl Iterator<Item=&HierarchyClass> {
+        self.classes.values()
+    }
+
+    /// Returns false if the class already exists.
+    pub fn add_class(&mut self, class: HierarchyClass) -> bool {
+        if self.classes.contains_key(&class.name) { return false; }
+        let name = class.name.clone();
+        if let Some(super_name) = &class.super_name {
+            self.subclasses.entry(super_name.clone()).or_default().push(name.clone());
+        }
+        for interface in &class.interfaces {
+            // interfaces extending interfaces are treated as implementors too.
+            self.implementors.entry(interface.clone()).or_default().push(name.clone());
+        }
+        self.classes.insert(name, class);
+        true
+    }
+
+    pub fn add_class_node(&mut self, node: &ClassNode) -> bool {
+        self.add_class(HierarchyClass::from_class_node(node))
+    }
+
+    pub fn add_class_def(&mut self, accessor: &DexFileAccessor, class_def: &ClassDef) -> AsmResult<bool> {
+        Ok(self.add_class(HierarchyClass::from_class_def(accessor, class_def)?))
+    }
+
+    /// Adds all classes in a dex file.
+    pub fn add_dex(&mut self, accessor: &DexFileAccessor) -> AsmResult<()> {
+        for class_def in &accessor.file.class_defs {
+            self.add_class_def(accessor, class_def)?;
+        }
+        Ok(())
+    }
+
+    pub fn super_name(&self, name: &str) -> Option<&InternalNameRef> {
+        self.classes.get(name).and_then(|c| c.super_name.as_ref())
+    }
+
+    pub fn interfaces(&self, name: &str) -> &[InternalNameRef] {
+        self.classes.get(name).map(|c| c.interfaces.as_slice()).unwrap_or_default()
+    }
+
+    /// Loaded classes whose super class is `name`.
+    pub fn direct_subclasses(&self, name: &str) -> &[InternalNameRef] {
+        self.subclasses.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Loaded classes and interfaces which directly implement or extend the interface `name`.
+    pub fn direct_implementors(&self, name: &str) -> &[InternalNameRef] {
+        self.implementors.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// All loaded classes which are assignable to `name`, not including itself.
+    pub fn all_subtypes(&self, name: &str) -> Vec<InternalNameRef> {
+        let mut res = vec![];
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(name.to_ref());
+        while let Some(current) = queue.pop_front() {
+            let subtypes = self.direct_subclasses(&current).iter()
+                .chain(self.direct_implementors(&current));
+            for subtype in subtypes {
+                if visited.insert(subtype.clone()) {
+                    res.push(subtype.clone());
+                    queue.push_back(subtype.clone());
+                }
+            }
+        }
+        res
+    }
+}
@@ -0,0 +1,1144 @@
+use crate::dex::insn::{DexInsn, FillArrayDataPayload, PackedSwitchPayload, SparseSwitchPayload};
+use crate::dex::insn_syntax::*;
+use crate::dex::{CodeItem, DebugInfoItem, DexFileAccessor, EncodedCatchHandler, MethodHandle, MethodHandleType};
+use crate::err::AsmResultOkExt;
+use crate::impls::dex::convert::types::{binop, invoke_desc, invoke_regs, is_shift, Kind, RegisterTypes, Res, Ty, UNOPS};
+use crate::impls::{desc_to_internal, method_arg_types, method_return_type, type_slots};
+use crate::node::element::{CodeBodyNode, ExceptionTable, LabelNode, LineNumberNode, LocalVariableNode};
+use crate::node::InsnNode;
+use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, Handle};
+use crate::{AsmErr, AsmResult, DescriptorRef, MethodHandleKind, NewArrayTypeOperand, Opcodes, StrRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The dalvik code of a method, with the address of each instruction resolved.
+pub(crate) struct DexCode<'a> {
+    pub accessor: &'a DexFileAccessor,
+    pub class_type: DescriptorRef,
+    pub desc: StrRef,
+    pub is_static: bool,
+    pub registers: u16,
+    pub ins: u16,
+    /// instructions except payloads, with their addresses in code units.
+    pub insns: Vec<(u32, &'a DexInsn)>,
+    payloads: HashMap<u32, &'a DexInsn>,
+    index_of_addr: HashMap<u32, usize>,
+    tries: Vec<TryRange>,
+}
+
+struct TryRange {
+    /// covered instructions, `[start, end)` in instruction indexes.
+    start: usize,
+    end: usize,
+    /// catch type descriptor (or [None] for catch-all) and handler address.
+    handlers: Vec<(Option<DescriptorRef>, u32)>,
+}
+
+/// Basic blocks of [DexCode], a throwing instruction inside a try block always starts a block,
+/// so that the exception edges can start from the entry state of the block.
+pub(crate) struct Blocks {
+    /// `[start, end)` instruction indexes of each block.
+    pub ranges: Vec<(usize, usize)>,
+    pub succs: Vec<Vec<usize>>,
+    pub handler_succs: Vec<Vec<usize>>,
+}
+
+/// A try item of [CodeItem] with its catch handlers resolved.
+pub(crate) struct TryCatch {
+    /// covered addresses in code units, `[start_addr, end_addr)`.
+    pub start_addr: u32,
+    pub end_addr: u32,
+    /// catch type descriptor (or [None] for catch-all) and handler address.
+    pub handlers: Vec<(Option<DescriptorRef>, u32)>,
+}
+
+pub(crate) fn read_try_catches(
+    accessor: &DexFileAccessor, code_off: u32, code_item: &CodeItem,
+) -> AsmResult<Vec<TryCatch>> {
+    if code_item.tries.is_empty() { return Ok(vec![]); }
+    // handler_off of each try item is relative to the start of the handler list,
+    // which follows the (4-byte aligned) tries.
+    let insns_size = code_item.insn_container.insns_size;
+    let padding = if insns_size % 2 == 1 { 2 } else { 0 };
+    let handlers_start = code_off + 16 + insns_size * 2 + padding + code_item.tries.len() as u32 * 8;
+    let mut tries = Vec::with_capacity(code_item.tries.len());
+    for try_item in &code_item.tries {
+        let start_addr = try_item.start_addr;
+        let end_addr = start_addr + try_item.insn_count as u32;
+        let handler_off = handlers_start + try_item.handler_off as u32;
+        let handler: EncodedCatchHandler = accessor.get_data_impl(handler_off)?;
+        let mut handlers = Vec::with_capacity(handler.handlers.len() + 1);
+        for pair in &handler.handlers {
+            let catch_type = accessor.get_type(pair.type_idx.value() as usize)?;
+            handlers.push((Some(catch_type), pair.addr.value()));
+        }
+        if let Some(catch_all) = handler.catch_all_addr {
+            handlers.push((None, catch_all.value()));
+        }
+        tries.push(TryCatch { start_addr, end_addr, handlers });
+    }
+    Ok(tries)
+}
+
+fn is_move_result(insn: &DexInsn) -> bool {
+    matches!(insn, DexInsn::MoveResult(_) | DexInsn::MoveResultWide(_) | DexInsn::MoveResultObject(_))
+}
+
+/// Whether the instruction may throw, following the flags of the dalvik verifier.
+pub(crate) fn can_throw(insn: &DexInsn) -> bool {
+    match insn {
+        DexInsn::ConstString(_) | DexInsn::ConstStringJumbo(_) | DexInsn::ConstClass(_) |
+        DexInsn::MonitorEnter(_) | DexInsn::MonitorExit(_) | DexInsn::CheckCast(_) |
+        DexInsn::InstanceOf(_) | DexInsn::ArrayLength(_) | DexInsn::NewInstance(_) |
+        DexInsn::NewArray(_) | DexInsn::FilledNewArray(_) | DexInsn::FilledNewArrayRange(_) |
+        DexInsn::FillArrayData(_) | DexInsn::Throw(_) | DexInsn::ArrayOp(_) |
+        DexInsn::IInstanceOp(_) | DexInsn::SStaticOp(_) | DexInsn::InvokeKind(_) |
+        DexInsn::InvokeKindRange(_) | DexInsn::InvokePoly(_) | DexInsn::InvokePolyRange(_) |
+        DexInsn::InvokeCustom(_) | DexInsn::InvokeCustomRange(_) |
+        DexInsn::ConstMethodHandle(_) | DexInsn::ConstMethodType(_) => true,
+        // div-int, rem-int, div-long, rem-long
+        DexInsn::Binop(F23x { opcode, .. }) => matches!(opcode, 0x93 | 0x94 | 0x9e | 0x9f),
+        DexInsn::Binop2Addr(F12x { opcode, .. }) => matches!(opcode, 0xb3 | 0xb4 | 0xbe | 0xbf),
+        DexInsn::BinopLit16(F22s { opcode, .. }) => matches!(opcode, 0xd3 | 0xd4),
+        DexInsn::BinopLit8(F22b { opcode, .. }) => matches!(opcode, 0xdb | 0xdc),
+        _ => false,
+    }
+}
+
+pub(crate) fn falls_through(insn: &DexInsn) -> bool {
+    !matches!(
+        insn,
+        DexInsn::Goto(_) | DexInsn::Goto16(_) | DexInsn::Goto32(_) | DexInsn::ReturnVoid(_) |
+        DexInsn::Return(_) | DexInsn::ReturnWide(_) | DexInsn::ReturnObject(_) | DexInsn::Throw(_)
+    )
+}
+
+impl<'a> DexCode<'a> {
+    pub fn new(
+        accessor: &'a DexFileAccessor, code_off: u32, code_item: &'a CodeItem,
+        class_type: DescriptorRef, desc: StrRef, is_static: bool,
+    ) -> AsmResult<Self> {
+        let mut insns = Vec::with_capacity(code_item.insn_container.insns.len());
+        let mut payloads = HashMap::new();
+        let mut index_of_addr = HashMap::new();
+        for (addr, insn) in code_item.insn_container.addressed_insns() {
+            if insn.is_payload() {
+                payloads.insert(addr, insn);
+            } else {
+                index_of_addr.insert(addr, insns.len());
+                insns.push((addr, insn));
+            }
+        }
+        let mut code = DexCode {
+            accessor, class_type, desc, is_static,
+            registers: code_item.registers_size, ins: code_item.ins_size,
+            insns, payloads, index_of_addr, tries: vec![],
+        };
+        code.tries = code.read_tries(code_off, code_item)?;
+        Ok(code)
+    }
+
+    fn read_tries(&self, code_off: u32, code_item: &CodeItem) -> AsmResult<Vec<TryRange>> {
+        let tries = read_try_catches(self.accessor, code_off, code_item)?.into_iter().map(|try_catch| {
+            let start = self.insns.partition_point(|(addr, _)| *addr < try_catch.start_addr);
+            let end = self.insns.partition_point(|(addr, _)| *addr < try_catch.end_addr);
+            TryRange { start, end, handlers: try_catch.handlers }
+        }).collect();
+        Ok(tries)
+    }
+
+    pub fn index_of(&self, addr: u32) -> AsmResult<usize> {
+        self.index_of_addr.get(&addr).copied()
+            .ok_or_else(|| AsmErr::IllegalFormat(format!("no instruction at address {addr}")))
+    }
+
+    fn payload(&self, addr: u32) -> AsmResult<&'a DexInsn> {
+        self.payloads.get(&addr).copied()
+            .ok_or_else(|| AsmErr::IllegalFormat(format!("no payload at address {addr}")))
+    }
+
+    /// The register of each parameter (including `this`) and its type.
+    pub fn param_regs(&self) -> Vec<(u16, DescriptorRef)> {
+        let mut reg = self.registers.saturating_sub(self.ins);
+        let mut params = vec![];
+        if !self.is_static {
+            params.push((reg, self.class_type.clone()));
+            reg += 1;
+        }
+        for arg in method_arg_types(&self.desc) {
+            params.push((reg, StrRef::from(arg)));
+            reg += type_slots(arg);
+        }
+        params
+    }
+
+    /// The type which `move-result*` at `index` takes from the previous instruction.
+    pub fn result_type(&self, index: usize) -> AsmResult<StrRef> {
+        let previous = index.checked_sub(1).map(|i| self.insns[i].1);
+        match previous {
+            Some(DexInsn::FilledNewArray(F35c { constB, .. })) |
+            Some(DexInsn::FilledNewArrayRange(F3rc { constB, .. })) => self.accessor.get_type(*constB),
+            Some(insn) => match invoke_desc(self.accessor, insn)? {
+                Some((_, desc)) => Ok(StrRef::from(method_return_type(&desc))),
+                None => Err(AsmErr::IllegalFormat(format!("move-result without invoke at {index}"))),
+            },
+            None => Err(AsmErr::IllegalFormat("move-result at the start of code".to_string())),
+        }
+    }
+
+    /// Branch targets of the instruction at `index`, in addresses.
+    fn targets(&self, index: usize) -> AsmResult<Vec<u32>> {
+        let (addr, insn) = self.insns[index];
+        let relative = |offset: i32| addr.wrapping_add(offset as u32);
+        let targets = match insn {
+            DexInsn::Goto(F10t { offsetA, .. }) => vec![relative(*offsetA as i32)],
+            DexInsn::Goto16(F20t { offsetA, .. }) => vec![relative(*offsetA as i32)],
+            DexInsn::Goto32(F30t { offsetA, .. }) => vec![relative(*offsetA)],
+            DexInsn::IfTest(F22t { offsetC, .. }) => vec![relative(*offsetC as i32)],
+            DexInsn::IfTestz(F21t { offsetB, .. }) => vec![relative(*offsetB as i32)],
+            DexInsn::PackedSwitch(F31t { offsetB, .. }) |
+            DexInsn::SparseSwitch(F31t { offsetB, .. }) => {
+                let targets = match self.payload(relative(*offsetB))? {
+                    DexInsn::PackedSwitchPayload(PackedSwitchPayload { targets, .. }) |
+                    DexInsn::SparseSwitchPayload(SparseSwitchPayload { targets, .. }) => targets,
+                    _ => return Err(AsmErr::IllegalFormat(format!("bad switch payload at {addr}"))),
+                };
+                targets.iter().map(|t| relative(*t)).collect()
+            }
+            _ => vec![],
+        };
+        Ok(targets)
+    }
+
+    /// Catch types of the handlers starting at `index`, [None] for catch-all handlers.
+    pub fn catch_types(&self, index: usize) -> Vec<Option<DescriptorRef>> {
+        let addr = self.insns[index].0;
+        self.tries.iter().flat_map(|t| &t.handlers)
+            .filter(|(_, handler)| *handler == addr)
+            .map(|(catch_type, _)| catch_type.clone())
+            .collect()
+    }
+
+    fn try_of(&self, index: usize) -> Option<&TryRange> {
+        self.tries.iter().find(|t| t.start <= index && index < t.end)
+    }
+
+    pub fn blocks(&self) -> Blocks {
+        let count = self.insns.len();
+        let mut leaders = vec![false; count + 1];
+        let mut targets = Vec::with_capacity(count);
+        leaders[0] = true;
+        for index in 0..count {
+            let insn = self.insns[index].1;
+            let insn_targets: Vec<usize> = self.targets(index).unwrap_or_default().into_iter()
+                .filter_map(|addr| self.index_of(addr).ok())
+                .collect();
+            for target in &insn_targets { leaders[*target] = true; }
+            if !insn_targets.is_empty() || !falls_through(insn) {
+                leaders[index + 1] = true;
+            }
+            if can_throw(insn) && self.try_of(index).is_some() {
+                leaders[index] = true;
+            }
+            targets.push(insn_targets);
+        }
+        for try_range in &self.tries {
+            for (_, addr) in &try_range.handlers {
+                if let Ok(index) = self.index_of(*addr) { leaders[index] = true; }
+            }
+        }
+
+        let mut ranges = vec![];
+        let mut block_of = vec![0; count];
+        let mut start = 0;
+        for index in 1..=count {
+            if index == count || leaders[index] {
+                block_of[start..index].fill(ranges.len());
+                ranges.push((start, index));
+                start = index;
+            }
+        }
+        let mut succs = Vec::with_capacity(ranges.len());
+        let mut handler_succs = Vec::with_capacity(ranges.len());
+        for &(start, end) in &ranges {
+            let last = end - 1;
+            let mut block_succs: Vec<usize> = targets[last].iter().map(|t| block_of[*t]).collect();
+            if falls_through(self.insns[last].1) && end < count {
+                block_succs.push(block_of[end]);
+            }
+            let mut block_handlers = vec![];
+            if can_throw(self.insns[start].1) && let Some(try_range) = self.try_of(start) {
+                for (_, addr) in &try_range.handlers {
+                    if let Ok(index) = self.index_of(*addr) { block_handlers.push(block_of[index]); }
+                }
+            }
+            succs.push(block_succs);
+            handler_succs.push(block_handlers);
+        }
+        Blocks { ranges, succs, handler_succs }
+    }
+
+    /// The jvm local variable index of a dalvik register.
+    /// Parameters are the last registers in dalvik but the first local variables in jvm.
+    pub fn local(&self, reg: u16) -> u16 {
+        let first_in = self.registers.saturating_sub(self.ins);
+        if reg >= first_in { reg - first_in } else { reg + self.ins }
+    }
+}
+
+fn load_opcode(kind: Kind) -> u8 {
+    match kind {
+        Kind::Int => Opcodes::ILOAD,
+        Kind::Float => Opcodes::FLOAD,
+        Kind::Long => Opcodes::LLOAD,
+        Kind::Double => Opcodes::DLOAD,
+        Kind::Ref => Opcodes::ALOAD,
+    }
+}
+
+fn store_opcode(kind: Kind) -> u8 {
+    match kind {
+        Kind::Int => Opcodes::ISTORE,
+        Kind::Float => Opcodes::FSTORE,
+        Kind::Long => Opcodes::LSTORE,
+        Kind::Double => Opcodes::DSTORE,
+        Kind::Ref => Opcodes::ASTORE,
+    }
+}
+
+fn return_opcode(kind: Kind) -> u8 {
+    match kind {
+        Kind::Int => Opcodes::IRETURN,
+        Kind::Float => Opcodes::FRETURN,
+        Kind::Long => Opcodes::LRETURN,
+        Kind::Double => Opcodes::DRETURN,
+        Kind::Ref => Opcodes::ARETURN,
+    }
+}
+
+fn size(kind: Kind) -> i32 {
+    if kind.is_wide() { 2 } else { 1 }
+}
+
+/// `xALOAD` and `xASTORE` of an array element type descriptor.
+fn array_opcodes(element: &str) -> (u8, u8) {
+    match element.as_bytes().first() {
+        Some(b'Z') | Some(b'B') => (Opcodes::BALOAD, Opcodes::BASTORE),
+        Some(b'C') => (Opcodes::CALOAD, Opcodes::CASTORE),
+        Some(b'S') => (Opcodes::SALOAD, Opcodes::SASTORE),
+        Some(b'I') => (Opcodes::IALOAD, Opcodes::IASTORE),
+        Some(b'F') => (Opcodes::FALOAD, Opcodes::FASTORE),
+        Some(b'J') => (Opcodes::LALOAD, Opcodes::LASTORE),
+        Some(b'D') => (Opcodes::DALOAD, Opcodes::DASTORE),
+        _ => (Opcodes::AALOAD, Opcodes::AASTORE),
+    }
+}
+
+fn kind_element(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Int => "I",
+        Kind::Float => "F",
+        Kind::Long => "J",
+        Kind::Double => "D",
+        Kind::Ref => "Ljava/lang/Object;",
+    }
+}
+
+pub(crate) fn convert_handle(accessor: &DexFileAccessor, handle: &MethodHandle) -> AsmResult<Handle> {
+    let handle_type = handle.method_handle_type;
+    let reference_kind = match handle_type {
+        MethodHandleType::H_STATIC_PUT => MethodHandleKind::H_PUTSTATIC,
+        MethodHandleType::H_STATIC_GET => MethodHandleKind::H_GETSTATIC,
+        MethodHandleType::H_INSTANCE_PUT => MethodHandleKind::H_PUTFIELD,
+        MethodHandleType::H_INSTANCE_GET => MethodHandleKind::H_GETFIELD,
+        MethodHandleType::H_INVOKE_STATIC => MethodHandleKind::H_INVOKESTATIC,
+        MethodHandleType::H_INVOKE_INSTANCE => MethodHandleKind::H_INVOKEVIRTUAL,
+        MethodHandleType::H_INVOKE_CONSTRUCTOR => MethodHandleKind::H_NEWINVOKESPECIAL,
+        MethodHandleType::H_INVOKE_DIRECT => MethodHandleKind::H_INVOKESPECIAL,
+        MethodHandleType::H_INVOKE_INTERFACE => MethodHandleKind::H_INVOKEINTERFACE,
+        _ => return Err(AsmErr::IllegalFormat(format!("unknown method handle type {handle_type}"))),
+    };
+    let member_idx = handle.field_or_method_id;
+    if handle_type <= MethodHandleType::H_INSTANCE_GET {
+        let field = accessor.get_field(member_idx)?;
+        Handle {
+            reference_kind, owner: StrRef::from(desc_to_internal(&field.class_type)),
+            name: field.field_name, desc: field.field_type,
+        }.ok()
+    } else {
+        let method = accessor.get_method(member_idx)?;
+        Handle {
+            reference_kind, owner: StrRef::from(desc_to_internal(&method.class_type)),
+            name: method.method_name, desc: method.desc,
+        }.ok()
+    }
+}
+
+pub(crate) fn call_site(accessor: &DexFileAccessor, call_site_idx: u16) -> AsmResult<ConstDynamic> {
+    use crate::dex::EncodedValue;
+    let call_site = accessor.get_call_site(call_site_idx)?;
+    let mut values = call_site.values.iter();
+    let bad_call_site = || AsmErr::IllegalFormat(format!("malformed call site {call_site_idx}"));
+    let bsm = match values.next() {
+        Some(EncodedValue::MethodHandle(idx)) => convert_handle(accessor, accessor.get_method_handle(idx.0 as usize)?)?,
+        _ => return Err(bad_call_site()),
+    };
+    let name = match values.next() {
+        Some(EncodedValue::String(idx)) => accessor.get_str(idx.0 as usize)?,
+        _ => return Err(bad_call_site()),
+    };
+    let desc = match values.next() {
+        Some(EncodedValue::MethodType(idx)) => StrRef::from(accessor.get_proto(idx.0 as usize)?.to_string()),
+        _ => return Err(bad_call_site()),
+    };
+    let mut bsm_args = vec![];
+    for value in values {
+        let argument = match value {
+            EncodedValue::Byte(v) => BootstrapMethodArgument::Integer(*v as i32),
+            EncodedValue::Short(v) => BootstrapMethodArgument::Integer(*v as i32),
+            EncodedValue::Char(v) => BootstrapMethodArgument::Integer(*v as i32),
+            EncodedValue::Int(v) => BootstrapMethodArgument::Integer(*v),
+            EncodedValue::Boolean(v) => BootstrapMethodArgument::Integer(*v as i32),
+            EncodedValue::Long(v) => BootstrapMethodArgument::Long(*v),
+            EncodedValue::Float(bytes) => BootstrapMethodArgument::Float(f32::from_le_bytes(*bytes)),
+            EncodedValue::Double(bytes) => BootstrapMethodArgument::Double(f64::from_le_bytes(*bytes)),
+            EncodedValue::String(idx) => BootstrapMethodArgument::String(accessor.get_str(idx.0 as usize)?),
+            EncodedValue::Type(idx) => {
+                let desc = accessor.get_type(idx.0 as usize)?;
+                BootstrapMethodArgument::Class(StrRef::from(desc_to_internal(&desc)))
+            }
+            EncodedValue::MethodHandle(idx) => {
+                BootstrapMethodArgument::Handle(convert_handle(accessor, accessor.get_method_handle(idx.0 as usize)?)?)
+            }
+            EncodedValue::MethodType(idx) => {
+                BootstrapMethodArgument::MethodType(StrRef::from(accessor.get_proto(idx.0 as usize)?.to_string()))
+            }
+            _ => return Err(bad_call_site()),
+        };
+        bsm_args.push(argument);
+    }
+    ConstDynamic { name, desc, bsm, bsm_args }.ok()
+}
+
+/// Converted jvm code of a dalvik method.
+pub(crate) struct ConvertedCode {
+    pub body: CodeBodyNode,
+    /// `invokedynamic` or `ldc` of method handles & method types are used, which needs
+    /// class file version 51 at least.
+    pub uses_java7: bool,
+}
+
+struct Emitter<'a, 'b> {
+    code: &'b DexCode<'a>,
+    types: &'b RegisterTypes,
+    insns: Vec<InsnNode>,
+    /// labels of jumps are indexes of this list before resolving, which keeps dalvik addresses.
+    targets: Vec<u32>,
+    depth: i32,
+    max_stack: i32,
+    /// where the result of current dalvik instruction is stored, see [convert_code].
+    def_store: Option<usize>,
+    max_locals: u16,
+    uses_java7: bool,
+}
+
+impl Emitter<'_, '_> {
+    fn emit(&mut self, insn: InsnNode, delta: i32) {
+        self.insns.push(insn);
+        self.depth += delta;
+        self.max_stack = self.max_stack.max(self.depth);
+    }
+
+    fn op(&mut self, opcode: u8, delta: i32) {
+        self.emit(InsnNode::NoOperand { opcode }, delta);
+    }
+
+    fn label(&mut self, addr: u32) -> AsmResult<LabelNode> {
+        let label = LabelNode::try_from(self.targets.len())
+            .map_err(|_| AsmErr::IllegalFormat("too many branch targets".to_string()))?;
+        self.targets.push(addr);
+        Ok(label)
+    }
+
+    fn var(&mut self, opcode: u8, kind: Kind, reg: u16, delta: i32) {
+        let var_index = self.code.local(reg);
+        self.max_locals = self.max_locals.max(var_index + size(kind) as u16);
+        self.emit(InsnNode::VarInsnNode { opcode, var_index }, delta);
+    }
+
+    fn load(&mut self, index: usize, reg: u16, ty: Ty) {
+        match self.types.use_res(index, reg) {
+            Res::Kind(kind) => self.var(load_opcode(kind), kind, reg, size(kind)),
+            Res::Const(literal) => self.push_const(literal, ty.or_default()),
+            Res::Unknown => {
+                let kind = ty.or_default();
+                self.var(load_opcode(kind), kind, reg, size(kind))
+            }
+        }
+    }
+
+    /// Stores the value on the top of the stack into the register defined by `index`.
+    fn store(&mut self, index: usize, ty: Ty) {
+        let Some((reg, _)) = self.types.ops[index].def else { return };
+        let kind = match self.types.def_res(index) {
+            Res::Kind(kind) => kind,
+            _ => ty.or_default(),
+        };
+        self.def_store = Some(self.insns.len());
+        self.var(store_opcode(kind), kind, reg, -size(kind));
+    }
+
+    fn push_int(&mut self, value: i32) {
+        match value {
+            -1..=5 => self.op((Opcodes::ICONST_0 as i32 + value) as u8, 1),
+            -128..=127 => self.emit(InsnNode::BIPushInsnNode { operand: value as i8 }, 1),
+            -32768..=32767 => self.emit(InsnNode::SIPushInsnNode { operand: value as i16 }, 1),
+            _ => self.ldc(ConstValue::Integer(value)),
+        }
+    }
+
+    fn push_const(&mut self, literal: i64, kind: Kind) {
+        match kind {
+            Kind::Int => self.push_int(literal as i32),
+            Kind::Float => match literal as u32 {
+                0 => self.op(Opcodes::FCONST_0, 1),
+                0x3f800000 => self.op(Opcodes::FCONST_1, 1),
+                0x40000000 => self.op(Opcodes::FCONST_2, 1),
+                bits => self.ldc(ConstValue::Float(f32::from_bits(bits))),
+            },
+            Kind::Long => match literal {
+                0 => self.op(Opcodes::LCONST_0, 2),
+                1 => self.op(Opcodes::LCONST_1, 2),
+                _ => self.ldc(ConstValue::Long(literal)),
+            },
+            Kind::Double => match literal as u64 {
+                0 => self.op(Opcodes::DCONST_0, 2),
+                0x3ff0000000000000 => self.op(Opcodes::DCONST_1, 2),
+                bits => self.ldc(ConstValue::Double(f64::from_bits(bits))),
+            },
+            Kind::Ref => self.op(Opcodes::ACONST_NULL, 1),
+        }
+    }
+
+    fn ldc(&mut self, value: ConstValue) {
+        let delta = match value {
+            ConstValue::Long(_) | ConstValue::Double(_) => 2,
+            ConstValue::MethodHandle(_) | ConstValue::MethodType(_) => {
+                self.uses_java7 = true;
+                1
+            }
+            _ => 1,
+        };
+        self.emit(InsnNode::LdcInsnNode(Arc::new(value)), delta);
+    }
+
+    fn type_insn(&mut self, opcode: u8, desc: &str, delta: i32) {
+        let type_name = StrRef::from(desc_to_internal(desc));
+        self.emit(InsnNode::TypeInsnNode { opcode, type_name }, delta);
+    }
+
+    fn new_array(&mut self, array_desc: &str) {
+        let element = array_desc.get(1..).unwrap_or_default();
+        let array_type = match element {
+            "Z" => NewArrayTypeOperand::T_BOOLEAN,
+            "C" => NewArrayTypeOperand::T_CHAR,
+            "F" => NewArrayTypeOperand::T_FLOAT,
+            "D" => NewArrayTypeOperand::T_DOUBLE,
+            "B" => NewArrayTypeOperand::T_BYTE,
+            "S" => NewArrayTypeOperand::T_SHORT,
+            "I" => NewArrayTypeOperand::T_INT,
+            "J" => NewArrayTypeOperand::T_LONG,
+            _ => return self.type_insn(Opcodes::ANEWARRAY, element, 0),
+        };
+        self.emit(InsnNode::NewArrayInsnNode { array_type }, 0);
+    }
+
+    /// Keeps the result for the following `move-result`, or drops it.
+    fn handle_result(&mut self, index: usize, result_size: i32) {
+        let next_is_move_result = self.code.insns.get(index + 1)
+            .is_some_and(|(_, insn)| is_move_result(insn));
+        if next_is_move_result { return; }
+        match result_size {
+            1 => self.op(Opcodes::POP, -1),
+            2 => self.op(Opcodes::POP2, -2),
+            _ => {}
+        }
+    }
+
+    fn field(&mut self, opcode: u8, field_idx: u16) -> AsmResult<Kind> {
+        let field = self.code.accessor.get_field(field_idx)?;
+        let kind = Kind::from_desc(&field.field_type);
+        let delta = match opcode {
+            Opcodes::GETSTATIC => size(kind),
+            Opcodes::PUTSTATIC => -size(kind),
+            Opcodes::GETFIELD => size(kind) - 1,
+            _ => -size(kind) - 1,
+        };
+        let owner = StrRef::from(desc_to_internal(&field.class_type));
+        self.emit(InsnNode::FieldInsnNode { opcode, owner, name: field.field_name, desc: field.field_type }, delta);
+        Ok(kind)
+    }
+
+    fn load_uses(&mut self, index: usize) {
+        let uses = self.types.ops[index].uses.clone();
+        for (reg, ty) in uses {
+            self.load(index, reg, ty);
+        }
+    }
+
+    fn invoke(&mut self, index: usize) -> AsmResult<()> {
+        let insn = self.code.insns[index].1;
+        let accessor = self.code.accessor;
+        let depth_before = self.depth;
+        self.load_uses(index);
+        let jvm_insn = match insn {
+            DexInsn::InvokeKind(F35c { opcode, constB, .. }) |
+            DexInsn::InvokeKindRange(F3rc { opcode, constB, .. }) => {
+                let method = accessor.get_method(*constB)?;
+                let opcode = match opcode {
+                    0x6e | 0x74 => Opcodes::INVOKEVIRTUAL,
+                    0x6f | 0x75 | 0x70 | 0x76 => Opcodes::INVOKESPECIAL,
+                    0x71 | 0x77 => Opcodes::INVOKESTATIC,
+                    _ => Opcodes::INVOKEINTERFACE,
+                };
+                let owner = StrRef::from(desc_to_internal(&method.class_type));
+                InsnNode::MethodInsnNode { opcode, owner, name: method.method_name, desc: method.desc }
+            }
+            DexInsn::InvokePoly(F45cc { constB, constH, .. }) |
+            DexInsn::InvokePolyRange(F4rcc { constB, constH, .. }) => {
+                // signature polymorphic methods are invoked with the descriptor of the call site
+                let method = accessor.get_method(*constB)?;
+                let desc = StrRef::from(accessor.get_proto(*constH)?.to_string());
+                let owner = StrRef::from(desc_to_internal(&method.class_type));
+                self.uses_java7 = true;
+                InsnNode::MethodInsnNode { opcode: Opcodes::INVOKEVIRTUAL, owner, name: method.method_name, desc }
+            }
+            DexInsn::InvokeCustom(F35c { constB, .. }) |
+            DexInsn::InvokeCustomRange(F3rc { constB, .. }) => {
+                self.uses_java7 = true;
+                InsnNode::InvokeDynamicInsnNode(call_site(accessor, *constB)?)
+            }
+            _ => return Ok(()),
+        };
+        let desc = invoke_desc(accessor, insn)?.map(|(_, desc)| desc).unwrap_or_default();
+        let result_size = type_slots(method_return_type(&desc)) as i32;
+        let delta = depth_before - self.depth + result_size;
+        self.emit(jvm_insn, delta);
+        self.handle_result(index, result_size);
+        Ok(())
+    }
+
+    fn fill_array_data(&mut self, index: usize, array_reg: u16, payload: &FillArrayDataPayload) {
+        let width = payload.element_width as usize;
+        let element: StrRef = match self.types.use_ref(index, array_reg).and_then(|t| t.strip_prefix('[')) {
+            Some(element) => StrRef::from(element),
+            None => StrRef::from(match width {
+                1 => "B",
+                2 => "S",
+                8 => "J",
+                _ => "I",
+            }),
+        };
+        let kind = Kind::from_desc(&element);
+        let (_, store) = array_opcodes(&element);
+        for (i, chunk) in payload.data.chunks_exact(width.max(1)).enumerate() {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len().min(8)].copy_from_slice(&chunk[..chunk.len().min(8)]);
+            let raw = u64::from_le_bytes(bytes);
+            // sign extend the little-endian element, except chars
+            let shift = 64 - 8 * chunk.len().min(8) as u32;
+            let literal = match element.as_ref() {
+                "C" | "Z" => raw as i64,
+                _ if shift == 0 => raw as i64,
+                _ => ((raw << shift) as i64) >> shift,
+            };
+            self.var(Opcodes::ALOAD, Kind::Ref, array_reg, 1);
+            self.push_int(i as i32);
+            self.push_const(literal, kind);
+            self.op(store, -2 - size(kind));
+        }
+    }
+
+    fn emit_insn(&mut self, index: usize) -> AsmResult<()> {
+        let (addr, insn) = self.code.insns[index];
+        let accessor = self.code.accessor;
+        let types = self.types;
+        match insn {
+            DexInsn::Nop(_) => self.op(Opcodes::NOP, 0),
+            DexInsn::Move(_) | DexInsn::MoveFrom16(_) | DexInsn::Move16(_) |
+            DexInsn::MoveWide(_) | DexInsn::MoveWideFrom16(_) | DexInsn::MoveWide16(_) |
+            DexInsn::MoveObject(_) | DexInsn::MoveObjectFrom16(_) | DexInsn::MoveObject16(_) => {
+                if let Res::Const(_) = types.def_res(index) { return Ok(()); }
+                let (src, ty) = types.ops[index].uses[0];
+                self.load(index, src, ty);
+                self.store(index, ty);
+            }
+            DexInsn::MoveResult(_) | DexInsn::MoveResultWide(_) | DexInsn::MoveResultObject(_) |
+            DexInsn::MoveException(_) => {
+                let ty = types.ops[index].def.map(|(_, ty)| ty).unwrap_or(Ty::Cat1);
+                self.store(index, ty);
+            }
+            DexInsn::ReturnVoid(_) => self.op(Opcodes::RETURN, 0),
+            DexInsn::Return(F11x { vA, .. }) |
+            DexInsn::ReturnWide(F11x { vA, .. }) |
+            DexInsn::ReturnObject(F11x { vA, .. }) => {
+                let kind = Kind::from_desc(method_return_type(&self.code.desc));
+                self.load(index, *vA as u16, Ty::Exact(kind));
+                self.op(return_opcode(kind), -size(kind));
+            }
+            DexInsn::Const4(_) | DexInsn::Const16(_) | DexInsn::Const(_) | DexInsn::ConstHigh16(_) |
+            DexInsn::ConstWide16(_) | DexInsn::ConstWide32(_) | DexInsn::ConstWide(_) |
+            DexInsn::ConstWideHigh16(_) => {
+                let literal = types.ops[index].literal.unwrap_or_default();
+                let ty = types.ops[index].def.map(|(_, ty)| ty).unwrap_or(Ty::Cat1);
+                let kind = match types.def_res(index) {
+                    Res::Const(_) => return Ok(()),
+                    Res::Kind(kind) => kind,
+                    Res::Unknown => ty.or_default(),
+                };
+                self.push_const(literal, kind);
+                self.store(index, ty);
+            }
+            DexInsn::ConstString(F21c { constB, .. }) => {
+                self.ldc(ConstValue::String(accessor.get_str(*constB)?));
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::ConstStringJumbo(F31c { constB, .. }) => {
+                self.ldc(ConstValue::String(accessor.get_str(constB.0 as usize)?));
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::ConstClass(F21c { constB, .. }) => {
+                let desc = accessor.get_type(*constB)?;
+                self.ldc(ConstValue::Class(StrRef::from(desc_to_internal(&desc))));
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::ConstMethodHandle(F21c { constB, .. }) => {
+                let handle = convert_handle(accessor, accessor.get_method_handle(*constB)?)?;
+                self.ldc(ConstValue::MethodHandle(handle));
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::ConstMethodType(F21c { constB, .. }) => {
+                let desc = accessor.get_proto(*constB)?.to_string();
+                self.ldc(ConstValue::MethodType(StrRef::from(desc)));
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::MonitorEnter(_) => {
+                self.load_uses(index);
+                self.op(Opcodes::MONITORENTER, -1);
+            }
+            DexInsn::MonitorExit(_) => {
+                self.load_uses(index);
+                self.op(Opcodes::MONITOREXIT, -1);
+            }
+            DexInsn::CheckCast(F21c { constB, .. }) => {
+                self.load_uses(index);
+                self.type_insn(Opcodes::CHECKCAST, &accessor.get_type(*constB)?, 0);
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::InstanceOf(F22c { constC, .. }) => {
+                self.load_uses(index);
+                self.type_insn(Opcodes::INSTANCEOF, &accessor.get_type(*constC)?, 0);
+                self.store(index, Ty::Exact(Kind::Int));
+            }
+            DexInsn::ArrayLength(_) => {
+                self.load_uses(index);
+                self.op(Opcodes::ARRAYLENGTH, 0);
+                self.store(index, Ty::Exact(Kind::Int));
+            }
+            DexInsn::NewInstance(F21c { constB, .. }) => {
+                // the uninitialized object is kept in the register until the constructor call.
+                self.type_insn(Opcodes::NEW, &accessor.get_type(*constB)?, 1);
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::NewArray(F22c { constC, .. }) => {
+                self.load_uses(index);
+                self.new_array(&accessor.get_type(*constC)?);
+                self.store(index, Ty::Exact(Kind::Ref));
+            }
+            DexInsn::FilledNewArray(F35c { constB, .. }) |
+            DexInsn::FilledNewArrayRange(F3rc { constB, .. }) => {
+                let array_type = accessor.get_type(*constB)?;
+                let element = array_type.get(1..).unwrap_or_default();
+                let (_, store) = array_opcodes(element);
+                let element_kind = Kind::from_desc(element);
+                let regs = invoke_regs(insn);
+                self.push_int(regs.len() as i32);
+                self.new_array(&array_type);
+                for (i, reg) in regs.into_iter().enumerate() {
+                    self.op(Opcodes::DUP, 1);
+                    self.push_int(i as i32);
+                    self.load(index, reg, Ty::Exact(element_kind));
+                    self.op(store, -2 - size(element_kind));
+                }
+                self.handle_result(index, 1);
+            }
+            DexInsn::FillArrayData(F31t { vA, offsetB, .. }) => {
+                let payload_addr = addr.wrapping_add(*offsetB as u32);
+                let DexInsn::FillArrayDataPayload(payload) = self.code.payload(payload_addr)? else {
+                    return Err(AsmErr::IllegalFormat(format!("bad fill-array-data payload at {addr}")));
+                };
+                self.fill_array_data(index, *vA as u16, payload);
+            }
+            DexInsn::Throw(_) => {
+                self.load_uses(index);
+                self.op(Opcodes::ATHROW, -1);
+            }
+            DexInsn::Goto(_) | DexInsn::Goto16(_) | DexInsn::Goto32(_) => {
+                let target = self.code.targets(index)?[0];
+                let label = self.label(target)?;
+                self.emit(InsnNode::JumpInsnNode { opcode: Opcodes::GOTO, label }, 0);
+            }
+            DexInsn::PackedSwitch(F31t { offsetB, .. }) |
+            DexInsn::SparseSwitch(F31t { offsetB, .. }) => {
+                self.load_uses(index);
+                let next_addr = self.code.insns.get(index + 1).map(|(a, _)| *a).unwrap_or(u32::MAX);
+                let default = self.label(next_addr)?;
+                let targets = self.code.targets(index)?;
+                let mut labels = Vec::with_capacity(targets.len());
+                for target in targets { labels.push(self.label(target)?); }
+                let payload = self.code.payload(addr.wrapping_add(*offsetB as u32))?;
+                let jvm_insn = match payload {
+                    DexInsn::PackedSwitchPayload(p) if p.size > 0 => {
+                        let min = p.first_key;
+                        let max = min.wrapping_add(p.size as i32 - 1);
+                        InsnNode::TableSwitchInsnNode { default, min, max, labels }
+                    }
+                    DexInsn::PackedSwitchPayload(_) => {
+                        InsnNode::LookupSwitchInsnNode { default, keys: vec![], labels }
+                    }
+                    DexInsn::SparseSwitchPayload(p) => {
+                        InsnNode::LookupSwitchInsnNode { default, keys: p.keys.clone(), labels }
+                    }
+                    _ => return Err(AsmErr::IllegalFormat(format!("bad switch payload at {addr}"))),
+                };
+                self.emit(jvm_insn, -1);
+            }
+            DexInsn::Cmpkind(F23x { opcode, .. }) => {
+                self.load_uses(index);
+                let (jvm_opcode, operand) = match opcode {
+                    0x2d => (Opcodes::FCMPL, Kind::Float),
+                    0x2e => (Opcodes::FCMPG, Kind::Float),
+                    0x2f => (Opcodes::DCMPL, Kind::Double),
+                    0x30 => (Opcodes::DCMPG, Kind::Double),
+                    _ => (Opcodes::LCMP, Kind::Long),
+                };
+                self.op(jvm_opcode, 1 - 2 * size(operand));
+                self.store(index, Ty::Exact(Kind::Int));
+            }
+            DexInsn::IfTest(F22t { opcode, vA, vB, .. }) => {
+                let condition = opcode.wrapping_sub(0x32);
+                let (a, b) = (vA.0 as u16, vB.0 as u16);
+                let is_ref = condition <= 1 && [a, b].iter()
+                    .any(|reg| types.use_res(index, *reg) == Res::Kind(Kind::Ref));
+                let kind = if is_ref { Kind::Ref } else { Kind::Int };
+                self.load(index, a, Ty::Exact(kind));
+                self.load(index, b, Ty::Exact(kind));
+                let jvm_opcode = if is_ref {
+                    Opcodes::IF_ACMPEQ + condition
+                } else {
+                    Opcodes::IF_ICMPEQ + condition
+                };
+                let label = self.label(self.code.targets(index)?[0])?;
+                self.emit(InsnNode::JumpInsnNode { opcode: jvm_opcode, label }, -2);
+            }
+            DexInsn::IfTestz(F21t { opcode, vA, .. }) => {
+                let condition = opcode.wrapping_sub(0x38);
+                let reg = *vA as u16;
+                let res = types.use_res(index, reg);
+                let is_ref = condition <= 1 && res == Res::Kind(Kind::Ref);
+                let is_float = res == Res::Kind(Kind::Float);
+                let jvm_opcode = if is_ref {
+                    if condition == 0 { Opcodes::IFNULL } else { Opcodes::IFNONNULL }
+                } else {
+                    Opcodes::IFEQ + condition
+                };
+                if is_ref {
+                    self.load(index, reg, Ty::Exact(Kind::Ref));
+                } else if is_float {
+                    self.load(index, reg, Ty::Exact(Kind::Float));
+                    self.op(Opcodes::FCONST_0, 1);
+                    self.op(Opcodes::FCMPL, -1);
+                } else {
+                    self.load(index, reg, Ty::Exact(Kind::Int));
+                }
+                let label = self.label(self.code.targets(index)?[0])?;
+                self.emit(InsnNode::JumpInsnNode { opcode: jvm_opcode, label }, -1);
+            }
+            DexInsn::ArrayOp(F23x { opcode, vA, vB, .. }) => {
+                let op = opcode.wrapping_sub(0x44);
+                let is_get = op < 7;
+                let element: StrRef = match op % 7 {
+                    0 | 1 => {
+                        let res = if is_get { types.def_res(index) } else { types.use_res(index, *vA as u16) };
+                        let array_element = types.use_ref(index, *vB as u16)
+                            .and_then(|t| t.strip_prefix('[')).map(StrRef::from);
+                        match (res, array_element) {
+                            (Res::Kind(kind), _) => StrRef::from(kind_element(kind)),
+                            (_, Some(element)) => element,
+                            _ => StrRef::from(if op % 7 == 0 { "I" } else { "J" }),
+                        }
+                    }
+                    2 => StrRef::from("Ljava/lang/Object;"),
+                    3 => StrRef::from("Z"),
+                    4 => StrRef::from("B"),
+                    5 => StrRef::from("C"),
+                    _ => StrRef::from("S"),
+                };
+                let kind = Kind::from_desc(&element);
+                let (load, store) = array_opcodes(&element);
+                let uses = types.ops[index].uses.clone();
+                self.load(index, uses[0].0, uses[0].1);
+                self.load(index, uses[1].0, uses[1].1);
+                if is_get {
+                    self.op(load, size(kind) - 2);
+                    self.store(index, Ty::Exact(kind));
+                } else {
+                    self.load(index, *vA as u16, Ty::Exact(kind));
+                    self.op(store, -2 - size(kind));
+                }
+            }
+            DexInsn::IInstanceOp(F22c { opcode, vA, vB, constC }) => {
+                self.load(index, vB.0 as u16, Ty::Exact(Kind::Ref));
+                if opcode.wrapping_sub(0x52) < 7 {
+                    let kind = self.field(Opcodes::GETFIELD, *constC)?;
+                    self.store(index, Ty::Exact(kind));
+                } else {
+                    let field_type = accessor.get_field(*constC)?.field_type;
+                    self.load(index, vA.0 as u16, Ty::Exact(Kind::from_desc(&field_type)));
+                    self.field(Opcodes::PUTFIELD, *constC)?;
+                }
+            }
+            DexInsn::SStaticOp(F21c { opcode, vA, constB }) => {
+                if opcode.wrapping_sub(0x60) < 7 {
+                    let kind = self.field(Opcodes::GETSTATIC, *constB)?;
+                    self.store(index, Ty::Exact(kind));
+                } else {
+                    let field_type = accessor.get_field(*constB)?.field_type;
+                    self.load(index, *vA as u16, Ty::Exact(Kind::from_desc(&field_type)));
+                    self.field(Opcodes::PUTSTATIC, *constB)?;
+                }
+            }
+            DexInsn::InvokeKind(_) | DexInsn::InvokeKindRange(_) |
+            DexInsn::InvokePoly(_) | DexInsn::InvokePolyRange(_) |
+            DexInsn::InvokeCustom(_) | DexInsn::InvokeCustomRange(_) => self.invoke(index)?,
+            DexInsn::Unop(F12x { opcode, .. }) => {
+                let (src, dst, jvm_opcode) = UNOPS[opcode.wrapping_sub(0x7b).min(20) as usize];
+                self.load_uses(index);
+                if jvm_opcode == Opcodes::NOP {
+                    // not-int & not-long: x ^ -1
+                    self.push_const(-1, src);
+                    let xor = if src == Kind::Long { Opcodes::LXOR } else { Opcodes::IXOR };
+                    self.op(xor, -size(src));
+                } else {
+                    self.op(jvm_opcode, size(dst) - size(src));
+                }
+                self.store(index, Ty::Exact(dst));
+            }
+            DexInsn::Binop(F23x { opcode, .. }) | DexInsn::Binop2Addr(F12x { opcode, .. }) => {
+                let base = if let DexInsn::Binop(_) = insn { 0x90 } else { 0xb0 };
+                let (kind, jvm_opcode) = binop(opcode.wrapping_sub(base));
+                self.load_uses(index);
+                let second = if is_shift(jvm_opcode) { 1 } else { size(kind) };
+                self.op(jvm_opcode, -second);
+                self.store(index, Ty::Exact(kind));
+            }
+            DexInsn::BinopLit16(F22s { opcode, vB, literalC, .. }) => {
+                self.binop_literal(index, opcode.wrapping_sub(0xd0), vB.0 as u16, *literalC as i32);
+            }
+            DexInsn::BinopLit8(F22b { opcode, vB, literalC, .. }) => {
+                self.binop_literal(index, opcode.wrapping_sub(0xd8), *vB as u16, *literalC as i32);
+            }
+            DexInsn::NotUsed(F10x { opcode, .. }) => {
+                return Err(AsmErr::IllegalFormat(format!("unused dalvik opcode 0x{opcode:02x} at {addr}")));
+            }
+            DexInsn::PackedSwitchPayload(_) | DexInsn::SparseSwitchPayload(_) |
+            DexInsn::FillArrayDataPayload(_) => {}
+        }
+        Ok(())
+    }
+
+    /// `binop/lit16` and `binop/lit8`, `op` is the index in: add, rsub, mul, div, rem, and, or,
+    /// xor, shl, shr, ushr.
+    fn binop_literal(&mut self, index: usize, op: u8, reg: u16, literal: i32) {
+        const OPS: [u8; 11] = [
+            Opcodes::IADD, Opcodes::ISUB, Opcodes::IMUL, Opcodes::IDIV, Opcodes::IREM, Opcodes::IAND,
+            Opcodes::IOR, Opcodes::IXOR, Opcodes::ISHL, Opcodes::ISHR, Opcodes::IUSHR,
+        ];
+        if op == 1 {
+            self.push_int(literal);
+            self.load(index, reg, Ty::Exact(Kind::Int));
+        } else {
+            self.load(index, reg, Ty::Exact(Kind::Int));
+            self.push_int(literal);
+        }
+        self.op(OPS[(op as usize).min(10)], -1);
+        self.store(index, Ty::Exact(Kind::Int));
+    }
+}
+
+/// Converts the dalvik code of a method into a jvm code body.
+///
+/// Labels of the result are instruction indexes. Stack map frames are not computed.
+pub(crate) fn convert_code(
+    code: &DexCode, debug_info: Option<&DebugInfoItem>,
+) -> AsmResult<ConvertedCode> {
+    let types = RegisterTypes::analyze(code)?;
+    let handler_addrs: Vec<u32> = code.tries.iter()
+        .flat_map(|t| t.handlers.iter().map(|(_, addr)| *addr))
+        .collect();
+    let mut emitter = Emitter {
+        code, types: &types, insns: vec![], targets: vec![],
+        depth: 0, max_stack: 0, max_locals: code.ins, uses_java7: false, def_store: None,
+    };
+    let mut starts = Vec::with_capacity(code.insns.len() + 1);
+    // the part of each instruction which may throw, the jvm verifier checks handlers with
+    // the locals before and after every instruction in the protected range, but a dalvik
+    // instruction never writes its result register when it throws.
+    let mut throw_ends = Vec::with_capacity(code.insns.len());
+    for index in 0..code.insns.len() {
+        let (addr, insn) = code.insns[index];
+        starts.push(emitter.insns.len());
+        // values are only kept in jvm operand stack inside a dalvik instruction, except
+        // invoke results for `move-result` and the exception for `move-exception`.
+        if !is_move_result(insn) {
+            let is_exception = matches!(insn, DexInsn::MoveException(_)) && handler_addrs.contains(&addr);
+            emitter.depth = if is_exception { 1 } else { 0 };
+            emitter.max_stack = emitter.max_stack.max(emitter.depth);
+        }
+        emitter.def_store = None;
+        emitter.emit_insn(index)?;
+        let throw_end = if can_throw(insn) {
+            emitter.def_store.unwrap_or(emitter.insns.len())
+        } else {
+            starts[index]
+        };
+        throw_ends.push(throw_end);
+    }
+    let code_end = emitter.insns.len();
+    starts.push(code_end);
+
+    // handlers which don't start with `move-exception` need to drop the exception first.
+    let mut handler_labels: HashMap<u32, usize> = HashMap::new();
+    for addr in &handler_addrs {
+        if handler_labels.contains_key(addr) { continue; }
+        let index = code.index_of(*addr)?;
+        let label = if let DexInsn::MoveException(_) = code.insns[index].1 {
+            starts[index]
+        } else {
+            let stub = emitter.insns.len();
+            emitter.depth = 1;
+            emitter.op(Opcodes::POP, -1);
+            let label = emitter.label(*addr)?;
+            emitter.emit(InsnNode::JumpInsnNode { opcode: Opcodes::GOTO, label }, 0);
+            stub
+        };
+        handler_labels.insert(*addr, label);
+    }
+
+    let to_label = |jvm_index: usize| -> AsmResult<LabelNode> {
+        LabelNode::try_from(jvm_index)
+            .map_err(|_| AsmErr::IllegalFormat("method code is too large".to_string()))
+    };
+    let label_of_addr = |addr: u32| -> AsmResult<LabelNode> {
+        to_label(starts[code.index_of(addr)?])
+    };
+    let Emitter { mut insns, targets, max_stack, mut max_locals, uses_java7, .. } = emitter;
+    let resolve = |label: &mut LabelNode| -> AsmResult<()> {
+        *label = label_of_addr(targets[*label as usize])?;
+        Ok(())
+    };
+    for insn in &mut insns {
+        match insn {
+            InsnNode::JumpInsnNode { label, .. } => resolve(label)?,
+            InsnNode::TableSwitchInsnNode { default, labels, .. } |
+            InsnNode::LookupSwitchInsnNode { default, labels, .. } => {
+                resolve(default)?;
+                for label in labels { resolve(label)?; }
+            }
+            _ => {}
+        }
+    }
+
+    let mut exception_table = vec![];
+    for try_range in &code.tries {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for index in try_range.start..try_range.end {
+            let (start, end) = (starts[index], throw_ends[index]);
+            if start == end { continue; }
+            match ranges.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => ranges.push((start, end)),
+            }
+        }
+        for (start, end) in ranges {
+            for (catch_type, addr) in &try_range.handlers {
+                exception_table.push(ExceptionTable {
+                    start: to_label(start)?,
+                    end: to_label(end)?,
+                    handler: to_label(handler_labels[addr])?,
+                    catch_type: catch_type.as_ref().map(|t| StrRef::from(desc_to_internal(t))),
+                });
+            }
+        }
+    }
+
+    let mut line_numbers = vec![];
+    let mut local_variables = vec![];
+    if let Some(debug_info) = debug_info {
+        for (addr, line, _) in &debug_info.records {
+            let Ok(start_pc) = label_of_addr(*addr) else { continue };
+            line_numbers.push(LineNumberNode { start_pc, line_number: *line as u16 });
+        }
+        let end_label = to_label(code_end)?;
+        let accessor = code.accessor;
+        let params = code.param_regs();
+        let names = params.iter().skip(if code.is_static { 0 } else { 1 })
+            .zip(debug_info.parameter_names.iter());
+        if !code.is_static && let Some((reg, desc)) = params.first() {
+            local_variables.push(LocalVariableNode {
+                name: StrRef::from("this"), desc: desc.clone(), signature: None,
+                start: 0, end: end_label, index: code.local(*reg),
+            });
+        }
+        for ((reg, desc), name_idx) in names {
+            let Some(name) = name_idx.value().and_then(|idx| accessor.get_str(idx as usize).ok()) else { continue };
+            local_variables.push(LocalVariableNode {
+                name, desc: desc.clone(), signature: None, start: 0, end: end_label, index: code.local(*reg),
+            });
+        }
+        for local_var in &debug_info.local_vars {
+            let name = local_var.name_idx.value().and_then(|idx| accessor.get_str(idx as usize).ok());
+            let desc = local_var.type_idx.value().and_then(|idx| accessor.get_type(idx as usize).ok());
+            let (Some(name), Some(desc)) = (name, desc) else { continue };
+            let signature = local_var.sig_idx.value().and_then(|idx| accessor.get_str(idx as usize).ok());
+            let Some(Ok(start)) = local_var.start_addr.map(label_of_addr) else { continue };
+            let end = match local_var.end_addr {
+                Some(addr) => label_of_addr(addr).unwrap_or(end_label),
+                None => end_label,
+            };
+            if start >= end { continue; }
+            let index = code.local(local_var.register.value() as u16);
+            // parameters may be declared again in debug info, which must not be duplicated
+            let is_duplicated = local_variables.iter()
+                .any(|v| v.index == index && v.start == start && v.end == end && v.name == name);
+            if is_duplicated { continue; }
+            max_locals = max_locals.max(index + type_slots(&desc));
+            local_variables.push(LocalVariableNode { name, desc, signature, start, end, index });
+        }
+    }
+
+    let insn_offsets = (0..insns.len()).map(to_label).collect::<AsmResult<Vec<_>>>()?;
+    let body = CodeBodyNode {
+        instructions: insns,
+        insn_offsets,
+        exception_table,
+        local_variables,
+        line_numbers,
+        max_stack: max_stack.clamp(0, u16::MAX as i32) as u16,
+        max_locals,
+        type_annotations: vec![],
+        stack_map_table: vec![],
+        unknown_attributes: vec![],
+    };
+    Ok(ConvertedCode { body, uses_java7 })
+}
@@ -0,0 +1,541 @@
+use crate::analysis::{Analyzer, BasicInterpreter};
+use crate::dex::{AnnotationItem, AnnotationSetItem, AnnotationSetRefList, AnnotationVisibility, AnnotationsDirectoryItem, ClassAccessFlags, ClassDataItem, ClassDef, CodeItem, DebugInfoItem, DexFileAccessor, EncodedAnnotation, EncodedArray, EncodedValue, MethodAccessFlags, NO_INDEX};
+use crate::err::AsmResultOkExt;
+use crate::hierarchy::HierarchyResolver;
+use crate::impls::dex::convert::code::{convert_code, DexCode};
+use crate::impls::{desc_to_internal, ToStringRef};
+use crate::node::element::{AnnotationNode, ClassNode, FieldNode, InnerClassNode, MethodNode, ParameterNode};
+use crate::node::InsnNode;
+use crate::node::values::{AnnotationValue, ConstValue, FieldInitialValue};
+use crate::verify::Verifier;
+use crate::{AsmErr, AsmResult, DescriptorRef, InternalNameRef, JavaClassAccessFlags, JavaMethodAccessFlags, JavaVersions, Opcodes, StrRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub(crate) mod code;
+pub(crate) mod types;
+
+/// Information kept in `dalvik.annotation.*` system annotations, which are attributes in jvm.
+#[derive(Default)]
+struct SystemAnnotations {
+    signature: Option<StrRef>,
+    throws: Vec<InternalNameRef>,
+    /// `InnerClass`: the simple name (or [None] for anonymous classes) and access flags.
+    inner_class: Option<(Option<StrRef>, u16)>,
+    enclosing_class: Option<DescriptorRef>,
+    /// `EnclosingMethod`: class type, name and descriptor of the method.
+    enclosing_method: Option<(DescriptorRef, StrRef, DescriptorRef)>,
+    member_classes: Vec<DescriptorRef>,
+    annotation_default: Vec<(StrRef, AnnotationValue)>,
+    parameters: Vec<ParameterNode>,
+    source_debug: Option<StrRef>,
+    nest_host: Option<DescriptorRef>,
+    nest_members: Vec<DescriptorRef>,
+    permitted_subclasses: Vec<DescriptorRef>,
+}
+
+/// Annotations of a class, field, method or parameter.
+struct Annotations {
+    annotations: Vec<AnnotationNode>,
+    system: SystemAnnotations,
+}
+
+fn value_type_err(value: &EncodedValue) -> AsmErr {
+    AsmErr::IllegalFormat(format!("unexpected encoded value in system annotation: {value:?}"))
+}
+
+fn convert_value(accessor: &DexFileAccessor, value: &EncodedValue) -> AsmResult<AnnotationValue> {
+    let const_value = |value: ConstValue| AnnotationValue::Const(Arc::new(value));
+    let annotation_value = match value {
+        EncodedValue::Byte(v) => const_value(ConstValue::Integer(*v as i32)),
+        EncodedValue::Short(v) => const_value(ConstValue::Integer(*v as i32)),
+        EncodedValue::Char(v) => const_value(ConstValue::Integer(*v as i32)),
+        EncodedValue::Int(v) => const_value(ConstValue::Integer(*v)),
+        EncodedValue::Boolean(v) => const_value(ConstValue::Integer(*v as i32)),
+        EncodedValue::Long(v) => const_value(ConstValue::Long(*v)),
+        EncodedValue::Float(bytes) => const_value(ConstValue::Float(f32::from_le_bytes(*bytes))),
+        EncodedValue::Double(bytes) => const_value(ConstValue::Double(f64::from_le_bytes(*bytes))),
+        EncodedValue::String(idx) => const_value(ConstValue::String(accessor.get_str(idx.0 as usize)?)),
+        EncodedValue::Type(idx) => AnnotationValue::Class(accessor.get_type(idx.0 as usize)?),
+        EncodedValue::Enum(idx) => {
+            let field = accessor.get_field(idx.0 as usize)?;
+            AnnotationValue::Enum(field.field_type, field.field_name)
+        }
+        EncodedValue::Array(array) => {
+            let mut values = Vec::with_capacity(array.values.len());
+            for value in &array.values {
+                values.push(convert_value(accessor, value)?);
+            }
+            AnnotationValue::Array(values)
+        }
+        EncodedValue::Annotation(annotation) => {
+            AnnotationValue::Annotation(convert_annotation(accessor, true, annotation)?)
+        }
+        _ => return Err(AsmErr::IllegalFormat(format!("cannot convert {value:?} to an annotation value"))),
+    };
+    Ok(annotation_value)
+}
+
+fn convert_annotation(
+    accessor: &DexFileAccessor, visible: bool, annotation: &EncodedAnnotation,
+) -> AsmResult<AnnotationNode> {
+    let type_name = accessor.get_type(annotation.type_idx.value() as usize)?;
+    let mut values = Vec::with_capacity(annotation.elements.len());
+    for element in &annotation.elements {
+        let name = accessor.get_str(element.name_idx.value() as usize)?;
+        values.push((name, convert_value(accessor, &element.value)?));
+    }
+    AnnotationNode { visible, type_name, values }.ok()
+}
+
+fn type_value(accessor: &DexFileAccessor, value: &EncodedValue) -> AsmResult<DescriptorRef> {
+    match value {
+        EncodedValue::Type(idx) => accessor.get_type(idx.0 as usize),
+        _ => Err(value_type_err(value)),
+    }
+}
+
+fn array_values(value: &EncodedValue) -> AsmResult<&[EncodedValue]> {
+    match value {
+        EncodedValue::Array(EncodedArray { values, .. }) => Ok(values),
+        _ => Err(value_type_err(value)),
+    }
+}
+
+fn type_array(accessor: &DexFileAccessor, value: &EncodedValue) -> AsmResult<Vec<DescriptorRef>> {
+    array_values(value)?.iter().map(|v| type_value(accessor, v)).collect()
+}
+
+fn internal_name(desc: &str) -> InternalNameRef {
+    desc_to_internal(desc).to_ref()
+}
+
+impl SystemAnnotations {
+    fn put(&mut self, accessor: &DexFileAccessor, annotation: &EncodedAnnotation) -> AsmResult<()> {
+        let type_name = accessor.get_type(annotation.type_idx.value() as usize)?;
+        let mut elements = HashMap::with_capacity(annotation.elements.len());
+        for element in &annotation.elements {
+            elements.insert(accessor.get_str(element.name_idx.value() as usize)?, &element.value);
+        }
+        let element = |name: &str| elements.get(name).copied()
+            .ok_or_else(|| AsmErr::IllegalFormat(format!("missing `{name}` in {type_name}")));
+        match type_name.as_ref() {
+            "Ldalvik/annotation/Signature;" => {
+                let mut signature = String::new();
+                for part in array_values(element("value")?)? {
+                    let EncodedValue::String(idx) = part else { return Err(value_type_err(part)) };
+                    signature.push_str(&accessor.get_str(idx.0 as usize)?);
+                }
+                self.signature = Some(signature.to_ref());
+            }
+            "Ldalvik/annotation/Throws;" => {
+                self.throws = type_array(accessor, element("value")?)?.iter().map(|t| internal_name(t)).collect();
+            }
+            "Ldalvik/annotation/InnerClass;" => {
+                let name = match element("name")? {
+                    EncodedValue::String(idx) => Some(accessor.get_str(idx.0 as usize)?),
+                    _ => None,
+                };
+                let access = match element("accessFlags")? {
+                    EncodedValue::Int(flags) => *flags as u16,
+                    value => return Err(value_type_err(value)),
+                };
+                self.inner_class = Some((name, access));
+            }
+            "Ldalvik/annotation/EnclosingClass;" => {
+                self.enclosing_class = Some(type_value(accessor, element("value")?)?);
+            }
+            "Ldalvik/annotation/EnclosingMethod;" => {
+                if let EncodedValue::Method(idx) = element("value")? {
+                    let method = accessor.get_method(idx.0 as usize)?;
+                    self.enclosing_method = Some((method.class_type, method.method_name, method.desc));
+                }
+            }
+            "Ldalvik/annotation/MemberClasses;" => {
+                self.member_classes = type_array(accessor, element("value")?)?;
+            }
+            "Ldalvik/annotation/AnnotationDefault;" => {
+                let EncodedValue::Annotation(defaults) = element("value")? else { return Ok(()) };
+                self.annotation_default = convert_annotation(accessor, true, defaults)?.values;
+            }
+            "Ldalvik/annotation/MethodParameters;" => {
+                let names = array_values(element("names")?)?;
+                let access_flags = array_values(element("accessFlags")?)?;
+                for (name, access) in names.iter().zip(access_flags) {
+                    let name = match name {
+                        EncodedValue::String(idx) => Some(accessor.get_str(idx.0 as usize)?),
+                        _ => None,
+                    };
+                    let access = match access {
+                        EncodedValue::Int(flags) => *flags as u16,
+                        _ => 0,
+                    };
+                    self.parameters.push(ParameterNode { name, access });
+                }
+            }
+            "Ldalvik/annotation/SourceDebugExtension;" => {
+                if let EncodedValue::String(idx) = element("value")? {
+                    self.source_debug = Some(accessor.get_str(idx.0 as usize)?);
+                }
+            }
+            "Ldalvik/annotation/NestHost;" => {
+                self.nest_host = Some(type_value(accessor, element("host")?)?);
+            }
+            "Ldalvik/annotation/NestMembers;" => {
+                self.nest_members = type_array(accessor, element("classes")?)?;
+            }
+            "Ldalvik/annotation/PermittedSubclasses;" => {
+                self.permitted_subclasses = type_array(accessor, element("value")?)?;
+            }
+            // other system annotations (e.g. `Record`) have no corresponding attributes here.
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn read_annotation_set(accessor: &DexFileAccessor, annotations_off: u32) -> AsmResult<Annotations> {
+    let mut result = Annotations { annotations: vec![], system: SystemAnnotations::default() };
+    if annotations_off == 0 { return Ok(result); }
+    let set: AnnotationSetItem = accessor.get_data_impl(annotations_off)?;
+    for entry in set.entries {
+        let item: AnnotationItem = accessor.get_data_impl(entry)?;
+        match item.visibility {
+            AnnotationVisibility::VISIBILITY_SYSTEM => result.system.put(accessor, &item.annotation)?,
+            visibility => {
+                let visible = visibility == AnnotationVisibility::VISIBILITY_RUNTIME;
+                result.annotations.push(convert_annotation(accessor, visible, &item.annotation)?);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn read_parameter_annotations(
+    accessor: &DexFileAccessor, annotations_off: u32,
+) -> AsmResult<Vec<Vec<AnnotationNode>>> {
+    let ref_list: AnnotationSetRefList = accessor.get_data_impl(annotations_off)?;
+    let mut parameter_annotations = Vec::with_capacity(ref_list.list.len());
+    for set_off in ref_list.list {
+        parameter_annotations.push(read_annotation_set(accessor, set_off)?.annotations);
+    }
+    Ok(parameter_annotations)
+}
+
+/// Access flags and simple name of a member class, taken from its own `InnerClass` annotation.
+fn member_class_info(accessor: &DexFileAccessor, class_type: &str) -> Option<(Option<StrRef>, u16)> {
+    let class_def = accessor.find_class_def(class_type)?;
+    if class_def.annotations_off == 0 { return None; }
+    let directory: AnnotationsDirectoryItem = accessor.get_data_impl(class_def.annotations_off).ok()?;
+    read_annotation_set(accessor, directory.class_annotations_off).ok()?.system.inner_class
+}
+
+fn field_initial_value(accessor: &DexFileAccessor, value: &EncodedValue) -> AsmResult<Option<FieldInitialValue>> {
+    let initial_value = match value {
+        EncodedValue::Byte(v) => FieldInitialValue::Integer(*v as i32),
+        EncodedValue::Short(v) => FieldInitialValue::Integer(*v as i32),
+        EncodedValue::Char(v) => FieldInitialValue::Integer(*v as i32),
+        EncodedValue::Int(v) => FieldInitialValue::Integer(*v),
+        EncodedValue::Boolean(v) => FieldInitialValue::Integer(*v as i32),
+        EncodedValue::Long(v) => FieldInitialValue::Long(*v),
+        EncodedValue::Float(bytes) => FieldInitialValue::Float(f32::from_le_bytes(*bytes)),
+        EncodedValue::Double(bytes) => FieldInitialValue::Double(f64::from_le_bytes(*bytes)),
+        EncodedValue::String(idx) => FieldInitialValue::String(accessor.get_str(idx.0 as usize)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(initial_value))
+}
+
+fn class_access(access_flags: u32) -> u16 {
+    let mask = ClassAccessFlags::ACC_PUBLIC | ClassAccessFlags::ACC_FINAL | ClassAccessFlags::ACC_INTERFACE |
+        ClassAccessFlags::ACC_ABSTRACT | ClassAccessFlags::ACC_SYNTHETIC | ClassAccessFlags::ACC_ANNOTATION |
+        ClassAccessFlags::ACC_ENUM;
+    let access = (access_flags & mask) as u16;
+    if access & JavaClassAccessFlags::ACC_INTERFACE == 0 {
+        access | JavaClassAccessFlags::ACC_SUPER
+    } else {
+        access
+    }
+}
+
+fn method_access(access_flags: u32) -> u16 {
+    let access = (access_flags & 0xFFFF) as u16;
+    if access_flags & MethodAccessFlags::ACC_DECLARED_SYNCHRONIZED != 0 {
+        access | JavaMethodAccessFlags::ACC_SYNCHRONIZED
+    } else {
+        access
+    }
+}
+
+impl ClassDef {
+    /// Annotations of this dex class, system annotations (`dalvik.annotation.*`) are not included.
+    pub fn class_annotations(&self, accessor: &DexFileAccessor) -> AsmResult<Vec<AnnotationNode>> {
+        if self.annotations_off == 0 { return Ok(vec![]); }
+        let directory: AnnotationsDirectoryItem = accessor.get_data_impl(self.annotations_off)?;
+        Ok(read_annotation_set(accessor, directory.class_annotations_off)?.annotations)
+    }
+
+    /// The `SourceDebugExtension` of this dex class, see [crate::smap::SourceMap].
+    pub fn source_debug(&self, accessor: &DexFileAccessor) -> AsmResult<Option<StrRef>> {
+        if self.annotations_off == 0 { return Ok(None); }
+        let directory: AnnotationsDirectoryItem = accessor.get_data_impl(self.annotations_off)?;
+        Ok(read_annotation_set(accessor, directory.class_annotations_off)?.system.source_debug)
+    }
+
+    /// Converts this dex class into a jvm [ClassNode].
+    ///
+    /// Dalvik registers become local variables, parameters keep their jvm slots and the other
+    /// registers follow them. Unreachable code, e.g. the `nop`s before payloads, is replaced by
+    /// `nop`s ending with an `athrow` like ASM does.
+    ///
+    /// The class file version is 50, or 51 and 52 for code which needs `invokedynamic` or
+    /// interface methods. Classes of version 51 and above get stack map frames
+    /// computed by [Verifier::compute_frames] with the classes of the dex file, see
+    /// [DexFileAccessor::get_class_node_with] to merge types with more classes.
+    pub fn to_class_node(&self, accessor: &DexFileAccessor) -> AsmResult<ClassNode> {
+        self.to_class_node_with(accessor, accessor.hierarchy())
+    }
+
+    /// Converts this dex class into a jvm [ClassNode], types in stack map frames are merged
+    /// with the classes found by `resolver`.
+    pub fn to_class_node_with(&self, accessor: &DexFileAccessor, resolver: &dyn HierarchyResolver) -> AsmResult<ClassNode> {
+        let class_type = accessor.get_type(self.class_idx)?;
+        let name = internal_name(&class_type);
+        let access = class_access(self.access_flags);
+        let super_name = if self.superclass_idx.0 != NO_INDEX {
+            Some(internal_name(&accessor.get_type(self.superclass_idx)?))
+        } else {
+            None
+        };
+        let interfaces = accessor.get_type_list(self.interfaces_off)?
+            .iter().map(|t| internal_name(t)).collect();
+        let source_file = if self.source_file_idx.0 != NO_INDEX {
+            Some(accessor.get_str(self.source_file_idx)?)
+        } else {
+            None
+        };
+
+        let directory: Option<AnnotationsDirectoryItem> = if self.annotations_off != 0 {
+            Some(accessor.get_data_impl(self.annotations_off)?)
+        } else {
+            None
+        };
+        let class_annotations = read_annotation_set(
+            accessor, directory.as_ref().map(|d| d.class_annotations_off).unwrap_or_default(),
+        )?;
+        let mut field_annotations = HashMap::new();
+        let mut method_annotations = HashMap::new();
+        let mut parameter_annotations = HashMap::new();
+        if let Some(directory) = &directory {
+            for item in &directory.field_annotations {
+                field_annotations.insert(item.field_idx, item.annotations_off);
+            }
+            for item in &directory.method_annotations {
+                method_annotations.insert(item.method_idx, item.annotations_off);
+            }
+            for item in &directory.parameter_annotations {
+                parameter_annotations.insert(item.method_idx, item.annotations_off);
+            }
+        }
+
+        let Annotations { annotations, system } = class_annotations;
+        let mut annotation_defaults: HashMap<StrRef, AnnotationValue> = system.annotation_default.into_iter().collect();
+
+        let mut outer_class = None;
+        let mut outer_method_name = None;
+        let mut outer_method_desc = None;
+        if let Some((owner, method_name, method_desc)) = system.enclosing_method {
+            outer_class = Some(internal_name(&owner));
+            outer_method_name = Some(method_name);
+            outer_method_desc = Some(method_desc);
+        } else if let (Some(enclosing), Some((None, _))) = (&system.enclosing_class, &system.inner_class) {
+            // anonymous classes declared in initializers only have the enclosing class
+            outer_class = Some(internal_name(enclosing));
+        }
+
+        let mut inner_classes = vec![];
+        if let Some((inner_name, inner_access)) = &system.inner_class {
+            let is_member = outer_method_name.is_none() && inner_name.is_some();
+            inner_classes.push(InnerClassNode {
+                name: name.clone(),
+                outer_name: if is_member { system.enclosing_class.as_ref().map(|t| internal_name(t)) } else { None },
+                inner_name: inner_name.clone().unwrap_or_default(),
+                access: *inner_access,
+            });
+        }
+        for member in &system.member_classes {
+            let Some((Some(inner_name), inner_access)) = member_class_info(accessor, member) else { continue };
+            inner_classes.push(InnerClassNode {
+                name: internal_name(member), outer_name: Some(name.clone()), inner_name, access: inner_access,
+            });
+        }
+
+        let mut fields = vec![];
+        let mut methods = vec![];
+        let mut major_version = JavaVersions::V1_6 as u16;
+        if self.class_data_off != 0 {
+            let class_data: ClassDataItem = accessor.get_data_impl(self.class_data_off)?;
+            let static_values = if self.static_values_off != 0 {
+                accessor.get_data_impl::<EncodedArray>(self.static_values_off)?.values
+            } else {
+                vec![]
+            };
+            let mut field_idx = 0;
+            for (i, encoded_field) in class_data.static_fields.iter().chain(&class_data.instance_fields).enumerate() {
+                // field indexes are restarted in instance fields
+                if i == class_data.static_fields.len() { field_idx = 0; }
+                field_idx += encoded_field.field_idx_diff.value();
+                let field = accessor.get_field(field_idx as usize)?;
+                let value = match static_values.get(i) {
+                    Some(value) if i < class_data.static_fields.len() => field_initial_value(accessor, value)?,
+                    _ => None,
+                };
+                let Annotations { annotations, system } = read_annotation_set(
+                    accessor, field_annotations.get(&field_idx).copied().unwrap_or_default(),
+                )?;
+                fields.push(FieldNode {
+                    access: (encoded_field.access_flags.value() & 0xFFFF) as u16,
+                    name: field.field_name,
+                    desc: field.field_type,
+                    signature: system.signature,
+                    value,
+                    annotations,
+                    type_annotations: vec![],
+                    attrs: vec![],
+                });
+            }
+
+            let mut method_idx = 0;
+            for (i, encoded_method) in class_data.direct_methods.iter().chain(&class_data.virtual_methods).enumerate() {
+                if i == class_data.direct_methods.len() { method_idx = 0; }
+                method_idx += encoded_method.method_idx_diff.value();
+                let method = accessor.get_method(method_idx as usize)?;
+                let access_flags = encoded_method.access_flags.value();
+                let Annotations { annotations, system } = read_annotation_set(
+                    accessor, method_annotations.get(&method_idx).copied().unwrap_or_default(),
+                )?;
+                let parameter_annotations = match parameter_annotations.get(&method_idx) {
+                    Some(off) => read_parameter_annotations(accessor, *off)?,
+                    None => vec![],
+                };
+                let code_off = encoded_method.code_off.value();
+                let code_body = match accessor.get_code_item(code_off)? {
+                    Some(code_item) => {
+                        let is_static = access_flags & MethodAccessFlags::ACC_STATIC != 0;
+                        let converted = convert_method_code(
+                            accessor, code_off, &code_item, &class_type, &method.desc, is_static,
+                        ).map_err(|e| AsmErr::IllegalFormat(
+                            format!("cannot convert {}.{}{}: {e:?}", name, method.method_name, method.desc)
+                        ))?;
+                        if converted.uses_java7 {
+                            major_version = major_version.max(JavaVersions::V1_7 as u16);
+                        }
+                        Some(converted.body.into())
+                    }
+                    None => None,
+                };
+                let is_interface = access & JavaClassAccessFlags::ACC_INTERFACE != 0;
+                if is_interface && code_body.is_some() && method.method_name.as_ref() != "<clinit>" {
+                    // default, static or private methods in interfaces
+                    major_version = major_version.max(JavaVersions::V1_8 as u16);
+                }
+                methods.push(MethodNode {
+                    access: method_access(access_flags),
+                    annotation_default: annotation_defaults.remove(&method.method_name),
+                    name: method.method_name,
+                    desc: method.desc,
+                    signature: system.signature,
+                    exceptions: system.throws,
+                    parameters: system.parameters,
+                    annotations,
+                    type_annotations: vec![],
+                    parameter_annotations,
+                    attrs: vec![],
+                    code_body,
+                });
+            }
+        }
+
+        let mut node = ClassNode {
+            minor_version: 0,
+            major_version,
+            access,
+            name,
+            signature: system.signature,
+            super_name,
+            interfaces,
+            source_file,
+            source_debug: system.source_debug,
+            module: None,
+            outer_class,
+            outer_method_name,
+            outer_method_desc,
+            annotations,
+            type_annotations: vec![],
+            attrs: vec![],
+            inner_classes,
+            nest_host_class: system.nest_host.map(|t| internal_name(&t)),
+            nest_members: system.nest_members.iter().map(|t| internal_name(t)).collect(),
+            permitted_subclasses: system.permitted_subclasses.iter().map(|t| internal_name(t)).collect(),
+            record_components: vec![],
+            fields,
+            methods,
+        };
+        for method in &mut node.methods {
+            replace_dead_code(&node.name, method)?;
+        }
+        if major_version as u32 >= JavaVersions::V1_7 {
+            compute_stack_map_frames(&mut node, resolver)?;
+        }
+        Ok(node)
+    }
+}
+
+/// The jvm verifies class files of version 51 and above only by type checking,
+/// which needs the stack map frames.
+fn compute_stack_map_frames(node: &mut ClassNode, resolver: &dyn HierarchyResolver) -> AsmResult<()> {
+    let verifier = Verifier::new(resolver);
+    let mut stack_map_tables = Vec::with_capacity(node.methods.len());
+    for method in &node.methods {
+        let stack_map_table = verifier.compute_frames(node, method).map_err(|e| {
+            AsmErr::IllegalFormat(format!("cannot compute stack map frames of {e}"))
+        })?;
+        stack_map_tables.push(stack_map_table);
+    }
+    for (method, stack_map_table) in node.methods.iter_mut().zip(stack_map_tables) {
+        if let Some(code) = method.code_mut()? {
+            code.stack_map_table = stack_map_table;
+        }
+    }
+    Ok(())
+}
+
+/// Unreachable code can't be type checked, see [Verifier::compute_frames].
+fn replace_dead_code(owner: &str, method: &mut MethodNode) -> AsmResult<()> {
+    let frames = Analyzer::new(BasicInterpreter).analyze(owner, method).map_err(|e| {
+        AsmErr::IllegalFormat(format!("cannot analyze {owner}.{}{}: {e}", method.name, method.desc))
+    })?;
+    let Some(code) = method.code_mut()? else { return Ok(()) };
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.is_some() { continue; }
+        let dead_end = frames.get(index + 1).is_none_or(Option::is_some);
+        let opcode = if dead_end { Opcodes::ATHROW } else { Opcodes::NOP };
+        code.instructions[index] = InsnNode::NoOperand { opcode };
+        // the frame of the dead code holds the thrown value.
+        code.max_stack = code.max_stack.max(1);
+    }
+    Ok(())
+}
+
+fn convert_method_code(
+    accessor: &DexFileAccessor, code_off: u32, code_item: &CodeItem,
+    class_type: &DescriptorRef, desc: &DescriptorRef, is_static: bool,
+) -> AsmResult<code::ConvertedCode> {
+    let code = DexCode::new(accessor, code_off, code_item, class_type.clone(), desc.clone(), is_static)?;
+    let debug_info: Option<DebugInfoItem> = if code_item.debug_info_off != 0 {
+        accessor.get_data_impl(code_item.debug_info_off).ok()
+    } else {
+        None
+    };
+    convert_code(&code, debug_info.as_ref())
+}
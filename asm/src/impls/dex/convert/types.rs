@@ -0,0 +1,828 @@
+use crate::dex::insn::DexInsn;
+use crate::dex::insn_syntax::*;
+use crate::dex::DexFileAccessor;
+use crate::impls::dex::convert::code::DexCode;
+use crate::impls::{method_arg_types, method_return_type};
+use crate::{AsmResult, DescriptorRef, Opcodes, StrRef};
+
+/// Kind of a value which lives in a JVM local variable or on the operand stack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Int,
+    Float,
+    Long,
+    Double,
+    Ref,
+}
+
+impl Kind {
+    pub fn from_desc(desc: &str) -> Kind {
+        match desc.as_bytes().first() {
+            Some(b'J') => Kind::Long,
+            Some(b'D') => Kind::Double,
+            Some(b'F') => Kind::Float,
+            Some(b'L') | Some(b'[') => Kind::Ref,
+            _ => Kind::Int,
+        }
+    }
+
+    pub fn is_wide(self) -> bool {
+        matches!(self, Kind::Long | Kind::Double)
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Type of a register operand from the view of a single instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Ty {
+    Exact(Kind),
+    /// An int, float or reference, `move` or `const` can't tell.
+    Cat1,
+    /// A long or double, `move-wide` or `const-wide` can't tell.
+    Wide,
+}
+
+impl Ty {
+    pub fn is_wide(self) -> bool {
+        match self {
+            Ty::Exact(kind) => kind.is_wide(),
+            Ty::Cat1 => false,
+            Ty::Wide => true,
+        }
+    }
+
+    /// The kind used when nothing else decides it.
+    pub fn or_default(self) -> Kind {
+        match self {
+            Ty::Exact(kind) => kind,
+            Ty::Cat1 => Kind::Int,
+            Ty::Wide => Kind::Long,
+        }
+    }
+}
+
+/// Registers read and written by a dalvik instruction.
+#[derive(Default)]
+pub(crate) struct Operands {
+    pub uses: Vec<(u16, Ty)>,
+    pub def: Option<(u16, Ty)>,
+    /// The sign extended literal of `const*` instructions.
+    pub literal: Option<i64>,
+    /// The type descriptor of the defined reference, if known.
+    pub def_ref: Option<DescriptorRef>,
+    /// The array register of `aget`, `aput` and their wide & object variants, the element type
+    /// of this array decides the kind of the element value.
+    pub array: Option<u16>,
+    pub is_move: bool,
+}
+
+/// How a register value is stored in the JVM code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Res {
+    /// Stored in the local variable with the given kind.
+    Kind(Kind),
+    /// A constant which is used as different kinds, e.g. a `const/4 v0, 0` used as both
+    /// `null` and `0`. It is never stored, every usage pushes the constant again instead.
+    Const(i64),
+    /// No definition reaches the usage, only happens in broken code.
+    Unknown,
+}
+
+/// Jvm opcodes and operand kinds of binary operations, indexed by `opcode - 0x90`.
+pub(crate) fn binop(op: u8) -> (Kind, u8) {
+    const INT: [u8; 11] = [
+        Opcodes::IADD, Opcodes::ISUB, Opcodes::IMUL, Opcodes::IDIV, Opcodes::IREM, Opcodes::IAND,
+        Opcodes::IOR, Opcodes::IXOR, Opcodes::ISHL, Opcodes::ISHR, Opcodes::IUSHR,
+    ];
+    const LONG: [u8; 11] = [
+        Opcodes::LADD, Opcodes::LSUB, Opcodes::LMUL, Opcodes::LDIV, Opcodes::LREM, Opcodes::LAND,
+        Opcodes::LOR, Opcodes::LXOR, Opcodes::LSHL, Opcodes::LSHR, Opcodes::LUSHR,
+    ];
+    const FLOAT: [u8; 5] = [Opcodes::FADD, Opcodes::FSUB, Opcodes::FMUL, Opcodes::FDIV, Opcodes::FREM];
+    const DOUBLE: [u8; 5] = [Opcodes::DADD, Opcodes::DSUB, Opcodes::DMUL, Opcodes::DDIV, Opcodes::DREM];
+    let op = op as usize;
+    match op {
+        0..=10 => (Kind::Int, INT[op]),
+        11..=21 => (Kind::Long, LONG[op - 11]),
+        22..=26 => (Kind::Float, FLOAT[op - 22]),
+        _ => (Kind::Double, DOUBLE[(op - 27).min(4)]),
+    }
+}
+
+/// Whether the second operand of a binary operation is an int shift distance.
+pub(crate) fn is_shift(jvm_opcode: u8) -> bool {
+    matches!(jvm_opcode, Opcodes::LSHL | Opcodes::LSHR | Opcodes::LUSHR)
+}
+
+/// Source kind, result kind and jvm opcode of unary operations, indexed by `opcode - 0x7b`.
+/// `not-int` and `not-long` have no jvm counterpart and use [Opcodes::NOP] here.
+pub(crate) const UNOPS: [(Kind, Kind, u8); 21] = [
+    (Kind::Int, Kind::Int, Opcodes::INEG),
+    (Kind::Int, Kind::Int, Opcodes::NOP),
+    (Kind::Long, Kind::Long, Opcodes::LNEG),
+    (Kind::Long, Kind::Long, Opcodes::NOP),
+    (Kind::Float, Kind::Float, Opcodes::FNEG),
+    (Kind::Double, Kind::Double, Opcodes::DNEG),
+    (Kind::Int, Kind::Long, Opcodes::I2L),
+    (Kind::Int, Kind::Float, Opcodes::I2F),
+    (Kind::Int, Kind::Double, Opcodes::I2D),
+    (Kind::Long, Kind::Int, Opcodes::L2I),
+    (Kind::Long, Kind::Float, Opcodes::L2F),
+    (Kind::Long, Kind::Double, Opcodes::L2D),
+    (Kind::Float, Kind::Int, Opcodes::F2I),
+    (Kind::Float, Kind::Long, Opcodes::F2L),
+    (Kind::Float, Kind::Double, Opcodes::F2D),
+    (Kind::Double, Kind::Int, Opcodes::D2I),
+    (Kind::Double, Kind::Long, Opcodes::D2L),
+    (Kind::Double, Kind::Float, Opcodes::D2F),
+    (Kind::Int, Kind::Int, Opcodes::I2B),
+    (Kind::Int, Kind::Int, Opcodes::I2C),
+    (Kind::Int, Kind::Int, Opcodes::I2S),
+];
+
+/// Argument registers of `invoke*` and `filled-new-array*` instructions.
+pub(crate) fn invoke_regs(insn: &DexInsn) -> Vec<u16> {
+    let five = |a: u8, regs: [u8; 5]| regs[..(a as usize).min(5)].iter().map(|r| *r as u16).collect();
+    let range = |a: u8, first: u16| (0..a as u16).map(|i| first.wrapping_add(i)).collect();
+    match insn {
+        DexInsn::FilledNewArray(F35c { a, vC, vD, vE, vF, vG, .. }) |
+        DexInsn::InvokeKind(F35c { a, vC, vD, vE, vF, vG, .. }) |
+        DexInsn::InvokeCustom(F35c { a, vC, vD, vE, vF, vG, .. }) |
+        DexInsn::InvokePoly(F45cc { a, vC, vD, vE, vF, vG, .. }) => {
+            five(a.0, [vC.0, vD.0, vE.0, vF.0, vG.0])
+        }
+        DexInsn::FilledNewArrayRange(F3rc { a, vC, .. }) |
+        DexInsn::InvokeKindRange(F3rc { a, vC, .. }) |
+        DexInsn::InvokeCustomRange(F3rc { a, vC, .. }) |
+        DexInsn::InvokePolyRange(F4rcc { a, vC, .. }) => range(*a, *vC),
+        _ => vec![],
+    }
+}
+
+/// The receiver type and method descriptor of an invoke instruction, the receiver is
+/// [None] for static and custom invocations.
+pub(crate) fn invoke_desc(
+    accessor: &DexFileAccessor, insn: &DexInsn,
+) -> AsmResult<Option<(Option<DescriptorRef>, StrRef)>> {
+    let res = match insn {
+        DexInsn::InvokeKind(F35c { opcode, constB, .. }) |
+        DexInsn::InvokeKindRange(F3rc { opcode, constB, .. }) => {
+            let method = accessor.get_method(*constB)?;
+            let is_static = *opcode == 0x71 || *opcode == 0x77;
+            let receiver = if is_static { None } else { Some(method.class_type) };
+            (receiver, method.desc)
+        }
+        DexInsn::InvokePoly(F45cc { constB, constH, .. }) |
+        DexInsn::InvokePolyRange(F4rcc { constB, constH, .. }) => {
+            let method = accessor.get_method(*constB)?;
+            let proto = accessor.get_proto(*constH)?;
+            (Some(method.class_type), StrRef::from(proto.to_string()))
+        }
+        DexInsn::InvokeCustom(F35c { constB, .. }) |
+        DexInsn::InvokeCustomRange(F3rc { constB, .. }) => {
+            (None, call_site_desc(accessor, *constB)?)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(res))
+}
+
+pub(crate) fn call_site_desc(accessor: &DexFileAccessor, call_site_idx: u16) -> AsmResult<StrRef> {
+    let call_site = accessor.get_call_site(call_site_idx)?;
+    match call_site.values.get(2) {
+        Some(crate::dex::EncodedValue::MethodType(proto_idx)) => {
+            Ok(StrRef::from(accessor.get_proto(proto_idx.0 as usize)?.to_string()))
+        }
+        _ => Err(crate::AsmErr::IllegalFormat(
+            format!("call site {call_site_idx} doesn't contain a method type"),
+        )),
+    }
+}
+
+fn reg_ty_of_desc(desc: &str) -> Ty {
+    Ty::Exact(Kind::from_desc(desc))
+}
+
+fn ref_desc(desc: &str) -> Option<DescriptorRef> {
+    match Kind::from_desc(desc) {
+        Kind::Ref => Some(StrRef::from(desc)),
+        _ => None,
+    }
+}
+
+/// Registers read and written by the instruction at `index`.
+pub(crate) fn operands(code: &DexCode, index: usize) -> AsmResult<Operands> {
+    let accessor = code.accessor;
+    let insn = code.insns[index].1;
+    let mut ops = Operands::default();
+    let cat1 = |r: u16| (r, Ty::Cat1);
+    let wide = |r: u16| (r, Ty::Wide);
+    let exact = |r: u16, kind: Kind| (r, Ty::Exact(kind));
+    match insn {
+        DexInsn::Move(F12x { vA, vB, .. }) => {
+            ops.uses.push(cat1(vB.0 as u16));
+            ops.def = Some(cat1(vA.0 as u16));
+            ops.is_move = true;
+        }
+        DexInsn::MoveFrom16(F22x { vA, vB, .. }) => {
+            ops.uses.push(cat1(*vB));
+            ops.def = Some(cat1(*vA as u16));
+            ops.is_move = true;
+        }
+        DexInsn::Move16(F32x { vA, vB, .. }) => {
+            ops.uses.push(cat1(*vB));
+            ops.def = Some(cat1(*vA));
+            ops.is_move = true;
+        }
+        DexInsn::MoveWide(F12x { vA, vB, .. }) => {
+            ops.uses.push(wide(vB.0 as u16));
+            ops.def = Some(wide(vA.0 as u16));
+            ops.is_move = true;
+        }
+        DexInsn::MoveWideFrom16(F22x { vA, vB, .. }) => {
+            ops.uses.push(wide(*vB));
+            ops.def = Some(wide(*vA as u16));
+            ops.is_move = true;
+        }
+        DexInsn::MoveWide16(F32x { vA, vB, .. }) => {
+            ops.uses.push(wide(*vB));
+            ops.def = Some(wide(*vA));
+            ops.is_move = true;
+        }
+        DexInsn::MoveObject(F12x { vA, vB, .. }) => {
+            ops.uses.push(exact(vB.0 as u16, Kind::Ref));
+            ops.def = Some(exact(vA.0 as u16, Kind::Ref));
+            ops.is_move = true;
+        }
+        DexInsn::MoveObjectFrom16(F22x { vA, vB, .. }) => {
+            ops.uses.push(exact(*vB, Kind::Ref));
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.is_move = true;
+        }
+        DexInsn::MoveObject16(F32x { vA, vB, .. }) => {
+            ops.uses.push(exact(*vB, Kind::Ref));
+            ops.def = Some(exact(*vA, Kind::Ref));
+            ops.is_move = true;
+        }
+        DexInsn::MoveResult(F11x { vA, .. }) |
+        DexInsn::MoveResultWide(F11x { vA, .. }) |
+        DexInsn::MoveResultObject(F11x { vA, .. }) => {
+            let result = code.result_type(index)?;
+            ops.def = Some((*vA as u16, reg_ty_of_desc(&result)));
+            ops.def_ref = ref_desc(&result);
+        }
+        DexInsn::MoveException(F11x { vA, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(StrRef::from("Ljava/lang/Throwable;"));
+        }
+        DexInsn::Return(F11x { vA, .. }) |
+        DexInsn::ReturnWide(F11x { vA, .. }) |
+        DexInsn::ReturnObject(F11x { vA, .. }) => {
+            ops.uses.push((*vA as u16, reg_ty_of_desc(method_return_type(&code.desc))));
+        }
+        DexInsn::Const4(F11n { literalB, vA, .. }) => {
+            ops.def = Some(cat1(vA.0 as u16));
+            ops.literal = Some(literalB.0 as i64);
+        }
+        DexInsn::Const16(F21s { vA, literalB, .. }) => {
+            ops.def = Some(cat1(*vA as u16));
+            ops.literal = Some(*literalB as i64);
+        }
+        DexInsn::Const(F31i { vA, literalB, .. }) => {
+            ops.def = Some(cat1(*vA as u16));
+            ops.literal = Some(*literalB as i64);
+        }
+        DexInsn::ConstHigh16(F21h { vA, literalB, .. }) => {
+            ops.def = Some(cat1(*vA as u16));
+            ops.literal = Some(((*literalB as i32) << 16) as i64);
+        }
+        DexInsn::ConstWide16(F21s { vA, literalB, .. }) => {
+            ops.def = Some(wide(*vA as u16));
+            ops.literal = Some(*literalB as i64);
+        }
+        DexInsn::ConstWide32(F31i { vA, literalB, .. }) => {
+            ops.def = Some(wide(*vA as u16));
+            ops.literal = Some(*literalB as i64);
+        }
+        DexInsn::ConstWide(F51l { vA, literalB, .. }) => {
+            ops.def = Some(wide(*vA as u16));
+            ops.literal = Some(*literalB);
+        }
+        DexInsn::ConstWideHigh16(F21h { vA, literalB, .. }) => {
+            ops.def = Some(wide(*vA as u16));
+            ops.literal = Some((*literalB as i64) << 48);
+        }
+        DexInsn::ConstString(F21c { vA, .. }) |
+        DexInsn::ConstStringJumbo(F31c { vA, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(StrRef::from("Ljava/lang/String;"));
+        }
+        DexInsn::ConstClass(F21c { vA, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(StrRef::from("Ljava/lang/Class;"));
+        }
+        DexInsn::ConstMethodHandle(F21c { vA, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(StrRef::from("Ljava/lang/invoke/MethodHandle;"));
+        }
+        DexInsn::ConstMethodType(F21c { vA, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(StrRef::from("Ljava/lang/invoke/MethodType;"));
+        }
+        DexInsn::MonitorEnter(F11x { vA, .. }) |
+        DexInsn::MonitorExit(F11x { vA, .. }) |
+        DexInsn::Throw(F11x { vA, .. }) => {
+            ops.uses.push(exact(*vA as u16, Kind::Ref));
+        }
+        DexInsn::CheckCast(F21c { vA, constB, .. }) => {
+            ops.uses.push(exact(*vA as u16, Kind::Ref));
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(accessor.get_type(*constB)?);
+        }
+        DexInsn::InstanceOf(F22c { vA, vB, .. }) => {
+            ops.uses.push(exact(vB.0 as u16, Kind::Ref));
+            ops.def = Some(exact(vA.0 as u16, Kind::Int));
+        }
+        DexInsn::ArrayLength(F12x { vA, vB, .. }) => {
+            ops.uses.push(exact(vB.0 as u16, Kind::Ref));
+            ops.def = Some(exact(vA.0 as u16, Kind::Int));
+        }
+        DexInsn::NewInstance(F21c { vA, constB, .. }) => {
+            ops.def = Some(exact(*vA as u16, Kind::Ref));
+            ops.def_ref = Some(accessor.get_type(*constB)?);
+        }
+        DexInsn::NewArray(F22c { vA, vB, constC, .. }) => {
+            ops.uses.push(exact(vB.0 as u16, Kind::Int));
+            ops.def = Some(exact(vA.0 as u16, Kind::Ref));
+            ops.def_ref = Some(accessor.get_type(*constC)?);
+        }
+        DexInsn::FilledNewArray(F35c { constB, .. }) |
+        DexInsn::FilledNewArrayRange(F3rc { constB, .. }) => {
+            let array_type = accessor.get_type(*constB)?;
+            let element = Kind::from_desc(array_type.get(1..).unwrap_or_default());
+            for reg in invoke_regs(insn) {
+                ops.uses.push(exact(reg, element));
+            }
+        }
+        DexInsn::FillArrayData(F31t { vA, .. }) => {
+            ops.uses.push(exact(*vA as u16, Kind::Ref));
+        }
+        DexInsn::PackedSwitch(F31t { vA, .. }) |
+        DexInsn::SparseSwitch(F31t { vA, .. }) => {
+            ops.uses.push(exact(*vA as u16, Kind::Int));
+        }
+        DexInsn::Cmpkind(F23x { opcode, vA, vB, vC }) => {
+            let kind = match opcode {
+                0x2d | 0x2e => Kind::Float,
+                0x2f | 0x30 => Kind::Double,
+                _ => Kind::Long,
+            };
+            ops.uses.push(exact(*vB as u16, kind));
+            ops.uses.push(exact(*vC as u16, kind));
+            ops.def = Some(exact(*vA as u16, Kind::Int));
+        }
+        DexInsn::IfTest(F22t { opcode, vA, vB, .. }) => {
+            let ty = if *opcode <= 0x33 { Ty::Cat1 } else { Ty::Exact(Kind::Int) };
+            ops.uses.push((vA.0 as u16, ty));
+            ops.uses.push((vB.0 as u16, ty));
+        }
+        DexInsn::IfTestz(F21t { opcode, vA, .. }) => {
+            let ty = if *opcode <= 0x39 { Ty::Cat1 } else { Ty::Exact(Kind::Int) };
+            ops.uses.push((*vA as u16, ty));
+        }
+        DexInsn::ArrayOp(F23x { opcode, vA, vB, vC }) => {
+            let op = opcode.wrapping_sub(0x44);
+            let ty = match op % 7 {
+                0 => Ty::Cat1,
+                1 => Ty::Wide,
+                2 => Ty::Exact(Kind::Ref),
+                _ => Ty::Exact(Kind::Int),
+            };
+            ops.uses.push(exact(*vB as u16, Kind::Ref));
+            ops.uses.push(exact(*vC as u16, Kind::Int));
+            if op < 7 {
+                ops.def = Some((*vA as u16, ty));
+            } else {
+                ops.uses.push((*vA as u16, ty));
+            }
+            if op % 7 <= 2 {
+                ops.array = Some(*vB as u16);
+            }
+        }
+        DexInsn::IInstanceOp(F22c { opcode, vA, vB, constC }) => {
+            let field_type = accessor.get_field(*constC)?.field_type;
+            ops.uses.push(exact(vB.0 as u16, Kind::Ref));
+            if opcode.wrapping_sub(0x52) < 7 {
+                ops.def = Some((vA.0 as u16, reg_ty_of_desc(&field_type)));
+                ops.def_ref = ref_desc(&field_type);
+            } else {
+                ops.uses.push((vA.0 as u16, reg_ty_of_desc(&field_type)));
+            }
+        }
+        DexInsn::SStaticOp(F21c { opcode, vA, constB }) => {
+            let field_type = accessor.get_field(*constB)?.field_type;
+            if opcode.wrapping_sub(0x60) < 7 {
+                ops.def = Some((*vA as u16, reg_ty_of_desc(&field_type)));
+                ops.def_ref = ref_desc(&field_type);
+            } else {
+                ops.uses.push((*vA as u16, reg_ty_of_desc(&field_type)));
+            }
+        }
+        DexInsn::InvokeKind(_) | DexInsn::InvokeKindRange(_) |
+        DexInsn::InvokePoly(_) | DexInsn::InvokePolyRange(_) |
+        DexInsn::InvokeCustom(_) | DexInsn::InvokeCustomRange(_) => {
+            let regs = invoke_regs(insn);
+            let Some((receiver, desc)) = invoke_desc(accessor, insn)? else { return Ok(ops) };
+            let mut regs = regs.into_iter();
+            if receiver.is_some() && let Some(reg) = regs.next() {
+                ops.uses.push(exact(reg, Kind::Ref));
+            }
+            for arg in method_arg_types(&desc) {
+                let Some(reg) = regs.next() else { break };
+                let ty = reg_ty_of_desc(arg);
+                // the high half of a wide argument takes a register as well
+                if ty.is_wide() { regs.next(); }
+                ops.uses.push((reg, ty));
+            }
+        }
+        DexInsn::Unop(F12x { opcode, vA, vB }) => {
+            let (src, dst, _) = UNOPS[opcode.wrapping_sub(0x7b).min(20) as usize];
+            ops.uses.push(exact(vB.0 as u16, src));
+            ops.def = Some(exact(vA.0 as u16, dst));
+        }
+        DexInsn::Binop(F23x { opcode, vA, vB, vC }) => {
+            let (kind, jvm_opcode) = binop(opcode.wrapping_sub(0x90));
+            let second = if is_shift(jvm_opcode) { Kind::Int } else { kind };
+            ops.uses.push(exact(*vB as u16, kind));
+            ops.uses.push(exact(*vC as u16, second));
+            ops.def = Some(exact(*vA as u16, kind));
+        }
+        DexInsn::Binop2Addr(F12x { opcode, vA, vB }) => {
+            let (kind, jvm_opcode) = binop(opcode.wrapping_sub(0xb0));
+            let second = if is_shift(jvm_opcode) { Kind::Int } else { kind };
+            ops.uses.push(exact(vA.0 as u16, kind));
+            ops.uses.push(exact(vB.0 as u16, second));
+            ops.def = Some(exact(vA.0 as u16, kind));
+        }
+        DexInsn::BinopLit16(F22s { vA, vB, .. }) => {
+            ops.uses.push(exact(vB.0 as u16, Kind::Int));
+            ops.def = Some(exact(vA.0 as u16, Kind::Int));
+        }
+        DexInsn::BinopLit8(F22b { vA, vB, .. }) => {
+            ops.uses.push(exact(*vB as u16, Kind::Int));
+            ops.def = Some(exact(*vA as u16, Kind::Int));
+        }
+        _ => {}
+    }
+    Ok(ops)
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parents[x] != x {
+            self.parents[x] = self.parents[self.parents[x]];
+            x = self.parents[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(size: usize) -> Self {
+        BitSet { words: vec![0; size.div_ceil(64)] }
+    }
+
+    fn insert(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// `self |= other & !mask`, returns whether `self` changed.
+    fn union_without(&mut self, other: &BitSet, mask: Option<&BitSet>) -> bool {
+        let mut changed = false;
+        for (i, word) in self.words.iter_mut().enumerate() {
+            let mut add = other.words[i];
+            if let Some(mask) = mask { add &= !mask.words[i]; }
+            let new_word = *word | add;
+            changed |= new_word != *word;
+            *word = new_word;
+        }
+        changed
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, word)| {
+            (0..64).filter(move |bit| word & (1 << bit) != 0).map(move |bit| i * 64 + bit)
+        })
+    }
+}
+
+/// A register definition, made by an instruction or by the method entry for parameters.
+struct Def {
+    reg: u16,
+    wide: bool,
+    ty: Ty,
+    literal: Option<i64>,
+    is_move: bool,
+    ref_type: Option<DescriptorRef>,
+}
+
+#[derive(Default)]
+struct ClassInfo {
+    kinds: u8,
+    /// `Some(Some(literal))` if all definitions are the same constant or moves of it.
+    literal: Option<Option<i64>>,
+    has_const: bool,
+    ref_type: Option<DescriptorRef>,
+    wide: bool,
+    res: Option<Res>,
+}
+
+/// Decides how each dalvik register value is kept in jvm local variables.
+///
+/// Dalvik registers are untyped, but jvm instructions which load or store a local variable
+/// need to know the kind of the value. Definitions which may reach the same usage must share
+/// the kind, so they are grouped with the reaching definitions of each usage, then the kind of
+/// each group is decided by the instructions which define or use it.
+pub(crate) struct RegisterTypes {
+    pub ops: Vec<Operands>,
+    /// the definition group of each usage, in the same order of [Operands::uses]
+    use_groups: Vec<Vec<Option<usize>>>,
+    def_groups: Vec<Option<usize>>,
+    groups: Vec<ClassInfo>,
+}
+
+impl RegisterTypes {
+    pub fn use_res(&self, index: usize, reg: u16) -> Res {
+        self.use_group(index, reg)
+            .and_then(|group| self.groups[group].res)
+            .unwrap_or(Res::Unknown)
+    }
+
+    pub fn use_ref(&self, index: usize, reg: u16) -> Option<&DescriptorRef> {
+        self.use_group(index, reg).and_then(|group| self.groups[group].ref_type.as_ref())
+    }
+
+    pub fn def_res(&self, index: usize) -> Res {
+        self.def_groups[index]
+            .and_then(|group| self.groups[group].res)
+            .unwrap_or(Res::Unknown)
+    }
+
+    /// The kind of the definition at `index` if its usages agree on exactly one kind.
+    pub fn def_kind(&self, index: usize) -> Option<Kind> {
+        let info = &self.groups[self.def_groups[index]?];
+        if info.kinds.count_ones() != 1 { return None; }
+        [Kind::Int, Kind::Float, Kind::Long, Kind::Double, Kind::Ref].into_iter()
+            .find(|kind| info.kinds & kind.bit() != 0)
+    }
+
+    fn use_group(&self, index: usize, reg: u16) -> Option<usize> {
+        let position = self.ops[index].uses.iter().position(|(r, _)| *r == reg)?;
+        self.use_groups[index][position]
+    }
+
+    pub fn analyze(code: &DexCode) -> AsmResult<RegisterTypes> {
+        let insn_count = code.insns.len();
+        let mut ops = Vec::with_capacity(insn_count);
+        for index in 0..insn_count {
+            ops.push(operands(code, index)?);
+        }
+
+        // parameters are defined at the method entry, then definitions of all instructions.
+        let mut defs = Vec::new();
+        for (reg, desc) in code.param_regs() {
+            let ty = reg_ty_of_desc(&desc);
+            let ref_type = ref_desc(&desc);
+            defs.push(Def { reg, wide: ty.is_wide(), ty, literal: None, is_move: false, ref_type });
+        }
+        let param_count = defs.len();
+        let mut def_of_insn = vec![None; insn_count];
+        for (index, op) in ops.iter().enumerate() {
+            if let Some((reg, ty)) = op.def {
+                def_of_insn[index] = Some(defs.len());
+                defs.push(Def {
+                    reg, wide: ty.is_wide(), ty, literal: op.literal,
+                    is_move: op.is_move, ref_type: op.def_ref.clone(),
+                });
+            }
+        }
+
+        // each definition writes one slot, or two slots for wide values.
+        // item = def * 2 + half, half 1 is the high half of a wide value.
+        let slot_count = code.registers as usize + 2;
+        let item_count = defs.len() * 2;
+        let mut items_of_slot: Vec<Vec<usize>> = vec![vec![]; slot_count];
+        for (d, def) in defs.iter().enumerate() {
+            let reg = def.reg as usize;
+            if reg < slot_count { items_of_slot[reg].push(d * 2); }
+            if def.wide && reg + 1 < slot_count { items_of_slot[reg + 1].push(d * 2 + 1); }
+        }
+        let def_items = |d: usize| {
+            let def = &defs[d];
+            let reg = def.reg as usize;
+            let mut items = vec![];
+            if reg < slot_count { items.push((reg, d * 2)); }
+            if def.wide && reg + 1 < slot_count { items.push((reg + 1, d * 2 + 1)); }
+            items
+        };
+
+        let blocks = code.blocks();
+        let block_count = blocks.ranges.len();
+        let mut gens = Vec::with_capacity(block_count);
+        let mut kills = Vec::with_capacity(block_count);
+        for &(start, end) in &blocks.ranges {
+            let mut last: Vec<Option<usize>> = vec![None; slot_count];
+            let mut kill = BitSet::new(item_count);
+            for d in def_of_insn[start..end].iter().flatten().copied() {
+                for (slot, item) in def_items(d) {
+                    last[slot] = Some(item);
+                    for other in &items_of_slot[slot] { kill.insert(*other); }
+                }
+            }
+            let mut generated = BitSet::new(item_count);
+            for item in last.into_iter().flatten() { generated.insert(item); }
+            gens.push(generated);
+            kills.push(kill);
+        }
+
+        let mut ins = vec![BitSet::new(item_count); block_count];
+        let mut outs = vec![BitSet::new(item_count); block_count];
+        if block_count > 0 {
+            for d in 0..param_count {
+                for (_, item) in def_items(d) { ins[0].insert(item); }
+            }
+        }
+        let mut worklist: Vec<usize> = (0..block_count).rev().collect();
+        let mut queued = vec![true; block_count];
+        while let Some(block) = worklist.pop() {
+            queued[block] = false;
+            let mut out = gens[block].clone();
+            out.union_without(&ins[block], Some(&kills[block]));
+            outs[block] = out;
+            let normal = blocks.succs[block].iter().map(|s| (*s, false));
+            let exceptional = blocks.handler_succs[block].iter().map(|s| (*s, true));
+            for (succ, is_exception) in normal.chain(exceptional).collect::<Vec<_>>() {
+                // a throwing instruction doesn't write its destination register
+                let from = if is_exception { ins[block].clone() } else { outs[block].clone() };
+                if ins[succ].union_without(&from, None) && !queued[succ] {
+                    queued[succ] = true;
+                    worklist.push(succ);
+                }
+            }
+        }
+
+        let mut uf = UnionFind { parents: (0..defs.len()).collect() };
+        let mut use_defs: Vec<Vec<Option<usize>>> = Vec::with_capacity(insn_count);
+        use_defs.resize_with(insn_count, Vec::new);
+        for (block, &(start, end)) in blocks.ranges.iter().enumerate() {
+            let mut state: Vec<Vec<usize>> = vec![vec![]; slot_count];
+            for item in ins[block].iter() {
+                let d = item / 2;
+                let slot = defs[d].reg as usize + item % 2;
+                state[slot].push(item);
+            }
+            for index in start..end {
+                let op = &ops[index];
+                let mut linked = Vec::with_capacity(op.uses.len());
+                for (reg, _) in &op.uses {
+                    let reaching: Vec<usize> = state.get(*reg as usize).into_iter().flatten()
+                        .filter(|item| *item % 2 == 0)
+                        .map(|item| item / 2)
+                        .collect();
+                    if let Some(first) = reaching.first() {
+                        for other in &reaching[1..] { uf.union(*first, *other); }
+                    }
+                    linked.push(reaching.first().copied());
+                }
+                if let Some(d) = def_of_insn[index] {
+                    if op.is_move && let Some(Some(src)) = linked.first() { uf.union(d, *src); }
+                    for (slot, item) in def_items(d) {
+                        state[slot] = vec![item];
+                    }
+                }
+                use_defs[index] = linked;
+            }
+        }
+
+        // collect constraints of each group
+        let mut group_of_root = vec![usize::MAX; defs.len()];
+        let mut groups: Vec<ClassInfo> = vec![];
+        let mut group_of = |uf: &mut UnionFind, d: usize, groups: &mut Vec<ClassInfo>| {
+            let root = uf.find(d);
+            if group_of_root[root] == usize::MAX {
+                group_of_root[root] = groups.len();
+                groups.push(ClassInfo::default());
+            }
+            group_of_root[root]
+        };
+        let mut def_groups = vec![None; insn_count];
+        for (d, def) in defs.iter().enumerate() {
+            let group = group_of(&mut uf, d, &mut groups);
+            let info = &mut groups[group];
+            info.add_ty(def.ty);
+            info.literal = match (info.literal, def.literal, def.is_move) {
+                (_, _, true) => info.literal,
+                (None, Some(literal), _) => Some(Some(literal)),
+                (Some(Some(old)), Some(literal), _) if old == literal => Some(Some(old)),
+                _ => Some(None),
+            };
+            info.has_const |= def.literal.is_some();
+            if info.ref_type.is_none() { info.ref_type = def.ref_type.clone(); }
+        }
+        for (index, d) in def_of_insn.iter().enumerate() {
+            if let Some(d) = d { def_groups[index] = Some(group_of(&mut uf, *d, &mut groups)); }
+        }
+        let mut use_groups = Vec::with_capacity(insn_count);
+        for (index, linked) in use_defs.iter().enumerate() {
+            let mut use_group = Vec::with_capacity(linked.len());
+            for (position, d) in linked.iter().enumerate() {
+                let group = d.map(|d| group_of(&mut uf, d, &mut groups));
+                if let Some(group) = group {
+                    groups[group].add_ty(ops[index].uses[position].1);
+                }
+                use_group.push(group);
+            }
+            use_groups.push(use_group);
+        }
+
+        let mut types = RegisterTypes { ops, use_groups, def_groups, groups };
+        types.apply_array_types();
+        for info in &mut types.groups {
+            info.res = Some(info.resolve());
+        }
+        Ok(types)
+    }
+
+    /// Plain `aget` and `aput` don't tell int from float, and `aget-wide` doesn't tell long
+    /// from double, the array type decides it if nothing else does.
+    fn apply_array_types(&mut self) {
+        loop {
+            let mut changed = false;
+            for index in 0..self.ops.len() {
+                let Some(array_reg) = self.ops[index].array else { continue };
+                let Some(array_group) = self.use_group(index, array_reg) else { continue };
+                let Some(array_type) = self.groups[array_group].ref_type.clone() else { continue };
+                let Some(element) = array_type.strip_prefix('[') else { continue };
+                // the value of `aput` is the third usage, after the array and the index
+                let value_group = match &self.ops[index].def {
+                    Some(_) => self.def_groups[index],
+                    None => self.use_groups[index].get(2).copied().flatten(),
+                };
+                let Some(value_group) = value_group else { continue };
+                let info = &mut self.groups[value_group];
+                let kind = Kind::from_desc(element);
+                if info.kinds == 0 {
+                    info.kinds |= kind.bit();
+                    changed = true;
+                }
+                if kind == Kind::Ref && info.ref_type.is_none() {
+                    info.ref_type = Some(StrRef::from(element));
+                    changed = true;
+                }
+            }
+            if !changed { break; }
+        }
+    }
+}
+
+impl ClassInfo {
+    fn add_ty(&mut self, ty: Ty) {
+        match ty {
+            Ty::Exact(kind) => self.kinds |= kind.bit(),
+            Ty::Cat1 => {}
+            Ty::Wide => self.wide = true,
+        }
+    }
+
+    fn resolve(&self) -> Res {
+        const PRIORITY: [Kind; 5] = [Kind::Ref, Kind::Double, Kind::Long, Kind::Float, Kind::Int];
+        let count = self.kinds.count_ones();
+        if count == 0 {
+            return Res::Kind(if self.wide { Kind::Long } else { Kind::Int });
+        }
+        if count > 1 && self.has_const && let Some(Some(literal)) = self.literal {
+            return Res::Const(literal);
+        }
+        let kind = PRIORITY.into_iter().find(|kind| self.kinds & kind.bit() != 0);
+        Res::Kind(kind.unwrap_or(Kind::Int))
+    }
+}
@@ -1 +1,2 @@
 pub(crate) mod r;
+pub(crate) mod convert;
@@ -1,4 +1,4 @@
-use crate::dex::{CallSiteId, CallSiteItem, DUInt, DUShort, FieldId, Header, MapList, MethodId, ProtoId, StringData, TypeList};
+use crate::dex::{CallSiteId, CallSiteItem, DUInt, FieldId, Header, MapList, MethodId, ProtoId, StringData, TypeList};
 use crate::dex::{DexFileAccessor, MethodHandle};
 use crate::err::{AsmResultExt, AsmResultOkExt};
 use crate::impls::jvms::r::{ReadContext, ReadFrom, U32BasedSize};
@@ -116,10 +116,11 @@ impl DexFileAccessor {
     }
 
     #[inline]
-    pub fn get_field(&self, field_idx: DUShort) -> AsmResult<FieldConst> {
+    pub fn get_field(&self, field_idx: impl Into<usize>) -> AsmResult<FieldConst> {
         let dex_file = &self.file;
-        let field_id = dex_file.field_ids.get(field_idx as usize)
-            .ok_or_error(|| AsmErr::OutOfRange(field_idx as usize).e())?;
+        let field_idx = field_idx.into();
+        let field_id = dex_file.field_ids.get(field_idx)
+            .ok_or_error(|| AsmErr::OutOfRange(field_idx).e())?;
         let FieldId { class_idx, type_idx, name_idx, .. } = *field_id;
         let class_type = self.get_type(class_idx)?;
         let field_type = self.get_type(type_idx)?;
@@ -1,4 +1,5 @@
-use crate::dex::{CodeItem, DSleb128, DUByte, DUInt, DULeb128, DULeb128P1, DebugInfoItem, DexFile, EncodedCatchHandler, EncodedValue, EncodedValueType, Header, InsnContainer, LocalVar, StringData};
+use crate::dex::insn::FillArrayDataPayload;
+use crate::dex::{CodeItem, DSleb128, DUByte, DUInt, DUShort, DULeb128, DULeb128P1, DebugInfoItem, DexFile, EncodedCatchHandler, EncodedValue, EncodedValueType, Header, InsnContainer, LocalVar, StringData};
 use crate::err::AsmResultOkExt;
 use crate::impls::jvms::r::*;
 use crate::{mutf8_to_string, AsmErr, AsmResult};
@@ -48,6 +49,21 @@ impl ReadFrom for InsnContainer {
     }
 }
 
+impl ReadFrom for FillArrayDataPayload {
+    fn read_from(context: &mut ReadContext) -> AsmResult<Self> {
+        let ident = context.read()?;
+        let element_width: DUShort = context.read()?;
+        let size: U32BasedSize = context.read()?;
+        let data_len = element_width as usize * size.0 as usize;
+        let data = context.read_vec(data_len)?;
+        // the whole payload is made of 16-bit code units
+        if data_len % 2 == 1 {
+            context.index += 1;
+        }
+        FillArrayDataPayload { ident, element_width, size, data }.ok()
+    }
+}
+
 impl DebugInfoItem {
     const DBG_END_SEQUENCE: u8 = 0x00;
     const DBG_ADVANCE_PC: u8 = 0x01;
@@ -157,7 +173,8 @@ impl ReadFrom for EncodedCatchHandler {
         let size_value = size.value();
         let handler_size = size_value.abs() as usize;
         let handlers = context.read_vec(handler_size)?;
-        let catch_all_addr = if size_value < 0 {
+        // non-positive size means there is a catch-all handler
+        let catch_all_addr = if size_value <= 0 {
             Some(context.read()?)
         } else {
             None
@@ -186,8 +203,9 @@ impl DexFile {
 impl ReadFrom for EncodedValue {
     fn read_from(context: &mut ReadContext) -> AsmResult<Self> {
         let header_byte: u8 = context.read()?;
-        let value_arg = header_byte & 0x1F;
-        let value_type = header_byte & 0xE0;
+        // the high 3 bits are `value_arg` and the low 5 bits are `value_type`.
+        let value_arg = header_byte >> 5;
+        let value_type = header_byte & 0x1F;
         let encoded_value = match value_type {
             EncodedValueType::VALUE_BYTE =>
                 EncodedValue::Byte(context.read()?),
@@ -327,44 +345,22 @@ fn read_i64(context: &mut ReadContext, value_arg: u8) -> AsmResult<i64> {
     Ok(value)
 }
 
+// float & double are zero-extended to the right, which means the omitted bytes
+// are the low-order bytes of the little-endian bit pattern.
 fn read_f32(context: &mut ReadContext, value_arg: u8) -> AsmResult<[DUByte; 4]> {
     let mut res = [0u8; 4];
-    res[0] = context.read()?;
-    if value_arg > 0 {
-        res[1] = context.read()?;
-    }
-    if value_arg > 1 {
-        res[2] = context.read()?;
-    }
-    if value_arg > 2 {
-        res[3] = context.read()?;
+    let size = (value_arg as usize + 1).min(4);
+    for byte in res[4 - size..].iter_mut() {
+        *byte = context.read()?;
     }
     Ok(res)
 }
 
 fn read_f64(context: &mut ReadContext, value_arg: u8) -> AsmResult<[DUByte; 8]> {
     let mut res = [0u8; 8];
-    res[0] = context.read()?;
-    if value_arg > 0 {
-        res[1] = context.read()?;
-    }
-    if value_arg > 1 {
-        res[2] = context.read()?;
-    }
-    if value_arg > 2 {
-        res[3] = context.read()?;
-    }
-    if value_arg > 3 {
-        res[4] = context.read()?;
-    }
-    if value_arg > 4 {
-        res[5] = context.read()?;
-    }
-    if value_arg > 5 {
-        res[6] = context.read()?;
-    }
-    if value_arg > 6 {
-        res[7] = context.read()?;
+    let size = (value_arg as usize + 1).min(8);
+    for byte in res[8 - size..].iter_mut() {
+        *byte = context.read()?;
     }
     Ok(res)
 }
@@ -1,12 +1,15 @@
 #![allow(non_snake_case)]
 
+use crate::bootstrap::BootstrapCall;
 use crate::dex::element::{ClassContentElement, FieldElement, MethodElement};
 use crate::dex::insn::{DexInsn, FillArrayDataPayload, PackedSwitchPayload, SparseSwitchPayload};
 use crate::dex::insn_syntax::*;
 use crate::dex::{ClassAccessFlags, ClassDef, CodeItem, DebugInfoItem, DexFileAccessor, EncodedAnnotation, EncodedAnnotationAttribute, EncodedArray, EncodedValue, FieldAccessFlags, InsnContainer, MethodAccessFlags, MethodHandle, MethodHandleType, NO_INDEX, U4};
+use crate::impls::dex::convert::code::call_site;
 use crate::impls::dex::r::element::DebugInfoMap;
 use crate::impls::ToStringRef;
 use crate::smali::{stb, tokens_to_raw, Dex2Smali, SmaliNode};
+use crate::smap::SourceMap;
 use crate::{raw_smali, AsmResult, ConstContainer, DescriptorRef, StrRef};
 use std::collections::HashMap;
 
@@ -503,9 +506,14 @@ fn render_proto(accessor: &DexFileAccessor, proto_idx: u16) -> DescriptorRef {
 }
 
 fn render_call_site(accessor: &DexFileAccessor, call_site_idx: u16) -> SmaliNode {
-    accessor.get_call_site(call_site_idx)
+    let mut smali = accessor.get_call_site(call_site_idx)
         .map(|cs| cs.to_smali(accessor))
-        .unwrap_or_else(|_| raw_smali!("call_site@{}", call_site_idx))
+        .unwrap_or_else(|_| raw_smali!("call_site@{}", call_site_idx));
+    let call = call_site(accessor, call_site_idx).ok().and_then(|cs| BootstrapCall::resolve(&cs));
+    if let Some(call) = call {
+        smali.children.insert(0, call.to_smali());
+    }
+    smali
 }
 
 fn render_method_handle(accessor: &DexFileAccessor, method_handle_idx: u16) -> SmaliNode {
@@ -622,8 +630,8 @@ impl Dex2Smali for EncodedValue {
             EncodedValue::Char(v) => tb.l(v.to_ref()).s(),
             EncodedValue::Int(v) => tb.l(v.to_ref()).s(),
             EncodedValue::Long(v) => tb.l(v.to_ref()).s(),
-            EncodedValue::Float(v) => tb.l(f32::from_be_bytes(*v).to_ref()).s(),
-            EncodedValue::Double(v) => tb.l(f64::from_be_bytes(*v).to_ref()).s(),
+            EncodedValue::Float(v) => tb.l(f32::from_le_bytes(*v).to_ref()).s(),
+            EncodedValue::Double(v) => tb.l(f64::from_le_bytes(*v).to_ref()).s(),
             EncodedValue::MethodType(v) => tb.l(render_proto(dex_file_accessor, v.0 as u16)).s(),
             EncodedValue::MethodHandle(v) => render_method_handle(dex_file_accessor, v.0 as u16),
             EncodedValue::String(v) => tb.l(dex_file_accessor.opt_str(*v)).s(),
@@ -736,6 +744,11 @@ impl ClassDef {
             // transparent for children, no more level
             smali.children.extend(class_element.to_smali(accessor)?.children);
         };
+        // lines of inlined kotlin functions are mapped to their own files
+        if let Ok(Some(source_debug)) = self.source_debug(accessor)
+            && let Ok(source_map) = SourceMap::parse(&source_debug) {
+            source_map.apply_to_smali(&mut smali);
+        }
         Ok(smali)
     }
 }
@@ -46,13 +46,15 @@ fn cp_infos_from_context(context: &mut ReadContext, max_len: usize) -> AsmResult
     while max_len > 0 {
         let tag: u8 = context.read()?;
         let info: Const = Const::from_context(context, tag)?;
+        result.push(CPInfo { tag, info });
         match tag {
             Constants::CONSTANT_Long | Constants::CONSTANT_Double => {
-                max_len -= 2;
+                // keep the unusable slot so that the vec index is the same as the cp index.
+                result.push(CPInfo { tag: Constants::CONSTANT_Invalid, info: Const::Invalid });
+                max_len = max_len.saturating_sub(2);
             },
             _ => { max_len -= 1; }
         }
-        result.push(CPInfo { tag, info });
     };
     Ok(result)
 }
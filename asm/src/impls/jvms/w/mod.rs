@@ -1,3 +1,5 @@
+use crate::jvms::element::{CPInfo, Const};
+
 pub struct WriteContext {
     pub bytes: Vec<u8>,
 }
@@ -51,3 +53,10 @@ impl WriteInto for u8 {
     }
 }
 
+impl WriteInto for CPInfo {
+    fn write_into(context: &mut WriteContext, from: CPInfo) {
+        if let Const::Invalid = from.info { return; }
+        context.write(from.tag);
+        context.write(from.info);
+    }
+}
@@ -1 +1,2 @@
-pub(crate) mod r;
\ No newline at end of file
+pub(crate) mod r;
+pub(crate) mod w;
@@ -9,6 +9,7 @@ use crate::node::element::{AnnotationNode, BootstrapMethodAttr, CodeAttribute, E
 use crate::node::element::Attribute as NodeAttribute;
 use crate::node::values::{AnnotationValue, LocalVariableInfo, LocalVariableTypeInfo, ModuleAttrValue, ModuleExportValue, ModuleOpenValue, ModuleProvidesValue, ModuleRequireValue};
 use crate::impls::{mutf8_to_string, VecEx};
+use crate::StrRef;
 
 impl ClassNodeContext {
     pub fn read_class_attrs(&self) -> AsmResult<Vec<(AttributeInfo, NodeAttribute)>> {
@@ -70,7 +71,12 @@ impl ConstPool {
                 let classes = classes.map_res(|inner_class| {
                     let name = self.read_class_info(inner_class.inner_class_info_index)?;
                     let outer_name = self.read_class_info(inner_class.outer_class_info_index).ok();
-                    let inner_name = self.read_utf8(inner_class.inner_name_index)?;
+                    // 0 for anonymous classes
+                    let inner_name = if inner_class.inner_name_index == 0 {
+                        StrRef::from("")
+                    } else {
+                        self.read_utf8(inner_class.inner_name_index)?
+                    };
                     let access = inner_class.inner_class_access_flags;
                     Ok(InnerClassNode { name, outer_name, inner_name, access })
                 })?;
@@ -78,7 +84,12 @@ impl ConstPool {
             },
             JvmsAttribute::EnclosingMethod { class_index, method_index } => {
                 let class = self.read_class_info(*class_index)?;
-                let (method_name, method_desc) = self.read_name_and_type(*method_index)?;
+                let (method_name, method_desc) = if *method_index == 0 {
+                    (None, None)
+                } else {
+                    let (name, desc) = self.read_name_and_type(*method_index)?;
+                    (Some(name), Some(desc))
+                };
                 NodeAttribute::EnclosingMethod(
                     EnclosingMethodAttribute { class, method_name, method_desc })
             },
@@ -274,7 +285,8 @@ impl ConstPool {
     }
 
     fn read_annotation_info(&self, visible: bool, annotation: &AnnotationInfo) -> AsmResult<AnnotationNode> {
-        let type_name = self.read_class_info(annotation.type_index)?;
+        // type_index refers to a CONSTANT_Utf8_info which holds a field descriptor.
+        let type_name = self.read_utf8(annotation.type_index)?;
         let values = annotation.element_value_pairs.map_res(|pair| {
             let element_name = self.read_utf8(pair.element_name_index)?;
             let value = self.read_annotation_value(visible, &pair.value.value)?;
@@ -1,62 +1,147 @@
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::OnceLock;
 
 use crate::err::{AsmErr, AsmResult, AsmResultExt};
 use crate::impls::{once_vec_builder, once_vec_unpack};
-use crate::impls::node::r::node_reader::ClassNodeContext;
+use crate::impls::node::r::node_reader::{ClassNodeContext, CodeLocation};
 use crate::impls::OnceAsmVec;
 use crate::impls::VecEx;
-use crate::jvms::attr::StackMapFrame;
-use crate::node::element::{Attribute, CodeAttribute, CodeBodyNode, LocalVariableNode, TypeAnnotationNode};
+use crate::jvms::attr::{StackMapFrame, VerificationTypeInfo};
+use crate::node::element::{Attribute, CodeAttribute, CodeBodyNode, LabelNode, LazyCodeBody, LineNumberNode, LocalVariableNode, MethodNode};
 use crate::node::InsnNode;
-use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, LocalVariableInfo, LocalVariableTypeInfo};
+use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, FrameAttributeValue, FrameValue, LocalVariableInfo, LocalVariableTypeInfo};
+use crate::visitor::{CodeBodyBuilder, MethodVisitor, ReadFlags};
 use crate::Opcodes;
 
 impl ClassNodeContext {
-    pub fn read_code_body(&self, code_attr: CodeAttribute) -> AsmResult<CodeBodyNode> {
+    pub fn read_code_attr(&self, location: &CodeLocation) -> AsmResult<CodeAttribute> {
+        let CodeLocation { method_index, attribute_index, .. } = location;
+        let attribute_info = self.jvms_file.methods.get(*method_index)
+            .and_then(|method| method.attributes.get(*attribute_index))
+            .ok_or_else(|| self.err(format!("no code attribute at method {method_index}")))?;
+        match self.read_attr(attribute_info)? {
+            Attribute::Code(code_attr) => Ok(code_attr),
+            attr => Err(self.err(format!("expect a code attribute, but found: {attr:?}"))),
+        }
+    }
+
+    pub fn read_code_body(&self, location: &CodeLocation) -> AsmResult<CodeBodyNode> {
+        let code_attr = self.read_code_attr(location)?;
+        let mut builder = CodeBodyBuilder::default();
+        self.accept_code_body(code_attr, &mut builder, 0)?;
+        Ok(builder.into_code())
+    }
+
+    /// Fires the code events of a method into `visitor`, `flags` is a combination of [ReadFlags].
+    pub fn accept_code_body(
+        &self, code_attr: CodeAttribute, visitor: &mut dyn MethodVisitor, flags: u32,
+    ) -> AsmResult<()> {
         let CodeAttribute { max_stack, max_locals, code, exception_table, attributes } = code_attr;
-        let instructions = self.read_code(code)?;
+        let skip_debug = flags & ReadFlags::SKIP_DEBUG != 0;
+        let (instructions, insn_offsets) = self.read_code(code)?;
+
+        visitor.visit_code(max_stack, max_locals);
+        for (insn, label) in instructions.into_iter().zip(insn_offsets) {
+            visitor.visit_insn(label, insn);
+        }
+        exception_table.into_iter().for_each(|e| visitor.visit_try_catch(e));
 
         once_vec_builder! {
             let local_variable_infos: LocalVariableInfo;
             let local_variable_type_infos: LocalVariableTypeInfo;
-            let type_annotations: TypeAnnotationNode;
-            let stack_map_table: StackMapFrame;
         }
 
+        // multiple LineNumberTable attributes may appear in a Code attribute.
+        let mut line_numbers: Vec<LineNumberNode> = vec![];
+        let mut type_annotations = vec![];
+        let mut stack_map_table = vec![];
         let mut unknown_attributes = vec![];
 
         for (attr_info, attr) in attributes {
             match attr {
                 Attribute::LocalVariableTable(lv) => local_variable_infos.put(lv)?,
                 Attribute::LocalVariableTypeTable(lv) => local_variable_type_infos.put(lv)?,
-                Attribute::RuntimeInvisibleTypeAnnotations(ta) => type_annotations.put(ta)?,
-                Attribute::RuntimeVisibleTypeAnnotations(ta) => type_annotations.put(ta)?,
-                Attribute::StackMapTable(table) => stack_map_table.put(table)?,
+                Attribute::RuntimeInvisibleTypeAnnotations(ta) => type_annotations.extend(ta),
+                Attribute::RuntimeVisibleTypeAnnotations(ta) => type_annotations.extend(ta),
+                Attribute::StackMapTable(table) => stack_map_table.extend(table),
+                Attribute::LineNumberTable(lines) => line_numbers.extend(lines),
                 Attribute::Unknown(a) => unknown_attributes.push(a),
                 _ => unknown_attributes.push(self.unknown_attr(attr_info)?),
             }
         }
 
-        once_vec_unpack!(local_variable_infos, local_variable_type_infos, type_annotations, stack_map_table);
+        once_vec_unpack!(local_variable_infos, local_variable_type_infos);
+
+        if !skip_debug {
+            merge_local_variables(local_variable_infos, local_variable_type_infos)
+                .into_iter().for_each(|lv| visitor.visit_local_variable(lv));
+            line_numbers.into_iter().for_each(|ln| visitor.visit_line_number(ln));
+        }
+        type_annotations.into_iter().for_each(|ta| visitor.visit_code_type_annotation(ta));
+        if flags & ReadFlags::SKIP_FRAMES == 0 {
+            for frame in &stack_map_table {
+                visitor.visit_frame(self.read_frame(frame)?);
+            }
+        }
+        unknown_attributes.into_iter().for_each(|a| visitor.visit_code_attribute(a));
+        Ok(())
+    }
 
-        let local_variables = merge_local_variables(
-            local_variable_infos, local_variable_type_infos,
-        );
+    fn read_frame(&self, frame: &StackMapFrame) -> AsmResult<FrameAttributeValue> {
+        let frame = match frame {
+            StackMapFrame::SameFrame { frame_type } => FrameAttributeValue::SameFrame {
+                offset_delta: *frame_type,
+            },
+            StackMapFrame::SameFrameExtended { offset_delta, .. } => FrameAttributeValue::SameFrameExtended {
+                offset_delta: *offset_delta,
+            },
+            StackMapFrame::SameLocals1StackItemFrame { frame_type, verification_type_info } => {
+                FrameAttributeValue::SameLocals1StackItemFrame {
+                    offset_delta: frame_type - 64,
+                    stack: self.read_frame_value(verification_type_info)?,
+                }
+            }
+            StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, verification_type_info, .. } => {
+                FrameAttributeValue::SameLocals1StackItemFrameExtended {
+                    offset_delta: *offset_delta,
+                    stack: self.read_frame_value(verification_type_info)?,
+                }
+            }
+            StackMapFrame::ChopFrame { frame_type, offset_delta } => FrameAttributeValue::ChopFrame {
+                chop_count: 251 - frame_type,
+                offset_delta: *offset_delta,
+            },
+            StackMapFrame::AppendFrame { offset_delta, locals, .. } => FrameAttributeValue::AppendFrame {
+                offset_delta: *offset_delta,
+                append_locals: locals.map_res(|v| self.read_frame_value(v))?,
+            },
+            StackMapFrame::FullFrame { offset_delta, locals, stack, .. } => FrameAttributeValue::FullFrame {
+                offset_delta: *offset_delta,
+                locals: locals.map_res(|v| self.read_frame_value(v))?,
+                stack: stack.map_res(|v| self.read_frame_value(v))?,
+            },
+        };
+        Ok(frame)
+    }
 
-        Ok(CodeBodyNode {
-            instructions,
-            exception_table,
-            local_variables,
-            max_stack,
-            max_locals,
-            type_annotations,
-            stack_map_table,
-            unknown_attributes,
-        })
+    fn read_frame_value(&self, value: &VerificationTypeInfo) -> AsmResult<FrameValue> {
+        let value = match value {
+            VerificationTypeInfo::Top { .. } => FrameValue::Top,
+            VerificationTypeInfo::Integer { .. } => FrameValue::Integer,
+            VerificationTypeInfo::Float { .. } => FrameValue::Float,
+            VerificationTypeInfo::Null { .. } => FrameValue::Null,
+            VerificationTypeInfo::UninitializedThis { .. } => FrameValue::UninitializedThis,
+            VerificationTypeInfo::Object { cpool_index, .. } => FrameValue::Object(self.read_class_info(*cpool_index)?),
+            VerificationTypeInfo::Uninitialized { offset, .. } => FrameValue::Uninitialized(*offset),
+            VerificationTypeInfo::Long { .. } => FrameValue::Long,
+            VerificationTypeInfo::Double { .. } => FrameValue::Double,
+        };
+        Ok(value)
     }
 
     //noinspection SpellCheckingInspection
-    pub fn read_code(&self, code: Vec<u8>) -> AsmResult<Vec<InsnNode>> {
+    pub fn read_code(&self, code: Vec<u8>) -> AsmResult<(Vec<InsnNode>, Vec<LabelNode>)> {
         let mut cur = 0usize;
 
         // read a 16bit const from index, in jvm bytecode, it stores high byte first (big-endian)
@@ -81,8 +166,10 @@ impl ClassNodeContext {
         };
 
         let mut res = vec![];
+        let mut offsets = vec![];
         while cur < code.len() {
             let opcode = code[cur];
+            offsets.push(cur as LabelNode);
             match opcode {
                 // getstatic | indexbyte1 | indexbyte2
                 Opcodes::GETSTATIC | Opcodes::PUTSTATIC | Opcodes::GETFIELD | Opcodes::PUTFIELD => {
@@ -98,7 +185,7 @@ impl ClassNodeContext {
                     cur += 3;
                 }
                 // const
-                Opcodes::ACONST_NULL | Opcodes::ICONST_M1 | Opcodes::ICONST_0 | Opcodes::ICONST_1 |
+                Opcodes::NOP | Opcodes::ACONST_NULL | Opcodes::ICONST_M1 | Opcodes::ICONST_0 | Opcodes::ICONST_1 |
                 Opcodes::ICONST_2 | Opcodes::ICONST_3 | Opcodes::ICONST_4 | Opcodes::ICONST_5 |
                 Opcodes::LCONST_0 | Opcodes::LCONST_1 | Opcodes::FCONST_0 | Opcodes::FCONST_1 |
                 Opcodes::FCONST_2 | Opcodes::DCONST_0 | Opcodes::DCONST_1 |
@@ -179,7 +266,7 @@ impl ClassNodeContext {
                     cur += 5;
                 }
                 // if<cond> | branchbyte1 | branchbyte2
-                Opcodes::IFLT | Opcodes::IFGE | Opcodes::IFGT | Opcodes::IFLE |
+                Opcodes::IFEQ | Opcodes::IFNE | Opcodes::IFLT | Opcodes::IFGE | Opcodes::IFGT | Opcodes::IFLE |
                 Opcodes::IF_ICMPEQ | Opcodes::IF_ICMPNE | Opcodes::IF_ICMPLT | Opcodes::IF_ICMPGE |
                 Opcodes::IF_ICMPGT | Opcodes::IF_ICMPLE | Opcodes::IF_ACMPEQ | Opcodes::IF_ACMPNE |
                 Opcodes::GOTO | Opcodes::JSR | Opcodes::IFNULL | Opcodes::IFNONNULL => {
@@ -199,18 +286,18 @@ impl ClassNodeContext {
                 // npairs1 | npairs2 | npairs3 | npairs4 |
                 // match-offset pairs...
                 Opcodes::LOOKUPSWITCH => {
-                    let lookup_start = cur as u16;
+                    let lookup_start = cur as i32;
                     cur += 1;
-                    let df_start = cur + 4 - (cur & 3);
-                    let default = read_i32(df_start) as u16;
+                    let df_start = cur + (4 - (cur & 3)) % 4;
+                    let default = (lookup_start + read_i32(df_start)) as u16;
                     let npairs = read_i32(df_start + 4);
                     let mut keys = vec![];
                     let mut labels = vec![];
-                    cur += 8;
+                    cur = df_start + 8;
                     for _ in 0..npairs {
                         keys.push(read_i32(cur));
-                        let offset = read_i32(cur + 4) as u16;
-                        labels.push(lookup_start + offset);
+                        let offset = read_i32(cur + 4);
+                        labels.push((lookup_start + offset) as u16);
                         cur += 8;
                     }
                     res.push(InsnNode::LookupSwitchInsnNode { default, keys, labels });
@@ -235,17 +322,17 @@ impl ClassNodeContext {
                 // highbyte1 | highbyte2 | highbyte3 | highbyte4 |
                 // jump offsets...
                 Opcodes::TABLESWITCH => {
-                    let table_start = cur as u16;
+                    let table_start = cur as i32;
                     cur += 1;
-                    let df_start = cur + 4 - (cur & 3);
-                    let default = read_i32(df_start) as u16;
+                    let df_start = cur + (4 - (cur & 3)) % 4;
+                    let default = (table_start + read_i32(df_start)) as u16;
                     let min = read_i32(df_start + 4);
                     let max = read_i32(df_start + 8);
                     let mut labels = vec![];
-                    cur += 12;
+                    cur = df_start + 12;
                     for _ in min..=max {
-                        let offset = read_i32(cur) as u16;
-                        labels.push(table_start + offset);
+                        let offset = read_i32(cur);
+                        labels.push((table_start + offset) as u16);
                         cur += 4;
                     }
                     res.push(InsnNode::TableSwitchInsnNode { default, min, max, labels });
@@ -302,7 +389,7 @@ impl ClassNodeContext {
                 }
             }
         }
-        Ok(res)
+        Ok((res, offsets))
     }
 }
 
@@ -315,6 +402,7 @@ fn const_to_bsm_arg(c: ConstValue) -> AsmResult<BootstrapMethodArgument> {
         ConstValue::String(s) => Ok(BootstrapMethodArgument::String(s)),
         ConstValue::Class(t) => Ok(BootstrapMethodArgument::Class(t)),
         ConstValue::MethodHandle(h) => Ok(BootstrapMethodArgument::Handle(h)),
+        ConstValue::MethodType(desc) => Ok(BootstrapMethodArgument::MethodType(desc)),
         _ => {
             let err_msg = format!("cannot convert correspond const value to bootstrap method argument: {:?}", c);
             Err(AsmErr::IllegalFormat(err_msg))
@@ -344,3 +432,52 @@ fn merge_local_variables(
     local_variables
 }
 
+
+impl LazyCodeBody {
+    pub(crate) fn new(location: CodeLocation) -> Self {
+        LazyCodeBody { location: Some(location), body: OnceLock::new() }
+    }
+
+    /// Decodes the code if needed, and returns it.
+    pub fn get(&self) -> AsmResult<&CodeBodyNode> {
+        if let Some(body) = self.body.get() {
+            return Ok(body);
+        }
+        let body = match &self.location {
+            Some(location) => location.context.read_code_body(location)?,
+            None => return Err(AsmErr::ResolveNode("code body without location is not initialized".to_string())),
+        };
+        // another thread may decode the same code meanwhile, the first one is kept.
+        Ok(self.body.get_or_init(|| body))
+    }
+
+    /// Decodes the code if needed, and returns it for modification.
+    pub fn get_mut(&mut self) -> AsmResult<&mut CodeBodyNode> {
+        self.get()?;
+        Ok(self.body.get_mut().expect("code body is decoded above"))
+    }
+}
+
+impl From<CodeBodyNode> for LazyCodeBody {
+    fn from(body: CodeBodyNode) -> Self {
+        LazyCodeBody { location: None, body: OnceLock::from(body) }
+    }
+}
+
+impl MethodNode {
+    /// The decoded code of this method, [None] for abstract and native methods.
+    pub fn code(&self) -> AsmResult<Option<&CodeBodyNode>> {
+        self.code_body.as_ref().map(LazyCodeBody::get).transpose()
+    }
+
+    /// The decoded code of this method for modification, [None] for abstract and native methods.
+    pub fn code_mut(&mut self) -> AsmResult<Option<&mut CodeBodyNode>> {
+        self.code_body.as_mut().map(LazyCodeBody::get_mut).transpose()
+    }
+}
+
+impl Debug for LazyCodeBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyCodeBody").field("body", &self.body).finish_non_exhaustive()
+    }
+}
@@ -8,16 +8,6 @@ use crate::*;
 use std::ops::Deref;
 use std::sync::Arc;
 
-impl ComputableSizedVecOwner<ConstValue> for ConstPool {
-    fn computable_vec(&self) -> &ComputableSizedVec<ConstValue> {
-        &self.pool
-    }
-
-    fn compute(&self, index: usize) -> AsmResult<ConstValue> {
-        self.read_const(index as u16)
-    }
-}
-
 macro_rules! read_const {
     {
         $($name:ident -> $ret:ty {
@@ -89,7 +79,13 @@ impl ConstPool {
 
     #[inline]
     pub fn get_res(&self, index: u16) -> AsmResult<Arc<ConstValue>> {
-        self.get_or_compute(index as usize)
+        let slot = self.pool.get(index as usize).ok_or(AsmErr::OutOfRange(index as usize))?;
+        if let Some(value) = slot.get() {
+            return Ok(Arc::clone(value));
+        }
+        let value = Arc::new(self.read_const(index)?);
+        // another thread may resolve the same constant meanwhile, the first one is kept.
+        Ok(Arc::clone(slot.get_or_init(|| value)))
     }
 
     fn read_const(&self, index: u16) -> AsmResult<ConstValue> {
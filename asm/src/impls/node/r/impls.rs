@@ -1,25 +1,22 @@
 use crate::err::{AsmErr, AsmResult};
-use crate::impls::node::r::node_reader::ClassNodeContext;
-use crate::impls::OnceAsmVec;
-use crate::impls::VecEx;
-use crate::impls::{once_vec_builder, once_vec_unpack};
+use crate::impls::node::r::node_reader::{ClassNodeContext, CodeLocation};
+use crate::jvms::attr::Attribute as JvmsAttribute;
 use crate::jvms::element::{ClassFile, FieldInfo, MethodInfo};
-use crate::node::element::{AnnotationNode, Attribute, ClassNode, FieldNode, InnerClassNode, MethodNode, ModuleNode, ParameterNode, RecordComponentNode, TypeAnnotationNode, UnknownAttribute};
+use crate::node::element::{Attribute, LazyCodeBody, ModuleNode, UnknownAttribute};
 use crate::node::values::{ConstValue, FieldInitialValue, ModuleAttrValue};
-use crate::InternalNameRef;
+use crate::visitor::{ClassHeader, ClassVisitor, MethodVisitor, ReadFlags};
 use std::sync::Arc;
 
-pub fn from_jvms_internal(jvms_file: ClassFile) -> AsmResult<ClassNode> {
-    let jvms_file = Arc::new(jvms_file);
-    let class_context = ClassNodeContext::new(Arc::clone(&jvms_file));
+pub fn accept_internal(jvms_file: Arc<ClassFile>, visitor: &mut dyn ClassVisitor, flags: u32) -> AsmResult<()> {
+    let class_context = Arc::new(ClassNodeContext::new(Arc::clone(&jvms_file)));
+    let skip_debug = flags & ReadFlags::SKIP_DEBUG != 0;
 
     let mut signature = None;
     let super_name = Some(class_context.read_class_info_or_default(jvms_file.super_class));
     let interfaces = jvms_file.interfaces.iter().map(|&index| {
         class_context.read_class_info_or_default(index)
     }).collect();
-    
-    
+
     let mut source_file = None;
     let mut source_debug = None;
     // module stuff
@@ -27,26 +24,13 @@ pub fn from_jvms_internal(jvms_file: ClassFile) -> AsmResult<ClassNode> {
     let mut module_packages = None;
     let mut module_main = None;
     // EnclosingMethod
-    let mut outer_class = None;
-    let mut outer_method_name = None;
-    let mut outer_method_desc = None;
-
-    once_vec_builder! {
-        let annotations: AnnotationNode;
-        let type_annotations: TypeAnnotationNode;
-        let inner_classes: InnerClassNode;
-        let nest_members: InternalNameRef;
-        let permitted_subclasses: InternalNameRef;
-        let record_components: RecordComponentNode;
-    }
-    
-    let mut attrs = vec![];
+    let mut enclosing_method = None;
     let mut nest_host_class = None;
 
     let name = class_context.name()?;
-    // read raw class attributes
-    let class_attrs = class_context.read_class_attrs()?;
-    for (attribute_info, attribute) in class_attrs {
+    // the first pass collects single-valued attributes, others are visited in order later.
+    let mut list_attrs = vec![];
+    for (attribute_info, attribute) in class_context.read_class_attrs()? {
         match attribute {
             Attribute::Signature(s) => signature = Some(s),
             Attribute::SourceFile(s) => source_file = Some(s),
@@ -55,25 +39,9 @@ pub fn from_jvms_internal(jvms_file: ClassFile) -> AsmResult<ClassNode> {
             Attribute::Module(a) => module_attr = Some(a),
             Attribute::ModulePackages(packages) => module_packages = Some(packages),
             Attribute::ModuleMainClass(main) => module_main = Some(main),
-
-            Attribute::EnclosingMethod(enc) => {
-                outer_class = Some(enc.class);
-                outer_method_name = Some(enc.method_name);
-                outer_method_desc = Some(enc.method_desc);
-            },
-
-            // annotations
-            Attribute::RuntimeVisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeInvisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeVisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-            Attribute::RuntimeInvisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-
-            Attribute::InnerClasses(ic) => inner_classes.put(ic)?,
+            Attribute::EnclosingMethod(enc) => enclosing_method = Some(enc),
             Attribute::NestHost(nh) => nest_host_class = Some(nh),
-            Attribute::NestMembers(nm) => nest_members.put(nm)?,
-            Attribute::PermittedSubclasses(ps) => permitted_subclasses.put(ps)?,
-            Attribute::Record(rc) => record_components.put(rc)?,
-            
+
             Attribute::BootstrapMethods(bm_attrs) => {
                 class_context.bootstrap_methods.set(bm_attrs).map_err(|prev| {
                     let err_msg = format!("most one bootstrap methods attribute is allowed, \
@@ -81,72 +49,85 @@ pub fn from_jvms_internal(jvms_file: ClassFile) -> AsmResult<ClassNode> {
                     AsmErr::ResolveNode(err_msg)
                 })?;
             }
+            attribute => list_attrs.push((attribute_info, attribute)),
+        }
+    }
+
+    visitor.visit(ClassHeader {
+        minor_version: jvms_file.minor_version,
+        major_version: jvms_file.major_version,
+        access: jvms_file.access_flags,
+        name: name.clone(),
+        signature, super_name, interfaces,
+    });
+    if !skip_debug && (source_file.is_some() || source_debug.is_some()) {
+        visitor.visit_source(source_file, source_debug);
+    }
+    if let Some(attr) = module_attr {
+        let ModuleAttrValue {
+            name, access, version,
+            requires, exports, opens,
+            uses, provides
+        } = attr;
+        let main_class = module_main;
+        let packages = module_packages.unwrap_or_default();
+        visitor.visit_module(ModuleNode {
+            name, access, version, main_class,
+            packages, requires, exports, opens, uses, provides,
+        });
+    }
+    if let Some(nest_host) = nest_host_class {
+        visitor.visit_nest_host(nest_host);
+    }
+    if let Some(enc) = enclosing_method {
+        visitor.visit_outer_class(enc.class, enc.method_name, enc.method_desc);
+    }
 
-            Attribute::Unknown(v) => attrs.push(v),
-            _ => attrs.push(UnknownAttribute {
+    for (attribute_info, attribute) in list_attrs {
+        match attribute {
+            // annotations
+            Attribute::RuntimeVisibleAnnotations(an) |
+            Attribute::RuntimeInvisibleAnnotations(an) => {
+                an.into_iter().for_each(|a| visitor.visit_annotation(a));
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(tan) |
+            Attribute::RuntimeInvisibleTypeAnnotations(tan) => {
+                tan.into_iter().for_each(|a| visitor.visit_type_annotation(a));
+            }
+
+            Attribute::InnerClasses(ic) => ic.into_iter().for_each(|c| visitor.visit_inner_class(c)),
+            Attribute::NestMembers(nm) => nm.into_iter().for_each(|n| visitor.visit_nest_member(n)),
+            Attribute::PermittedSubclasses(ps) => {
+                ps.into_iter().for_each(|p| visitor.visit_permitted_subclass(p));
+            }
+            Attribute::Record(rc) => rc.into_iter().for_each(|r| visitor.visit_record_component(r)),
+
+            Attribute::Unknown(v) => visitor.visit_attribute(v),
+            _ => visitor.visit_attribute(UnknownAttribute {
                 name: class_context.read_utf8(attribute_info.attribute_name_index)?,
                 origin: attribute_info.info.clone(),
             }),
         }
     }
 
-    let module = match module_attr {
-        Some(attr) => {
-            let ModuleAttrValue {
-                name, access, version,
-                requires, exports, opens,
-                uses, provides
-            } = attr;
-            let version = if let Some(v) = version { Some(v) } else { None };
-            let main_class = module_main;
-            let packages = module_packages.unwrap_or_default();
-
-            Some(ModuleNode {
-                name, access, version, main_class,
-                packages, requires, exports, opens, uses, provides,
-            })
-        }
-        None => None,
-    };
-
-  
-    let fields = jvms_file.fields.map_res(|field_info| {
-        field_from_jvms(&class_context, field_info)
-    })?;
-
-    let methods = jvms_file.methods.map_res(|method_info| {
-        method_from_jvms(&class_context, method_info)
-    })?;
-
-    let minor_version = *&jvms_file.minor_version;
-    let major_version = *&jvms_file.major_version;
-    let access = *&jvms_file.access_flags;
-
-    once_vec_unpack!(annotations, type_annotations, 
-        inner_classes, nest_members, permitted_subclasses, record_components);
-    
-    let class_node = ClassNode {
-        minor_version,
-        major_version,
-        access,
-        name, signature, super_name, interfaces, source_file, source_debug,
-        module, outer_class, outer_method_name, outer_method_desc,
-        annotations, type_annotations, inner_classes, nest_host_class, nest_members,
-        permitted_subclasses, record_components, fields, methods, attrs,
-    };
-    Ok(class_node)
+    for field_info in &jvms_file.fields {
+        accept_field(&class_context, field_info, visitor)?;
+    }
+    for (method_index, method_info) in jvms_file.methods.iter().enumerate() {
+        accept_method(&class_context, method_index, method_info, visitor, flags)?;
+    }
+    visitor.visit_end();
+    Ok(())
 }
 
-fn field_from_jvms(class_context: &ClassNodeContext, field_info: &FieldInfo) -> AsmResult<FieldNode> {
+fn accept_field(
+    class_context: &ClassNodeContext, field_info: &FieldInfo, visitor: &mut dyn ClassVisitor,
+) -> AsmResult<()> {
     let name = class_context.read_utf8(field_info.name_index)?;
     let access = field_info.access_flags;
     let desc = class_context.read_utf8(field_info.descriptor_index)?;
     let mut signature = None;
     let mut value = None;
-    once_vec_builder! {
-        let annotations: AnnotationNode;
-        let type_annotations: TypeAnnotationNode;
-    }
     let mut attrs = vec![];
 
     for (attribute_info, attribute) in class_context.read_attrs(&field_info.attributes)? {
@@ -164,80 +145,105 @@ fn field_from_jvms(class_context: &ClassNodeContext, field_info: &FieldInfo) ->
                     ).e()?,
                 }
             },
-            Attribute::RuntimeVisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeInvisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeVisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-            Attribute::RuntimeInvisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-            Attribute::Unknown(v) => attrs.push(v),
-            _ => attrs.push(UnknownAttribute {
+            attribute => attrs.push((attribute_info, attribute)),
+        }
+    }
+
+    let Some(mut field_visitor) = visitor.visit_field(access, name, desc, signature, value) else {
+        return Ok(());
+    };
+    for (attribute_info, attribute) in attrs {
+        match attribute {
+            Attribute::RuntimeVisibleAnnotations(an) |
+            Attribute::RuntimeInvisibleAnnotations(an) => {
+                an.into_iter().for_each(|a| field_visitor.visit_annotation(a));
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(tan) |
+            Attribute::RuntimeInvisibleTypeAnnotations(tan) => {
+                tan.into_iter().for_each(|a| field_visitor.visit_type_annotation(a));
+            }
+            Attribute::Unknown(v) => field_visitor.visit_attribute(v),
+            _ => field_visitor.visit_attribute(UnknownAttribute {
                 name: class_context.read_utf8(attribute_info.attribute_name_index)?,
                 origin: attribute_info.info.clone(),
             }),
         }
     }
-
-    once_vec_unpack!(annotations, type_annotations);
-
-    let field_node = FieldNode {
-        name, access, desc, signature, value, annotations, type_annotations, attrs,
-    };
-    Ok(field_node)
+    field_visitor.visit_end();
+    Ok(())
 }
 
-fn method_from_jvms(class_context: &ClassNodeContext, method_info: &MethodInfo) -> AsmResult<MethodNode> {
+fn accept_method(
+    class_context: &Arc<ClassNodeContext>, method_index: usize, method_info: &MethodInfo,
+    visitor: &mut dyn ClassVisitor, flags: u32,
+) -> AsmResult<()> {
+    let name = class_context.read_utf8(method_info.name_index)?;
+    let access = method_info.access_flags;
+    let desc = class_context.read_utf8(method_info.descriptor_index)?;
     let mut signature = None;
-
-    once_vec_builder! {
-        let exceptions: InternalNameRef;
-        let parameters: ParameterNode;
-        let annotations: AnnotationNode;
-        let type_annotations: TypeAnnotationNode;
-        let parameter_annotations: Vec<AnnotationNode>;
-    }
-    
+    let mut exceptions = vec![];
     let mut attrs = vec![];
+    // the code is converted at last, or even not converted until it is used.
+    let mut code_index = None;
 
-    let mut annotation_default = None;
-    let mut code_body = None;
+    for (attribute_index, attribute_info) in method_info.attributes.iter().enumerate() {
+        if let JvmsAttribute::Code { .. } = attribute_info.info {
+            code_index = Some(attribute_index);
+            continue;
+        }
+        match class_context.read_attr(attribute_info)? {
+            Attribute::Signature(s) => signature = Some(s),
+            Attribute::Exceptions(ex) => exceptions.extend(ex),
+            attribute => attrs.push((attribute_info, attribute)),
+        }
+    }
 
-    let name = class_context.read_utf8(method_info.name_index)?;
-    let all_attributes = class_context.read_attrs(&method_info.attributes)?;
-    for (attribute_info, attribute) in all_attributes {
+    let Some(mut method_visitor) = visitor.visit_method(access, name, desc, signature, exceptions) else {
+        return Ok(());
+    };
+    let method_visitor: &mut dyn MethodVisitor = method_visitor.as_mut();
+    for (attribute_info, attribute) in attrs {
         match attribute {
-            Attribute::Signature(s) => signature = Some(s),
-            Attribute::Exceptions(ex) => exceptions.put(ex)?,
-            Attribute::MethodParameters(ps) => parameters.put(ps)?,
+            Attribute::MethodParameters(ps) => {
+                if flags & ReadFlags::SKIP_DEBUG == 0 {
+                    ps.into_iter().for_each(|p| method_visitor.visit_parameter(p));
+                }
+            }
 
-            Attribute::RuntimeVisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeInvisibleAnnotations(an) => annotations.put(an)?,
-            Attribute::RuntimeVisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-            Attribute::RuntimeInvisibleTypeAnnotations(tan) => type_annotations.put(tan)?,
-            Attribute::RuntimeVisibleParameterAnnotations(pan) => parameter_annotations.put(pan)?,
-            Attribute::RuntimeInvisibleParameterAnnotations(pan) => parameter_annotations.put(pan)?,
+            Attribute::RuntimeVisibleAnnotations(an) |
+            Attribute::RuntimeInvisibleAnnotations(an) => {
+                an.into_iter().for_each(|a| method_visitor.visit_annotation(a));
+            }
+            Attribute::RuntimeVisibleTypeAnnotations(tan) |
+            Attribute::RuntimeInvisibleTypeAnnotations(tan) => {
+                tan.into_iter().for_each(|a| method_visitor.visit_type_annotation(a));
+            }
+            Attribute::RuntimeVisibleParameterAnnotations(pan) |
+            Attribute::RuntimeInvisibleParameterAnnotations(pan) => {
+                method_visitor.visit_annotable_parameter_count(pan.len());
+                for (parameter, annotations) in pan.into_iter().enumerate() {
+                    annotations.into_iter()
+                        .for_each(|a| method_visitor.visit_parameter_annotation(parameter, a));
+                }
+            }
 
-            Attribute::AnnotationDefault(v) => annotation_default = Some(v),
+            Attribute::AnnotationDefault(v) => method_visitor.visit_annotation_default(v),
 
-            Attribute::Code(code_attribute) => {
-                code_body = Some(class_context.read_code_body(code_attribute)?);
-            }
-            
-            Attribute::Unknown(v) => attrs.push(v),
-            _ => attrs.push(UnknownAttribute {
+            Attribute::Unknown(v) => method_visitor.visit_attribute(v),
+            _ => method_visitor.visit_attribute(UnknownAttribute {
                 name: class_context.read_utf8(attribute_info.attribute_name_index)?,
                 origin: attribute_info.info.clone(),
             }),
         }
     }
-
-    once_vec_unpack!(exceptions, parameters, annotations, type_annotations, parameter_annotations);
-
-    let access = method_info.access_flags;
-    let desc = class_context.read_utf8(method_info.descriptor_index)?;
-    let method_node = MethodNode {
-        name, access, desc, signature, exceptions, parameters,
-        annotations, type_annotations, parameter_annotations, attrs,
-        annotation_default,
-        code_body,
-    };
-    Ok(method_node)
+    if let Some(attribute_index) = code_index && flags & ReadFlags::SKIP_CODE == 0 {
+        let location = CodeLocation { context: Arc::clone(class_context), method_index, attribute_index };
+        if flags & ReadFlags::LAZY_CODE != 0 {
+            method_visitor.visit_lazy_code(LazyCodeBody::new(location));
+        } else {
+            class_context.accept_code_body(class_context.read_code_attr(&location)?, method_visitor, flags)?;
+        }
+    }
+    method_visitor.visit_end();
+    Ok(())
 }
@@ -2,20 +2,28 @@ use crate::err::AsmErr;
 use crate::jvms::element::ClassFile;
 use crate::node::element::BootstrapMethodAttr;
 use crate::node::values::ConstValue;
-use crate::ComputableSizedVec;
-use std::cell::OnceCell;
 use std::fmt::Display;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 pub struct ConstPool {
     pub jvms_file: Arc<ClassFile>,
-    pub pool: ComputableSizedVec<ConstValue>,
+    /// resolved constants, shared with lazy code bodies which may be decoded in other threads.
+    pub pool: Vec<OnceLock<Arc<ConstValue>>>,
+}
+
+/// The position of a `Code` attribute in [ClassNodeContext::jvms_file], which is decoded
+/// on demand by [crate::node::element::LazyCodeBody].
+#[derive(Clone)]
+pub(crate) struct CodeLocation {
+    pub context: Arc<ClassNodeContext>,
+    pub method_index: usize,
+    pub attribute_index: usize,
 }
 
 pub(crate) struct ClassNodeContext {
     pub jvms_file: Arc<ClassFile>,
     pub cp: Arc<ConstPool>,
-    pub bootstrap_methods: OnceCell<Vec<BootstrapMethodAttr>>,
+    pub bootstrap_methods: OnceLock<Vec<BootstrapMethodAttr>>,
 }
 
 impl ClassNodeContext {
@@ -23,12 +31,12 @@ impl ClassNodeContext {
         let const_pool_size = jvms_file.constant_pool.len();
         let const_pool = ConstPool {
             jvms_file: Arc::clone(&jvms_file),
-            pool: ComputableSizedVec::new(const_pool_size),
+            pool: (0..const_pool_size).map(|_| OnceLock::new()).collect(),
         };
         // attrs need to be read entirely, because we need to traverse the attributes
         // when constructing the class node, we just uses LazyCell for read it lazily.
         let cp = Arc::new(const_pool);
-        let bootstrap_methods = OnceCell::default();
+        let bootstrap_methods = OnceLock::default();
         ClassNodeContext {
             jvms_file: Arc::clone(&jvms_file),
             cp, bootstrap_methods,
@@ -1,3 +1,4 @@
+use crate::bootstrap::BootstrapCall;
 use crate::impls::ToStringRef;
 use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, Handle};
 use crate::node::InsnNode;
@@ -95,7 +96,8 @@ impl ToSmali for ConstDynamic {
     fn to_smali(&self) -> SmaliNode {
         let ConstDynamic { name, desc, bsm, bsm_args } = self;
         let prefix = format!("invoke-dynamic {name} {desc} {bsm}");
-        let children = bsm_args.iter().map(|arg| arg.to_smali()).collect();
+        let call = BootstrapCall::resolve(self).map(|call| call.to_smali());
+        let children = call.into_iter().chain(bsm_args.iter().map(|arg| arg.to_smali())).collect();
         stb().other(prefix.to_ref()).s_with_children(children)
     }
 }
@@ -126,6 +128,7 @@ impl ToSmali for BootstrapMethodArgument {
             BootstrapMethodArgument::String(v) => raw_smali!("{v}"),
             BootstrapMethodArgument::Class(v) => raw_smali!("{v}"),
             BootstrapMethodArgument::Handle(v) => raw_smali!("{v}"),
+            BootstrapMethodArgument::MethodType(v) => v.to_smali(),
         }
     }
 }
@@ -0,0 +1,454 @@
+use crate::analysis::flow_of;
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::jvms::r::frame::Frame;
+use crate::impls::jvms::w::WriteContext;
+use crate::impls::method_arg_slots;
+use crate::impls::node::w::const_pool::ConstPoolBuilder;
+use crate::jvms::attr::{Attribute as JvmsAttribute, ExceptionTable as JvmsExceptionTable, LineNumberTableInfo, LocalVariableTableInfo, LocalVariableTypeTableInfo, StackMapFrame, VerificationTypeInfo};
+use crate::jvms::element::AttributeInfo;
+use crate::node::element::{CodeBodyNode, LabelNode};
+use crate::node::values::{ConstValue, FrameAttributeValue, FrameValue};
+use crate::node::InsnNode;
+use crate::{Constants, Opcodes};
+
+/// Maps the labels of a [CodeBodyNode] to the offsets in the newly written bytecode.
+struct LabelMapper {
+    labels: Vec<LabelNode>,
+    offsets: Vec<u32>,
+    code_length: u32,
+}
+
+impl LabelMapper {
+    /// A label maps to the first instruction whose label is not less than it,
+    /// or the end of the code if there is no such instruction.
+    fn map(&self, label: LabelNode) -> u32 {
+        let index = match self.labels.binary_search(&label) {
+            Ok(index) => index,
+            Err(index) => index,
+        };
+        self.offsets.get(index).copied().unwrap_or(self.code_length)
+    }
+
+    fn map_u16(&self, label: LabelNode) -> AsmResult<u16> {
+        let offset = self.map(label);
+        u16::try_from(offset).map_err(|_| {
+            AsmErr::IllegalFormat(format!("code offset out of range: {offset}"))
+        })
+    }
+}
+
+#[inline]
+fn switch_padding(offset: u32) -> u32 {
+    (4 - (offset + 1) % 4) % 4
+}
+
+/// Resolves constant pool entries and the byte size of an instruction placed at `offset`.
+fn resolve_insn(cp: &mut ConstPoolBuilder, insn: &InsnNode, offset: u32) -> AsmResult<(u16, u32)> {
+    let resolved = match insn {
+        InsnNode::FieldInsnNode { owner, name, desc, .. } => (cp.field(owner, name, desc)?, 3),
+        InsnNode::IIncInsnNode { var, incr } => {
+            let wide = *var > u8::MAX as u16 || *incr < i8::MIN as i16 || *incr > i8::MAX as i16;
+            (0, if wide { 6 } else { 3 })
+        }
+        InsnNode::NoOperand { .. } => (0, 1),
+        InsnNode::BIPushInsnNode { .. } => (0, 2),
+        InsnNode::SIPushInsnNode { .. } => (0, 3),
+        InsnNode::InvokeDynamicInsnNode(const_dynamic) => (cp.invoke_dynamic(const_dynamic)?, 5),
+        InsnNode::JumpInsnNode { opcode, .. } => match *opcode {
+            Opcodes::GOTO_W | Opcodes::JSR_W => (0, 5),
+            _ => (0, 3),
+        },
+        InsnNode::LdcInsnNode(value) => {
+            let index = cp.loadable(value)?;
+            match value.as_ref() {
+                ConstValue::Long(_) | ConstValue::Double(_) => (index, 3),
+                _ if index <= u8::MAX as u16 => (index, 2),
+                _ => (index, 3),
+            }
+        }
+        InsnNode::TableSwitchInsnNode { labels, .. } => {
+            (0, 1 + switch_padding(offset) + 12 + 4 * labels.len() as u32)
+        }
+        InsnNode::LookupSwitchInsnNode { labels, .. } => {
+            (0, 1 + switch_padding(offset) + 8 + 8 * labels.len() as u32)
+        }
+        InsnNode::MethodInsnNode { opcode, owner, name, desc } => {
+            let is_interface = *opcode == Opcodes::INVOKEINTERFACE;
+            let index = cp.method(owner, name, desc, is_interface)?;
+            (index, if is_interface { 5 } else { 3 })
+        }
+        InsnNode::NewArrayInsnNode { .. } => (0, 2),
+        InsnNode::MultiANewArrayInsnNode { array_type, .. } => (cp.class(array_type)?, 4),
+        InsnNode::TypeInsnNode { type_name, .. } => (cp.class(type_name)?, 3),
+        InsnNode::VarInsnNode { var_index, .. } => {
+            (0, if *var_index > u8::MAX as u16 { 4 } else { 2 })
+        }
+    };
+    Ok(resolved)
+}
+
+fn jump_offset(mapper: &LabelMapper, from: u32, label: LabelNode) -> i32 {
+    mapper.map(label) as i32 - from as i32
+}
+
+fn write_insn(
+    context: &mut WriteContext, mapper: &LabelMapper,
+    insn: &InsnNode, offset: u32, cp_index: u16, size: u32,
+) -> AsmResult<()> {
+    match insn {
+        InsnNode::FieldInsnNode { opcode, .. } => {
+            context.write(*opcode);
+            context.write(cp_index);
+        }
+        InsnNode::IIncInsnNode { var, incr } => {
+            if size == 6 {
+                context.write(Opcodes::WIDE);
+                context.write(Opcodes::IINC);
+                context.write(*var);
+                context.write(*incr as u16);
+            } else {
+                context.write(Opcodes::IINC);
+                context.write(*var as u8);
+                context.write(*incr as i8 as u8);
+            }
+        }
+        InsnNode::NoOperand { opcode } => context.write(*opcode),
+        InsnNode::BIPushInsnNode { operand } => {
+            context.write(Opcodes::BIPUSH);
+            context.write(*operand as u8);
+        }
+        InsnNode::SIPushInsnNode { operand } => {
+            context.write(Opcodes::SIPUSH);
+            context.write(*operand as u16);
+        }
+        InsnNode::InvokeDynamicInsnNode(_) => {
+            context.write(Opcodes::INVOKEDYNAMIC);
+            context.write(cp_index);
+            context.write(0u16);
+        }
+        InsnNode::JumpInsnNode { opcode, label } => {
+            let jump = jump_offset(mapper, offset, *label);
+            context.write(*opcode);
+            if size == 5 {
+                context.write(jump as u32);
+            } else {
+                let jump = i16::try_from(jump).map_err(|_| {
+                    AsmErr::IllegalFormat(format!("jump offset overflow at {offset}: {jump}"))
+                })?;
+                context.write(jump as u16);
+            }
+        }
+        InsnNode::LdcInsnNode(value) => {
+            match value.as_ref() {
+                ConstValue::Long(_) | ConstValue::Double(_) => {
+                    context.write(Opcodes::LDC2_W);
+                    context.write(cp_index);
+                }
+                _ if size == 2 => {
+                    context.write(Opcodes::LDC);
+                    context.write(cp_index as u8);
+                }
+                _ => {
+                    context.write(Opcodes::LDC_W);
+                    context.write(cp_index);
+                }
+            }
+        }
+        InsnNode::TableSwitchInsnNode { default, min, max, labels } => {
+            context.write(Opcodes::TABLESWITCH);
+            for _ in 0..switch_padding(offset) { context.write(0u8); }
+            context.write(jump_offset(mapper, offset, *default) as u32);
+            context.write(*min as u32);
+            context.write(*max as u32);
+            for label in labels {
+                context.write(jump_offset(mapper, offset, *label) as u32);
+            }
+        }
+        InsnNode::LookupSwitchInsnNode { default, keys, labels } => {
+            context.write(Opcodes::LOOKUPSWITCH);
+            for _ in 0..switch_padding(offset) { context.write(0u8); }
+            context.write(jump_offset(mapper, offset, *default) as u32);
+            context.write(labels.len() as u32);
+            for (key, label) in keys.iter().zip(labels) {
+                context.write(*key as u32);
+                context.write(jump_offset(mapper, offset, *label) as u32);
+            }
+        }
+        InsnNode::MethodInsnNode { opcode, desc, .. } => {
+            context.write(*opcode);
+            context.write(cp_index);
+            if *opcode == Opcodes::INVOKEINTERFACE {
+                context.write((method_arg_slots(desc) + 1) as u8);
+                context.write(0u8);
+            }
+        }
+        InsnNode::NewArrayInsnNode { array_type } => {
+            context.write(Opcodes::NEWARRAY);
+            context.write(*array_type);
+        }
+        InsnNode::MultiANewArrayInsnNode { dims, .. } => {
+            context.write(Opcodes::MULTIANEWARRAY);
+            context.write(cp_index);
+            context.write(*dims);
+        }
+        InsnNode::TypeInsnNode { opcode, .. } => {
+            context.write(*opcode);
+            context.write(cp_index);
+        }
+        InsnNode::VarInsnNode { opcode, var_index } => {
+            if size == 4 {
+                context.write(Opcodes::WIDE);
+                context.write(*opcode);
+                context.write(*var_index);
+            } else {
+                context.write(*opcode);
+                context.write(*var_index as u8);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the code can't be verified by type checking without a `StackMapTable`,
+/// that is it has a branch target or an exception handler.
+pub(crate) fn needs_stack_map(code: &CodeBodyNode) -> bool {
+    if !code.exception_table.is_empty() { return true; }
+    let last = code.instructions.len().saturating_sub(1);
+    code.instructions.iter().enumerate().any(|(index, insn)| {
+        let (falls_through, targets) = flow_of(insn);
+        !targets.is_empty() || (!falls_through && index != last)
+    })
+}
+
+fn write_frame_value(cp: &mut ConstPoolBuilder, mapper: &LabelMapper, value: &FrameValue) -> AsmResult<VerificationTypeInfo> {
+    let info = match value {
+        FrameValue::Top => VerificationTypeInfo::Top { tag: Frame::ITEM_Top },
+        FrameValue::Integer => VerificationTypeInfo::Integer { tag: Frame::ITEM_Integer },
+        FrameValue::Float => VerificationTypeInfo::Float { tag: Frame::ITEM_Float },
+        FrameValue::Long => VerificationTypeInfo::Long { tag: Frame::ITEM_Long },
+        FrameValue::Double => VerificationTypeInfo::Double { tag: Frame::ITEM_Double },
+        FrameValue::Null => VerificationTypeInfo::Null { tag: Frame::ITEM_Null },
+        FrameValue::UninitializedThis => VerificationTypeInfo::UninitializedThis { tag: Frame::ITEM_UninitializedThis },
+        FrameValue::Object(name) => VerificationTypeInfo::Object { tag: Frame::ITEM_Object, cpool_index: cp.class(name)? },
+        FrameValue::Uninitialized(label) => VerificationTypeInfo::Uninitialized {
+            tag: Frame::ITEM_Uninitialized, offset: mapper.map_u16(*label)?,
+        },
+    };
+    Ok(info)
+}
+
+fn write_frame_values(cp: &mut ConstPoolBuilder, mapper: &LabelMapper, values: &[FrameValue]) -> AsmResult<Vec<VerificationTypeInfo>> {
+    let mut infos = Vec::with_capacity(values.len());
+    for value in values {
+        infos.push(write_frame_value(cp, mapper, value)?);
+    }
+    Ok(infos)
+}
+
+/// Writes the frames with their offset deltas recomputed for the rewritten code.
+/// The kind of each frame is kept, except that a short delta which doesn't fit anymore
+/// takes the extended form.
+fn write_stack_map(
+    cp: &mut ConstPoolBuilder, mapper: &LabelMapper, frames: &[FrameAttributeValue],
+) -> AsmResult<JvmsAttribute> {
+    let mut entries = Vec::with_capacity(frames.len());
+    // the label and the new offset of the previous frame.
+    let mut previous: Option<(u32, u32)> = None;
+    for frame in frames {
+        let offset_delta = match frame {
+            FrameAttributeValue::SameFrame { offset_delta } |
+            FrameAttributeValue::SameLocals1StackItemFrame { offset_delta, .. } => *offset_delta as u32,
+            FrameAttributeValue::SameFrameExtended { offset_delta } |
+            FrameAttributeValue::SameLocals1StackItemFrameExtended { offset_delta, .. } |
+            FrameAttributeValue::ChopFrame { offset_delta, .. } |
+            FrameAttributeValue::AppendFrame { offset_delta, .. } |
+            FrameAttributeValue::FullFrame { offset_delta, .. } => *offset_delta as u32,
+        };
+        let label = match previous {
+            None => offset_delta,
+            Some((previous_label, _)) => previous_label + offset_delta + 1,
+        };
+        let Ok(label) = LabelNode::try_from(label) else {
+            return Err(AsmErr::IllegalFormat(format!("stack map frame out of code: {label}")));
+        };
+        let offset = mapper.map(label);
+        let offset_delta = match previous {
+            None => offset,
+            Some((_, previous_offset)) if offset > previous_offset => offset - previous_offset - 1,
+            Some(_) => {
+                let err_msg = format!("stack map frames at the same offset: {offset}");
+                return Err(AsmErr::IllegalFormat(err_msg));
+            }
+        };
+        previous = Some((label as u32, offset));
+        // code_length is limited to u16, so is the delta.
+        let offset_delta = offset_delta as u16;
+        let entry = match frame {
+            FrameAttributeValue::SameFrame { .. } if offset_delta <= 63 => {
+                StackMapFrame::SameFrame { frame_type: offset_delta as u8 }
+            }
+            FrameAttributeValue::SameFrame { .. } | FrameAttributeValue::SameFrameExtended { .. } => {
+                StackMapFrame::SameFrameExtended { frame_type: 251, offset_delta }
+            }
+            FrameAttributeValue::SameLocals1StackItemFrame { stack, .. } if offset_delta <= 63 => {
+                StackMapFrame::SameLocals1StackItemFrame {
+                    frame_type: 64 + offset_delta as u8,
+                    verification_type_info: write_frame_value(cp, mapper, stack)?,
+                }
+            }
+            FrameAttributeValue::SameLocals1StackItemFrame { stack, .. } |
+            FrameAttributeValue::SameLocals1StackItemFrameExtended { stack, .. } => {
+                StackMapFrame::SameLocals1StackItemFrameExtended {
+                    frame_type: 247,
+                    offset_delta,
+                    verification_type_info: write_frame_value(cp, mapper, stack)?,
+                }
+            }
+            FrameAttributeValue::ChopFrame { chop_count, .. } => {
+                StackMapFrame::ChopFrame { frame_type: 251 - chop_count, offset_delta }
+            }
+            FrameAttributeValue::AppendFrame { append_locals, .. } => StackMapFrame::AppendFrame {
+                frame_type: 251 + append_locals.len() as u8,
+                offset_delta,
+                locals: write_frame_values(cp, mapper, append_locals)?,
+            },
+            FrameAttributeValue::FullFrame { locals, stack, .. } => StackMapFrame::FullFrame {
+                frame_type: 255,
+                offset_delta,
+                number_of_locals: locals.len() as u16,
+                locals: write_frame_values(cp, mapper, locals)?,
+                number_of_stack_items: stack.len() as u16,
+                stack: write_frame_values(cp, mapper, stack)?,
+            },
+        };
+        entries.push(entry);
+    }
+    Ok(JvmsAttribute::StackMapTable { number_of_entries: entries.len() as u16, entries })
+}
+
+/// Writes a [CodeBodyNode] into a jvms `Code` attribute.
+///
+/// Labels are resolved through [CodeBodyNode::insn_offsets], an empty `insn_offsets`
+/// means labels are the indexes of instructions.
+/// The offsets of the `StackMapTable` are rewritten for the new code, type annotations are not
+/// written because they still refer to the constant pool of the class they were read from.
+pub(crate) fn write_code(cp: &mut ConstPoolBuilder, code: &CodeBodyNode) -> AsmResult<JvmsAttribute> {
+    let CodeBodyNode {
+        instructions, insn_offsets, exception_table, local_variables, line_numbers,
+        max_stack, max_locals, stack_map_table, unknown_attributes, ..
+    } = code;
+    let labels: Vec<LabelNode> = if insn_offsets.is_empty() {
+        (0..instructions.len()).map(|i| i as LabelNode).collect()
+    } else if insn_offsets.len() == instructions.len() {
+        insn_offsets.clone()
+    } else {
+        let err_msg = format!(
+            "insn_offsets size {} doesn't match instructions size {}",
+            insn_offsets.len(), instructions.len(),
+        );
+        return Err(AsmErr::IllegalFormat(err_msg));
+    };
+
+    let mut resolved = Vec::with_capacity(instructions.len());
+    let mut offsets = Vec::with_capacity(instructions.len());
+    let mut code_length = 0u32;
+    for insn in instructions {
+        let (cp_index, size) = resolve_insn(cp, insn, code_length)?;
+        resolved.push((cp_index, size));
+        offsets.push(code_length);
+        code_length += size;
+    }
+    if code_length > u16::MAX as u32 {
+        return Err(AsmErr::IllegalFormat(format!("code too large: {code_length} bytes")));
+    }
+    let mapper = LabelMapper { labels, offsets, code_length };
+
+    let mut context = WriteContext { bytes: Vec::with_capacity(code_length as usize) };
+    for (index, insn) in instructions.iter().enumerate() {
+        let (cp_index, size) = resolved[index];
+        write_insn(&mut context, &mapper, insn, mapper.offsets[index], cp_index, size)?;
+    }
+
+    let mut jvms_exception_table = Vec::with_capacity(exception_table.len());
+    for item in exception_table {
+        let catch_type = match &item.catch_type {
+            Some(catch_type) => cp.class(catch_type)?,
+            None => 0,
+        };
+        jvms_exception_table.push(JvmsExceptionTable {
+            start_pc: mapper.map_u16(item.start)?,
+            end_pc: mapper.map_u16(item.end)?,
+            handler_pc: mapper.map_u16(item.handler)?,
+            catch_type,
+        });
+    }
+
+    let mut attributes: Vec<AttributeInfo> = vec![];
+    if !line_numbers.is_empty() {
+        let mut line_number_table = Vec::with_capacity(line_numbers.len());
+        for line in line_numbers {
+            line_number_table.push(LineNumberTableInfo {
+                start_pc: mapper.map_u16(line.start_pc)?,
+                line_number: line.line_number,
+            });
+        }
+        let info = JvmsAttribute::LineNumberTable {
+            line_number_table_length: line_number_table.len() as u16,
+            line_number_table,
+        };
+        attributes.push(cp.attr(Constants::LINE_NUMBER_TABLE, info)?);
+    }
+    if !local_variables.is_empty() {
+        let mut local_variable_table = vec![];
+        let mut local_variable_type_table = vec![];
+        for local in local_variables {
+            let start_pc = mapper.map_u16(local.start)?;
+            let length = mapper.map_u16(local.end)?.saturating_sub(start_pc);
+            let name_index = cp.utf8(&local.name)?;
+            local_variable_table.push(LocalVariableTableInfo {
+                start_pc, length, name_index,
+                descriptor_index: cp.utf8(&local.desc)?,
+                index: local.index,
+            });
+            if let Some(signature) = &local.signature {
+                local_variable_type_table.push(LocalVariableTypeTableInfo {
+                    start_pc, length, name_index,
+                    signature_index: cp.utf8(signature)?,
+                    index: local.index,
+                });
+            }
+        }
+        let info = JvmsAttribute::LocalVariableTable {
+            local_variable_table_length: local_variable_table.len() as u16,
+            local_variable_table,
+        };
+        attributes.push(cp.attr(Constants::LOCAL_VARIABLE_TABLE, info)?);
+        if !local_variable_type_table.is_empty() {
+            let info = JvmsAttribute::LocalVariableTypeTable {
+                local_variable_type_table_length: local_variable_type_table.len() as u16,
+                local_variable_table: local_variable_type_table,
+            };
+            attributes.push(cp.attr(Constants::LOCAL_VARIABLE_TYPE_TABLE, info)?);
+        }
+    }
+    if !stack_map_table.is_empty() {
+        let info = write_stack_map(cp, &mapper, stack_map_table)?;
+        attributes.push(cp.attr(Constants::STACK_MAP_TABLE, info)?);
+    }
+    for unknown in unknown_attributes {
+        if let Some(attr) = super::impls::portable_attr(cp, unknown)? {
+            attributes.push(attr);
+        }
+    }
+
+    Ok(JvmsAttribute::Code {
+        max_stack: *max_stack,
+        max_locals: *max_locals,
+        code_length,
+        code: context.bytes,
+        exception_table_length: jvms_exception_table.len() as u16,
+        exception_table: jvms_exception_table,
+        attributes_count: attributes.len() as u16,
+        attributes,
+    })
+}
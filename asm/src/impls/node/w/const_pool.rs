@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use crate::constants::Constants;
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::jvms::w::WriteContext;
+use crate::impls::utf8_to_mutf8;
+use crate::jvms::attr::{Attribute as JvmsAttribute, BootstrapMethod};
+use crate::jvms::element::{AttributeInfo, CPInfo, Const};
+use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, Handle};
+use crate::{MethodHandleKind, StrRef};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum ConstKey {
+    Utf8(StrRef),
+    Class(u16),
+    String(u16),
+    Integer(i32),
+    Float(u32),
+    Long(i64),
+    Double(u64),
+    NameAndType(u16, u16),
+    Field(u16, u16),
+    Method(u16, u16),
+    InterfaceMethod(u16, u16),
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    InvokeDynamic(u16, u16),
+}
+
+/// Builds a deduplicated constant pool and the bootstrap methods table while writing a class.
+pub(crate) struct ConstPoolBuilder {
+    pool: Vec<CPInfo>,
+    indexes: HashMap<ConstKey, u16>,
+    bootstrap_methods: Vec<BootstrapMethod>,
+    bootstrap_indexes: HashMap<(u16, Vec<u16>), u16>,
+}
+
+impl ConstPoolBuilder {
+    pub fn new() -> Self {
+        Self {
+            pool: vec![CPInfo { tag: Constants::CONSTANT_Invalid, info: Const::Invalid }],
+            indexes: HashMap::new(),
+            bootstrap_methods: vec![],
+            bootstrap_indexes: HashMap::new(),
+        }
+    }
+
+    fn put(&mut self, key: ConstKey, tag: u8, info: Const) -> AsmResult<u16> {
+        if let Some(index) = self.indexes.get(&key) {
+            return Ok(*index);
+        }
+        let index = self.pool.len();
+        let wide = tag == Constants::CONSTANT_Long || tag == Constants::CONSTANT_Double;
+        let next_len = index + if wide { 2 } else { 1 };
+        if next_len > u16::MAX as usize {
+            return Err(AsmErr::IllegalFormat("too many constants in constant pool".to_string()));
+        }
+        self.pool.push(CPInfo { tag, info });
+        if wide {
+            self.pool.push(CPInfo { tag: Constants::CONSTANT_Invalid, info: Const::Invalid });
+        }
+        let index = index as u16;
+        self.indexes.insert(key, index);
+        Ok(index)
+    }
+
+    pub fn utf8(&mut self, value: &str) -> AsmResult<u16> {
+        let key = ConstKey::Utf8(StrRef::from(value));
+        if let Some(index) = self.indexes.get(&key) {
+            return Ok(*index);
+        }
+        let bytes = utf8_to_mutf8(value.as_bytes())?;
+        let length = u16::try_from(bytes.len()).map_err(|_| {
+            AsmErr::IllegalFormat(format!("utf8 constant too long: {} bytes", bytes.len()))
+        })?;
+        self.put(key, Constants::CONSTANT_Utf8, Const::Utf8 { length, bytes })
+    }
+
+    pub fn class(&mut self, internal_name: &str) -> AsmResult<u16> {
+        let name_index = self.utf8(internal_name)?;
+        self.put(ConstKey::Class(name_index), Constants::CONSTANT_Class, Const::Class { name_index })
+    }
+
+    pub fn string(&mut self, value: &str) -> AsmResult<u16> {
+        let string_index = self.utf8(value)?;
+        self.put(ConstKey::String(string_index), Constants::CONSTANT_String, Const::String { string_index })
+    }
+
+    pub fn integer(&mut self, value: i32) -> AsmResult<u16> {
+        let bytes = value as u32;
+        self.put(ConstKey::Integer(value), Constants::CONSTANT_Integer, Const::Integer { bytes })
+    }
+
+    pub fn float(&mut self, value: f32) -> AsmResult<u16> {
+        let bytes = value.to_bits();
+        self.put(ConstKey::Float(bytes), Constants::CONSTANT_Float, Const::Float { bytes })
+    }
+
+    pub fn long(&mut self, value: i64) -> AsmResult<u16> {
+        let bits = value as u64;
+        let (high_bytes, low_bytes) = ((bits >> 32) as u32, bits as u32);
+        self.put(ConstKey::Long(value), Constants::CONSTANT_Long, Const::Long { high_bytes, low_bytes })
+    }
+
+    pub fn double(&mut self, value: f64) -> AsmResult<u16> {
+        let bits = value.to_bits();
+        let (high_bytes, low_bytes) = ((bits >> 32) as u32, bits as u32);
+        self.put(ConstKey::Double(bits), Constants::CONSTANT_Double, Const::Double { high_bytes, low_bytes })
+    }
+
+    pub fn name_and_type(&mut self, name: &str, desc: &str) -> AsmResult<u16> {
+        let name_index = self.utf8(name)?;
+        let descriptor_index = self.utf8(desc)?;
+        self.put(
+            ConstKey::NameAndType(name_index, descriptor_index),
+            Constants::CONSTANT_NameAndType,
+            Const::NameAndType { name_index, descriptor_index },
+        )
+    }
+
+    pub fn field(&mut self, owner: &str, name: &str, desc: &str) -> AsmResult<u16> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, desc)?;
+        self.put(
+            ConstKey::Field(class_index, name_and_type_index),
+            Constants::CONSTANT_Fieldref,
+            Const::Field { class_index, name_and_type_index },
+        )
+    }
+
+    pub fn method(&mut self, owner: &str, name: &str, desc: &str, is_interface: bool) -> AsmResult<u16> {
+        let class_index = self.class(owner)?;
+        let name_and_type_index = self.name_and_type(name, desc)?;
+        if is_interface {
+            self.put(
+                ConstKey::InterfaceMethod(class_index, name_and_type_index),
+                Constants::CONSTANT_InterfaceMethodref,
+                Const::InterfaceMethod { class_index, name_and_type_index },
+            )
+        } else {
+            self.put(
+                ConstKey::Method(class_index, name_and_type_index),
+                Constants::CONSTANT_Methodref,
+                Const::Method { class_index, name_and_type_index },
+            )
+        }
+    }
+
+    pub fn handle(&mut self, handle: &Handle) -> AsmResult<u16> {
+        let Handle { reference_kind, owner, name, desc } = handle;
+        let reference_kind = *reference_kind;
+        let reference_index = match reference_kind {
+            MethodHandleKind::H_GETFIELD | MethodHandleKind::H_GETSTATIC |
+            MethodHandleKind::H_PUTFIELD | MethodHandleKind::H_PUTSTATIC => {
+                self.field(owner, name, desc)?
+            }
+            MethodHandleKind::H_INVOKEINTERFACE => self.method(owner, name, desc, true)?,
+            _ => self.method(owner, name, desc, false)?,
+        };
+        self.put(
+            ConstKey::MethodHandle(reference_kind, reference_index),
+            Constants::CONSTANT_MethodHandle,
+            Const::MethodHandle { reference_kind, reference_index },
+        )
+    }
+
+    pub fn method_type(&mut self, desc: &str) -> AsmResult<u16> {
+        let descriptor_index = self.utf8(desc)?;
+        self.put(
+            ConstKey::MethodType(descriptor_index),
+            Constants::CONSTANT_MethodType,
+            Const::MethodType { descriptor_index },
+        )
+    }
+
+    pub fn invoke_dynamic(&mut self, const_dynamic: &ConstDynamic) -> AsmResult<u16> {
+        let ConstDynamic { name, desc, bsm, bsm_args } = const_dynamic;
+        let bootstrap_method_attr_index = self.bootstrap_method(bsm, bsm_args)?;
+        let name_and_type_index = self.name_and_type(name, desc)?;
+        self.put(
+            ConstKey::InvokeDynamic(bootstrap_method_attr_index, name_and_type_index),
+            Constants::CONSTANT_InvokeDynamic,
+            Const::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index },
+        )
+    }
+
+    /// Constants which can be loaded by `ldc` or used as a `ConstantValue`.
+    pub fn loadable(&mut self, value: &ConstValue) -> AsmResult<u16> {
+        match value {
+            ConstValue::Class(name) => self.class(name),
+            ConstValue::String(s) => self.string(s),
+            ConstValue::Integer(i) => self.integer(*i),
+            ConstValue::Float(f) => self.float(*f),
+            ConstValue::Long(l) => self.long(*l),
+            ConstValue::Double(d) => self.double(*d),
+            ConstValue::MethodHandle(handle) => self.handle(handle),
+            ConstValue::MethodType(desc) => self.method_type(desc),
+            _ => Err(AsmErr::IllegalFormat(format!("cannot write {value:?} as a loadable constant"))),
+        }
+    }
+
+    pub fn bootstrap_argument(&mut self, argument: &BootstrapMethodArgument) -> AsmResult<u16> {
+        match argument {
+            BootstrapMethodArgument::Integer(i) => self.integer(*i),
+            BootstrapMethodArgument::Float(f) => self.float(*f),
+            BootstrapMethodArgument::Long(l) => self.long(*l),
+            BootstrapMethodArgument::Double(d) => self.double(*d),
+            BootstrapMethodArgument::String(s) => self.string(s),
+            BootstrapMethodArgument::Class(c) => self.class(c),
+            BootstrapMethodArgument::Handle(h) => self.handle(h),
+            BootstrapMethodArgument::MethodType(desc) => self.method_type(desc),
+        }
+    }
+
+    pub fn bootstrap_method(&mut self, bsm: &Handle, arguments: &[BootstrapMethodArgument]) -> AsmResult<u16> {
+        let bootstrap_method_ref = self.handle(bsm)?;
+        let mut bootstrap_arguments = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            bootstrap_arguments.push(self.bootstrap_argument(argument)?);
+        }
+        let key = (bootstrap_method_ref, bootstrap_arguments);
+        if let Some(index) = self.bootstrap_indexes.get(&key) {
+            return Ok(*index);
+        }
+        let index = self.bootstrap_methods.len() as u16;
+        let (bootstrap_method_ref, bootstrap_arguments) = key.clone();
+        self.bootstrap_methods.push(BootstrapMethod {
+            bootstrap_method_ref,
+            num_bootstrap_arguments: bootstrap_arguments.len() as u16,
+            bootstrap_arguments,
+        });
+        self.bootstrap_indexes.insert(key, index);
+        Ok(index)
+    }
+
+    /// Wraps an attribute with its name index and computed length.
+    pub fn attr(&mut self, name: &str, info: JvmsAttribute) -> AsmResult<AttributeInfo> {
+        let attribute_name_index = self.utf8(name)?;
+        let mut context = WriteContext { bytes: vec![] };
+        context.write(info.clone());
+        let attribute_length = context.bytes.len() as u32;
+        Ok(AttributeInfo { attribute_name_index, attribute_length, info })
+    }
+
+    pub fn take_bootstrap_methods(&mut self) -> Vec<BootstrapMethod> {
+        self.bootstrap_indexes.clear();
+        std::mem::take(&mut self.bootstrap_methods)
+    }
+
+    pub fn into_pool(self) -> Vec<CPInfo> {
+        self.pool
+    }
+}
@@ -0,0 +1,388 @@
+use crate::constants::{Constants, JavaVersions};
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::node::w::code_writer::{needs_stack_map, write_code};
+use crate::impls::node::w::const_pool::ConstPoolBuilder;
+use crate::impls::utf8_to_mutf8;
+use crate::jvms::attr::annotation::{AnnotationElement, AnnotationElementValue, AnnotationElementValueInfo, AnnotationInfo, ParameterAnnotationInfo};
+use crate::jvms::attr::{Attribute as JvmsAttribute, InnerClassInfo, MethodParameter, RecordComponentInfo};
+use crate::jvms::element::{AttributeInfo, ClassFile, FieldInfo, MethodInfo};
+use crate::node::element::{AnnotationNode, ClassNode, FieldNode, MethodNode, RecordComponentNode, UnknownAttribute};
+use crate::node::values::{AnnotationValue, ConstValue, FieldInitialValue};
+
+/// Writes a [ClassNode] into a jvms [ClassFile] with a freshly built constant pool.
+///
+/// Attributes which still refer to the constant pool they were read from can't be carried over,
+/// so type annotations, the module attributes and non-custom unknown attributes are dropped.
+/// The `StackMapTable` is written with the offsets of the new code, classes of version 51 and
+/// above whose methods have branches but no frames are rejected as the jvm can't verify them.
+pub fn to_jvms_internal(node: &ClassNode) -> AsmResult<ClassFile> {
+    let mut cp = ConstPoolBuilder::new();
+
+    let this_class = cp.class(&node.name)?;
+    let super_class = match &node.super_name {
+        Some(super_name) => cp.class(super_name)?,
+        None => 0,
+    };
+    let mut interfaces = Vec::with_capacity(node.interfaces.len());
+    for interface in &node.interfaces {
+        interfaces.push(cp.class(interface)?);
+    }
+
+    let mut fields = Vec::with_capacity(node.fields.len());
+    for field in &node.fields {
+        fields.push(write_field(&mut cp, field)?);
+    }
+    let mut methods = Vec::with_capacity(node.methods.len());
+    for method in &node.methods {
+        methods.push(write_method(&mut cp, node, method)?);
+    }
+
+    let mut attributes = vec![];
+    if let Some(source_file) = &node.source_file {
+        let sourcefile_index = cp.utf8(source_file)?;
+        attributes.push(cp.attr(Constants::SOURCE_FILE, JvmsAttribute::SourceFile { sourcefile_index })?);
+    }
+    if let Some(source_debug) = &node.source_debug {
+        let debug_extension = utf8_to_mutf8(source_debug.as_bytes())?;
+        let info = JvmsAttribute::SourceDebugExtension { debug_extension };
+        attributes.push(cp.attr(Constants::SOURCE_DEBUG_EXTENSION, info)?);
+    }
+    if let Some(signature) = &node.signature {
+        let signature_index = cp.utf8(signature)?;
+        attributes.push(cp.attr(Constants::SIGNATURE, JvmsAttribute::Signature { signature_index })?);
+    }
+    if let Some(outer_class) = &node.outer_class {
+        let class_index = cp.class(outer_class)?;
+        let method_index = match (&node.outer_method_name, &node.outer_method_desc) {
+            (Some(name), Some(desc)) => cp.name_and_type(name, desc)?,
+            _ => 0,
+        };
+        let info = JvmsAttribute::EnclosingMethod { class_index, method_index };
+        attributes.push(cp.attr(Constants::ENCLOSING_METHOD, info)?);
+    }
+    if !node.inner_classes.is_empty() {
+        let mut classes = Vec::with_capacity(node.inner_classes.len());
+        for inner_class in &node.inner_classes {
+            let outer_class_info_index = match &inner_class.outer_name {
+                Some(outer_name) => cp.class(outer_name)?,
+                None => 0,
+            };
+            let inner_name_index = if inner_class.inner_name.is_empty() {
+                0
+            } else {
+                cp.utf8(&inner_class.inner_name)?
+            };
+            classes.push(InnerClassInfo {
+                inner_class_info_index: cp.class(&inner_class.name)?,
+                outer_class_info_index,
+                inner_name_index,
+                inner_class_access_flags: inner_class.access,
+            });
+        }
+        let info = JvmsAttribute::InnerClasses { number_of_classes: classes.len() as u16, classes };
+        attributes.push(cp.attr(Constants::INNER_CLASSES, info)?);
+    }
+    if let Some(nest_host) = &node.nest_host_class {
+        let host_class_index = cp.class(nest_host)?;
+        attributes.push(cp.attr(Constants::NEST_HOST, JvmsAttribute::NestHost { host_class_index })?);
+    }
+    if !node.nest_members.is_empty() {
+        let classes = class_indexes(&mut cp, &node.nest_members)?;
+        let info = JvmsAttribute::NestMembers { number_of_classes: classes.len() as u16, classes };
+        attributes.push(cp.attr(Constants::NEST_MEMBERS, info)?);
+    }
+    if !node.permitted_subclasses.is_empty() {
+        let classes = class_indexes(&mut cp, &node.permitted_subclasses)?;
+        let info = JvmsAttribute::PermittedSubclasses { number_of_classes: classes.len() as u16, classes };
+        attributes.push(cp.attr(Constants::PERMITTED_SUBCLASSES, info)?);
+    }
+    if !node.record_components.is_empty() {
+        let mut components = Vec::with_capacity(node.record_components.len());
+        for component in &node.record_components {
+            components.push(write_record_component(&mut cp, component)?);
+        }
+        let info = JvmsAttribute::Record { components_count: components.len() as u16, components };
+        attributes.push(cp.attr(Constants::RECORD, info)?);
+    }
+    write_annotations(&mut cp, &node.annotations, &mut attributes)?;
+    write_unknown_attrs(&mut cp, &node.attrs, &mut attributes)?;
+
+    // must be the last one, all bootstrap methods are collected while writing method codes.
+    let bootstrap_methods = cp.take_bootstrap_methods();
+    if !bootstrap_methods.is_empty() {
+        let info = JvmsAttribute::BootstrapMethods {
+            num_bootstrap_methods: bootstrap_methods.len() as u16,
+            bootstrap_methods,
+        };
+        attributes.push(cp.attr(Constants::BOOTSTRAP_METHODS, info)?);
+    }
+
+    let constant_pool = cp.into_pool();
+    Ok(ClassFile {
+        magic: 0xCAFEBABE,
+        minor_version: node.minor_version,
+        major_version: node.major_version,
+        constant_pool_count: constant_pool.len() as u16,
+        constant_pool,
+        access_flags: node.access,
+        this_class,
+        super_class,
+        interfaces_count: interfaces.len() as u16,
+        interfaces,
+        fields_count: fields.len() as u16,
+        fields,
+        methods_count: methods.len() as u16,
+        methods,
+        attributes_count: attributes.len() as u16,
+        attributes,
+    })
+}
+
+fn class_indexes(cp: &mut ConstPoolBuilder, names: &[crate::InternalNameRef]) -> AsmResult<Vec<u16>> {
+    let mut indexes = Vec::with_capacity(names.len());
+    for name in names {
+        indexes.push(cp.class(name)?);
+    }
+    Ok(indexes)
+}
+
+fn write_field(cp: &mut ConstPoolBuilder, field: &FieldNode) -> AsmResult<FieldInfo> {
+    let name_index = cp.utf8(&field.name)?;
+    let descriptor_index = cp.utf8(&field.desc)?;
+    let mut attributes = vec![];
+    if let Some(value) = &field.value {
+        let constantvalue_index = match value {
+            FieldInitialValue::Integer(i) => cp.integer(*i)?,
+            FieldInitialValue::Float(f) => cp.float(*f)?,
+            FieldInitialValue::Long(l) => cp.long(*l)?,
+            FieldInitialValue::Double(d) => cp.double(*d)?,
+            FieldInitialValue::String(s) => cp.string(s)?,
+        };
+        let info = JvmsAttribute::ConstantValue { constantvalue_index };
+        attributes.push(cp.attr(Constants::CONSTANT_VALUE, info)?);
+    }
+    if let Some(signature) = &field.signature {
+        let signature_index = cp.utf8(signature)?;
+        attributes.push(cp.attr(Constants::SIGNATURE, JvmsAttribute::Signature { signature_index })?);
+    }
+    write_annotations(cp, &field.annotations, &mut attributes)?;
+    write_unknown_attrs(cp, &field.attrs, &mut attributes)?;
+    Ok(FieldInfo {
+        access_flags: field.access,
+        name_index,
+        descriptor_index,
+        attributes_count: attributes.len() as u16,
+        attributes,
+    })
+}
+
+fn write_method(cp: &mut ConstPoolBuilder, class: &ClassNode, method: &MethodNode) -> AsmResult<MethodInfo> {
+    let name_index = cp.utf8(&method.name)?;
+    let descriptor_index = cp.utf8(&method.desc)?;
+    let mut attributes = vec![];
+    if let Some(code_body) = method.code()? {
+        if class.major_version as u32 >= JavaVersions::V1_7
+            && code_body.stack_map_table.is_empty() && needs_stack_map(code_body) {
+            let err_msg = format!(
+                "{}.{}{} has no stack map frames, which are required by class file version {}",
+                class.name, method.name, method.desc, class.major_version,
+            );
+            return Err(AsmErr::IllegalFormat(err_msg));
+        }
+        let info = write_code(cp, code_body)?;
+        attributes.push(cp.attr(Constants::CODE, info)?);
+    }
+    if !method.exceptions.is_empty() {
+        let exception_index_table = class_indexes(cp, &method.exceptions)?;
+        let info = JvmsAttribute::Exceptions {
+            number_of_exceptions: exception_index_table.len() as u16,
+            exception_index_table,
+        };
+        attributes.push(cp.attr(Constants::EXCEPTIONS, info)?);
+    }
+    if let Some(signature) = &method.signature {
+        let signature_index = cp.utf8(signature)?;
+        attributes.push(cp.attr(Constants::SIGNATURE, JvmsAttribute::Signature { signature_index })?);
+    }
+    if !method.parameters.is_empty() {
+        let mut parameters = Vec::with_capacity(method.parameters.len());
+        for parameter in &method.parameters {
+            let name_index = match &parameter.name {
+                Some(name) => cp.utf8(name)?,
+                None => 0,
+            };
+            parameters.push(MethodParameter { name_index, access_flags: parameter.access });
+        }
+        let info = JvmsAttribute::MethodParameters { parameters_count: parameters.len() as u8, parameters };
+        attributes.push(cp.attr(Constants::METHOD_PARAMETERS, info)?);
+    }
+    write_annotations(cp, &method.annotations, &mut attributes)?;
+    write_parameter_annotations(cp, &method.parameter_annotations, &mut attributes)?;
+    if let Some(default_value) = &method.annotation_default {
+        let default_value = element_value(cp, default_value)?;
+        let info = JvmsAttribute::AnnotationDefault { default_value };
+        attributes.push(cp.attr(Constants::ANNOTATION_DEFAULT, info)?);
+    }
+    write_unknown_attrs(cp, &method.attrs, &mut attributes)?;
+    Ok(MethodInfo {
+        access_flags: method.access,
+        name_index,
+        descriptor_index,
+        attributes_count: attributes.len() as u16,
+        attributes,
+    })
+}
+
+fn write_record_component(cp: &mut ConstPoolBuilder, component: &RecordComponentNode) -> AsmResult<RecordComponentInfo> {
+    let name_index = cp.utf8(&component.name)?;
+    let descriptor_index = cp.utf8(&component.desc)?;
+    let mut attributes = vec![];
+    if let Some(signature) = &component.signature {
+        let signature_index = cp.utf8(signature)?;
+        attributes.push(cp.attr(Constants::SIGNATURE, JvmsAttribute::Signature { signature_index })?);
+    }
+    write_annotations(cp, &component.annotations, &mut attributes)?;
+    write_unknown_attrs(cp, &component.attrs, &mut attributes)?;
+    Ok(RecordComponentInfo {
+        name_index,
+        descriptor_index,
+        attributes_count: attributes.len() as u16,
+        attributes,
+    })
+}
+
+fn write_annotations(
+    cp: &mut ConstPoolBuilder, annotations: &[AnnotationNode], attributes: &mut Vec<AttributeInfo>,
+) -> AsmResult<()> {
+    let (visible, invisible): (Vec<_>, Vec<_>) = annotations.iter().partition(|a| a.visible);
+    for (name, annotations) in [
+        (Constants::RUNTIME_VISIBLE_ANNOTATIONS, visible),
+        (Constants::RUNTIME_INVISIBLE_ANNOTATIONS, invisible),
+    ] {
+        if annotations.is_empty() { continue; }
+        let mut infos = Vec::with_capacity(annotations.len());
+        for annotation in annotations {
+            infos.push(annotation_info(cp, annotation)?);
+        }
+        let info = if name == Constants::RUNTIME_VISIBLE_ANNOTATIONS {
+            JvmsAttribute::RuntimeVisibleAnnotations { num_annotations: infos.len() as u16, annotations: infos }
+        } else {
+            JvmsAttribute::RuntimeInvisibleAnnotations { num_annotations: infos.len() as u16, annotations: infos }
+        };
+        attributes.push(cp.attr(name, info)?);
+    }
+    Ok(())
+}
+
+fn write_parameter_annotations(
+    cp: &mut ConstPoolBuilder, parameters: &[Vec<AnnotationNode>], attributes: &mut Vec<AttributeInfo>,
+) -> AsmResult<()> {
+    for visible in [true, false] {
+        if !parameters.iter().flatten().any(|a| a.visible == visible) { continue; }
+        let mut parameter_annotations = Vec::with_capacity(parameters.len());
+        for annotations in parameters {
+            let mut infos = vec![];
+            for annotation in annotations.iter().filter(|a| a.visible == visible) {
+                infos.push(annotation_info(cp, annotation)?);
+            }
+            parameter_annotations.push(ParameterAnnotationInfo {
+                num_annotations: infos.len() as u16,
+                annotations: infos,
+            });
+        }
+        let num_parameters = parameter_annotations.len() as u8;
+        let (name, info) = if visible {
+            (Constants::RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS,
+             JvmsAttribute::RuntimeVisibleParameterAnnotations { num_parameters, parameter_annotations })
+        } else {
+            (Constants::RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS,
+             JvmsAttribute::RuntimeInvisibleParameterAnnotations { num_parameters, parameter_annotations })
+        };
+        attributes.push(cp.attr(name, info)?);
+    }
+    Ok(())
+}
+
+fn annotation_info(cp: &mut ConstPoolBuilder, annotation: &AnnotationNode) -> AsmResult<AnnotationInfo> {
+    let type_index = cp.utf8(&annotation.type_name)?;
+    let mut element_value_pairs = Vec::with_capacity(annotation.values.len());
+    for (name, value) in &annotation.values {
+        element_value_pairs.push(AnnotationElement {
+            element_name_index: cp.utf8(name)?,
+            value: element_value(cp, value)?,
+        });
+    }
+    Ok(AnnotationInfo {
+        type_index,
+        num_element_value_pairs: element_value_pairs.len() as u16,
+        element_value_pairs,
+    })
+}
+
+/// [AnnotationValue::Const] doesn't keep the original element tag, so `boolean`, `byte`,
+/// `char` and `short` values are written as `int` ones.
+fn element_value(cp: &mut ConstPoolBuilder, value: &AnnotationValue) -> AsmResult<AnnotationElementValueInfo> {
+    let info = match value {
+        AnnotationValue::Const(value) => {
+            let (tag, const_value_index) = match value.as_ref() {
+                ConstValue::Integer(i) => (b'I', cp.integer(*i)?),
+                ConstValue::Float(f) => (b'F', cp.float(*f)?),
+                ConstValue::Long(l) => (b'J', cp.long(*l)?),
+                ConstValue::Double(d) => (b'D', cp.double(*d)?),
+                ConstValue::String(s) => (b's', cp.utf8(s)?),
+                _ => {
+                    let err_msg = format!("cannot write {value:?} as an annotation element");
+                    return Err(AsmErr::IllegalFormat(err_msg));
+                }
+            };
+            AnnotationElementValueInfo { tag, value: AnnotationElementValue::Const { const_value_index } }
+        }
+        AnnotationValue::Enum(type_name, const_name) => AnnotationElementValueInfo {
+            tag: b'e',
+            value: AnnotationElementValue::EnumConst {
+                type_name_index: cp.utf8(type_name)?,
+                const_name_index: cp.utf8(const_name)?,
+            },
+        },
+        AnnotationValue::Class(class) => AnnotationElementValueInfo {
+            tag: b'c',
+            value: AnnotationElementValue::Class { class_info_index: cp.utf8(class)? },
+        },
+        AnnotationValue::Annotation(annotation) => AnnotationElementValueInfo {
+            tag: b'@',
+            value: AnnotationElementValue::Annotation { annotation_value: annotation_info(cp, annotation)? },
+        },
+        AnnotationValue::Array(values) => {
+            let mut infos = Vec::with_capacity(values.len());
+            for value in values {
+                infos.push(element_value(cp, value)?);
+            }
+            AnnotationElementValueInfo {
+                tag: b'[',
+                value: AnnotationElementValue::Array { num_values: infos.len() as u16, values: infos },
+            }
+        }
+    };
+    Ok(info)
+}
+
+fn write_unknown_attrs(
+    cp: &mut ConstPoolBuilder, attrs: &[UnknownAttribute], attributes: &mut Vec<AttributeInfo>,
+) -> AsmResult<()> {
+    for attr in attrs {
+        if let Some(attr) = portable_attr(cp, attr)? {
+            attributes.push(attr);
+        }
+    }
+    Ok(())
+}
+
+/// Unknown attributes which don't refer to the original constant pool, others are dropped.
+pub(crate) fn portable_attr(cp: &mut ConstPoolBuilder, attr: &UnknownAttribute) -> AsmResult<Option<AttributeInfo>> {
+    match &attr.origin {
+        JvmsAttribute::Custom(_) | JvmsAttribute::Synthetic | JvmsAttribute::Deprecated => {
+            Ok(Some(cp.attr(&attr.name, attr.origin.clone())?))
+        }
+        _ => Ok(None),
+    }
+}
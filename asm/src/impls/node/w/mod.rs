@@ -0,0 +1,3 @@
+pub(crate) mod const_pool;
+pub(crate) mod code_writer;
+pub(crate) mod impls;
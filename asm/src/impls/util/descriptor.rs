@@ -0,0 +1,45 @@
+/// Splits the argument types out of a method descriptor, e.g. `(IJ[Ljava/lang/String;)V`
+/// gives `["I", "J", "[Ljava/lang/String;"]`. Malformed tails are ignored.
+pub(crate) fn method_arg_types(desc: &str) -> Vec<&str> {
+    let mut res = vec![];
+    let Some(args) = desc.strip_prefix('(').and_then(|d| d.split(')').next()) else {
+        return res;
+    };
+    let bytes = args.as_bytes();
+    let mut cur = 0;
+    while cur < bytes.len() {
+        let start = cur;
+        while cur < bytes.len() && bytes[cur] == b'[' { cur += 1; }
+        if cur < bytes.len() && bytes[cur] == b'L' {
+            while cur < bytes.len() && bytes[cur] != b';' { cur += 1; }
+        }
+        cur += 1;
+        res.push(&args[start..cur.min(args.len())]);
+    }
+    res
+}
+
+/// The return type of a method descriptor, e.g. `V` for `(I)V`.
+pub(crate) fn method_return_type(desc: &str) -> &str {
+    desc.rsplit_once(')').map(|(_, ret)| ret).unwrap_or(desc)
+}
+
+/// Slots taken by a value of the given field descriptor, 2 for long & double, 0 for void.
+pub(crate) fn type_slots(desc: &str) -> u16 {
+    match desc.as_bytes().first() {
+        Some(b'J') | Some(b'D') => 2,
+        Some(b'V') | None => 0,
+        _ => 1,
+    }
+}
+
+/// Slots taken by all arguments of a method descriptor, not including `this`.
+pub(crate) fn method_arg_slots(desc: &str) -> u16 {
+    method_arg_types(desc).iter().map(|t| type_slots(t)).sum()
+}
+
+/// The internal name of a class type descriptor, e.g. `java/lang/String` for
+/// `Ljava/lang/String;`. Array descriptors are already internal names and returned as is.
+pub(crate) fn desc_to_internal(desc: &str) -> &str {
+    desc.strip_prefix('L').and_then(|d| d.strip_suffix(';')).unwrap_or(desc)
+}
@@ -1,10 +1,12 @@
 pub(crate) use ex::*;
 pub(crate) use mutf8::*;
+pub(crate) use descriptor::*;
 pub use once_vec::*;
 pub use refs::*;
 
 mod ex;
 mod mutf8;
+mod descriptor;
 mod once_vec;
 mod computable;
 pub(crate) mod refs;
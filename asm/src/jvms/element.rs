@@ -45,7 +45,10 @@ pub struct ClassFile {
 //     u1 tag;
 //     u1 info[];
 // }
-#[derive(Clone, Debug, WriteInto)]
+/// [Const::Invalid] entries are placeholders which only keeps the index of [ClassFile::constant_pool]
+/// same as the constant pool index, e.g. the slot 0 or the slot after a long / double constant.
+/// They won't be written into the class file.
+#[derive(Clone, Debug)]
 pub struct CPInfo {
     pub tag: u8,
     pub info: Const,
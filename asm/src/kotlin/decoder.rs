@@ -0,0 +1,262 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::kotlin::proto::Fields;
+use crate::kotlin::strings::StringTable;
+use crate::kotlin::*;
+
+/// Types may be nested in each other, this limits malformed metadata.
+const MAX_TYPE_DEPTH: usize = 64;
+
+/// Builds declarations from the messages of `metadata.proto`. Field numbers follow the
+/// protobuf definitions of Kotlin.
+pub(crate) struct Decoder<'a> {
+    strings: &'a StringTable,
+    /// Types of the enclosing classes, packages and functions, which may be referred by id.
+    type_tables: Vec<TypeTable<'a>>,
+    /// Ids and names of type parameters in scope.
+    type_parameters: Vec<(u32, StrRef)>,
+}
+
+struct TypeTable<'a> {
+    types: Vec<&'a [u8]>,
+    /// Types from this index on are nullable.
+    first_nullable: Option<usize>,
+}
+
+fn missing(field: &str) -> AsmErr {
+    AsmErr::IllegalFormat(format!("kotlin metadata: missing {field}"))
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(strings: &'a StringTable) -> Self {
+        Decoder { strings, type_tables: Vec::new(), type_parameters: Vec::new() }
+    }
+
+    fn string(&self, fields: &Fields, number: u32, field: &str) -> AsmResult<StrRef> {
+        self.strings.get(fields.int(number).ok_or_else(|| missing(field))?)
+    }
+
+    fn strings(&self, fields: &Fields, number: u32) -> AsmResult<Vec<StrRef>> {
+        fields.ints(number)?.into_iter().map(|index| self.strings.get(index)).collect()
+    }
+
+    /// Runs `decode` with the type table and the type parameters of a declaration in scope.
+    fn scoped<T>(
+        &mut self, fields: &Fields<'a>, type_table: Option<u32>, type_parameters: Option<u32>,
+        decode: impl FnOnce(&mut Self, Vec<KotlinTypeParameter>) -> AsmResult<T>,
+    ) -> AsmResult<T> {
+        let table = match type_table.map(|number| fields.message(number)).transpose()?.flatten() {
+            Some(table) => {
+                // `first_nullable` is -1 if no type is nullable
+                let first_nullable = table.int(2).filter(|index| (*index as i32) >= 0).map(|index| index as usize);
+                Some(TypeTable { types: table.all_bytes(1).collect(), first_nullable })
+            }
+            None => None,
+        };
+        let pushed_table = table.is_some();
+        if let Some(table) = table {
+            self.type_tables.push(table);
+        }
+        let scope = self.type_parameters.len();
+        let result = self.type_parameter_list(fields, type_parameters)
+            .and_then(|type_parameters| decode(self, type_parameters));
+        self.type_parameters.truncate(scope);
+        if pushed_table {
+            self.type_tables.pop();
+        }
+        result
+    }
+
+    fn type_parameter_list(&mut self, fields: &Fields<'a>, number: Option<u32>) -> AsmResult<Vec<KotlinTypeParameter>> {
+        let Some(number) = number else { return Ok(Vec::new()); };
+        let messages = fields.messages(number)?;
+        // bounds may refer to any parameter of the list, e.g. `<T : Comparable<U>, U>`
+        for parameter in &messages {
+            let id = parameter.int(1).ok_or_else(|| missing("type parameter id"))?;
+            self.type_parameters.push((id, self.string(parameter, 2, "type parameter name")?));
+        }
+        let mut type_parameters = Vec::with_capacity(messages.len());
+        for parameter in &messages {
+            let variance = match parameter.int(4) {
+                Some(0) => KotlinVariance::In,
+                Some(1) => KotlinVariance::Out,
+                _ => KotlinVariance::Invariant,
+            };
+            type_parameters.push(KotlinTypeParameter {
+                id: parameter.int(1).unwrap_or_default(),
+                name: self.string(parameter, 2, "type parameter name")?,
+                reified: parameter.bool(3),
+                variance,
+                upper_bounds: self.types(parameter, 5, 6)?,
+            });
+        }
+        Ok(type_parameters)
+    }
+
+    pub(crate) fn class(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinClass> {
+        self.scoped(fields, Some(30), Some(5), |decoder, type_parameters| {
+            let enum_entries = fields.messages(13)?.iter()
+                .filter_map(|entry| entry.int(1))
+                .map(|index| decoder.strings.get(index))
+                .collect::<AsmResult<_>>()?;
+            Ok(KotlinClass {
+                name: decoder.string(fields, 3, "class name")?,
+                flags: fields.int(1).unwrap_or(6),
+                type_parameters,
+                supertypes: decoder.types(fields, 6, 2)?,
+                constructors: fields.messages(8)?.iter().map(|c| decoder.constructor(c)).collect::<AsmResult<_>>()?,
+                functions: fields.messages(9)?.iter().map(|f| decoder.function(f)).collect::<AsmResult<_>>()?,
+                properties: fields.messages(10)?.iter().map(|p| decoder.property(p)).collect::<AsmResult<_>>()?,
+                type_aliases: fields.messages(11)?.iter().map(|t| decoder.type_alias(t)).collect::<AsmResult<_>>()?,
+                nested_classes: decoder.strings(fields, 7)?,
+                enum_entries,
+                sealed_subclasses: decoder.strings(fields, 16)?,
+                companion_object: fields.int(4).map(|index| decoder.strings.get(index)).transpose()?,
+            })
+        })
+    }
+
+    pub(crate) fn package(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinPackage> {
+        self.scoped(fields, Some(30), None, |decoder, _| {
+            Ok(KotlinPackage {
+                functions: fields.messages(3)?.iter().map(|f| decoder.function(f)).collect::<AsmResult<_>>()?,
+                properties: fields.messages(4)?.iter().map(|p| decoder.property(p)).collect::<AsmResult<_>>()?,
+                type_aliases: fields.messages(5)?.iter().map(|t| decoder.type_alias(t)).collect::<AsmResult<_>>()?,
+            })
+        })
+    }
+
+    fn constructor(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinConstructor> {
+        Ok(KotlinConstructor {
+            flags: fields.int(1).unwrap_or(6),
+            parameters: self.value_parameters(fields, 2)?,
+        })
+    }
+
+    pub(crate) fn function(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinFunction> {
+        self.scoped(fields, Some(30), Some(4), |decoder, type_parameters| {
+            Ok(KotlinFunction {
+                name: decoder.string(fields, 2, "function name")?,
+                flags: fields.int(9).unwrap_or(6),
+                type_parameters,
+                receiver: decoder.optional_type(fields, 5, 8)?,
+                parameters: decoder.value_parameters(fields, 6)?,
+                return_type: decoder.optional_type(fields, 3, 7)?.ok_or_else(|| missing("return type"))?,
+            })
+        })
+    }
+
+    fn property(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinProperty> {
+        self.scoped(fields, None, Some(4), |decoder, type_parameters| {
+            Ok(KotlinProperty {
+                name: decoder.string(fields, 2, "property name")?,
+                flags: fields.int(11).unwrap_or(518),
+                type_parameters,
+                receiver: decoder.optional_type(fields, 5, 10)?,
+                return_type: decoder.optional_type(fields, 3, 9)?.ok_or_else(|| missing("property type"))?,
+            })
+        })
+    }
+
+    fn type_alias(&mut self, fields: &Fields<'a>) -> AsmResult<KotlinTypeAlias> {
+        self.scoped(fields, None, Some(3), |decoder, type_parameters| {
+            Ok(KotlinTypeAlias {
+                name: decoder.string(fields, 2, "type alias name")?,
+                flags: fields.int(1).unwrap_or(6),
+                type_parameters,
+                underlying_type: decoder.optional_type(fields, 4, 5)?.ok_or_else(|| missing("underlying type"))?,
+                expanded_type: decoder.optional_type(fields, 6, 7)?.ok_or_else(|| missing("expanded type"))?,
+            })
+        })
+    }
+
+    fn value_parameters(&mut self, fields: &Fields<'a>, number: u32) -> AsmResult<Vec<KotlinValueParameter>> {
+        let mut parameters = Vec::new();
+        for parameter in fields.messages(number)? {
+            parameters.push(KotlinValueParameter {
+                name: self.string(&parameter, 2, "parameter name")?,
+                flags: parameter.int(1).unwrap_or_default(),
+                value_type: self.optional_type(&parameter, 3, 5)?.ok_or_else(|| missing("parameter type"))?,
+                vararg_element_type: self.optional_type(&parameter, 4, 6)?,
+            });
+        }
+        Ok(parameters)
+    }
+
+    /// Types of a repeated field, which are either messages or ids of the type table.
+    fn types(&mut self, fields: &Fields<'a>, message: u32, id: u32) -> AsmResult<Vec<KotlinType>> {
+        let mut types = Vec::new();
+        for type_fields in fields.messages(message)? {
+            types.push(self.kotlin_type(&type_fields, 0)?);
+        }
+        for type_id in fields.ints(id)? {
+            types.push(self.type_by_id(type_id, 0)?);
+        }
+        Ok(types)
+    }
+
+    fn optional_type(&mut self, fields: &Fields<'a>, message: u32, id: u32) -> AsmResult<Option<KotlinType>> {
+        self.nested_type(fields, message, id, 0)
+    }
+
+    fn nested_type(&mut self, fields: &Fields<'a>, message: u32, id: u32, depth: usize) -> AsmResult<Option<KotlinType>> {
+        if let Some(type_fields) = fields.message(message)? {
+            return self.kotlin_type(&type_fields, depth).map(Some);
+        }
+        fields.int(id).map(|type_id| self.type_by_id(type_id, depth)).transpose()
+    }
+
+    fn type_by_id(&mut self, id: u32, depth: usize) -> AsmResult<KotlinType> {
+        let table = self.type_tables.last().ok_or_else(|| missing("type table"))?;
+        let bytes = *table.types.get(id as usize)
+            .ok_or_else(|| AsmErr::IllegalFormat(format!("kotlin metadata: type id {id} is out of bounds")))?;
+        let nullable = table.first_nullable.is_some_and(|first| id as usize >= first);
+        let mut kotlin_type = self.kotlin_type(&Fields::parse(bytes)?, depth)?;
+        kotlin_type.nullable |= nullable;
+        Ok(kotlin_type)
+    }
+
+    fn kotlin_type(&mut self, fields: &Fields<'a>, depth: usize) -> AsmResult<KotlinType> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(AsmErr::IllegalFormat("kotlin metadata: types are nested too deep".to_string()));
+        }
+        let classifier = if let Some(class_name) = fields.int(6) {
+            KotlinClassifier::Class(self.strings.get(class_name)?)
+        } else if let Some(id) = fields.int(7) {
+            let name = self.type_parameters.iter().rev()
+                .find(|(parameter_id, _)| *parameter_id == id)
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| StrRef::from(format!("T#{id}")));
+            KotlinClassifier::TypeParameter(name)
+        } else if let Some(name) = fields.int(9) {
+            KotlinClassifier::TypeParameter(self.strings.get(name)?)
+        } else if let Some(alias) = fields.int(12) {
+            KotlinClassifier::TypeAlias(self.strings.get(alias)?)
+        } else {
+            return Err(missing("classifier of type"));
+        };
+        let mut arguments = Vec::new();
+        for argument in fields.messages(2)? {
+            let variance = match argument.int(1) {
+                Some(0) => KotlinVariance::In,
+                Some(1) => KotlinVariance::Out,
+                Some(3) => {
+                    arguments.push(KotlinTypeArgument::Star);
+                    continue;
+                }
+                _ => KotlinVariance::Invariant,
+            };
+            let argument = match self.nested_type(&argument, 2, 3, depth + 1)? {
+                Some(argument_type) => KotlinTypeArgument::Projection(variance, argument_type),
+                None => KotlinTypeArgument::Star,
+            };
+            arguments.push(argument);
+        }
+        Ok(KotlinType {
+            classifier,
+            arguments,
+            nullable: fields.bool(3),
+            suspend: fields.int(1).is_some_and(|flags| flags & 1 != 0),
+            flexible_upper_bound: self.nested_type(fields, 5, 8, depth + 1)?.map(Box::new),
+        })
+    }
+}
@@ -0,0 +1,137 @@
+use crate::dex::{ClassDef, DexFileAccessor};
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::ToStringRef;
+use crate::kotlin::decoder::Decoder;
+use crate::kotlin::proto::Fields;
+use crate::kotlin::strings::{decode_bytes, split_string_table, StringTable};
+use crate::kotlin::KotlinDeclarations;
+use crate::node::element::{AnnotationNode, ClassNode};
+use crate::node::values::{AnnotationValue, ConstValue};
+use crate::smali::{stb, SmaliNode};
+use crate::StrRef;
+
+/// The values of a `@kotlin.Metadata` annotation, declarations are encoded in `d1` and `d2`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KotlinMetadata {
+    /// `k`, see [KotlinMetadata::CLASS] and the other kinds.
+    pub kind: i32,
+    /// `mv`, e.g. `[1, 9, 0]`.
+    pub version: Vec<i32>,
+    pub data1: Vec<StrRef>,
+    pub data2: Vec<StrRef>,
+    /// `xs`, the facade class of a multi-file class part.
+    pub extra_string: Option<StrRef>,
+    /// `pn`, the package name if it differs from the package of the class.
+    pub package_name: Option<StrRef>,
+    /// `xi`, flags of the compiler.
+    pub extra_int: i32,
+}
+
+impl KotlinMetadata {
+    pub const DESCRIPTOR: &'static str = "Lkotlin/Metadata;";
+
+    pub const CLASS: i32 = 1;
+    pub const FILE_FACADE: i32 = 2;
+    pub const SYNTHETIC_CLASS: i32 = 3;
+    pub const MULTI_FILE_CLASS_FACADE: i32 = 4;
+    pub const MULTI_FILE_CLASS_PART: i32 = 5;
+
+    /// Reads a `@kotlin.Metadata` annotation, [None] for other annotations.
+    pub fn from_annotation(annotation: &AnnotationNode) -> Option<KotlinMetadata> {
+        if &*annotation.type_name != Self::DESCRIPTOR { return None; }
+        // `k` is 1 if it is missing
+        let mut metadata = KotlinMetadata { kind: Self::CLASS, ..Default::default() };
+        for (name, value) in &annotation.values {
+            match &**name {
+                "k" => metadata.kind = int_value(value).unwrap_or(Self::CLASS),
+                "mv" => metadata.version = array_values(value).filter_map(int_value).collect(),
+                "d1" => metadata.data1 = array_values(value).filter_map(string_value).collect(),
+                "d2" => metadata.data2 = array_values(value).filter_map(string_value).collect(),
+                "xs" => metadata.extra_string = string_value(value).filter(|s| !s.is_empty()),
+                "pn" => metadata.package_name = string_value(value).filter(|s| !s.is_empty()),
+                "xi" => metadata.extra_int = int_value(value).unwrap_or_default(),
+                _ => {}
+            }
+        }
+        Some(metadata)
+    }
+
+    pub fn from_class_node(class_node: &ClassNode) -> Option<KotlinMetadata> {
+        class_node.annotations.iter().find_map(Self::from_annotation)
+    }
+
+    pub fn from_dex_class(accessor: &DexFileAccessor, class_def: &ClassDef) -> AsmResult<Option<KotlinMetadata>> {
+        let annotations = class_def.class_annotations(accessor)?;
+        Ok(annotations.iter().find_map(Self::from_annotation))
+    }
+
+    /// The version of the metadata, e.g. `1.9.0`.
+    pub fn version_string(&self) -> String {
+        self.version.iter().map(|part| part.to_string()).collect::<Vec<_>>().join(".")
+    }
+
+    /// The decoded declarations as smali comments, or the error if they can't be decoded.
+    pub fn to_smali(&self) -> SmaliNode {
+        let header = format!("# kotlin metadata {}", self.version_string());
+        let lines = match self.decode() {
+            Ok(declarations) => declarations.to_string(),
+            Err(err) => format!("failed to decode: {err:?}"),
+        };
+        let children = lines.lines()
+            .map(|line| stb().other(format!("# {line}").to_ref()).s())
+            .collect();
+        stb().other(header.to_ref()).s_with_children(children)
+    }
+
+    /// Decodes the declarations in `d1` and `d2`.
+    pub fn decode(&self) -> AsmResult<KotlinDeclarations> {
+        match self.kind {
+            Self::MULTI_FILE_CLASS_FACADE => {
+                return Ok(KotlinDeclarations::MultiFileClassFacade(self.data1.clone()));
+            }
+            Self::SYNTHETIC_CLASS if self.data1.is_empty() => {
+                return Ok(KotlinDeclarations::SyntheticClass(None));
+            }
+            Self::CLASS | Self::FILE_FACADE | Self::SYNTHETIC_CLASS | Self::MULTI_FILE_CLASS_PART => {}
+            kind => return Err(AsmErr::IllegalFormat(format!("kotlin metadata: unknown kind {kind}"))),
+        }
+        let bytes = decode_bytes(&self.data1)?;
+        let (string_table, message) = split_string_table(&bytes)?;
+        let strings = StringTable::new(&Fields::parse(string_table)?, &self.data2)?;
+        let message = Fields::parse(message)?;
+        let mut decoder = Decoder::new(&strings);
+        let declarations = match self.kind {
+            Self::CLASS => KotlinDeclarations::Class(decoder.class(&message)?),
+            Self::SYNTHETIC_CLASS => KotlinDeclarations::SyntheticClass(Some(decoder.function(&message)?)),
+            _ => KotlinDeclarations::Package(decoder.package(&message)?),
+        };
+        Ok(declarations)
+    }
+}
+
+fn int_value(value: &AnnotationValue) -> Option<i32> {
+    match value {
+        AnnotationValue::Const(value) => match **value {
+            ConstValue::Integer(value) => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn string_value(value: &AnnotationValue) -> Option<StrRef> {
+    match value {
+        AnnotationValue::Const(value) => match &**value {
+            ConstValue::String(value) => Some(value.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn array_values(value: &AnnotationValue) -> impl Iterator<Item=&AnnotationValue> {
+    match value {
+        AnnotationValue::Array(values) => values.iter(),
+        _ => [].iter(),
+    }
+}
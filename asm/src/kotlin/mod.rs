@@ -0,0 +1,274 @@
+pub use metadata::*;
+
+mod decoder;
+mod metadata;
+mod proto;
+mod strings;
+mod summary;
+
+use crate::StrRef;
+
+/// Declarations decoded from the `d1` and `d2` of a `@kotlin.Metadata`, see [KotlinMetadata::decode].
+#[derive(Clone, Debug, PartialEq)]
+pub enum KotlinDeclarations {
+    Class(KotlinClass),
+    /// Top level declarations of a file facade (`FooKt`) or a part of a multi-file class.
+    Package(KotlinPackage),
+    /// A synthetic class, with the function of it if it is a lambda.
+    SyntheticClass(Option<KotlinFunction>),
+    /// A multi-file class facade with the internal names of its parts.
+    MultiFileClassFacade(Vec<StrRef>),
+}
+
+/// Class names in Kotlin metadata use `/` for packages and `.` for nested classes,
+/// e.g. `kotlin/collections/Map.Entry`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinClass {
+    pub name: StrRef,
+    pub flags: u32,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    pub supertypes: Vec<KotlinType>,
+    pub constructors: Vec<KotlinConstructor>,
+    pub functions: Vec<KotlinFunction>,
+    pub properties: Vec<KotlinProperty>,
+    pub type_aliases: Vec<KotlinTypeAlias>,
+    /// Simple names of nested classes.
+    pub nested_classes: Vec<StrRef>,
+    pub enum_entries: Vec<StrRef>,
+    pub sealed_subclasses: Vec<StrRef>,
+    /// Simple name of the companion object.
+    pub companion_object: Option<StrRef>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KotlinPackage {
+    pub functions: Vec<KotlinFunction>,
+    pub properties: Vec<KotlinProperty>,
+    pub type_aliases: Vec<KotlinTypeAlias>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinConstructor {
+    pub flags: u32,
+    pub parameters: Vec<KotlinValueParameter>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinFunction {
+    pub name: StrRef,
+    pub flags: u32,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    /// The receiver of an extension function.
+    pub receiver: Option<KotlinType>,
+    pub parameters: Vec<KotlinValueParameter>,
+    pub return_type: KotlinType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinProperty {
+    pub name: StrRef,
+    pub flags: u32,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    /// The receiver of an extension property.
+    pub receiver: Option<KotlinType>,
+    pub return_type: KotlinType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinTypeAlias {
+    pub name: StrRef,
+    pub flags: u32,
+    pub type_parameters: Vec<KotlinTypeParameter>,
+    pub underlying_type: KotlinType,
+    /// The underlying type with all type aliases in it expanded.
+    pub expanded_type: KotlinType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinValueParameter {
+    pub name: StrRef,
+    pub flags: u32,
+    pub value_type: KotlinType,
+    /// The element type of a `vararg` parameter, whose [KotlinValueParameter::value_type] is an array.
+    pub vararg_element_type: Option<KotlinType>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinTypeParameter {
+    /// Types refer to type parameters by this id.
+    pub id: u32,
+    pub name: StrRef,
+    pub reified: bool,
+    pub variance: KotlinVariance,
+    pub upper_bounds: Vec<KotlinType>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KotlinType {
+    pub classifier: KotlinClassifier,
+    pub arguments: Vec<KotlinTypeArgument>,
+    pub nullable: bool,
+    /// `suspend` of a function type, e.g. `suspend () -> Unit`.
+    pub suspend: bool,
+    /// The upper bound of a platform type, e.g. `String!` is `String..String?`.
+    pub flexible_upper_bound: Option<Box<KotlinType>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KotlinClassifier {
+    Class(StrRef),
+    /// The name of a type parameter.
+    TypeParameter(StrRef),
+    TypeAlias(StrRef),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KotlinTypeArgument {
+    /// `*`
+    Star,
+    Projection(KotlinVariance, KotlinType),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KotlinVariance {
+    In,
+    Out,
+    Invariant,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KotlinVisibility {
+    Internal,
+    Private,
+    Protected,
+    Public,
+    PrivateToThis,
+    Local,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KotlinModality {
+    Final,
+    Open,
+    Abstract,
+    Sealed,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KotlinClassKind {
+    Class,
+    Interface,
+    EnumClass,
+    EnumEntry,
+    AnnotationClass,
+    Object,
+    CompanionObject,
+}
+
+/// Bits of the `flags` of Kotlin declarations, visibility and modality are shared by all
+/// of them and read by [kotlin_visibility] and [kotlin_modality].
+pub mod flags {
+    pub const HAS_ANNOTATIONS: u32 = 1;
+
+    pub const CLASS_IS_INNER: u32 = 1 << 9;
+    pub const CLASS_IS_DATA: u32 = 1 << 10;
+    pub const CLASS_IS_EXTERNAL: u32 = 1 << 11;
+    pub const CLASS_IS_EXPECT: u32 = 1 << 12;
+    pub const CLASS_IS_VALUE: u32 = 1 << 13;
+    pub const CLASS_IS_FUN_INTERFACE: u32 = 1 << 14;
+
+    pub const CONSTRUCTOR_IS_SECONDARY: u32 = 1 << 4;
+
+    pub const FUNCTION_IS_OPERATOR: u32 = 1 << 8;
+    pub const FUNCTION_IS_INFIX: u32 = 1 << 9;
+    pub const FUNCTION_IS_INLINE: u32 = 1 << 10;
+    pub const FUNCTION_IS_TAILREC: u32 = 1 << 11;
+    pub const FUNCTION_IS_EXTERNAL: u32 = 1 << 12;
+    pub const FUNCTION_IS_SUSPEND: u32 = 1 << 13;
+    pub const FUNCTION_IS_EXPECT: u32 = 1 << 14;
+
+    pub const PROPERTY_IS_VAR: u32 = 1 << 8;
+    pub const PROPERTY_HAS_GETTER: u32 = 1 << 9;
+    pub const PROPERTY_HAS_SETTER: u32 = 1 << 10;
+    pub const PROPERTY_IS_CONST: u32 = 1 << 11;
+    pub const PROPERTY_IS_LATEINIT: u32 = 1 << 12;
+    pub const PROPERTY_HAS_CONSTANT: u32 = 1 << 13;
+    pub const PROPERTY_IS_EXTERNAL: u32 = 1 << 14;
+    pub const PROPERTY_IS_DELEGATED: u32 = 1 << 15;
+    pub const PROPERTY_IS_EXPECT: u32 = 1 << 16;
+
+    pub const PARAMETER_DECLARES_DEFAULT_VALUE: u32 = 1 << 1;
+    pub const PARAMETER_IS_CROSSINLINE: u32 = 1 << 2;
+    pub const PARAMETER_IS_NOINLINE: u32 = 1 << 3;
+}
+
+pub fn kotlin_visibility(flags: u32) -> KotlinVisibility {
+    match (flags >> 1) & 0b111 {
+        0 => KotlinVisibility::Internal,
+        1 => KotlinVisibility::Private,
+        2 => KotlinVisibility::Protected,
+        4 => KotlinVisibility::PrivateToThis,
+        5 => KotlinVisibility::Local,
+        _ => KotlinVisibility::Public,
+    }
+}
+
+pub fn kotlin_modality(flags: u32) -> KotlinModality {
+    match (flags >> 4) & 0b11 {
+        0 => KotlinModality::Final,
+        1 => KotlinModality::Open,
+        2 => KotlinModality::Abstract,
+        _ => KotlinModality::Sealed,
+    }
+}
+
+impl KotlinClass {
+    pub fn kind(&self) -> KotlinClassKind {
+        match (self.flags >> 6) & 0b111 {
+            1 => KotlinClassKind::Interface,
+            2 => KotlinClassKind::EnumClass,
+            3 => KotlinClassKind::EnumEntry,
+            4 => KotlinClassKind::AnnotationClass,
+            5 => KotlinClassKind::Object,
+            6 => KotlinClassKind::CompanionObject,
+            _ => KotlinClassKind::Class,
+        }
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.flags & flags::CLASS_IS_DATA != 0
+    }
+
+    /// `value class`, or `inline class` of older versions.
+    pub fn is_value(&self) -> bool {
+        self.flags & flags::CLASS_IS_VALUE != 0
+    }
+}
+
+impl KotlinFunction {
+    pub fn is_suspend(&self) -> bool {
+        self.flags & flags::FUNCTION_IS_SUSPEND != 0
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.flags & flags::FUNCTION_IS_INLINE != 0
+    }
+}
+
+impl KotlinProperty {
+    pub fn is_var(&self) -> bool {
+        self.flags & flags::PROPERTY_IS_VAR != 0
+    }
+
+    pub fn is_const(&self) -> bool {
+        self.flags & flags::PROPERTY_IS_CONST != 0
+    }
+
+    pub fn is_lateinit(&self) -> bool {
+        self.flags & flags::PROPERTY_IS_LATEINIT != 0
+    }
+
+    pub fn is_delegated(&self) -> bool {
+        self.flags & flags::PROPERTY_IS_DELEGATED != 0
+    }
+}
@@ -0,0 +1,124 @@
+use crate::err::{AsmErr, AsmResult};
+
+/// A field of a protobuf message, groups are not used by Kotlin metadata.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Wire<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+/// The fields of a protobuf message in their order, messages are small so they are
+/// looked up by scanning.
+pub(crate) struct Fields<'a> {
+    fields: Vec<(u32, Wire<'a>)>,
+}
+
+fn proto_err(message: &str) -> AsmErr {
+    AsmErr::IllegalFormat(format!("kotlin metadata: {message}"))
+}
+
+/// Reads a varint at `offset` and moves `offset` behind it.
+pub(crate) fn read_varint(bytes: &[u8], offset: &mut usize) -> AsmResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset).ok_or_else(|| proto_err("truncated varint"))?;
+        *offset += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 { return Ok(value); }
+    }
+    Err(proto_err("varint is too long"))
+}
+
+fn take<'a>(bytes: &'a [u8], offset: &mut usize, len: usize) -> AsmResult<&'a [u8]> {
+    let end = offset.checked_add(len).filter(|end| *end <= bytes.len())
+        .ok_or_else(|| proto_err("truncated field"))?;
+    let slice = &bytes[*offset..end];
+    *offset = end;
+    Ok(slice)
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn parse(bytes: &'a [u8]) -> AsmResult<Fields<'a>> {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let key = read_varint(bytes, &mut offset)?;
+            let wire = match key & 0b111 {
+                0 => Wire::Varint(read_varint(bytes, &mut offset)?),
+                1 => {
+                    take(bytes, &mut offset, 8)?;
+                    Wire::Fixed64
+                }
+                2 => {
+                    let len = read_varint(bytes, &mut offset)? as usize;
+                    Wire::Bytes(take(bytes, &mut offset, len)?)
+                }
+                5 => {
+                    take(bytes, &mut offset, 4)?;
+                    Wire::Fixed32
+                }
+                wire_type => return Err(proto_err(&format!("unsupported wire type {wire_type}"))),
+            };
+            fields.push(((key >> 3) as u32, wire));
+        }
+        Ok(Fields { fields })
+    }
+
+    fn all(&self, number: u32) -> impl Iterator<Item=Wire<'a>> + '_ {
+        self.fields.iter().filter(move |(n, _)| *n == number).map(|(_, wire)| *wire)
+    }
+
+    /// The last value of an optional int field, like protobuf does.
+    pub(crate) fn int(&self, number: u32) -> Option<u32> {
+        self.all(number).filter_map(|wire| match wire {
+            Wire::Varint(value) => Some(value as u32),
+            _ => None,
+        }).last()
+    }
+
+    pub(crate) fn bool(&self, number: u32) -> bool {
+        self.int(number).is_some_and(|value| value != 0)
+    }
+
+    /// Values of a repeated int field, packed or not.
+    pub(crate) fn ints(&self, number: u32) -> AsmResult<Vec<u32>> {
+        let mut values = Vec::new();
+        for wire in self.all(number) {
+            match wire {
+                Wire::Varint(value) => values.push(value as u32),
+                Wire::Bytes(packed) => {
+                    let mut offset = 0;
+                    while offset < packed.len() {
+                        values.push(read_varint(packed, &mut offset)? as u32);
+                    }
+                }
+                _ => return Err(proto_err(&format!("field {number} is not an int"))),
+            }
+        }
+        Ok(values)
+    }
+
+    pub(crate) fn bytes(&self, number: u32) -> Option<&'a [u8]> {
+        self.all_bytes(number).last()
+    }
+
+    pub(crate) fn all_bytes(&self, number: u32) -> impl Iterator<Item=&'a [u8]> + '_ {
+        self.all(number).filter_map(|wire| match wire {
+            Wire::Bytes(bytes) => Some(bytes),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn messages(&self, number: u32) -> AsmResult<Vec<Fields<'a>>> {
+        self.all(number).map(|wire| match wire {
+            Wire::Bytes(bytes) => Fields::parse(bytes),
+            _ => Err(proto_err(&format!("field {number} is not a message"))),
+        }).collect()
+    }
+
+    pub(crate) fn message(&self, number: u32) -> AsmResult<Option<Fields<'a>>> {
+        self.bytes(number).map(Fields::parse).transpose()
+    }
+}
@@ -0,0 +1,117 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::kotlin::proto::{read_varint, Fields};
+use crate::StrRef;
+
+/// Class names which records of a string table may refer to by index instead of `d2`.
+const PREDEFINED_STRINGS: [&str; 44] = [
+    "kotlin/Any", "kotlin/Nothing", "kotlin/Unit", "kotlin/Throwable", "kotlin/Number",
+    "kotlin/Byte", "kotlin/Double", "kotlin/Float", "kotlin/Int", "kotlin/Long", "kotlin/Short",
+    "kotlin/Boolean", "kotlin/Char", "kotlin/CharSequence", "kotlin/String", "kotlin/Comparable",
+    "kotlin/Enum", "kotlin/Array", "kotlin/ByteArray", "kotlin/DoubleArray", "kotlin/FloatArray",
+    "kotlin/IntArray", "kotlin/LongArray", "kotlin/ShortArray", "kotlin/BooleanArray",
+    "kotlin/CharArray", "kotlin/Cloneable", "kotlin/Annotation",
+    "kotlin/collections/Iterable", "kotlin/collections/MutableIterable",
+    "kotlin/collections/Collection", "kotlin/collections/MutableCollection",
+    "kotlin/collections/List", "kotlin/collections/MutableList",
+    "kotlin/collections/Set", "kotlin/collections/MutableSet",
+    "kotlin/collections/Map", "kotlin/collections/MutableMap",
+    "kotlin/collections/Map.Entry", "kotlin/collections/MutableMap.MutableEntry",
+    "kotlin/collections/Iterator", "kotlin/collections/MutableIterator",
+    "kotlin/collections/ListIterator", "kotlin/collections/MutableListIterator",
+];
+
+/// Decodes the protobuf bytes of `d1`. Since Kotlin 1.3 `d1` starts with `\u0000` and
+/// every char is a byte, older versions pack 7 bits in every char to avoid zeros.
+pub(crate) fn decode_bytes(data: &[StrRef]) -> AsmResult<Vec<u8>> {
+    let chars = data.iter().flat_map(|part| part.chars());
+    if data.first().is_some_and(|first| first.starts_with('\0')) {
+        return chars.skip(1).map(char_to_byte).collect();
+    }
+    // every byte was incremented by one when encoding, 0x7F is the same as -1 modulo 256
+    let bytes: Vec<u8> = chars.map(|c| char_to_byte(c).map(|b| b.wrapping_add(0x7F)))
+        .collect::<AsmResult<_>>()?;
+    let mut result = Vec::with_capacity(bytes.len() * 7 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer |= ((byte & 0x7F) as u32) << bits;
+        bits += 7;
+        if bits >= 8 {
+            result.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+    Ok(result)
+}
+
+fn char_to_byte(c: char) -> AsmResult<u8> {
+    u8::try_from(c as u32)
+        .map_err(|_| AsmErr::IllegalFormat(format!("kotlin metadata: unexpected char {c:?} in d1")))
+}
+
+/// Resolves the string indexes of the metadata protobuf, which refer to `d2` through the
+/// records of a `StringTableTypes` message.
+pub(crate) struct StringTable {
+    strings: Vec<StrRef>,
+}
+
+impl StringTable {
+    pub(crate) fn new(types: &Fields, d2: &[StrRef]) -> AsmResult<StringTable> {
+        let mut strings = Vec::with_capacity(d2.len());
+        for record in types.messages(1)? {
+            let range = record.int(1).unwrap_or(1);
+            for _ in 0..range {
+                let index = strings.len();
+                let mut string: String = match (record.bytes(6), record.int(2)) {
+                    (Some(bytes), _) => String::from_utf8_lossy(bytes).into_owned(),
+                    (None, Some(predefined)) if (predefined as usize) < PREDEFINED_STRINGS.len() => {
+                        PREDEFINED_STRINGS[predefined as usize].to_string()
+                    }
+                    _ => d2.get(index).map(|s| s.to_string()).unwrap_or_default(),
+                };
+                if let [begin, end, ..] = record.ints(4)?[..] {
+                    let chars: Vec<char> = string.chars().collect();
+                    if begin <= end && (end as usize) <= chars.len() {
+                        string = chars[begin as usize..end as usize].iter().collect();
+                    }
+                }
+                if let [from, to, ..] = record.ints(5)?[..]
+                    && let (Some(from), Some(to)) = (char::from_u32(from), char::from_u32(to)) {
+                    string = string.replace(from, &to.to_string());
+                }
+                match record.int(3) {
+                    // internal name to class id
+                    Some(1) => string = string.replace('$', "."),
+                    // descriptor to class id
+                    Some(2) => {
+                        let mut chars = string.chars();
+                        if chars.next().is_some() && chars.next_back().is_some() {
+                            string = chars.as_str().to_string();
+                        }
+                        string = string.replace('$', ".");
+                    }
+                    _ => {}
+                }
+                strings.push(StrRef::from(string));
+            }
+        }
+        // strings without records are used as they are
+        strings.extend(d2.iter().skip(strings.len()).cloned());
+        Ok(StringTable { strings })
+    }
+
+    pub(crate) fn get(&self, index: u32) -> AsmResult<StrRef> {
+        self.strings.get(index as usize).cloned().ok_or_else(|| {
+            AsmErr::IllegalFormat(format!("kotlin metadata: string index {index} is out of bounds"))
+        })
+    }
+}
+
+/// Splits `d1` bytes into the delimited `StringTableTypes` message and the message behind it.
+pub(crate) fn split_string_table(bytes: &[u8]) -> AsmResult<(&[u8], &[u8])> {
+    let mut offset = 0;
+    let len = read_varint(bytes, &mut offset)? as usize;
+    let end = offset.checked_add(len).filter(|end| *end <= bytes.len())
+        .ok_or_else(|| AsmErr::IllegalFormat("kotlin metadata: truncated string table".to_string()))?;
+    Ok((&bytes[offset..end], &bytes[end..]))
+}
@@ -0,0 +1,286 @@
+use crate::kotlin::*;
+use std::fmt::{Display, Formatter, Result, Write};
+
+/// Renders declarations like Kotlin sources without bodies, one declaration per line.
+/// `public` and `final` are omitted since they are the defaults.
+impl Display for KotlinDeclarations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            KotlinDeclarations::Class(class) => class.fmt(f),
+            KotlinDeclarations::Package(package) => {
+                members(f, "", &package.type_aliases, &package.properties, &package.functions)
+            }
+            KotlinDeclarations::SyntheticClass(Some(function)) => writeln!(f, "lambda {function}"),
+            KotlinDeclarations::SyntheticClass(None) => writeln!(f, "synthetic class"),
+            KotlinDeclarations::MultiFileClassFacade(parts) => {
+                for part in parts {
+                    writeln!(f, "part {}", class_name(part))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for KotlinClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let kind = self.kind();
+        visibility(f, self.flags)?;
+        let default_modality = match kind {
+            KotlinClassKind::Interface | KotlinClassKind::AnnotationClass => KotlinModality::Abstract,
+            _ => KotlinModality::Final,
+        };
+        modality(f, self.flags, default_modality)?;
+        for (flag, keyword) in [
+            (flags::CLASS_IS_EXPECT, "expect"), (flags::CLASS_IS_EXTERNAL, "external"),
+            (flags::CLASS_IS_INNER, "inner"), (flags::CLASS_IS_DATA, "data"),
+            (flags::CLASS_IS_VALUE, "value"), (flags::CLASS_IS_FUN_INTERFACE, "fun"),
+        ] {
+            if self.flags & flag != 0 { write!(f, "{keyword} ")?; }
+        }
+        let keyword = match kind {
+            KotlinClassKind::Class => "class",
+            KotlinClassKind::Interface => "interface",
+            KotlinClassKind::EnumClass => "enum class",
+            KotlinClassKind::EnumEntry => "enum entry",
+            KotlinClassKind::AnnotationClass => "annotation class",
+            KotlinClassKind::Object => "object",
+            KotlinClassKind::CompanionObject => "companion object",
+        };
+        write!(f, "{keyword} {}", class_name(&self.name))?;
+        type_parameters(f, &self.type_parameters)?;
+        if !self.supertypes.is_empty() {
+            f.write_str(" : ")?;
+            separated(f, &self.supertypes, ", ")?;
+        }
+        f.write_str(" {\n")?;
+        let indent = "    ";
+        if !self.enum_entries.is_empty() {
+            writeln!(f, "{indent}{};", self.enum_entries.join(", "))?;
+        }
+        for constructor in &self.constructors {
+            f.write_str(indent)?;
+            visibility(f, constructor.flags)?;
+            f.write_str("constructor(")?;
+            separated(f, &constructor.parameters, ", ")?;
+            f.write_str(")\n")?;
+        }
+        members(f, indent, &self.type_aliases, &self.properties, &self.functions)?;
+        if let Some(companion) = &self.companion_object {
+            writeln!(f, "{indent}companion object {companion}")?;
+        }
+        for nested in &self.nested_classes {
+            if Some(nested) != self.companion_object.as_ref() && !self.enum_entries.contains(nested) {
+                writeln!(f, "{indent}nested class {nested}")?;
+            }
+        }
+        for subclass in &self.sealed_subclasses {
+            writeln!(f, "{indent}sealed subclass {}", class_name(subclass))?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+fn members(
+    f: &mut Formatter<'_>, indent: &str,
+    type_aliases: &[KotlinTypeAlias], properties: &[KotlinProperty], functions: &[KotlinFunction],
+) -> Result {
+    for type_alias in type_aliases {
+        f.write_str(indent)?;
+        visibility(f, type_alias.flags)?;
+        write!(f, "typealias {}", type_alias.name)?;
+        type_parameters(f, &type_alias.type_parameters)?;
+        writeln!(f, " = {}", type_alias.underlying_type)?;
+    }
+    for property in properties {
+        writeln!(f, "{indent}{property}")?;
+    }
+    for function in functions {
+        writeln!(f, "{indent}{function}")?;
+    }
+    Ok(())
+}
+
+impl Display for KotlinProperty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        visibility(f, self.flags)?;
+        modality(f, self.flags, KotlinModality::Final)?;
+        for (flag, keyword) in [
+            (flags::PROPERTY_IS_EXPECT, "expect"), (flags::PROPERTY_IS_EXTERNAL, "external"),
+            (flags::PROPERTY_IS_CONST, "const"), (flags::PROPERTY_IS_LATEINIT, "lateinit"),
+        ] {
+            if self.flags & flag != 0 { write!(f, "{keyword} ")?; }
+        }
+        f.write_str(if self.is_var() { "var" } else { "val" })?;
+        if !self.type_parameters.is_empty() {
+            f.write_char(' ')?;
+            type_parameters(f, &self.type_parameters)?;
+        }
+        f.write_char(' ')?;
+        if let Some(receiver) = &self.receiver {
+            write!(f, "{receiver}.")?;
+        }
+        write!(f, "{}: {}", self.name, self.return_type)?;
+        if self.is_delegated() {
+            f.write_str(" by ...")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for KotlinFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        visibility(f, self.flags)?;
+        modality(f, self.flags, KotlinModality::Final)?;
+        for (flag, keyword) in [
+            (flags::FUNCTION_IS_EXPECT, "expect"), (flags::FUNCTION_IS_EXTERNAL, "external"),
+            (flags::FUNCTION_IS_TAILREC, "tailrec"), (flags::FUNCTION_IS_SUSPEND, "suspend"),
+            (flags::FUNCTION_IS_INLINE, "inline"), (flags::FUNCTION_IS_INFIX, "infix"),
+            (flags::FUNCTION_IS_OPERATOR, "operator"),
+        ] {
+            if self.flags & flag != 0 { write!(f, "{keyword} ")?; }
+        }
+        f.write_str("fun ")?;
+        if !self.type_parameters.is_empty() {
+            type_parameters(f, &self.type_parameters)?;
+            f.write_char(' ')?;
+        }
+        if let Some(receiver) = &self.receiver {
+            write!(f, "{receiver}.")?;
+        }
+        write!(f, "{}(", self.name)?;
+        separated(f, &self.parameters, ", ")?;
+        write!(f, "): {}", self.return_type)
+    }
+}
+
+impl Display for KotlinValueParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (flag, keyword) in [
+            (flags::PARAMETER_IS_CROSSINLINE, "crossinline"), (flags::PARAMETER_IS_NOINLINE, "noinline"),
+        ] {
+            if self.flags & flag != 0 { write!(f, "{keyword} ")?; }
+        }
+        match &self.vararg_element_type {
+            Some(element_type) => write!(f, "vararg {}: {element_type}", self.name)?,
+            None => write!(f, "{}: {}", self.name, self.value_type)?,
+        }
+        if self.flags & flags::PARAMETER_DECLARES_DEFAULT_VALUE != 0 {
+            f.write_str(" = ...")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for KotlinTypeParameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.reified {
+            f.write_str("reified ")?;
+        }
+        variance(f, self.variance)?;
+        f.write_str(&self.name)?;
+        if !self.upper_bounds.is_empty() {
+            f.write_str(" : ")?;
+            separated(f, &self.upper_bounds, " & ")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for KotlinType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.suspend {
+            f.write_str("suspend ")?;
+        }
+        let name = match &self.classifier {
+            KotlinClassifier::Class(name) | KotlinClassifier::TypeAlias(name) => class_name(name),
+            KotlinClassifier::TypeParameter(name) => name.to_string(),
+        };
+        let function_arity = name.strip_prefix("kotlin.Function")
+            .and_then(|arity| arity.parse::<usize>().ok())
+            .filter(|arity| *arity + 1 == self.arguments.len());
+        if let Some(arity) = function_arity {
+            // `(A) -> R` instead of `kotlin.Function1<A, R>`
+            if self.nullable { f.write_char('(')?; }
+            f.write_char('(')?;
+            separated(f, &self.arguments[..arity], ", ")?;
+            write!(f, ") -> {}", self.arguments[arity])?;
+            if self.nullable { f.write_str(")")?; }
+        } else {
+            f.write_str(&name)?;
+            if !self.arguments.is_empty() {
+                f.write_char('<')?;
+                separated(f, &self.arguments, ", ")?;
+                f.write_char('>')?;
+            }
+        }
+        if self.nullable {
+            f.write_char('?')?;
+        }
+        if self.flexible_upper_bound.is_some() {
+            f.write_char('!')?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for KotlinTypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            KotlinTypeArgument::Star => f.write_char('*'),
+            KotlinTypeArgument::Projection(projection, argument) => {
+                variance(f, *projection)?;
+                argument.fmt(f)
+            }
+        }
+    }
+}
+
+/// `kotlin/collections/Map.Entry` as `kotlin.collections.Map.Entry`.
+fn class_name(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+fn separated<T: Display>(f: &mut Formatter<'_>, items: &[T], separator: &str) -> Result {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 { f.write_str(separator)?; }
+        item.fmt(f)?;
+    }
+    Ok(())
+}
+
+fn type_parameters(f: &mut Formatter<'_>, parameters: &[KotlinTypeParameter]) -> Result {
+    if parameters.is_empty() { return Ok(()); }
+    f.write_char('<')?;
+    separated(f, parameters, ", ")?;
+    f.write_char('>')
+}
+
+fn variance(f: &mut Formatter<'_>, variance: KotlinVariance) -> Result {
+    match variance {
+        KotlinVariance::In => f.write_str("in "),
+        KotlinVariance::Out => f.write_str("out "),
+        KotlinVariance::Invariant => Ok(()),
+    }
+}
+
+fn visibility(f: &mut Formatter<'_>, flags: u32) -> Result {
+    match kotlin_visibility(flags) {
+        KotlinVisibility::Public => Ok(()),
+        KotlinVisibility::Internal => f.write_str("internal "),
+        KotlinVisibility::Private | KotlinVisibility::PrivateToThis => f.write_str("private "),
+        KotlinVisibility::Protected => f.write_str("protected "),
+        KotlinVisibility::Local => f.write_str("local "),
+    }
+}
+
+fn modality(f: &mut Formatter<'_>, flags: u32, default: KotlinModality) -> Result {
+    let modality = kotlin_modality(flags);
+    if modality == default { return Ok(()); }
+    f.write_str(match modality {
+        KotlinModality::Final => "final ",
+        KotlinModality::Open => "open ",
+        KotlinModality::Abstract => "abstract ",
+        KotlinModality::Sealed => "sealed ",
+    })
+}
@@ -20,6 +20,53 @@ pub mod node;
 /// - [DEX Format](https://source.android.com/docs/core/runtime/dex-format)
 pub mod dex;
 
+/// class hierarchy index across dex and jvm classes, also a resolver for type queries.
+pub mod hierarchy;
+
+/// cross references between methods, fields, strings and classes for finding usages.
+pub mod xref;
+
+/// bytecode verifier which type checks method code like the JVM (JVMS 4.10).
+pub mod verify;
+
+/// data-flow analysis of method code with pluggable interpreters, like ASM's analysis package.
+pub mod analysis;
+
+/// control-flow graphs with basic blocks, dominators and loops for jvm and dalvik code.
+pub mod cfg;
+
+/// event-driven visitor api for reading class files, like ASM's ClassVisitor.
+pub mod visitor;
+
+/// renaming classes, fields and methods in class nodes, like ASM's ClassRemapper.
+pub mod remap;
+
+/// obfuscation mappings: ProGuard / R8 `mapping.txt`, Tiny v2, SRG / TSRG and Enigma.
+pub mod mapping;
+
+/// declarations of Kotlin classes decoded from their `@kotlin.Metadata` annotations.
+pub mod kotlin;
+
+/// java release compatibility of class files: versions, version dependent features and api references.
+pub mod compat;
+
+/// well-known bootstrap methods of `invokedynamic` call sites, e.g. lambdas and string concatenation.
+pub mod bootstrap;
+
+/// JSR-45 source maps (`SourceDebugExtension`), which map lines of inlined Kotlin code to their files.
+pub mod smap;
+
+/// semantic diff between two sets of classes: classes, members and normalized method code.
+pub mod diff;
+
+/// binary compatibility between two versions of a library, classified by the rules of JLS chapter 13.
+pub mod binary_compat;
+
+/// android binary resources: binary XML (AXML) files like the `AndroidManifest.xml` and the
+/// resource table `resources.arsc`, the v1 to v4 signatures of APKs, and the dex files embedded
+/// in VDEX and OAT files.
+pub mod android;
+
 mod err;
 mod pub_jvms_utils;
 mod pub_refs;
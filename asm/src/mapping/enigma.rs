@@ -0,0 +1,143 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::mapping::tree::{class_entry, illegal_line};
+use crate::mapping::{MappedMember, MappedParam, MappingTree};
+use crate::StrRef;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The element which the following deeper lines belong to.
+#[derive(Clone, Copy)]
+enum Parent {
+    Class(usize),
+    Field(usize, usize),
+    Method(usize, usize),
+    Arg,
+}
+
+impl MappingTree {
+    /// Reads an Enigma file with the namespaces `obf` and `deobf`. Names of nested classes
+    /// may be relative to their outer classes, e.g. `CLASS b Inner` in `CLASS a Outer`.
+    pub fn parse_enigma(content: &str) -> AsmResult<MappingTree> {
+        let mut tree = MappingTree::new(vec![StrRef::from("obf"), StrRef::from("deobf")]);
+        let mut class_index = HashMap::new();
+        let mut parents: Vec<Parent> = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let err = || illegal_line("enigma", index, line);
+            if line.trim().is_empty() { continue; }
+            let depth = line.len() - line.trim_start_matches('\t').len();
+            if depth > parents.len() { return Err(err()); }
+            parents.truncate(depth);
+            let parent = parents.last().copied();
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix("COMMENT") {
+                let comment = comment.strip_prefix(' ').unwrap_or(comment);
+                let target = match parent.ok_or_else(err)? {
+                    Parent::Class(class) => &mut tree.classes[class].comment,
+                    Parent::Field(class, field) => &mut tree.classes[class].fields[field].comment,
+                    Parent::Method(class, method) => &mut tree.classes[class].methods[method].comment,
+                    Parent::Arg => continue,
+                };
+                *target = Some(match target.take() {
+                    Some(previous) => StrRef::from(format!("{previous}\n{comment}")),
+                    None => StrRef::from(comment),
+                });
+                continue;
+            }
+            // modifiers like `ACC:PUBLIC` are skipped
+            let columns: Vec<&str> = line.split_whitespace().filter(|column| !column.starts_with("ACC:")).collect();
+            let current = match (parent, &columns[..]) {
+                (None | Some(Parent::Class(_)), ["CLASS", obf, deobf @ ..]) if deobf.len() <= 1 => {
+                    let outer = match parent {
+                        Some(Parent::Class(outer)) => Some(&tree.classes[outer].names),
+                        _ => None,
+                    };
+                    let (obf, deobf) = match outer {
+                        None => (obf.to_string(), deobf.first().map(|name| name.to_string())),
+                        Some(outer) => {
+                            let outer_obf = outer[0].as_deref().unwrap_or_default();
+                            let outer_deobf = outer[1].as_deref().unwrap_or(outer_obf);
+                            let obf = if obf.starts_with(&format!("{outer_obf}$")) {
+                                obf.to_string()
+                            } else {
+                                format!("{outer_obf}${obf}")
+                            };
+                            let deobf = deobf.first().map(|name| match name.rsplit_once('$') {
+                                Some((_, simple_name)) => format!("{outer_deobf}${simple_name}"),
+                                None => format!("{outer_deobf}${name}"),
+                            });
+                            (obf, deobf)
+                        }
+                    };
+                    let class = class_entry(&mut tree, &mut class_index, &obf);
+                    if deobf.is_some() {
+                        class.names[1] = deobf.map(StrRef::from);
+                    }
+                    Parent::Class(class_index[&*obf])
+                }
+                (Some(Parent::Class(class)), [kind @ ("FIELD" | "METHOD"), obf, names @ .., desc]) if names.len() <= 1 => {
+                    let member = MappedMember {
+                        names: vec![Some(StrRef::from(*obf)), names.first().map(|name| StrRef::from(*name))],
+                        desc: Some(StrRef::from(*desc)),
+                        ..Default::default()
+                    };
+                    let owner = &mut tree.classes[class];
+                    if *kind == "FIELD" {
+                        owner.fields.push(member);
+                        Parent::Field(class, owner.fields.len() - 1)
+                    } else {
+                        owner.methods.push(member);
+                        Parent::Method(class, owner.methods.len() - 1)
+                    }
+                }
+                (Some(Parent::Method(class, method)), ["ARG", lv_index, name]) => {
+                    let lv_index = lv_index.parse().map_err(|_| err())?;
+                    let param = MappedParam { lv_index, names: vec![None, Some(StrRef::from(*name))] };
+                    tree.classes[class].methods[method].params.push(param);
+                    Parent::Arg
+                }
+                _ => return Err(err()),
+            };
+            parents.push(current);
+        }
+        Ok(tree)
+    }
+
+    /// Writes the first two namespaces as an Enigma file, nested classes are written with
+    /// their full names at the top level.
+    pub fn to_enigma(&self) -> AsmResult<String> {
+        if self.namespaces.len() < 2 {
+            return Err(AsmErr::IllegalFormat(format!("enigma needs 2 namespaces, found {:?}", self.namespaces)));
+        }
+        let mut output = String::new();
+        let element = |output: &mut String, indent: &str, keyword: &str, names: &[Option<StrRef>]| {
+            let obf = names[0].as_deref().unwrap_or_default();
+            let _ = write!(output, "{indent}{keyword} {obf}");
+            if let Some(Some(deobf)) = names.get(1) && **deobf != *obf {
+                let _ = write!(output, " {deobf}");
+            }
+        };
+        let comment = |output: &mut String, indent: &str, comment: &Option<StrRef>| {
+            for line in comment.iter().flat_map(|comment| comment.lines()) {
+                let _ = writeln!(output, "{indent}COMMENT {line}");
+            }
+        };
+        for class in &self.classes {
+            element(&mut output, "", "CLASS", &class.names);
+            output.push('\n');
+            comment(&mut output, "\t", &class.comment);
+            for (keyword, members) in [("FIELD", &class.fields), ("METHOD", &class.methods)] {
+                for member in members {
+                    let Some(desc) = &member.desc else { continue; };
+                    element(&mut output, "\t", keyword, &member.names);
+                    let _ = writeln!(output, " {desc}");
+                    comment(&mut output, "\t\t", &member.comment);
+                    for param in &member.params {
+                        let Some(Some(name)) = param.names.get(1) else { continue; };
+                        let _ = writeln!(output, "\t\tARG {} {name}", param.lv_index);
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,8 @@
+pub use proguard::*;
+pub use tree::*;
+
+mod enigma;
+mod proguard;
+mod srg;
+mod tiny;
+mod tree;
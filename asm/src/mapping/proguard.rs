@@ -0,0 +1,344 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::mapping::{MappedClass, MappedMember, MappingTree};
+use crate::remap::Remapper;
+use crate::{DescriptorRef, InternalNameRef, StrRef};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// A ProGuard / R8 `mapping.txt`, which maps obfuscated names back to the original ones.
+///
+/// As a [Remapper] it renames obfuscated classes and members to their original names,
+/// member hooks take the obfuscated owner and the obfuscated descriptor.
+#[derive(Clone, Debug, Default)]
+pub struct ProguardMapping {
+    pub classes: Vec<ClassMapping>,
+    by_obfuscated: HashMap<InternalNameRef, usize>,
+    by_original: HashMap<InternalNameRef, usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ClassMapping {
+    pub original: InternalNameRef,
+    pub obfuscated: InternalNameRef,
+    pub fields: Vec<FieldMapping>,
+    /// Methods in the order of the file, inline frames of the same obfuscated range are
+    /// consecutive, the innermost one first.
+    pub methods: Vec<MethodMapping>,
+    /// Obfuscated name -> (obfuscated descriptor, original name).
+    field_index: HashMap<StrRef, Vec<(DescriptorRef, StrRef)>>,
+    method_index: HashMap<StrRef, Vec<(DescriptorRef, StrRef)>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldMapping {
+    pub original: StrRef,
+    pub obfuscated: StrRef,
+    /// The descriptor with original class names.
+    pub desc: DescriptorRef,
+}
+
+#[derive(Clone, Debug)]
+pub struct MethodMapping {
+    pub original: StrRef,
+    pub obfuscated: StrRef,
+    /// The descriptor with original class names.
+    pub desc: DescriptorRef,
+    /// The original class of a method inlined from another class, e.g. `b/C` for `b.C.run()`.
+    pub original_class: Option<InternalNameRef>,
+    /// The line numbers of the obfuscated code.
+    pub obfuscated_lines: Option<LineRange>,
+    /// The original line numbers, [None] means they are the same as the obfuscated ones.
+    pub original_lines: Option<LineRange>,
+}
+
+/// An inclusive range of line numbers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineRange {
+    pub fn contains(&self, line: u32) -> bool {
+        self.start <= line && line <= self.end
+    }
+}
+
+/// An original method of an obfuscated stack frame, see [ProguardMapping::retrace].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetracedFrame {
+    pub class: InternalNameRef,
+    pub method: StrRef,
+    pub desc: DescriptorRef,
+    pub line: Option<u32>,
+}
+
+impl MethodMapping {
+    /// The original line of an obfuscated line in [MethodMapping::obfuscated_lines].
+    pub fn original_line(&self, line: u32) -> u32 {
+        let Some(obfuscated) = self.obfuscated_lines else { return line; };
+        match self.original_lines {
+            None => line,
+            // callers of inlined methods map the whole range to the line of the call
+            Some(original) if original.start == original.end => original.start,
+            Some(original) => original.start + line.saturating_sub(obfuscated.start),
+        }
+    }
+}
+
+impl ProguardMapping {
+    pub fn from_read<T: Read>(mut read: T) -> AsmResult<Self> {
+        let mut content = String::new();
+        read.read_to_string(&mut content).map_err(|e| AsmErr::IOReadErr(e.into()))?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> AsmResult<Self> {
+        let mut mapping = ProguardMapping::default();
+        for (index, line) in content.lines().enumerate() {
+            let err = |message: &str| AsmErr::IllegalFormat(
+                format!("mapping line {}: {message}: {line}", index + 1),
+            );
+            let trimmed = line.trim();
+            // R8 writes metadata as json in comments, e.g. `# {"id":"sourceFile",...}`
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                let class = trimmed.strip_suffix(':').ok_or_else(|| err("missing `:` of class"))?;
+                let (original, obfuscated) = class.split_once(" -> ").ok_or_else(|| err("missing `->`"))?;
+                mapping.classes.push(ClassMapping {
+                    original: StrRef::from(original.trim().replace('.', "/")),
+                    obfuscated: StrRef::from(obfuscated.trim().replace('.', "/")),
+                    ..Default::default()
+                });
+                continue;
+            }
+            let class = mapping.classes.last_mut().ok_or_else(|| err("member without class"))?;
+            let (member, obfuscated) = trimmed.rsplit_once(" -> ").ok_or_else(|| err("missing `->`"))?;
+            let obfuscated = StrRef::from(obfuscated.trim());
+            if member.contains('(') {
+                class.methods.push(parse_method(member, obfuscated).ok_or_else(|| err("illegal method"))?);
+            } else {
+                let (field_type, name) = member.split_once(' ').ok_or_else(|| err("illegal field"))?;
+                class.fields.push(FieldMapping {
+                    original: StrRef::from(name.trim()),
+                    obfuscated,
+                    desc: StrRef::from(java_type_to_desc(field_type)),
+                });
+            }
+        }
+        mapping.build_index();
+        Ok(mapping)
+    }
+
+    fn build_index(&mut self) {
+        for (index, class) in self.classes.iter().enumerate() {
+            self.by_obfuscated.insert(class.obfuscated.clone(), index);
+            self.by_original.insert(class.original.clone(), index);
+        }
+        let to_obfuscated = ToObfuscated(self);
+        let mut field_indexes = Vec::with_capacity(self.classes.len());
+        let mut method_indexes = Vec::with_capacity(self.classes.len());
+        for class in &self.classes {
+            let mut field_index: HashMap<StrRef, Vec<(DescriptorRef, StrRef)>> = HashMap::new();
+            for field in &class.fields {
+                let desc = to_obfuscated.map_desc(&field.desc).unwrap_or_else(|_| field.desc.clone());
+                field_index.entry(field.obfuscated.clone()).or_default().push((desc, field.original.clone()));
+            }
+            let mut method_index: HashMap<StrRef, Vec<(DescriptorRef, StrRef)>> = HashMap::new();
+            for (i, method) in class.methods.iter().enumerate() {
+                // only the last frame of inlined frames is the method in the obfuscated class
+                let next = class.methods.get(i + 1);
+                let inlined = method.obfuscated_lines.is_some() && next.is_some_and(|next| {
+                    next.obfuscated == method.obfuscated && next.obfuscated_lines == method.obfuscated_lines
+                });
+                if inlined { continue; }
+                let desc = to_obfuscated.map_desc(&method.desc).unwrap_or_else(|_| method.desc.clone());
+                let candidates = method_index.entry(method.obfuscated.clone()).or_default();
+                if !candidates.iter().any(|(d, _)| *d == desc) {
+                    candidates.push((desc, method.original.clone()));
+                }
+            }
+            field_indexes.push(field_index);
+            method_indexes.push(method_index);
+        }
+        for ((class, field_index), method_index) in self.classes.iter_mut().zip(field_indexes).zip(method_indexes) {
+            class.field_index = field_index;
+            class.method_index = method_index;
+        }
+    }
+
+    pub fn class_by_obfuscated(&self, obfuscated: &str) -> Option<&ClassMapping> {
+        self.by_obfuscated.get(obfuscated).map(|&index| &self.classes[index])
+    }
+
+    pub fn class_by_original(&self, original: &str) -> Option<&ClassMapping> {
+        self.by_original.get(original).map(|&index| &self.classes[index])
+    }
+
+    /// The obfuscated internal name of an original class, or [None] if it is not renamed.
+    pub fn obfuscated_class(&self, original: &str) -> Option<&InternalNameRef> {
+        self.class_by_original(original).map(|class| &class.obfuscated)
+    }
+
+    /// The original methods of a frame of an obfuscated stack trace, innermost inline frame first.
+    /// Empty if no line range of the method contains the line.
+    pub fn retrace(&self, obfuscated_class: &str, obfuscated_method: &str, line: u32) -> Vec<RetracedFrame> {
+        let Some(class) = self.class_by_obfuscated(obfuscated_class) else { return vec![]; };
+        let methods = &class.methods;
+        let start = methods.iter().position(|method| {
+            *method.obfuscated == *obfuscated_method && method.obfuscated_lines.is_some_and(|lines| lines.contains(line))
+        });
+        let Some(start) = start else { return vec![]; };
+        let obfuscated_lines = methods[start].obfuscated_lines;
+        methods[start..].iter()
+            .take_while(|method| *method.obfuscated == *obfuscated_method && method.obfuscated_lines == obfuscated_lines)
+            .map(|method| RetracedFrame {
+                class: method.original_class.clone().unwrap_or_else(|| class.original.clone()),
+                method: method.original.clone(),
+                desc: method.desc.clone(),
+                line: Some(method.original_line(line)),
+            })
+            .collect()
+    }
+}
+
+impl ProguardMapping {
+    /// The mapping with the namespaces `obfuscated` and `original`, inline frames are dropped.
+    pub fn to_tree(&self) -> MappingTree {
+        let mut tree = MappingTree::new(vec![StrRef::from("obfuscated"), StrRef::from("original")]);
+        let member = |obfuscated: &StrRef, desc: &DescriptorRef, original: &StrRef| MappedMember {
+            names: vec![Some(obfuscated.clone()), Some(original.clone())],
+            desc: Some(desc.clone()),
+            ..Default::default()
+        };
+        for class in &self.classes {
+            let mut mapped = MappedClass {
+                names: vec![Some(class.obfuscated.clone()), Some(class.original.clone())],
+                ..Default::default()
+            };
+            for (index, members) in [(&class.field_index, &mut mapped.fields), (&class.method_index, &mut mapped.methods)] {
+                let mut names: Vec<_> = index.iter().collect();
+                names.sort_by_key(|(name, _)| *name);
+                for (obfuscated, candidates) in names {
+                    members.extend(candidates.iter().map(|(desc, original)| member(obfuscated, desc, original)));
+                }
+            }
+            tree.classes.push(mapped);
+        }
+        tree
+    }
+}
+
+impl Remapper for ProguardMapping {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.class_by_obfuscated(internal_name).map(|class| class.original.clone())
+    }
+
+    fn map_method_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        let candidates = self.class_by_obfuscated(owner)?.method_index.get(name)?;
+        find_member(candidates, desc)
+    }
+
+    fn map_field_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        let candidates = self.class_by_obfuscated(owner)?.field_index.get(name)?;
+        find_member(candidates, desc)
+    }
+}
+
+fn find_member(candidates: &[(DescriptorRef, StrRef)], desc: &str) -> Option<StrRef> {
+    match candidates.iter().find(|(d, _)| **d == *desc) {
+        Some((_, original)) => Some(original.clone()),
+        // descriptors may differ when the mapping is not complete, a single candidate is still a match
+        None if candidates.len() == 1 => Some(candidates[0].1.clone()),
+        None => None,
+    }
+}
+
+/// Maps original class names to obfuscated ones, for the obfuscated descriptors of members.
+struct ToObfuscated<'a>(&'a ProguardMapping);
+
+impl Remapper for ToObfuscated<'_> {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.0.obfuscated_class(internal_name).cloned()
+    }
+}
+
+/// Parses `[a:b:]return [class.]name(args)[:c[:d]]`.
+fn parse_method(member: &str, obfuscated: StrRef) -> Option<MethodMapping> {
+    let mut rest = member;
+    let mut obfuscated_lines = None;
+    if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        let (start, tail) = rest.split_once(':')?;
+        let (end, tail) = tail.split_once(':')?;
+        obfuscated_lines = Some(LineRange { start: start.parse().ok()?, end: end.parse().ok()? });
+        rest = tail;
+    }
+    let (return_type, rest) = rest.trim().split_once(' ')?;
+    let open = rest.find('(')?;
+    let close = rest.find(')')?;
+    let full_name = &rest[..open];
+    let args = &rest[open + 1..close];
+    let original_lines = match rest[close + 1..].strip_prefix(':') {
+        None => None,
+        Some(lines) => {
+            let (start, end) = match lines.split_once(':') {
+                Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                None => {
+                    let line = lines.parse().ok()?;
+                    (line, line)
+                }
+            };
+            Some(LineRange { start, end })
+        }
+    };
+    let (original_class, original) = match full_name.rsplit_once('.') {
+        Some((class, name)) => (Some(StrRef::from(class.replace('.', "/"))), name),
+        None => (None, full_name),
+    };
+    let mut desc = String::from("(");
+    for arg in args.split(',').map(str::trim).filter(|arg| !arg.is_empty()) {
+        desc.push_str(&java_type_to_desc(arg));
+    }
+    desc.push(')');
+    desc.push_str(&java_type_to_desc(return_type));
+    Some(MethodMapping {
+        original: StrRef::from(original),
+        obfuscated,
+        desc: StrRef::from(desc),
+        original_class,
+        obfuscated_lines,
+        original_lines,
+    })
+}
+
+/// `int[]` -> `[I`, `java.lang.String` -> `Ljava/lang/String;`.
+fn java_type_to_desc(java_type: &str) -> String {
+    let mut java_type = java_type.trim();
+    let mut desc = String::new();
+    while let Some(element) = java_type.strip_suffix("[]") {
+        desc.push('[');
+        java_type = element;
+    }
+    let primitive = match java_type {
+        "void" => "V",
+        "boolean" => "Z",
+        "byte" => "B",
+        "char" => "C",
+        "short" => "S",
+        "int" => "I",
+        "long" => "J",
+        "float" => "F",
+        "double" => "D",
+        _ => "",
+    };
+    if primitive.is_empty() {
+        desc.push('L');
+        desc.push_str(&java_type.replace('.', "/"));
+        desc.push(';');
+    } else {
+        desc.push_str(primitive);
+    }
+    desc
+}
@@ -0,0 +1,218 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::mapping::tree::{class_entry, illegal_line, name_or_source};
+use crate::mapping::{MappedClass, MappedMember, MappedParam, MappingTree};
+use crate::remap::Remapper;
+use crate::StrRef;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+impl MappingTree {
+    /// Reads a SRG file of Forge with the namespaces `obf` and `srg`, e.g. `CL: a net/minecraft/Foo`.
+    /// Packages are skipped.
+    pub fn parse_srg(content: &str) -> AsmResult<MappingTree> {
+        let mut tree = MappingTree::new(vec![StrRef::from("obf"), StrRef::from("srg")]);
+        let mut class_index = HashMap::new();
+        for (index, line) in content.lines().enumerate() {
+            let err = || illegal_line("srg", index, line);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (kind, rest) = line.split_once(' ').ok_or_else(err)?;
+            let columns: Vec<&str> = rest.split_whitespace().collect();
+            match (kind, &columns[..]) {
+                ("PK:", _) => {}
+                ("CL:", [obf, srg]) => {
+                    class_entry(&mut tree, &mut class_index, obf).names[1] = Some(StrRef::from(*srg));
+                }
+                // XSRG adds descriptors to fields
+                ("FD:", [obf, srg] | [obf, _, srg, _]) => {
+                    let (owner, name) = obf.rsplit_once('/').ok_or_else(err)?;
+                    let (_, srg_name) = srg.rsplit_once('/').ok_or_else(err)?;
+                    let desc = match columns[..] {
+                        [_, desc, _, _] => Some(StrRef::from(desc)),
+                        _ => None,
+                    };
+                    class_entry(&mut tree, &mut class_index, owner).fields.push(member(name, srg_name, desc));
+                }
+                ("MD:", [obf, desc, srg, _]) => {
+                    let (owner, name) = obf.rsplit_once('/').ok_or_else(err)?;
+                    let (_, srg_name) = srg.rsplit_once('/').ok_or_else(err)?;
+                    let method = member(name, srg_name, Some(StrRef::from(*desc)));
+                    class_entry(&mut tree, &mut class_index, owner).methods.push(method);
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Reads a TSRG file with the namespaces `obf` and `srg`, or a TSRG v2 file with the
+    /// namespaces in its header, e.g. `tsrg2 obf srg id`.
+    pub fn parse_tsrg(content: &str) -> AsmResult<MappingTree> {
+        let mut lines = content.lines().enumerate().peekable();
+        let namespaces: Vec<StrRef> = match lines.peek() {
+            Some((_, header)) if header.starts_with("tsrg2 ") => {
+                let namespaces = header.split_whitespace().skip(1).map(StrRef::from).collect();
+                lines.next();
+                namespaces
+            }
+            _ => vec![StrRef::from("obf"), StrRef::from("srg")],
+        };
+        let namespace_count = namespaces.len();
+        let mut tree = MappingTree::new(namespaces);
+        for (index, line) in lines {
+            let err = || illegal_line("tsrg", index, line);
+            if line.trim().is_empty() || line.starts_with('#') { continue; }
+            let depth = line.len() - line.trim_start_matches('\t').len();
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            match depth {
+                0 => {
+                    if columns.len() != namespace_count { return Err(err()); }
+                    tree.classes.push(MappedClass { names: names(&columns), ..Default::default() });
+                }
+                1 => {
+                    let class = tree.classes.last_mut().ok_or_else(err)?;
+                    match &columns[..] {
+                        [name, desc, mapped @ ..] if desc.starts_with('(') && mapped.len() + 1 == namespace_count => {
+                            let member_names = [&[*name][..], mapped].concat();
+                            class.methods.push(MappedMember {
+                                names: names(&member_names),
+                                desc: Some(StrRef::from(*desc)),
+                                ..Default::default()
+                            });
+                        }
+                        _ if columns.len() == namespace_count => {
+                            class.fields.push(MappedMember { names: names(&columns), ..Default::default() });
+                        }
+                        [name, desc, mapped @ ..] if mapped.len() + 1 == namespace_count => {
+                            let member_names = [&[*name][..], mapped].concat();
+                            class.fields.push(MappedMember {
+                                names: names(&member_names),
+                                desc: Some(StrRef::from(*desc)),
+                                ..Default::default()
+                            });
+                        }
+                        _ => return Err(err()),
+                    }
+                }
+                2 => {
+                    let method = tree.classes.last_mut()
+                        .and_then(|class| class.methods.last_mut())
+                        .ok_or_else(err)?;
+                    match &columns[..] {
+                        ["static"] => {}
+                        [lv_index, param_names @ ..] if param_names.len() == namespace_count => {
+                            let lv_index = lv_index.parse().map_err(|_| err())?;
+                            method.params.push(MappedParam { lv_index, names: names(param_names) });
+                        }
+                        _ => return Err(err()),
+                    }
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Writes the first two namespaces as a SRG file.
+    pub fn to_srg(&self) -> AsmResult<String> {
+        self.check_two_namespaces("srg")?;
+        let renamer = self.class_renamer(1);
+        let mut output = String::new();
+        for class in &self.classes {
+            let _ = writeln!(output, "CL: {} {}", name_or_source(&class.names, 0), name_or_source(&class.names, 1));
+        }
+        for class in &self.classes {
+            let (owner, mapped_owner) = (name_or_source(&class.names, 0), name_or_source(&class.names, 1));
+            for field in &class.fields {
+                let (name, mapped) = (name_or_source(&field.names, 0), name_or_source(&field.names, 1));
+                let _ = writeln!(output, "FD: {owner}/{name} {mapped_owner}/{mapped}");
+            }
+            for method in &class.methods {
+                let Some(desc) = &method.desc else { continue; };
+                let (name, mapped) = (name_or_source(&method.names, 0), name_or_source(&method.names, 1));
+                let mapped_desc = renamer.map_desc(desc)?;
+                let _ = writeln!(output, "MD: {owner}/{name} {desc} {mapped_owner}/{mapped} {mapped_desc}");
+            }
+        }
+        Ok(output)
+    }
+
+    /// Writes the first two namespaces as a TSRG file.
+    pub fn to_tsrg(&self) -> AsmResult<String> {
+        self.check_two_namespaces("tsrg")?;
+        let mut output = String::new();
+        for class in &self.classes {
+            let _ = writeln!(output, "{} {}", name_or_source(&class.names, 0), name_or_source(&class.names, 1));
+            for field in &class.fields {
+                let _ = writeln!(output, "\t{} {}", name_or_source(&field.names, 0), name_or_source(&field.names, 1));
+            }
+            for method in &class.methods {
+                let Some(desc) = &method.desc else { continue; };
+                let (name, mapped) = (name_or_source(&method.names, 0), name_or_source(&method.names, 1));
+                let _ = writeln!(output, "\t{name} {desc} {mapped}");
+            }
+        }
+        Ok(output)
+    }
+
+    /// Writes all namespaces as a TSRG v2 file.
+    pub fn to_tsrg2(&self) -> String {
+        let mut output = String::from("tsrg2");
+        for namespace in &self.namespaces {
+            output.push(' ');
+            output.push_str(namespace);
+        }
+        output.push('\n');
+        let names = |output: &mut String, names: &[Option<StrRef>]| {
+            for ns in 1..self.namespaces.len() {
+                output.push(' ');
+                output.push_str(name_or_source(names, ns));
+            }
+            output.push('\n');
+        };
+        for class in &self.classes {
+            output.push_str(name_or_source(&class.names, 0));
+            names(&mut output, &class.names);
+            for (members, is_method) in [(&class.fields, false), (&class.methods, true)] {
+                for member in members {
+                    let _ = write!(output, "\t{}", name_or_source(&member.names, 0));
+                    match &member.desc {
+                        Some(desc) => { let _ = write!(output, " {desc}"); }
+                        None if is_method => continue,
+                        None => {}
+                    }
+                    names(&mut output, &member.names);
+                    for param in &member.params {
+                        let _ = write!(output, "\t\t{}", param.lv_index);
+                        // parameters often have no names in the first namespace, `o` is used by Forge then
+                        for ns in 0..self.namespaces.len() {
+                            let name = param.names.get(ns).cloned().flatten();
+                            let _ = write!(output, " {}", name.as_deref().unwrap_or("o"));
+                        }
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    fn check_two_namespaces(&self, format: &str) -> AsmResult<()> {
+        if self.namespaces.len() < 2 {
+            return Err(AsmErr::IllegalFormat(format!("{format} needs 2 namespaces, found {:?}", self.namespaces)));
+        }
+        Ok(())
+    }
+}
+
+fn member(name: &str, mapped: &str, desc: Option<StrRef>) -> MappedMember {
+    MappedMember {
+        names: vec![Some(StrRef::from(name)), Some(StrRef::from(mapped))],
+        desc,
+        ..Default::default()
+    }
+}
+
+fn names(columns: &[&str]) -> Vec<Option<StrRef>> {
+    columns.iter().map(|name| Some(StrRef::from(*name))).collect()
+}
@@ -0,0 +1,189 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::mapping::tree::{class_entry, illegal_line, non_empty};
+use crate::mapping::{MappedMember, MappedParam, MappingTree};
+use crate::StrRef;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The element which the following deeper lines belong to.
+#[derive(Clone, Copy)]
+enum Parent {
+    None,
+    Class(usize),
+    Field(usize, usize),
+    Method(usize, usize),
+    Param,
+}
+
+impl MappingTree {
+    /// Reads a Tiny v2 file of Fabric, e.g. `tiny\t2\t0\tofficial\tintermediary\tnamed`.
+    /// Local variables are skipped.
+    pub fn parse_tiny_v2(content: &str) -> AsmResult<MappingTree> {
+        let mut lines = content.lines().enumerate();
+        let header = lines.next().map(|(_, line)| line).unwrap_or_default();
+        let namespaces: Vec<&str> = match header.split('\t').collect::<Vec<_>>()[..] {
+            ["tiny", "2", _, ref namespaces @ ..] if namespaces.len() >= 2 => namespaces.to_vec(),
+            _ => return Err(AsmErr::IllegalFormat(format!("illegal tiny v2 header: {header}"))),
+        };
+        let mut tree = MappingTree::new(namespaces.iter().map(|ns| StrRef::from(*ns)).collect());
+        let namespace_count = namespaces.len();
+        let mut class_index = HashMap::new();
+        let mut escaped_names = false;
+        let mut in_header = true;
+        let mut parents = [Parent::None; 4];
+        for (index, line) in lines {
+            if line.trim().is_empty() { continue; }
+            let depth = line.len() - line.trim_start_matches('\t').len();
+            let columns: Vec<&str> = line[depth..].split('\t').collect();
+            let err = || illegal_line("tiny", index, line);
+            if in_header && depth == 1 {
+                escaped_names |= columns[0] == "escaped-names";
+                continue;
+            }
+            in_header = false;
+            let parent = match depth {
+                0 => Parent::None,
+                _ => *parents.get(depth - 1).ok_or_else(err)?,
+            };
+            let names = |columns: &[&str]| -> Vec<Option<StrRef>> {
+                let mut names: Vec<_> = columns.iter()
+                    .map(|name| if escaped_names { non_empty(&unescape(name)) } else { non_empty(name) })
+                    .collect();
+                names.resize(namespace_count, None);
+                names
+            };
+            let current = match (parent, &columns[..]) {
+                (Parent::None, ["c", class_names @ ..]) => {
+                    let class_names = names(class_names);
+                    let source = class_names[0].clone().ok_or_else(err)?;
+                    let class = class_entry(&mut tree, &mut class_index, &source);
+                    for (name, new_name) in class.names.iter_mut().zip(class_names) {
+                        if new_name.is_some() { *name = new_name; }
+                    }
+                    Parent::Class(class_index[&source])
+                }
+                (Parent::Class(class), ["c", comment]) => {
+                    tree.classes[class].comment = Some(StrRef::from(unescape(comment)));
+                    Parent::None
+                }
+                (Parent::Class(class), [kind @ ("f" | "m"), desc, member_names @ ..]) => {
+                    let member = MappedMember {
+                        names: names(member_names),
+                        desc: Some(StrRef::from(*desc)),
+                        ..Default::default()
+                    };
+                    if member.names[0].is_none() { return Err(err()); }
+                    let owner = &mut tree.classes[class];
+                    if *kind == "f" {
+                        owner.fields.push(member);
+                        Parent::Field(class, owner.fields.len() - 1)
+                    } else {
+                        owner.methods.push(member);
+                        Parent::Method(class, owner.methods.len() - 1)
+                    }
+                }
+                (Parent::Field(class, field), ["c", comment]) => {
+                    tree.classes[class].fields[field].comment = Some(StrRef::from(unescape(comment)));
+                    Parent::None
+                }
+                (Parent::Method(class, method), ["c", comment]) => {
+                    tree.classes[class].methods[method].comment = Some(StrRef::from(unescape(comment)));
+                    Parent::None
+                }
+                (Parent::Method(class, method), ["p", lv_index, param_names @ ..]) => {
+                    let lv_index = lv_index.parse().map_err(|_| err())?;
+                    let param = MappedParam { lv_index, names: names(param_names) };
+                    tree.classes[class].methods[method].params.push(param);
+                    Parent::Param
+                }
+                // local variables and comments of parameters
+                (Parent::Method(..), ["v", ..]) | (Parent::Param, ["c", _]) => Parent::None,
+                _ => return Err(err()),
+            };
+            parents[depth] = current;
+        }
+        Ok(tree)
+    }
+
+    /// Writes all namespaces as a Tiny v2 file, fields without descriptors are skipped
+    /// since Tiny requires them.
+    pub fn to_tiny_v2(&self) -> String {
+        let mut output = String::from("tiny\t2\t0");
+        for namespace in &self.namespaces {
+            output.push('\t');
+            output.push_str(namespace);
+        }
+        output.push('\n');
+        let names = |output: &mut String, names: &[Option<StrRef>]| {
+            for ns in 0..self.namespaces.len() {
+                output.push('\t');
+                if let Some(Some(name)) = names.get(ns) {
+                    output.push_str(name);
+                }
+            }
+            output.push('\n');
+        };
+        let comment = |output: &mut String, indent: &str, comment: &Option<StrRef>| {
+            if let Some(comment) = comment {
+                let _ = writeln!(output, "{indent}c\t{}", escape(comment));
+            }
+        };
+        for class in &self.classes {
+            output.push('c');
+            names(&mut output, &class.names);
+            comment(&mut output, "\t", &class.comment);
+            for field in &class.fields {
+                let Some(desc) = &field.desc else { continue; };
+                let _ = write!(output, "\tf\t{desc}");
+                names(&mut output, &field.names);
+                comment(&mut output, "\t\t", &field.comment);
+            }
+            for method in &class.methods {
+                let Some(desc) = &method.desc else { continue; };
+                let _ = write!(output, "\tm\t{desc}");
+                names(&mut output, &method.names);
+                comment(&mut output, "\t\t", &method.comment);
+                for param in &method.params {
+                    let _ = write!(output, "\t\tp\t{}", param.lv_index);
+                    names(&mut output, &param.names);
+                }
+            }
+        }
+        output
+    }
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('0') => result.push('\0'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\0' => result.push_str("\\0"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
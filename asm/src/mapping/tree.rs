@@ -0,0 +1,365 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::remap::Remapper;
+use crate::{DescriptorRef, InternalNameRef, StrRef};
+use std::collections::HashMap;
+
+/// Names of classes, fields and methods in several namespaces, the common model of
+/// mapping formats, e.g. `official`, `intermediary` and `named` of a Tiny file.
+///
+/// Names are indexed like [MappingTree::namespaces], [None] means the element keeps its name
+/// of the first namespace. Descriptors always use class names of the first namespace.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MappingTree {
+    pub namespaces: Vec<StrRef>,
+    pub classes: Vec<MappedClass>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MappedClass {
+    pub names: Vec<Option<InternalNameRef>>,
+    pub fields: Vec<MappedMember>,
+    pub methods: Vec<MappedMember>,
+    pub comment: Option<StrRef>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MappedMember {
+    pub names: Vec<Option<StrRef>>,
+    /// [None] for fields of formats without field descriptors, e.g. SRG.
+    pub desc: Option<DescriptorRef>,
+    /// Parameters of a method.
+    pub params: Vec<MappedParam>,
+    pub comment: Option<StrRef>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MappedParam {
+    /// The index of the local variable, `this` is 0 for instance methods.
+    pub lv_index: u16,
+    /// Unlike classes and members, parameters may have no name in the first namespace.
+    pub names: Vec<Option<StrRef>>,
+}
+
+/// The format of a mapping file, see [MappingTree::parse] and [MappingTree::write].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MappingFormat {
+    Proguard,
+    TinyV2,
+    Srg,
+    Tsrg,
+    Tsrg2,
+    Enigma,
+}
+
+impl MappingFormat {
+    /// Guesses the format from the content of a mapping file.
+    pub fn detect(content: &str) -> Option<MappingFormat> {
+        let first_line = content.lines()
+            .map(str::trim_end)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+        let format = if first_line.starts_with("tiny\t2\t") {
+            MappingFormat::TinyV2
+        } else if first_line.starts_with("tsrg2 ") {
+            MappingFormat::Tsrg2
+        } else if ["PK: ", "CL: ", "FD: ", "MD: "].iter().any(|prefix| first_line.starts_with(prefix)) {
+            MappingFormat::Srg
+        } else if first_line.starts_with("CLASS") {
+            MappingFormat::Enigma
+        } else if first_line.contains(" -> ") {
+            MappingFormat::Proguard
+        } else if !first_line.starts_with(char::is_whitespace) {
+            MappingFormat::Tsrg
+        } else {
+            return None;
+        };
+        Some(format)
+    }
+}
+
+impl MappedClass {
+    fn new(names: Vec<Option<InternalNameRef>>) -> Self {
+        MappedClass { names, ..Default::default() }
+    }
+}
+
+impl MappingTree {
+    pub fn new(namespaces: Vec<StrRef>) -> Self {
+        MappingTree { namespaces, classes: Vec::new() }
+    }
+
+    /// Reads a mapping file of any [MappingFormat].
+    pub fn parse(content: &str) -> AsmResult<MappingTree> {
+        let format = MappingFormat::detect(content)
+            .ok_or_else(|| AsmErr::IllegalFormat("unknown mapping format".to_string()))?;
+        Self::parse_format(content, format)
+    }
+
+    pub fn parse_format(content: &str, format: MappingFormat) -> AsmResult<MappingTree> {
+        match format {
+            MappingFormat::Proguard => Ok(super::ProguardMapping::parse(content)?.to_tree()),
+            MappingFormat::TinyV2 => Self::parse_tiny_v2(content),
+            MappingFormat::Srg => Self::parse_srg(content),
+            MappingFormat::Tsrg | MappingFormat::Tsrg2 => Self::parse_tsrg(content),
+            MappingFormat::Enigma => Self::parse_enigma(content),
+        }
+    }
+
+    /// Writes the mapping in `format`, formats with two namespaces write the first two.
+    pub fn write(&self, format: MappingFormat) -> AsmResult<String> {
+        match format {
+            MappingFormat::Proguard => Err(AsmErr::IllegalFormat(
+                "writing ProGuard mappings is not supported".to_string(),
+            )),
+            MappingFormat::TinyV2 => Ok(self.to_tiny_v2()),
+            MappingFormat::Srg => self.to_srg(),
+            MappingFormat::Tsrg => self.to_tsrg(),
+            MappingFormat::Tsrg2 => Ok(self.to_tsrg2()),
+            MappingFormat::Enigma => self.to_enigma(),
+        }
+    }
+
+    pub fn namespace_index(&self, namespace: &str) -> AsmResult<usize> {
+        self.namespaces.iter().position(|ns| **ns == *namespace).ok_or_else(|| {
+            AsmErr::IllegalFormat(format!("unknown namespace `{namespace}`, namespaces are {:?}", self.namespaces))
+        })
+    }
+
+    /// The name of a class in the namespace `ns`, which is its name of the first namespace if it is missing.
+    pub fn class_name<'a>(&self, class: &'a MappedClass, ns: usize) -> &'a InternalNameRef {
+        name_or_source(&class.names, ns)
+    }
+
+    /// Renames classes from the first namespace to the namespace `ns`.
+    pub(crate) fn class_renamer(&self, ns: usize) -> impl Remapper + '_ {
+        let names: HashMap<&str, &InternalNameRef> = self.classes.iter()
+            .filter_map(|class| Some((&**class.names[0].as_ref()?, self.class_name(class, ns))))
+            .collect();
+        ClassRenamer(names)
+    }
+
+    /// The descriptor of `member` with class names of the namespace `ns`.
+    fn member_desc(&self, renamer: &impl Remapper, member: &MappedMember) -> AsmResult<Option<DescriptorRef>> {
+        member.desc.as_ref().map(|desc| renamer.map_desc(desc)).transpose()
+    }
+
+    /// A mapping with the given namespaces in the given order, the first one is the new source
+    /// namespace. Names missing in the new source namespace are taken from the old one.
+    pub fn with_namespaces(&self, namespaces: &[&str]) -> AsmResult<MappingTree> {
+        let indexes = namespaces.iter()
+            .map(|namespace| self.namespace_index(namespace))
+            .collect::<AsmResult<Vec<_>>>()?;
+        let Some(&source) = indexes.first() else {
+            return Err(AsmErr::IllegalFormat("no namespaces selected".to_string()));
+        };
+        let select = |names: &[Option<StrRef>]| -> Vec<Option<StrRef>> {
+            let mut selected: Vec<_> = indexes.iter().map(|&ns| names.get(ns).cloned().flatten()).collect();
+            if selected[0].is_none() {
+                selected[0] = names.first().cloned().flatten();
+            }
+            selected
+        };
+        let renamer = self.class_renamer(source);
+        let mut tree = MappingTree::new(namespaces.iter().map(|ns| StrRef::from(*ns)).collect());
+        for class in &self.classes {
+            let select_member = |member: &MappedMember| -> AsmResult<MappedMember> {
+                Ok(MappedMember {
+                    names: select(&member.names),
+                    desc: self.member_desc(&renamer, member)?,
+                    params: member.params.iter()
+                        .map(|param| MappedParam { lv_index: param.lv_index, names: select(&param.names) })
+                        .collect(),
+                    comment: member.comment.clone(),
+                })
+            };
+            tree.classes.push(MappedClass {
+                names: select(&class.names),
+                fields: class.fields.iter().map(select_member).collect::<AsmResult<_>>()?,
+                methods: class.methods.iter().map(select_member).collect::<AsmResult<_>>()?,
+                comment: class.comment.clone(),
+            });
+        }
+        Ok(tree)
+    }
+
+    /// Appends the namespaces of `next` to this mapping, e.g. `official -> intermediary` chained
+    /// with `intermediary -> named` is `official -> intermediary -> named`.
+    ///
+    /// The first namespace of `next` must be one of this mapping, elements are matched by their
+    /// names in it. Elements only in `next` are dropped, since they have no names in this mapping.
+    pub fn chain(&self, next: &MappingTree) -> AsmResult<MappingTree> {
+        let joint_name = next.namespaces.first()
+            .ok_or_else(|| AsmErr::IllegalFormat("no namespaces in the chained mapping".to_string()))?;
+        let joint = self.namespace_index(joint_name)?;
+        let appended: Vec<usize> = (1..next.namespaces.len())
+            .filter(|&ns| !self.namespaces.contains(&next.namespaces[ns]))
+            .collect();
+        let mut namespaces = self.namespaces.clone();
+        namespaces.extend(appended.iter().map(|&ns| next.namespaces[ns].clone()));
+        let append = |names: &[Option<StrRef>], next_names: Option<&[Option<StrRef>]>| -> Vec<Option<StrRef>> {
+            let mut names = names.to_vec();
+            names.resize(self.namespaces.len(), None);
+            names.extend(appended.iter().map(|&ns| next_names.and_then(|next| next.get(ns).cloned().flatten())));
+            names
+        };
+
+        let next_classes: HashMap<&str, &MappedClass> = next.classes.iter()
+            .filter_map(|class| Some((&**class.names[0].as_ref()?, class)))
+            .collect();
+        let to_joint = self.class_renamer(joint);
+        let mut tree = MappingTree::new(namespaces);
+        for class in &self.classes {
+            let next_class = next_classes.get(&**self.class_name(class, joint)).copied();
+            let chain_members = |members: &[MappedMember], next_members: Option<&[MappedMember]>| -> AsmResult<Vec<MappedMember>> {
+                let mut chained = Vec::with_capacity(members.len());
+                for member in members {
+                    let name = name_or_source(&member.names, joint);
+                    let desc = self.member_desc(&to_joint, member)?;
+                    let next_member = next_members.and_then(|next_members| find_mapped_member(next_members, name, desc.as_deref()));
+                    let params = member.params.iter().map(|param| {
+                        let next_param = next_member
+                            .and_then(|next| next.params.iter().find(|p| p.lv_index == param.lv_index));
+                        MappedParam { lv_index: param.lv_index, names: append(&param.names, next_param.map(|p| &p.names[..])) }
+                    }).collect();
+                    chained.push(MappedMember {
+                        names: append(&member.names, next_member.map(|m| &m.names[..])),
+                        desc: member.desc.clone(),
+                        params,
+                        comment: member.comment.clone().or_else(|| next_member.and_then(|m| m.comment.clone())),
+                    });
+                }
+                Ok(chained)
+            };
+            tree.classes.push(MappedClass {
+                names: append(&class.names, next_class.map(|c| &c.names[..])),
+                fields: chain_members(&class.fields, next_class.map(|c| &c.fields[..]))?,
+                methods: chain_members(&class.methods, next_class.map(|c| &c.methods[..]))?,
+                comment: class.comment.clone().or_else(|| next_class.and_then(|c| c.comment.clone())),
+            });
+        }
+        Ok(tree)
+    }
+
+    /// A [Remapper] from the namespace `from` to the namespace `to`.
+    pub fn remapper(&self, from: &str, to: &str) -> AsmResult<TreeRemapper> {
+        let from = self.namespace_index(from)?;
+        let to = self.namespace_index(to)?;
+        let to_from = self.class_renamer(from);
+        let mut remapper = TreeRemapper::default();
+        for class in &self.classes {
+            let owner = self.class_name(class, from).clone();
+            let mapped = self.class_name(class, to).clone();
+            remapper.unmapped.insert(mapped.clone(), owner.clone());
+            remapper.classes.insert(owner.clone(), mapped);
+            for (members, index) in [(&class.fields, &mut remapper.fields), (&class.methods, &mut remapper.methods)] {
+                let owner_index = index.entry(owner.clone()).or_default();
+                for member in members {
+                    let desc = self.member_desc(&to_from, member)?;
+                    let candidates = owner_index.entry(name_or_source(&member.names, from).clone()).or_default();
+                    candidates.push((desc, name_or_source(&member.names, to).clone()));
+                }
+            }
+        }
+        Ok(remapper)
+    }
+
+    /// A [Remapper] from the first namespace to the last one, e.g. from names in an obfuscated
+    /// file to readable names.
+    pub fn deobfuscator(&self) -> AsmResult<TreeRemapper> {
+        let (Some(from), Some(to)) = (self.namespaces.first(), self.namespaces.last()) else {
+            return Err(AsmErr::IllegalFormat("no namespaces in the mapping".to_string()));
+        };
+        self.remapper(from, to)
+    }
+}
+
+/// Member name -> candidates of (descriptor, mapped name).
+type MemberIndex = HashMap<StrRef, Vec<(Option<DescriptorRef>, StrRef)>>;
+
+/// A [Remapper] between two namespaces of a [MappingTree], see [MappingTree::remapper].
+///
+/// Members without descriptors in the mapping match any descriptor, and a member whose
+/// descriptor differs still matches when it is the only one with its name.
+#[derive(Clone, Debug, Default)]
+pub struct TreeRemapper {
+    classes: HashMap<InternalNameRef, InternalNameRef>,
+    unmapped: HashMap<InternalNameRef, InternalNameRef>,
+    fields: HashMap<InternalNameRef, MemberIndex>,
+    methods: HashMap<InternalNameRef, MemberIndex>,
+}
+
+impl TreeRemapper {
+    /// The name of a class before mapping, or [None] if the class is not in the mapping.
+    pub fn unmap_class(&self, mapped: &str) -> Option<&InternalNameRef> {
+        self.unmapped.get(mapped)
+    }
+
+    fn map_member(index: &HashMap<InternalNameRef, MemberIndex>, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        let candidates = index.get(owner)?.get(name)?;
+        let exact = candidates.iter().find(|(d, _)| d.as_deref().is_none_or(|d| d == desc));
+        match exact {
+            Some((_, mapped)) => Some(mapped.clone()),
+            None if candidates.len() == 1 => Some(candidates[0].1.clone()),
+            None => None,
+        }
+    }
+}
+
+impl Remapper for TreeRemapper {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.classes.get(internal_name).cloned()
+    }
+
+    fn map_method_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        Self::map_member(&self.methods, owner, name, desc)
+    }
+
+    fn map_field_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        Self::map_member(&self.fields, owner, name, desc)
+    }
+}
+
+struct ClassRenamer<'a>(HashMap<&'a str, &'a InternalNameRef>);
+
+impl Remapper for ClassRenamer<'_> {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.0.get(internal_name).map(|&name| name.clone())
+    }
+}
+
+pub(crate) fn name_or_source(names: &[Option<StrRef>], ns: usize) -> &StrRef {
+    names.get(ns).and_then(Option::as_ref)
+        .or_else(|| names.first().and_then(Option::as_ref))
+        .expect("mapped elements always have a name in the first namespace")
+}
+
+fn find_mapped_member<'a>(members: &'a [MappedMember], name: &str, desc: Option<&str>) -> Option<&'a MappedMember> {
+    let mut same_name = members.iter().filter(|member| member.names.first().cloned().flatten().as_deref() == Some(name));
+    let first = same_name.next()?;
+    if desc.is_none() || first.desc.is_none() || first.desc.as_deref() == desc {
+        return Some(first);
+    }
+    same_name.find(|member| member.desc.is_none() || member.desc.as_deref() == desc)
+}
+
+/// Finds or adds the class named `name` in the first namespace, for readers of formats
+/// which may list members of a class in several places.
+pub(crate) fn class_entry<'a>(
+    tree: &'a mut MappingTree, index: &mut HashMap<InternalNameRef, usize>, name: &str,
+) -> &'a mut MappedClass {
+    let position = *index.entry(StrRef::from(name)).or_insert_with(|| {
+        let mut names = vec![None; tree.namespaces.len()];
+        names[0] = Some(StrRef::from(name));
+        tree.classes.push(MappedClass::new(names));
+        tree.classes.len() - 1
+    });
+    &mut tree.classes[position]
+}
+
+/// `None` for empty names, which formats use for names which are not mapped.
+pub(crate) fn non_empty(name: &str) -> Option<StrRef> {
+    if name.is_empty() { None } else { Some(StrRef::from(name)) }
+}
+
+pub(crate) fn illegal_line(format: &str, index: usize, line: &str) -> AsmErr {
+    AsmErr::IllegalFormat(format!("{format} line {}: {line}", index + 1))
+}
@@ -1,16 +1,17 @@
+use crate::impls::node::r::node_reader::CodeLocation;
 use crate::jvms::attr::type_annotation::{TypeAnnotationTargetInfo, TypeAnnotationTargetPath};
 use crate::jvms::attr::Attribute as JvmsAttribute;
 use crate::jvms::attr::{LineNumberTableInfo, StackMapFrame};
 use crate::jvms::element::AttributeInfo;
 use crate::node::values::{
-    AnnotationValue, ConstValue, FieldInitialValue, LocalVariableInfo, LocalVariableTypeInfo,
+    AnnotationValue, ConstValue, FieldInitialValue, FrameAttributeValue, LocalVariableInfo, LocalVariableTypeInfo,
     ModuleAttrValue, ModuleExportValue, ModuleOpenValue, ModuleProvidesValue, ModuleRequireValue,
 };
 use crate::node::InsnNode;
 use crate::*;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ClassNode {
     /// The class version.
     pub minor_version: u16,
@@ -88,7 +89,7 @@ pub struct ClassNode {
     pub methods: Vec<MethodNode>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct MethodNode {
     /// The method's access flags (see [Opcodes]).
     pub access: u16,
@@ -134,18 +135,44 @@ pub struct MethodNode {
     /// The default value of this annotation interface method
     pub annotation_default: Option<AnnotationValue>,
 
-    pub code_body: Option<CodeBodyNode>,
+    /// The code of this method, it is decoded from the `Code` attribute on first use
+    /// when the method is read by [ClassNode::from_jvms].
+    ///
+    /// Breaking change: this was an `Option<CodeBodyNode>` before the code is decoded lazily.
+    /// Use [MethodNode::code] and [MethodNode::code_mut] to get the decoded code, and
+    /// `CodeBodyNode::into` to create it from a [CodeBodyNode].
+    pub code_body: Option<LazyCodeBody>,
 }
 
+/// The code of a method which is decoded when it is first used, like dex methods
+/// which only keep the offset of their code until [crate::dex::DexFileAccessor::get_code_item].
+///
+/// Use [LazyCodeBody::get] to get the decoded [CodeBodyNode]. It can be shared between
+/// threads, and the code is decoded only once.
+#[derive(Clone)]
+pub struct LazyCodeBody {
+    /// [None] for code which is created from a [CodeBodyNode] directly.
+    pub(crate) location: Option<CodeLocation>,
+    pub(crate) body: OnceLock<CodeBodyNode>,
+}
 
-#[derive(Clone, Debug)]
+
+#[derive(Clone, Debug, Default)]
 pub struct CodeBodyNode {
     pub instructions: Vec<InsnNode>,
 
+    /// The label of each instruction in [CodeBodyNode::instructions], in the same order.
+    /// Labels used by jumps, switches, exception tables, local variables and line numbers
+    /// refer to these values. When reading from a class file, it is the bytecode offset
+    /// of each instruction.
+    pub insn_offsets: Vec<LabelNode>,
+
     pub exception_table: Vec<ExceptionTable>,
 
     pub local_variables: Vec<LocalVariableNode>,
 
+    pub line_numbers: Vec<LineNumberNode>,
+
     pub max_stack: u16,
 
     pub max_locals: u16,
@@ -153,8 +180,10 @@ pub struct CodeBodyNode {
     /// it stores type annotations which located in local variable declarations,
     /// exception parameter declarations, expressions etc.
     pub type_annotations: Vec<TypeAnnotationNode>,
-    
-    pub stack_map_table: Vec<StackMapFrame>,
+
+    /// The frames of the `StackMapTable` attribute, class types are resolved from the
+    /// constant pool and offsets are bytecode offsets like [CodeBodyNode::insn_offsets].
+    pub stack_map_table: Vec<FrameAttributeValue>,
 
     /// The non-standard attributes of this code body.
     /// or didn't implement currently.
@@ -203,7 +232,7 @@ pub struct ParameterNode {
     pub access: u16,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct FieldNode {
     /// The field's access flags (see [Opcodes]).
     pub access: u16,
@@ -356,8 +385,9 @@ pub enum Attribute {
 #[derive(Clone, Debug)]
 pub struct EnclosingMethodAttribute {
     pub class: InternalNameRef,
-    pub method_name: StrRef,
-    pub method_desc: DescriptorRef,
+    /// [None] if the class is not enclosed in a method or constructor.
+    pub method_name: Option<StrRef>,
+    pub method_desc: Option<DescriptorRef>,
 }
 
 #[derive(Clone, Debug)]
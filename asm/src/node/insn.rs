@@ -1,6 +1,6 @@
 use crate::node::element::LabelNode;
 use crate::node::values::{ConstDynamic, ConstValue};
-use crate::{InternalNameRef, StrRef};
+use crate::{InternalNameRef, Opcodes, StrRef};
 use std::sync::Arc;
 
 //noinspection SpellCheckingInspection
@@ -71,3 +71,24 @@ pub enum InsnNode {
         var_index: u16, // index of the local variable to load or store
     },
 }
+
+impl InsnNode {
+    /// The opcode of this instruction, constants are always [Opcodes::LDC] no matter
+    /// how they are encoded in the bytecode.
+    pub fn opcode(&self) -> u8 {
+        match self {
+            InsnNode::FieldInsnNode { opcode, .. } | InsnNode::NoOperand { opcode } |
+            InsnNode::JumpInsnNode { opcode, .. } | InsnNode::MethodInsnNode { opcode, .. } |
+            InsnNode::TypeInsnNode { opcode, .. } | InsnNode::VarInsnNode { opcode, .. } => *opcode,
+            InsnNode::IIncInsnNode { .. } => Opcodes::IINC,
+            InsnNode::BIPushInsnNode { .. } => Opcodes::BIPUSH,
+            InsnNode::SIPushInsnNode { .. } => Opcodes::SIPUSH,
+            InsnNode::InvokeDynamicInsnNode(_) => Opcodes::INVOKEDYNAMIC,
+            InsnNode::LdcInsnNode(_) => Opcodes::LDC,
+            InsnNode::TableSwitchInsnNode { .. } => Opcodes::TABLESWITCH,
+            InsnNode::LookupSwitchInsnNode { .. } => Opcodes::LOOKUPSWITCH,
+            InsnNode::NewArrayInsnNode { .. } => Opcodes::NEWARRAY,
+            InsnNode::MultiANewArrayInsnNode { .. } => Opcodes::MULTIANEWARRAY,
+        }
+    }
+}
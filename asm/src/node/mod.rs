@@ -6,4 +6,5 @@ pub mod element;
 pub mod values;
 
 mod read;
+mod write;
 mod insn;
@@ -2,14 +2,17 @@ use std::io::Read;
 
 use crate::err::AsmResult;
 
-use crate::impls::node::r::impls::from_jvms_internal;
 use crate::jvms::element::ClassFile;
 use crate::jvms::JvmsClassReader;
 use crate::node::element::ClassNode;
+use crate::visitor::{ClassNodeBuilder, ClassReader, ReadFlags};
 
 impl ClassNode {
+    /// Reads the class, method code is decoded when [crate::node::element::MethodNode::code_body] is first used.
     pub fn from_jvms(jvms_file: ClassFile) -> AsmResult<ClassNode> {
-        from_jvms_internal(jvms_file)
+        let mut builder = ClassNodeBuilder::new();
+        ClassReader::new(jvms_file).accept(&mut builder, ReadFlags::LAZY_CODE)?;
+        Ok(builder.into_node())
     }
 
     pub fn from_read<T: Read>(read: T) -> AsmResult<ClassNode> {
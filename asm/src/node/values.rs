@@ -104,6 +104,8 @@ pub enum BootstrapMethodArgument {
     String(StrRef),
     Class(InternalNameRef),
     Handle(Handle),
+    /// A method descriptor, e.g. the erased and the instantiated method types of lambdas.
+    MethodType(DescriptorRef),
 }
 
 #[derive(Clone, Debug)]
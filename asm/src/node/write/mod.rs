@@ -0,0 +1,21 @@
+use std::io::Write;
+
+use crate::err::AsmResult;
+use crate::impls::node::w::impls::to_jvms_internal;
+use crate::jvms::element::ClassFile;
+use crate::jvms::JvmsClassWriter;
+use crate::node::element::ClassNode;
+
+impl ClassNode {
+    pub fn to_jvms(&self) -> AsmResult<ClassFile> {
+        to_jvms_internal(self)
+    }
+
+    pub fn to_write<T: Write>(&self, write: T) -> AsmResult<()> {
+        JvmsClassWriter::write_class_file(write, self.to_jvms()?)
+    }
+
+    pub fn to_bytes(&self) -> AsmResult<Vec<u8>> {
+        JvmsClassWriter::write_class_bytes(vec![], self.to_jvms()?)
+    }
+}
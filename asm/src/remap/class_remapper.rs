@@ -0,0 +1,333 @@
+use crate::err::AsmResult;
+use crate::impls::desc_to_internal;
+use crate::node::element::{AnnotationNode, ClassNode, CodeBodyNode, FieldNode, InnerClassNode, MethodNode, ModuleNode, RecordComponentNode, TypeAnnotationNode};
+use crate::node::values::{AnnotationValue, BootstrapMethodArgument, ConstDynamic, ConstValue, FieldInitialValue, FrameAttributeValue, FrameValue};
+use crate::node::InsnNode;
+use crate::remap::Remapper;
+use crate::StrRef;
+use std::sync::Arc;
+
+/// Rewrites every name of a [ClassNode] with a [Remapper], like ASM's `ClassRemapper`.
+///
+/// Names, descriptors and signatures of the class and its members are rewritten, as well as
+/// annotations, inner classes, nest members, permitted subclasses, record components and
+/// the references in method code. Method code is decoded if it is still lazy.
+pub struct ClassRemapper<'a, R: Remapper + ?Sized> {
+    remapper: &'a R,
+}
+
+impl<'a, R: Remapper + ?Sized> ClassRemapper<'a, R> {
+    pub fn new(remapper: &'a R) -> Self {
+        ClassRemapper { remapper }
+    }
+
+    pub fn remap(&self, class: &mut ClassNode) -> AsmResult<()> {
+        let r = self.remapper;
+        let owner = class.name.clone();
+        class.name = r.map_type(&owner)?;
+        class.signature = self.signature(&class.signature, false)?;
+        class.super_name = self.opt_type(&class.super_name)?;
+        self.types(&mut class.interfaces)?;
+        if let Some(module) = &mut class.module {
+            self.module(module)?;
+        }
+
+        if let Some(outer_class) = &class.outer_class {
+            if let (Some(name), Some(desc)) = (&class.outer_method_name, &class.outer_method_desc) {
+                class.outer_method_name = Some(r.map_method_name(outer_class, name, desc).unwrap_or_else(|| name.clone()));
+            }
+            class.outer_class = Some(r.map_type(outer_class)?);
+        }
+        class.outer_method_desc = self.opt_desc(&class.outer_method_desc)?;
+
+        self.annotations(&mut class.annotations)?;
+        self.type_annotations(&mut class.type_annotations)?;
+        for inner_class in &mut class.inner_classes {
+            self.inner_class(inner_class)?;
+        }
+        class.nest_host_class = self.opt_type(&class.nest_host_class)?;
+        self.types(&mut class.nest_members)?;
+        self.types(&mut class.permitted_subclasses)?;
+        for record_component in &mut class.record_components {
+            self.record_component(&owner, record_component)?;
+        }
+        for field in &mut class.fields {
+            self.field(&owner, field)?;
+        }
+        for method in &mut class.methods {
+            self.method(&owner, method)?;
+        }
+        Ok(())
+    }
+
+    fn module(&self, module: &mut ModuleNode) -> AsmResult<()> {
+        let r = self.remapper;
+        module.name = self.module_name(&module.name);
+        module.main_class = self.opt_type(&module.main_class)?;
+        for package in &mut module.packages {
+            *package = self.package_name(package);
+        }
+        for require in &mut module.requires {
+            require.module = self.module_name(&require.module);
+        }
+        for export in &mut module.exports {
+            export.package = self.package_name(&export.package);
+            for module_name in &mut export.modules {
+                *module_name = self.module_name(module_name);
+            }
+        }
+        for open in &mut module.opens {
+            open.package = self.package_name(&open.package);
+            for module_name in &mut open.modules {
+                *module_name = self.module_name(module_name);
+            }
+        }
+        self.types(&mut module.uses)?;
+        for provide in &mut module.provides {
+            provide.service = r.map_type(&provide.service)?;
+            self.types(&mut provide.providers)?;
+        }
+        Ok(())
+    }
+
+    fn inner_class(&self, inner_class: &mut InnerClassNode) -> AsmResult<()> {
+        // anonymous classes have no simple name
+        if !inner_class.inner_name.is_empty() {
+            inner_class.inner_name = self.remapper.map_inner_class_name(&inner_class.name, &inner_class.inner_name)?;
+        }
+        inner_class.name = self.remapper.map_type(&inner_class.name)?;
+        inner_class.outer_name = self.opt_type(&inner_class.outer_name)?;
+        Ok(())
+    }
+
+    fn record_component(&self, owner: &str, component: &mut RecordComponentNode) -> AsmResult<()> {
+        let r = self.remapper;
+        if let Some(name) = r.map_record_component_name(owner, &component.name, &component.desc) {
+            component.name = name;
+        }
+        component.desc = r.map_desc(&component.desc)?;
+        component.signature = self.signature(&component.signature, true)?;
+        self.annotations(&mut component.annotations)?;
+        self.type_annotations(&mut component.type_annotations)
+    }
+
+    fn field(&self, owner: &str, field: &mut FieldNode) -> AsmResult<()> {
+        let r = self.remapper;
+        if let Some(name) = r.map_field_name(owner, &field.name, &field.desc) {
+            field.name = name;
+        }
+        field.desc = r.map_desc(&field.desc)?;
+        field.signature = self.signature(&field.signature, true)?;
+        if let Some(FieldInitialValue::String(value)) = &mut field.value {
+            *value = self.string(value);
+        }
+        self.annotations(&mut field.annotations)?;
+        self.type_annotations(&mut field.type_annotations)
+    }
+
+    fn method(&self, owner: &str, method: &mut MethodNode) -> AsmResult<()> {
+        let r = self.remapper;
+        if let Some(name) = r.map_method_name(owner, &method.name, &method.desc) {
+            method.name = name;
+        }
+        method.desc = r.map_desc(&method.desc)?;
+        method.signature = self.signature(&method.signature, false)?;
+        self.types(&mut method.exceptions)?;
+        self.annotations(&mut method.annotations)?;
+        self.type_annotations(&mut method.type_annotations)?;
+        for annotations in &mut method.parameter_annotations {
+            self.annotations(annotations)?;
+        }
+        if let Some(value) = &mut method.annotation_default {
+            self.annotation_value(value)?;
+        }
+        if let Some(code_body) = method.code_mut()? {
+            self.code(code_body)?;
+        }
+        Ok(())
+    }
+
+    fn code(&self, code: &mut CodeBodyNode) -> AsmResult<()> {
+        let r = self.remapper;
+        for insn in &mut code.instructions {
+            self.insn(insn)?;
+        }
+        for exception in &mut code.exception_table {
+            exception.catch_type = self.opt_type(&exception.catch_type)?;
+        }
+        for local_variable in &mut code.local_variables {
+            local_variable.desc = r.map_desc(&local_variable.desc)?;
+            local_variable.signature = self.signature(&local_variable.signature, true)?;
+        }
+        self.type_annotations(&mut code.type_annotations)?;
+        for frame in &mut code.stack_map_table {
+            match frame {
+                FrameAttributeValue::SameLocals1StackItemFrame { stack, .. } |
+                FrameAttributeValue::SameLocals1StackItemFrameExtended { stack, .. } => self.frame_value(stack)?,
+                FrameAttributeValue::AppendFrame { append_locals, .. } => {
+                    for local in append_locals {
+                        self.frame_value(local)?;
+                    }
+                }
+                FrameAttributeValue::FullFrame { locals, stack, .. } => {
+                    for value in locals.iter_mut().chain(stack.iter_mut()) {
+                        self.frame_value(value)?;
+                    }
+                }
+                FrameAttributeValue::SameFrame { .. } | FrameAttributeValue::SameFrameExtended { .. } |
+                FrameAttributeValue::ChopFrame { .. } => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn frame_value(&self, value: &mut FrameValue) -> AsmResult<()> {
+        if let FrameValue::Object(name) = value {
+            *name = self.remapper.map_type(name)?;
+        }
+        Ok(())
+    }
+
+    fn insn(&self, insn: &mut InsnNode) -> AsmResult<()> {
+        let r = self.remapper;
+        match insn {
+            InsnNode::FieldInsnNode { owner, name, desc, .. } => {
+                if let Some(new_name) = r.map_field_name(owner, name, desc) {
+                    *name = new_name;
+                }
+                *owner = r.map_type(owner)?;
+                *desc = r.map_desc(desc)?;
+            }
+            InsnNode::MethodInsnNode { owner, name, desc, .. } => {
+                if let Some(new_name) = r.map_method_name(owner, name, desc) {
+                    *name = new_name;
+                }
+                *owner = r.map_type(owner)?;
+                *desc = r.map_desc(desc)?;
+            }
+            InsnNode::InvokeDynamicInsnNode(const_dynamic) => self.const_dynamic(const_dynamic)?,
+            InsnNode::LdcInsnNode(value) => {
+                let mapped = match &**value {
+                    ConstValue::Class(name) => ConstValue::Class(r.map_type(name)?),
+                    ConstValue::String(string) => ConstValue::String(self.string(string)),
+                    ConstValue::MethodType(desc) => ConstValue::MethodType(r.map_desc(desc)?),
+                    ConstValue::MethodHandle(handle) => ConstValue::MethodHandle(r.map_handle(handle)?),
+                    ConstValue::Dynamic { bootstrap_method_attr_index, name, desc } => ConstValue::Dynamic {
+                        bootstrap_method_attr_index: *bootstrap_method_attr_index,
+                        name: r.map_invoke_dynamic_method_name(name, desc).unwrap_or_else(|| name.clone()),
+                        desc: r.map_desc(desc)?,
+                    },
+                    _ => return Ok(()),
+                };
+                *value = Arc::new(mapped);
+            }
+            InsnNode::TypeInsnNode { type_name, .. } => *type_name = r.map_type(type_name)?,
+            InsnNode::MultiANewArrayInsnNode { array_type, .. } => *array_type = r.map_type(array_type)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn const_dynamic(&self, const_dynamic: &mut ConstDynamic) -> AsmResult<()> {
+        let r = self.remapper;
+        if let Some(name) = r.map_invoke_dynamic_method_name(&const_dynamic.name, &const_dynamic.desc) {
+            const_dynamic.name = name;
+        }
+        const_dynamic.desc = r.map_desc(&const_dynamic.desc)?;
+        const_dynamic.bsm = r.map_handle(&const_dynamic.bsm)?;
+        for arg in &mut const_dynamic.bsm_args {
+            match arg {
+                BootstrapMethodArgument::Class(name) => *name = r.map_type(name)?,
+                BootstrapMethodArgument::Handle(handle) => *handle = r.map_handle(handle)?,
+                BootstrapMethodArgument::MethodType(desc) => *desc = r.map_desc(desc)?,
+                BootstrapMethodArgument::String(string) => *string = self.string(string),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn annotations(&self, annotations: &mut [AnnotationNode]) -> AsmResult<()> {
+        for annotation in annotations {
+            self.annotation(annotation)?;
+        }
+        Ok(())
+    }
+
+    fn type_annotations(&self, type_annotations: &mut [TypeAnnotationNode]) -> AsmResult<()> {
+        for type_annotation in type_annotations {
+            self.annotation(&mut type_annotation.annotation_node)?;
+        }
+        Ok(())
+    }
+
+    fn annotation(&self, annotation: &mut AnnotationNode) -> AsmResult<()> {
+        let r = self.remapper;
+        for (name, value) in &mut annotation.values {
+            if let Some(new_name) = r.map_annotation_attribute_name(&annotation.type_name, name) {
+                *name = new_name;
+            }
+            self.annotation_value(value)?;
+        }
+        // the type of an annotation is a descriptor
+        annotation.type_name = r.map_desc(&annotation.type_name)?;
+        Ok(())
+    }
+
+    fn annotation_value(&self, value: &mut AnnotationValue) -> AsmResult<()> {
+        let r = self.remapper;
+        match value {
+            AnnotationValue::Enum(desc, name) => {
+                if let Some(new_name) = r.map_field_name(desc_to_internal(desc), name, desc) {
+                    *name = new_name;
+                }
+                *desc = r.map_desc(desc)?;
+            }
+            // a return descriptor, e.g. `Ljava/lang/String;` or `V`
+            AnnotationValue::Class(desc) => *desc = r.map_desc(desc)?,
+            AnnotationValue::Annotation(annotation) => self.annotation(annotation)?,
+            AnnotationValue::Array(values) => {
+                for value in values {
+                    self.annotation_value(value)?;
+                }
+            }
+            AnnotationValue::Const(constant) => {
+                if let ConstValue::String(string) = &**constant {
+                    *constant = Arc::new(ConstValue::String(self.string(string)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn types(&self, names: &mut [StrRef]) -> AsmResult<()> {
+        for name in names {
+            *name = self.remapper.map_type(name)?;
+        }
+        Ok(())
+    }
+
+    fn opt_type(&self, name: &Option<StrRef>) -> AsmResult<Option<StrRef>> {
+        name.as_ref().map(|name| self.remapper.map_type(name)).transpose()
+    }
+
+    fn opt_desc(&self, desc: &Option<StrRef>) -> AsmResult<Option<StrRef>> {
+        desc.as_ref().map(|desc| self.remapper.map_desc(desc)).transpose()
+    }
+
+    fn signature(&self, signature: &Option<StrRef>, type_signature: bool) -> AsmResult<Option<StrRef>> {
+        signature.as_ref().map(|signature| self.remapper.map_signature(signature, type_signature)).transpose()
+    }
+
+    fn string(&self, value: &StrRef) -> StrRef {
+        self.remapper.map_string(value).unwrap_or_else(|| value.clone())
+    }
+
+    fn module_name(&self, name: &StrRef) -> StrRef {
+        self.remapper.map_module_name(name).unwrap_or_else(|| name.clone())
+    }
+
+    fn package_name(&self, name: &StrRef) -> StrRef {
+        self.remapper.map_package_name(name).unwrap_or_else(|| name.clone())
+    }
+}
@@ -0,0 +1,186 @@
+pub use class_remapper::*;
+pub use relocate::*;
+pub use smali::*;
+
+use crate::err::{AsmErr, AsmResult};
+use crate::node::values::Handle;
+use crate::{MethodHandleKind, StrRef};
+use std::collections::HashMap;
+
+mod class_remapper;
+mod relocate;
+mod signature;
+mod smali;
+
+/// Renames classes, fields and methods, like ASM's `Remapper`.
+///
+/// The `map_*` hooks return the new name, or [None] to keep the original one. Member hooks
+/// are called with the original owner and the original descriptor. The provided methods
+/// rewrite types, descriptors, signatures and handles with these hooks.
+pub trait Remapper {
+    /// Maps an internal name of a class.
+    fn map(&self, _internal_name: &str) -> Option<StrRef> {
+        None
+    }
+
+    fn map_method_name(&self, _owner: &str, _name: &str, _desc: &str) -> Option<StrRef> {
+        None
+    }
+
+    fn map_field_name(&self, _owner: &str, _name: &str, _desc: &str) -> Option<StrRef> {
+        None
+    }
+
+    /// Maps the name of an `invokedynamic` call site or a dynamic constant.
+    fn map_invoke_dynamic_method_name(&self, _name: &str, _desc: &str) -> Option<StrRef> {
+        None
+    }
+
+    /// Maps the name of a record component, which is the name of its field by default.
+    fn map_record_component_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        self.map_field_name(owner, name, desc)
+    }
+
+    /// Maps the name of an element of an annotation, `desc` is the descriptor of the annotation.
+    fn map_annotation_attribute_name(&self, _desc: &str, _name: &str) -> Option<StrRef> {
+        None
+    }
+
+    /// Maps an internal name of a package, like `java/lang`.
+    fn map_package_name(&self, _name: &str) -> Option<StrRef> {
+        None
+    }
+
+    fn map_module_name(&self, _name: &str) -> Option<StrRef> {
+        None
+    }
+
+    /// Maps a string constant, e.g. a class name used by reflection. Strings are kept by default.
+    fn map_string(&self, _value: &str) -> Option<StrRef> {
+        None
+    }
+
+    /// Maps an internal name, or the descriptor of an array type.
+    fn map_type(&self, internal_name: &StrRef) -> AsmResult<StrRef> {
+        if internal_name.starts_with('[') {
+            self.map_desc(internal_name)
+        } else {
+            Ok(self.map(internal_name).unwrap_or_else(|| internal_name.clone()))
+        }
+    }
+
+    /// Maps a field or a method descriptor.
+    fn map_desc(&self, desc: &StrRef) -> AsmResult<StrRef> {
+        let mut mapped = String::with_capacity(desc.len());
+        let mut rest = &**desc;
+        while let Some(start) = rest.find('L') {
+            let end = rest[start..].find(';').ok_or_else(|| {
+                AsmErr::IllegalFormat(format!("unterminated class type in descriptor: {desc}"))
+            })? + start;
+            let internal_name = &rest[start + 1..end];
+            mapped.push_str(&rest[..=start]);
+            match self.map(internal_name) {
+                Some(new_name) => mapped.push_str(&new_name),
+                None => mapped.push_str(internal_name),
+            }
+            mapped.push(';');
+            rest = &rest[end + 1..];
+        }
+        mapped.push_str(rest);
+        Ok(keep_if_same(desc, mapped))
+    }
+
+    /// Maps a class, a method or a field signature, `type_signature` is true for field signatures.
+    fn map_signature(&self, signature: &StrRef, type_signature: bool) -> AsmResult<StrRef> {
+        let mapped = signature::remap_signature(self, signature, type_signature)?;
+        Ok(keep_if_same(signature, mapped))
+    }
+
+    fn map_handle(&self, handle: &Handle) -> AsmResult<Handle> {
+        let name = match handle.reference_kind {
+            MethodHandleKind::H_GETFIELD | MethodHandleKind::H_GETSTATIC |
+            MethodHandleKind::H_PUTFIELD | MethodHandleKind::H_PUTSTATIC =>
+                self.map_field_name(&handle.owner, &handle.name, &handle.desc),
+            _ => self.map_method_name(&handle.owner, &handle.name, &handle.desc),
+        };
+        Ok(Handle {
+            reference_kind: handle.reference_kind,
+            owner: self.map_type(&handle.owner)?,
+            name: name.unwrap_or_else(|| handle.name.clone()),
+            desc: self.map_desc(&handle.desc)?,
+        })
+    }
+
+    /// Maps the simple name of an inner class, `name` is the internal name of the inner class.
+    ///
+    /// The simple name only changes when the inner class is renamed to another simple name,
+    /// then it is the part of the new name after the last `$` and the digits of local classes.
+    fn map_inner_class_name(&self, name: &StrRef, inner_name: &StrRef) -> AsmResult<StrRef> {
+        let mapped = self.map_type(name)?;
+        if mapped == *name {
+            return Ok(inner_name.clone());
+        }
+        let same_simple_name = match (name.rfind('/'), mapped.rfind('/')) {
+            (Some(origin), Some(remapped)) => name[origin..] == mapped[remapped..],
+            _ => false,
+        };
+        if same_simple_name {
+            return Ok(inner_name.clone());
+        }
+        match mapped.rfind('$') {
+            Some(index) => {
+                let simple_name = mapped[index + 1..].trim_start_matches(|c: char| c.is_ascii_digit());
+                Ok(StrRef::from(simple_name))
+            }
+            None => Ok(inner_name.clone()),
+        }
+    }
+}
+
+fn keep_if_same(origin: &StrRef, mapped: String) -> StrRef {
+    if **origin == *mapped { origin.clone() } else { StrRef::from(mapped) }
+}
+
+/// A [Remapper] with a fixed mapping, the keys follow ASM's `SimpleRemapper`:
+///
+/// - `owner.name desc` for methods, without the space, e.g. `a/B.foo(I)V`.
+/// - `owner.name` for fields, e.g. `a/B.bar`.
+/// - `.name desc` for `invokedynamic` names, e.g. `.run()Ljava/lang/Runnable;`.
+/// - `desc.name` for annotation elements, e.g. `La/Anno;.value`.
+/// - the internal name for classes, e.g. `a/B`.
+#[derive(Clone, Debug, Default)]
+pub struct SimpleRemapper {
+    mapping: HashMap<String, StrRef>,
+}
+
+impl SimpleRemapper {
+    pub fn new(mapping: HashMap<String, StrRef>) -> Self {
+        SimpleRemapper { mapping }
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<StrRef>) {
+        self.mapping.insert(key.into(), value.into());
+    }
+}
+
+impl Remapper for SimpleRemapper {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.mapping.get(internal_name).cloned()
+    }
+
+    fn map_method_name(&self, owner: &str, name: &str, desc: &str) -> Option<StrRef> {
+        self.mapping.get(&format!("{owner}.{name}{desc}")).cloned()
+    }
+
+    fn map_field_name(&self, owner: &str, name: &str, _desc: &str) -> Option<StrRef> {
+        self.mapping.get(&format!("{owner}.{name}")).cloned()
+    }
+
+    fn map_invoke_dynamic_method_name(&self, name: &str, desc: &str) -> Option<StrRef> {
+        self.mapping.get(&format!(".{name}{desc}")).cloned()
+    }
+
+    fn map_annotation_attribute_name(&self, desc: &str, name: &str) -> Option<StrRef> {
+        self.mapping.get(&format!("{desc}.{name}")).cloned()
+    }
+}
@@ -0,0 +1,122 @@
+use crate::remap::Remapper;
+use crate::StrRef;
+
+/// Moves classes of a package into another package, like a relocation of the Maven shade plugin.
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    /// The internal name of the package with a trailing `/`, e.g. `com/google/common/`.
+    pattern: String,
+    shaded: String,
+    excludes: Vec<String>,
+}
+
+/// A [Remapper] which applies [Relocation]s to class names, package names and string
+/// constants which look like class names, the first matching relocation wins.
+#[derive(Clone, Debug, Default)]
+pub struct Relocator {
+    relocations: Vec<Relocation>,
+}
+
+impl Relocation {
+    /// `pattern` and `shaded` are package names, dotted or internal, e.g. `com.google.common`.
+    pub fn new(pattern: &str, shaded: &str) -> Self {
+        Relocation { pattern: package_prefix(pattern), shaded: package_prefix(shaded), excludes: Vec::new() }
+    }
+
+    /// Keeps a package or a class where it is, e.g. `com.google.common.annotations`.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.excludes.push(pattern.trim_end_matches(['.', '/', '*']).replace('.', "/"));
+        self
+    }
+
+    fn relocate(&self, internal_name: &str) -> Option<String> {
+        let rest = internal_name.strip_prefix(&self.pattern)?;
+        let excluded = self.excludes.iter().any(|exclude| {
+            internal_name.strip_prefix(exclude.as_str())
+                .is_some_and(|tail| tail.is_empty() || tail.starts_with(['/', '$']))
+        });
+        if excluded { return None; }
+        Some(format!("{}{rest}", self.shaded))
+    }
+}
+
+impl Relocator {
+    pub fn new(relocations: Vec<Relocation>) -> Self {
+        Relocator { relocations }
+    }
+
+    /// The relocated internal name of a class, or the relocated path of a resource in a JAR,
+    /// e.g. `com/google/common/base/messages.properties`. [None] if nothing is relocated.
+    pub fn relocate(&self, name: &str) -> Option<String> {
+        // classes of multi-release JARs are in `META-INF/versions/<version>/`
+        if let Some(versioned) = name.strip_prefix("META-INF/versions/")
+            && let Some((version, rest)) = versioned.split_once('/') {
+            return self.relocate(rest).map(|rest| format!("META-INF/versions/{version}/{rest}"));
+        }
+        self.relocations.iter().find_map(|relocation| relocation.relocate(name))
+    }
+
+    /// Relocates a dotted class name, e.g. `com.google.common.base.Joiner`.
+    pub fn relocate_dotted(&self, class_name: &str) -> Option<String> {
+        self.relocate(&class_name.replace('.', "/")).map(|name| name.replace('/', "."))
+    }
+
+    /// Relocates a `META-INF/services` file, which is named by a service interface and lists
+    /// its implementations. Returns the new path and content, or [None] if nothing changes.
+    pub fn relocate_service(&self, path: &str, content: &str) -> Option<(String, String)> {
+        let service = path.strip_prefix("META-INF/services/")?;
+        let mut changed = false;
+        let new_path = match self.relocate_dotted(service) {
+            Some(service) => {
+                changed = true;
+                format!("META-INF/services/{service}")
+            }
+            None => path.to_string(),
+        };
+        let mut new_content = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            // `# comments` may follow an implementation on the same line
+            let end = line.find('#').unwrap_or(line.len());
+            let implementation = line[..end].trim();
+            match self.relocate_dotted(implementation) {
+                Some(relocated) => {
+                    changed = true;
+                    new_content.push_str(&line.replacen(implementation, &relocated, 1));
+                }
+                None => new_content.push_str(line),
+            }
+        }
+        changed.then_some((new_path, new_content))
+    }
+}
+
+impl Remapper for Relocator {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.relocate(internal_name).map(StrRef::from)
+    }
+
+    fn map_package_name(&self, name: &str) -> Option<StrRef> {
+        let relocated = self.relocate(&format!("{name}/"))?;
+        Some(StrRef::from(relocated.trim_end_matches('/')))
+    }
+
+    /// Relocates strings which are class names or resource paths, e.g. `com.google.common.Foo`,
+    /// `com/google/common/Foo` or `/com/google/common/data.bin` of `Class.getResource`.
+    fn map_string(&self, value: &str) -> Option<StrRef> {
+        if value.is_empty() || value.contains(char::is_whitespace) { return None; }
+        if let Some(path) = value.strip_prefix('/') {
+            return self.relocate(path).map(|path| StrRef::from(format!("/{path}")));
+        }
+        let relocated = if value.contains('/') {
+            self.relocate(value)
+        } else {
+            self.relocate_dotted(value)
+        };
+        relocated.map(StrRef::from)
+    }
+}
+
+fn package_prefix(package: &str) -> String {
+    let package = package.trim_end_matches(['.', '/', '*']).replace('.', "/");
+    if package.is_empty() { package } else { format!("{package}/") }
+}
@@ -0,0 +1,164 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::remap::Remapper;
+use crate::StrRef;
+
+/// Rewrites class names of a signature (JVMS 4.7.9.1), the rest is copied as is.
+pub(crate) fn remap_signature<R: Remapper + ?Sized>(
+    remapper: &R, signature: &str, type_signature: bool,
+) -> AsmResult<String> {
+    let mut remapper = SignatureRemapper {
+        remapper, signature, pos: 0, out: String::with_capacity(signature.len()),
+    };
+    if type_signature {
+        remapper.type_signature()?;
+    } else {
+        remapper.class_or_method_signature()?;
+    }
+    if remapper.pos != signature.len() {
+        return Err(remapper.err());
+    }
+    Ok(remapper.out)
+}
+
+struct SignatureRemapper<'a, R: Remapper + ?Sized> {
+    remapper: &'a R,
+    signature: &'a str,
+    pos: usize,
+    out: String,
+}
+
+impl<'a, R: Remapper + ?Sized> SignatureRemapper<'a, R> {
+    fn peek(&self) -> Option<u8> {
+        self.signature.as_bytes().get(self.pos).copied()
+    }
+
+    fn err(&self) -> AsmErr {
+        AsmErr::IllegalFormat(format!("illegal signature at {}: {}", self.pos, self.signature))
+    }
+
+    fn copy(&mut self, expected: u8) -> AsmResult<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.err());
+        }
+        self.out.push(expected as char);
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Reads until one of `ends`, the end itself is not consumed.
+    fn identifier(&mut self, ends: &[u8]) -> AsmResult<&'a str> {
+        let start = self.pos;
+        let signature = self.signature;
+        while let Some(c) = self.peek() {
+            if ends.contains(&c) {
+                return Ok(&signature[start..self.pos]);
+            }
+            self.pos += 1;
+        }
+        Err(self.err())
+    }
+
+    fn class_or_method_signature(&mut self) -> AsmResult<()> {
+        if self.peek() == Some(b'<') {
+            self.formal_type_parameters()?;
+        }
+        if self.peek() == Some(b'(') {
+            self.copy(b'(')?;
+            while self.peek() != Some(b')') {
+                self.type_signature()?;
+            }
+            self.copy(b')')?;
+            // the return type
+            self.type_signature()?;
+            while self.peek() == Some(b'^') {
+                self.copy(b'^')?;
+                self.type_signature()?;
+            }
+        } else {
+            // the super class and the interfaces
+            while self.peek().is_some() {
+                self.type_signature()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn formal_type_parameters(&mut self) -> AsmResult<()> {
+        self.copy(b'<')?;
+        while self.peek() != Some(b'>') {
+            let name = self.identifier(b":>")?;
+            self.out.push_str(name);
+            // the class bound may be empty, interface bounds follow it
+            self.copy(b':')?;
+            if matches!(self.peek(), Some(b'L' | b'T' | b'[')) {
+                self.type_signature()?;
+            }
+            while self.peek() == Some(b':') {
+                self.copy(b':')?;
+                self.type_signature()?;
+            }
+        }
+        self.copy(b'>')
+    }
+
+    fn type_signature(&mut self) -> AsmResult<()> {
+        match self.peek() {
+            Some(c @ (b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b'V')) => self.copy(c),
+            Some(b'[') => {
+                self.copy(b'[')?;
+                self.type_signature()
+            }
+            Some(b'T') => {
+                let name = self.identifier(b";")?;
+                self.out.push_str(name);
+                self.copy(b';')
+            }
+            Some(b'L') => self.class_type_signature(),
+            _ => Err(self.err()),
+        }
+    }
+
+    fn class_type_signature(&mut self) -> AsmResult<()> {
+        self.copy(b'L')?;
+        let mut class_name = StrRef::from(self.identifier(b"<.;")?);
+        let mapped = self.remapper.map_type(&class_name)?;
+        self.out.push_str(&mapped);
+        loop {
+            match self.peek() {
+                Some(b'<') => self.type_arguments()?,
+                Some(b'.') => {
+                    self.copy(b'.')?;
+                    let inner_name = self.identifier(b"<.;")?;
+                    // like ASM, the inner part is the new name of `Outer$Inner` without
+                    // the new name of `Outer`
+                    let mapped_outer = format!("{}$", self.remapper.map_type(&class_name)?);
+                    class_name = StrRef::from(format!("{class_name}${inner_name}"));
+                    let mapped = self.remapper.map_type(&class_name)?;
+                    let index = if mapped.starts_with(&mapped_outer) {
+                        mapped_outer.len()
+                    } else {
+                        mapped.rfind('$').map_or(0, |index| index + 1)
+                    };
+                    self.out.push_str(&mapped[index..]);
+                }
+                Some(b';') => return self.copy(b';'),
+                _ => return Err(self.err()),
+            }
+        }
+    }
+
+    fn type_arguments(&mut self) -> AsmResult<()> {
+        self.copy(b'<')?;
+        while self.peek() != Some(b'>') {
+            match self.peek() {
+                Some(b'*') => self.copy(b'*')?,
+                Some(c @ (b'+' | b'-')) => {
+                    self.copy(c)?;
+                    self.type_signature()?;
+                }
+                _ => self.type_signature()?,
+            }
+        }
+        self.copy(b'>')
+    }
+}
@@ -0,0 +1,40 @@
+use crate::remap::Remapper;
+use crate::smali::{SmaliNode, SmaliToken};
+
+/// Renames [SmaliToken::Descriptor] and [SmaliToken::MemberName] tokens of a rendered class.
+///
+/// A member name belongs to the class descriptor right before it, e.g. `La/B; run ()V`,
+/// or to `owner` for declarations. Tokens which can't be mapped are kept.
+pub fn remap_smali<R: Remapper + ?Sized>(node: &mut SmaliNode, owner: &str, remapper: &R) {
+    remap_tokens(&mut node.content, owner, remapper);
+    for child in &mut node.children {
+        remap_smali(child, owner, remapper);
+    }
+}
+
+fn remap_tokens<R: Remapper + ?Sized>(tokens: &mut [SmaliToken], owner: &str, remapper: &R) {
+    // names are looked up with the original descriptors, so they are mapped first
+    for i in 0..tokens.len() {
+        let SmaliToken::MemberName(name) = &tokens[i] else { continue; };
+        let member_owner = match i.checked_sub(1).map(|prev| &tokens[prev]) {
+            Some(SmaliToken::Descriptor(desc)) if desc.starts_with('L') =>
+                desc.strip_prefix('L').and_then(|d| d.strip_suffix(';')).unwrap_or(desc),
+            _ => owner,
+        };
+        let Some(SmaliToken::Descriptor(desc)) = tokens.get(i + 1) else { continue; };
+        let mapped = if desc.starts_with('(') {
+            remapper.map_method_name(member_owner, name, desc)
+        } else {
+            remapper.map_field_name(member_owner, name, desc)
+        };
+        if let Some(mapped) = mapped {
+            tokens[i] = SmaliToken::MemberName(mapped);
+        }
+    }
+    for token in tokens {
+        if let SmaliToken::Descriptor(desc) = token
+            && let Ok(mapped) = remapper.map_desc(desc) {
+            *token = SmaliToken::Descriptor(mapped);
+        }
+    }
+}
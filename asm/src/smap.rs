@@ -0,0 +1,202 @@
+use crate::err::{AsmErr, AsmResult};
+use crate::impls::ToStringRef;
+use crate::smali::{SmaliNode, SmaliToken};
+use crate::StrRef;
+use std::fmt::{Display, Formatter};
+
+/// A JSR-45 source map, which is kept in the `SourceDebugExtension` attribute of a class
+/// (or the `dalvik.annotation.SourceDebugExtension` annotation in dex files).
+///
+/// Kotlin uses it for inlined functions: lines of the inlined body are appended behind the
+/// last line of the class file, and the source map maps them back to the file of the body.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SourceMap {
+    /// The name of the file which the class is compiled from, e.g. `Main.kt`.
+    pub output_file: StrRef,
+    pub default_stratum: StrRef,
+    pub strata: Vec<Stratum>,
+}
+
+/// A view of the lines, e.g. `Kotlin` for the real files or `KotlinDebug` for call sites.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stratum {
+    pub name: StrRef,
+    pub files: Vec<SmapFile>,
+    pub lines: Vec<LineMapping>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SmapFile {
+    pub id: u32,
+    pub name: StrRef,
+    /// The path of the file, Kotlin uses the internal name of its class, e.g. `kotlin/collections/MapsKt__MapsKt`.
+    pub path: Option<StrRef>,
+}
+
+/// Maps `repeat_count` input lines from `input_start` to output lines from `output_start`,
+/// each input line takes `output_increment` output lines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineMapping {
+    pub input_start: u32,
+    pub repeat_count: u32,
+    pub file_id: u32,
+    pub output_start: u32,
+    pub output_increment: u32,
+}
+
+/// A line of a source file, see [SourceMap::resolve].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceLine {
+    pub file: StrRef,
+    pub path: Option<StrRef>,
+    pub line: u32,
+}
+
+impl Display for SourceLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+fn smap_err(index: usize, line: &str) -> AsmErr {
+    AsmErr::IllegalFormat(format!("illegal SMAP line {}: {line}", index + 1))
+}
+
+/// Reads a number of a line info, e.g. `12` of `12#2,3:40`.
+fn number(value: &str, index: usize, line: &str) -> AsmResult<u32> {
+    value.trim().parse().map_err(|_| smap_err(index, line))
+}
+
+impl SourceMap {
+    /// Parses the content of a `SourceDebugExtension`, embedded source maps are not supported.
+    pub fn parse(content: &str) -> AsmResult<SourceMap> {
+        let mut lines = content.lines().map(str::trim_end).enumerate();
+        let mut next = |expected: &str| match lines.next() {
+            Some((_, line)) => Ok(line),
+            None => Err(AsmErr::IllegalFormat(format!("SMAP misses {expected}"))),
+        };
+        if next("header")? != "SMAP" {
+            return Err(AsmErr::IllegalFormat("SMAP header is missing".to_string()));
+        }
+        let output_file = next("output file")?.to_ref();
+        let default_stratum = next("default stratum")?.to_ref();
+        let mut source_map = SourceMap { output_file, default_stratum, strata: Vec::new() };
+
+        #[derive(PartialEq)]
+        enum Section { None, File, Line, Vendor }
+        let mut section = Section::None;
+        let mut file_id = 0;
+        let mut pending_file: Option<SmapFile> = None;
+        for (index, line) in lines {
+            if let Some(marker) = line.strip_prefix('*') {
+                if let Some(file) = pending_file.take() {
+                    source_map.stratum(index, line)?.files.push(file);
+                }
+                section = match marker.split_whitespace().next().unwrap_or_default() {
+                    "S" => {
+                        let name = marker[1..].trim().to_ref();
+                        source_map.strata.push(Stratum { name, ..Default::default() });
+                        file_id = 0;
+                        Section::None
+                    }
+                    "F" => Section::File,
+                    "L" => Section::Line,
+                    "V" => Section::Vendor,
+                    "E" => Section::None,
+                    "O" | "C" => {
+                        return Err(AsmErr::IllegalFormat("embedded SMAPs are not supported".to_string()));
+                    }
+                    _ => return Err(smap_err(index, line)),
+                };
+                continue;
+            }
+            match section {
+                Section::File => {
+                    // `+ 1 Inline.kt` is followed by its path, `1 Inline.kt` has no path
+                    if let Some(file) = pending_file.take() {
+                        source_map.stratum(index, line)?.files.push(SmapFile { path: Some(line.to_ref()), ..file });
+                        continue;
+                    }
+                    let (with_path, info) = match line.strip_prefix('+') {
+                        Some(info) => (true, info.trim_start()),
+                        None => (false, line.trim_start()),
+                    };
+                    let (id, name) = info.split_once(' ').ok_or_else(|| smap_err(index, line))?;
+                    let file = SmapFile { id: number(id, index, line)?, name: name.trim().to_ref(), path: None };
+                    if with_path {
+                        pending_file = Some(file);
+                    } else {
+                        source_map.stratum(index, line)?.files.push(file);
+                    }
+                }
+                Section::Line => {
+                    if line.is_empty() { continue; }
+                    let mapping = Self::line_mapping(line, index, &mut file_id)?;
+                    source_map.stratum(index, line)?.lines.push(mapping);
+                }
+                Section::Vendor | Section::None => {}
+            }
+        }
+        Ok(source_map)
+    }
+
+    /// `InputStartLine#LineFileID,RepeatCount:OutputStartLine,OutputLineIncrement`, the file id
+    /// is the one of the previous line if it is omitted.
+    fn line_mapping(line: &str, index: usize, file_id: &mut u32) -> AsmResult<LineMapping> {
+        let (input, output) = line.split_once(':').ok_or_else(|| smap_err(index, line))?;
+        let (input, repeat_count) = match input.split_once(',') {
+            Some((input, repeat_count)) => (input, number(repeat_count, index, line)?),
+            None => (input, 1),
+        };
+        let input_start = match input.split_once('#') {
+            Some((input, id)) => {
+                *file_id = number(id, index, line)?;
+                number(input, index, line)?
+            }
+            None => number(input, index, line)?,
+        };
+        let (output_start, output_increment) = match output.split_once(',') {
+            Some((start, increment)) => (number(start, index, line)?, number(increment, index, line)?),
+            None => (number(output, index, line)?, 1),
+        };
+        Ok(LineMapping { input_start, repeat_count, file_id: *file_id, output_start, output_increment })
+    }
+
+    fn stratum(&mut self, index: usize, line: &str) -> AsmResult<&mut Stratum> {
+        self.strata.last_mut().ok_or_else(|| smap_err(index, line))
+    }
+
+    /// Resolves a line of the class file with the default stratum, [None] if it isn't mapped.
+    pub fn resolve(&self, line: u32) -> Option<SourceLine> {
+        self.resolve_in(&self.default_stratum, line)
+    }
+
+    pub fn resolve_in(&self, stratum: &str, line: u32) -> Option<SourceLine> {
+        let stratum = self.strata.iter().find(|s| &*s.name == stratum)?;
+        stratum.lines.iter().find_map(|mapping| {
+            let offset = line.checked_sub(mapping.output_start)?;
+            let increment = mapping.output_increment.max(1);
+            if offset >= mapping.repeat_count.saturating_mul(increment) { return None; }
+            let file = stratum.files.iter().find(|file| file.id == mapping.file_id)?;
+            Some(SourceLine { file: file.name.clone(), path: file.path.clone(), line: mapping.input_start + offset / increment })
+        })
+    }
+
+    /// Whether `line` is a line of another file or another line of the output file, which is
+    /// worth showing next to the line of the class file.
+    pub fn resolve_changed(&self, line: u32) -> Option<SourceLine> {
+        self.resolve(line).filter(|source| source.line != line || source.file != self.output_file)
+    }
+
+    /// Appends the source lines to `.source-line` directives of a dex class,
+    /// e.g. `.source-line 21 # Maps.kt:5`.
+    pub fn apply_to_smali(&self, node: &mut SmaliNode) {
+        if let [SmaliToken::Raw(".source-line"), SmaliToken::Other(line), ..] = &node.content[..]
+            && let Some(source) = line.parse().ok().and_then(|line| self.resolve_changed(line)) {
+            node.content.push(SmaliToken::Other(format!("# {source}").to_ref()));
+        }
+        for child in &mut node.children {
+            self.apply_to_smali(child);
+        }
+    }
+}
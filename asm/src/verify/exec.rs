@@ -0,0 +1,599 @@
+use crate::hierarchy::OBJECT;
+use crate::impls::{method_arg_types, method_return_type, ToStringRef};
+use crate::node::values::ConstValue;
+use crate::node::InsnNode;
+use crate::verify::{array_of, Flow, MethodVerifier, VerifyErrorKind, VerifyFrame, VerifyType};
+use crate::{NewArrayTypeOperand, Opcodes};
+
+type ExecResult<T> = Result<T, VerifyErrorKind>;
+
+/// Instruction semantics shared by type checking and type inference.
+impl MethodVerifier<'_> {
+    /// Executes the instruction at `index` on `frame`, which then becomes the frame of
+    /// all successors of the instruction.
+    pub(super) fn execute(&self, index: usize, frame: &mut VerifyFrame) -> ExecResult<Flow> {
+        let insn = &self.code.instructions[index];
+        match insn {
+            InsnNode::NoOperand { opcode } => return self.execute_no_operand(*opcode, frame),
+            InsnNode::BIPushInsnNode { .. } | InsnNode::SIPushInsnNode { .. } => {
+                self.push(frame, VerifyType::Integer)?;
+            }
+            InsnNode::LdcInsnNode(value) => {
+                let ty = match &**value {
+                    ConstValue::Integer(_) => VerifyType::Integer,
+                    ConstValue::Float(_) => VerifyType::Float,
+                    ConstValue::Long(_) => VerifyType::Long,
+                    ConstValue::Double(_) => VerifyType::Double,
+                    ConstValue::String(_) => VerifyType::reference("java/lang/String"),
+                    ConstValue::Class(_) => VerifyType::reference("java/lang/Class"),
+                    ConstValue::MethodType(_) => VerifyType::reference("java/lang/invoke/MethodType"),
+                    ConstValue::MethodHandle(_) => VerifyType::reference("java/lang/invoke/MethodHandle"),
+                    ConstValue::Dynamic { desc, .. } => self.desc_type(desc)?,
+                    other => return Err(VerifyErrorKind::Illegal(format!("ldc of an unloadable constant: {other:?}"))),
+                };
+                self.push(frame, ty)?;
+            }
+            InsnNode::VarInsnNode { opcode, var_index } => {
+                let index = *var_index as usize;
+                match *opcode {
+                    Opcodes::ILOAD => self.load(frame, index, VerifyType::Integer)?,
+                    Opcodes::LLOAD => self.load(frame, index, VerifyType::Long)?,
+                    Opcodes::FLOAD => self.load(frame, index, VerifyType::Float)?,
+                    Opcodes::DLOAD => self.load(frame, index, VerifyType::Double)?,
+                    Opcodes::ALOAD => self.load_reference(frame, index)?,
+                    Opcodes::ISTORE => self.store_typed(frame, index, VerifyType::Integer)?,
+                    Opcodes::LSTORE => self.store_typed(frame, index, VerifyType::Long)?,
+                    Opcodes::FSTORE => self.store_typed(frame, index, VerifyType::Float)?,
+                    Opcodes::DSTORE => self.store_typed(frame, index, VerifyType::Double)?,
+                    Opcodes::ASTORE => self.store_reference(frame, index)?,
+                    Opcodes::RET => {
+                        if !self.inference {
+                            return Err(VerifyErrorKind::Illegal("ret is not allowed in type checked code".to_string()));
+                        }
+                        let actual = self.local(frame, index)?;
+                        if actual != VerifyType::ReturnAddress {
+                            return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::ReturnAddress, actual });
+                        }
+                        return Ok(Flow { falls_through: false, targets: vec![], returns_from_subroutine: true });
+                    }
+                    other => return Err(unknown_opcode(other)),
+                }
+            }
+            InsnNode::IIncInsnNode { var, .. } => {
+                let index = *var as usize;
+                let actual = self.local(frame, index)?;
+                if actual != VerifyType::Integer {
+                    return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::Integer, actual });
+                }
+            }
+            InsnNode::JumpInsnNode { opcode, label } => {
+                match *opcode {
+                    Opcodes::IFEQ..=Opcodes::IFLE => {
+                        self.pop_expect(frame, &VerifyType::Integer)?;
+                    }
+                    Opcodes::IF_ICMPEQ..=Opcodes::IF_ICMPLE => {
+                        self.pop_expect(frame, &VerifyType::Integer)?;
+                        self.pop_expect(frame, &VerifyType::Integer)?;
+                    }
+                    Opcodes::IF_ACMPEQ | Opcodes::IF_ACMPNE => {
+                        self.pop_reference(frame)?;
+                        self.pop_reference(frame)?;
+                    }
+                    Opcodes::IFNULL | Opcodes::IFNONNULL => {
+                        self.pop_reference(frame)?;
+                    }
+                    Opcodes::GOTO | Opcodes::GOTO_W => {
+                        return Ok(Flow { falls_through: false, targets: vec![*label], returns_from_subroutine: false });
+                    }
+                    Opcodes::JSR | Opcodes::JSR_W => {
+                        if !self.inference {
+                            return Err(VerifyErrorKind::Illegal("jsr is not allowed in type checked code".to_string()));
+                        }
+                        self.push(frame, VerifyType::ReturnAddress)?;
+                        return Ok(Flow { falls_through: false, targets: vec![*label], returns_from_subroutine: false });
+                    }
+                    other => return Err(unknown_opcode(other)),
+                }
+                return Ok(Flow { falls_through: true, targets: vec![*label], returns_from_subroutine: false });
+            }
+            InsnNode::TableSwitchInsnNode { default, labels, .. } |
+            InsnNode::LookupSwitchInsnNode { default, labels, .. } => {
+                self.pop_expect(frame, &VerifyType::Integer)?;
+                let targets = std::iter::once(*default).chain(labels.iter().copied()).collect();
+                return Ok(Flow { falls_through: false, targets, returns_from_subroutine: false });
+            }
+            InsnNode::FieldInsnNode { opcode, owner, desc, .. } => {
+                let ty = self.desc_type(desc)?;
+                match *opcode {
+                    Opcodes::GETSTATIC => self.push(frame, ty)?,
+                    Opcodes::PUTSTATIC => {
+                        self.pop_expect(frame, &ty)?;
+                    }
+                    Opcodes::GETFIELD => {
+                        self.pop_expect(frame, &VerifyType::Reference(owner.clone()))?;
+                        self.push(frame, ty)?;
+                    }
+                    Opcodes::PUTFIELD => {
+                        self.pop_expect(frame, &ty)?;
+                        let receiver = self.pop(frame)?;
+                        // fields of this class can be assigned before the super constructor is called.
+                        let uninitialized_this = receiver == VerifyType::UninitializedThis && *owner == self.class.name;
+                        let expected = VerifyType::Reference(owner.clone());
+                        if !uninitialized_this && !self.verifier.is_assignable(&expected, &receiver) {
+                            return Err(VerifyErrorKind::TypeMismatch { expected, actual: receiver });
+                        }
+                    }
+                    other => return Err(unknown_opcode(other)),
+                }
+            }
+            InsnNode::MethodInsnNode { opcode, owner, name, desc } => {
+                self.pop_args(frame, desc)?;
+                if *opcode != Opcodes::INVOKESTATIC {
+                    if name.as_ref() == "<init>" {
+                        if *opcode != Opcodes::INVOKESPECIAL {
+                            return Err(VerifyErrorKind::Illegal("<init> must be invoked by invokespecial".to_string()));
+                        }
+                        self.initialize(frame, owner)?;
+                    } else {
+                        self.pop_expect(frame, &VerifyType::Reference(owner.clone()))?;
+                    }
+                }
+                self.push_return(frame, desc)?;
+            }
+            InsnNode::InvokeDynamicInsnNode(const_dynamic) => {
+                self.pop_args(frame, &const_dynamic.desc)?;
+                self.push_return(frame, &const_dynamic.desc)?;
+            }
+            InsnNode::NewArrayInsnNode { array_type } => {
+                let array = match *array_type {
+                    NewArrayTypeOperand::T_BOOLEAN => "[Z",
+                    NewArrayTypeOperand::T_CHAR => "[C",
+                    NewArrayTypeOperand::T_FLOAT => "[F",
+                    NewArrayTypeOperand::T_DOUBLE => "[D",
+                    NewArrayTypeOperand::T_BYTE => "[B",
+                    NewArrayTypeOperand::T_SHORT => "[S",
+                    NewArrayTypeOperand::T_INT => "[I",
+                    NewArrayTypeOperand::T_LONG => "[J",
+                    other => return Err(VerifyErrorKind::Illegal(format!("unknown newarray type: {other}"))),
+                };
+                self.pop_expect(frame, &VerifyType::Integer)?;
+                self.push(frame, VerifyType::reference(array))?;
+            }
+            InsnNode::MultiANewArrayInsnNode { array_type, dims } => {
+                let array_dims = array_type.bytes().take_while(|b| *b == b'[').count();
+                if *dims == 0 || *dims as usize > array_dims {
+                    return Err(VerifyErrorKind::Illegal(format!("{dims} dimensions for {array_type}")));
+                }
+                for _ in 0..*dims {
+                    self.pop_expect(frame, &VerifyType::Integer)?;
+                }
+                self.push(frame, VerifyType::Reference(array_type.clone()))?;
+            }
+            InsnNode::TypeInsnNode { opcode, type_name } => {
+                match *opcode {
+                    Opcodes::NEW => self.push(frame, VerifyType::Uninitialized(self.label(index)))?,
+                    Opcodes::ANEWARRAY => {
+                        self.pop_expect(frame, &VerifyType::Integer)?;
+                        self.push(frame, VerifyType::Reference(array_of(type_name)))?;
+                    }
+                    Opcodes::CHECKCAST => {
+                        self.pop_reference(frame)?;
+                        self.push(frame, VerifyType::Reference(type_name.clone()))?;
+                    }
+                    Opcodes::INSTANCEOF => {
+                        self.pop_reference(frame)?;
+                        self.push(frame, VerifyType::Integer)?;
+                    }
+                    other => return Err(unknown_opcode(other)),
+                }
+            }
+        }
+        Ok(Flow::next())
+    }
+
+    fn execute_no_operand(&self, opcode: u8, frame: &mut VerifyFrame) -> ExecResult<Flow> {
+        match opcode {
+            Opcodes::NOP => {}
+            Opcodes::ACONST_NULL => self.push(frame, VerifyType::Null)?,
+            Opcodes::ICONST_M1..=Opcodes::ICONST_5 => self.push(frame, VerifyType::Integer)?,
+            Opcodes::LCONST_0 | Opcodes::LCONST_1 => self.push(frame, VerifyType::Long)?,
+            Opcodes::FCONST_0..=Opcodes::FCONST_2 => self.push(frame, VerifyType::Float)?,
+            Opcodes::DCONST_0 | Opcodes::DCONST_1 => self.push(frame, VerifyType::Double)?,
+            Opcodes::ILOAD_0..=Opcodes::ILOAD_3 => {
+                self.load(frame, (opcode - Opcodes::ILOAD_0) as usize, VerifyType::Integer)?
+            }
+            Opcodes::LLOAD_0..=Opcodes::LLOAD_3 => {
+                self.load(frame, (opcode - Opcodes::LLOAD_0) as usize, VerifyType::Long)?
+            }
+            Opcodes::FLOAD_0..=Opcodes::FLOAD_3 => {
+                self.load(frame, (opcode - Opcodes::FLOAD_0) as usize, VerifyType::Float)?
+            }
+            Opcodes::DLOAD_0..=Opcodes::DLOAD_3 => {
+                self.load(frame, (opcode - Opcodes::DLOAD_0) as usize, VerifyType::Double)?
+            }
+            Opcodes::ALOAD_0..=Opcodes::ALOAD_3 => {
+                self.load_reference(frame, (opcode - Opcodes::ALOAD_0) as usize)?
+            }
+            Opcodes::ISTORE_0..=Opcodes::ISTORE_3 => {
+                self.store_typed(frame, (opcode - Opcodes::ISTORE_0) as usize, VerifyType::Integer)?
+            }
+            Opcodes::LSTORE_0..=Opcodes::LSTORE_3 => {
+                self.store_typed(frame, (opcode - Opcodes::LSTORE_0) as usize, VerifyType::Long)?
+            }
+            Opcodes::FSTORE_0..=Opcodes::FSTORE_3 => {
+                self.store_typed(frame, (opcode - Opcodes::FSTORE_0) as usize, VerifyType::Float)?
+            }
+            Opcodes::DSTORE_0..=Opcodes::DSTORE_3 => {
+                self.store_typed(frame, (opcode - Opcodes::DSTORE_0) as usize, VerifyType::Double)?
+            }
+            Opcodes::ASTORE_0..=Opcodes::ASTORE_3 => {
+                self.store_reference(frame, (opcode - Opcodes::ASTORE_0) as usize)?
+            }
+            Opcodes::IALOAD => self.array_load(frame, &["[I"], VerifyType::Integer)?,
+            Opcodes::LALOAD => self.array_load(frame, &["[J"], VerifyType::Long)?,
+            Opcodes::FALOAD => self.array_load(frame, &["[F"], VerifyType::Float)?,
+            Opcodes::DALOAD => self.array_load(frame, &["[D"], VerifyType::Double)?,
+            Opcodes::BALOAD => self.array_load(frame, &["[B", "[Z"], VerifyType::Integer)?,
+            Opcodes::CALOAD => self.array_load(frame, &["[C"], VerifyType::Integer)?,
+            Opcodes::SALOAD => self.array_load(frame, &["[S"], VerifyType::Integer)?,
+            Opcodes::AALOAD => {
+                self.pop_expect(frame, &VerifyType::Integer)?;
+                let array = self.pop_expect(frame, &VerifyType::reference("[Ljava/lang/Object;"))?;
+                let element = array.array_element().unwrap_or(VerifyType::Null);
+                self.push(frame, element)?;
+            }
+            Opcodes::IASTORE => self.array_store(frame, &["[I"], VerifyType::Integer)?,
+            Opcodes::LASTORE => self.array_store(frame, &["[J"], VerifyType::Long)?,
+            Opcodes::FASTORE => self.array_store(frame, &["[F"], VerifyType::Float)?,
+            Opcodes::DASTORE => self.array_store(frame, &["[D"], VerifyType::Double)?,
+            Opcodes::BASTORE => self.array_store(frame, &["[B", "[Z"], VerifyType::Integer)?,
+            Opcodes::CASTORE => self.array_store(frame, &["[C"], VerifyType::Integer)?,
+            Opcodes::SASTORE => self.array_store(frame, &["[S"], VerifyType::Integer)?,
+            Opcodes::AASTORE => {
+                // the component type is only checked at runtime.
+                self.pop_reference(frame)?;
+                self.pop_expect(frame, &VerifyType::Integer)?;
+                self.pop_expect(frame, &VerifyType::reference("[Ljava/lang/Object;"))?;
+            }
+            Opcodes::POP => {
+                self.pop_words(frame, 1)?;
+            }
+            Opcodes::POP2 => {
+                self.pop_words(frame, 2)?;
+            }
+            Opcodes::DUP => self.dup(frame, 1, 0)?,
+            Opcodes::DUP_X1 => self.dup(frame, 1, 1)?,
+            Opcodes::DUP_X2 => self.dup(frame, 1, 2)?,
+            Opcodes::DUP2 => self.dup(frame, 2, 0)?,
+            Opcodes::DUP2_X1 => self.dup(frame, 2, 1)?,
+            Opcodes::DUP2_X2 => self.dup(frame, 2, 2)?,
+            Opcodes::SWAP => {
+                let first = self.pop_words(frame, 1)?;
+                let second = self.pop_words(frame, 1)?;
+                self.push_all(frame, first)?;
+                self.push_all(frame, second)?;
+            }
+            Opcodes::IADD..=Opcodes::DREM => {
+                let ty = arithmetic_type(opcode - Opcodes::IADD);
+                self.pop_expect(frame, &ty)?;
+                self.pop_expect(frame, &ty)?;
+                self.push(frame, ty)?;
+            }
+            Opcodes::INEG..=Opcodes::DNEG => {
+                let ty = arithmetic_type(opcode - Opcodes::INEG);
+                self.pop_expect(frame, &ty)?;
+                self.push(frame, ty)?;
+            }
+            Opcodes::ISHL..=Opcodes::LUSHR => {
+                let ty = if (opcode - Opcodes::ISHL).is_multiple_of(2) { VerifyType::Integer } else { VerifyType::Long };
+                self.pop_expect(frame, &VerifyType::Integer)?;
+                self.pop_expect(frame, &ty)?;
+                self.push(frame, ty)?;
+            }
+            Opcodes::IAND..=Opcodes::LXOR => {
+                let ty = if (opcode - Opcodes::IAND).is_multiple_of(2) { VerifyType::Integer } else { VerifyType::Long };
+                self.pop_expect(frame, &ty)?;
+                self.pop_expect(frame, &ty)?;
+                self.push(frame, ty)?;
+            }
+            Opcodes::I2L..=Opcodes::I2S => {
+                let (from, to) = match opcode {
+                    Opcodes::I2L => (VerifyType::Integer, VerifyType::Long),
+                    Opcodes::I2F => (VerifyType::Integer, VerifyType::Float),
+                    Opcodes::I2D => (VerifyType::Integer, VerifyType::Double),
+                    Opcodes::L2I => (VerifyType::Long, VerifyType::Integer),
+                    Opcodes::L2F => (VerifyType::Long, VerifyType::Float),
+                    Opcodes::L2D => (VerifyType::Long, VerifyType::Double),
+                    Opcodes::F2I => (VerifyType::Float, VerifyType::Integer),
+                    Opcodes::F2L => (VerifyType::Float, VerifyType::Long),
+                    Opcodes::F2D => (VerifyType::Float, VerifyType::Double),
+                    Opcodes::D2I => (VerifyType::Double, VerifyType::Integer),
+                    Opcodes::D2L => (VerifyType::Double, VerifyType::Long),
+                    Opcodes::D2F => (VerifyType::Double, VerifyType::Float),
+                    // I2B, I2C, I2S
+                    _ => (VerifyType::Integer, VerifyType::Integer),
+                };
+                self.pop_expect(frame, &from)?;
+                self.push(frame, to)?;
+            }
+            Opcodes::LCMP..=Opcodes::DCMPG => {
+                let ty = match opcode {
+                    Opcodes::LCMP => VerifyType::Long,
+                    Opcodes::FCMPL | Opcodes::FCMPG => VerifyType::Float,
+                    _ => VerifyType::Double,
+                };
+                self.pop_expect(frame, &ty)?;
+                self.pop_expect(frame, &ty)?;
+                self.push(frame, VerifyType::Integer)?;
+            }
+            Opcodes::IRETURN..=Opcodes::RETURN => {
+                self.check_return(opcode, frame)?;
+                return Ok(Flow::end());
+            }
+            Opcodes::ARRAYLENGTH => {
+                let actual = self.pop(frame)?;
+                let is_array = actual == VerifyType::Null ||
+                    matches!(&actual, VerifyType::Reference(name) if name.starts_with('['));
+                if !is_array {
+                    return Err(VerifyErrorKind::NotAnArray { actual });
+                }
+                self.push(frame, VerifyType::Integer)?;
+            }
+            Opcodes::ATHROW => {
+                self.pop_expect(frame, &VerifyType::reference("java/lang/Throwable"))?;
+                return Ok(Flow::end());
+            }
+            Opcodes::MONITORENTER | Opcodes::MONITOREXIT => {
+                self.pop_reference(frame)?;
+            }
+            other => return Err(unknown_opcode(other)),
+        }
+        Ok(Flow::next())
+    }
+
+    fn desc_type(&self, desc: &str) -> ExecResult<VerifyType> {
+        VerifyType::from_desc(desc)
+            .ok_or_else(|| VerifyErrorKind::Illegal(format!("invalid type descriptor: {desc}")))
+    }
+
+    fn pop(&self, frame: &mut VerifyFrame) -> ExecResult<VerifyType> {
+        frame.stack.pop().ok_or(VerifyErrorKind::StackUnderflow)
+    }
+
+    /// Pops a value which must be assignable to `expected`.
+    fn pop_expect(&self, frame: &mut VerifyFrame, expected: &VerifyType) -> ExecResult<VerifyType> {
+        let actual = self.pop(frame)?;
+        if !self.verifier.is_assignable(expected, &actual) {
+            return Err(VerifyErrorKind::TypeMismatch { expected: expected.clone(), actual });
+        }
+        Ok(actual)
+    }
+
+    fn pop_reference(&self, frame: &mut VerifyFrame) -> ExecResult<VerifyType> {
+        let actual = self.pop(frame)?;
+        if !actual.is_reference() {
+            return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::reference(OBJECT), actual });
+        }
+        Ok(actual)
+    }
+
+    /// Pops values taking exactly `words` slots, in the order they were pushed.
+    fn pop_words(&self, frame: &mut VerifyFrame, words: usize) -> ExecResult<Vec<VerifyType>> {
+        let mut values = vec![];
+        let mut size = 0;
+        while size < words {
+            let value = self.pop(frame)?;
+            size += value.size();
+            values.push(value);
+        }
+        if size != words {
+            let message = format!("cannot split the {} value on the operand stack", values[values.len() - 1]);
+            return Err(VerifyErrorKind::Illegal(message));
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    /// The `DUP` family, duplicates the top `words` slots and inserts them below the next `under` slots.
+    fn dup(&self, frame: &mut VerifyFrame, words: usize, under: usize) -> ExecResult<()> {
+        let top = self.pop_words(frame, words)?;
+        let below = self.pop_words(frame, under)?;
+        self.push_all(frame, top.clone())?;
+        self.push_all(frame, below)?;
+        self.push_all(frame, top)
+    }
+
+    fn push(&self, frame: &mut VerifyFrame, ty: VerifyType) -> ExecResult<()> {
+        frame.stack.push(ty);
+        if frame.stack_size() > self.code.max_stack as usize {
+            return Err(VerifyErrorKind::StackOverflow { max_stack: self.code.max_stack });
+        }
+        Ok(())
+    }
+
+    fn push_all(&self, frame: &mut VerifyFrame, types: Vec<VerifyType>) -> ExecResult<()> {
+        types.into_iter().try_for_each(|ty| self.push(frame, ty))
+    }
+
+    fn check_local(&self, frame: &VerifyFrame, index: usize, size: usize) -> ExecResult<()> {
+        if index + size > frame.locals.len() {
+            return Err(VerifyErrorKind::LocalOutOfRange { index, max_locals: self.code.max_locals });
+        }
+        Ok(())
+    }
+
+    fn local(&self, frame: &VerifyFrame, index: usize) -> ExecResult<VerifyType> {
+        self.check_local(frame, index, 1)?;
+        Ok(frame.locals[index].clone())
+    }
+
+    fn load(&self, frame: &mut VerifyFrame, index: usize, expected: VerifyType) -> ExecResult<()> {
+        self.check_local(frame, index, expected.size())?;
+        let actual = frame.locals[index].clone();
+        if actual != expected {
+            return Err(VerifyErrorKind::TypeMismatch { expected, actual });
+        }
+        self.push(frame, actual)
+    }
+
+    fn load_reference(&self, frame: &mut VerifyFrame, index: usize) -> ExecResult<()> {
+        let actual = self.local(frame, index)?;
+        if !actual.is_reference() {
+            return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::reference(OBJECT), actual });
+        }
+        self.push(frame, actual)
+    }
+
+    fn store(&self, frame: &mut VerifyFrame, index: usize, value: VerifyType) -> ExecResult<()> {
+        let size = value.size();
+        self.check_local(frame, index, size)?;
+        // storing into the second half of a long or double invalidates it.
+        if index > 0 && frame.locals[index - 1].size() == 2 {
+            frame.locals[index - 1] = VerifyType::Top;
+        }
+        frame.locals[index] = value;
+        if size == 2 {
+            frame.locals[index + 1] = VerifyType::Top;
+        }
+        Ok(())
+    }
+
+    fn store_typed(&self, frame: &mut VerifyFrame, index: usize, expected: VerifyType) -> ExecResult<()> {
+        let value = self.pop_expect(frame, &expected)?;
+        self.store(frame, index, value)
+    }
+
+    /// `astore` accepts return addresses as well as references.
+    fn store_reference(&self, frame: &mut VerifyFrame, index: usize) -> ExecResult<()> {
+        let value = self.pop(frame)?;
+        if !value.is_reference() && value != VerifyType::ReturnAddress {
+            return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::reference(OBJECT), actual: value });
+        }
+        self.store(frame, index, value)
+    }
+
+    /// Pops an array of one of the `arrays` types, `null` is accepted as well.
+    fn pop_array(&self, frame: &mut VerifyFrame, arrays: &[&str]) -> ExecResult<()> {
+        let actual = self.pop(frame)?;
+        let matched = match &actual {
+            VerifyType::Null => true,
+            VerifyType::Reference(name) => arrays.contains(&name.as_ref()),
+            _ => false,
+        };
+        if !matched {
+            return Err(VerifyErrorKind::TypeMismatch { expected: VerifyType::reference(arrays[0]), actual });
+        }
+        Ok(())
+    }
+
+    fn array_load(&self, frame: &mut VerifyFrame, arrays: &[&str], element: VerifyType) -> ExecResult<()> {
+        self.pop_expect(frame, &VerifyType::Integer)?;
+        self.pop_array(frame, arrays)?;
+        self.push(frame, element)
+    }
+
+    fn array_store(&self, frame: &mut VerifyFrame, arrays: &[&str], element: VerifyType) -> ExecResult<()> {
+        self.pop_expect(frame, &element)?;
+        self.pop_expect(frame, &VerifyType::Integer)?;
+        self.pop_array(frame, arrays)
+    }
+
+    fn pop_args(&self, frame: &mut VerifyFrame, desc: &str) -> ExecResult<()> {
+        let args = method_arg_types(desc);
+        for arg in args.iter().rev() {
+            let ty = self.desc_type(arg)?;
+            self.pop_expect(frame, &ty)?;
+        }
+        Ok(())
+    }
+
+    fn push_return(&self, frame: &mut VerifyFrame, desc: &str) -> ExecResult<()> {
+        match method_return_type(desc) {
+            "V" => Ok(()),
+            ret => {
+                let ty = self.desc_type(ret)?;
+                self.push(frame, ty)
+            }
+        }
+    }
+
+    /// Pops the receiver of `invokespecial <init>` and replaces all occurrences of it with
+    /// the initialized type.
+    fn initialize(&self, frame: &mut VerifyFrame, owner: &str) -> ExecResult<()> {
+        let receiver = self.pop(frame)?;
+        let initialized = match &receiver {
+            VerifyType::UninitializedThis => {
+                let is_super = self.class.super_name.as_deref() == Some(owner);
+                if owner != self.class.name.as_ref() && !is_super {
+                    let message = format!("{owner}.<init> can't initialize {}", self.class.name);
+                    return Err(VerifyErrorKind::Illegal(message));
+                }
+                self.class.name.clone()
+            }
+            VerifyType::Uninitialized(label) => {
+                let new_insn = self.index_of(*label).map(|index| &self.code.instructions[index]);
+                let Some(InsnNode::TypeInsnNode { opcode: Opcodes::NEW, type_name }) = new_insn else {
+                    return Err(VerifyErrorKind::Illegal(format!("no new instruction at {label}")));
+                };
+                if type_name.as_ref() != owner {
+                    let expected = VerifyType::Reference(owner.to_ref());
+                    return Err(VerifyErrorKind::TypeMismatch { expected, actual: VerifyType::Reference(type_name.clone()) });
+                }
+                type_name.clone()
+            }
+            _ => {
+                let expected = VerifyType::Uninitialized(0);
+                return Err(VerifyErrorKind::TypeMismatch { expected, actual: receiver });
+            }
+        };
+        let initialized = VerifyType::Reference(initialized);
+        for ty in frame.locals.iter_mut().chain(frame.stack.iter_mut()) {
+            if *ty == receiver { *ty = initialized.clone(); }
+        }
+        Ok(())
+    }
+
+    fn check_return(&self, opcode: u8, frame: &mut VerifyFrame) -> ExecResult<()> {
+        let return_type = method_return_type(&self.method.desc);
+        if opcode == Opcodes::RETURN {
+            if return_type != "V" {
+                return Err(VerifyErrorKind::Illegal(format!("return without a value in a method returning {return_type}")));
+            }
+            if self.is_constructor() && frame.locals.contains(&VerifyType::UninitializedThis) {
+                return Err(VerifyErrorKind::Illegal("constructor returns before this is initialized".to_string()));
+            }
+            return Ok(());
+        }
+        let expected = match VerifyType::from_desc(return_type) {
+            Some(expected) => expected,
+            None => return Err(VerifyErrorKind::Illegal("value returned from a void method".to_string())),
+        };
+        let matches = match opcode {
+            Opcodes::IRETURN => expected == VerifyType::Integer,
+            Opcodes::LRETURN => expected == VerifyType::Long,
+            Opcodes::FRETURN => expected == VerifyType::Float,
+            Opcodes::DRETURN => expected == VerifyType::Double,
+            _ => expected.is_reference(),
+        };
+        if !matches {
+            let message = format!("return instruction doesn't match the return type {return_type}");
+            return Err(VerifyErrorKind::Illegal(message));
+        }
+        self.pop_expect(frame, &expected)?;
+        Ok(())
+    }
+}
+
+/// `int`, `long`, `float` and `double` in turn, for the arithmetic opcode groups.
+fn arithmetic_type(offset: u8) -> VerifyType {
+    match offset % 4 {
+        0 => VerifyType::Integer,
+        1 => VerifyType::Long,
+        2 => VerifyType::Float,
+        _ => VerifyType::Double,
+    }
+}
+
+fn unknown_opcode(opcode: u8) -> VerifyErrorKind {
+    VerifyErrorKind::Illegal(format!("unexpected opcode: {opcode}"))
+}
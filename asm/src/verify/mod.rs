@@ -0,0 +1,621 @@
+pub use types::*;
+
+use crate::analysis::flow_of;
+use crate::hierarchy::{HierarchyResolver, OBJECT};
+use crate::impls::{method_arg_types, ToStringRef};
+use crate::node::element::{ClassNode, CodeBodyNode, LabelNode, MethodNode};
+use crate::node::values::{FrameAttributeValue, FrameValue};
+use crate::node::InsnNode;
+use crate::xref::MemberRef;
+use crate::{InternalNameRef, JavaMethodAccessFlags, JavaVersions, Opcodes};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+mod exec;
+mod types;
+
+/// How the types of a method are verified.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum VerifyMode {
+    /// Type checking for class files of version 50 and above, type inference for older ones.
+    /// Version 50 falls back to type inference when type checking fails, just like the JVM.
+    #[default]
+    Auto,
+    /// Type checking against the `StackMapTable` (JVMS §4.10.1).
+    TypeChecking,
+    /// Type inference by data-flow analysis (JVMS §4.10.2), stack map frames are ignored.
+    TypeInference,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VerifyErrorKind {
+    /// A value of type `actual` is used where `expected` is required.
+    TypeMismatch { expected: VerifyType, actual: VerifyType },
+    /// `arraylength` on a value which is not an array.
+    NotAnArray { actual: VerifyType },
+    StackUnderflow,
+    StackOverflow { max_stack: u16 },
+    LocalOutOfRange { index: usize, max_locals: u16 },
+    /// The types flowing into `target` are not assignable to the stack map frame at `target`.
+    FrameMismatch { target: LabelNode, expected: VerifyFrame },
+    /// Two inferred frames with different stack heights meet at `target`.
+    StackHeightMismatch { target: LabelNode, expected: usize, actual: usize },
+    /// A branch target, exception handler or an instruction after an unconditional branch
+    /// has no stack map frame.
+    MissingFrame { target: LabelNode },
+    /// A branch target or exception handler which is not the start of an instruction.
+    InvalidTarget { target: LabelNode },
+    InvalidStackMap(String),
+    /// The execution can fall off the end of the code.
+    FallOffEnd,
+    Illegal(String),
+}
+
+impl Display for VerifyErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyErrorKind::TypeMismatch { expected, actual } =>
+                write!(f, "type mismatch, expected: {expected}, actual: {actual}"),
+            VerifyErrorKind::NotAnArray { actual } => write!(f, "expected an array, actual: {actual}"),
+            VerifyErrorKind::StackUnderflow => f.write_str("operand stack underflow"),
+            VerifyErrorKind::StackOverflow { max_stack } =>
+                write!(f, "operand stack overflow, max stack: {max_stack}"),
+            VerifyErrorKind::LocalOutOfRange { index, max_locals } =>
+                write!(f, "local variable {index} out of range, max locals: {max_locals}"),
+            VerifyErrorKind::FrameMismatch { target, expected } =>
+                write!(f, "incompatible with the stack map frame at {target}, expected {expected}"),
+            VerifyErrorKind::StackHeightMismatch { target, expected, actual } =>
+                write!(f, "inconsistent stack height at {target}, expected: {expected}, actual: {actual}"),
+            VerifyErrorKind::MissingFrame { target } => write!(f, "expecting a stack map frame at {target}"),
+            VerifyErrorKind::InvalidTarget { target } => write!(f, "{target} is not an instruction"),
+            VerifyErrorKind::InvalidStackMap(message) => write!(f, "invalid stack map: {message}"),
+            VerifyErrorKind::FallOffEnd => f.write_str("falling off the end of the code"),
+            VerifyErrorKind::Illegal(message) => f.write_str(message),
+        }
+    }
+}
+
+/// The first verification failure of a method.
+#[derive(Clone, Debug)]
+pub struct VerifyError {
+    pub method: MemberRef,
+    /// The index of the failing instruction in [CodeBodyNode::instructions], it is the size
+    /// of the instructions if the failure is [VerifyErrorKind::FallOffEnd].
+    pub insn_index: usize,
+    pub insn: Option<InsnNode>,
+    /// The types before the failing instruction.
+    pub frame: VerifyFrame,
+    pub kind: VerifyErrorKind,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} #{}", self.method, self.insn_index)?;
+        if let Some(insn) = &self.insn {
+            write!(f, " {insn:?}")?;
+        }
+        write!(f, ": {} ({})", self.kind, self.frame)
+    }
+}
+
+/// Verifies the code of [ClassNode]s like the JVM does (JVMS §4.10).
+///
+/// Super types are looked up from the [HierarchyResolver], classes unknown to it are
+/// assumed to be assignable since they can't be checked.
+pub struct Verifier<'a> {
+    resolver: &'a dyn HierarchyResolver,
+    mode: VerifyMode,
+    /// Merged classes must be assignable for the jvm too, so unknown classes are not assumed
+    /// to be assignable when they are merged.
+    computing_frames: bool,
+}
+
+impl<'a> Verifier<'a> {
+    pub fn new(resolver: &'a dyn HierarchyResolver) -> Self {
+        Verifier { resolver, mode: VerifyMode::Auto, computing_frames: false }
+    }
+
+    pub fn with_mode(mut self, mode: VerifyMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Verifies all methods of `class`, returns the first failure of each failed method.
+    pub fn verify_class(&self, class: &ClassNode) -> Vec<VerifyError> {
+        class.methods.iter()
+            .filter_map(|method| self.verify_method(class, method).err().map(|e| *e))
+            .collect()
+    }
+
+    /// Verifies a method of `class`, methods without code always pass.
+    pub fn verify_method(&self, class: &ClassNode, method: &MethodNode) -> Result<(), Box<VerifyError>> {
+        let Some(code) = read_code(class, method)? else { return Ok(()) };
+        let verifier = |inference| MethodVerifier { verifier: self, class, method, code, inference };
+        let major_version = class.major_version as u32;
+        match self.mode {
+            VerifyMode::TypeChecking => verifier(false).type_check(),
+            VerifyMode::TypeInference => verifier(true).infer().map(|_| ()),
+            VerifyMode::Auto if major_version < JavaVersions::V1_6 => verifier(true).infer().map(|_| ()),
+            VerifyMode::Auto if major_version == JavaVersions::V1_6 => {
+                verifier(false).type_check().or_else(|_| verifier(true).infer().map(|_| ()))
+            }
+            VerifyMode::Auto => verifier(false).type_check(),
+        }
+    }
+
+    /// Computes the `StackMapTable` of a method of `class` from the inferred types, like the
+    /// `COMPUTE_FRAMES` option of ASM. Branch targets, exception handlers and instructions after
+    /// an unconditional branch get a full frame, offsets are the labels of the code.
+    ///
+    /// Unreachable code can't be type checked, it must be replaced by `nop`s ending with an
+    /// `athrow` as ASM does, which then gets a frame with only a `java/lang/Throwable`.
+    ///
+    /// Classes unknown to the [HierarchyResolver] are merged to their common super class found
+    /// by [HierarchyResolver::common_super_class], which is `java/lang/Object` for them.
+    pub fn compute_frames(
+        &self, class: &ClassNode, method: &MethodNode,
+    ) -> Result<Vec<FrameAttributeValue>, Box<VerifyError>> {
+        let Some(code) = read_code(class, method)? else { return Ok(vec![]) };
+        let verifier = Verifier { resolver: self.resolver, mode: VerifyMode::TypeInference, computing_frames: true };
+        let method_verifier = MethodVerifier { verifier: &verifier, class, method, code, inference: true };
+        let frames = method_verifier.infer()?;
+        method_verifier.stack_map_frames(&frames)
+    }
+
+    /// Returns true if a value of type `from` can be used where `to` is required.
+    pub fn is_assignable(&self, to: &VerifyType, from: &VerifyType) -> bool {
+        if to == from || *to == VerifyType::Top { return true; }
+        match (to, from) {
+            (VerifyType::Reference(_), VerifyType::Null) => true,
+            (VerifyType::Reference(to), VerifyType::Reference(from)) => self.is_class_assignable(to, from),
+            _ => false,
+        }
+    }
+
+    /// The type of a local or stack entry where two paths meet, [VerifyType::Top] if they
+    /// can't be merged.
+    pub fn merge(&self, a: &VerifyType, b: &VerifyType) -> VerifyType {
+        if a == b { return a.clone(); }
+        match (a, b) {
+            (VerifyType::Reference(_), VerifyType::Null) => a.clone(),
+            (VerifyType::Null, VerifyType::Reference(_)) => b.clone(),
+            (VerifyType::Reference(a), VerifyType::Reference(b)) => VerifyType::Reference(self.merge_classes(a, b)),
+            _ => VerifyType::Top,
+        }
+    }
+
+    /// Interfaces are treated as `java/lang/Object` like JVMS `isJavaAssignable`.
+    fn is_class_assignable(&self, to: &str, from: &str) -> bool {
+        if to == from || to == OBJECT { return true; }
+        match (to.strip_prefix('['), from.strip_prefix('[')) {
+            (Some(to_element), Some(from_element)) => {
+                match (VerifyType::from_desc(to_element), VerifyType::from_desc(from_element)) {
+                    (Some(VerifyType::Reference(to)), Some(VerifyType::Reference(from))) => {
+                        self.is_class_assignable(&to, &from)
+                    }
+                    _ => false,
+                }
+            }
+            (Some(_), None) => false,
+            (None, Some(_)) => {
+                matches!(to, "java/lang/Cloneable" | "java/io/Serializable") || self.resolver.is_interface(to)
+            }
+            (None, None) => {
+                if self.resolver.resolve(to).is_none_or(|c| c.is_interface()) { return true; }
+                let mut visited = HashSet::new();
+                let mut current = from.to_ref();
+                loop {
+                    if current.as_ref() == to { return true; }
+                    // cyclic super classes only exist in broken inputs, they are not assignable.
+                    if !visited.insert(current.clone()) { return false; }
+                    let Some(class) = self.resolver.resolve(&current) else { return true };
+                    let Some(super_name) = &class.super_name else { return false };
+                    current = super_name.clone();
+                }
+            }
+        }
+    }
+
+    fn merge_classes(&self, a: &str, b: &str) -> InternalNameRef {
+        if !self.computing_frames {
+            if self.is_class_assignable(a, b) { return a.to_ref(); }
+            if self.is_class_assignable(b, a) { return b.to_ref(); }
+        }
+        if let (Some(a_element), Some(b_element)) = (a.strip_prefix('['), b.strip_prefix('[')) {
+            if let (Some(VerifyType::Reference(a)), Some(VerifyType::Reference(b))) =
+                (VerifyType::from_desc(a_element), VerifyType::from_desc(b_element)) {
+                return array_of(&self.merge_classes(&a, &b));
+            }
+            return OBJECT.to_ref();
+        }
+        self.resolver.common_super_class(a, b)
+    }
+}
+
+fn read_code<'a>(class: &ClassNode, method: &'a MethodNode) -> Result<Option<&'a CodeBodyNode>, Box<VerifyError>> {
+    let Some(code) = &method.code_body else { return Ok(None) };
+    code.get().map(Some).map_err(|e| Box::new(VerifyError {
+        method: MemberRef { owner: class.name.clone(), name: method.name.clone(), desc: method.desc.clone() },
+        insn_index: 0,
+        insn: None,
+        frame: VerifyFrame::default(),
+        kind: VerifyErrorKind::Illegal(format!("cannot read the code: {e:?}")),
+    }))
+}
+
+/// The array type whose elements are instances of the class or array `element`.
+pub(crate) fn array_of(element: &str) -> InternalNameRef {
+    if element.starts_with('[') {
+        format!("[{element}").to_ref()
+    } else {
+        format!("[L{element};").to_ref()
+    }
+}
+
+/// How the control flows out of an instruction, all successors share the frame after it.
+struct Flow {
+    falls_through: bool,
+    targets: Vec<LabelNode>,
+    /// `RET` returns to the instructions following every `JSR`.
+    returns_from_subroutine: bool,
+}
+
+impl Flow {
+    fn next() -> Self {
+        Flow { falls_through: true, targets: vec![], returns_from_subroutine: false }
+    }
+
+    fn end() -> Self {
+        Flow { falls_through: false, targets: vec![], returns_from_subroutine: false }
+    }
+}
+
+struct MethodVerifier<'a> {
+    verifier: &'a Verifier<'a>,
+    class: &'a ClassNode,
+    method: &'a MethodNode,
+    code: &'a CodeBodyNode,
+    inference: bool,
+}
+
+impl MethodVerifier<'_> {
+    fn label(&self, index: usize) -> LabelNode {
+        self.code.insn_offsets.get(index).copied().unwrap_or(index as LabelNode)
+    }
+
+    /// An empty [CodeBodyNode::insn_offsets] means labels are the indexes of instructions.
+    fn index_of(&self, label: LabelNode) -> Option<usize> {
+        if self.code.insn_offsets.is_empty() {
+            let index = label as usize;
+            (index < self.code.instructions.len()).then_some(index)
+        } else {
+            self.code.insn_offsets.binary_search(&label).ok()
+        }
+    }
+
+    fn target_index(&self, target: LabelNode) -> Result<usize, VerifyErrorKind> {
+        self.index_of(target).ok_or(VerifyErrorKind::InvalidTarget { target })
+    }
+
+    fn error(&self, insn_index: usize, frame: &VerifyFrame, kind: VerifyErrorKind) -> Box<VerifyError> {
+        Box::new(VerifyError {
+            method: MemberRef {
+                owner: self.class.name.clone(),
+                name: self.method.name.clone(),
+                desc: self.method.desc.clone(),
+            },
+            insn_index,
+            insn: self.code.instructions.get(insn_index).cloned(),
+            frame: frame.clone(),
+            kind,
+        })
+    }
+
+    fn is_constructor(&self) -> bool {
+        self.method.name.as_ref() == "<init>"
+    }
+
+    /// The locals of the implicit first frame, long and double take only one entry.
+    fn initial_locals(&self) -> Vec<FrameValue> {
+        let mut locals = vec![];
+        if self.method.access & JavaMethodAccessFlags::ACC_STATIC == 0 {
+            if self.is_constructor() && self.class.name.as_ref() != OBJECT {
+                locals.push(FrameValue::UninitializedThis);
+            } else {
+                locals.push(FrameValue::Object(self.class.name.clone()));
+            }
+        }
+        for arg in method_arg_types(&self.method.desc) {
+            let value = match VerifyType::from_desc(arg) {
+                Some(VerifyType::Integer) => FrameValue::Integer,
+                Some(VerifyType::Float) => FrameValue::Float,
+                Some(VerifyType::Long) => FrameValue::Long,
+                Some(VerifyType::Double) => FrameValue::Double,
+                Some(VerifyType::Reference(name)) => FrameValue::Object(name),
+                _ => FrameValue::Top,
+            };
+            locals.push(value);
+        }
+        locals
+    }
+
+    fn initial_frame(&self) -> Result<VerifyFrame, Box<VerifyError>> {
+        let max_locals = self.code.max_locals as usize;
+        let frame = VerifyFrame::from_frame_values(&self.initial_locals(), &[], max_locals);
+        if frame.locals.len() > max_locals {
+            let kind = VerifyErrorKind::LocalOutOfRange { index: frame.locals.len() - 1, max_locals: self.code.max_locals };
+            return Err(self.error(0, &frame, kind));
+        }
+        Ok(frame)
+    }
+
+    /// The exception handlers whose range covers the instruction at `index`, with the
+    /// instruction index of the handler and the type of the caught exception.
+    fn handlers(&self, index: usize) -> Result<Vec<(usize, VerifyType)>, VerifyErrorKind> {
+        let label = self.label(index);
+        let mut handlers = vec![];
+        for item in &self.code.exception_table {
+            if label < item.start || label >= item.end { continue; }
+            let handler = self.target_index(item.handler)?;
+            let catch_type = item.catch_type.as_deref().unwrap_or("java/lang/Throwable");
+            handlers.push((handler, VerifyType::reference(catch_type)));
+        }
+        Ok(handlers)
+    }
+
+    /// Expands the `StackMapTable` into full frames indexed by instructions.
+    fn stack_map(&self, initial: &VerifyFrame) -> Result<Vec<Option<VerifyFrame>>, Box<VerifyError>> {
+        let max_locals = self.code.max_locals as usize;
+        let mut frames = vec![None; self.code.instructions.len()];
+        let mut locals = self.initial_locals();
+        let mut offset: Option<u32> = None;
+        for frame in &self.code.stack_map_table {
+            let invalid = |message: String| self.error(0, initial, VerifyErrorKind::InvalidStackMap(message));
+            let (offset_delta, stack) = match frame {
+                FrameAttributeValue::SameFrame { offset_delta } => (*offset_delta as u16, vec![]),
+                FrameAttributeValue::SameFrameExtended { offset_delta } => (*offset_delta, vec![]),
+                FrameAttributeValue::SameLocals1StackItemFrame { offset_delta, stack } => {
+                    (*offset_delta as u16, vec![stack.clone()])
+                }
+                FrameAttributeValue::SameLocals1StackItemFrameExtended { offset_delta, stack } => {
+                    (*offset_delta, vec![stack.clone()])
+                }
+                FrameAttributeValue::ChopFrame { chop_count, offset_delta } => {
+                    let chop_count = *chop_count as usize;
+                    if chop_count > locals.len() {
+                        return Err(invalid(format!("cannot chop {chop_count} locals from {}", locals.len())));
+                    }
+                    locals.truncate(locals.len() - chop_count);
+                    (*offset_delta, vec![])
+                }
+                FrameAttributeValue::AppendFrame { offset_delta, append_locals } => {
+                    locals.extend(append_locals.iter().cloned());
+                    (*offset_delta, vec![])
+                }
+                FrameAttributeValue::FullFrame { offset_delta, locals: full_locals, stack } => {
+                    locals = full_locals.clone();
+                    (*offset_delta, stack.clone())
+                }
+            };
+            let current = match offset {
+                None => offset_delta as u32,
+                Some(offset) => offset + offset_delta as u32 + 1,
+            };
+            offset = Some(current);
+            let Some(index) = u16::try_from(current).ok().and_then(|label| self.index_of(label)) else {
+                return Err(invalid(format!("frame at {current} is not an instruction")));
+            };
+            let frame = VerifyFrame::from_frame_values(&locals, &stack, max_locals);
+            if frame.locals.len() > max_locals {
+                return Err(invalid(format!("frame at {current} has more locals than {max_locals}")));
+            }
+            frames[index] = Some(frame);
+        }
+        Ok(frames)
+    }
+
+    fn is_frame_assignable(&self, to: &VerifyFrame, from: &VerifyFrame) -> bool {
+        let verifier = self.verifier;
+        let top = VerifyType::Top;
+        let locals_len = to.locals.len().max(from.locals.len());
+        to.stack.len() == from.stack.len() &&
+            to.stack.iter().zip(&from.stack).all(|(to, from)| verifier.is_assignable(to, from)) &&
+            (0..locals_len).all(|i| {
+                let to = to.locals.get(i).unwrap_or(&top);
+                let from = from.locals.get(i).unwrap_or(&top);
+                verifier.is_assignable(to, from)
+            })
+    }
+
+    /// Checks `from` flowing into the stack map frame at the instruction `target`.
+    fn check_flow(
+        &self, frames: &[Option<VerifyFrame>], target: usize, from: &VerifyFrame,
+    ) -> Result<(), VerifyErrorKind> {
+        let label = self.label(target);
+        let Some(expected) = &frames[target] else {
+            return Err(VerifyErrorKind::MissingFrame { target: label });
+        };
+        if !self.is_frame_assignable(expected, from) {
+            return Err(VerifyErrorKind::FrameMismatch { target: label, expected: expected.clone() });
+        }
+        Ok(())
+    }
+
+    /// Type checking against the `StackMapTable` in a single pass (JVMS §4.10.1).
+    fn type_check(&self) -> Result<(), Box<VerifyError>> {
+        let initial = self.initial_frame()?;
+        let frames = self.stack_map(&initial)?;
+        let size = self.code.instructions.len();
+        let mut current = Some(initial);
+        for index in 0..size {
+            if let Some(expected) = &frames[index] {
+                if let Some(frame) = &current && !self.is_frame_assignable(expected, frame) {
+                    let kind = VerifyErrorKind::FrameMismatch { target: self.label(index), expected: expected.clone() };
+                    return Err(self.error(index, frame, kind));
+                }
+                current = Some(expected.clone());
+            }
+            let Some(frame_in) = current.take() else {
+                let kind = VerifyErrorKind::MissingFrame { target: self.label(index) };
+                return Err(self.error(index, &VerifyFrame::default(), kind));
+            };
+            let mut frame = frame_in.clone();
+            let result = self.execute(index, &mut frame).and_then(|flow| {
+                for (handler, catch_type) in self.handlers(index)? {
+                    for locals in [&frame_in.locals, &frame.locals] {
+                        let handler_frame = VerifyFrame { locals: locals.clone(), stack: vec![catch_type.clone()] };
+                        self.check_flow(&frames, handler, &handler_frame)?;
+                    }
+                }
+                for target in &flow.targets {
+                    self.check_flow(&frames, self.target_index(*target)?, &frame)?;
+                }
+                Ok(flow)
+            });
+            let flow = result.map_err(|kind| self.error(index, &frame_in, kind))?;
+            if flow.falls_through {
+                current = Some(frame);
+            }
+        }
+        match current {
+            Some(frame) => Err(self.error(size, &frame, VerifyErrorKind::FallOffEnd)),
+            None => Ok(()),
+        }
+    }
+
+    /// Merges `incoming` into the inferred frame at `target`, returns true if it changed.
+    fn merge_into(
+        &self, frames: &mut [Option<VerifyFrame>], target: usize, incoming: &VerifyFrame,
+    ) -> Result<bool, VerifyErrorKind> {
+        let Some(existing) = &mut frames[target] else {
+            frames[target] = Some(incoming.clone());
+            return Ok(true);
+        };
+        if existing.stack.len() != incoming.stack.len() {
+            return Err(VerifyErrorKind::StackHeightMismatch {
+                target: self.label(target), expected: existing.stack.len(), actual: incoming.stack.len(),
+            });
+        }
+        let mut changed = false;
+        let pairs = existing.locals.iter_mut().zip(&incoming.locals)
+            .chain(existing.stack.iter_mut().zip(&incoming.stack));
+        for (current, incoming) in pairs {
+            let merged = self.verifier.merge(current, incoming);
+            if merged != *current {
+                *current = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Type inference by a data-flow analysis over all reachable instructions (JVMS §4.10.2),
+    /// returns the inferred frame before each instruction, [None] for unreachable ones.
+    fn infer(&self) -> Result<Vec<Option<VerifyFrame>>, Box<VerifyError>> {
+        let initial = self.initial_frame()?;
+        let instructions = &self.code.instructions;
+        let size = instructions.len();
+        if size == 0 {
+            return Err(self.error(0, &initial, VerifyErrorKind::FallOffEnd));
+        }
+        let jsr_returns: Vec<usize> = instructions.iter().enumerate()
+            .filter(|(_, insn)| matches!(
+                insn, InsnNode::JumpInsnNode { opcode: Opcodes::JSR | Opcodes::JSR_W, .. }
+            ))
+            .map(|(index, _)| index + 1)
+            .filter(|index| *index < size)
+            .collect();
+        let mut frames = vec![None; size];
+        frames[0] = Some(initial);
+        let mut queued = vec![false; size];
+        let mut queue = VecDeque::from([0]);
+        queued[0] = true;
+        while let Some(index) = queue.pop_front() {
+            queued[index] = false;
+            let Some(frame_in) = frames[index].clone() else { continue };
+            let mut frame = frame_in.clone();
+            let mut successors = vec![];
+            let result = self.execute(index, &mut frame).and_then(|flow| {
+                for (handler, catch_type) in self.handlers(index)? {
+                    for locals in [&frame_in.locals, &frame.locals] {
+                        let handler_frame = VerifyFrame { locals: locals.clone(), stack: vec![catch_type.clone()] };
+                        successors.push((handler, handler_frame));
+                    }
+                }
+                if flow.falls_through {
+                    if index + 1 >= size { return Err(VerifyErrorKind::FallOffEnd); }
+                    successors.push((index + 1, frame.clone()));
+                }
+                for target in &flow.targets {
+                    successors.push((self.target_index(*target)?, frame.clone()));
+                }
+                if flow.returns_from_subroutine {
+                    successors.extend(jsr_returns.iter().map(|target| (*target, frame.clone())));
+                }
+                for (target, successor) in &successors {
+                    if self.merge_into(&mut frames, *target, successor)? && !queued[*target] {
+                        queued[*target] = true;
+                        queue.push_back(*target);
+                    }
+                }
+                Ok(())
+            });
+            result.map_err(|kind| self.error(index, &frame_in, kind))?;
+        }
+        Ok(frames)
+    }
+
+    /// The full stack map frames of the inferred `frames` at the instructions which need one.
+    fn stack_map_frames(&self, frames: &[Option<VerifyFrame>]) -> Result<Vec<FrameAttributeValue>, Box<VerifyError>> {
+        let instructions = &self.code.instructions;
+        let size = instructions.len();
+        let mut needs_frame = vec![false; size];
+        for item in &self.code.exception_table {
+            let handler = self.target_index(item.handler)
+                .map_err(|kind| self.error(0, &VerifyFrame::default(), kind))?;
+            needs_frame[handler] = true;
+        }
+        for (index, insn) in instructions.iter().enumerate() {
+            let (falls_through, targets) = flow_of(insn);
+            for target in targets {
+                let target = self.target_index(target)
+                    .map_err(|kind| self.error(index, &VerifyFrame::default(), kind))?;
+                needs_frame[target] = true;
+            }
+            if !falls_through && index + 1 < size {
+                needs_frame[index + 1] = true;
+            }
+        }
+        let mut stack_map = vec![];
+        let mut previous: Option<LabelNode> = None;
+        for index in (0..size).filter(|index| needs_frame[*index]) {
+            let (locals, stack) = match &frames[index] {
+                Some(frame) => frame.to_frame_values().ok_or_else(|| {
+                    let kind = VerifyErrorKind::Illegal("return addresses can't be in a stack map frame".to_string());
+                    self.error(index, frame, kind)
+                })?,
+                None if self.is_dead_code(index, frames) => (vec![], vec![FrameValue::Object("java/lang/Throwable".to_ref())]),
+                None => {
+                    let kind = VerifyErrorKind::Illegal("unreachable code must be nops ending with athrow".to_string());
+                    return Err(self.error(index, &VerifyFrame::default(), kind));
+                }
+            };
+            let label = self.label(index);
+            let offset_delta = match previous {
+                None => label,
+                Some(previous) => label - previous - 1,
+            };
+            previous = Some(label);
+            stack_map.push(FrameAttributeValue::FullFrame { offset_delta, locals, stack });
+        }
+        Ok(stack_map)
+    }
+
+    /// Whether the unreachable code from `index` is `nop`s ending with an `athrow`.
+    fn is_dead_code(&self, index: usize, frames: &[Option<VerifyFrame>]) -> bool {
+        let end = (index..frames.len()).find(|i| frames[*i].is_some()).unwrap_or(frames.len());
+        let Some((last, nops)) = self.code.instructions[index..end].split_last() else { return false };
+        matches!(last, InsnNode::NoOperand { opcode: Opcodes::ATHROW })
+            && nops.iter().all(|insn| matches!(insn, InsnNode::NoOperand { opcode: Opcodes::NOP }))
+    }
+}
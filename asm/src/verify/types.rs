@@ -0,0 +1,176 @@
+use crate::impls::{desc_to_internal, ToStringRef};
+use crate::node::element::LabelNode;
+use crate::node::values::FrameValue;
+use crate::InternalNameRef;
+use std::fmt::{Display, Formatter};
+
+/// A verification type (JVMS §4.10.1.2) held by a local variable or an operand stack entry.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum VerifyType {
+    /// An unusable value, e.g. an unassigned local or the second slot of a long.
+    Top,
+    /// `int`, also used for `boolean`, `byte`, `char` and `short`.
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    /// `this` in a constructor before the super (or another) constructor is called.
+    UninitializedThis,
+    /// An object created by the `NEW` instruction at the label which is not initialized yet.
+    Uninitialized(LabelNode),
+    /// A class or an array, arrays use their descriptors, e.g. `[I` or `[Ljava/lang/String;`.
+    Reference(InternalNameRef),
+    /// The address pushed by `JSR`, only appears when the types are inferred.
+    ReturnAddress,
+}
+
+impl VerifyType {
+    pub fn reference(name: &str) -> Self {
+        VerifyType::Reference(name.to_ref())
+    }
+
+    /// The type of a value of the field descriptor `desc`, [None] for `V`.
+    pub fn from_desc(desc: &str) -> Option<Self> {
+        let ty = match desc.as_bytes().first()? {
+            b'Z' | b'B' | b'C' | b'S' | b'I' => VerifyType::Integer,
+            b'F' => VerifyType::Float,
+            b'J' => VerifyType::Long,
+            b'D' => VerifyType::Double,
+            b'L' | b'[' => VerifyType::reference(desc_to_internal(desc)),
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    pub fn from_frame_value(value: &FrameValue) -> Self {
+        match value {
+            FrameValue::Top => VerifyType::Top,
+            FrameValue::Integer => VerifyType::Integer,
+            FrameValue::Float => VerifyType::Float,
+            FrameValue::Long => VerifyType::Long,
+            FrameValue::Double => VerifyType::Double,
+            FrameValue::Null => VerifyType::Null,
+            FrameValue::UninitializedThis => VerifyType::UninitializedThis,
+            FrameValue::Object(name) => VerifyType::Reference(name.clone()),
+            FrameValue::Uninitialized(offset) => VerifyType::Uninitialized(*offset),
+        }
+    }
+
+    /// The type of a stack map frame, [None] for [VerifyType::ReturnAddress].
+    pub fn to_frame_value(&self) -> Option<FrameValue> {
+        let value = match self {
+            VerifyType::Top => FrameValue::Top,
+            VerifyType::Integer => FrameValue::Integer,
+            VerifyType::Float => FrameValue::Float,
+            VerifyType::Long => FrameValue::Long,
+            VerifyType::Double => FrameValue::Double,
+            VerifyType::Null => FrameValue::Null,
+            VerifyType::UninitializedThis => FrameValue::UninitializedThis,
+            VerifyType::Uninitialized(label) => FrameValue::Uninitialized(*label),
+            VerifyType::Reference(name) => FrameValue::Object(name.clone()),
+            VerifyType::ReturnAddress => return None,
+        };
+        Some(value)
+    }
+
+    /// Slots taken by this type, 2 for long and double.
+    pub fn size(&self) -> usize {
+        match self {
+            VerifyType::Long | VerifyType::Double => 2,
+            _ => 1,
+        }
+    }
+
+    /// Returns true for all kinds of object references, including `null` and uninitialized objects.
+    pub fn is_reference(&self) -> bool {
+        matches!(
+            self,
+            VerifyType::Null | VerifyType::UninitializedThis |
+            VerifyType::Uninitialized(_) | VerifyType::Reference(_)
+        )
+    }
+
+    /// The element type of an array reference, [None] if this is not an array.
+    pub fn array_element(&self) -> Option<VerifyType> {
+        let VerifyType::Reference(name) = self else { return None };
+        VerifyType::from_desc(name.strip_prefix('[')?)
+    }
+}
+
+impl Display for VerifyType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyType::Top => f.write_str("top"),
+            VerifyType::Integer => f.write_str("int"),
+            VerifyType::Float => f.write_str("float"),
+            VerifyType::Long => f.write_str("long"),
+            VerifyType::Double => f.write_str("double"),
+            VerifyType::Null => f.write_str("null"),
+            VerifyType::UninitializedThis => f.write_str("uninitializedThis"),
+            VerifyType::Uninitialized(label) => write!(f, "uninitialized({label})"),
+            VerifyType::Reference(name) => f.write_str(name),
+            VerifyType::ReturnAddress => f.write_str("returnAddress"),
+        }
+    }
+}
+
+/// The types of local variables and operand stack entries before an instruction.
+///
+/// Locals are indexed by slots, a long or double takes two slots and the second one is
+/// [VerifyType::Top]. The stack keeps one entry per value no matter the size of it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifyFrame {
+    pub locals: Vec<VerifyType>,
+    pub stack: Vec<VerifyType>,
+}
+
+impl VerifyFrame {
+    /// Builds a frame from the locals and stack of a stack map frame, in which a long or
+    /// double local takes only one entry. Locals are padded with [VerifyType::Top] to `max_locals`.
+    pub fn from_frame_values(locals: &[FrameValue], stack: &[FrameValue], max_locals: usize) -> Self {
+        let mut frame_locals = Vec::with_capacity(max_locals);
+        for value in locals {
+            let ty = VerifyType::from_frame_value(value);
+            let size = ty.size();
+            frame_locals.push(ty);
+            if size == 2 { frame_locals.push(VerifyType::Top); }
+        }
+        if frame_locals.len() < max_locals {
+            frame_locals.resize(max_locals, VerifyType::Top);
+        }
+        let stack = stack.iter().map(VerifyType::from_frame_value).collect();
+        VerifyFrame { locals: frame_locals, stack }
+    }
+
+    /// The locals and stack of a stack map frame, the reverse of [VerifyFrame::from_frame_values].
+    /// Trailing [VerifyType::Top] locals are left out, [None] if there is a return address.
+    pub fn to_frame_values(&self) -> Option<(Vec<FrameValue>, Vec<FrameValue>)> {
+        let mut locals = vec![];
+        let mut slots = self.locals.iter();
+        while let Some(ty) = slots.next() {
+            // the second slot of a long or double is implied.
+            if ty.size() == 2 { slots.next(); }
+            locals.push(ty.to_frame_value()?);
+        }
+        while matches!(locals.last(), Some(FrameValue::Top)) {
+            locals.pop();
+        }
+        let stack = self.stack.iter().map(VerifyType::to_frame_value).collect::<Option<_>>()?;
+        Some((locals, stack))
+    }
+
+    /// Slots taken by the operand stack.
+    pub fn stack_size(&self) -> usize {
+        self.stack.iter().map(VerifyType::size).sum()
+    }
+}
+
+impl Display for VerifyFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |types: &[VerifyType]| {
+            types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+        };
+        write!(f, "locals: [{}], stack: [{}]", join(&self.locals), join(&self.stack))
+    }
+}
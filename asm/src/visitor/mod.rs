@@ -0,0 +1,181 @@
+pub use node_builder::*;
+
+use crate::err::AsmResult;
+use crate::impls::node::r::impls::accept_internal;
+use crate::jvms::element::ClassFile;
+use crate::jvms::JvmsClassReader;
+use crate::node::element::{AnnotationNode, ExceptionTable, InnerClassNode, LabelNode, LazyCodeBody, LineNumberNode, LocalVariableNode, ModuleNode, ParameterNode, RecordComponentNode, TypeAnnotationNode, UnknownAttribute};
+use crate::node::values::{AnnotationValue, FieldInitialValue, FrameAttributeValue};
+use crate::node::InsnNode;
+use crate::{DescriptorRef, InternalNameRef, StrRef};
+use std::io::Read;
+use std::sync::Arc;
+
+mod node_builder;
+
+/// Options of [ClassReader::accept], like the parsing options of ASM's `ClassReader`.
+pub struct ReadFlags;
+
+impl ReadFlags {
+    /// Skips `Code` attributes, methods are visited without [MethodVisitor::visit_code],
+    /// instructions are not decoded at all.
+    pub const SKIP_CODE: u32 = 1;
+    /// Skips `SourceFile`, `SourceDebugExtension`, `LocalVariableTable`,
+    /// `LocalVariableTypeTable`, `LineNumberTable` and `MethodParameters` attributes.
+    pub const SKIP_DEBUG: u32 = 2;
+    /// Skips `StackMapTable` attributes.
+    pub const SKIP_FRAMES: u32 = 4;
+    /// Doesn't decode `Code` attributes, methods get [MethodVisitor::visit_lazy_code] instead of
+    /// the code events, and the code is decoded when the [LazyCodeBody] is first used.
+    pub const LAZY_CODE: u32 = 8;
+}
+
+/// Feeds the content of a [ClassFile] into a [ClassVisitor] as events.
+///
+/// [crate::node::element::ClassNode::from_jvms] is built from the events of this reader
+/// with [ClassNodeBuilder].
+pub struct ClassReader {
+    jvms_file: Arc<ClassFile>,
+}
+
+impl ClassReader {
+    pub fn new(jvms_file: ClassFile) -> Self {
+        ClassReader { jvms_file: Arc::new(jvms_file) }
+    }
+
+    pub fn from_read<T: Read>(read: T) -> AsmResult<Self> {
+        Ok(Self::new(JvmsClassReader::read_class_file(read)?))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> AsmResult<Self> {
+        Ok(Self::new(JvmsClassReader::read_class_bytes(bytes)?))
+    }
+
+    /// Visits the class with `visitor`, `flags` is a combination of [ReadFlags].
+    pub fn accept(&self, visitor: &mut dyn ClassVisitor, flags: u32) -> AsmResult<()> {
+        accept_internal(Arc::clone(&self.jvms_file), visitor, flags)
+    }
+}
+
+/// The header of a class, passed to [ClassVisitor::visit].
+#[derive(Clone, Debug)]
+pub struct ClassHeader {
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub access: u16,
+    pub name: InternalNameRef,
+    pub signature: Option<StrRef>,
+    pub super_name: Option<InternalNameRef>,
+    pub interfaces: Vec<InternalNameRef>,
+}
+
+/// Events of a class, all methods do nothing by default.
+///
+/// The events are fired in this order: [ClassVisitor::visit], [ClassVisitor::visit_source],
+/// [ClassVisitor::visit_module], [ClassVisitor::visit_nest_host], [ClassVisitor::visit_outer_class],
+/// then annotations, attributes, nest members, permitted subclasses, inner classes and record
+/// components in the order of the class attributes, then fields, methods and [ClassVisitor::visit_end].
+pub trait ClassVisitor {
+    fn visit(&mut self, _header: ClassHeader) {}
+
+    fn visit_source(&mut self, _source_file: Option<StrRef>, _source_debug: Option<StrRef>) {}
+
+    fn visit_module(&mut self, _module: ModuleNode) {}
+
+    fn visit_nest_host(&mut self, _nest_host: InternalNameRef) {}
+
+    /// The `EnclosingMethod` attribute of local and anonymous classes.
+    fn visit_outer_class(
+        &mut self, _owner: InternalNameRef, _method_name: Option<StrRef>, _method_desc: Option<DescriptorRef>,
+    ) {}
+
+    fn visit_annotation(&mut self, _annotation: AnnotationNode) {}
+
+    fn visit_type_annotation(&mut self, _type_annotation: TypeAnnotationNode) {}
+
+    /// A non-standard attribute, or a standard one which is not expected here.
+    fn visit_attribute(&mut self, _attribute: UnknownAttribute) {}
+
+    fn visit_nest_member(&mut self, _nest_member: InternalNameRef) {}
+
+    fn visit_permitted_subclass(&mut self, _permitted_subclass: InternalNameRef) {}
+
+    fn visit_inner_class(&mut self, _inner_class: InnerClassNode) {}
+
+    fn visit_record_component(&mut self, _record_component: RecordComponentNode) {}
+
+    /// Returns a visitor for the content of the field, or [None] to skip it.
+    fn visit_field(
+        &mut self, _access: u16, _name: StrRef, _desc: DescriptorRef,
+        _signature: Option<StrRef>, _value: Option<FieldInitialValue>,
+    ) -> Option<Box<dyn FieldVisitor + '_>> {
+        None
+    }
+
+    /// Returns a visitor for the content of the method, or [None] to skip it.
+    fn visit_method(
+        &mut self, _access: u16, _name: StrRef, _desc: DescriptorRef,
+        _signature: Option<StrRef>, _exceptions: Vec<InternalNameRef>,
+    ) -> Option<Box<dyn MethodVisitor + '_>> {
+        None
+    }
+
+    fn visit_end(&mut self) {}
+}
+
+/// Events of a field, all methods do nothing by default.
+pub trait FieldVisitor {
+    fn visit_annotation(&mut self, _annotation: AnnotationNode) {}
+
+    fn visit_type_annotation(&mut self, _type_annotation: TypeAnnotationNode) {}
+
+    fn visit_attribute(&mut self, _attribute: UnknownAttribute) {}
+
+    fn visit_end(&mut self) {}
+}
+
+/// Events of a method, all methods do nothing by default.
+///
+/// Non-code events come first, then [MethodVisitor::visit_code] starts the code events:
+/// instructions, try-catch blocks, local variables, line numbers, type annotations, frames
+/// and attributes of the code. [MethodVisitor::visit_end] is the last event.
+pub trait MethodVisitor {
+    fn visit_parameter(&mut self, _parameter: ParameterNode) {}
+
+    fn visit_annotation_default(&mut self, _value: AnnotationValue) {}
+
+    fn visit_annotation(&mut self, _annotation: AnnotationNode) {}
+
+    fn visit_type_annotation(&mut self, _type_annotation: TypeAnnotationNode) {}
+
+    /// The number of parameters which may have annotations, it is fired before the
+    /// annotations of each `Runtime[In]VisibleParameterAnnotations` attribute.
+    fn visit_annotable_parameter_count(&mut self, _count: usize) {}
+
+    fn visit_parameter_annotation(&mut self, _parameter: usize, _annotation: AnnotationNode) {}
+
+    fn visit_attribute(&mut self, _attribute: UnknownAttribute) {}
+
+    /// The undecoded code of the method, only fired with [ReadFlags::LAZY_CODE].
+    fn visit_lazy_code(&mut self, _code: LazyCodeBody) {}
+
+    fn visit_code(&mut self, _max_stack: u16, _max_locals: u16) {}
+
+    /// An instruction with its label, which is the bytecode offset.
+    fn visit_insn(&mut self, _label: LabelNode, _insn: InsnNode) {}
+
+    fn visit_try_catch(&mut self, _try_catch: ExceptionTable) {}
+
+    fn visit_local_variable(&mut self, _local_variable: LocalVariableNode) {}
+
+    fn visit_line_number(&mut self, _line_number: LineNumberNode) {}
+
+    /// A type annotation of the code, e.g. on a local variable or an expression.
+    fn visit_code_type_annotation(&mut self, _type_annotation: TypeAnnotationNode) {}
+
+    fn visit_frame(&mut self, _frame: FrameAttributeValue) {}
+
+    fn visit_code_attribute(&mut self, _attribute: UnknownAttribute) {}
+
+    fn visit_end(&mut self) {}
+}
@@ -0,0 +1,257 @@
+use crate::node::element::{AnnotationNode, ClassNode, CodeBodyNode, ExceptionTable, FieldNode, InnerClassNode, LabelNode, LazyCodeBody, LineNumberNode, LocalVariableNode, MethodNode, ModuleNode, ParameterNode, RecordComponentNode, TypeAnnotationNode, UnknownAttribute};
+use crate::node::values::{AnnotationValue, FieldInitialValue, FrameAttributeValue};
+use crate::node::InsnNode;
+use crate::visitor::{ClassHeader, ClassVisitor, FieldVisitor, MethodVisitor};
+use crate::{DescriptorRef, InternalNameRef, StrRef};
+
+/// A [ClassVisitor] which collects all events into a [ClassNode], like ASM's `ClassNode`
+/// used as a visitor.
+#[derive(Default)]
+pub struct ClassNodeBuilder {
+    node: ClassNode,
+}
+
+impl ClassNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_node(self) -> ClassNode {
+        self.node
+    }
+}
+
+impl ClassVisitor for ClassNodeBuilder {
+    fn visit(&mut self, header: ClassHeader) {
+        let ClassHeader {
+            minor_version, major_version, access, name, signature, super_name, interfaces,
+        } = header;
+        let node = &mut self.node;
+        node.minor_version = minor_version;
+        node.major_version = major_version;
+        node.access = access;
+        node.name = name;
+        node.signature = signature;
+        node.super_name = super_name;
+        node.interfaces = interfaces;
+    }
+
+    fn visit_source(&mut self, source_file: Option<StrRef>, source_debug: Option<StrRef>) {
+        self.node.source_file = source_file;
+        self.node.source_debug = source_debug;
+    }
+
+    fn visit_module(&mut self, module: ModuleNode) {
+        self.node.module = Some(module);
+    }
+
+    fn visit_nest_host(&mut self, nest_host: InternalNameRef) {
+        self.node.nest_host_class = Some(nest_host);
+    }
+
+    fn visit_outer_class(
+        &mut self, owner: InternalNameRef, method_name: Option<StrRef>, method_desc: Option<DescriptorRef>,
+    ) {
+        self.node.outer_class = Some(owner);
+        self.node.outer_method_name = method_name;
+        self.node.outer_method_desc = method_desc;
+    }
+
+    fn visit_annotation(&mut self, annotation: AnnotationNode) {
+        self.node.annotations.push(annotation);
+    }
+
+    fn visit_type_annotation(&mut self, type_annotation: TypeAnnotationNode) {
+        self.node.type_annotations.push(type_annotation);
+    }
+
+    fn visit_attribute(&mut self, attribute: UnknownAttribute) {
+        self.node.attrs.push(attribute);
+    }
+
+    fn visit_nest_member(&mut self, nest_member: InternalNameRef) {
+        self.node.nest_members.push(nest_member);
+    }
+
+    fn visit_permitted_subclass(&mut self, permitted_subclass: InternalNameRef) {
+        self.node.permitted_subclasses.push(permitted_subclass);
+    }
+
+    fn visit_inner_class(&mut self, inner_class: InnerClassNode) {
+        self.node.inner_classes.push(inner_class);
+    }
+
+    fn visit_record_component(&mut self, record_component: RecordComponentNode) {
+        self.node.record_components.push(record_component);
+    }
+
+    fn visit_field(
+        &mut self, access: u16, name: StrRef, desc: DescriptorRef,
+        signature: Option<StrRef>, value: Option<FieldInitialValue>,
+    ) -> Option<Box<dyn FieldVisitor + '_>> {
+        self.node.fields.push(FieldNode { access, name, desc, signature, value, ..Default::default() });
+        let field = self.node.fields.last_mut()?;
+        Some(Box::new(FieldNodeVisitor(field)))
+    }
+
+    fn visit_method(
+        &mut self, access: u16, name: StrRef, desc: DescriptorRef,
+        signature: Option<StrRef>, exceptions: Vec<InternalNameRef>,
+    ) -> Option<Box<dyn MethodVisitor + '_>> {
+        self.node.methods.push(MethodNode { access, name, desc, signature, exceptions, ..Default::default() });
+        let method = self.node.methods.last_mut()?;
+        Some(Box::new(MethodNodeVisitor { method, code: CodeBodyBuilder::default() }))
+    }
+}
+
+struct FieldNodeVisitor<'a>(&'a mut FieldNode);
+
+impl FieldVisitor for FieldNodeVisitor<'_> {
+    fn visit_annotation(&mut self, annotation: AnnotationNode) {
+        self.0.annotations.push(annotation);
+    }
+
+    fn visit_type_annotation(&mut self, type_annotation: TypeAnnotationNode) {
+        self.0.type_annotations.push(type_annotation);
+    }
+
+    fn visit_attribute(&mut self, attribute: UnknownAttribute) {
+        self.0.attrs.push(attribute);
+    }
+}
+
+struct MethodNodeVisitor<'a> {
+    method: &'a mut MethodNode,
+    code: CodeBodyBuilder,
+}
+
+impl MethodVisitor for MethodNodeVisitor<'_> {
+    fn visit_parameter(&mut self, parameter: ParameterNode) {
+        self.method.parameters.push(parameter);
+    }
+
+    fn visit_annotation_default(&mut self, value: AnnotationValue) {
+        self.method.annotation_default = Some(value);
+    }
+
+    fn visit_annotation(&mut self, annotation: AnnotationNode) {
+        self.method.annotations.push(annotation);
+    }
+
+    fn visit_type_annotation(&mut self, type_annotation: TypeAnnotationNode) {
+        self.method.type_annotations.push(type_annotation);
+    }
+
+    fn visit_annotable_parameter_count(&mut self, count: usize) {
+        let parameter_annotations = &mut self.method.parameter_annotations;
+        if parameter_annotations.len() < count {
+            parameter_annotations.resize_with(count, Vec::new);
+        }
+    }
+
+    fn visit_parameter_annotation(&mut self, parameter: usize, annotation: AnnotationNode) {
+        let parameter_annotations = &mut self.method.parameter_annotations;
+        if parameter_annotations.len() <= parameter {
+            parameter_annotations.resize_with(parameter + 1, Vec::new);
+        }
+        parameter_annotations[parameter].push(annotation);
+    }
+
+    fn visit_attribute(&mut self, attribute: UnknownAttribute) {
+        self.method.attrs.push(attribute);
+    }
+
+    fn visit_lazy_code(&mut self, code: LazyCodeBody) {
+        self.method.code_body = Some(code);
+    }
+
+    fn visit_code(&mut self, max_stack: u16, max_locals: u16) {
+        self.code.visit_code(max_stack, max_locals);
+    }
+
+    fn visit_insn(&mut self, label: LabelNode, insn: InsnNode) {
+        self.code.visit_insn(label, insn);
+    }
+
+    fn visit_try_catch(&mut self, try_catch: ExceptionTable) {
+        self.code.visit_try_catch(try_catch);
+    }
+
+    fn visit_local_variable(&mut self, local_variable: LocalVariableNode) {
+        self.code.visit_local_variable(local_variable);
+    }
+
+    fn visit_line_number(&mut self, line_number: LineNumberNode) {
+        self.code.visit_line_number(line_number);
+    }
+
+    fn visit_code_type_annotation(&mut self, type_annotation: TypeAnnotationNode) {
+        self.code.visit_code_type_annotation(type_annotation);
+    }
+
+    fn visit_frame(&mut self, frame: FrameAttributeValue) {
+        self.code.visit_frame(frame);
+    }
+
+    fn visit_code_attribute(&mut self, attribute: UnknownAttribute) {
+        self.code.visit_code_attribute(attribute);
+    }
+
+    fn visit_end(&mut self) {
+        if let Some(code) = self.code.body.take() {
+            self.method.code_body = Some(code.into());
+        }
+    }
+}
+
+/// Collects code events of a method into a [CodeBodyNode].
+#[derive(Default)]
+pub(crate) struct CodeBodyBuilder {
+    body: Option<CodeBodyNode>,
+}
+
+impl CodeBodyBuilder {
+    pub(crate) fn into_code(self) -> CodeBodyNode {
+        self.body.unwrap_or_default()
+    }
+
+    fn code(&mut self) -> &mut CodeBodyNode {
+        self.body.get_or_insert_with(Default::default)
+    }
+}
+
+impl MethodVisitor for CodeBodyBuilder {
+    fn visit_code(&mut self, max_stack: u16, max_locals: u16) {
+        self.body = Some(CodeBodyNode { max_stack, max_locals, ..Default::default() });
+    }
+
+    fn visit_insn(&mut self, label: LabelNode, insn: InsnNode) {
+        let code = self.code();
+        code.instructions.push(insn);
+        code.insn_offsets.push(label);
+    }
+
+    fn visit_try_catch(&mut self, try_catch: ExceptionTable) {
+        self.code().exception_table.push(try_catch);
+    }
+
+    fn visit_local_variable(&mut self, local_variable: LocalVariableNode) {
+        self.code().local_variables.push(local_variable);
+    }
+
+    fn visit_line_number(&mut self, line_number: LineNumberNode) {
+        self.code().line_numbers.push(line_number);
+    }
+
+    fn visit_code_type_annotation(&mut self, type_annotation: TypeAnnotationNode) {
+        self.code().type_annotations.push(type_annotation);
+    }
+
+    fn visit_frame(&mut self, frame: FrameAttributeValue) {
+        self.code().stack_map_table.push(frame);
+    }
+
+    fn visit_code_attribute(&mut self, attribute: UnknownAttribute) {
+        self.code().unknown_attributes.push(attribute);
+    }
+}
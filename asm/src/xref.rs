@@ -0,0 +1,342 @@
+use crate::dex::insn::DexInsn;
+use crate::dex::insn_syntax::*;
+use crate::dex::{ClassDataItem, ClassDef, DexFileAccessor};
+use crate::impls::dex::convert::code::{call_site, convert_handle};
+use crate::impls::{desc_to_internal, ToStringRef};
+use crate::node::element::ClassNode;
+use crate::node::InsnNode;
+use crate::node::values::{BootstrapMethodArgument, ConstDynamic, ConstValue, Handle};
+use crate::{AsmResult, DescriptorRef, InternalNameRef, MethodHandleKind, Opcodes, StrRef};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// A field or a method of a class.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct MemberRef {
+    /// The internal name of the class which declares the member, e.g. `java/lang/String`.
+    pub owner: InternalNameRef,
+    pub name: StrRef,
+    /// The descriptor of the member, e.g. `(I)V` for methods and `I` for fields.
+    pub desc: DescriptorRef,
+}
+
+impl MemberRef {
+    pub fn new(owner: &str, name: &str, desc: &str) -> Self {
+        MemberRef { owner: owner.to_ref(), name: name.to_ref(), desc: desc.to_ref() }
+    }
+
+    pub fn is_method(&self) -> bool {
+        self.desc.starts_with('(')
+    }
+}
+
+impl Display for MemberRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_method() {
+            write!(f, "{}.{}{}", self.owner, self.name, self.desc)
+        } else {
+            write!(f, "{}.{}:{}", self.owner, self.name, self.desc)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum XrefKind {
+    /// invoke instructions, invokedynamic bootstraps and method handles.
+    Call,
+    FieldRead,
+    FieldWrite,
+    /// string constants, including string arguments of bootstrap methods.
+    StringUse,
+    /// type instructions (new, check-cast, instance-of, array creations) and class constants.
+    ClassUse,
+}
+
+/// The thing which is referenced by an instruction.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum XrefTarget {
+    Method(MemberRef),
+    Field(MemberRef),
+    String(StrRef),
+    /// Internal name of a class, array types are recorded as their element class.
+    Class(InternalNameRef),
+}
+
+impl XrefTarget {
+    /// The class that this target belongs to, [None] for strings.
+    pub fn class(&self) -> Option<&InternalNameRef> {
+        match self {
+            XrefTarget::Method(member) | XrefTarget::Field(member) => Some(&member.owner),
+            XrefTarget::Class(name) => Some(name),
+            XrefTarget::String(_) => None,
+        }
+    }
+}
+
+impl Display for XrefTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XrefTarget::Method(member) | XrefTarget::Field(member) => write!(f, "{member}"),
+            XrefTarget::String(s) => write!(f, "{s:?}"),
+            XrefTarget::Class(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// A single reference from the code of a method.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Xref {
+    /// The method whose code contains the reference.
+    pub from: MemberRef,
+    /// The location of the instruction: the instruction index for class files,
+    /// the address in code units for dex files.
+    pub offset: u32,
+    pub kind: XrefKind,
+    pub target: XrefTarget,
+}
+
+/// An index of all references between methods, fields, strings and classes,
+/// which answers "find usages" queries.
+///
+/// References are recorded as they appear in the instructions, virtual calls are not
+/// resolved against the class hierarchy.
+#[derive(Clone, Debug, Default)]
+pub struct XrefIndex {
+    xrefs: Vec<Xref>,
+    by_target: HashMap<XrefTarget, Vec<usize>>,
+    by_class: HashMap<InternalNameRef, Vec<usize>>,
+    by_from: HashMap<MemberRef, Vec<usize>>,
+}
+
+impl XrefIndex {
+    pub fn new() -> Self { Default::default() }
+
+    pub fn len(&self) -> usize { self.xrefs.len() }
+
+    pub fn is_empty(&self) -> bool { self.xrefs.is_empty() }
+
+    pub fn xrefs(&self) -> &[Xref] { &self.xrefs }
+
+    /// All references to `target`, in the order they were added.
+    pub fn usages(&self, target: &XrefTarget) -> Vec<&Xref> {
+        self.collect(self.by_target.get(target))
+    }
+
+    /// References to a method.
+    pub fn callers(&self, method: &MemberRef) -> Vec<&Xref> {
+        self.usages(&XrefTarget::Method(method.clone()))
+    }
+
+    pub fn field_readers(&self, field: &MemberRef) -> Vec<&Xref> {
+        self.field_usages(field, XrefKind::FieldRead)
+    }
+
+    pub fn field_writers(&self, field: &MemberRef) -> Vec<&Xref> {
+        self.field_usages(field, XrefKind::FieldWrite)
+    }
+
+    pub fn string_users(&self, value: &str) -> Vec<&Xref> {
+        self.usages(&XrefTarget::String(value.to_ref()))
+    }
+
+    /// References to the class itself and to any of its members.
+    pub fn class_users(&self, name: &str) -> Vec<&Xref> {
+        self.collect(self.by_class.get(name))
+    }
+
+    /// All references in the code of `method`.
+    pub fn references_from(&self, method: &MemberRef) -> Vec<&Xref> {
+        self.collect(self.by_from.get(method))
+    }
+
+    /// Methods called by `method`, including bootstrap methods and method handles.
+    pub fn callees(&self, method: &MemberRef) -> Vec<&Xref> {
+        self.references_from(method).into_iter().filter(|x| x.kind == XrefKind::Call).collect()
+    }
+
+    fn field_usages(&self, field: &MemberRef, kind: XrefKind) -> Vec<&Xref> {
+        let target = XrefTarget::Field(field.clone());
+        self.usages(&target).into_iter().filter(|x| x.kind == kind).collect()
+    }
+
+    fn collect(&self, indices: Option<&Vec<usize>>) -> Vec<&Xref> {
+        indices.map(|indices| indices.iter().map(|i| &self.xrefs[*i]).collect()).unwrap_or_default()
+    }
+
+    pub fn add(&mut self, xref: Xref) {
+        let index = self.xrefs.len();
+        if let Some(class) = xref.target.class() {
+            self.by_class.entry(class.clone()).or_default().push(index);
+        }
+        self.by_target.entry(xref.target.clone()).or_default().push(index);
+        self.by_from.entry(xref.from.clone()).or_default().push(index);
+        self.xrefs.push(xref);
+    }
+
+    /// Adds the references in all method bodies of a class file.
+    pub fn add_class_node(&mut self, node: &ClassNode) -> AsmResult<()> {
+        for method in &node.methods {
+            let Some(code_body) = method.code()? else { continue };
+            let from = MemberRef { owner: node.name.clone(), name: method.name.clone(), desc: method.desc.clone() };
+            for (index, insn) in code_body.instructions.iter().enumerate() {
+                let mut refs = Refs { index: self, from: &from, offset: index as u32 };
+                refs.insn(insn);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the references in all method bodies of a dex class.
+    pub fn add_class_def(&mut self, accessor: &DexFileAccessor, class_def: &ClassDef) -> AsmResult<()> {
+        if class_def.class_data_off == 0 { return Ok(()); }
+        let owner = desc_to_internal(&accessor.get_type(class_def.class_idx)?).to_ref();
+        let class_data: ClassDataItem = accessor.get_data_impl(class_def.class_data_off)?;
+        let mut method_idx = 0;
+        for (i, encoded_method) in class_data.direct_methods.iter().chain(&class_data.virtual_methods).enumerate() {
+            if i == class_data.direct_methods.len() { method_idx = 0; }
+            method_idx += encoded_method.method_idx_diff.value();
+            let Some(code_item) = accessor.get_code_item(encoded_method.code_off.value())? else { continue };
+            let method = accessor.get_method(method_idx as usize)?;
+            let from = MemberRef { owner: owner.clone(), name: method.method_name, desc: method.desc };
+            for (addr, insn) in code_item.insn_container.addressed_insns() {
+                let mut refs = Refs { index: self, from: &from, offset: addr };
+                refs.dex_insn(accessor, insn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds all classes in a dex file.
+    pub fn add_dex(&mut self, accessor: &DexFileAccessor) -> AsmResult<()> {
+        for class_def in &accessor.file.class_defs {
+            self.add_class_def(accessor, class_def)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the references of one instruction.
+struct Refs<'a> {
+    index: &'a mut XrefIndex,
+    from: &'a MemberRef,
+    offset: u32,
+}
+
+impl Refs<'_> {
+    fn push(&mut self, kind: XrefKind, target: XrefTarget) {
+        let xref = Xref { from: self.from.clone(), offset: self.offset, kind, target };
+        self.index.add(xref);
+    }
+
+    fn method(&mut self, owner: &str, name: &StrRef, desc: &StrRef) {
+        let member = MemberRef { owner: owner.to_ref(), name: name.clone(), desc: desc.clone() };
+        self.push(XrefKind::Call, XrefTarget::Method(member));
+    }
+
+    fn field(&mut self, owner: &str, name: &StrRef, desc: &StrRef, write: bool) {
+        let member = MemberRef { owner: owner.to_ref(), name: name.clone(), desc: desc.clone() };
+        let kind = if write { XrefKind::FieldWrite } else { XrefKind::FieldRead };
+        self.push(kind, XrefTarget::Field(member));
+    }
+
+    fn string(&mut self, value: &StrRef) {
+        self.push(XrefKind::StringUse, XrefTarget::String(value.clone()));
+    }
+
+    /// `name` is an internal name or an array descriptor, primitive arrays are ignored.
+    fn class(&mut self, name: &str) {
+        if name.trim_start_matches('[').len() == 1 { return; }
+        self.push(XrefKind::ClassUse, XrefTarget::Class(element_class(name)));
+    }
+
+    fn class_desc(&mut self, desc: &str) {
+        if desc.starts_with('[') {
+            self.class(desc)
+        } else if desc.starts_with('L') {
+            self.class(desc_to_internal(desc))
+        }
+    }
+
+    fn handle(&mut self, handle: &Handle) {
+        match handle.reference_kind {
+            MethodHandleKind::H_GETFIELD | MethodHandleKind::H_GETSTATIC =>
+                self.field(&handle.owner, &handle.name, &handle.desc, false),
+            MethodHandleKind::H_PUTFIELD | MethodHandleKind::H_PUTSTATIC =>
+                self.field(&handle.owner, &handle.name, &handle.desc, true),
+            _ => self.method(&handle.owner, &handle.name, &handle.desc),
+        }
+    }
+
+    fn const_dynamic(&mut self, const_dynamic: &ConstDynamic) {
+        self.handle(&const_dynamic.bsm);
+        for arg in &const_dynamic.bsm_args {
+            match arg {
+                BootstrapMethodArgument::String(s) => self.string(s),
+                BootstrapMethodArgument::Class(name) => self.class(name),
+                BootstrapMethodArgument::Handle(handle) => self.handle(handle),
+                _ => {}
+            }
+        }
+    }
+
+    fn insn(&mut self, insn: &InsnNode) {
+        match insn {
+            InsnNode::FieldInsnNode { opcode, owner, name, desc } => {
+                let write = matches!(*opcode, Opcodes::PUTFIELD | Opcodes::PUTSTATIC);
+                self.field(owner, name, desc, write);
+            }
+            InsnNode::MethodInsnNode { owner, name, desc, .. } => self.method(owner, name, desc),
+            InsnNode::InvokeDynamicInsnNode(const_dynamic) => self.const_dynamic(const_dynamic),
+            InsnNode::LdcInsnNode(value) => match &**value {
+                ConstValue::String(s) => self.string(s),
+                ConstValue::Class(name) => self.class(name),
+                ConstValue::MethodHandle(handle) => self.handle(handle),
+                _ => {}
+            },
+            InsnNode::TypeInsnNode { type_name, .. } => self.class(type_name),
+            InsnNode::MultiANewArrayInsnNode { array_type, .. } => self.class(array_type),
+            _ => {}
+        }
+    }
+
+    fn dex_insn(&mut self, accessor: &DexFileAccessor, insn: &DexInsn) -> AsmResult<()> {
+        match insn {
+            DexInsn::InvokeKind(F35c { constB, .. }) | DexInsn::InvokeKindRange(F3rc { constB, .. }) |
+            DexInsn::InvokePoly(F45cc { constB, .. }) | DexInsn::InvokePolyRange(F4rcc { constB, .. }) => {
+                let method = accessor.get_method(*constB)?;
+                self.method(desc_to_internal(&method.class_type), &method.method_name, &method.desc);
+            }
+            DexInsn::InvokeCustom(F35c { constB, .. }) | DexInsn::InvokeCustomRange(F3rc { constB, .. }) => {
+                self.const_dynamic(&call_site(accessor, *constB)?);
+            }
+            DexInsn::IInstanceOp(F22c { opcode, constC, .. }) => {
+                let field = accessor.get_field(*constC)?;
+                let write = opcode.wrapping_sub(0x52) >= 7;
+                self.field(desc_to_internal(&field.class_type), &field.field_name, &field.field_type, write);
+            }
+            DexInsn::SStaticOp(F21c { opcode, constB, .. }) => {
+                let field = accessor.get_field(*constB)?;
+                let write = opcode.wrapping_sub(0x60) >= 7;
+                self.field(desc_to_internal(&field.class_type), &field.field_name, &field.field_type, write);
+            }
+            DexInsn::ConstString(F21c { constB, .. }) => self.string(&accessor.get_str(*constB)?),
+            DexInsn::ConstStringJumbo(F31c { constB, .. }) => self.string(&accessor.get_str(constB.0 as usize)?),
+            DexInsn::ConstClass(F21c { constB, .. }) | DexInsn::CheckCast(F21c { constB, .. }) |
+            DexInsn::NewInstance(F21c { constB, .. }) | DexInsn::FilledNewArray(F35c { constB, .. }) |
+            DexInsn::FilledNewArrayRange(F3rc { constB, .. }) => self.class_desc(&accessor.get_type(*constB)?),
+            DexInsn::InstanceOf(F22c { constC, .. }) | DexInsn::NewArray(F22c { constC, .. }) =>
+                self.class_desc(&accessor.get_type(*constC)?),
+            DexInsn::ConstMethodHandle(F21c { constB, .. }) =>
+                self.handle(&convert_handle(accessor, accessor.get_method_handle(*constB)?)?),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The element class of an array type, or the name itself if it's not an array.
+fn element_class(name: &str) -> InternalNameRef {
+    let element = name.trim_start_matches('[');
+    if element.len() == name.len() { return name.to_ref(); }
+    desc_to_internal(element).to_ref()
+}
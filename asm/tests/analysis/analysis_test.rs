@@ -0,0 +1,86 @@
+use crate::dex::read_test::read_test_dex_file;
+use crate::jvms::read_test::read_jvms;
+use java_asm::analysis::{Analyzer, BasicInterpreter, BasicValue, SourceInterpreter, VerifierInterpreter};
+use java_asm::hierarchy::ClassHierarchy;
+use java_asm::node::element::{ClassNode, MethodNode};
+use java_asm::node::InsnNode;
+use java_asm::verify::VerifyType;
+use java_asm::Opcodes;
+use std::collections::BTreeSet;
+
+fn compile_testing() -> ClassNode {
+    ClassNode::from_jvms(read_jvms().unwrap()).unwrap()
+}
+
+fn find_method<'a>(node: &'a mut ClassNode, name: &str) -> &'a mut MethodNode {
+    node.methods.iter_mut().find(|m| m.name.as_ref() == name).unwrap()
+}
+
+#[test]
+fn basic_interpreter_test() {
+    let mut node = compile_testing();
+    let owner = node.name.clone();
+    let method = find_method(&mut node, "addNumbers");
+    let frames = Analyzer::new(BasicInterpreter).analyze(&owner, method).unwrap();
+    // iload_0, iload_1, iadd, ireturn
+    let stacks: Vec<_> = frames.iter().map(|frame| frame.as_ref().unwrap().stack.clone()).collect();
+    assert_eq!(stacks, vec![
+        vec![],
+        vec![BasicValue::Int],
+        vec![BasicValue::Int, BasicValue::Int],
+        vec![BasicValue::Int],
+    ]);
+    assert_eq!(frames[0].as_ref().unwrap().locals, vec![BasicValue::Int, BasicValue::Int]);
+
+    for method in &node.methods {
+        Analyzer::new(BasicInterpreter).analyze(&owner, method).unwrap();
+    }
+}
+
+#[test]
+fn source_interpreter_test() {
+    let mut node = compile_testing();
+    let owner = node.name.clone();
+    let method = find_method(&mut node, "addNumbers");
+    let frames = Analyzer::new(SourceInterpreter).analyze(&owner, method).unwrap();
+    let iadd = frames[2].as_ref().unwrap();
+    let sources: Vec<_> = iadd.stack.iter().map(|value| value.insns.clone()).collect();
+    assert_eq!(sources, vec![BTreeSet::from([0]), BTreeSet::from([1])]);
+    let ireturn = frames[3].as_ref().unwrap();
+    assert_eq!(ireturn.top().unwrap().insns, BTreeSet::from([2]));
+}
+
+#[test]
+fn verifier_interpreter_test() {
+    let mut node = compile_testing();
+    let owner = node.name.clone();
+    let hierarchy = ClassHierarchy::new();
+    let analyzer = Analyzer::new(VerifierInterpreter::new(&hierarchy));
+    for method in &node.methods {
+        analyzer.analyze(&owner, method).unwrap();
+    }
+
+    let method = find_method(&mut node, "addNumbers");
+    method.code_mut().unwrap().unwrap().instructions[1] = InsnNode::NoOperand { opcode: Opcodes::FCONST_0 };
+    let error = analyzer.analyze(&owner, method).unwrap_err();
+    assert_eq!(error.insn_index, 2);
+    assert!(error.message.contains(&VerifyType::Float.to_string()), "{error}");
+}
+
+#[test]
+fn analyze_converted_dex_test() {
+    let accessor = read_test_dex_file();
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_dex(&accessor).unwrap();
+    let basic = Analyzer::new(BasicInterpreter);
+    let verifier = Analyzer::new(VerifierInterpreter::new(&hierarchy));
+    for class_def in accessor.file.class_defs.iter() {
+        let node = accessor.get_class_node(*class_def).unwrap();
+        for method in &node.methods {
+            basic.analyze(&node.name, method).unwrap();
+            if let Err(error) = verifier.analyze(&node.name, method) {
+                panic!("{}.{}: {error}", node.name, method.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::android::{chunk, string_pool, u32s};
+use java_asm::android::{annotate_resource_ids, ResValue, ResourceTable, ResourceValue};
+use java_asm::smali::stb;
+
+const PACKAGE_HEADER_SIZE: u32 = 288;
+const CONFIG_SIZE: usize = 64;
+
+/// `ResTable_config` which is the default one, or the one of a language.
+fn config(language: &str) -> Vec<u8> {
+    let mut config = vec![0; CONFIG_SIZE];
+    config[..4].copy_from_slice(&(CONFIG_SIZE as u32).to_le_bytes());
+    config[8..8 + language.len()].copy_from_slice(language.as_bytes());
+    config
+}
+
+fn res_value(data_type: u8, data: u32) -> Vec<u8> {
+    let mut value = vec![8, 0, 0, data_type];
+    value.extend_from_slice(&data.to_le_bytes());
+    value
+}
+
+/// A type chunk whose entries are already encoded, `None` for entries missing in the config.
+fn type_chunk(type_id: u8, config: Vec<u8>, entries: &[Option<Vec<u8>>]) -> Vec<u8> {
+    let mut header = vec![type_id, 0, 0, 0];
+    header.extend(u32s(&[entries.len() as u32, 8 + 12 + CONFIG_SIZE as u32 + entries.len() as u32 * 4]));
+    header.extend(config);
+    let mut offsets = vec![];
+    let mut data = vec![];
+    for entry in entries {
+        match entry {
+            Some(entry) => {
+                offsets.push(data.len() as u32);
+                data.extend_from_slice(entry);
+            }
+            None => offsets.push(u32::MAX),
+        }
+    }
+    let mut body = u32s(&offsets);
+    body.extend(data);
+    chunk(0x0201, &header, &body)
+}
+
+fn simple_entry(key: u32, value: Vec<u8>) -> Vec<u8> {
+    let mut entry = vec![8, 0, 0, 0];
+    entry.extend(u32s(&[key]));
+    entry.extend(value);
+    entry
+}
+
+/// A style entry with its parent and `(attribute, value)` items.
+fn complex_entry(key: u32, parent: u32, items: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut entry = vec![16, 0, 1, 0];
+    entry.extend(u32s(&[key, parent, items.len() as u32]));
+    for (name, value) in items {
+        entry.extend(u32s(&[*name]));
+        entry.extend_from_slice(value);
+    }
+    entry
+}
+
+/// Package `0x7f` with `string/app_name` (default and german), `string/title` and `style/AppTheme`.
+fn resource_table() -> Vec<u8> {
+    let global_strings = string_pool(&["Example", "Beispiel", "Title"], true);
+    let type_strings = string_pool(&["string", "style"], false);
+    let key_strings = string_pool(&["app_name", "title", "AppTheme"], false);
+
+    let mut package_body = type_strings.clone();
+    package_body.extend(&key_strings);
+    let mut spec_header = vec![1, 0, 0, 0];
+    spec_header.extend(u32s(&[2]));
+    package_body.extend(chunk(0x0202, &spec_header, &u32s(&[0, 0])));
+    package_body.extend(type_chunk(1, config(""), &[
+        Some(simple_entry(0, res_value(ResValue::TYPE_STRING, 0))),
+        Some(simple_entry(1, res_value(ResValue::TYPE_STRING, 2))),
+    ]));
+    package_body.extend(type_chunk(1, config("de"), &[
+        Some(simple_entry(0, res_value(ResValue::TYPE_STRING, 1))),
+        None,
+    ]));
+    package_body.extend(type_chunk(2, config(""), &[
+        Some(complex_entry(2, 0x0103_0001, &[
+            (0x7f01_0001, res_value(ResValue::TYPE_REFERENCE, 0x7f01_0000)),
+            (0x0101_0098, res_value(0x1C, 0xff00_0000)),
+        ])),
+    ]));
+
+    let mut package_header = u32s(&[0x7f]);
+    let mut name = [0u8; 256];
+    for (index, unit) in "com.example".encode_utf16().enumerate() {
+        name[index * 2..index * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+    }
+    package_header.extend_from_slice(&name);
+    let key_strings_offset = PACKAGE_HEADER_SIZE + type_strings.len() as u32;
+    package_header.extend(u32s(&[PACKAGE_HEADER_SIZE, 2, key_strings_offset, 3, 0]));
+    let package = chunk(0x0200, &package_header, &package_body);
+
+    let mut table_body = global_strings;
+    table_body.extend(package);
+    chunk(0x0002, &u32s(&[1]), &table_body)
+}
+
+#[test]
+fn resource_table_test() {
+    let bytes = resource_table();
+    assert!(ResourceTable::is_resource_table(&bytes));
+    let table = ResourceTable::from_bytes(&bytes).unwrap();
+    assert_eq!(table.packages.len(), 1);
+    let package = &table.packages[0];
+    assert_eq!((package.id, package.name.as_ref()), (0x7f, "com.example"));
+    let type_names: Vec<&str> = package.types.iter().map(|resource_type| resource_type.name.as_ref()).collect();
+    assert_eq!(type_names, ["string", "style"]);
+
+    let (_, _, app_name) = table.entry(0x7f01_0000).unwrap();
+    assert_eq!(app_name.name.as_ref(), "app_name");
+    assert_eq!(app_name.values, vec![
+        ("".into(), ResourceValue::Simple(ResValue::String("Example".into()))),
+        ("de".into(), ResourceValue::Simple(ResValue::String("Beispiel".into()))),
+    ]);
+    let (_, _, title) = table.entry(0x7f01_0001).unwrap();
+    assert_eq!(title.values.len(), 1);
+    let (_, _, theme) = table.entry(0x7f02_0000).unwrap();
+    let ResourceValue::Complex { parent, items } = &theme.values[0].1 else { panic!("style is not complex") };
+    assert_eq!(*parent, 0x0103_0001);
+    assert_eq!(items[1], (0x0101_0098, ResValue::Color(0xff00_0000)));
+
+    assert_eq!(table.reference_name(0x7f01_0000).as_deref(), Some("@string/app_name"));
+    assert_eq!(table.reference_name(0x7f02_0000).as_deref(), Some("@style/AppTheme"));
+    assert_eq!(table.reference_name(0x7f01_0002), None);
+    assert_eq!(table.value_string(&ResValue::Reference(0x7f01_0001)), "@string/title");
+
+    let description = table.describe_type(&package.types[1]);
+    assert!(description.contains("0x7f020000 @style/AppTheme"), "{description}");
+    assert!(description.contains("@string/title = @string/app_name"), "{description}");
+}
+
+#[test]
+fn annotate_resource_ids_test() {
+    let table = ResourceTable::from_bytes(&resource_table()).unwrap();
+    let mut method = stb().other(".method".into()).s_with_children(vec![
+        stb().op("const").v(0u16).l((0x7f01_0000u32 as i32).to_string().into()).s(),
+        stb().op("const").v(1u16).l("16".into()).s(),
+    ]);
+    annotate_resource_ids(&mut method, &|id| table.reference_name(id));
+    let smali = method.render(0);
+    assert!(smali.contains("const v0 2130771968 # @string/app_name"), "{smali}");
+    assert!(!smali.contains("v1 16 #"), "{smali}");
+}
@@ -0,0 +1,131 @@
+use crate::android::{chunk, string_pool, u32s};
+use java_asm::android::{AndroidManifest, ComponentKind, ResValue, XmlDocument, ANDROID_NAMESPACE};
+
+const NO_INDEX: u32 = 0xFFFF_FFFF;
+
+// indices into STRINGS
+const ANDROID: u32 = 0;
+const URI: u32 = 1;
+const MANIFEST: u32 = 2;
+const PACKAGE: u32 = 3;
+const PACKAGE_NAME: u32 = 4;
+const USES_SDK: u32 = 5;
+const USES_PERMISSION: u32 = 6;
+const INTERNET: u32 = 7;
+const APPLICATION: u32 = 8;
+const ACTIVITY: u32 = 9;
+const MAIN_ACTIVITY: u32 = 10;
+const INTENT_FILTER: u32 = 11;
+const ACTION: u32 = 12;
+const MAIN_ACTION: u32 = 13;
+const LABEL: u32 = 14;
+// attribute names come first in real files, stripped names are empty
+const EMPTY: u32 = 15;
+const LABEL_TEXT: u32 = 16;
+const PADDING: u32 = 17;
+
+const STRINGS: [&str; 18] = [
+    "android", ANDROID_NAMESPACE, "manifest", "package", "com.example", "uses-sdk",
+    "uses-permission", "android.permission.INTERNET", "application", "activity", ".MainActivity",
+    "intent-filter", "action", "android.intent.action.MAIN", "label", "", "Main <beta> & co", "padding",
+];
+
+// ids of the stripped attribute names, by string index
+const ATTRIBUTE_IDS: [u32; 16] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0x01010003, // name
+];
+
+fn node(chunk_type: u16, line: u32, ext: &[u8]) -> Vec<u8> {
+    chunk(chunk_type, &u32s(&[line, NO_INDEX]), ext)
+}
+
+/// `(namespace, name, raw value, type, data)`
+type Attr = (u32, u32, u32, u8, u32);
+
+fn start(line: u32, name: u32, attrs: &[Attr]) -> Vec<u8> {
+    let mut ext = u32s(&[NO_INDEX, name]);
+    for value in [20u16, 20, attrs.len() as u16, 0, 0, 0] {
+        ext.extend_from_slice(&value.to_le_bytes());
+    }
+    for (namespace, name, raw, data_type, data) in attrs {
+        ext.extend(u32s(&[*namespace, *name, *raw]));
+        ext.extend_from_slice(&[8, 0, 0, *data_type]);
+        ext.extend(u32s(&[*data]));
+    }
+    node(0x0102, line, &ext)
+}
+
+fn end(line: u32, name: u32) -> Vec<u8> {
+    node(0x0103, line, &u32s(&[NO_INDEX, name]))
+}
+
+fn manifest_bytes(utf8: bool) -> Vec<u8> {
+    let mut body = string_pool(&STRINGS, utf8);
+    body.extend(chunk(0x0180, &[], &u32s(&ATTRIBUTE_IDS)));
+    body.extend(node(0x0100, 1, &u32s(&[ANDROID, URI])));
+    body.extend(start(1, MANIFEST, &[
+        (URI, EMPTY, NO_INDEX, ResValue::TYPE_INT_DEC, 0),
+        (NO_INDEX, PACKAGE, PACKAGE_NAME, ResValue::TYPE_STRING, PACKAGE_NAME),
+    ]));
+    body.extend(start(2, USES_SDK, &[]));
+    body.extend(end(2, USES_SDK));
+    body.extend(start(3, USES_PERMISSION, &[(URI, EMPTY, INTERNET, ResValue::TYPE_STRING, INTERNET)]));
+    body.extend(end(3, USES_PERMISSION));
+    body.extend(start(4, APPLICATION, &[(NO_INDEX, PADDING, NO_INDEX, ResValue::TYPE_DIMENSION, (16 << 8) | 1)]));
+    body.extend(start(5, ACTIVITY, &[
+        (URI, EMPTY, MAIN_ACTIVITY, ResValue::TYPE_STRING, MAIN_ACTIVITY),
+        (NO_INDEX, LABEL, LABEL_TEXT, ResValue::TYPE_STRING, LABEL_TEXT),
+    ]));
+    body.extend(start(6, INTENT_FILTER, &[]));
+    body.extend(start(7, ACTION, &[(URI, EMPTY, MAIN_ACTION, ResValue::TYPE_STRING, MAIN_ACTION)]));
+    body.extend(end(7, ACTION));
+    body.extend(end(8, INTENT_FILTER));
+    body.extend(end(9, ACTIVITY));
+    body.extend(end(10, APPLICATION));
+    body.extend(end(11, MANIFEST));
+    body.extend(node(0x0101, 11, &u32s(&[ANDROID, URI])));
+    chunk(0x0003, &[], &body)
+}
+
+#[test]
+fn decode_binary_xml_test() {
+    let bytes = manifest_bytes(false);
+    assert!(XmlDocument::is_binary_xml(&bytes));
+    let document = XmlDocument::from_bytes(&bytes).unwrap();
+    // stripped attribute names are restored from the resource map
+    assert_eq!(document.root.android_attribute("name"), Some(&ResValue::Int(0)));
+    assert_eq!(document.to_xml(), r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns:android="http://schemas.android.com/apk/res/android" android:name="0" package="com.example">
+    <uses-sdk/>
+    <uses-permission android:name="android.permission.INTERNET"/>
+    <application padding="16.0dp">
+        <activity android:name=".MainActivity" label="Main &lt;beta&gt; &amp; co">
+            <intent-filter>
+                <action android:name="android.intent.action.MAIN"/>
+            </intent-filter>
+        </activity>
+    </application>
+</manifest>
+"#);
+    assert_eq!(XmlDocument::from_bytes(&manifest_bytes(true)).unwrap(), document);
+    assert!(XmlDocument::from_bytes(&bytes[..bytes.len() - 8]).is_err());
+}
+
+#[test]
+fn manifest_summary_test() {
+    let manifest = AndroidManifest::from_bytes(&manifest_bytes(false)).unwrap();
+    assert_eq!(manifest.package.as_deref(), Some("com.example"));
+    assert_eq!(manifest.permissions, vec!["android.permission.INTERNET".into()]);
+    let component = &manifest.components[0];
+    assert_eq!(component.kind, ComponentKind::Activity);
+    assert_eq!(&*component.name, "com.example.MainActivity");
+    assert_eq!(component.actions, vec!["android.intent.action.MAIN".into()]);
+    assert_eq!(component.exported, None);
+    assert_eq!(
+        manifest.to_string(),
+        "package com.example\nversion - (-)\nsdk min - target - compile -\n\
+        uses-permission android.permission.INTERNET\n\
+        activity com.example.MainActivity android.intent.action.MAIN\n",
+    );
+}
@@ -0,0 +1,47 @@
+mod arsc_test;
+mod axml_test;
+mod odex_test;
+mod signing_test;
+
+/// A chunk with the `ResChunk_header` in front of the rest of the `header` and the `body`.
+pub fn chunk(chunk_type: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+    let header_size = 8 + header.len();
+    let mut bytes = Vec::with_capacity(header_size + body.len());
+    bytes.extend_from_slice(&chunk_type.to_le_bytes());
+    bytes.extend_from_slice(&(header_size as u16).to_le_bytes());
+    bytes.extend_from_slice(&((header_size + body.len()) as u32).to_le_bytes());
+    bytes.extend_from_slice(header);
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+pub fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+/// A string pool chunk, with utf-8 or utf-16 strings.
+pub fn string_pool(strings: &[&str], utf8: bool) -> Vec<u8> {
+    let mut offsets = vec![];
+    let mut data = vec![];
+    for string in strings {
+        offsets.push(data.len() as u32);
+        if utf8 {
+            data.push(string.encode_utf16().count() as u8);
+            data.push(string.len() as u8);
+            data.extend_from_slice(string.as_bytes());
+            data.push(0);
+        } else {
+            let units: Vec<u16> = string.encode_utf16().collect();
+            data.extend_from_slice(&(units.len() as u16).to_le_bytes());
+            data.extend(units.iter().flat_map(|unit| unit.to_le_bytes()));
+            data.extend_from_slice(&[0, 0]);
+        }
+    }
+    while data.len() % 4 != 0 { data.push(0); }
+    let strings_start = 28 + offsets.len() as u32 * 4;
+    let flags = if utf8 { 0x100 } else { 0 };
+    let header = u32s(&[strings.len() as u32, 0, flags, strings_start, 0]);
+    let mut body = u32s(&offsets);
+    body.extend(data);
+    chunk(0x0001, &header, &body)
+}
@@ -0,0 +1,134 @@
+use crate::android::u32s;
+use java_asm::android::{extract_dex_files, is_oat_bytes, is_vdex_bytes};
+use java_asm::dex::DexFile;
+
+const DEX: &[u8] = include_bytes!("../res/dex/classes14.dex");
+
+/// A compact dex header with a data section outside of the file, which is shared by the
+/// dex files of a container.
+fn compact_dex() -> Vec<u8> {
+    let mut bytes = b"cdex001\0".to_vec();
+    bytes.resize(32, 0);
+    bytes.extend(u32s(&[0x88, 0x88, 0x12345678]));
+    bytes.resize(0x88, 0);
+    bytes
+}
+
+/// A VDEX 027 file with a checksum section, the dex section and the verifier deps.
+fn vdex_with_sections(dex_files: &[&[u8]]) -> Vec<u8> {
+    let mut dex_section = vec![];
+    for dex in dex_files {
+        dex_section.extend_from_slice(dex);
+        while dex_section.len() % 4 != 0 { dex_section.push(0); }
+    }
+    let dex_offset = 12 + 3 * 12 + 4;
+    let mut bytes = b"vdex027\0".to_vec();
+    bytes.extend(u32s(&[3]));
+    bytes.extend(u32s(&[0, 48, 4]));
+    bytes.extend(u32s(&[1, dex_offset, dex_section.len() as u32]));
+    bytes.extend(u32s(&[2, dex_offset + dex_section.len() as u32, 8]));
+    bytes.extend(u32s(&[0xcafe]));
+    bytes.extend(dex_section);
+    bytes.extend([0xff; 8]);
+    bytes
+}
+
+/// A 64-bit ELF file with a null section, the `.rodata` section of `oat_data` and the
+/// section names.
+fn oat_file(oat_data: &[u8]) -> Vec<u8> {
+    let names = b"\0.rodata\0.shstrtab\0";
+    let rodata_offset = 0x1000;
+    let names_offset = rodata_offset + oat_data.len();
+    let section_offset = (names_offset + names.len()).next_multiple_of(8);
+    let mut bytes = b"\x7fELF\x02\x01\x01".to_vec();
+    bytes.resize(0x28, 0);
+    bytes.extend((section_offset as u64).to_le_bytes());
+    bytes.resize(0x3A, 0);
+    bytes.extend(64u16.to_le_bytes());
+    bytes.extend(3u16.to_le_bytes());
+    bytes.extend(2u16.to_le_bytes());
+    bytes.resize(rodata_offset, 0);
+    bytes.extend_from_slice(oat_data);
+    bytes.extend_from_slice(names);
+    bytes.resize(section_offset + 64, 0);
+    for (name, offset, size) in [(1u32, rodata_offset, oat_data.len()), (9, names_offset, names.len())] {
+        let mut header = u32s(&[name, 1]);
+        header.resize(24, 0);
+        header.extend((offset as u64).to_le_bytes());
+        header.extend((size as u64).to_le_bytes());
+        header.resize(64, 0);
+        bytes.extend(header);
+    }
+    bytes
+}
+
+#[test]
+fn extract_from_vdex_test() {
+    let compact = compact_dex();
+    let vdex = vdex_with_sections(&[DEX, &compact]);
+    assert!(is_vdex_bytes(&vdex));
+    let dex_files = extract_dex_files(&vdex).unwrap();
+    assert_eq!(dex_files.len(), 2);
+    assert_eq!(dex_files[0].offset, 52);
+    assert_eq!(dex_files[0].bytes, DEX);
+    assert_eq!(dex_files[0].name(0), "classes.dex");
+    assert!(DexFile::resolve_from_bytes(&dex_files[0].bytes).is_ok());
+    assert!(dex_files[1].compact);
+    assert_eq!(dex_files[1].bytes, compact);
+    assert_eq!(dex_files[1].name(1), "classes2.cdex");
+
+    // since Android 12 the dex files usually stay in the APK
+    assert!(extract_dex_files(&vdex_with_sections(&[])).unwrap().is_empty());
+}
+
+#[test]
+fn extract_from_old_vdex_test() {
+    // VDEX 019: header, dex section header, then a quickening offset in front of every dex
+    let mut vdex = b"vdex019\0".to_vec();
+    vdex.extend(u32s(&[2, DEX.len() as u32 * 2, 0, 0, 0]));
+    for _ in 0..2 {
+        vdex.extend(u32s(&[0]));
+        vdex.extend_from_slice(DEX);
+    }
+    let dex_files = extract_dex_files(&vdex).unwrap();
+    let names: Vec<String> = dex_files.iter().enumerate().map(|(index, dex)| dex.name(index)).collect();
+    assert_eq!(names, ["classes.dex", "classes2.dex"]);
+    assert!(dex_files.iter().all(|dex| dex.bytes == DEX));
+}
+
+#[test]
+fn extract_from_oat_test() {
+    let mut oat_data = b"oat\n079\0".to_vec();
+    oat_data.resize(0x40, 0);
+    oat_data.extend_from_slice(DEX);
+    let oat = oat_file(&oat_data);
+    assert!(is_oat_bytes(&oat));
+    let dex_files = extract_dex_files(&oat).unwrap();
+    let [dex] = dex_files.as_slice() else { panic!("{} dex files", dex_files.len()) };
+    assert_eq!(dex.offset, 0x1040);
+    assert_eq!(dex.bytes, DEX);
+
+    // a shared library is an ELF file without OAT data
+    let library = oat_file(b"not an oat header");
+    assert!(extract_dex_files(&library).is_err());
+    assert!(extract_dex_files(DEX).is_err());
+}
+
+#[test]
+fn extract_from_malformed_oat_test() {
+    let mut oat_data = b"oat\n079\0".to_vec();
+    oat_data.resize(0x40, 0);
+    let oat = oat_file(&oat_data);
+    let section_offset = u64::from_le_bytes(oat[0x28..0x30].try_into().unwrap()) as usize;
+
+    // section header table at the end of the address space
+    let mut huge_section_offset = oat.clone();
+    huge_section_offset[0x28..0x30].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    assert!(extract_dex_files(&huge_section_offset).is_err());
+
+    // `.shstrtab` at the end of the address space
+    let mut huge_names_offset = oat.clone();
+    let names_header = section_offset + 2 * 64;
+    huge_names_offset[names_header + 24..names_header + 32].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(extract_dex_files(&huge_names_offset).is_err());
+}
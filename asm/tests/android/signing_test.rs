@@ -0,0 +1,109 @@
+use java_asm::android::{ApkSignatures, SignatureScheme, Sha256, to_hex};
+use std::collections::BTreeMap;
+
+/// Signed by v1, v2 and v3 with `CN=Test Signer, O=java_asm, C=US`, all entries are stored.
+const SIGNED_APK: &[u8] = include_bytes!("../res/apk/signed.apk");
+const SIGNED_APK_IDSIG: &[u8] = include_bytes!("../res/apk/signed.apk.idsig");
+const FINGERPRINT: &str = "1b6d0d4739f9b32815a7d296f965aadcfcbcc5cfc4a6c0e35414ca6f12931f2c";
+
+/// Entries of a ZIP file without compression, read by the central directory.
+fn stored_entries(zip: &[u8]) -> BTreeMap<String, Vec<u8>> {
+    let u16_at = |offset: usize| u16::from_le_bytes([zip[offset], zip[offset + 1]]) as usize;
+    let u32_at = |offset: usize| u32::from_le_bytes(zip[offset..offset + 4].try_into().unwrap()) as usize;
+    let eocd = (0..zip.len() - 3).rev().find(|offset| zip[*offset..*offset + 4] == *b"PK\x05\x06").unwrap();
+    let mut entries = BTreeMap::new();
+    let mut offset = u32_at(eocd + 16);
+    for _ in 0..u16_at(eocd + 10) {
+        assert_eq!(u16_at(offset + 10), 0, "entry is compressed");
+        let size = u32_at(offset + 20);
+        let name_len = u16_at(offset + 28);
+        let name = String::from_utf8(zip[offset + 46..offset + 46 + name_len].to_vec()).unwrap();
+        let local = u32_at(offset + 42);
+        let data = local + 30 + u16_at(local + 26) + u16_at(local + 28);
+        entries.insert(name, zip[data..data + size].to_vec());
+        offset += 46 + name_len + u16_at(offset + 30) + u16_at(offset + 32);
+    }
+    entries
+}
+
+#[test]
+fn sha256_test() {
+    assert_eq!(to_hex(&Sha256::digest(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(to_hex(&Sha256::digest(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    // updates which cross the blocks of 64 bytes
+    let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".repeat(20);
+    let mut sha256 = Sha256::new();
+    for chunk in long.chunks(7) {
+        sha256.update(chunk);
+    }
+    assert_eq!(sha256.finish(), Sha256::digest(&long));
+    assert_eq!(
+        to_hex(&Sha256::digest(&long[..56])),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    );
+}
+
+#[test]
+fn verify_signed_apk_test() {
+    let entries = stored_entries(SIGNED_APK);
+    let signatures = ApkSignatures::verify(SIGNED_APK, &entries, Some(SIGNED_APK_IDSIG)).unwrap();
+    assert!(signatures.is_verified(), "{signatures:#?}");
+    let schemes: Vec<SignatureScheme> = signatures.signers.iter().map(|signer| signer.scheme).collect();
+    assert_eq!(schemes, [SignatureScheme::V1, SignatureScheme::V2, SignatureScheme::V3, SignatureScheme::V4]);
+    assert_eq!(signatures.block_ids, [0x7109871a, 0xf05368c0, 0x42726577]);
+
+    let v1 = &signatures.signers[0];
+    assert_eq!(v1.name.as_deref(), Some("CERT"));
+    assert_eq!(v1.declared_schemes, [SignatureScheme::V2, SignatureScheme::V3]);
+    let v3 = &signatures.signers[2];
+    assert_eq!((v3.min_sdk, v3.max_sdk), (Some(28), Some(0x7fffffff)));
+    for signer in &signatures.signers {
+        let certificate = signer.certificate().unwrap();
+        assert_eq!(certificate.subject.as_ref(), "CN=Test Signer, O=java_asm, C=US");
+        assert_eq!(certificate.issuer, certificate.subject);
+        assert_eq!(certificate.public_key_algorithm.as_ref(), "RSA");
+        assert_eq!(certificate.sha256_fingerprint(), FINGERPRINT);
+    }
+    let certificate = v1.certificate().unwrap();
+    assert_eq!(certificate.serial.as_ref(), "ce128a3fc9e76214");
+    assert_eq!(certificate.not_before.as_ref(), "2026-10-18T20:47:48Z");
+    assert_eq!(certificate.not_after.as_ref(), "2081-07-21T20:47:48Z");
+}
+
+#[test]
+fn verify_modified_apk_test() {
+    let content = b"hello from java_asm";
+    let position = SIGNED_APK.windows(content.len()).position(|window| window == content).unwrap();
+    let mut apk = SIGNED_APK.to_vec();
+    apk[position] = b'j';
+    let signatures = ApkSignatures::verify(&apk, &stored_entries(&apk), None).unwrap();
+    assert!(!signatures.is_verified());
+    for signer in &signatures.signers {
+        assert!(!signer.is_verified(), "{signer:#?}");
+    }
+    assert!(signatures.signers[0].errors.contains(&"the digest of assets/hello.txt doesn't match the manifest".to_string()));
+    assert_eq!(signatures.signers[1].errors, ["the RSASSA-PKCS1-v1_5 with SHA2-256 digest doesn't match the content"]);
+}
+
+#[test]
+fn verify_stripped_apk_test() {
+    // remove the APK Signing Block, the v1 signature declares the v2 and v3 ones
+    let apk = SIGNED_APK;
+    let eocd = apk.len() - 22;
+    let central_directory = u32::from_le_bytes(apk[eocd + 16..eocd + 20].try_into().unwrap()) as usize;
+    let block_size = u64::from_le_bytes(apk[central_directory - 24..central_directory - 16].try_into().unwrap());
+    let block_start = central_directory - block_size as usize - 8;
+    let mut stripped = apk[..block_start].to_vec();
+    stripped.extend_from_slice(&apk[central_directory..eocd + 16]);
+    stripped.extend_from_slice(&(block_start as u32).to_le_bytes());
+    stripped.extend_from_slice(&apk[eocd + 20..]);
+
+    let signatures = ApkSignatures::verify(&stripped, &stored_entries(&stripped), None).unwrap();
+    assert!(signatures.block_ids.is_empty());
+    let [v1] = signatures.signers.as_slice() else { panic!("{signatures:#?}") };
+    assert_eq!(v1.errors, [
+        "the APK declares a v2 signature which is missing",
+        "the APK declares a v3 signature which is missing",
+    ]);
+    assert!(!signatures.is_verified());
+}
@@ -0,0 +1,120 @@
+use java_asm::binary_compat::{ApiChangeKind, ApiReport, Severity, Visibility};
+use java_asm::node::element::{ClassNode, FieldNode, MethodNode};
+use java_asm::node::values::FieldInitialValue;
+use java_asm::{JavaAccessFlags, JavaClassAccessFlags};
+
+const PUBLIC: u16 = JavaAccessFlags::ACC_PUBLIC;
+const ABSTRACT: u16 = JavaAccessFlags::ACC_ABSTRACT;
+const INTERFACE: u16 = JavaClassAccessFlags::ACC_INTERFACE | ABSTRACT;
+
+fn class(access: u16, name: &str, super_name: &str) -> ClassNode {
+    ClassNode {
+        access, name: name.into(), super_name: Some(super_name.into()), ..Default::default()
+    }
+}
+
+fn method(access: u16, name: &str, desc: &str) -> MethodNode {
+    MethodNode { access, name: name.into(), desc: desc.into(), ..Default::default() }
+}
+
+fn constant(value: i32) -> FieldNode {
+    FieldNode {
+        access: PUBLIC | JavaAccessFlags::ACC_STATIC | JavaAccessFlags::ACC_FINAL,
+        name: "SIZE".into(), desc: "I".into(), value: Some(FieldInitialValue::Integer(value)),
+        ..Default::default()
+    }
+}
+
+/// A public base class with a public interface and a package private helper.
+fn old_classes() -> Vec<ClassNode> {
+    let mut base = class(PUBLIC, "lib/Base", "java/lang/Object");
+    base.methods.push(method(PUBLIC, "inherited", "()V"));
+    let mut api = class(PUBLIC, "lib/Api", "lib/Base");
+    api.interfaces.push("lib/Listener".into());
+    api.fields.push(constant(1));
+    api.methods.extend([
+        method(PUBLIC, "<init>", "()V"),
+        method(PUBLIC, "get", "()I"),
+        method(PUBLIC, "set", "(I)V"),
+        method(JavaAccessFlags::ACC_PROTECTED, "hook", "()V"),
+        method(PUBLIC, "moved", "()V"),
+        method(JavaAccessFlags::ACC_PRIVATE, "internal", "()V"),
+    ]);
+    let mut listener = class(PUBLIC | INTERFACE, "lib/Listener", "java/lang/Object");
+    listener.methods.push(method(PUBLIC | ABSTRACT, "onEvent", "()V"));
+    let helper = class(0, "lib/Helper", "java/lang/Object");
+    vec![base, api, listener, helper]
+}
+
+#[test]
+fn unchanged_classes_are_compatible() {
+    let classes = old_classes();
+    let report = ApiReport::new(&classes, &classes);
+    assert!(report.is_compatible());
+    assert!(report.changes.is_empty());
+    // the package private helper is no api
+    assert_eq!(report.api_class_count, 3);
+}
+
+#[test]
+fn breaking_member_changes_test() {
+    let old = old_classes();
+    let mut new = old_classes();
+    let api = &mut new[1];
+    api.fields[0] = constant(2);
+    // private methods are no api, and moved methods are still inherited from the super class
+    api.methods.retain(|m| &*m.name != "internal" && &*m.name != "moved");
+    api.methods.iter_mut().find(|m| &*m.name == "get").unwrap().desc = "()J".into();
+    api.methods.iter_mut().find(|m| &*m.name == "set").unwrap().access |= JavaAccessFlags::ACC_FINAL;
+    api.methods.iter_mut().find(|m| &*m.name == "hook").unwrap().access = 0;
+    new[0].methods.push(method(PUBLIC, "moved", "()V"));
+    new[2].methods.push(method(PUBLIC | ABSTRACT, "onClose", "()V"));
+    new[2].methods.push(method(PUBLIC, "onOpen", "()V"));
+
+    let report = ApiReport::new(&old, &new);
+    let changes: Vec<_> = report.changes.iter()
+        .map(|change| (change.member.as_ref().map(|m| m.to_string()), change.kind.clone()))
+        .collect();
+    assert_eq!(changes, vec![
+        (Some("lib/Api.SIZE:I".into()), ApiChangeKind::ConstantChanged { old: "1".into(), new: Some("2".into()) }),
+        (Some("lib/Api.get()I".into()), ApiChangeKind::DescriptorChanged { new_descs: vec!["()J".into()] }),
+        (Some("lib/Api.set(I)V".into()), ApiChangeKind::MemberMadeFinal),
+        (Some("lib/Api.hook()V".into()), ApiChangeKind::MemberLessAccessible {
+            old: Visibility::Protected, new: Visibility::Package,
+        }),
+        (Some("lib/Listener.onClose()V".into()), ApiChangeKind::AbstractMethodAdded),
+    ]);
+    assert_eq!(report.breaking_count(), 4);
+    assert_eq!(report.changes[0].severity(), Severity::Behavioral);
+    assert_eq!(report.changes[0].jls_section(), "13.4.9");
+    assert_eq!(report.changes[1].jls_section(), "13.4.14");
+    assert_eq!(report.changes[4].jls_section(), "13.5.3");
+    assert_eq!(
+        report.changes[1].to_string(),
+        "BREAKING lib/Api.get()I: descriptor changed to ()J (JLS 13.4.14)",
+    );
+}
+
+#[test]
+fn breaking_class_changes_test() {
+    let old = old_classes();
+    let mut new = old_classes();
+    new.remove(0);
+    let api = &mut new[0];
+    api.super_name = Some("java/lang/Object".into());
+    api.access |= JavaAccessFlags::ACC_FINAL;
+    new[1].access = INTERFACE;
+
+    let report = ApiReport::new(&old, &new);
+    let changes: Vec<_> = report.changes.iter()
+        .map(|change| (&*change.class, change.member.is_some(), change.kind.clone()))
+        .collect();
+    assert_eq!(changes, vec![
+        ("lib/Api", false, ApiChangeKind::ClassMadeFinal),
+        ("lib/Api", false, ApiChangeKind::SupertypeRemoved("lib/Base".into())),
+        ("lib/Base", false, ApiChangeKind::ClassRemoved),
+        ("lib/Listener", false, ApiChangeKind::ClassLessAccessible),
+    ]);
+    assert_eq!(report.changes[3].jls_section(), "13.5.1");
+    assert!(!report.is_compatible());
+}
@@ -0,0 +1 @@
+mod binary_compat_test;
@@ -0,0 +1,122 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::bootstrap::{BootstrapCall, ConcatPart};
+use java_asm::node::element::ClassNode;
+use java_asm::node::values::{BootstrapMethodArgument, ConstDynamic, Handle};
+use java_asm::node::InsnNode;
+use java_asm::smali::ToSmali;
+use java_asm::{MethodHandleKind, StrRef};
+
+fn handle(reference_kind: u8, owner: &str, name: &str, desc: &str) -> Handle {
+    Handle { reference_kind, owner: StrRef::from(owner), name: StrRef::from(name), desc: StrRef::from(desc) }
+}
+
+fn call_site(name: &str, desc: &str, bsm: Handle, bsm_args: Vec<BootstrapMethodArgument>) -> ConstDynamic {
+    ConstDynamic { name: StrRef::from(name), desc: StrRef::from(desc), bsm, bsm_args }
+}
+
+fn method_type(desc: &str) -> BootstrapMethodArgument {
+    BootstrapMethodArgument::MethodType(StrRef::from(desc))
+}
+
+fn string(value: &str) -> BootstrapMethodArgument {
+    BootstrapMethodArgument::String(StrRef::from(value))
+}
+
+fn class(name: &str) -> BootstrapMethodArgument {
+    BootstrapMethodArgument::Class(StrRef::from(name))
+}
+
+fn resolve(call_site: &ConstDynamic) -> BootstrapCall {
+    BootstrapCall::resolve(call_site).unwrap()
+}
+
+#[test]
+fn resolve_lambda_test() {
+    let metafactory = handle(MethodHandleKind::H_INVOKESTATIC, "java/lang/invoke/LambdaMetafactory", "metafactory", "");
+    let implementation = handle(MethodHandleKind::H_INVOKESTATIC, "Main", "lambda$main$0", "(Ljava/lang/String;Ljava/lang/Object;)V");
+    let lambda = call_site("accept", "(Ljava/lang/String;)Ljava/util/function/Consumer;", metafactory, vec![
+        method_type("(Ljava/lang/Object;)V"),
+        BootstrapMethodArgument::Handle(implementation.clone()),
+        method_type("(Ljava/lang/Object;)V"),
+    ]);
+    let BootstrapCall::Lambda(call) = resolve(&lambda) else { panic!("expected a lambda") };
+    assert_eq!(&*call.interface, "java/util/function/Consumer");
+    assert_eq!(&*call.method_name, "accept");
+    assert_eq!(&*call.implementation.name, "lambda$main$0");
+    assert_eq!(call.captured, vec![StrRef::from("Ljava/lang/String;")]);
+    assert_eq!(
+        call.to_string(),
+        "lambda java/util/function/Consumer.accept(Ljava/lang/Object;)V -> \
+        invokestatic Main.lambda$main$0(Ljava/lang/String;Ljava/lang/Object;)V captures Ljava/lang/String;",
+    );
+
+    // `Runnable & Serializable r = Main::run` with a bridge
+    let alt_metafactory = handle(MethodHandleKind::H_INVOKESTATIC, "java/lang/invoke/LambdaMetafactory", "altMetafactory", "");
+    let serializable = call_site("run", "()Ljava/lang/Runnable;", alt_metafactory, vec![
+        method_type("()V"),
+        BootstrapMethodArgument::Handle(handle(MethodHandleKind::H_INVOKESTATIC, "Main", "run", "()V")),
+        method_type("()V"),
+        BootstrapMethodArgument::Integer(1 | 2 | 4),
+        BootstrapMethodArgument::Integer(1),
+        class("java/io/Serializable"),
+        BootstrapMethodArgument::Integer(1),
+        method_type("()Ljava/lang/Object;"),
+    ]);
+    let BootstrapCall::Lambda(call) = resolve(&serializable) else { panic!("expected a lambda") };
+    assert!(call.serializable);
+    assert_eq!(call.marker_interfaces, vec![StrRef::from("java/io/Serializable")]);
+    assert_eq!(call.bridges, vec![StrRef::from("()Ljava/lang/Object;")]);
+    assert!(call.to_string().ends_with("invokestatic Main.run()V serializable & java/io/Serializable"));
+
+    let missing_marker = ConstDynamic { bsm_args: serializable.bsm_args[..5].to_vec(), ..serializable };
+    assert!(BootstrapCall::resolve(&missing_marker).is_none());
+}
+
+#[test]
+fn resolve_string_concat_test() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let main = node.methods.iter().find(|method| &*method.name == "main").unwrap();
+    let code_body = main.code().unwrap().unwrap();
+    let concat = code_body.instructions.iter().find_map(|insn| match insn {
+        InsnNode::InvokeDynamicInsnNode(call_site) => Some(call_site),
+        _ => None,
+    }).unwrap();
+    let BootstrapCall::StringConcat(call) = resolve(concat) else { panic!("expected a string concat") };
+    assert!(matches!(&call.parts[1], ConcatPart::Argument(0, desc) if &**desc == "I"));
+    assert_eq!(call.to_string(), "concat \"Result: \" + arg0");
+    let smali = InsnNode::InvokeDynamicInsnNode(concat.clone()).to_smali().render(0);
+    assert!(smali.contains("\n    # concat \"Result: \" + arg0"));
+
+    let factory = handle(MethodHandleKind::H_INVOKESTATIC, "java/lang/invoke/StringConcatFactory", "makeConcatWithConstants", "");
+    let with_constant = call_site("makeConcatWithConstants", "(Ljava/lang/String;J)Ljava/lang/String;", factory.clone(), vec![
+        string("\u{1} = \u{2}\u{1}!"), string("\u{1}"),
+    ]);
+    assert_eq!(resolve(&with_constant).to_string(), "concat arg0 + \" = \" + \"\\u{1}\" + arg1 + \"!\"");
+    let missing_argument = call_site("makeConcatWithConstants", "()Ljava/lang/String;", factory, vec![string("\u{1}")]);
+    assert!(BootstrapCall::resolve(&missing_argument).is_none());
+}
+
+#[test]
+fn resolve_records_and_switches_test() {
+    let object_methods = handle(MethodHandleKind::H_INVOKESTATIC, "java/lang/runtime/ObjectMethods", "bootstrap", "");
+    let to_string = call_site("toString", "(LPoint;)Ljava/lang/String;", object_methods, vec![
+        class("Point"), string("x;y"),
+        BootstrapMethodArgument::Handle(handle(MethodHandleKind::H_GETFIELD, "Point", "x", "I")),
+        BootstrapMethodArgument::Handle(handle(MethodHandleKind::H_GETFIELD, "Point", "y", "I")),
+    ]);
+    let BootstrapCall::ObjectMethods(call) = resolve(&to_string) else { panic!("expected object methods") };
+    assert_eq!(call.components, vec![StrRef::from("x"), StrRef::from("y")]);
+    assert_eq!(call.to_string(), "record Point.toString [x, y]");
+
+    let switches = handle(MethodHandleKind::H_INVOKESTATIC, "java/lang/runtime/SwitchBootstraps", "typeSwitch", "");
+    let type_switch = call_site("typeSwitch", "(Ljava/lang/Object;I)I", switches, vec![
+        class("java/lang/String"), BootstrapMethodArgument::Integer(42),
+    ]);
+    assert_eq!(resolve(&type_switch).to_string(), "typeSwitch [java/lang/String, 42]");
+
+    let unknown = handle(MethodHandleKind::H_INVOKESTATIC, "com/example/Bootstraps", "bootstrap", "");
+    let unknown = call_site("call", "()V", unknown, vec![]);
+    assert!(BootstrapCall::resolve(&unknown).is_none());
+    let smali = InsnNode::InvokeDynamicInsnNode(unknown).to_smali().render(0);
+    assert!(!smali.contains('#'));
+}
@@ -0,0 +1 @@
+mod bootstrap_test;
@@ -0,0 +1,98 @@
+use crate::dex::read_test::read_test_dex_file;
+use crate::jvms::read_test::read_jvms;
+use java_asm::cfg::{ControlFlowGraph, EdgeKind};
+use java_asm::node::element::ClassNode;
+
+fn check_graph(graph: &ControlFlowGraph) {
+    let dominators = graph.dominators();
+    let reachable = graph.reachable();
+    for (index, block) in graph.blocks.iter().enumerate() {
+        assert!(block.start < block.end);
+        assert!(block.insns().all(|insn| graph.block_of(insn) == Some(index)));
+        for edge in &block.succs {
+            assert!(graph.blocks[edge.target].preds.contains(&index));
+        }
+        assert_eq!(dominators.is_reachable(index), reachable[index]);
+        if reachable[index] {
+            assert!(dominators.dominates(0, index));
+            assert_eq!(*dominators.dominators_of(index).last().unwrap(), 0);
+        }
+    }
+    for l in graph.loops() {
+        assert!(l.blocks.iter().all(|block| dominators.dominates(l.header, *block)));
+        assert!(l.latches.iter().all(|latch| l.blocks.contains(latch)));
+    }
+}
+
+#[test]
+fn jvm_cfg_test() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let method = node.methods.iter().find(|m| m.name.as_ref() == "main").unwrap();
+    let graph = ControlFlowGraph::from_code(method.code().unwrap().unwrap()).unwrap();
+    check_graph(&graph);
+    assert!(graph.reachable().iter().all(|reachable| *reachable));
+
+    // the for-each loop over `numbers`.
+    let loops = graph.loops();
+    assert_eq!(loops.len(), 1);
+    let header = &graph.blocks[loops[0].header];
+    assert_eq!(header.succs.len(), 2);
+    let exit = header.succs.iter().map(|edge| edge.target)
+        .find(|target| !loops[0].blocks.contains(target)).unwrap();
+    assert!(graph.blocks[exit].exits);
+    let post_dominators = graph.post_dominators();
+    assert!(post_dominators.dominates(exit, 0));
+    assert!(post_dominators.dominates(loops[0].header, 0));
+    assert_eq!(post_dominators.immediate(exit), None);
+
+    let method = node.methods.iter().find(|m| m.name.as_ref() == "addNumbers").unwrap();
+    let graph = ControlFlowGraph::from_code(method.code().unwrap().unwrap()).unwrap();
+    assert_eq!(graph.blocks.len(), 1);
+    assert!(graph.blocks[0].exits && graph.blocks[0].succs.is_empty());
+}
+
+#[test]
+fn dex_cfg_test() {
+    let accessor = read_test_dex_file();
+    let mut exception_edges = 0;
+    let mut loops = 0;
+    for class_def in accessor.file.class_defs.iter() {
+        if class_def.class_data_off == 0 { continue; }
+        let class_data = accessor.get_class_element(class_def.class_data_off).unwrap();
+        let methods = class_data.direct_methods.iter().chain(class_data.virtual_methods.iter());
+        for method in methods {
+            let Some(code_item) = accessor.get_code_item(method.code_off).unwrap() else { continue };
+            let graph = ControlFlowGraph::from_dex(&accessor, method.code_off, &code_item).unwrap();
+            check_graph(&graph);
+            let insns = &code_item.insn_container.insns;
+            for (index, insn) in insns.iter().enumerate() {
+                assert_eq!(graph.block_of(index).is_none(), insn.is_payload());
+            }
+            exception_edges += graph.blocks.iter().flat_map(|block| &block.succs)
+                .filter(|edge| matches!(edge.kind, EdgeKind::Exception(_)))
+                .count();
+            loops += graph.loops().len();
+        }
+    }
+    assert!(exception_edges > 0);
+    assert!(loops > 0);
+}
+
+#[test]
+fn converted_dex_cfg_test() {
+    let accessor = read_test_dex_file();
+    for class_def in accessor.file.class_defs.iter() {
+        let node = accessor.get_class_node(*class_def).unwrap();
+        for method in &node.methods {
+            let Some(code) = &method.code_body else { continue };
+            let code = code.get().unwrap();
+            let graph = ControlFlowGraph::from_code(code).unwrap();
+            check_graph(&graph);
+            if !code.exception_table.is_empty() {
+                let has_exception_edge = graph.blocks.iter().flat_map(|block| &block.succs)
+                    .any(|edge| matches!(edge.kind, EdgeKind::Exception(_)));
+                assert!(has_exception_edge, "{}.{}", node.name, method.name);
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+mod cfg_test;
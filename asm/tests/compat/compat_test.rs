@@ -0,0 +1,77 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::compat::{ApiSignatures, ClassCompat, CompatFeature, CompatIssue};
+use java_asm::node::element::ClassNode;
+use java_asm::xref::{MemberRef, XrefIndex};
+use java_asm::StrRef;
+
+#[test]
+fn class_features_test() {
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let compat = ClassCompat::from_class_node(&node).unwrap();
+    assert_eq!(compat.version_string(), "61.0");
+    assert_eq!(compat.release(), 17);
+    assert_eq!(compat.features, vec![CompatFeature::InvokeDynamic]);
+    assert!(compat.check_release(17).is_empty());
+    assert_eq!(compat.check_release(6), vec![
+        CompatIssue::ClassVersion { release: 17 }, CompatIssue::Feature(CompatFeature::InvokeDynamic),
+    ]);
+
+    node.minor_version = ClassCompat::PREVIEW_MINOR_VERSION;
+    node.super_name = Some(StrRef::from("java/lang/Record"));
+    node.permitted_subclasses.push(StrRef::from("CompileTesting$Sub"));
+    node.nest_members.push(StrRef::from("CompileTesting$Sub"));
+    let compat = ClassCompat::from_class_node(&node).unwrap();
+    assert!(compat.is_preview());
+    assert_eq!(compat.features, vec![
+        CompatFeature::InvokeDynamic, CompatFeature::NestMates, CompatFeature::Records, CompatFeature::SealedClasses,
+    ]);
+    assert_eq!(compat.check_release(17), vec![CompatIssue::Preview]);
+    assert_eq!(
+        compat.check_release(11).iter().map(|issue| issue.to_string()).collect::<Vec<_>>(),
+        ["class file targets java 17", "class file uses preview features", "record requires java 16", "sealed requires java 17"],
+    );
+}
+
+const API: &str = "\
+# java.base of an old release
+java/lang/Object.<init>()V
+java/lang/Object.hashCode()I
+java/util/List
+java/util/List.get(I)Ljava/lang/Object;
+java/util/ArrayList : java/util/AbstractList java/util/List
+java/util/ArrayList.<init>()V
+java/io/PrintStream : java/io/FilterOutputStream
+java/lang/System.out:Ljava/io/PrintStream;
+";
+
+#[test]
+fn api_signatures_test() {
+    let api = ApiSignatures::parse(API).unwrap();
+    assert_eq!(api.len(), 5);
+    assert!(api.contains_class("java/util/ArrayList"));
+    // declared by a super type
+    assert!(api.contains_member("java/util/ArrayList", "get", "(I)Ljava/lang/Object;"));
+    assert!(api.contains_member("java/util/List", "hashCode", "()I"));
+    assert!(!api.contains_member("java/util/List", "of", "()Ljava/util/List;"));
+    // unlisted super types may declare anything
+    assert!(api.contains_member("java/util/ArrayList", "stream", "()Ljava/util/stream/Stream;"));
+    assert!(api.contains_member("java/io/PrintStream", "println", "(I)V"));
+    assert!(api.covers("java/util/Optional") && !api.covers("com/example/Main") && !api.covers("[I"));
+    assert!(ApiSignatures::parse("java/lang/String.length").is_err());
+    assert!(ApiSignatures::parse("java/lang/String.length()I : java/lang/Object").is_err());
+}
+
+#[test]
+fn check_references_test() {
+    let api = ApiSignatures::parse(API).unwrap();
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let mut xref = XrefIndex::new();
+    xref.add_class_node(&node).unwrap();
+    let issues: Vec<CompatIssue> = xref.xrefs().iter().filter_map(|usage| api.check(usage)).collect();
+    assert!(issues.contains(&CompatIssue::MissingClass(StrRef::from("java/lang/String"))));
+    assert!(!issues.iter().any(|issue| matches!(issue, CompatIssue::MissingMember(member) if &*member.owner == "java/io/PrintStream")));
+    assert_eq!(
+        CompatIssue::MissingMember(MemberRef::new("java/util/List", "of", "()Ljava/util/List;")).to_string(),
+        "missing member java/util/List.of()Ljava/util/List;",
+    );
+}
@@ -0,0 +1,49 @@
+use crate::dex::read_test::read_test_dex_file;
+use java_asm::node::element::ClassNode;
+use java_asm::node::InsnNode;
+use std::time::Instant;
+
+#[test]
+fn convert_dex_classes_test() {
+    let dex_accessor = read_test_dex_file();
+    let convert_start = Instant::now();
+    let mut converted = 0;
+    for class_def in dex_accessor.file.class_defs.iter() {
+        let node = dex_accessor.get_class_node(*class_def).unwrap();
+        let bytes = node.to_bytes().unwrap();
+        let rewritten = ClassNode::from_bytes(&bytes).unwrap();
+        assert_eq!(node.name, rewritten.name);
+        assert_eq!(node.super_name, rewritten.super_name);
+        assert_eq!(node.fields.len(), rewritten.fields.len());
+        assert_eq!(node.methods.len(), rewritten.methods.len());
+        converted += 1;
+    }
+    println!("{converted} classes converted in {:?}", convert_start.elapsed());
+}
+
+#[test]
+fn converted_code_labels_are_valid() {
+    let dex_accessor = read_test_dex_file();
+    let sample_class_def = dex_accessor.file.class_defs[100];
+    let node = dex_accessor.get_class_node(sample_class_def).unwrap();
+    assert!(node.methods.iter().any(|m| m.code_body.is_some()));
+    for method in &node.methods {
+        let Some(code) = &method.code_body else { continue };
+        let code = code.get().unwrap();
+        let count = code.instructions.len() as u16;
+        assert_eq!(code.instructions.len(), code.insn_offsets.len());
+        assert!(code.max_locals > 0 || code.instructions.iter().all(|i| !matches!(i, InsnNode::VarInsnNode { .. })));
+        for insn in &code.instructions {
+            if let InsnNode::JumpInsnNode { label, .. } = insn {
+                assert!(*label < count);
+            }
+        }
+        for exception in &code.exception_table {
+            assert!(exception.start < exception.end);
+            assert!(exception.handler < count);
+        }
+        for line in &code.line_numbers {
+            assert!(line.start_pc < count);
+        }
+    }
+}
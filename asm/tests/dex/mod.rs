@@ -1 +1,3 @@
-mod read_test;
\ No newline at end of file
+pub(crate) mod read_test;
+mod convert_test;
+mod register_types_test;
@@ -1,4 +1,6 @@
-use java_asm::dex::{DexFile, DexFileAccessor};
+use java_asm::dex::insn::{DexInsn, FillArrayDataPayload};
+use java_asm::dex::insn_syntax::F11n;
+use java_asm::dex::{DexFile, DexFileAccessor, EncodedCatchHandler, EncodedValue, InsnContainer};
 use std::sync::Arc;
 use std::time::Instant;
 use java_asm::StrRef;
@@ -29,10 +31,82 @@ fn read_dex_test() {
     println!("{}", class_smali.render(0));
 }
 
-fn read_test_dex_file() -> DexFileAccessor {
+pub(crate) fn read_test_dex_file() -> DexFileAccessor {
     let start = Instant::now();
     let dex_file_bytes = include_bytes!("../res/dex/classes14.dex");
     let dex_file = DexFile::resolve_from_bytes(dex_file_bytes).unwrap();
     println!("Dex file resolved in {:?}", start.elapsed());
     DexFileAccessor::new(dex_file, dex_file_bytes.to_vec(), StrRef::from("classes14.dex"))
 }
+
+/// Reads a [InsnContainer] from the little endian code units.
+fn read_insns(code_units: &[u16]) -> InsnContainer {
+    let mut bytes = (code_units.len() as u32).to_le_bytes().to_vec();
+    bytes.extend(code_units.iter().flat_map(|unit| unit.to_le_bytes()));
+    DexFileAccessor::get_data_in_bytes(&bytes, 0, false).unwrap()
+}
+
+#[test]
+fn read_const4_literal_test() {
+    // const/4 v0, 1 | const/4 v1, -1 | const/4 v2, -8 | const/4 v3, 7
+    let container = read_insns(&[0x1012, 0xF112, 0x8212, 0x7312]);
+    let literals: Vec<i8> = container.insns.iter().map(|insn| match insn {
+        DexInsn::Const4(F11n { literalB, .. }) => literalB.0,
+        insn => panic!("unexpected {insn:?}"),
+    }).collect();
+    assert_eq!(literals, [1, -1, -8, 7]);
+}
+
+#[test]
+fn read_encoded_value_header_test() {
+    // VALUE_INT with 2 bytes, VALUE_STRING with 1 byte, VALUE_BOOLEAN true
+    let bytes = [0x24, 0x34, 0x12, 0x17, 0x03, 0x3F];
+    let int: EncodedValue = DexFileAccessor::get_data_in_bytes(&bytes, 0, false).unwrap();
+    assert_eq!(int, EncodedValue::Int(0x1234));
+    let string: EncodedValue = DexFileAccessor::get_data_in_bytes(&bytes, 3, false).unwrap();
+    assert!(matches!(string, EncodedValue::String(index) if index.0 == 3));
+    let boolean: EncodedValue = DexFileAccessor::get_data_in_bytes(&bytes, 5, false).unwrap();
+    assert_eq!(boolean, EncodedValue::Boolean(true));
+}
+
+#[test]
+fn read_encoded_float_test() {
+    // VALUE_FLOAT 1.0 and VALUE_DOUBLE -2.0, both with the zero low-order bytes omitted
+    let bytes = [0x30, 0x80, 0x3F, 0x11, 0xC0];
+    let float: EncodedValue = DexFileAccessor::get_data_in_bytes(&bytes, 0, false).unwrap();
+    let EncodedValue::Float(float) = float else { panic!("unexpected {float:?}") };
+    assert_eq!(f32::from_le_bytes(float), 1.0);
+    let double: EncodedValue = DexFileAccessor::get_data_in_bytes(&bytes, 3, false).unwrap();
+    let EncodedValue::Double(double) = double else { panic!("unexpected {double:?}") };
+    assert_eq!(f64::from_le_bytes(double), -2.0);
+}
+
+#[test]
+fn read_fill_array_data_payload_test() {
+    // fill-array-data-payload of 3 bytes (padded to a whole code unit) | const/4 v0, 1
+    let container = read_insns(&[0x0300, 0x0001, 0x0003, 0x0000, 0x0201, 0x0003, 0x1012]);
+    let [DexInsn::FillArrayDataPayload(payload), DexInsn::Const4(_)] = container.insns.as_slice() else {
+        panic!("unexpected {:?}", container.insns);
+    };
+    let FillArrayDataPayload { element_width, data, .. } = payload;
+    assert_eq!(*element_width, 1);
+    assert_eq!(data, &[1, 2, 3]);
+}
+
+#[test]
+fn read_catch_all_only_handler_test() {
+    // a handler with no typed catches and a catch-all at address 5
+    let handler: EncodedCatchHandler = DexFileAccessor::get_data_in_bytes(&[0x00, 0x05], 0, false).unwrap();
+    assert!(handler.handlers.is_empty());
+    assert_eq!(handler.catch_all_addr.map(|addr| addr.value()), Some(5));
+}
+
+#[test]
+fn find_class_def_test() {
+    let accessor = read_test_dex_file();
+    for class_def in accessor.file.class_defs.iter() {
+        let class_type = accessor.get_type(class_def.class_idx).unwrap();
+        assert_eq!(accessor.find_class_def(&class_type), Some(class_def));
+    }
+    assert!(accessor.find_class_def("Lnot/in/Dex;").is_none());
+}
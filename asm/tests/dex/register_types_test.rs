@@ -0,0 +1,63 @@
+use crate::dex::read_test::read_test_dex_file;
+use java_asm::dex::insn::DexInsn;
+use java_asm::dex::insn_syntax::*;
+use java_asm::dex::{MethodAccessFlags, RegisterType, RegisterTypeMap};
+
+#[test]
+fn register_types_test() {
+    let accessor = read_test_dex_file();
+    let (mut nulls, mut ints, mut initialized) = (0, 0, 0);
+    for class_def in accessor.file.class_defs.iter() {
+        if class_def.class_data_off == 0 { continue; }
+        let class_type = accessor.get_type(class_def.class_idx).unwrap();
+        let class_data = accessor.get_class_element(class_def.class_data_off).unwrap();
+        for method in class_data.direct_methods.iter().chain(&class_data.virtual_methods) {
+            let Some(types) = RegisterTypeMap::analyze(&accessor, &class_type, method).unwrap() else { continue };
+            let code_item = accessor.get_code_item(method.code_off).unwrap().unwrap();
+            let entry = types.frames[0].as_ref().unwrap();
+            assert_eq!(entry.len(), code_item.registers_size as usize);
+            // parameters are the last registers.
+            let mut reg = (code_item.registers_size - code_item.ins_size) as usize;
+            if method.access_flags & MethodAccessFlags::ACC_STATIC == 0 {
+                let expected = if method.name.as_ref() == "<init>" {
+                    RegisterType::UninitializedThis(class_type.clone())
+                } else {
+                    RegisterType::Reference(class_type.clone())
+                };
+                assert_eq!(entry[reg], expected);
+                reg += 1;
+            }
+            for param in &method.parameters {
+                let expected = RegisterType::from_desc(param).unwrap();
+                assert_eq!(entry[reg], expected);
+                reg += if expected.is_wide() { 2 } else { 1 };
+            }
+
+            let insns: Vec<_> = code_item.insn_container.addressed_insns()
+                .filter(|(_, insn)| !insn.is_payload())
+                .collect();
+            for (position, (_, insn)) in insns.iter().enumerate() {
+                let Some((next_addr, _)) = insns.get(position + 1) else { continue };
+                let Some(next) = types.types_at(*next_addr) else { continue };
+                match insn {
+                    DexInsn::Const4(F11n { literalB, vA, .. }) if literalB.0 == 0 => {
+                        match &next[vA.0 as usize] {
+                            RegisterType::Null => nulls += 1,
+                            RegisterType::Int => ints += 1,
+                            _ => {}
+                        }
+                    }
+                    DexInsn::InvokeKind(F35c { opcode: 0x70, vC, .. }) => {
+                        let Some(before) = types.type_at(insns[position].0, vC.0 as u16) else { continue };
+                        if matches!(before, RegisterType::Uninitialized { .. } | RegisterType::UninitializedThis(_)) {
+                            assert!(matches!(next[vC.0 as usize], RegisterType::Reference(_)));
+                            initialized += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    assert!(nulls > 0 && ints > 0 && initialized > 0, "{nulls} {ints} {initialized}");
+}
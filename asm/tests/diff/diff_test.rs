@@ -0,0 +1,92 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::diff::{normalize_code, ClassChange, ClassSetDiff, CodeDiff, DiffKind, DiffLine, MemberChange};
+use java_asm::node::element::{ClassNode, CodeBodyNode, ExceptionTable, FieldNode};
+use java_asm::node::values::FieldInitialValue;
+use java_asm::node::InsnNode;
+use java_asm::{JavaAccessFlags, Opcodes, StrRef};
+
+fn lines(values: &[&str]) -> Vec<StrRef> {
+    values.iter().map(|value| StrRef::from(*value)).collect()
+}
+
+fn branch_code(base: u16) -> CodeBodyNode {
+    CodeBodyNode {
+        instructions: vec![
+            InsnNode::NoOperand { opcode: Opcodes::ICONST_0 },
+            InsnNode::JumpInsnNode { opcode: Opcodes::IFEQ, label: base + 4 },
+            InsnNode::NoOperand { opcode: Opcodes::ICONST_1 },
+            InsnNode::NoOperand { opcode: Opcodes::IRETURN },
+        ],
+        insn_offsets: vec![base, base + 1, base + 3, base + 4],
+        exception_table: vec![ExceptionTable {
+            start: base, end: base + 5, handler: base + 4, catch_type: None,
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn normalize_code_test() {
+    let expected = lines(&[
+        "L0:", "iconst_0", "ifeq L1", "iconst_1", "L1:", "ireturn", "L2:", ".catch any L0 L2 -> L1",
+    ]);
+    assert_eq!(normalize_code(&branch_code(0)), expected);
+    // offsets don't matter, only the positions of the labels
+    assert_eq!(normalize_code(&branch_code(100)), expected);
+}
+
+#[test]
+fn code_diff_test() {
+    let diff = CodeDiff::new(&lines(&["a", "b", "c", "d"]), &lines(&["a", "x", "c", "d", "e"]));
+    assert_eq!(diff.lines, vec![
+        DiffLine::Same("a".into()), DiffLine::Removed("b".into()), DiffLine::Added("x".into()),
+        DiffLine::Same("c".into()), DiffLine::Same("d".into()), DiffLine::Added("e".into()),
+    ]);
+    assert_eq!((diff.removed_count(), diff.added_count()), (1, 2));
+    assert_eq!(diff.to_string(), "  a\n- b\n+ x\n  c\n  d\n+ e\n");
+    let rows = diff.side_by_side();
+    assert_eq!(rows[1], (Some("b".into()), Some("x".into())));
+    assert_eq!(rows[4], (None, Some("e".into())));
+    assert_eq!(rows.len(), 5);
+}
+
+#[test]
+fn class_set_diff_test() {
+    let old = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let mut new = old.clone();
+    new.interfaces.push("java/io/Serializable".into());
+    new.fields.push(FieldNode {
+        access: JavaAccessFlags::ACC_STATIC, name: "VERSION".into(), desc: "I".into(),
+        value: Some(FieldInitialValue::Integer(2)), ..Default::default()
+    });
+    let removed = new.methods.pop().unwrap();
+    let method = new.methods.iter_mut().find(|m| m.code_body.is_some()).unwrap();
+    method.access ^= JavaAccessFlags::ACC_FINAL;
+    let code = method.code_mut().unwrap().unwrap();
+    code.instructions[0] = InsnNode::NoOperand { opcode: Opcodes::NOP };
+    let changed_name = method.name.clone();
+    let mut added_class = old.clone();
+    added_class.name = "CompileTesting2".into();
+
+    let diff = ClassSetDiff::new([&old], [&new, &added_class]).unwrap();
+    assert_eq!(diff.added, vec![StrRef::from("CompileTesting2")]);
+    assert!(diff.removed.is_empty());
+    let class = &diff.changed[0];
+    assert_eq!(class.changes, vec![ClassChange::Interfaces {
+        added: vec!["java/io/Serializable".into()], removed: vec![],
+    }]);
+    assert_eq!((&*class.fields[0].name, class.fields[0].kind), ("VERSION", DiffKind::Added));
+
+    let changed = class.methods.iter().find(|m| m.kind == DiffKind::Changed).unwrap();
+    assert_eq!(changed.name, changed_name);
+    assert!(matches!(changed.changes[0], MemberChange::Access { .. }));
+    let MemberChange::Code(code) = &changed.changes[1] else { panic!("expected a code change") };
+    assert_eq!((code.removed_count(), code.added_count()), (1, 1));
+    assert_eq!(code.side_by_side()[0].1.as_deref(), Some("nop"));
+    let removed_diff = class.methods.iter().find(|m| m.kind == DiffKind::Removed).unwrap();
+    assert_eq!(removed_diff.name, removed.name);
+
+    let same = ClassSetDiff::new([&old], [&old]).unwrap();
+    assert!(same.is_empty());
+    assert_eq!(same.unchanged_count, 1);
+}
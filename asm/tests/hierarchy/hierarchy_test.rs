@@ -0,0 +1,67 @@
+use crate::dex::read_test::read_test_dex_file;
+use crate::jvms::read_test::read_jvms;
+use java_asm::hierarchy::{ClassHierarchy, HierarchyClass, HierarchyResolver};
+use java_asm::node::element::ClassNode;
+use java_asm::StrRef;
+
+fn class(name: &str, super_name: &str, interfaces: &[&str], access: u16) -> HierarchyClass {
+    HierarchyClass {
+        name: StrRef::from(name),
+        access,
+        super_name: Some(StrRef::from(super_name)),
+        interfaces: interfaces.iter().map(|i| StrRef::from(*i)).collect(),
+    }
+}
+
+#[test]
+fn hierarchy_queries_test() {
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_class(class("a/Shape", "java/lang/Object", &["a/Drawable"], 0x0401));
+    hierarchy.add_class(class("a/Drawable", "java/lang/Object", &[], 0x0601));
+    hierarchy.add_class(class("a/Circle", "a/Shape", &[], 0x0001));
+    hierarchy.add_class(class("a/Square", "a/Shape", &["java/lang/Comparable"], 0x0001));
+    assert!(!hierarchy.add_class(class("a/Square", "java/lang/Object", &[], 0)));
+
+    let mut subtypes = hierarchy.all_subtypes("a/Drawable");
+    subtypes.sort();
+    assert_eq!(subtypes, vec![StrRef::from("a/Circle"), "a/Shape".into(), "a/Square".into()]);
+    assert_eq!(hierarchy.direct_subclasses("a/Shape").len(), 2);
+
+    assert!(hierarchy.is_assignable("a/Drawable", "a/Circle"));
+    assert!(!hierarchy.is_assignable("a/Circle", "a/Shape"));
+    assert!(hierarchy.is_assignable("[La/Shape;", "[La/Square;"));
+    assert!(hierarchy.is_assignable("java/lang/Cloneable", "[I"));
+    assert!(!hierarchy.is_assignable("[J", "[I"));
+
+    assert_eq!(hierarchy.common_super_class("a/Circle", "a/Square").as_ref(), "a/Shape");
+    assert_eq!(hierarchy.common_super_class("a/Circle", "a/Drawable").as_ref(), "a/Drawable");
+    assert_eq!(hierarchy.common_super_class("a/Circle", "java/lang/String").as_ref(), "java/lang/Object");
+}
+
+#[test]
+fn hierarchy_cycle_test() {
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_class(class("a/A", "a/B", &[], 0x0001));
+    hierarchy.add_class(class("a/B", "a/A", &[], 0x0001));
+    hierarchy.add_class(class("a/C", "java/lang/Object", &[], 0x0001));
+
+    assert!(!hierarchy.is_assignable("a/C", "a/A"));
+    assert_eq!(hierarchy.common_super_class("a/A", "a/C").as_ref(), "java/lang/Object");
+}
+
+#[test]
+fn hierarchy_from_dex_and_class_test() {
+    let dex_accessor = read_test_dex_file();
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_dex(&dex_accessor).unwrap();
+    assert_eq!(hierarchy.len(), dex_accessor.file.class_defs.len());
+    for class in hierarchy.classes() {
+        for super_type in class.super_name.iter().chain(&class.interfaces) {
+            assert!(hierarchy.all_subtypes(super_type).contains(&class.name));
+        }
+    }
+
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    assert!(hierarchy.add_class_node(&node));
+    assert!(hierarchy.is_assignable("java/lang/Object", &node.name));
+}
@@ -0,0 +1 @@
+mod hierarchy_test;
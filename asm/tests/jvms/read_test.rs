@@ -1,8 +1,8 @@
 use std::time::Instant;
 
 use java_asm::AsmResult;
-use java_asm::jvms::element::ClassFile;
-use java_asm::jvms::JvmsClassReader;
+use java_asm::jvms::element::{ClassFile, Const};
+use java_asm::jvms::{JvmsClassReader, JvmsClassWriter};
 
 #[test]
 fn read_jvms_test() {
@@ -16,3 +16,15 @@ pub(crate) fn read_jvms() -> AsmResult<ClassFile> {
     println!("read jvms class file cost: {:?}", start.elapsed());
     class_file
 }
+
+#[test]
+fn read_wide_constants_test() {
+    let bytes = include_bytes!("../res/bytecode/WideConstants.class");
+    let class_file = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    // long & double constants occupy 2 slots, the vec index should still be the cp index.
+    assert_eq!(class_file.constant_pool.len(), class_file.constant_pool_count as usize);
+    let this_class = &class_file.constant_pool[class_file.this_class as usize];
+    assert!(matches!(this_class.info, Const::Class { .. }));
+    let written = JvmsClassWriter::write_class_bytes(vec![], class_file).unwrap();
+    assert_eq!(written, bytes);
+}
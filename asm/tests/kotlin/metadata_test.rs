@@ -0,0 +1,245 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::kotlin::{KotlinClassifier, KotlinDeclarations, KotlinMetadata};
+use java_asm::node::element::{AnnotationNode, ClassNode};
+use java_asm::node::values::{AnnotationValue, ConstValue};
+use java_asm::StrRef;
+use std::sync::Arc;
+
+/// A protobuf message written field by field.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl Message {
+    fn int(mut self, number: u32, value: u32) -> Self {
+        varint(&mut self.0, (number as u64) << 3);
+        varint(&mut self.0, value as u64);
+        self
+    }
+
+    fn bytes(mut self, number: u32, bytes: &[u8]) -> Self {
+        varint(&mut self.0, (number as u64) << 3 | 2);
+        varint(&mut self.0, bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn msg(self, number: u32, message: Message) -> Self {
+        self.bytes(number, &message.0)
+    }
+
+    fn packed(self, number: u32, values: &[u32]) -> Self {
+        let mut packed = Vec::new();
+        for value in values {
+            varint(&mut packed, *value as u64);
+        }
+        self.bytes(number, &packed)
+    }
+}
+
+fn class_type(name: u32) -> Message {
+    Message::default().int(6, name)
+}
+
+fn type_parameter_type(id: u32) -> Message {
+    Message::default().int(7, id)
+}
+
+const D2: [&str; 15] = [
+    "com/example/User", "T", "", "name", "kotlin/String", "nick", "load", "id", "kotlin/Int",
+    "kotlin/collections/List", "parse", "R", "block", "Lcom/example/Base$Outer;", "kotlin/Function1",
+];
+
+/// `d1` bytes of:
+/// ```kotlin
+/// data class User<T : Any>(val name: String, var nick: String?) : Base.Outer() {
+///     suspend fun load(id: Int = 0): List<T>
+///     inline fun <reified R> String.parse(block: (String) -> R): R?
+/// }
+/// ```
+fn user_class() -> Vec<u8> {
+    let string_table = Message::default()
+        .msg(1, Message::default().int(1, 2))
+        // `kotlin/Any` is predefined
+        .msg(1, Message::default().int(2, 0))
+        .msg(1, Message::default().int(1, 10))
+        // a descriptor to a class id
+        .msg(1, Message::default().int(3, 2));
+    let type_table = Message::default()
+        .msg(1, class_type(2))
+        .msg(1, class_type(13))
+        .msg(1, class_type(4))
+        .msg(1, class_type(4))
+        .int(2, 3);
+    let load = Message::default()
+        .int(2, 6)
+        .msg(3, class_type(9).msg(2, Message::default().msg(2, type_parameter_type(0))))
+        .msg(6, Message::default().int(1, 2).int(2, 7).msg(3, class_type(8)))
+        .int(9, 6 | 1 << 13);
+    let function_type = class_type(14)
+        .msg(2, Message::default().msg(2, class_type(4)))
+        .msg(2, Message::default().msg(2, type_parameter_type(1)));
+    let parse = Message::default()
+        .int(2, 10)
+        .msg(3, type_parameter_type(1).int(3, 1))
+        .msg(4, Message::default().int(1, 1).int(2, 11).int(3, 1))
+        .msg(6, Message::default().int(2, 12).msg(3, function_type))
+        .int(8, 2)
+        .int(9, 6 | 1 << 10);
+    let class = Message::default()
+        .int(1, 6 | 1 << 10)
+        .packed(2, &[1])
+        .int(3, 0)
+        .msg(5, Message::default().int(1, 0).int(2, 1).packed(6, &[0]))
+        .msg(8, Message::default()
+            .msg(2, Message::default().int(2, 3).int(5, 2))
+            .msg(2, Message::default().int(2, 5).int(5, 3)))
+        .msg(9, load)
+        .msg(9, parse)
+        .msg(10, Message::default().int(2, 3).int(9, 2))
+        .msg(10, Message::default().int(2, 5).int(9, 3).int(11, 518 | 1 << 8 | 1 << 10))
+        .msg(30, type_table);
+    let mut bytes = Vec::new();
+    varint(&mut bytes, string_table.0.len() as u64);
+    bytes.extend(string_table.0);
+    bytes.extend(class.0);
+    bytes
+}
+
+fn strings(values: &[&str]) -> Vec<StrRef> {
+    values.iter().map(|value| StrRef::from(*value)).collect()
+}
+
+/// `d1` of Kotlin 1.3+, every char is a byte after a `\u0000` marker.
+fn utf8_mode(bytes: &[u8]) -> Vec<StrRef> {
+    let data: String = std::iter::once('\0').chain(bytes.iter().map(|byte| *byte as char)).collect();
+    vec![StrRef::from(data)]
+}
+
+/// `d1` of older versions, 7 bits per char and every char incremented by one.
+fn seven_bit_mode(bytes: &[u8]) -> Vec<StrRef> {
+    let (mut buffer, mut bits, mut data) = (0u32, 0, String::new());
+    for byte in bytes {
+        buffer |= (*byte as u32) << bits;
+        bits += 8;
+        while bits >= 7 {
+            data.push(((buffer & 0x7F) as u8 + 1) as char);
+            buffer >>= 7;
+            bits -= 7;
+        }
+    }
+    if bits > 0 {
+        data.push(((buffer & 0x7F) as u8 + 1) as char);
+    }
+    // strings are split like the compiler does for long data
+    let (first, second) = data.split_at(data.len() / 2);
+    vec![StrRef::from(first), StrRef::from(second)]
+}
+
+fn metadata(kind: i32, data1: Vec<StrRef>, data2: &[&str]) -> KotlinMetadata {
+    KotlinMetadata { kind, version: vec![1, 9, 0], data1, data2: strings(data2), ..Default::default() }
+}
+
+const USER_SUMMARY: &str = "\
+data class com.example.User<T : kotlin.Any> : com.example.Base.Outer {
+    constructor(name: kotlin.String, nick: kotlin.String?)
+    val name: kotlin.String
+    var nick: kotlin.String?
+    suspend fun load(id: kotlin.Int = ...): kotlin.collections.List<T>
+    inline fun <reified R> kotlin.String.parse(block: (kotlin.String) -> R): R?
+}
+";
+
+#[test]
+fn decode_class_test() {
+    let metadata = metadata(KotlinMetadata::CLASS, utf8_mode(&user_class()), &D2);
+    let KotlinDeclarations::Class(class) = metadata.decode().unwrap() else { panic!("expected a class") };
+    assert_eq!(&*class.name, "com/example/User");
+    assert!(class.is_data());
+    assert_eq!(class.type_parameters[0].upper_bounds[0].classifier, KotlinClassifier::Class(StrRef::from("kotlin/Any")));
+    assert_eq!(class.supertypes[0].classifier, KotlinClassifier::Class(StrRef::from("com/example/Base.Outer")));
+    let nick = &class.properties[1];
+    assert!(nick.is_var() && nick.return_type.nullable);
+    assert!(!class.properties[0].return_type.nullable);
+    let (load, parse) = (&class.functions[0], &class.functions[1]);
+    assert!(load.is_suspend() && !load.is_inline());
+    assert!(parse.is_inline() && parse.type_parameters[0].reified);
+    assert_eq!(parse.receiver.as_ref().unwrap().classifier, KotlinClassifier::Class(StrRef::from("kotlin/String")));
+    assert_eq!(parse.return_type.classifier, KotlinClassifier::TypeParameter(StrRef::from("R")));
+
+    assert_eq!(metadata.decode().unwrap().to_string(), USER_SUMMARY);
+    let legacy = KotlinMetadata { data1: seven_bit_mode(&user_class()), ..metadata.clone() };
+    assert_eq!(legacy.decode().unwrap(), metadata.decode().unwrap());
+
+    let smali = metadata.to_smali().render(0);
+    assert!(smali.starts_with("# kotlin metadata 1.9.0"));
+    assert!(smali.contains("\n    #     suspend fun load(id: kotlin.Int = ...): kotlin.collections.List<T>"));
+}
+
+#[test]
+fn decode_file_facade_test() {
+    let string_table = Message::default();
+    // typealias Names = List<String>
+    // val String.size: Int get() = length
+    let package = Message::default()
+        .msg(4, Message::default().int(2, 0).msg(5, class_type(1)).msg(3, class_type(2)).int(11, 518))
+        .msg(5, Message::default()
+            .int(2, 3)
+            .msg(4, class_type(4).msg(2, Message::default().msg(2, class_type(1))))
+            .msg(6, class_type(4).msg(2, Message::default().msg(2, class_type(1)))));
+    let mut bytes = Vec::new();
+    varint(&mut bytes, string_table.0.len() as u64);
+    bytes.extend(package.0);
+    let d2 = ["size", "kotlin/String", "kotlin/Int", "Names", "kotlin/collections/List"];
+    let metadata = metadata(KotlinMetadata::FILE_FACADE, utf8_mode(&bytes), &d2);
+    let KotlinDeclarations::Package(package) = metadata.decode().unwrap() else { panic!("expected a package") };
+    assert_eq!(&*package.type_aliases[0].name, "Names");
+    assert!(package.properties[0].receiver.is_some());
+    assert_eq!(
+        metadata.decode().unwrap().to_string(),
+        "typealias Names = kotlin.collections.List<kotlin.String>\nval kotlin.String.size: kotlin.Int\n",
+    );
+
+    let facade = KotlinMetadata {
+        kind: KotlinMetadata::MULTI_FILE_CLASS_FACADE,
+        data1: strings(&["com/example/UtilsKt__StringsKt"]),
+        ..Default::default()
+    };
+    assert_eq!(facade.decode().unwrap().to_string(), "part com.example.UtilsKt__StringsKt\n");
+    let synthetic = KotlinMetadata { kind: KotlinMetadata::SYNTHETIC_CLASS, ..Default::default() };
+    assert_eq!(synthetic.decode().unwrap(), KotlinDeclarations::SyntheticClass(None));
+    let broken = KotlinMetadata { data1: utf8_mode(&bytes[..bytes.len() - 1]), ..metadata };
+    assert!(broken.decode().is_err());
+}
+
+#[test]
+fn read_metadata_annotation_test() {
+    let value = |value: ConstValue| AnnotationValue::Const(Arc::new(value));
+    let strings_value = |values: Vec<StrRef>| {
+        AnnotationValue::Array(values.into_iter().map(|s| value(ConstValue::String(s))).collect())
+    };
+    let annotation = AnnotationNode {
+        visible: true,
+        type_name: StrRef::from(KotlinMetadata::DESCRIPTOR),
+        values: vec![
+            (StrRef::from("mv"), AnnotationValue::Array(vec![1, 9, 0].into_iter()
+                .map(|v| value(ConstValue::Integer(v))).collect())),
+            (StrRef::from("k"), value(ConstValue::Integer(1))),
+            (StrRef::from("d1"), strings_value(utf8_mode(&user_class()))),
+            (StrRef::from("d2"), strings_value(strings(&D2))),
+        ],
+    };
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    assert_eq!(KotlinMetadata::from_class_node(&node), None);
+    node.annotations.push(annotation);
+    let metadata = KotlinMetadata::from_class_node(&node).unwrap();
+    assert_eq!(metadata.version_string(), "1.9.0");
+    assert_eq!(metadata.decode().unwrap().to_string(), USER_SUMMARY);
+}
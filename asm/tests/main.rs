@@ -1,3 +1,18 @@
 pub mod jvms;
 pub mod node;
 pub mod dex;
+pub mod hierarchy;
+pub mod xref;
+pub mod verify;
+pub mod analysis;
+pub mod cfg;
+pub mod visitor;
+pub mod remap;
+pub mod mapping;
+pub mod kotlin;
+pub mod smap;
+pub mod bootstrap;
+pub mod compat;
+pub mod diff;
+pub mod binary_compat;
+pub mod android;
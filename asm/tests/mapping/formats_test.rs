@@ -0,0 +1,168 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::mapping::{MappingFormat, MappingTree, ProguardMapping};
+use java_asm::node::element::ClassNode;
+use java_asm::remap::{ClassRemapper, Remapper};
+use java_asm::StrRef;
+
+const TINY: &str = "\
+tiny\t2\t0\tofficial\tintermediary\tnamed
+c\ta\tnet/minecraft/class_1\tnet/minecraft/Entity
+\tc\tA thing in a world.\\nWith a second line.
+\tf\tLb;\ta\tfield_1\tworld
+\tm\t(Lb;I)V\ta\tmethod_1\tmove
+\t\tc\tMoves the entity.
+\t\tp\t2\t\t\tdistance
+c\tb\tnet/minecraft/class_2\t
+";
+
+const SRG: &str = "\
+PK: ./ net/minecraft/src
+CL: a net/minecraft/Entity
+CL: b net/minecraft/World
+FD: a/a net/minecraft/Entity/world
+MD: a/a (Lb;I)V net/minecraft/Entity/move (Lnet/minecraft/World;I)V
+";
+
+const ENIGMA: &str = "\
+CLASS a net/minecraft/Entity
+\tCOMMENT A thing in a world.
+\tFIELD a world Lb;
+\tMETHOD a move (Lb;I)V
+\t\tARG 2 distance
+\tCLASS c Pose
+\t\tFIELD a standing Z
+CLASS b
+\tMETHOD b tick ()V
+";
+
+#[test]
+fn tiny_v2_test() {
+    let tree = MappingTree::parse(TINY).unwrap();
+    assert_eq!(tree.namespaces, ["official", "intermediary", "named"].map(StrRef::from));
+    let entity = &tree.classes[0];
+    assert_eq!(entity.names[2].as_deref(), Some("net/minecraft/Entity"));
+    assert_eq!(entity.comment.as_deref(), Some("A thing in a world.\nWith a second line."));
+    assert_eq!(entity.fields[0].desc.as_deref(), Some("Lb;"));
+    let method = &entity.methods[0];
+    assert_eq!(method.comment.as_deref(), Some("Moves the entity."));
+    assert_eq!(method.params[0].lv_index, 2);
+    assert_eq!(method.params[0].names, [None, None, Some(StrRef::from("distance"))]);
+    assert_eq!(tree.classes[1].names[2], None);
+
+    assert_eq!(tree.to_tiny_v2(), TINY);
+    assert_eq!(MappingTree::parse(&tree.write(MappingFormat::TinyV2).unwrap()).unwrap(), tree);
+}
+
+#[test]
+fn srg_and_tsrg_test() {
+    assert_eq!(MappingFormat::detect(SRG), Some(MappingFormat::Srg));
+    let tree = MappingTree::parse(SRG).unwrap();
+    assert_eq!(tree.classes.len(), 2);
+    let entity = &tree.classes[0];
+    assert_eq!(entity.fields[0].desc, None);
+    assert_eq!(entity.methods[0].names[1].as_deref(), Some("move"));
+
+    let tsrg = tree.to_tsrg().unwrap();
+    assert_eq!(tsrg, "a net/minecraft/Entity\n\ta world\n\ta (Lb;I)V move\nb net/minecraft/World\n");
+    assert_eq!(MappingFormat::detect(&tsrg), Some(MappingFormat::Tsrg));
+    assert_eq!(MappingTree::parse(&tsrg).unwrap(), tree);
+    let srg = tree.to_srg().unwrap();
+    assert_eq!(MappingTree::parse(&srg).unwrap(), tree);
+
+    let tsrg2 = MappingTree::parse(TINY).unwrap().to_tsrg2();
+    assert!(tsrg2.starts_with("tsrg2 official intermediary named\na net/minecraft/class_1 net/minecraft/Entity\n"));
+    assert!(tsrg2.contains("\t\t2 o o distance\n"));
+    let from_tsrg2 = MappingTree::parse(&tsrg2).unwrap();
+    assert_eq!(from_tsrg2.classes[0].methods[0].names[2].as_deref(), Some("move"));
+    assert_eq!(from_tsrg2.classes[0].fields[0].desc.as_deref(), Some("Lb;"));
+}
+
+#[test]
+fn enigma_test() {
+    let tree = MappingTree::parse(ENIGMA).unwrap();
+    assert_eq!(tree.namespaces, ["obf", "deobf"].map(StrRef::from));
+    let names: Vec<(&str, Option<&str>)> = tree.classes.iter()
+        .map(|class| (class.names[0].as_deref().unwrap(), class.names[1].as_deref()))
+        .collect();
+    assert_eq!(names, [
+        ("a", Some("net/minecraft/Entity")),
+        ("a$c", Some("net/minecraft/Entity$Pose")),
+        ("b", None),
+    ]);
+    let entity = &tree.classes[0];
+    assert_eq!(entity.comment.as_deref(), Some("A thing in a world."));
+    assert_eq!(entity.methods[0].params[0].names, [None, Some(StrRef::from("distance"))]);
+
+    let written = tree.to_enigma().unwrap();
+    assert!(written.contains("CLASS a$c net/minecraft/Entity$Pose\n\tFIELD a standing Z\n"));
+    assert!(written.contains("CLASS b\n\tMETHOD b tick ()V\n"));
+    assert_eq!(MappingTree::parse(&written).unwrap(), tree);
+}
+
+#[test]
+fn convert_and_chain_test() {
+    let tiny = MappingTree::parse(TINY).unwrap();
+    // named names first, descriptors follow the new first namespace
+    let named = tiny.with_namespaces(&["named", "official"]).unwrap();
+    assert_eq!(named.classes[0].names[0].as_deref(), Some("net/minecraft/Entity"));
+    assert_eq!(named.classes[0].methods[0].desc.as_deref(), Some("(Lb;I)V"));
+    // `b` has no named name, so it keeps its official name
+    assert_eq!(named.classes[1].names[0].as_deref(), Some("b"));
+
+    let intermediary = tiny.with_namespaces(&["official", "intermediary"]).unwrap();
+    let enigma = MappingTree::parse_enigma("\
+CLASS net/minecraft/class_1 net/minecraft/Entity
+\tMETHOD method_1 move (Lnet/minecraft/class_2;I)V
+CLASS net/minecraft/class_2 net/minecraft/World
+").unwrap().with_namespaces(&["obf", "deobf"]).unwrap();
+    let mut next = enigma.clone();
+    next.namespaces = ["intermediary", "named"].map(StrRef::from).to_vec();
+    let chained = intermediary.chain(&next).unwrap();
+    assert_eq!(chained.namespaces, ["official", "intermediary", "named"].map(StrRef::from));
+
+    let remapper = chained.remapper("official", "named").unwrap();
+    assert_eq!(remapper.map("b").as_deref(), Some("net/minecraft/World"));
+    assert_eq!(remapper.map_method_name("a", "a", "(Lb;I)V").as_deref(), Some("move"));
+    assert_eq!(&*remapper.map_desc(&StrRef::from("(Lb;I)La;")).unwrap(), "(Lnet/minecraft/World;I)Lnet/minecraft/Entity;");
+    assert_eq!(remapper.unmap_class("net/minecraft/Entity").map(|name| &**name), Some("a"));
+    // the field is not in the chained mapping, so it has no named name and keeps its official one
+    assert_eq!(remapper.map_field_name("a", "a", "Lb;").as_deref(), Some("a"));
+    assert_eq!(chained.classes[0].fields[0].names[1].as_deref(), Some("field_1"));
+
+    assert!(intermediary.chain(&enigma).is_err());
+}
+
+#[test]
+fn proguard_to_tree_test() {
+    let mapping = ProguardMapping::parse("\
+com.example.Main -> a.a:
+    com.example.Data data -> b
+    1:1:void helper():10:10 -> c
+    1:1:void run(com.example.Data):20 -> c
+com.example.Data -> a.b:
+").unwrap();
+    let tree = mapping.to_tree();
+    assert_eq!(tree.namespaces, ["obfuscated", "original"].map(StrRef::from));
+    let main = &tree.classes[0];
+    assert_eq!(main.fields[0].desc.as_deref(), Some("La/b;"));
+    // the inlined `helper` is not a method of the obfuscated class
+    assert_eq!(main.methods.len(), 1);
+    assert_eq!(main.methods[0].names[1].as_deref(), Some("run"));
+    let tiny = tree.to_tiny_v2();
+    assert!(tiny.contains("\tm\t(La/b;)V\tc\trun\n"));
+}
+
+#[test]
+fn remap_class_node_with_tree_test() {
+    let tree = MappingTree::parse("\
+tiny\t2\t0\tobf\tnamed
+c\tCompileTesting\tdemo/Renamed
+\tf\tI\tfield1\tcount
+\tm\t(II)I\taddNumbers\tsum
+").unwrap();
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    ClassRemapper::new(&tree.deobfuscator().unwrap()).remap(&mut node).unwrap();
+    assert_eq!(&*node.name, "demo/Renamed");
+    assert_eq!(&*node.fields[0].name, "count");
+    assert!(node.methods.iter().any(|m| &*m.name == "sum" && &*m.desc == "(II)I"));
+}
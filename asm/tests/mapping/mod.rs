@@ -0,0 +1,2 @@
+mod formats_test;
+mod proguard_test;
@@ -0,0 +1,87 @@
+use crate::dex::read_test::read_test_dex_file;
+use java_asm::mapping::{LineRange, ProguardMapping, RetracedFrame};
+use java_asm::remap::{remap_smali, Remapper};
+use java_asm::smali::SmaliToken;
+use java_asm::StrRef;
+
+const MAPPING: &str = "\
+# compiler: R8
+com.example.Main -> a.a:
+# {\"id\":\"sourceFile\",\"fileName\":\"Main.kt\"}
+    int count -> a
+    com.example.Data data -> b
+    1:4:void <init>() -> <init>
+    5:5:void helper(int):40:40 -> c
+    5:5:void com.example.Data.check():12 -> c
+    5:5:void run(java.lang.String[]):20 -> c
+    6:9:void run(java.lang.String[]):21:24 -> c
+    java.lang.String describe(com.example.Data,long[]) -> d
+com.example.Data -> a.b:
+    boolean valid -> a
+";
+
+fn frame(class: &str, method: &str, desc: &str, line: u32) -> RetracedFrame {
+    RetracedFrame { class: class.into(), method: method.into(), desc: desc.into(), line: Some(line) }
+}
+
+#[test]
+fn parse_proguard_mapping_test() {
+    let mapping = ProguardMapping::parse(MAPPING).unwrap();
+    assert_eq!(mapping.classes.len(), 2);
+    let main = mapping.class_by_obfuscated("a/a").unwrap();
+    assert_eq!(&*main.original, "com/example/Main");
+    assert_eq!(main.fields.len(), 2);
+    assert_eq!(main.methods.len(), 6);
+    let check = &main.methods[2];
+    assert_eq!(check.original_class.as_deref(), Some("com/example/Data"));
+    assert_eq!(check.obfuscated_lines, Some(LineRange { start: 5, end: 5 }));
+    assert_eq!(check.original_lines, Some(LineRange { start: 12, end: 12 }));
+    assert_eq!(&*main.methods[5].desc, "(Lcom/example/Data;[J)Ljava/lang/String;");
+    assert_eq!(mapping.obfuscated_class("com/example/Data").map(|n| &**n), Some("a/b"));
+
+    assert_eq!(mapping.map("a/b").as_deref(), Some("com/example/Data"));
+    assert_eq!(mapping.map_field_name("a/a", "b", "La/b;").as_deref(), Some("data"));
+    // inlined frames are not methods of the obfuscated class
+    assert_eq!(mapping.map_method_name("a/a", "c", "([Ljava/lang/String;)V").as_deref(), Some("run"));
+    assert_eq!(mapping.map_method_name("a/a", "d", "(La/b;[J)Ljava/lang/String;").as_deref(), Some("describe"));
+    assert_eq!(&*mapping.map_desc(&StrRef::from("(La/a;I)La/b;")).unwrap(), "(Lcom/example/Main;I)Lcom/example/Data;");
+
+    assert!(ProguardMapping::parse("a.B -> c:\n    int x\n").is_err());
+}
+
+#[test]
+fn retrace_inline_frames_test() {
+    let mapping = ProguardMapping::parse(MAPPING).unwrap();
+    assert_eq!(mapping.retrace("a/a", "c", 5), vec![
+        frame("com/example/Main", "helper", "(I)V", 40),
+        frame("com/example/Data", "check", "()V", 12),
+        frame("com/example/Main", "run", "([Ljava/lang/String;)V", 20),
+    ]);
+    assert_eq!(mapping.retrace("a/a", "c", 8), vec![frame("com/example/Main", "run", "([Ljava/lang/String;)V", 23)]);
+    assert_eq!(mapping.retrace("a/a", "<init>", 2), vec![frame("com/example/Main", "<init>", "()V", 2)]);
+    assert!(mapping.retrace("a/a", "c", 10).is_empty());
+}
+
+#[test]
+fn remap_dex_smali_test() {
+    let accessor = read_test_dex_file();
+    // a class with a method without arguments, which is renamed by the mapping
+    let (class_def, class_type, method_name) = accessor.file.class_defs.iter().find_map(|class_def| {
+        if class_def.class_data_off == 0 { return None; }
+        let data = accessor.get_class_element(class_def.class_data_off).ok()?;
+        let method = data.virtual_methods.iter()
+            .find(|m| m.parameters.is_empty() && &*m.return_type == "V")?;
+        Some((*class_def, accessor.get_type(class_def.class_idx).ok()?, method.name.clone()))
+    }).unwrap();
+    let obfuscated = class_type.strip_prefix('L').unwrap().strip_suffix(';').unwrap();
+    let text = format!("com.example.Original -> {}:\n    void original() -> {method_name}\n", obfuscated.replace('/', "."));
+    let mapping = ProguardMapping::parse(&text).unwrap();
+
+    let mut smali = accessor.get_class_smali(class_def).unwrap();
+    remap_smali(&mut smali, obfuscated, &mapping);
+    let lines = smali.render_to_lines();
+    let tokens: Vec<&SmaliToken> = lines.iter().flatten().collect();
+    assert!(tokens.contains(&&SmaliToken::Descriptor("Lcom/example/Original;".into())));
+    assert!(!tokens.contains(&&SmaliToken::Descriptor(class_type.clone())));
+    assert!(tokens.contains(&&SmaliToken::MemberName("original".into())));
+}
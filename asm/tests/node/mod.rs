@@ -1 +1,2 @@
-mod read_test;
\ No newline at end of file
+mod read_test;
+mod write_test;
@@ -1,5 +1,10 @@
 use crate::jvms::read_test::read_jvms;
+use java_asm::jvms::attr::Attribute as JvmsAttribute;
+use java_asm::jvms::JvmsClassReader;
 use java_asm::node::element::ClassNode;
+use java_asm::node::InsnNode;
+use java_asm::visitor::{ClassNodeBuilder, ClassReader};
+use java_asm::Opcodes;
 use std::time::Instant;
 
 #[test]
@@ -12,3 +17,158 @@ fn read_node() {
     println!("node resolve cost: {:?}", start.elapsed());
     println!("{:#?}", node.unwrap());
 }
+
+#[test]
+fn read_node_lazy_code() {
+    let mut lazy = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let mut builder = ClassNodeBuilder::new();
+    ClassReader::new(read_jvms().unwrap()).accept(&mut builder, 0).unwrap();
+    let eager = builder.into_node();
+
+    for (lazy_method, eager_method) in lazy.methods.iter().zip(&eager.methods) {
+        let lazy_code = lazy_method.code().unwrap().unwrap();
+        let eager_code = eager_method.code().unwrap().unwrap();
+        assert_eq!(lazy_code.instructions.len(), eager_code.instructions.len());
+        assert_eq!(lazy_code.insn_offsets, eager_code.insn_offsets);
+        assert_eq!(lazy_code.line_numbers.len(), eager_code.line_numbers.len());
+    }
+
+    // changes are kept after the code is decoded.
+    let code = lazy.methods[0].code_mut().unwrap().unwrap();
+    code.instructions.clear();
+    assert!(lazy.methods[0].code().unwrap().unwrap().instructions.is_empty());
+}
+
+#[test]
+fn read_node_lazy_code_in_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ClassNode>();
+
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let method = node.methods.iter().find(|method| &*method.name == "main").unwrap();
+    let codes: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4).map(|_| scope.spawn(|| method.code().unwrap().unwrap())).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    // the code is decoded only once.
+    assert!(codes.iter().all(|code| std::ptr::eq(*code, codes[0])));
+}
+
+#[test]
+fn read_node_annotations() {
+    let bytes = include_bytes!("../res/bytecode/DeprecatedTesting.class");
+    let jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    let annotation = &node.annotations[0];
+    assert!(annotation.visible);
+    assert_eq!(&*annotation.type_name, "Ljava/lang/Deprecated;");
+}
+
+#[test]
+fn read_node_anonymous_inner_class() {
+    let bytes = include_bytes!("../res/bytecode/AnonymousTesting.class");
+    let jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    let inner_class = &node.inner_classes[0];
+    assert_eq!(&*inner_class.name, "AnonymousTesting$1");
+    assert!(inner_class.inner_name.is_empty());
+}
+
+#[test]
+fn read_node_enclosing_class_only() {
+    // anonymous class in a field initializer, which is not enclosed in any method
+    let bytes = include_bytes!("../res/bytecode/FieldAnonymousTesting$1.class");
+    let jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    assert_eq!(node.outer_class.as_deref(), Some("FieldAnonymousTesting"));
+    assert!(node.outer_method_name.is_none());
+    assert!(node.outer_method_desc.is_none());
+}
+
+#[test]
+fn read_node_switches() {
+    let bytes = include_bytes!("../res/bytecode/SwitchTesting.class");
+    let jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    let instructions = |name: &str| {
+        let method = node.methods.iter().find(|method| &*method.name == name).unwrap();
+        method.code().unwrap().unwrap().instructions.clone()
+    };
+    // both switches start at offset 3, so there is no padding before the default offset.
+    let table_switch = instructions("tableSwitch");
+    let InsnNode::TableSwitchInsnNode { default, min, max, labels } = &table_switch[3] else {
+        panic!("unexpected {:?}", table_switch[3]);
+    };
+    assert_eq!((*default, *min, *max), (37, 1, 3));
+    assert_eq!(labels, &[28, 31, 34]);
+    assert_eq!(table_switch.len(), 12);
+
+    let lookup_switch = instructions("lookupSwitch");
+    let InsnNode::LookupSwitchInsnNode { default, keys, labels } = &lookup_switch[3] else {
+        panic!("unexpected {:?}", lookup_switch[3]);
+    };
+    assert_eq!(*default, 45);
+    assert_eq!(keys, &[1, 100, 10000]);
+    assert_eq!(labels, &[36, 39, 42]);
+    assert_eq!(lookup_switch.len(), 12);
+}
+
+#[test]
+fn read_node_nop_and_zero_jumps() {
+    let bytes = include_bytes!("../res/bytecode/ConditionTesting.class");
+    let mut jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    // javac never emits `nop`, replace the body of `empty` with `nop; return`.
+    let empty = jvms.methods.last_mut().unwrap();
+    let JvmsAttribute::Code { code_length, code, .. } = &mut empty.attributes[0].info else {
+        panic!("unexpected {:?}", empty.attributes[0].info);
+    };
+    *code_length = 2;
+    *code = vec![Opcodes::NOP, Opcodes::RETURN];
+
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    let instructions = |name: &str| {
+        let method = node.methods.iter().find(|method| &*method.name == name).unwrap();
+        method.code().unwrap().unwrap().instructions.clone()
+    };
+    assert!(matches!(instructions("isZero")[1],
+        InsnNode::JumpInsnNode { opcode: Opcodes::IFEQ, label: 6 }));
+    assert!(matches!(instructions("isNotZero")[1],
+        InsnNode::JumpInsnNode { opcode: Opcodes::IFNE, label: 6 }));
+    assert!(matches!(instructions("empty")[0], InsnNode::NoOperand { opcode: Opcodes::NOP }));
+}
+
+#[test]
+fn read_node_backward_switches() {
+    let bytes = include_bytes!("../res/bytecode/SwitchTesting.class");
+    let mut jvms = JvmsClassReader::read_class_bytes(bytes).unwrap();
+    // replace the body of `tableSwitch` with switches jumping back to earlier instructions.
+    let mut switches = vec![Opcodes::ICONST_0, Opcodes::IRETURN, Opcodes::ILOAD_0, Opcodes::TABLESWITCH];
+    for value in [-3i32, 0, 0, -1] {
+        switches.extend(value.to_be_bytes());
+    }
+    switches.extend([Opcodes::ILOAD_0, Opcodes::LOOKUPSWITCH, 0, 0]);
+    for value in [-19i32, 1, 7, -21] {
+        switches.extend(value.to_be_bytes());
+    }
+    let table_switch = &mut jvms.methods[1];
+    let JvmsAttribute::Code { code_length, code, attributes_count, attributes, .. } =
+        &mut table_switch.attributes[0].info else {
+        panic!("unexpected {:?}", table_switch.attributes[0].info);
+    };
+    *code_length = switches.len() as u32;
+    *code = switches;
+    *attributes_count = 0;
+    attributes.clear();
+
+    let node = ClassNode::from_jvms(jvms).unwrap();
+    let method = node.methods.iter().find(|method| &*method.name == "tableSwitch").unwrap();
+    let instructions = &method.code().unwrap().unwrap().instructions;
+    let InsnNode::TableSwitchInsnNode { default, labels, .. } = &instructions[3] else {
+        panic!("unexpected {:?}", instructions[3]);
+    };
+    assert_eq!((*default, labels.as_slice()), (0, [2].as_slice()));
+    let InsnNode::LookupSwitchInsnNode { default, keys, labels } = &instructions[5] else {
+        panic!("unexpected {:?}", instructions[5]);
+    };
+    assert_eq!((*default, keys.as_slice(), labels.as_slice()), (2, [7].as_slice(), [0].as_slice()));
+}
@@ -0,0 +1,80 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::hierarchy::ClassHierarchy;
+use java_asm::node::element::ClassNode;
+use java_asm::node::InsnNode;
+use java_asm::verify::{Verifier, VerifyMode};
+
+#[test]
+fn write_node_round_trip() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let bytes = node.to_bytes().unwrap();
+    let rewritten = ClassNode::from_bytes(&bytes).unwrap();
+
+    assert_eq!(node.name, rewritten.name);
+    assert_eq!(node.super_name, rewritten.super_name);
+    assert_eq!(node.source_file, rewritten.source_file);
+    assert_eq!(node.fields.len(), rewritten.fields.len());
+    assert_eq!(node.methods.len(), rewritten.methods.len());
+    for (method, rewritten_method) in node.methods.iter().zip(&rewritten.methods) {
+        assert_eq!(method.name, rewritten_method.name);
+        assert_eq!(method.desc, rewritten_method.desc);
+        let code = method.code().unwrap().unwrap();
+        let rewritten_code = rewritten_method.code().unwrap().unwrap();
+        assert_eq!(code.instructions.len(), rewritten_code.instructions.len());
+        assert_eq!(code.line_numbers.len(), rewritten_code.line_numbers.len());
+        assert_eq!(code.local_variables.len(), rewritten_code.local_variables.len());
+        assert_eq!(code.stack_map_table.len(), rewritten_code.stack_map_table.len());
+    }
+    // the same constant pool layout, so writing again gives the same bytes.
+    assert_eq!(bytes, rewritten.to_bytes().unwrap());
+}
+
+#[test]
+fn write_node_stack_map_frames() {
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let main = node.methods.iter_mut().find(|m| m.name.as_ref() == "main").unwrap();
+    let code = main.code_mut().unwrap().unwrap();
+    assert!(!code.stack_map_table.is_empty());
+    // iconst_5 -> bipush 5, one byte longer, so the frames after it move.
+    code.instructions[0] = InsnNode::BIPushInsnNode { operand: 5 };
+
+    let bytes = node.to_bytes().unwrap();
+    let rewritten = ClassNode::from_bytes(&bytes).unwrap();
+    let main = rewritten.methods.iter().find(|m| m.name.as_ref() == "main").unwrap();
+    let code = main.code().unwrap().unwrap();
+    assert_eq!(code.insn_offsets[1], 2);
+    assert_eq!(code.stack_map_table.len(), 2);
+    let hierarchy = ClassHierarchy::new();
+    let errors = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeChecking).verify_class(&rewritten);
+    assert!(errors.is_empty(), "{}", errors[0]);
+}
+
+#[test]
+fn write_node_without_frames() {
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let main = node.methods.iter_mut().find(|m| m.name.as_ref() == "main").unwrap();
+    main.code_mut().unwrap().unwrap().stack_map_table.clear();
+    // the jvm can't verify branches without frames since java 7.
+    assert!(node.major_version >= 51);
+    assert!(node.to_bytes().is_err());
+    node.major_version = 50;
+    assert!(node.to_bytes().is_ok());
+}
+
+#[test]
+fn read_node_keeps_insn_offsets() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let main = node.methods.iter().find(|m| m.name.as_ref() == "main").unwrap();
+    let code = main.code().unwrap().unwrap();
+    assert_eq!(code.instructions.len(), code.insn_offsets.len());
+    assert_eq!(code.insn_offsets[0], 0);
+    assert!(code.insn_offsets.windows(2).all(|w| w[0] < w[1]));
+    assert!(!code.line_numbers.is_empty());
+    // jump targets are always the offset of an instruction.
+    for insn in &code.instructions {
+        if let InsnNode::JumpInsnNode { label, .. } = insn {
+            assert!(code.insn_offsets.contains(label));
+        }
+    }
+}
+
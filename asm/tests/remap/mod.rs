@@ -0,0 +1,2 @@
+mod relocate_test;
+mod remap_test;
@@ -0,0 +1,50 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::node::element::ClassNode;
+use java_asm::remap::{ClassRemapper, Relocation, Relocator, Remapper};
+
+fn relocator() -> Relocator {
+    Relocator::new(vec![
+        Relocation::new("com.google.common", "shaded.guava").exclude("com.google.common.annotations"),
+        Relocation::new("java/lang", "shaded/lang"),
+    ])
+}
+
+#[test]
+fn relocate_names_test() {
+    let relocator = relocator();
+    assert_eq!(relocator.relocate("com/google/common/base/Joiner").as_deref(), Some("shaded/guava/base/Joiner"));
+    assert_eq!(relocator.relocate("com/google/common/annotations/Beta"), None);
+    assert_eq!(relocator.relocate("com/google/commonx/Foo"), None);
+    assert_eq!(
+        relocator.relocate("META-INF/versions/11/com/google/common/Foo.class").as_deref(),
+        Some("META-INF/versions/11/shaded/guava/Foo.class"),
+    );
+    assert_eq!(relocator.map_package_name("com/google/common/base").as_deref(), Some("shaded/guava/base"));
+
+    let map_string = |value: &str| relocator.map_string(value).map(|value| value.to_string());
+    assert_eq!(map_string("com.google.common.base.Joiner").as_deref(), Some("shaded.guava.base.Joiner"));
+    assert_eq!(map_string("/com/google/common/data.bin").as_deref(), Some("/shaded/guava/data.bin"));
+    assert_eq!(map_string("com.google.common is great"), None);
+
+    let services = "# implementations\ncom.google.common.Impl # default\nother.Impl\n";
+    let (path, content) = relocator
+        .relocate_service("META-INF/services/com.google.common.Service", services)
+        .unwrap();
+    assert_eq!(path, "META-INF/services/shaded.guava.Service");
+    assert_eq!(content, "# implementations\nshaded.guava.Impl # default\nother.Impl\n");
+    assert_eq!(relocator.relocate_service("META-INF/services/other.Service", "other.Impl\n"), None);
+}
+
+#[test]
+fn relocate_class_node_test() {
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    ClassRemapper::new(&relocator()).remap(&mut node).unwrap();
+    assert_eq!(&*node.name, "CompileTesting");
+    assert_eq!(node.super_name.as_deref(), Some("shaded/lang/Object"));
+    assert_eq!(&*node.fields[1].desc, "Lshaded/lang/String;");
+    let main = node.methods.iter().find(|m| &*m.name == "main").unwrap();
+    assert_eq!(&*main.desc, "([Lshaded/lang/String;)V");
+
+    let rewritten = ClassNode::from_bytes(&node.to_bytes().unwrap()).unwrap();
+    assert_eq!(rewritten.super_name.as_deref(), Some("shaded/lang/Object"));
+}
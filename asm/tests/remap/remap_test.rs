@@ -0,0 +1,78 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::node::element::ClassNode;
+use java_asm::node::InsnNode;
+use java_asm::remap::{ClassRemapper, Remapper, SimpleRemapper};
+use java_asm::StrRef;
+
+fn remapper() -> SimpleRemapper {
+    let mut remapper = SimpleRemapper::default();
+    remapper.insert("CompileTesting", "demo/Renamed");
+    remapper.insert("java/lang/String", "demo/Text");
+    remapper.insert("CompileTesting.addNumbers(II)I", "sum");
+    remapper.insert("CompileTesting.field1", "count");
+    remapper
+}
+
+#[test]
+fn remap_class_node_test() {
+    let mut node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    ClassRemapper::new(&remapper()).remap(&mut node).unwrap();
+
+    assert_eq!(&*node.name, "demo/Renamed");
+    let fields: Vec<(&str, &str)> = node.fields.iter().map(|f| (&*f.name, &*f.desc)).collect();
+    assert_eq!(fields, [("count", "I"), ("field2", "Ldemo/Text;"), ("field3", "Z")]);
+    let main = node.methods.iter().find(|m| &*m.name == "main").unwrap();
+    assert_eq!(&*main.desc, "([Ldemo/Text;)V");
+    assert!(node.methods.iter().any(|m| &*m.name == "sum" && &*m.desc == "(II)I"));
+
+    let code = main.code().unwrap().unwrap();
+    let calls_sum = code.instructions.iter().any(|insn| matches!(insn,
+        InsnNode::MethodInsnNode { owner, name, .. } if &**owner == "demo/Renamed" && &**name == "sum"));
+    assert!(calls_sum);
+    // the string concatenation is an invokedynamic instruction since java 9
+    let concat = code.instructions.iter().find_map(|insn| match insn {
+        InsnNode::InvokeDynamicInsnNode(indy) => Some(indy),
+        _ => None,
+    }).unwrap();
+    assert_eq!(&*concat.desc, "(I)Ldemo/Text;");
+
+    let init = node.methods.iter().find(|m| &*m.name == "<init>").unwrap();
+    let init_code = init.code().unwrap().unwrap();
+    let put_fields: Vec<(&str, &str)> = init_code.instructions.iter().filter_map(|insn| match insn {
+        InsnNode::FieldInsnNode { owner, name, .. } => Some((&**owner, &**name)),
+        _ => None,
+    }).collect();
+    assert_eq!(put_fields, [("demo/Renamed", "count"), ("demo/Renamed", "field2"), ("demo/Renamed", "field3")]);
+
+    let rewritten = ClassNode::from_bytes(&node.to_bytes().unwrap()).unwrap();
+    assert_eq!(&*rewritten.name, "demo/Renamed");
+    assert_eq!(&*rewritten.fields[0].name, "count");
+    assert_eq!(&*rewritten.fields[1].desc, "Ldemo/Text;");
+}
+
+#[test]
+fn remap_signature_test() {
+    let mut remapper = SimpleRemapper::default();
+    remapper.insert("a/Outer", "b/Moved");
+    remapper.insert("a/Outer$Inner", "b/Moved$Renamed");
+    remapper.insert("a/Value", "b/Data");
+
+    let map = |signature: &str, type_signature: bool| -> String {
+        remapper.map_signature(&StrRef::from(signature), type_signature).unwrap().to_string()
+    };
+    assert_eq!(
+        map("<T:La/Value;U::Ljava/lang/Comparable<-TT;>;>La/Outer<TT;>.Inner<[La/Value;>;", false),
+        "<T:Lb/Data;U::Ljava/lang/Comparable<-TT;>;>Lb/Moved<TT;>.Renamed<[Lb/Data;>;",
+    );
+    assert_eq!(
+        map("<E:Ljava/lang/Exception;>(Ljava/util/List<+La/Value;>;I)La/Outer<*>;^TE;^La/Value;", false),
+        "<E:Ljava/lang/Exception;>(Ljava/util/List<+Lb/Data;>;I)Lb/Moved<*>;^TE;^Lb/Data;",
+    );
+    assert_eq!(map("Ljava/util/Map<La/Value;TT;>;", true), "Ljava/util/Map<Lb/Data;TT;>;");
+    assert!(remapper.map_signature(&StrRef::from("La/Value"), true).is_err());
+
+    let desc = StrRef::from("(La/Value;[La/Outer$Inner;J)La/Value;");
+    assert_eq!(&*remapper.map_desc(&desc).unwrap(), "(Lb/Data;[Lb/Moved$Renamed;J)Lb/Data;");
+    let unchanged = StrRef::from("(I)V");
+    assert!(std::sync::Arc::ptr_eq(&remapper.map_desc(&unchanged).unwrap(), &unchanged));
+}
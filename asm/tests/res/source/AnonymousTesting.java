@@ -0,0 +1,9 @@
+public class AnonymousTesting {
+    Runnable inMethod() {
+        return new Runnable() {
+            @Override
+            public void run() {
+            }
+        };
+    }
+}
@@ -0,0 +1,14 @@
+public class ConditionTesting {
+    static int isZero(int a) {
+        if (a != 0) return 0;
+        return 1;
+    }
+
+    static int isNotZero(int a) {
+        if (a == 0) return 0;
+        return 1;
+    }
+
+    static void empty() {
+    }
+}
@@ -0,0 +1,3 @@
+@Deprecated
+public class DeprecatedTesting {
+}
@@ -0,0 +1,7 @@
+public class FieldAnonymousTesting {
+    Runnable inField = new Runnable() {
+        @Override
+        public void run() {
+        }
+    };
+}
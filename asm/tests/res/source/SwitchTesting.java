@@ -0,0 +1,19 @@
+public class SwitchTesting {
+    static int tableSwitch(int a, int b) {
+        switch (a + b) {
+            case 1: return 10;
+            case 2: return 20;
+            case 3: return 30;
+            default: return 0;
+        }
+    }
+
+    static int lookupSwitch(int a, int b) {
+        switch (a + b) {
+            case 1: return 10;
+            case 100: return 20;
+            case 10000: return 30;
+            default: return 0;
+        }
+    }
+}
@@ -0,0 +1,5 @@
+public class WideConstants {
+    long longField = 1234567890123L;
+    double doubleField = 3.25;
+    String stringField = "after wide constants";
+}
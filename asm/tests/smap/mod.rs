@@ -0,0 +1 @@
+mod smap_test;
@@ -0,0 +1,72 @@
+use java_asm::smali::{stb, SmaliNode};
+use java_asm::smap::{SmapFile, SourceMap};
+use java_asm::StrRef;
+
+/// Written by kotlinc for `Main.kt` which calls the inline `mapOf` of `Maps.kt` at line 10.
+const KOTLIN_SMAP: &str = "\
+SMAP
+Main.kt
+Kotlin
+*S Kotlin
+*F
++ 1 Main.kt
+com/example/MainKt
++ 2 Maps.kt
+kotlin/collections/MapsKt__MapsKt
+*L
+1#1,20:1
+5#2,3:21
+*E
+*S KotlinDebug
+*F
++ 1 Main.kt
+com/example/MainKt
+*L
+10#1:21,3
+*E
+";
+
+#[test]
+fn parse_smap_test() {
+    let source_map = SourceMap::parse(KOTLIN_SMAP).unwrap();
+    assert_eq!(&*source_map.output_file, "Main.kt");
+    assert_eq!(&*source_map.default_stratum, "Kotlin");
+    assert_eq!(source_map.strata.len(), 2);
+    let kotlin = &source_map.strata[0];
+    assert_eq!(kotlin.files[1], SmapFile {
+        id: 2, name: StrRef::from("Maps.kt"), path: Some(StrRef::from("kotlin/collections/MapsKt__MapsKt")),
+    });
+    assert_eq!(kotlin.lines[1].file_id, 2);
+    assert_eq!(source_map.strata[1].lines[0].output_increment, 3);
+
+    assert!(SourceMap::parse("SMAP\nMain.kt\nKotlin\n*L\n1#1:1\n").is_err());
+    assert!(SourceMap::parse("SMAP\nMain.kt\nKotlin\n*S Kotlin\n*L\n1#1:x\n").is_err());
+    assert!(SourceMap::parse("Main.kt").is_err());
+}
+
+#[test]
+fn resolve_lines_test() {
+    let source_map = SourceMap::parse(KOTLIN_SMAP).unwrap();
+    let maps_line = source_map.resolve(23).unwrap();
+    assert_eq!(maps_line.to_string(), "Maps.kt:7");
+    assert_eq!(maps_line.path.as_deref(), Some("kotlin/collections/MapsKt__MapsKt"));
+    assert_eq!(source_map.resolve(3).unwrap().to_string(), "Main.kt:3");
+    assert_eq!(source_map.resolve(24), None);
+    // lines of the class file itself are not worth showing
+    assert_eq!(source_map.resolve_changed(3), None);
+    assert_eq!(source_map.resolve_changed(21).unwrap().to_string(), "Maps.kt:5");
+    // every output line of the call site maps to the same line
+    assert_eq!(source_map.resolve_in("KotlinDebug", 23).unwrap().to_string(), "Main.kt:10");
+    assert_eq!(source_map.resolve_in("Java", 3), None);
+}
+
+#[test]
+fn apply_to_smali_test() {
+    let source_map = SourceMap::parse(KOTLIN_SMAP).unwrap();
+    let source_line = |line: &str| stb().raw(".source-line").other(StrRef::from(line)).s();
+    let mut method = stb().raw(".method").other(StrRef::from("main()V"))
+        .s_with_children(vec![source_line("3"), source_line("22")]);
+    source_map.apply_to_smali(&mut method);
+    let rendered: Vec<String> = method.children.iter().map(|child: &SmaliNode| child.render(0).trim_end().to_string()).collect();
+    assert_eq!(rendered, vec![".source-line 3", ".source-line 22 # Maps.kt:6"]);
+}
@@ -0,0 +1,125 @@
+use crate::dex::read_test::read_test_dex_file;
+use crate::jvms::read_test::read_jvms;
+use java_asm::hierarchy::{ClassHierarchy, HierarchyClass};
+use java_asm::node::element::ClassNode;
+use java_asm::node::InsnNode;
+use java_asm::verify::{Verifier, VerifyErrorKind, VerifyMode, VerifyType};
+use java_asm::{Opcodes, StrRef};
+
+fn compile_testing() -> ClassNode {
+    ClassNode::from_jvms(read_jvms().unwrap()).unwrap()
+}
+
+#[test]
+fn verify_class_test() {
+    let node = compile_testing();
+    let hierarchy = ClassHierarchy::new();
+    for mode in [VerifyMode::Auto, VerifyMode::TypeChecking, VerifyMode::TypeInference] {
+        let errors = Verifier::new(&hierarchy).with_mode(mode).verify_class(&node);
+        assert!(errors.is_empty(), "{mode:?}: {}", errors[0]);
+    }
+}
+
+#[test]
+fn verify_type_mismatch_test() {
+    let mut node = compile_testing();
+    let method = node.methods.iter_mut().find(|m| m.name.as_ref() == "addNumbers").unwrap();
+    let code = method.code_mut().unwrap().unwrap();
+    // iload_0, iload_1, iadd, ireturn
+    code.instructions[1] = InsnNode::NoOperand { opcode: Opcodes::FCONST_0 };
+    let hierarchy = ClassHierarchy::new();
+    let errors = Verifier::new(&hierarchy).verify_class(&node);
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.method.name.as_ref(), "addNumbers");
+    assert_eq!(error.insn_index, 2);
+    assert!(matches!(error.insn, Some(InsnNode::NoOperand { opcode: Opcodes::IADD })));
+    assert_eq!(error.kind, VerifyErrorKind::TypeMismatch { expected: VerifyType::Integer, actual: VerifyType::Float });
+    assert_eq!(error.frame.stack, vec![VerifyType::Integer, VerifyType::Float]);
+}
+
+#[test]
+fn verify_stack_map_test() {
+    let mut node = compile_testing();
+    let method = node.methods.iter_mut().find(|m| m.name.as_ref() == "main").unwrap();
+    method.code_mut().unwrap().unwrap().stack_map_table.clear();
+    let hierarchy = ClassHierarchy::new();
+    let errors = Verifier::new(&hierarchy).verify_class(&node);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0].kind, VerifyErrorKind::MissingFrame { .. }), "{}", errors[0]);
+    // old class files have no stack map frames, the types are inferred instead.
+    let errors = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeInference).verify_class(&node);
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn verify_converted_dex_test() {
+    let accessor = read_test_dex_file();
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_dex(&accessor).unwrap();
+    // converted classes of version 50 have no stack map frames.
+    let verifier = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeInference);
+    for class_def in accessor.file.class_defs.iter() {
+        let node = accessor.get_class_node(*class_def).unwrap();
+        let errors = verifier.verify_class(&node);
+        assert!(errors.is_empty(), "{}", errors[0]);
+    }
+}
+
+#[test]
+fn compute_frames_test() {
+    let mut node = compile_testing();
+    let hierarchy = ClassHierarchy::new();
+    let verifier = Verifier::new(&hierarchy);
+    let method = node.methods.iter().position(|m| m.name.as_ref() == "main").unwrap();
+    let frames = verifier.compute_frames(&node, &node.methods[method]).unwrap();
+    // the loop condition and the return after it.
+    assert_eq!(frames.len(), 2);
+    node.methods[method].code_mut().unwrap().unwrap().stack_map_table = frames;
+    let errors = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeChecking).verify_class(&node);
+    assert!(errors.is_empty(), "{}", errors[0]);
+}
+
+#[test]
+fn compute_frames_of_converted_dex_test() {
+    let accessor = read_test_dex_file();
+    let mut hierarchy = ClassHierarchy::new();
+    hierarchy.add_dex(&accessor).unwrap();
+    let verifier = Verifier::new(&hierarchy);
+    for class_def in accessor.file.class_defs.iter() {
+        let mut node = accessor.get_class_node_with(*class_def, &hierarchy).unwrap();
+        // as if the class needed java 8, then only type checking is allowed.
+        node.major_version = 52;
+        let mut stack_map_tables = vec![];
+        for method in &node.methods {
+            stack_map_tables.push(verifier.compute_frames(&node, method).unwrap());
+        }
+        for (method, stack_map_table) in node.methods.iter_mut().zip(stack_map_tables) {
+            if let Some(code) = method.code_mut().unwrap() {
+                code.stack_map_table = stack_map_table;
+            }
+        }
+        let errors = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeChecking).verify_class(&node);
+        assert!(errors.is_empty(), "{}", errors[0]);
+    }
+}
+
+#[test]
+fn verify_cyclic_hierarchy_test() {
+    let mut hierarchy = ClassHierarchy::new();
+    let classes = [
+        ("a/A", Some("a/B")), ("a/B", Some("a/A")),
+        ("a/C", Some("java/lang/Object")), ("java/lang/Object", None),
+    ];
+    for (name, super_name) in classes {
+        hierarchy.add_class(HierarchyClass {
+            name: StrRef::from(name), access: 0x0001,
+            super_name: super_name.map(StrRef::from), interfaces: vec![],
+        });
+    }
+    let verifier = Verifier::new(&hierarchy);
+    let a = VerifyType::Reference(StrRef::from("a/A"));
+    let c = VerifyType::Reference(StrRef::from("a/C"));
+    assert!(!verifier.is_assignable(&c, &a));
+    assert_eq!(verifier.merge(&a, &c), VerifyType::Reference(StrRef::from("java/lang/Object")));
+}
@@ -0,0 +1 @@
+mod visitor_test;
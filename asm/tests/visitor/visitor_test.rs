@@ -0,0 +1,68 @@
+use crate::jvms::read_test::read_jvms;
+use java_asm::node::element::{ClassNode, LabelNode, LineNumberNode};
+use java_asm::node::InsnNode;
+use java_asm::visitor::{ClassReader, ClassVisitor, MethodVisitor, ReadFlags};
+use java_asm::{DescriptorRef, InternalNameRef, StrRef};
+
+#[derive(Default)]
+struct CountingVisitor {
+    methods: Vec<StrRef>,
+    insns: usize,
+    line_numbers: usize,
+    ended: bool,
+}
+
+struct CountingMethodVisitor<'a>(&'a mut CountingVisitor);
+
+impl ClassVisitor for CountingVisitor {
+    fn visit_method(
+        &mut self, _access: u16, name: StrRef, _desc: DescriptorRef,
+        _signature: Option<StrRef>, _exceptions: Vec<InternalNameRef>,
+    ) -> Option<Box<dyn MethodVisitor + '_>> {
+        self.methods.push(name);
+        Some(Box::new(CountingMethodVisitor(self)))
+    }
+
+    fn visit_end(&mut self) {
+        self.ended = true;
+    }
+}
+
+impl MethodVisitor for CountingMethodVisitor<'_> {
+    fn visit_insn(&mut self, _label: LabelNode, _insn: InsnNode) {
+        self.0.insns += 1;
+    }
+
+    fn visit_line_number(&mut self, _line_number: LineNumberNode) {
+        self.0.line_numbers += 1;
+    }
+}
+
+#[test]
+fn visitor_events_test() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let reader = ClassReader::new(read_jvms().unwrap());
+
+    let mut visitor = CountingVisitor::default();
+    reader.accept(&mut visitor, 0).unwrap();
+    assert!(visitor.ended);
+    let names: Vec<StrRef> = node.methods.iter().map(|m| m.name.clone()).collect();
+    assert_eq!(visitor.methods, names);
+    let code_bodies = node.methods.iter().filter_map(|m| m.code_body.as_ref());
+    let (insns, lines) = code_bodies.map(|code| code.get().unwrap()).fold((0, 0), |(insns, lines), code| {
+        (insns + code.instructions.len(), lines + code.line_numbers.len())
+    });
+    assert_eq!(visitor.insns, insns);
+    assert_eq!(visitor.line_numbers, lines);
+    assert!(lines > 0);
+
+    let mut visitor = CountingVisitor::default();
+    reader.accept(&mut visitor, ReadFlags::SKIP_DEBUG).unwrap();
+    assert_eq!(visitor.insns, insns);
+    assert_eq!(visitor.line_numbers, 0);
+
+    let mut visitor = CountingVisitor::default();
+    reader.accept(&mut visitor, ReadFlags::SKIP_CODE).unwrap();
+    assert_eq!(visitor.methods, names);
+    assert_eq!(visitor.insns, 0);
+}
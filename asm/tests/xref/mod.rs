@@ -0,0 +1 @@
+mod xref_test;
@@ -0,0 +1,52 @@
+use crate::dex::read_test::read_test_dex_file;
+use crate::jvms::read_test::read_jvms;
+use java_asm::node::element::ClassNode;
+use java_asm::xref::{MemberRef, XrefIndex, XrefKind, XrefTarget};
+
+#[test]
+fn xref_from_class_test() {
+    let node = ClassNode::from_jvms(read_jvms().unwrap()).unwrap();
+    let mut index = XrefIndex::new();
+    index.add_class_node(&node).unwrap();
+
+    let main = MemberRef::new("CompileTesting", "main", "([Ljava/lang/String;)V");
+    let add_numbers = MemberRef::new("CompileTesting", "addNumbers", "(II)I");
+    let callers = index.callers(&add_numbers);
+    assert_eq!(callers.len(), 1);
+    assert_eq!(callers[0].from, main);
+
+    let callees = index.callees(&main);
+    assert!(callees.iter().any(|x| x.target == XrefTarget::Method(add_numbers.clone())));
+    assert!(callees.iter().any(|x| matches!(&x.target, XrefTarget::Method(m) if m.name.as_ref() == "makeConcatWithConstants")));
+
+    let field1 = MemberRef::new("CompileTesting", "field1", "I");
+    let writers = index.field_writers(&field1);
+    assert_eq!(writers.len(), 1);
+    assert_eq!(writers[0].from.name.as_ref(), "<init>");
+    assert!(index.field_readers(&field1).is_empty());
+
+    let out = MemberRef::new("java/lang/System", "out", "Ljava/io/PrintStream;");
+    assert_eq!(index.field_readers(&out).len(), 2);
+    assert_eq!(index.string_users("Hello").len(), 1);
+    // the recipe of the string concatenation is a bootstrap argument.
+    assert!(index.xrefs().iter().any(|x| matches!(&x.target, XrefTarget::String(s) if s.starts_with("Result: "))));
+    assert!(index.class_users("java/io/PrintStream").iter().all(|x| x.kind == XrefKind::Call));
+}
+
+#[test]
+fn xref_from_dex_test() {
+    let accessor = read_test_dex_file();
+    let mut index = XrefIndex::new();
+    index.add_dex(&accessor).unwrap();
+    assert!(!index.is_empty());
+    for xref in index.xrefs() {
+        assert!(index.usages(&xref.target).contains(&xref));
+        assert!(index.references_from(&xref.from).contains(&xref));
+        if let XrefTarget::Class(class) = &xref.target {
+            assert!(!class.starts_with('['));
+        }
+        if let Some(class) = xref.target.class() {
+            assert!(index.class_users(class).contains(&xref));
+        }
+    }
+}
@@ -4,21 +4,37 @@ mod render;
 compile_error!("java_asm_cli is a native-only executable");
 
 use clap::{Args, Parser, Subcommand, ValueEnum, ValueHint};
-use java_asm::AsmErr;
+use java_asm::{AsmErr, ConstContainer, JavaClassAccessFlags, JavaFieldAccessFlags, JavaMethodAccessFlags};
 use java_asm::StrRef;
 use java_asm::dex::{ClassDef, DexFile, DexFileAccessor};
+use java_asm::compat::{ApiSignatures, ClassCompat, CompatIssue};
+use java_asm::android::{
+    annotate_resource_ids, extract_dex_files, is_oat_bytes, is_vdex_bytes, signing_block_name, to_hex,
+    AndroidManifest, ApkSignatures, ResourceTable, XmlDocument,
+};
+use java_asm::binary_compat::{ApiChangeKind, ApiReport, Severity};
+use java_asm::diff::{ClassChange, ClassDiff, ClassSetDiff, DiffKind, MemberChange, MemberDiff};
+use java_asm::hierarchy::{ClassHierarchy, HierarchyClass, HierarchyResolver};
+use java_asm::kotlin::KotlinMetadata;
+use java_asm::mapping::{MappingTree, TreeRemapper};
 use java_asm::node::element::ClassNode;
-use java_asm_server::fuzzy::FuzzyMatchModel;
+use java_asm::remap::{remap_smali, ClassRemapper, Relocation, Relocator, Remapper};
+use java_asm::xref::{MemberRef, Xref, XrefIndex, XrefKind, XrefTarget};
+use java_asm_server::fuzzy::{member_search_key, FuzzyMatchModel};
 use serde_json::{Map, Value, json};
 use std::fmt::{Display, Formatter};
 use std::fs;
-use std::io::{Cursor, Read};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use zip::ZipArchive;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
 
-use crate::render::render_jvm_class;
+use crate::render::{java_flag_names, render_jvm_class};
 
 pub const DEFAULT_OUTPUT_DIR: &str = "asm_cli_output";
 const MAX_ARCHIVE_DEPTH: usize = 8;
@@ -59,7 +75,7 @@ impl std::error::Error for CliError {}
     name = "java_asm_cli",
     version,
     about = "Find and export classes from Java and Android bytecode",
-    after_help = "Find commands emit JSON. export-class writes Smali to stdout unless --output is provided.\n\nExamples:\n  java_asm_cli find-classes app.apks com.example.Main\n  java_asm_cli export-class app.apks com.example.Main --internal-path base.apk!classes2.dex\n  java_asm_cli export-all app.apk --class-filter com.example --output exported",
+    after_help = "Find commands emit JSON. export-class writes Smali to stdout unless --output is provided.\n\nExamples:\n  java_asm_cli find-classes app.apks com.example.Main\n  java_asm_cli export-class app.apks com.example.Main --internal-path base.apk!classes2.dex\n  java_asm_cli export-all app.apk --class-filter com.example --output exported\n  java_asm_cli to-jar app.apk --output app.jar\n  java_asm_cli hierarchy app.apk com.example.Base\n  java_asm_cli find-usages app.apk com.example.Main --member run\n  java_asm_cli find-members app.apk Main.onCreate\n  java_asm_cli find-classes app.apk com.example.Main --mapping mapping.txt\n  java_asm_cli relocate lib.jar --relocation com.google.common=shaded.guava --output shaded.jar\n  java_asm_cli check-compat lib.jar --release 8 --api java8.txt\n  java_asm_cli diff app-1.0.apk app-1.1.apk --class-filter com.example\n  java_asm_cli check-api lib-1.0.jar lib-1.1.jar --fail-on-breaking\n  java_asm_cli manifest app.apk --xml\n  java_asm_cli verify-signature app.apk --fail-on-unverified",
     arg_required_else_help = true,
     propagate_version = true
 )]
@@ -85,6 +101,44 @@ enum Commands {
         about = "Export all classes matching an optional filter"
     )]
     ExportAll(ExportAllArgs),
+    #[command(
+        visible_alias = "toJar",
+        about = "Convert all classes, including DEX ones, into a JAR"
+    )]
+    ToJar(ToJarArgs),
+    #[command(about = "Show super types and sub types of one class")]
+    Hierarchy(HierarchyArgs),
+    #[command(
+        visible_alias = "findUsages",
+        about = "Find the code which references a class, a member or a string"
+    )]
+    FindUsages(FindUsagesArgs),
+    #[command(
+        visible_alias = "findMembers",
+        about = "Find methods and fields by a fuzzy name"
+    )]
+    FindMembers(FindMembersArgs),
+    #[command(about = "Move packages of a JAR into other packages, like shading")]
+    Relocate(RelocateArgs),
+    #[command(
+        visible_alias = "checkCompat",
+        about = "Check that classes run on a Java release and only use its API"
+    )]
+    CheckCompat(CheckCompatArgs),
+    #[command(about = "Compare the classes, members and method code of two inputs")]
+    Diff(DiffArgs),
+    #[command(
+        visible_alias = "checkApi",
+        about = "Find the API changes of a library which break code compiled against the old version"
+    )]
+    CheckApi(CheckApiArgs),
+    #[command(about = "Decode the binary AndroidManifest.xml of APKs")]
+    Manifest(ManifestArgs),
+    #[command(
+        visible_alias = "verifySignature",
+        about = "Verify the v1 to v4 signatures of APKs and show their signers"
+    )]
+    VerifySignature(VerifySignatureArgs),
 }
 
 #[derive(Debug, Args)]
@@ -95,6 +149,9 @@ struct FindClassesArgs {
     /// Dotted name, slash-separated name, or descriptor. Omit to list every class.
     #[arg(value_name = "QUERY")]
     query: Option<String>,
+    /// Mapping file (ProGuard / R8, Tiny v2, SRG, TSRG or Enigma), classes and members are shown with their original names.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    mapping: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -114,6 +171,9 @@ struct ExportClassArgs {
     /// Export representation. Additional formats may be added in the future.
     #[arg(long, value_enum, default_value_t = ExportFormat::Smali)]
     format: ExportFormat,
+    /// Mapping file (ProGuard / R8, Tiny v2, SRG, TSRG or Enigma), classes and members are shown with their original names.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    mapping: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -136,6 +196,151 @@ struct ExportAllArgs {
     /// Export representation. Additional formats may be added in the future.
     #[arg(long, value_enum, default_value_t = ExportFormat::Smali)]
     format: ExportFormat,
+    /// Mapping file (ProGuard / R8, Tiny v2, SRG, TSRG or Enigma), classes and members are shown with their original names.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    mapping: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct ToJarArgs {
+    /// APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Fuzzy class-name filter. Omit to convert every class.
+    #[arg(long, alias = "filter", value_name = "QUERY")]
+    class_filter: Option<String>,
+    /// The JAR file to write.
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct RelocateArgs {
+    /// JAR or ZIP file with class files.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Package and its new package, for example com.google.common=shaded.guava. Repeat for more packages.
+    #[arg(short, long = "relocation", value_name = "FROM=TO", required = true, value_parser = parse_relocation)]
+    relocations: Vec<(String, String)>,
+    /// Package or class which is not moved, for example com.google.common.annotations.
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+    /// The JAR file to write.
+    #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct CheckCompatArgs {
+    /// APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// The Java release which the classes must run on, for example 8.
+    #[arg(long, value_name = "N")]
+    release: u16,
+    /// API signature file, one class or member per line. References into its packages must exist in it.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    api: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct DiffArgs {
+    /// The old APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "OLD", value_hint = ValueHint::FilePath)]
+    old: PathBuf,
+    /// The new input, which may be of another kind than the old one.
+    #[arg(value_name = "NEW", value_hint = ValueHint::FilePath)]
+    new: PathBuf,
+    /// Fuzzy class-name filter. Omit to compare every class.
+    #[arg(long, alias = "filter", value_name = "QUERY")]
+    class_filter: Option<String>,
+    /// Only count the changed instructions of methods instead of listing them.
+    #[arg(long)]
+    no_code: bool,
+}
+
+#[derive(Debug, Args)]
+struct CheckApiArgs {
+    /// The old version of the library: JAR, APK, DEX, class file, or another supported input.
+    #[arg(value_name = "OLD", value_hint = ValueHint::FilePath)]
+    old: PathBuf,
+    /// The new version of the library.
+    #[arg(value_name = "NEW", value_hint = ValueHint::FilePath)]
+    new: PathBuf,
+    /// Fuzzy class-name filter. Omit to check every class.
+    #[arg(long, alias = "filter", value_name = "QUERY")]
+    class_filter: Option<String>,
+    /// Exit with status 2 if a change breaks existing callers, for release builds.
+    #[arg(long)]
+    fail_on_breaking: bool,
+}
+
+#[derive(Debug, Args)]
+struct ManifestArgs {
+    /// APK, APKS, XAPK, or a binary AndroidManifest.xml.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Also include the manifest as text XML.
+    #[arg(long)]
+    xml: bool,
+}
+
+#[derive(Debug, Args)]
+struct VerifySignatureArgs {
+    /// APK, or APKS and XAPK whose APKs are verified one by one.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// The v4 signature file. Defaults to INPUT.idsig if it exists.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    idsig: Option<PathBuf>,
+    /// Exit with status 2 if an APK is unsigned or a signature doesn't verify.
+    #[arg(long)]
+    fail_on_unverified: bool,
+}
+
+#[derive(Debug, Args)]
+struct HierarchyArgs {
+    /// APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Exact dotted name, slash-separated name, or descriptor.
+    #[arg(value_name = "CLASS")]
+    class_name: String,
+}
+
+#[derive(Debug, Args)]
+struct FindUsagesArgs {
+    /// APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Exact dotted name, slash-separated name, or descriptor of the referenced class.
+    #[arg(value_name = "CLASS", required_unless_present = "string")]
+    class_name: Option<String>,
+    /// Only find usages of the fields and methods with this name in CLASS.
+    #[arg(long, value_name = "NAME", conflicts_with = "string")]
+    member: Option<String>,
+    /// Member descriptor, for example (I)V or Ljava/lang/String;. Omit to match all overloads.
+    #[arg(long, value_name = "DESC", requires = "member")]
+    desc: Option<String>,
+    /// Find usages of this string constant instead of a class.
+    #[arg(long, value_name = "VALUE", conflicts_with = "class_name")]
+    string: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct FindMembersArgs {
+    /// APK, APKS, DEX, JAR, ZIP, class file, or another supported input.
+    #[arg(value_name = "INPUT", value_hint = ValueHint::FilePath)]
+    input: PathBuf,
+    /// Member name, optionally prefixed by its class, for example onCreate or MainActivity.onCreate.
+    #[arg(value_name = "QUERY")]
+    query: String,
+    /// Maximum count of returned members.
+    #[arg(long, default_value_t = 50, value_name = "COUNT")]
+    limit: usize,
+    /// Mapping file (ProGuard / R8, Tiny v2, SRG, TSRG or Enigma), classes and members are shown with their original names.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    mapping: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -159,6 +364,8 @@ impl ExportFormat {
 pub enum CliOutput {
     Json(Value),
     Text(String),
+    /// A JSON result of a check which failed, the process exits with status 2.
+    Failed(Value),
 }
 
 enum ClassPayload {
@@ -176,11 +383,20 @@ struct ClassEntry {
     descriptor: String,
     internal_path: Option<String>,
     payload: ClassPayload,
+    /// The name in the input when [ClassEntry::internal_name] is renamed by a mapping.
+    obfuscated_name: Option<String>,
+    mapping: Option<Arc<TreeRemapper>>,
+    /// Resource tables of the input, resource ids in smali are annotated with their names.
+    resources: Option<Arc<Vec<ResourceTable>>>,
 }
 
 #[derive(Default)]
 struct InputIndex {
     classes: Vec<ClassEntry>,
+    /// The `resources.arsc` tables of all APKs in the input.
+    resource_tables: Vec<ResourceTable>,
+    /// Internal paths of the compact dex files in VDEX or OAT files, which can't be read yet.
+    skipped_compact_dex: Vec<String>,
 }
 
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
@@ -200,33 +416,49 @@ pub fn execute(cli: Cli) -> Result<CliOutput, CliError> {
         Commands::FindClasses(args) => execute_find_classes(args),
         Commands::ExportClass(args) => execute_export_class(args),
         Commands::ExportAll(args) => execute_export_all(args),
+        Commands::ToJar(args) => execute_to_jar(args),
+        Commands::Hierarchy(args) => execute_hierarchy(args),
+        Commands::FindUsages(args) => execute_find_usages(args),
+        Commands::FindMembers(args) => execute_find_members(args),
+        Commands::Relocate(args) => execute_relocate(args),
+        Commands::CheckCompat(args) => execute_check_compat(args),
+        Commands::Diff(args) => execute_diff(args),
+        Commands::CheckApi(args) => execute_check_api(args),
+        Commands::Manifest(args) => execute_manifest(args),
+        Commands::VerifySignature(args) => execute_verify_signature(args),
     }
 }
 
 fn execute_find_classes(args: FindClassesArgs) -> Result<CliOutput, CliError> {
-    let index = InputIndex::load(&args.input, None)?;
+    let mut index = InputIndex::load(&args.input, None)?;
+    index.apply_mapping(args.mapping.as_deref())?;
     let query = args.query.unwrap_or_default();
     let classes = find_matching_classes(&index, &query)
         .into_iter()
         .map(|entry| entry.to_json())
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(CliOutput::Json(json!({
+    let mut result = json!({
         "ok": true,
         "operation": "findClasses",
         "input": args.input,
         "query": query,
         "count": classes.len(),
         "classes": classes,
-    })))
+    });
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
 }
 
 fn execute_export_class(args: ExportClassArgs) -> Result<CliOutput, CliError> {
-    let index = InputIndex::load(&args.input, args.internal_path.as_deref())?;
+    let mut index = InputIndex::load(&args.input, args.internal_path.as_deref())?;
+    index.apply_mapping(args.mapping.as_deref())?;
     let expected = normalize_class_name(&args.class_name);
     let matches: Vec<&ClassEntry> = index
         .classes
         .iter()
-        .filter(|entry| entry.internal_name == expected)
+        .filter(|entry| {
+            entry.internal_name == expected || entry.obfuscated_name.as_deref() == Some(&expected)
+        })
         .collect();
     let entry = match matches.as_slice() {
         [] => {
@@ -275,61 +507,1118 @@ fn execute_export_class(args: ExportClassArgs) -> Result<CliOutput, CliError> {
         ("output".to_owned(), json!(output)),
     ]);
     insert_internal_path(&mut result, entry.internal_path.as_deref());
-    Ok(CliOutput::Json(Value::Object(result)))
+    let mut result = Value::Object(result);
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn execute_export_all(args: ExportAllArgs) -> Result<CliOutput, CliError> {
+    let mut index = InputIndex::load(&args.input, None)?;
+    index.apply_mapping(args.mapping.as_deref())?;
+    let filter = args.class_filter.as_deref().unwrap_or_default();
+    let selected = find_matching_classes(&index, filter);
+    if selected.is_empty() {
+        return Err(CliError::NotFound(format!(
+            "no classes matched filter: {filter}"
+        )));
+    }
+    fs::create_dir_all(&args.output).map_err(|source| CliError::Io {
+        path: args.output.clone(),
+        source,
+    })?;
+    let mut classes = Vec::with_capacity(selected.len());
+    for entry in selected {
+        let output = class_output_path(&args.output, entry, args.format);
+        let content = entry.render(args.format)?;
+        write_file(&output, content.as_bytes())?;
+        let mut class = Map::from_iter([
+            (
+                "class_name".to_owned(),
+                Value::String(entry.qualified_name()),
+            ),
+            (
+                "descriptor".to_owned(),
+                Value::String(entry.descriptor.clone()),
+            ),
+            ("output".to_owned(), json!(output)),
+        ]);
+        insert_internal_path(&mut class, entry.internal_path.as_deref());
+        classes.push(Value::Object(class));
+    }
+    let mut manifest = json!({
+        "ok": true,
+        "operation": "exportAll",
+        "input": args.input,
+        "class_filter": args.class_filter,
+        "format": args.format.as_str(),
+        "output_dir": args.output,
+        "count": classes.len(),
+        "classes": classes,
+    });
+    index.report_skipped(&mut manifest, "skipped_compact_dex");
+    let manifest_path = args.output.join("manifest.json");
+    let bytes = serde_json::to_vec_pretty(&manifest).map_err(|error| CliError::Parse {
+        source: "manifest.json".to_owned(),
+        message: format!("serialize manifest: {error}"),
+    })?;
+    write_file(&manifest_path, &bytes)?;
+    Ok(CliOutput::Json(manifest))
+}
+
+fn execute_to_jar(args: ToJarArgs) -> Result<CliOutput, CliError> {
+    let index = InputIndex::load(&args.input, None)?;
+    let filter = args.class_filter.as_deref().unwrap_or_default();
+    let selected = find_matching_classes(&index, filter);
+    if selected.is_empty() {
+        return Err(CliError::NotFound(format!(
+            "no classes matched filter: {filter}"
+        )));
+    }
+    let zip_error = |error: ZipError| CliError::Zip {
+        path: args.output.clone(),
+        message: error.to_string(),
+    };
+    let hierarchy = index.hierarchy();
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut written = HashSet::new();
+    let mut failures = Vec::new();
+    for entry in selected {
+        let entry_name = format!("{}.class", entry.internal_name);
+        let converted = if written.contains(&entry_name) {
+            Err("duplicate class, the first one is kept".to_owned())
+        } else {
+            entry.to_class_bytes(&hierarchy)
+        };
+        let bytes = match converted {
+            Ok(bytes) => bytes,
+            Err(message) => {
+                let mut failure = Map::from_iter([
+                    (
+                        "class_name".to_owned(),
+                        Value::String(entry.qualified_name()),
+                    ),
+                    ("error".to_owned(), Value::String(message)),
+                ]);
+                insert_internal_path(&mut failure, entry.internal_path.as_deref());
+                failures.push(Value::Object(failure));
+                continue;
+            }
+        };
+        writer
+            .start_file(entry_name.as_str(), SimpleFileOptions::default())
+            .map_err(zip_error)?;
+        writer.write_all(&bytes).map_err(|source| CliError::Io {
+            path: args.output.clone(),
+            source,
+        })?;
+        written.insert(entry_name);
+    }
+    let bytes = writer.finish().map_err(zip_error)?.into_inner();
+    write_file(&args.output, &bytes)?;
+    let mut result = json!({
+        "ok": true,
+        "operation": "toJar",
+        "input": args.input,
+        "class_filter": args.class_filter,
+        "output": args.output,
+        "count": written.len(),
+        "failed_count": failures.len(),
+        "failures": failures,
+    });
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn execute_hierarchy(args: HierarchyArgs) -> Result<CliOutput, CliError> {
+    let index = InputIndex::load(&args.input, None)?;
+    let hierarchy = index.hierarchy();
+    let name = normalize_class_name(&args.class_name);
+    if !hierarchy.contains(&name) {
+        return Err(CliError::NotFound(format!(
+            "class not found: {}",
+            args.class_name
+        )));
+    }
+    let qualified_names = |names: &[StrRef]| -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|n| n.replace('/', ".")).collect();
+        names.sort();
+        names
+    };
+    let mut super_classes = Vec::new();
+    let mut current = hierarchy.super_name(&name);
+    while let Some(super_name) = current {
+        // malformed inputs may contain inheritance cycles.
+        if super_classes.len() > hierarchy.len() {
+            break;
+        }
+        super_classes.push(super_name.replace('/', "."));
+        current = hierarchy.super_name(super_name);
+    }
+    let interfaces: Vec<StrRef> = hierarchy
+        .all_supertypes(&name)
+        .into_iter()
+        .filter(|super_type| hierarchy.is_interface(super_type))
+        .collect();
+    let mut result = json!({
+        "ok": true,
+        "operation": "hierarchy",
+        "input": args.input,
+        "class_name": name.replace('/', "."),
+        "interface": hierarchy.is_interface(&name),
+        "super_classes": super_classes,
+        "interfaces": qualified_names(&interfaces),
+        "direct_subclasses": qualified_names(hierarchy.direct_subclasses(&name)),
+        "direct_implementors": qualified_names(hierarchy.direct_implementors(&name)),
+        "all_subtypes": qualified_names(&hierarchy.all_subtypes(&name)),
+    });
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn execute_find_usages(args: FindUsagesArgs) -> Result<CliOutput, CliError> {
+    let index = InputIndex::load(&args.input, None)?;
+    let xref = index.xref();
+    let (target, usages) = match (&args.string, &args.class_name) {
+        (Some(value), _) => (json!({ "string": value }), xref.string_users(value)),
+        (None, Some(class_name)) => {
+            let name = normalize_class_name(class_name);
+            let usages = xref.class_users(&name).into_iter().filter(|usage| {
+                let member = match &usage.target {
+                    XrefTarget::Method(member) | XrefTarget::Field(member) => Some(member),
+                    _ => None,
+                };
+                match (&args.member, member) {
+                    (None, _) => true,
+                    (Some(_), None) => false,
+                    (Some(name), Some(member)) => {
+                        *member.name == **name
+                            && args.desc.as_deref().is_none_or(|desc| *member.desc == *desc)
+                    }
+                }
+            });
+            let target = json!({
+                "class_name": name.replace('/', "."),
+                "member": args.member,
+                "descriptor": args.desc,
+            });
+            (target, usages.collect())
+        }
+        (None, None) => unreachable!("clap requires CLASS or --string"),
+    };
+    let usages: Vec<Value> = usages.into_iter().map(usage_to_json).collect();
+    let mut result = json!({
+        "ok": true,
+        "operation": "findUsages",
+        "input": args.input,
+        "target": target,
+        "count": usages.len(),
+        "usages": usages,
+    });
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn execute_find_members(args: FindMembersArgs) -> Result<CliOutput, CliError> {
+    let mut index = InputIndex::load(&args.input, None)?;
+    index.apply_mapping(args.mapping.as_deref())?;
+    let mut members: Vec<(&ClassEntry, MemberRef, &str)> = Vec::new();
+    for entry in &index.classes {
+        let (methods, fields) = entry.members()?;
+        for method in methods {
+            let member = MemberRef::new(&entry.internal_name, &method.name, &method.signature);
+            members.push((entry, member, "method"));
+        }
+        for field in fields {
+            let member = MemberRef::new(&entry.internal_name, &field.name, &field.field_type);
+            members.push((entry, member, "field"));
+        }
+    }
+    let keys: Vec<StrRef> = members.iter().map(|(_, member, _)| member_search_key(member)).collect();
+    let mut model = FuzzyMatchModel::new_for_members("".into(), &keys, args.limit);
+    let result = model.search_with_new_input(args.query.as_str().into());
+    let matched: Vec<Value> = result
+        .items
+        .into_iter()
+        .filter_map(|item| members.get(item.index))
+        .map(|(entry, member, kind)| {
+            let mut value = Map::from_iter([
+                (
+                    "class_name".to_owned(),
+                    Value::String(entry.qualified_name()),
+                ),
+                ("name".to_owned(), json!(member.name.as_ref())),
+                ("descriptor".to_owned(), json!(member.desc.as_ref())),
+                ("kind".to_owned(), json!(kind)),
+            ]);
+            insert_internal_path(&mut value, entry.internal_path.as_deref());
+            Value::Object(value)
+        })
+        .collect();
+    let mut result = json!({
+        "ok": true,
+        "operation": "findMembers",
+        "input": args.input,
+        "query": args.query,
+        "count": matched.len(),
+        "members": matched,
+    });
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn execute_relocate(args: RelocateArgs) -> Result<CliOutput, CliError> {
+    let bytes = fs::read(&args.input).map_err(|source| CliError::Io {
+        path: args.input.clone(),
+        source,
+    })?;
+    let relocations = args
+        .relocations
+        .iter()
+        .map(|(pattern, shaded)| {
+            args.exclude
+                .iter()
+                .fold(Relocation::new(pattern, shaded), |relocation, exclude| relocation.exclude(exclude))
+        })
+        .collect();
+    let relocator = Relocator::new(relocations);
+    let relocated = relocate_jar(&args.input, bytes, &relocator)?;
+    write_file(&args.output, &relocated.jar)?;
+    Ok(CliOutput::Json(json!({
+        "ok": true,
+        "operation": "relocate",
+        "input": args.input,
+        "output": args.output,
+        "relocations": args.relocations.iter().map(|(pattern, shaded)| json!({
+            "pattern": pattern,
+            "shaded": shaded,
+        })).collect::<Vec<_>>(),
+        "exclude": args.exclude,
+        "count": relocated.count,
+        "relocated_count": relocated.relocated_count,
+        "failed_count": relocated.failures.len(),
+        "failures": relocated.failures,
+    })))
+}
+
+fn execute_check_compat(args: CheckCompatArgs) -> Result<CliOutput, CliError> {
+    let index = InputIndex::load(&args.input, None)?;
+    let api = match &args.api {
+        Some(path) => {
+            let content = fs::read_to_string(path).map_err(|source| CliError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            let api = ApiSignatures::parse(&content)
+                .map_err(|error| parse_error(&path.display().to_string(), error))?;
+            Some(api)
+        }
+        None => None,
+    };
+    let mut result = check_compat(&index, args.release, api.as_ref())?;
+    result.insert("input".to_owned(), json!(args.input));
+    result.insert("api".to_owned(), json!(args.api));
+    let mut result = Value::Object(result);
+    index.report_skipped(&mut result, "skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+/// Checks every class against `release` and `api`, only classes with issues are listed.
+/// Dex classes have no class file version, so only their API references are checked.
+fn check_compat(
+    index: &InputIndex,
+    release: u16,
+    api: Option<&ApiSignatures>,
+) -> Result<Map<String, Value>, CliError> {
+    let mut versions: BTreeMap<String, usize> = BTreeMap::new();
+    let mut classes = Vec::new();
+    let mut issue_count = 0;
+    for entry in &index.classes {
+        let mut value = Map::from_iter([(
+            "class_name".to_owned(),
+            Value::String(entry.qualified_name()),
+        )]);
+        insert_internal_path(&mut value, entry.internal_path.as_deref());
+        let mut issues = Vec::new();
+        let mut xref = XrefIndex::new();
+        match &entry.payload {
+            ClassPayload::Jvm { node } => {
+                let compat = ClassCompat::from_class_node(node)
+                    .map_err(|error| parse_error(entry.input_name(), error))?;
+                *versions.entry(compat.version_string()).or_default() += 1;
+                value.insert("version".to_owned(), json!(compat.version_string()));
+                value.insert("release".to_owned(), json!(compat.release()));
+                value.insert("preview".to_owned(), json!(compat.is_preview()));
+                let features: Vec<&str> = compat.features.iter().map(|feature| feature.name()).collect();
+                value.insert("features".to_owned(), json!(features));
+                issues.extend(compat.check_release(release).iter().map(|issue| compat_issue_to_json(issue, None)));
+                if api.is_some() {
+                    xref.add_class_node(node)
+                        .map_err(|error| parse_error(entry.input_name(), error))?;
+                }
+            }
+            ClassPayload::Dex {
+                accessor,
+                class_def,
+            } => {
+                if api.is_some() {
+                    xref.add_class_def(accessor, class_def)
+                        .map_err(|error| parse_error(entry.input_name(), error))?;
+                }
+            }
+        }
+        if let Some(api) = api {
+            for usage in xref.xrefs() {
+                if let Some(issue) = api.check(usage) {
+                    issues.push(compat_issue_to_json(&issue, Some(usage)));
+                }
+            }
+        }
+        if issues.is_empty() {
+            continue;
+        }
+        issue_count += issues.len();
+        value.insert("issues".to_owned(), Value::Array(issues));
+        classes.push(Value::Object(value));
+    }
+    Ok(Map::from_iter([
+        ("ok".to_owned(), json!(true)),
+        ("operation".to_owned(), json!("checkCompat")),
+        ("release".to_owned(), json!(release)),
+        ("compatible".to_owned(), json!(issue_count == 0)),
+        ("class_count".to_owned(), json!(index.classes.len())),
+        ("versions".to_owned(), json!(versions)),
+        ("issue_count".to_owned(), json!(issue_count)),
+        ("classes".to_owned(), Value::Array(classes)),
+    ]))
+}
+
+fn compat_issue_to_json(issue: &CompatIssue, usage: Option<&Xref>) -> Value {
+    let kind = match issue {
+        CompatIssue::ClassVersion { .. } => "class_version",
+        CompatIssue::Preview => "preview",
+        CompatIssue::Feature(_) => "feature",
+        CompatIssue::MissingClass(_) => "missing_class",
+        CompatIssue::MissingMember(_) => "missing_member",
+    };
+    let mut value = Map::from_iter([
+        ("kind".to_owned(), json!(kind)),
+        ("message".to_owned(), json!(issue.to_string())),
+    ]);
+    if let Some(usage) = usage {
+        value.insert("method".to_owned(), json!(usage.from.name.as_ref()));
+        value.insert("descriptor".to_owned(), json!(usage.from.desc.as_ref()));
+        value.insert("offset".to_owned(), json!(usage.offset));
+    }
+    Value::Object(value)
+}
+
+fn execute_diff(args: DiffArgs) -> Result<CliOutput, CliError> {
+    let old = InputIndex::load(&args.old, None)?;
+    let new = InputIndex::load(&args.new, None)?;
+    let filter = args.class_filter.as_deref().unwrap_or_default();
+    let mut result = diff_inputs(&old, &new, filter, !args.no_code)?;
+    result.insert("old".to_owned(), json!(args.old));
+    result.insert("new".to_owned(), json!(args.new));
+    result.insert("class_filter".to_owned(), json!(args.class_filter));
+    let mut result = Value::Object(result);
+    old.report_skipped(&mut result, "old_skipped_compact_dex");
+    new.report_skipped(&mut result, "new_skipped_compact_dex");
+    Ok(CliOutput::Json(result))
+}
+
+fn matching_class_nodes(index: &InputIndex, filter: &str) -> Result<Vec<Arc<ClassNode>>, CliError> {
+    find_matching_classes(index, filter)
+        .into_iter()
+        .map(ClassEntry::class_node)
+        .collect()
+}
+
+/// Compares the classes matching `filter`, DEX classes are converted to class nodes first so
+/// DEX and JVM inputs can be compared with each other.
+fn diff_inputs(
+    old: &InputIndex,
+    new: &InputIndex,
+    filter: &str,
+    with_code: bool,
+) -> Result<Map<String, Value>, CliError> {
+    let old_nodes = matching_class_nodes(old, filter)?;
+    let new_nodes = matching_class_nodes(new, filter)?;
+    let diff = ClassSetDiff::new(
+        old_nodes.iter().map(Arc::as_ref),
+        new_nodes.iter().map(Arc::as_ref),
+    )
+    .map_err(|error| parse_error("method code", error))?;
+    let qualified_names = |names: &[StrRef]| -> Vec<String> {
+        names.iter().map(|name| name.replace('/', ".")).collect()
+    };
+    let changed: Vec<Value> = diff
+        .changed
+        .iter()
+        .map(|class| class_diff_to_json(class, with_code))
+        .collect();
+    Ok(Map::from_iter([
+        ("ok".to_owned(), json!(true)),
+        ("operation".to_owned(), json!("diff")),
+        ("identical".to_owned(), json!(diff.is_empty())),
+        ("added_count".to_owned(), json!(diff.added.len())),
+        ("removed_count".to_owned(), json!(diff.removed.len())),
+        ("changed_count".to_owned(), json!(diff.changed.len())),
+        ("unchanged_count".to_owned(), json!(diff.unchanged_count)),
+        ("added".to_owned(), json!(qualified_names(&diff.added))),
+        ("removed".to_owned(), json!(qualified_names(&diff.removed))),
+        ("changed".to_owned(), Value::Array(changed)),
+    ]))
+}
+
+fn execute_check_api(args: CheckApiArgs) -> Result<CliOutput, CliError> {
+    let old = InputIndex::load(&args.old, None)?;
+    let new = InputIndex::load(&args.new, None)?;
+    let filter = args.class_filter.as_deref().unwrap_or_default();
+    let mut result = check_api(&old, &new, filter)?;
+    let compatible = result["compatible"] == true;
+    result.insert("old".to_owned(), json!(args.old));
+    result.insert("new".to_owned(), json!(args.new));
+    result.insert("class_filter".to_owned(), json!(args.class_filter));
+    let mut result = Value::Object(result);
+    old.report_skipped(&mut result, "old_skipped_compact_dex");
+    new.report_skipped(&mut result, "new_skipped_compact_dex");
+    if args.fail_on_breaking && !compatible {
+        Ok(CliOutput::Failed(result))
+    } else {
+        Ok(CliOutput::Json(result))
+    }
+}
+
+/// Checks the public API of the classes matching `filter`, changes are sorted by class name.
+fn check_api(old: &InputIndex, new: &InputIndex, filter: &str) -> Result<Map<String, Value>, CliError> {
+    let old_nodes = matching_class_nodes(old, filter)?;
+    let new_nodes = matching_class_nodes(new, filter)?;
+    let report = ApiReport::new(
+        old_nodes.iter().map(Arc::as_ref),
+        new_nodes.iter().map(Arc::as_ref),
+    );
+    let breaking_count = report.breaking_count();
+    let changes: Vec<Value> = report
+        .changes
+        .iter()
+        .map(|change| {
+            let severity = match change.severity() {
+                Severity::Breaking => "breaking",
+                Severity::Behavioral => "behavioral",
+            };
+            json!({
+                "severity": severity,
+                "kind": api_change_kind_name(&change.kind),
+                "class_name": change.class.replace('/', "."),
+                "member": change.member.as_ref().map(|member| member.name.as_ref()),
+                "descriptor": change.member.as_ref().map(|member| member.desc.as_ref()),
+                "message": change.description(),
+                "jls": change.jls_section(),
+            })
+        })
+        .collect();
+    Ok(Map::from_iter([
+        ("ok".to_owned(), json!(true)),
+        ("operation".to_owned(), json!("checkApi")),
+        ("compatible".to_owned(), json!(report.is_compatible())),
+        ("api_class_count".to_owned(), json!(report.api_class_count)),
+        ("breaking_count".to_owned(), json!(breaking_count)),
+        ("behavioral_count".to_owned(), json!(report.changes.len() - breaking_count)),
+        ("changes".to_owned(), Value::Array(changes)),
+    ]))
+}
+
+fn api_change_kind_name(kind: &ApiChangeKind) -> &'static str {
+    match kind {
+        ApiChangeKind::ClassRemoved => "class_removed",
+        ApiChangeKind::ClassLessAccessible => "class_less_accessible",
+        ApiChangeKind::ClassMadeFinal => "class_made_final",
+        ApiChangeKind::ClassMadeAbstract => "class_made_abstract",
+        ApiChangeKind::ClassMadeSealed => "class_made_sealed",
+        ApiChangeKind::ClassKindChanged { .. } => "class_kind_changed",
+        ApiChangeKind::SupertypeRemoved(_) => "supertype_removed",
+        ApiChangeKind::MemberRemoved => "member_removed",
+        ApiChangeKind::DescriptorChanged { .. } => "descriptor_changed",
+        ApiChangeKind::MemberLessAccessible { .. } => "member_less_accessible",
+        ApiChangeKind::MemberMadeFinal => "member_made_final",
+        ApiChangeKind::MemberStaticChanged { .. } => "member_static_changed",
+        ApiChangeKind::MethodMadeAbstract => "method_made_abstract",
+        ApiChangeKind::AbstractMethodAdded => "abstract_method_added",
+        ApiChangeKind::ConstantChanged { .. } => "constant_changed",
+    }
+}
+
+fn execute_manifest(args: ManifestArgs) -> Result<CliOutput, CliError> {
+    let bytes = fs::read(&args.input).map_err(|source| CliError::Io {
+        path: args.input.clone(),
+        source,
+    })?;
+    let mut manifests = Vec::new();
+    collect_manifests(bytes, None, 0, &mut manifests)?;
+    if manifests.is_empty() {
+        return Err(CliError::NotFound(format!(
+            "no binary AndroidManifest.xml found in {}",
+            args.input.display()
+        )));
+    }
+    let manifests = manifests
+        .iter()
+        .map(|(internal_path, bytes)| manifest_to_json(internal_path.as_deref(), bytes, args.xml))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(CliOutput::Json(json!({
+        "ok": true,
+        "operation": "manifest",
+        "input": args.input,
+        "count": manifests.len(),
+        "manifests": manifests,
+    })))
+}
+
+/// The binary manifests in `bytes` and the archives nested in it, with their internal paths.
+fn collect_manifests(
+    bytes: Vec<u8>,
+    prefix: Option<&str>,
+    depth: usize,
+    manifests: &mut Vec<(Option<String>, Vec<u8>)>,
+) -> Result<(), CliError> {
+    if XmlDocument::is_binary_xml(&bytes) {
+        manifests.push((prefix.map(ToOwned::to_owned), bytes));
+        return Ok(());
+    }
+    if !is_zip(&bytes) || depth >= MAX_ARCHIVE_DEPTH {
+        return Ok(());
+    }
+    let archive_label = prefix.unwrap_or("input");
+    let zip_error = |error: zip::result::ZipError| CliError::Zip {
+        path: PathBuf::from(archive_label),
+        message: error.to_string(),
+    };
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let name = entry.name().to_owned();
+        let nested = name.rsplit_once('.').is_some_and(|(_, extension)| {
+            ["apk", "apks", "xapk", "zip"]
+                .iter()
+                .any(|candidate| extension.eq_ignore_ascii_case(candidate))
+        });
+        if entry.is_dir() || (name != MANIFEST_ENTRY && !nested) {
+            continue;
+        }
+        let mut entry_bytes = Vec::with_capacity(entry.size().min(usize::MAX as u64) as usize);
+        entry
+            .read_to_end(&mut entry_bytes)
+            .map_err(|source| CliError::Io {
+                path: PathBuf::from(&name),
+                source,
+            })?;
+        drop(entry);
+        let entry_path = join_internal_path(prefix, &name);
+        collect_manifests(entry_bytes, Some(&entry_path), depth + 1, manifests)?;
+    }
+    Ok(())
+}
+
+fn manifest_to_json(internal_path: Option<&str>, bytes: &[u8], with_xml: bool) -> Result<Value, CliError> {
+    let source = internal_path.unwrap_or(MANIFEST_ENTRY);
+    let document = XmlDocument::from_bytes(bytes).map_err(|error| parse_error(source, error))?;
+    let manifest = AndroidManifest::from_document(&document);
+    let components: Vec<Value> = manifest
+        .components
+        .iter()
+        .map(|component| {
+            json!({
+                "kind": component.kind.tag(),
+                "name": component.name.as_ref(),
+                "exported": component.exported,
+                "permission": component.permission.as_deref(),
+                "actions": component.actions.iter().map(AsRef::as_ref).collect::<Vec<&str>>(),
+            })
+        })
+        .collect();
+    let names = |names: &[StrRef]| -> Value { names.iter().map(AsRef::as_ref).collect::<Vec<&str>>().into() };
+    let mut value = Map::from_iter([
+        ("package".to_owned(), json!(manifest.package.as_deref())),
+        ("version_code".to_owned(), json!(manifest.version_code)),
+        ("version_name".to_owned(), json!(manifest.version_name.as_deref())),
+        ("min_sdk".to_owned(), json!(manifest.min_sdk)),
+        ("target_sdk".to_owned(), json!(manifest.target_sdk)),
+        ("compile_sdk".to_owned(), json!(manifest.compile_sdk)),
+        ("application".to_owned(), json!(manifest.application.as_deref())),
+        ("permissions".to_owned(), names(&manifest.permissions)),
+        ("declared_permissions".to_owned(), names(&manifest.declared_permissions)),
+        ("components".to_owned(), Value::Array(components)),
+    ]);
+    insert_internal_path(&mut value, internal_path);
+    if with_xml {
+        value.insert("xml".to_owned(), json!(document.to_xml()));
+    }
+    Ok(Value::Object(value))
+}
+
+fn execute_verify_signature(args: VerifySignatureArgs) -> Result<CliOutput, CliError> {
+    let bytes = fs::read(&args.input).map_err(|source| CliError::Io {
+        path: args.input.clone(),
+        source,
+    })?;
+    let idsig_path = args.idsig.clone().or_else(|| {
+        let mut path = args.input.clone().into_os_string();
+        path.push(".idsig");
+        Some(PathBuf::from(path)).filter(|path| path.is_file())
+    });
+    let idsig = match &idsig_path {
+        Some(path) => Some(fs::read(path).map_err(|source| CliError::Io {
+            path: path.clone(),
+            source,
+        })?),
+        None => None,
+    };
+    let mut apks = Vec::new();
+    collect_apks(bytes, None, 0, &mut apks)?;
+    if apks.is_empty() {
+        return Err(CliError::NotFound(format!("no APK found in {}", args.input.display())));
+    }
+    // a v4 signature belongs to the input itself, not to APKs inside of it
+    let idsig = idsig.as_deref().filter(|_| apks.len() == 1 && apks[0].0.is_none());
+    let apks = apks
+        .iter()
+        .map(|(internal_path, bytes)| verify_apk(internal_path.as_deref(), bytes, idsig))
+        .collect::<Result<Vec<_>, _>>()?;
+    let verified = apks.iter().all(|apk| apk["verified"] == true);
+    let result = json!({
+        "ok": true,
+        "operation": "verifySignature",
+        "input": args.input,
+        "idsig": idsig.and(idsig_path),
+        "verified": verified,
+        "count": apks.len(),
+        "apks": apks,
+    });
+    if args.fail_on_unverified && !verified {
+        Ok(CliOutput::Failed(result))
+    } else {
+        Ok(CliOutput::Json(result))
+    }
+}
+
+/// The APKs in `bytes`: the input itself, or the APKs of an APKS or XAPK.
+fn collect_apks(
+    bytes: Vec<u8>,
+    prefix: Option<&str>,
+    depth: usize,
+    apks: &mut Vec<(Option<String>, Vec<u8>)>,
+) -> Result<(), CliError> {
+    if !is_zip(&bytes) || depth >= MAX_ARCHIVE_DEPTH {
+        return Ok(());
+    }
+    let archive_label = prefix.unwrap_or("input");
+    let zip_error = |error: zip::result::ZipError| CliError::Zip {
+        path: PathBuf::from(archive_label),
+        message: error.to_string(),
+    };
+    let mut archive = ZipArchive::new(Cursor::new(&bytes)).map_err(zip_error)?;
+    let nested: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.to_ascii_lowercase().ends_with(".apk"))
+        .map(ToOwned::to_owned)
+        .collect();
+    if nested.is_empty() {
+        drop(archive);
+        apks.push((prefix.map(ToOwned::to_owned), bytes));
+        return Ok(());
+    }
+    for name in nested {
+        let mut entry = archive.by_name(&name).map_err(zip_error)?;
+        let mut entry_bytes = Vec::with_capacity(entry.size().min(usize::MAX as u64) as usize);
+        entry
+            .read_to_end(&mut entry_bytes)
+            .map_err(|source| CliError::Io {
+                path: PathBuf::from(&name),
+                source,
+            })?;
+        drop(entry);
+        let entry_path = join_internal_path(prefix, &name);
+        collect_apks(entry_bytes, Some(&entry_path), depth + 1, apks)?;
+    }
+    Ok(())
+}
+
+/// Verifies all signatures of one APK, its entries are decompressed for the v1 signatures.
+fn verify_apk(internal_path: Option<&str>, bytes: &[u8], idsig: Option<&[u8]>) -> Result<Value, CliError> {
+    let source = internal_path.unwrap_or("input.apk");
+    let zip_error = |error: zip::result::ZipError| CliError::Zip {
+        path: PathBuf::from(source),
+        message: error.to_string(),
+    };
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+    let mut entries = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let mut entry_bytes = Vec::with_capacity(entry.size().min(usize::MAX as u64) as usize);
+        entry
+            .read_to_end(&mut entry_bytes)
+            .map_err(|source| CliError::Io {
+                path: PathBuf::from(entry.name()),
+                source,
+            })?;
+        entries.insert(entry.name().to_owned(), entry_bytes);
+    }
+    let signatures =
+        ApkSignatures::verify(bytes, &entries, idsig).map_err(|error| parse_error(source, error))?;
+    let mut schemes: Vec<&str> = signatures.signers.iter().map(|signer| signer.scheme.name()).collect();
+    schemes.dedup();
+    let blocks: Vec<Value> = signatures
+        .block_ids
+        .iter()
+        .map(|id| json!({ "id": format!("0x{id:08x}"), "name": signing_block_name(*id) }))
+        .collect();
+    let signers: Vec<Value> = signatures
+        .signers
+        .iter()
+        .map(|signer| {
+            let digests: Vec<Value> = signer
+                .digests
+                .iter()
+                .map(|digest| {
+                    json!({
+                        "algorithm": digest.algorithm.as_ref(),
+                        "digest": to_hex(&digest.digest),
+                        "verified": digest.verified,
+                    })
+                })
+                .collect();
+            let certificates: Vec<Value> = signer
+                .certificates
+                .iter()
+                .map(|certificate| {
+                    json!({
+                        "subject": certificate.subject.as_ref(),
+                        "issuer": certificate.issuer.as_ref(),
+                        "serial": certificate.serial.as_ref(),
+                        "not_before": certificate.not_before.as_ref(),
+                        "not_after": certificate.not_after.as_ref(),
+                        "public_key_algorithm": certificate.public_key_algorithm.as_ref(),
+                        "sha256": certificate.sha256_fingerprint(),
+                    })
+                })
+                .collect();
+            json!({
+                "scheme": signer.scheme.name(),
+                "name": signer.name.as_deref(),
+                "verified": signer.is_verified(),
+                "min_sdk": signer.min_sdk,
+                "max_sdk": signer.max_sdk,
+                "digests": digests,
+                "certificates": certificates,
+                "errors": signer.errors,
+            })
+        })
+        .collect();
+    let mut value = Map::from_iter([
+        ("verified".to_owned(), json!(signatures.is_verified())),
+        ("schemes".to_owned(), json!(schemes)),
+        ("blocks".to_owned(), Value::Array(blocks)),
+        ("signers".to_owned(), Value::Array(signers)),
+    ]);
+    insert_internal_path(&mut value, internal_path);
+    Ok(Value::Object(value))
+}
+
+fn class_diff_to_json(diff: &ClassDiff, with_code: bool) -> Value {
+    let qualified_name = |name: &Option<StrRef>| name.as_ref().map(|name| name.replace('/', "."));
+    let changes: Vec<Value> = diff
+        .changes
+        .iter()
+        .map(|change| match change {
+            ClassChange::Version { old, new } => json!({
+                "kind": "version",
+                "old": format!("{}.{}", old.0, old.1),
+                "new": format!("{}.{}", new.0, new.1),
+            }),
+            ClassChange::Access { old, new } => json!({
+                "kind": "access",
+                "old": java_flag_names(*old, JavaClassAccessFlags::const_name),
+                "new": java_flag_names(*new, JavaClassAccessFlags::const_name),
+            }),
+            ClassChange::SuperName { old, new } => json!({
+                "kind": "super",
+                "old": qualified_name(old),
+                "new": qualified_name(new),
+            }),
+            ClassChange::Interfaces { added, removed } => json!({
+                "kind": "interfaces",
+                "added": added.iter().map(|name| name.replace('/', ".")).collect::<Vec<_>>(),
+                "removed": removed.iter().map(|name| name.replace('/', ".")).collect::<Vec<_>>(),
+            }),
+            ClassChange::Signature { old, new } => json!({
+                "kind": "signature",
+                "old": old.as_deref(),
+                "new": new.as_deref(),
+            }),
+        })
+        .collect();
+    let members = |members: &[MemberDiff], const_name: fn(u16) -> Option<&'static str>| -> Vec<Value> {
+        members
+            .iter()
+            .map(|member| member_diff_to_json(member, const_name, with_code))
+            .collect()
+    };
+    json!({
+        "class_name": diff.name.replace('/', "."),
+        "changes": changes,
+        "fields": members(&diff.fields, JavaFieldAccessFlags::const_name),
+        "methods": members(&diff.methods, JavaMethodAccessFlags::const_name),
+    })
+}
+
+fn member_diff_to_json(
+    diff: &MemberDiff,
+    const_name: fn(u16) -> Option<&'static str>,
+    with_code: bool,
+) -> Value {
+    let kind = match diff.kind {
+        DiffKind::Added => "added",
+        DiffKind::Removed => "removed",
+        DiffKind::Changed => "changed",
+    };
+    let changes: Vec<Value> = diff
+        .changes
+        .iter()
+        .map(|change| match change {
+            MemberChange::Access { old, new } => json!({
+                "kind": "access",
+                "old": java_flag_names(*old, const_name),
+                "new": java_flag_names(*new, const_name),
+            }),
+            MemberChange::Signature { old, new } => json!({
+                "kind": "signature",
+                "old": old.as_deref(),
+                "new": new.as_deref(),
+            }),
+            MemberChange::Value { old, new } => json!({
+                "kind": "value",
+                "old": old.as_deref(),
+                "new": new.as_deref(),
+            }),
+            MemberChange::Exceptions { old, new } => json!({
+                "kind": "exceptions",
+                "old": old.iter().map(|name| name.replace('/', ".")).collect::<Vec<_>>(),
+                "new": new.iter().map(|name| name.replace('/', ".")).collect::<Vec<_>>(),
+            }),
+            MemberChange::Code(code) => {
+                let mut value = Map::from_iter([
+                    ("kind".to_owned(), json!("code")),
+                    ("removed_count".to_owned(), json!(code.removed_count())),
+                    ("added_count".to_owned(), json!(code.added_count())),
+                ]);
+                if with_code {
+                    let lines: Vec<String> = code.lines.iter().map(|line| line.to_string()).collect();
+                    value.insert("lines".to_owned(), json!(lines));
+                }
+                Value::Object(value)
+            }
+        })
+        .collect();
+    json!({
+        "name": diff.name.as_ref(),
+        "descriptor": diff.desc.as_ref(),
+        "kind": kind,
+        "access": java_flag_names(diff.access, const_name),
+        "changes": changes,
+    })
+}
+
+struct RelocatedJar {
+    jar: Vec<u8>,
+    /// Count of written entries.
+    count: usize,
+    /// Count of entries which are moved or rewritten.
+    relocated_count: usize,
+    failures: Vec<Value>,
+}
+
+/// Relocates classes, `META-INF/services` files and resources of a JAR. Classes which cannot
+/// be read are copied as they are and reported in [RelocatedJar::failures].
+fn relocate_jar(input: &Path, bytes: Vec<u8>, relocator: &Relocator) -> Result<RelocatedJar, CliError> {
+    let zip_error = |error: ZipError| CliError::Zip {
+        path: input.to_owned(),
+        message: error.to_string(),
+    };
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(zip_error)?;
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut written = HashSet::new();
+    let mut relocated = RelocatedJar { jar: Vec::new(), count: 0, relocated_count: 0, failures: Vec::new() };
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let name = entry.name().to_owned();
+        let mut content = Vec::with_capacity(entry.size().min(usize::MAX as u64) as usize);
+        entry.read_to_end(&mut content).map_err(|source| CliError::Io {
+            path: PathBuf::from(&name),
+            source,
+        })?;
+        let is_dir = entry.is_dir();
+        drop(entry);
+        let (new_name, new_content) = if is_dir {
+            (relocator.relocate(&name), None)
+        } else if name.ends_with(".class") {
+            match relocate_class(&content, relocator) {
+                Ok(class) => class,
+                Err(message) => {
+                    relocated.failures.push(json!({ "entry": name, "error": message }));
+                    (None, None)
+                }
+            }
+        } else if let Some((path, services)) = std::str::from_utf8(&content)
+            .ok()
+            .and_then(|text| relocator.relocate_service(&name, text))
+        {
+            (Some(path), Some(services.into_bytes()))
+        } else {
+            (relocator.relocate(&name), None)
+        };
+        if new_name.is_some() || new_content.is_some() {
+            relocated.relocated_count += 1;
+        }
+        let new_name = new_name.unwrap_or(name);
+        if !written.insert(new_name.clone()) {
+            if !is_dir {
+                relocated.failures.push(json!({
+                    "entry": new_name,
+                    "error": "duplicate entry, the first one is kept",
+                }));
+            }
+            continue;
+        }
+        if is_dir {
+            writer.add_directory(new_name.as_str(), SimpleFileOptions::default()).map_err(zip_error)?;
+        } else {
+            writer.start_file(new_name.as_str(), SimpleFileOptions::default()).map_err(zip_error)?;
+            writer
+                .write_all(new_content.as_deref().unwrap_or(&content))
+                .map_err(|source| CliError::Io {
+                    path: PathBuf::from(&new_name),
+                    source,
+                })?;
+        }
+        relocated.count += 1;
+    }
+    relocated.jar = writer.finish().map_err(zip_error)?.into_inner();
+    Ok(relocated)
 }
 
-fn execute_export_all(args: ExportAllArgs) -> Result<CliOutput, CliError> {
-    let index = InputIndex::load(&args.input, None)?;
-    let filter = args.class_filter.as_deref().unwrap_or_default();
-    let selected = find_matching_classes(&index, filter);
-    if selected.is_empty() {
-        return Err(CliError::NotFound(format!(
-            "no classes matched filter: {filter}"
-        )));
+/// The new entry name and content of a relocated class, [None] for unchanged ones.
+fn relocate_class(bytes: &[u8], relocator: &Relocator) -> Result<(Option<String>, Option<Vec<u8>>), String> {
+    let mut node = ClassNode::from_bytes(bytes).map_err(|error| format!("{error:?}"))?;
+    let old_name = node.name.clone();
+    let tracked = TrackedRemapper { remapper: relocator, changed: Cell::new(false) };
+    ClassRemapper::new(&tracked).remap(&mut node).map_err(|error| format!("{error:?}"))?;
+    // classes without relocated names are kept byte by byte
+    if !tracked.changed.get() {
+        return Ok((None, None));
     }
-    fs::create_dir_all(&args.output).map_err(|source| CliError::Io {
-        path: args.output.clone(),
-        source,
-    })?;
-    let mut classes = Vec::with_capacity(selected.len());
-    for entry in selected {
-        let output = class_output_path(&args.output, entry, args.format);
-        let content = entry.render(args.format)?;
-        write_file(&output, content.as_bytes())?;
-        let mut class = Map::from_iter([
-            (
-                "class_name".to_owned(),
-                Value::String(entry.qualified_name()),
-            ),
-            (
-                "descriptor".to_owned(),
-                Value::String(entry.descriptor.clone()),
-            ),
-            ("output".to_owned(), json!(output)),
-        ]);
-        insert_internal_path(&mut class, entry.internal_path.as_deref());
-        classes.push(Value::Object(class));
+    let new_bytes = node.to_bytes().map_err(|error| format!("{error:?}"))?;
+    let new_name = (node.name != old_name).then(|| format!("{}.class", node.name));
+    Ok((new_name, Some(new_bytes)))
+}
+
+/// Records whether a [Relocator] renamed anything.
+struct TrackedRemapper<'a> {
+    remapper: &'a Relocator,
+    changed: Cell<bool>,
+}
+
+impl TrackedRemapper<'_> {
+    fn track(&self, mapped: Option<StrRef>) -> Option<StrRef> {
+        if mapped.is_some() {
+            self.changed.set(true);
+        }
+        mapped
     }
-    let manifest = json!({
-        "ok": true,
-        "operation": "exportAll",
-        "input": args.input,
-        "class_filter": args.class_filter,
-        "format": args.format.as_str(),
-        "output_dir": args.output,
-        "count": classes.len(),
-        "classes": classes,
-    });
-    let manifest_path = args.output.join("manifest.json");
-    let bytes = serde_json::to_vec_pretty(&manifest).map_err(|error| CliError::Parse {
-        source: "manifest.json".to_owned(),
-        message: format!("serialize manifest: {error}"),
-    })?;
-    write_file(&manifest_path, &bytes)?;
-    Ok(CliOutput::Json(manifest))
+}
+
+impl Remapper for TrackedRemapper<'_> {
+    fn map(&self, internal_name: &str) -> Option<StrRef> {
+        self.track(self.remapper.map(internal_name))
+    }
+
+    fn map_package_name(&self, name: &str) -> Option<StrRef> {
+        self.track(self.remapper.map_package_name(name))
+    }
+
+    fn map_string(&self, value: &str) -> Option<StrRef> {
+        self.track(self.remapper.map_string(value))
+    }
+}
+
+fn parse_relocation(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((pattern, shaded)) if !pattern.is_empty() && !shaded.is_empty() => {
+            Ok((pattern.to_owned(), shaded.to_owned()))
+        }
+        _ => Err(format!("expected FROM=TO, for example com.google.common=shaded.guava, got {value}")),
+    }
+}
+
+fn usage_to_json(usage: &Xref) -> Value {
+    let kind = match usage.kind {
+        XrefKind::Call => "call",
+        XrefKind::FieldRead => "field_read",
+        XrefKind::FieldWrite => "field_write",
+        XrefKind::StringUse => "string",
+        XrefKind::ClassUse => "class",
+    };
+    json!({
+        "class_name": usage.from.owner.replace('/', "."),
+        "method": usage.from.name.as_ref(),
+        "descriptor": usage.from.desc.as_ref(),
+        "offset": usage.offset,
+        "kind": kind,
+        "target": usage.target.to_string(),
+    })
 }
 
 impl InputIndex {
+    /// References in all loaded classes, classes which cannot be read are skipped.
+    fn xref(&self) -> XrefIndex {
+        let mut xref = XrefIndex::new();
+        for entry in &self.classes {
+            match &entry.payload {
+                ClassPayload::Jvm { node } => {
+                    let _ = xref.add_class_node(node);
+                }
+                ClassPayload::Dex {
+                    accessor,
+                    class_def,
+                } => {
+                    let _ = xref.add_class_def(accessor, class_def);
+                }
+            }
+        }
+        xref
+    }
+
+    /// Hierarchy of all loaded classes, the first definition of a class wins.
+    fn hierarchy(&self) -> ClassHierarchy {
+        let mut hierarchy = ClassHierarchy::new();
+        for entry in &self.classes {
+            let class = match &entry.payload {
+                ClassPayload::Jvm { node } => HierarchyClass::from_class_node(node),
+                ClassPayload::Dex {
+                    accessor,
+                    class_def,
+                } => match HierarchyClass::from_class_def(accessor, class_def) {
+                    Ok(class) => class,
+                    Err(_) => continue,
+                },
+            };
+            hierarchy.add_class(class);
+        }
+        hierarchy
+    }
+
     fn load(path: &Path, internal_path: Option<&str>) -> Result<Self, CliError> {
         let bytes = fs::read(path).map_err(|source| CliError::Io {
             path: path.to_owned(),
@@ -343,14 +1632,62 @@ impl InputIndex {
             index.collect_embedded(bytes, None, 0)?;
         }
         if index.classes.is_empty() {
+            let mut message = "no supported DEX or class files found".to_owned();
+            if !index.skipped_compact_dex.is_empty() {
+                message += &format!(
+                    ", compact dex files are not supported: {}",
+                    index.skipped_compact_dex.join(", ")
+                );
+            }
             return Err(CliError::Parse {
                 source: input_label(path, internal_path),
-                message: "no supported DEX or class files found".to_owned(),
+                message,
             });
         }
+        index.attach_resource_tables();
         Ok(index)
     }
 
+    /// Lists the skipped compact dex files as `key` of `result`, nothing is added without them.
+    fn report_skipped(&self, result: &mut Value, key: &str) {
+        if !self.skipped_compact_dex.is_empty() {
+            result[key] = json!(self.skipped_compact_dex);
+        }
+    }
+
+    /// Shares the collected resource tables with all DEX classes.
+    fn attach_resource_tables(&mut self) {
+        if self.resource_tables.is_empty() { return; }
+        let tables = Arc::new(std::mem::take(&mut self.resource_tables));
+        for entry in &mut self.classes {
+            if let ClassPayload::Dex { .. } = entry.payload {
+                entry.resources = Some(Arc::clone(&tables));
+            }
+        }
+    }
+
+    /// Renames classes to their original names in `mapping`, members are renamed when they are read.
+    fn apply_mapping(&mut self, mapping: Option<&Path>) -> Result<(), CliError> {
+        let Some(path) = mapping else { return Ok(()) };
+        let content = fs::read(path).map_err(|source| CliError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mapping = MappingTree::parse(&String::from_utf8_lossy(&content))
+            .and_then(|tree| tree.deobfuscator())
+            .map_err(|error| parse_error(&path.display().to_string(), error))?;
+        let mapping = Arc::new(mapping);
+        for entry in &mut self.classes {
+            if let Some(original) = mapping.map(&entry.internal_name) {
+                let obfuscated_name = std::mem::replace(&mut entry.internal_name, original.to_string());
+                entry.descriptor = format!("L{};", entry.internal_name);
+                entry.obfuscated_name = Some(obfuscated_name);
+            }
+            entry.mapping = Some(Arc::clone(&mapping));
+        }
+        Ok(())
+    }
+
     fn collect_embedded(
         &mut self,
         bytes: Vec<u8>,
@@ -363,6 +1700,9 @@ impl InputIndex {
         if is_class(&bytes) {
             return self.add_class(internal_path, bytes);
         }
+        if is_dex_container(&bytes) {
+            return self.add_dex_container(internal_path, &bytes, depth);
+        }
         if !is_zip(&bytes) {
             return Ok(());
         }
@@ -391,10 +1731,27 @@ impl InputIndex {
                 path: PathBuf::from(archive_label),
                 message: error.to_string(),
             })?;
-            if entry.is_dir() || !is_possible_input_entry(entry.name()) || entry.size() < 4 {
+            if entry.is_dir() || entry.size() < 4 {
                 continue;
             }
             let name = entry.name().to_owned();
+            if name == RESOURCES_ENTRY {
+                let mut entry_bytes = Vec::new();
+                entry
+                    .read_to_end(&mut entry_bytes)
+                    .map_err(|source| CliError::Io {
+                        path: PathBuf::from(&name),
+                        source,
+                    })?;
+                // a broken table only loses the resource names in smali
+                if let Ok(table) = ResourceTable::from_bytes(&entry_bytes) {
+                    self.resource_tables.push(table);
+                }
+                continue;
+            }
+            if !is_possible_input_entry(&name) {
+                continue;
+            }
             let mut header = [0; 4];
             entry
                 .read_exact(&mut header)
@@ -444,11 +1801,41 @@ impl InputIndex {
                     accessor: Arc::clone(&accessor),
                     class_def,
                 },
+                obfuscated_name: None,
+                mapping: None,
+                resources: None,
             });
         }
         Ok(())
     }
 
+    /// Adds the dex files of a VDEX or an OAT file as `base.vdex!classes2.dex`. Compact dex
+    /// files can't be read yet, they are skipped and reported in the output.
+    fn add_dex_container(
+        &mut self,
+        internal_path: Option<String>,
+        bytes: &[u8],
+        depth: usize,
+    ) -> Result<(), CliError> {
+        let dex_files = match extract_dex_files(bytes) {
+            Ok(dex_files) => dex_files,
+            // other ELF files in archives like native libraries can have no extension
+            Err(_) if depth > 0 => return Ok(()),
+            Err(error) => {
+                return Err(parse_error(internal_path.as_deref().unwrap_or("input"), error));
+            }
+        };
+        for (index, dex) in dex_files.into_iter().enumerate() {
+            let entry_path = join_internal_path(internal_path.as_deref(), &dex.name(index));
+            if dex.compact {
+                self.skipped_compact_dex.push(entry_path);
+                continue;
+            }
+            self.add_dex(Some(entry_path), dex.bytes)?;
+        }
+        Ok(())
+    }
+
     fn add_class(&mut self, internal_path: Option<String>, bytes: Vec<u8>) -> Result<(), CliError> {
         let source = internal_path.as_deref().unwrap_or("input.class");
         let node = ClassNode::from_bytes(&bytes).map_err(|error| parse_error(source, error))?;
@@ -460,6 +1847,9 @@ impl InputIndex {
             payload: ClassPayload::Jvm {
                 node: Arc::new(node),
             },
+            obfuscated_name: None,
+            mapping: None,
+            resources: None,
         });
         Ok(())
     }
@@ -470,6 +1860,11 @@ impl ClassEntry {
         self.internal_name.replace('/', ".")
     }
 
+    /// The internal name of this class in the input.
+    fn input_name(&self) -> &str {
+        self.obfuscated_name.as_deref().unwrap_or(&self.internal_name)
+    }
+
     fn members(&self) -> Result<(Vec<MethodInfo>, Vec<FieldInfo>), CliError> {
         let (mut methods, mut fields) = match &self.payload {
             ClassPayload::Jvm { node } => (
@@ -529,6 +1924,21 @@ impl ClassEntry {
                 }
             }
         };
+        if let Some(mapping) = &self.mapping {
+            let owner = self.input_name();
+            for method in &mut methods {
+                if let Some(name) = mapping.map_method_name(owner, &method.name, &method.signature) {
+                    method.name = name.to_string();
+                }
+                method.signature = map_desc(mapping, &method.signature);
+            }
+            for field in &mut fields {
+                if let Some(name) = mapping.map_field_name(owner, &field.name, &field.field_type) {
+                    field.name = name.to_string();
+                }
+                field.field_type = map_desc(mapping, &field.field_type);
+            }
+        }
         methods.sort();
         fields.sort();
         Ok((methods, fields))
@@ -574,26 +1984,93 @@ impl ClassEntry {
                 ),
             ),
         ]);
+        if let Some(obfuscated_name) = &self.obfuscated_name {
+            class.insert(
+                "obfuscated_name".to_owned(),
+                Value::String(obfuscated_name.replace('/', ".")),
+            );
+        }
         insert_internal_path(&mut class, self.internal_path.as_deref());
         Ok(Value::Object(class))
     }
 
+    /// The [ClassNode] of this class, DEX classes are converted to JVM classes first.
+    fn class_node(&self) -> Result<Arc<ClassNode>, CliError> {
+        match &self.payload {
+            ClassPayload::Jvm { node } => Ok(Arc::clone(node)),
+            ClassPayload::Dex {
+                accessor,
+                class_def,
+            } => accessor
+                .get_class_node(*class_def)
+                .map(Arc::new)
+                .map_err(|error| parse_error(self.input_name(), error)),
+        }
+    }
+
+    /// DEX classes are converted with `hierarchy`, which merges the types of stack map frames.
+    fn to_class_bytes(&self, hierarchy: &ClassHierarchy) -> Result<Vec<u8>, String> {
+        let converted;
+        let node = match &self.payload {
+            ClassPayload::Jvm { node } => node.as_ref(),
+            ClassPayload::Dex {
+                accessor,
+                class_def,
+            } => {
+                converted = accessor
+                    .get_class_node_with(*class_def, hierarchy)
+                    .map_err(|error| format!("{error:?}"))?;
+                &converted
+            }
+        };
+        node.to_bytes().map_err(|error| format!("{error:?}"))
+    }
+
     fn render(&self, format: ExportFormat) -> Result<String, CliError> {
         match format {
             ExportFormat::Smali => match &self.payload {
-                ClassPayload::Jvm { node } => Ok(render_jvm_class(node)),
+                ClassPayload::Jvm { node } => self.render_jvm(node).map_err(|error| {
+                    parse_error(self.internal_path.as_deref().unwrap_or("input.class"), error)
+                }),
                 ClassPayload::Dex {
                     accessor,
                     class_def,
                 } => accessor
                     .get_class_smali(*class_def)
-                    .map(|node| node.render(0))
+                    .map(|mut node| {
+                        if let Some(mapping) = &self.mapping {
+                            remap_smali(&mut node, self.input_name(), mapping.as_ref());
+                        }
+                        if let Some(tables) = &self.resources {
+                            annotate_resource_ids(&mut node, &|id| {
+                                tables.iter().find_map(|table| table.reference_name(id))
+                            });
+                        }
+                        if let Ok(Some(metadata)) = KotlinMetadata::from_dex_class(accessor, class_def) {
+                            node.children.insert(0, metadata.to_smali());
+                        }
+                        node.render(0)
+                    })
                     .map_err(|error| {
                         parse_error(self.internal_path.as_deref().unwrap_or("input.dex"), error)
                     }),
             },
         }
     }
+
+    fn render_jvm(&self, node: &ClassNode) -> Result<String, AsmErr> {
+        let Some(mapping) = &self.mapping else {
+            return render_jvm_class(node);
+        };
+        let mut node = node.clone();
+        ClassRemapper::new(mapping.as_ref()).remap(&mut node)?;
+        render_jvm_class(&node)
+    }
+}
+
+fn map_desc(mapping: &TreeRemapper, desc: &str) -> String {
+    let desc = StrRef::from(desc);
+    mapping.map_desc(&desc).unwrap_or(desc).to_string()
 }
 
 fn find_matching_classes<'a>(index: &'a InputIndex, query: &str) -> Vec<&'a ClassEntry> {
@@ -749,15 +2226,25 @@ fn is_zip(bytes: &[u8]) -> bool {
     )
 }
 
+/// VDEX files and OAT files (`.odex`, `.oat`) which embed dex files.
+fn is_dex_container(bytes: &[u8]) -> bool {
+    is_vdex_bytes(bytes) || is_oat_bytes(bytes)
+}
+
 fn is_supported_magic(bytes: &[u8]) -> bool {
-    is_dex(bytes) || is_class(bytes) || is_zip(bytes)
+    is_dex(bytes) || is_class(bytes) || is_zip(bytes) || is_dex_container(bytes)
 }
 
+/// The binary XML manifest in the root of every APK.
+const MANIFEST_ENTRY: &str = "AndroidManifest.xml";
+/// The compiled resource table in the root of every APK.
+const RESOURCES_ENTRY: &str = "resources.arsc";
+
 fn is_possible_input_entry(name: &str) -> bool {
     let Some((_, extension)) = name.rsplit_once('.') else {
         return true;
     };
-    ["dex", "class", "apk", "apks", "xapk", "aab", "zip", "jar"]
+    ["dex", "class", "apk", "apks", "xapk", "aab", "zip", "jar", "vdex", "odex", "oat"]
         .iter()
         .any(|candidate| extension.eq_ignore_ascii_case(candidate))
 }
@@ -783,9 +2270,18 @@ fn safe_component(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        Cli, Commands, ExportFormat, InputIndex, class_output_path, normalize_class_name,
-        read_internal_entry,
+        Cli, Commands, ExportFormat, FuzzyMatchModel, InputIndex, MemberRef, check_api, check_compat,
+        class_output_path, collect_manifests, diff_inputs, find_matching_classes, manifest_to_json,
+        member_search_key, normalize_class_name, read_internal_entry, relocate_jar, collect_apks, verify_apk,
     };
+    use java_asm::compat::ApiSignatures;
+    use java_asm::hierarchy::ClassHierarchy;
+    use java_asm::node::element::ClassNode;
+    use java_asm::node::InsnNode;
+    use java_asm::{JavaAccessFlags, Opcodes};
+    use java_asm::remap::{Relocation, Relocator};
+    use java_asm::verify::{Verifier, VerifyMode};
+    use std::io::Read;
     use clap::Parser;
     use std::io::{Cursor, Write};
     use std::path::PathBuf;
@@ -897,6 +2393,383 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dex_files_are_extracted_from_vdex() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        // VDEX 019 with a dex file and a compact dex file behind their quickening offsets
+        let mut compact = b"cdex001\0".to_vec();
+        compact.resize(32, 0);
+        for value in [0x88u32, 0x88, 0x12345678] {
+            compact.extend(value.to_le_bytes());
+        }
+        compact.resize(0x88, 0);
+        let mut vdex = b"vdex019\0".to_vec();
+        for value in [2, dex.len() as u32 + 0x88, 0, 0, 0, 0] {
+            vdex.extend(value.to_le_bytes());
+        }
+        vdex.extend_from_slice(dex);
+        vdex.resize(vdex.len().next_multiple_of(4) + 4, 0);
+        vdex.extend_from_slice(&compact);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("oat/arm64/base.vdex", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&vdex).unwrap();
+        // an ELF file which is no OAT file
+        writer.start_file("libstub", SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"\x7fELF\x02\x01\x01\0").unwrap();
+        let dump = writer.finish().unwrap().into_inner();
+        let mut index = InputIndex::default();
+        index.collect_embedded(dump, None, 0).unwrap();
+
+        assert!(!index.classes.is_empty());
+        assert!(
+            index
+                .classes
+                .iter()
+                .all(|class| class.internal_path.as_deref() == Some("oat/arm64/base.vdex!classes.dex"))
+        );
+        assert_eq!(index.skipped_compact_dex, ["oat/arm64/base.vdex!classes2.cdex"]);
+        let mut result = serde_json::json!({ "ok": true });
+        index.report_skipped(&mut result, "skipped_compact_dex");
+        assert_eq!(result["skipped_compact_dex"], serde_json::json!(["oat/arm64/base.vdex!classes2.cdex"]));
+        let mut index = InputIndex::default();
+        assert!(index.collect_embedded(b"\x7fELF\x02\x01\x01\0".to_vec(), None, 0).is_err());
+    }
+
+    #[test]
+    fn dex_classes_convert_to_class_files() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        let mut index = InputIndex::default();
+        index.collect_embedded(dex.to_vec(), None, 0).unwrap();
+
+        let class = &index.classes[0];
+        let bytes = class.to_class_bytes(&index.hierarchy()).unwrap();
+        assert_eq!(&bytes[..4], &[0xCA, 0xFE, 0xBA, 0xBE]);
+        let node = java_asm::node::element::ClassNode::from_bytes(&bytes).unwrap();
+        assert_eq!(&*node.name, class.internal_name);
+    }
+
+    #[test]
+    fn hierarchy_contains_dex_classes() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        let mut index = InputIndex::default();
+        index.collect_embedded(dex.to_vec(), None, 0).unwrap();
+
+        let hierarchy = index.hierarchy();
+        assert_eq!(hierarchy.len(), index.classes.len());
+        let class = &index.classes[0];
+        assert!(hierarchy.contains(&class.internal_name));
+    }
+
+    #[test]
+    fn xref_finds_usages_in_dex_classes() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        let mut index = InputIndex::default();
+        index.collect_embedded(dex.to_vec(), None, 0).unwrap();
+
+        let xref = index.xref();
+        assert!(!xref.is_empty());
+        let usage = &xref.xrefs()[0];
+        assert!(xref.usages(&usage.target).contains(&usage));
+        assert!(index
+            .classes
+            .iter()
+            .any(|class| class.internal_name == *usage.from.owner));
+    }
+
+    #[test]
+    fn member_keys_find_dex_methods() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        let mut index = InputIndex::default();
+        index.collect_embedded(dex.to_vec(), None, 0).unwrap();
+
+        let (entry, method) = index
+            .classes
+            .iter()
+            .filter(|entry| !entry.internal_name.rsplit('/').next().unwrap().contains('$'))
+            .find_map(|entry| Some((entry, entry.members().ok()?.0.into_iter().next()?)))
+            .unwrap();
+        let member = MemberRef::new(&entry.internal_name, &method.name, &method.signature);
+        let keys = vec![member_search_key(&member)];
+        let simple_name = entry.internal_name.rsplit('/').next().unwrap();
+        let query = format!("{simple_name}.{}", method.name);
+        let mut model = FuzzyMatchModel::new_for_members("".into(), &keys, 10);
+        assert_eq!(model.search_with_new_input(query.into()).items.len(), 1);
+    }
+
+    #[test]
+    fn mapping_renames_dex_classes() {
+        let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
+        let mut index = InputIndex::default();
+        index.collect_embedded(dex.to_vec(), None, 0).unwrap();
+        let obfuscated = index.classes[0].internal_name.clone();
+        let mapping_path = std::env::temp_dir()
+            .join(format!("java_asm_cli_mapping_{}.txt", std::process::id()));
+        let mapping = format!("demo.Original -> {}:\n", obfuscated.replace('/', "."));
+        std::fs::write(&mapping_path, mapping).unwrap();
+        let applied = index.apply_mapping(Some(&mapping_path));
+        std::fs::remove_file(&mapping_path).unwrap();
+        applied.unwrap();
+
+        let matched = find_matching_classes(&index, "demo.Original");
+        let entry = matched.first().unwrap();
+        assert_eq!(entry.internal_name, "demo/Original");
+        assert_eq!(entry.descriptor, "Ldemo/Original;");
+        let json = entry.to_json().unwrap();
+        assert_eq!(json["class_name"], "demo.Original");
+        assert_eq!(json["obfuscated_name"], obfuscated.replace('/', "."));
+        let smali = entry.render(ExportFormat::Smali).unwrap();
+        assert!(smali.contains("Ldemo/Original;"));
+        assert!(!smali.contains(&format!("L{obfuscated};")));
+    }
+
+    #[test]
+    fn relocate_moves_classes_services_and_resources() {
+        let class = include_bytes!("../../asm/tests/res/bytecode/CompileTesting.class");
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.add_directory("com/example/", SimpleFileOptions::default()).unwrap();
+        for (name, content) in [
+            ("CompileTesting.class", &class[..]),
+            ("com/example/data.txt", b"data"),
+            ("META-INF/services/com.example.Service", b"com.example.Impl\n"),
+            ("README.md", b"readme"),
+        ] {
+            writer.start_file(name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        let jar = writer.finish().unwrap().into_inner();
+        let relocator = Relocator::new(vec![
+            Relocation::new("com.example", "shaded.example"),
+            Relocation::new("java.lang", "shaded.lang"),
+        ]);
+
+        let relocated = relocate_jar(&PathBuf::from("app.jar"), jar, &relocator).unwrap();
+        assert_eq!(relocated.count, 5);
+        assert_eq!(relocated.relocated_count, 4);
+        assert!(relocated.failures.is_empty());
+        let mut archive = zip::ZipArchive::new(Cursor::new(relocated.jar)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        for name in [
+            "shaded/example/",
+            "CompileTesting.class",
+            "shaded/example/data.txt",
+            "META-INF/services/shaded.example.Service",
+            "README.md",
+        ] {
+            assert!(names.contains(&name), "{name} is missing in {names:?}");
+        }
+        let mut services = String::new();
+        archive
+            .by_name("META-INF/services/shaded.example.Service")
+            .unwrap()
+            .read_to_string(&mut services)
+            .unwrap();
+        assert_eq!(services, "shaded.example.Impl\n");
+        let mut class = Vec::new();
+        archive.by_name("CompileTesting.class").unwrap().read_to_end(&mut class).unwrap();
+        let node = ClassNode::from_bytes(&class).unwrap();
+        assert_eq!(node.super_name.as_deref(), Some("shaded/lang/Object"));
+        // the loop in main needs its frames, which are required by the class file version.
+        assert!(node.major_version >= 51);
+        let main = node.methods.iter().find(|method| method.name.as_ref() == "main").unwrap();
+        let code = main.code().unwrap().unwrap();
+        assert_eq!(code.stack_map_table.len(), 2);
+        let hierarchy = ClassHierarchy::new();
+        let errors = Verifier::new(&hierarchy).with_mode(VerifyMode::TypeChecking).verify_class(&node);
+        assert!(errors.is_empty(), "{}", errors[0]);
+    }
+
+    #[test]
+    fn diff_reports_changed_members_and_code() {
+        let bytes = include_bytes!("../../asm/tests/res/bytecode/CompileTesting.class");
+        let mut old = InputIndex::default();
+        old.collect_embedded(bytes.to_vec(), None, 0).unwrap();
+
+        let mut node = ClassNode::from_bytes(bytes).unwrap();
+        let removed = node.methods.pop().unwrap();
+        let method = node.methods.iter_mut().find(|method| method.code_body.is_some()).unwrap();
+        let code = method.code_mut().unwrap().unwrap();
+        code.instructions.insert(0, InsnNode::NoOperand { opcode: Opcodes::NOP });
+        code.insn_offsets.insert(0, 0);
+        let changed = method.name.to_string();
+        let mut new = InputIndex::default();
+        new.collect_embedded(node.to_bytes().unwrap(), None, 0).unwrap();
+
+        let result = diff_inputs(&old, &old, "", true).unwrap();
+        assert_eq!(result["identical"], true);
+        assert_eq!(result["unchanged_count"], 1);
+
+        let result = diff_inputs(&old, &new, "", true).unwrap();
+        assert_eq!(result["changed_count"], 1);
+        let class = &result["changed"][0];
+        assert_eq!(class["class_name"], "CompileTesting");
+        let methods = class["methods"].as_array().unwrap();
+        let changed = methods.iter().find(|method| method["name"] == changed.as_str()).unwrap();
+        assert_eq!(changed["kind"], "changed");
+        let code = &changed["changes"][0];
+        assert_eq!(code["kind"], "code");
+        assert_eq!(code["added_count"], 1);
+        assert!(code["lines"].as_array().unwrap().contains(&"+ nop".into()));
+        let removed = methods.iter().find(|method| method["name"] == removed.name.as_ref()).unwrap();
+        assert_eq!(removed["kind"], "removed");
+
+        let result = diff_inputs(&old, &new, "", false).unwrap();
+        assert!(result["changed"][0]["methods"][0]["changes"][0].get("lines").is_none());
+    }
+
+    #[test]
+    fn check_api_reports_breaking_changes() {
+        let bytes = include_bytes!("../../asm/tests/res/bytecode/CompileTesting.class");
+        let mut old = InputIndex::default();
+        old.collect_embedded(bytes.to_vec(), None, 0).unwrap();
+
+        let result = check_api(&old, &old, "").unwrap();
+        assert_eq!(result["compatible"], true);
+        assert_eq!(result["changes"], serde_json::json!([]));
+
+        let mut node = ClassNode::from_bytes(bytes).unwrap();
+        node.access |= JavaAccessFlags::ACC_PUBLIC;
+        let mut public = InputIndex::default();
+        public.collect_embedded(node.to_bytes().unwrap(), None, 0).unwrap();
+        let constructor = node.methods.iter().position(|method| method.name.as_ref() == "<init>").unwrap();
+        node.methods.remove(constructor);
+        node.access |= JavaAccessFlags::ACC_FINAL;
+        let mut new = InputIndex::default();
+        new.collect_embedded(node.to_bytes().unwrap(), None, 0).unwrap();
+
+        let result = check_api(&public, &new, "").unwrap();
+        assert_eq!(result["compatible"], false);
+        assert_eq!(result["api_class_count"], 1);
+        let changes = result["changes"].as_array().unwrap();
+        assert_eq!(changes[0]["kind"], "class_made_final");
+        assert_eq!(changes[0]["jls"], "13.4.2.3");
+        let removed = changes.iter().find(|change| change["member"] == "<init>").unwrap();
+        assert_eq!(removed["severity"], "breaking");
+        assert_eq!(removed["kind"], "member_removed");
+    }
+
+    /// `<manifest package="com.example"/>` as binary XML with utf-8 strings.
+    fn binary_manifest() -> Vec<u8> {
+        fn chunk(chunk_type: u16, header: &[u32], body: &[u8]) -> Vec<u8> {
+            let header_size = 8 + header.len() * 4;
+            let mut bytes = chunk_type.to_le_bytes().to_vec();
+            bytes.extend_from_slice(&(header_size as u16).to_le_bytes());
+            bytes.extend_from_slice(&((header_size + body.len()) as u32).to_le_bytes());
+            bytes.extend(header.iter().flat_map(|value| value.to_le_bytes()));
+            bytes.extend_from_slice(body);
+            bytes
+        }
+        let words = |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|value| value.to_le_bytes()).collect() };
+        let mut pool_body = words(&[0, 11, 21]);
+        pool_body.extend_from_slice(b"\x08\x08manifest\0\x07\x07package\0\x0b\x0bcom.example\0\0\0");
+        let mut body = chunk(0x0001, &[3, 0, 0x100, 40, 0], &pool_body);
+        let mut element = words(&[u32::MAX, 0]);
+        element.extend([20u16, 20, 1, 0, 0, 0].iter().flat_map(|value| value.to_le_bytes()));
+        element.extend(words(&[u32::MAX, 1, 2, 0x0300_0008, 2]));
+        body.extend(chunk(0x0102, &[1, u32::MAX], &element));
+        body.extend(chunk(0x0103, &[1, u32::MAX, u32::MAX, 0], &[]));
+        chunk(0x0003, &[], &body)
+    }
+
+    #[test]
+    fn manifest_is_found_in_nested_apks() {
+        let apks = zip_file("base.apk", &zip_file("AndroidManifest.xml", &binary_manifest()));
+        let mut manifests = Vec::new();
+        collect_manifests(apks, None, 0, &mut manifests).unwrap();
+        assert_eq!(manifests.len(), 1);
+        let (internal_path, bytes) = &manifests[0];
+        assert_eq!(internal_path.as_deref(), Some("base.apk!AndroidManifest.xml"));
+
+        let manifest = manifest_to_json(internal_path.as_deref(), bytes, true).unwrap();
+        assert_eq!(manifest["package"], "com.example");
+        assert_eq!(manifest["internal_path"], "base.apk!AndroidManifest.xml");
+        assert_eq!(manifest["components"], serde_json::json!([]));
+        assert!(manifest["xml"].as_str().unwrap().contains("<manifest package=\"com.example\"/>"));
+    }
+
+    #[test]
+    fn verify_signature_of_nested_apks() {
+        let signed = include_bytes!("../../asm/tests/res/apk/signed.apk");
+        let idsig = include_bytes!("../../asm/tests/res/apk/signed.apk.idsig");
+        let result = verify_apk(None, signed, Some(idsig)).unwrap();
+        assert_eq!(result["verified"], true);
+        assert_eq!(result["schemes"], serde_json::json!(["v1", "v2", "v3", "v4"]));
+        assert_eq!(result["blocks"][2]["name"], "verity padding");
+        let certificate = &result["signers"][0]["certificates"][0];
+        assert_eq!(certificate["subject"], "CN=Test Signer, O=java_asm, C=US");
+        assert_eq!(certificate["sha256"], "1b6d0d4739f9b32815a7d296f965aadcfcbcc5cfc4a6c0e35414ca6f12931f2c");
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("base.apk", SimpleFileOptions::default()).unwrap();
+        writer.write_all(signed).unwrap();
+        writer.start_file("split_config.en.apk", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&zip_file("resources.arsc", b"unsigned")).unwrap();
+        let apks = writer.finish().unwrap().into_inner();
+        let mut nested = Vec::new();
+        collect_apks(apks, None, 0, &mut nested).unwrap();
+        let paths: Vec<Option<&str>> = nested.iter().map(|(path, _)| path.as_deref()).collect();
+        assert_eq!(paths, [Some("base.apk"), Some("split_config.en.apk")]);
+        let result = verify_apk(Some("base.apk"), &nested[0].1, None).unwrap();
+        assert_eq!(result["verified"], true);
+        assert_eq!(result["internal_path"], "base.apk");
+        let result = verify_apk(Some("split_config.en.apk"), &nested[1].1, None).unwrap();
+        assert_eq!(result["verified"], false);
+        assert_eq!(result["signers"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn check_compat_reports_versions_features_and_missing_apis() {
+        let bytes = include_bytes!("../../asm/tests/res/bytecode/CompileTesting.class");
+        let mut index = InputIndex::default();
+        index.collect_embedded(bytes.to_vec(), None, 0).unwrap();
+
+        let result = check_compat(&index, 17, None).unwrap();
+        assert_eq!(result["compatible"], true);
+        assert_eq!(result["versions"]["61.0"], 1);
+
+        let result = check_compat(&index, 11, None).unwrap();
+        assert_eq!(result["compatible"], false);
+        let class = &result["classes"][0];
+        assert_eq!(class["release"], 17);
+        assert_eq!(class["features"][0], "invokedynamic");
+        assert_eq!(class["issues"][0]["message"], "class file targets java 17");
+
+        let api = ApiSignatures::parse(
+            "java/lang/Object.<init>()V\n\
+            java/lang/System.out:Ljava/io/PrintStream;\n\
+            java/io/PrintStream\n\
+            java/io/PrintStream.println(I)V\n",
+        )
+        .unwrap();
+        let result = check_compat(&index, 17, Some(&api)).unwrap();
+        let messages: Vec<&str> = result["classes"][0]["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|issue| issue["message"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "missing member java/io/PrintStream.println(Ljava/lang/String;)V",
+                "missing class java/lang/String",
+            ]
+        );
+    }
+
+    #[test]
+    fn find_usages_requires_class_or_string() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["java_asm_cli", "find-usages"][..], args].concat());
+        assert!(parse(&["app.apk"]).is_err());
+        assert!(parse(&["app.apk", "--string", "hello"]).is_ok());
+        assert!(parse(&["app.apk", "com.example.Main", "--string", "hello"]).is_err());
+        let cli = parse(&["app.apk", "com.example.Main", "--member", "run", "--desc", "()V"]).unwrap();
+        let Commands::FindUsages(args) = cli.command else {
+            panic!("expected find-usages");
+        };
+        assert_eq!(args.member.as_deref(), Some("run"));
+        assert_eq!(args.desc.as_deref(), Some("()V"));
+    }
+
     #[test]
     fn internal_path_reads_only_the_requested_nested_dex() {
         let dex = include_bytes!("../../asm/tests/res/dex/classes14.dex");
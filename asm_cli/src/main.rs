@@ -8,6 +8,13 @@ fn main() {
             Ok(output) => println!("{output}"),
             Err(error) => fail(&format!("failed to encode result: {error}")),
         },
+        Ok(java_asm_cli::CliOutput::Failed(result)) => {
+            match serde_json::to_string_pretty(&result) {
+                Ok(output) => println!("{output}"),
+                Err(error) => fail(&format!("failed to encode result: {error}")),
+            }
+            std::process::exit(2);
+        }
         Ok(java_asm_cli::CliOutput::Text(output)) => {
             if let Err(error) = std::io::stdout().write_all(output.as_bytes()) {
                 fail(&format!("failed to write stdout: {error}"));
@@ -1,8 +1,12 @@
+use java_asm::kotlin::KotlinMetadata;
 use java_asm::node::element::ClassNode;
 use java_asm::smali::ToSmali;
-use java_asm::{ConstContainer, JavaClassAccessFlags, JavaFieldAccessFlags, JavaMethodAccessFlags};
+use java_asm::smap::SourceMap;
+use java_asm::{
+    AsmResult, ConstContainer, JavaClassAccessFlags, JavaFieldAccessFlags, JavaMethodAccessFlags,
+};
 
-pub(crate) fn render_jvm_class(node: &ClassNode) -> String {
+pub(crate) fn render_jvm_class(node: &ClassNode) -> AsmResult<String> {
     let mut output = String::new();
     output.push_str(".class");
     append_java_flags(&mut output, node.access, JavaClassAccessFlags::const_name);
@@ -24,6 +28,12 @@ pub(crate) fn render_jvm_class(node: &ClassNode) -> String {
         output.push_str(source_file);
         output.push('\n');
     }
+    if let Some(metadata) = KotlinMetadata::from_class_node(node) {
+        for line in metadata.to_smali().render(0).lines() {
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+    }
     for field in &node.fields {
         output.push_str(".field");
         append_java_flags(&mut output, field.access, JavaFieldAccessFlags::const_name);
@@ -37,6 +47,7 @@ pub(crate) fn render_jvm_class(node: &ClassNode) -> String {
         }
         output.push('\n');
     }
+    let source_map = node.source_debug.as_deref().and_then(|content| SourceMap::parse(content).ok());
     for method in &node.methods {
         output.push_str(".method");
         append_java_flags(
@@ -49,11 +60,23 @@ pub(crate) fn render_jvm_class(node: &ClassNode) -> String {
         output.push(' ');
         output.push_str(&method.desc);
         output.push('\n');
-        if let Some(code_body) = &method.code_body {
+        if let Some(code_body) = method.code()? {
             output.push_str("  .registers ");
             output.push_str(&code_body.max_locals.to_string());
             output.push('\n');
-            for instruction in &code_body.instructions {
+            for (index, instruction) in code_body.instructions.iter().enumerate() {
+                let offset = code_body.insn_offsets.get(index);
+                for line_number in code_body.line_numbers.iter().filter(|line| Some(&line.start_pc) == offset) {
+                    output.push_str("  .line ");
+                    output.push_str(&line_number.line_number.to_string());
+                    // lines of inlined kotlin functions are mapped to their own files
+                    if let Some(source) = source_map.as_ref()
+                        .and_then(|source_map| source_map.resolve_changed(line_number.line_number as u32)) {
+                        output.push_str(" # ");
+                        output.push_str(&source.to_string());
+                    }
+                    output.push('\n');
+                }
                 for line in instruction.to_smali().render(0).lines() {
                     output.push_str("  ");
                     output.push_str(line);
@@ -64,19 +87,22 @@ pub(crate) fn render_jvm_class(node: &ClassNode) -> String {
         output.push_str(".end method\n");
     }
     output.push_str(".end class\n");
-    output
+    Ok(output)
 }
 
 fn append_java_flags(output: &mut String, flags: u16, const_name: fn(u16) -> Option<&'static str>) {
-    for bit in 0..u16::BITS {
-        let flag = 1u16 << bit;
-        if flags & flag == 0 {
-            continue;
-        }
-        let Some(name) = const_name(flag) else {
-            continue;
-        };
+    for name in java_flag_names(flags, const_name) {
         output.push(' ');
-        output.push_str(name.strip_prefix("acc_").unwrap_or(name));
+        output.push_str(name);
     }
 }
+
+/// Names of the set access flags without their `acc_` prefix, e.g. `["public", "final"]`.
+pub(crate) fn java_flag_names(flags: u16, const_name: fn(u16) -> Option<&'static str>) -> Vec<&'static str> {
+    (0..u16::BITS)
+        .map(|bit| 1u16 << bit)
+        .filter(|flag| flags & flag != 0)
+        .filter_map(const_name)
+        .map(|name| name.strip_prefix("acc_").unwrap_or(name))
+        .collect()
+}
@@ -73,7 +73,9 @@ impl EguiApp {
         // 1. process server messages
         self.ui_app.process_messages(server);
         // 2. process loading state
-        if server.loading_state.in_loading { // Keep the progress bar responsive.
+        let searching = self.ui_app.global_search().lock().in_progress;
+        let comparing = self.ui_app.comparison().lock().in_progress;
+        if server.loading_state.in_loading || searching || comparing { // Keep the progress bar responsive.
             ctx.request_repaint_after(Duration::from_millis(150));
         }
     }
@@ -1,11 +1,15 @@
 use crate::app::EguiApp;
-use egui::{ScrollArea, Ui};
-use java_asm_server::ui::ToastKind;
+use egui::{Align, Color32, ComboBox, Key, Layout, ProgressBar, RichText, ScrollArea, TextEdit, TextStyle, Ui};
+use egui_extras::{Size, StripBuilder};
+use java_asm_server::ui::{DiffRowKind, OpenFileMessage, SearchScope, ToastKind, UIMessage};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) enum BottomWindow {
     Notifications,
     Log,
+    Usages,
+    Search,
+    Compare,
 }
 
 impl EguiApp {
@@ -14,6 +18,24 @@ impl EguiApp {
             .resizable(true)
             .min_size(0.0)
             .show(ui, |ui| {
+                let mut usages = self.ui_app.usages().lock();
+                if usages.reveal {
+                    usages.reveal = false;
+                    self.bottom_window = Some(BottomWindow::Usages);
+                }
+                drop(usages);
+                let mut comparison = self.ui_app.comparison().lock();
+                if comparison.reveal {
+                    comparison.reveal = false;
+                    self.bottom_window = Some(BottomWindow::Compare);
+                }
+                drop(comparison);
+                let search_requested = ui.input(|input| {
+                    input.key_pressed(Key::F) && input.modifiers.command && input.modifiers.shift
+                });
+                if search_requested {
+                    self.bottom_window = Some(BottomWindow::Search);
+                }
                 ui.horizontal(|ui| {
                     Self::bottom_window_button(
                         ui, &mut self.bottom_window,
@@ -23,6 +45,18 @@ impl EguiApp {
                         ui, &mut self.bottom_window,
                         BottomWindow::Log, "Log / 日志",
                     );
+                    Self::bottom_window_button(
+                        ui, &mut self.bottom_window,
+                        BottomWindow::Usages, "Usages",
+                    );
+                    Self::bottom_window_button(
+                        ui, &mut self.bottom_window,
+                        BottomWindow::Search, "Search All",
+                    );
+                    Self::bottom_window_button(
+                        ui, &mut self.bottom_window,
+                        BottomWindow::Compare, "Compare",
+                    );
                     if ui.button("GitHub").clicked() {
                         ui.ctx().open_url(egui::OpenUrl {
                             url: "https://github.com/zsqw123/rust-java-asm".to_owned(),
@@ -36,6 +70,9 @@ impl EguiApp {
                 match bottom_window {
                     BottomWindow::Notifications => self.notifications_window(ui),
                     BottomWindow::Log => self.log_window(ui),
+                    BottomWindow::Usages => self.usages_window(ui),
+                    BottomWindow::Search => self.search_window(ui, search_requested),
+                    BottomWindow::Compare => self.compare_window(ui),
                 }
             });
     }
@@ -77,6 +114,164 @@ impl EguiApp {
             });
     }
 
+    fn usages_window(&self, ui: &mut Ui) {
+        let usages = self.ui_app.usages().lock();
+        let Some(target) = &usages.target else {
+            ui.weak("Right click a class or member to find its usages");
+            return;
+        };
+        ui.label(format!("{} usages of {target}", usages.results.len()));
+        ui.separator();
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for xref in &usages.results {
+                    let text = format!("{} @{}", xref.from, xref.offset);
+                    if ui.link(text).clicked() {
+                        let path = format!("L{};", xref.from.owner);
+                        let message = OpenFileMessage { path: path.into() };
+                        self.ui_app.send_message(UIMessage::OpenFile(message));
+                    }
+                }
+            });
+    }
+
+    fn search_window(&self, ui: &mut Ui, focus_requested: bool) {
+        let mut search = self.ui_app.global_search().lock();
+        let mut search_triggered = false;
+        ui.horizontal(|ui| {
+            let edit_response = TextEdit::singleline(&mut search.query)
+                .hint_text("Search strings, members and instructions in all classes...")
+                .desired_width(360.0)
+                .show(ui)
+                .response
+                .response;
+            if focus_requested {
+                edit_response.request_focus();
+            }
+            let enter_pressed = edit_response.lost_focus()
+                && ui.input(|input| input.key_pressed(Key::Enter));
+            ComboBox::from_id_salt("global_search_scope")
+                .selected_text(search.scope.label())
+                .show_ui(ui, |ui| {
+                    for scope in SearchScope::ALL {
+                        ui.selectable_value(&mut search.scope, scope, scope.label());
+                    }
+                });
+            ui.checkbox(&mut search.case_sensitive, "Aa");
+            search_triggered = ui.button("Search").clicked() || enter_pressed;
+            if search.in_progress {
+                ui.add(ProgressBar::new(search.progress()).desired_width(160.0).show_percentage());
+            }
+            let truncated = if search.truncated { "+" } else { "" };
+            ui.label(format!("{}{truncated} results", search.results.len()));
+        });
+        if search_triggered {
+            self.ui_app.send_message(UIMessage::GlobalSearch);
+        }
+        ui.separator();
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show_rows(ui, row_height, search.results.len(), |ui, range| {
+                for matched in &search.results[range] {
+                    let text = format!("{}:{}  {}", matched.class_key, matched.line + 1, matched.text.trim());
+                    let label = egui::Label::new(egui::RichText::new(text).monospace())
+                        .sense(egui::Sense::click())
+                        .truncate();
+                    if ui.add(label).clicked() {
+                        let message = OpenFileMessage { path: matched.class_key.clone() };
+                        self.ui_app.send_message(UIMessage::OpenFileAt(message, matched.line));
+                    }
+                }
+            });
+    }
+
+    fn compare_window(&self, ui: &mut Ui) {
+        let mut comparison = self.ui_app.comparison().lock();
+        if comparison.in_progress {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Comparing with {}...", comparison.file_name));
+            });
+            return;
+        }
+        let Some(diff) = comparison.diff.clone() else {
+            ui.weak("Use Compare... to compare the opened input with another build");
+            return;
+        };
+        ui.label(format!(
+            "Compared with {}: {} added, {} removed, {} changed, {} unchanged classes",
+            comparison.file_name, diff.added.len(), diff.removed.len(),
+            diff.changed.len(), diff.unchanged_count,
+        ));
+        ui.separator();
+        let rows = comparison.selected_rows();
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        StripBuilder::new(ui)
+            .size(Size::relative(0.25))
+            .size(Size::remainder())
+            .horizontal(|mut strip| {
+                strip.cell(|ui| {
+                    ScrollArea::vertical()
+                        .id_salt("compare_classes")
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| {
+                            for (index, class) in diff.changed.iter().enumerate() {
+                                let selected = comparison.selected == Some(index);
+                                if ui.selectable_label(selected, format!("~ {}", class.name)).clicked() {
+                                    comparison.selected = Some(index);
+                                }
+                            }
+                            for name in &diff.added {
+                                ui.label(format!("+ {name}"));
+                            }
+                            for name in &diff.removed {
+                                ui.label(format!("- {name}"));
+                            }
+                        });
+                });
+                strip.cell(|ui| {
+                    if rows.is_empty() {
+                        ui.weak("Select a changed class to show it side by side");
+                        return;
+                    }
+                    ScrollArea::both()
+                        .id_salt("compare_rows")
+                        .auto_shrink([false, false])
+                        .show_rows(ui, row_height, rows.len(), |ui, range| {
+                            let width = (ui.available_width() / 2.0 - ui.spacing().item_spacing.x).max(0.0);
+                            for row in &rows[range] {
+                                ui.horizontal(|ui| {
+                                    Self::diff_cell(ui, row.kind, row.old.as_deref(), false, width);
+                                    Self::diff_cell(ui, row.kind, row.new.as_deref(), true, width);
+                                });
+                            }
+                        });
+                });
+            });
+    }
+
+    fn diff_cell(ui: &mut Ui, kind: DiffRowKind, text: Option<&str>, new_side: bool, width: f32) {
+        let dark_mode = ui.visuals().dark_mode;
+        let mut text = RichText::new(text.unwrap_or_default()).monospace();
+        text = match kind {
+            DiffRowKind::Header => text.strong(),
+            DiffRowKind::Same => text,
+            DiffRowKind::Changed if new_side => text.color(if dark_mode {
+                Color32::from_rgb(140, 220, 140)
+            } else {
+                Color32::from_rgb(25, 110, 25)
+            }),
+            DiffRowKind::Changed => text.color(ui.visuals().error_fg_color),
+        };
+        let size = egui::vec2(width, ui.text_style_height(&TextStyle::Monospace));
+        ui.allocate_ui_with_layout(size, Layout::left_to_right(Align::Center), |ui| {
+            ui.set_width(width);
+            ui.add(egui::Label::new(text).truncate());
+        });
+    }
+
     fn log_window(&self, ui: &mut Ui) {
         let current_records = self.log_holder.records.lock();
         let log_text = current_records
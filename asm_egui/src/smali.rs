@@ -3,6 +3,7 @@ use egui::text::{LayoutJob, TextFormat};
 use egui::containers::{Popup, PopupCloseBehavior, PopupKind};
 use egui::{Align, Button, FontId, Id, Key, Modifiers, Response, ScrollArea, SetOpenCommand, TextEdit, TextStyle, Ui, Vec2};
 use java_asm::smali::SmaliToken;
+use java_asm::xref::{MemberRef, XrefTarget};
 use java_asm::StrRef;
 use java_asm_server::ui::{AppContainer, FindState, OpenFileMessage, SmaliLine, SmaliLineToken, UIMessage};
 use java_asm_server::AsmServer;
@@ -25,7 +26,7 @@ pub fn smali_layout(
     let lines = selected_tab.rendered_lines.clone();
     let reveal_line = find_toolbar(
         ui, &mut selected_tab.find, selected_tab.file_key.as_ref(), &lines,
-    );
+    ).or(selected_tab.reveal_line.take());
     let row_height = ui.text_style_height(&TextStyle::Monospace);
     let spacing_y = ui.spacing().item_spacing.y;
 
@@ -46,6 +47,7 @@ pub fn smali_layout(
     let mut render_context = RenderContext {
         app: &app,
         server,
+        file_key: selected_tab.file_key.as_ref(),
         font: &font,
         lines: lines.as_ref(),
         find: &selected_tab.find,
@@ -138,6 +140,8 @@ fn find_toolbar(
 struct RenderContext<'a> {
     pub app: &'a AppContainer,
     pub server: &'a AsmServer,
+    // the descriptor of the class in current tab.
+    pub file_key: &'a str,
     pub lines: &'a [SmaliLine],
     pub find: &'a FindState,
     pub find_open: bool,
@@ -155,8 +159,8 @@ impl<'a> RenderContext<'a> {
         let line = &self.lines[line_index];
         let line_response = ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
-            for token_item in &line.tokens {
-                self.token(ui, token_item, line_index);
+            for (token_index, token_item) in line.tokens.iter().enumerate() {
+                self.token(ui, token_item, line_index, token_index);
             }
         });
         if self.reveal_line == Some(line_index) {
@@ -189,7 +193,8 @@ impl<'a> RenderContext<'a> {
 
 
     fn token(
-        &mut self, ui: &mut Ui, rendered_token: &SmaliLineToken, line_index: usize,
+        &mut self, ui: &mut Ui, rendered_token: &SmaliLineToken,
+        line_index: usize, token_index: usize,
     ) -> Response {
         let token = &rendered_token.token;
         let dft_color = self.dft_color;
@@ -241,7 +246,13 @@ impl<'a> RenderContext<'a> {
                 self.styled_text(ui, line_index, rendered_token, self.smali_style.register)
             },
             SmaliToken::MemberName(_) => {
-                self.styled_text(ui, line_index, rendered_token, dft_color)
+                let text_ui = self.styled_text(ui, line_index, rendered_token, dft_color);
+                if let Some(member) = self.member_at(line_index, token_index) {
+                    text_ui.context_menu(|ui| {
+                        self.member_menu(ui, &member);
+                    });
+                }
+                text_ui
             },
             SmaliToken::Descriptor(s) => {
                 let mut text_ui = self.styled_text(
@@ -343,10 +354,47 @@ impl<'a> RenderContext<'a> {
                     ui.label("type: ");
                     self.render_single_descriptor(ui, descriptor);
                 });
+                let element = descriptor.trim_start_matches('[');
+                if element.starts_with('L') {
+                    let target = XrefTarget::Class(internal_name(element).into());
+                    self.find_usages_button(ui, target);
+                }
             }
         });
     }
 
+    // member names are rendered as `owner name descriptor` for references,
+    // and `name descriptor` for declarations in current class.
+    fn member_at(&self, line_index: usize, token_index: usize) -> Option<MemberRef> {
+        let tokens = &self.lines[line_index].tokens;
+        let SmaliToken::MemberName(name) = &tokens.get(token_index)?.token else { return None; };
+        let is_raw = |token: &&SmaliLineToken| matches!(token.token, SmaliToken::Raw(_));
+        let next = tokens[token_index + 1..].iter().find(|t| !is_raw(t))?;
+        let SmaliToken::Descriptor(desc) = &next.token else { return None; };
+        let owner = match tokens[..token_index].iter().rev().find(|t| !is_raw(t)) {
+            Some(SmaliLineToken { token: SmaliToken::Descriptor(owner), .. }) => &**owner,
+            _ => self.file_key,
+        };
+        Some(MemberRef::new(internal_name(owner), name, desc))
+    }
+
+    fn member_menu(&mut self, ui: &mut Ui, member: &MemberRef) {
+        ui.label(member.to_string());
+        let target = if member.is_method() {
+            XrefTarget::Method(member.clone())
+        } else {
+            XrefTarget::Field(member.clone())
+        };
+        self.find_usages_button(ui, target);
+    }
+
+    fn find_usages_button(&mut self, ui: &mut Ui, target: XrefTarget) {
+        if ui.button("Find Usages").clicked() {
+            self.app.send_message(UIMessage::FindUsages(target));
+            ui.close();
+        }
+    }
+
     // function descriptors, e.g. (Ljava/lang/String;I)V, show ui like:
     // arg1: Ljava/lang/String;
     // arg2: I
@@ -432,6 +480,11 @@ impl<'a> RenderContext<'a> {
     }
 }
 
+// `Lcom/a/B;` -> `com/a/B`
+fn internal_name(descriptor: &str) -> &str {
+    descriptor.strip_prefix('L').and_then(|d| d.strip_suffix(';')).unwrap_or(descriptor)
+}
+
 fn append_text(
     job: &mut LayoutJob, text: &str, font: &FontId, color: Color32,
     background: Option<Color32>,
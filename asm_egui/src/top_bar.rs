@@ -42,6 +42,8 @@ impl EguiApp {
             if self.server.lock().is_some() {
                 self.locate_button(ui);
                 self.export_button(ui);
+                self.mapping_button(ui);
+                self.compare_button(ui);
                 // searchable file path
                 self.file_path_input(ui);
             }
@@ -64,7 +66,7 @@ impl EguiApp {
         }
         let search_input_triggered = search_input_opened || search_input_changed;
 
-        if locked_top.search_result.items.is_empty() { return; }
+        if locked_top.search_result.items.is_empty() && locked_top.member_search_result.is_empty() { return; }
         drop(locked_top);
 
         Popup::from_response(&edit_path_ui)
@@ -114,6 +116,24 @@ impl EguiApp {
         });
     }
 
+    fn mapping_button(&mut self, ui: &mut Ui) {
+        if !ui.button("Mapping...").on_hover_text("Show original names with a ProGuard / R8, Tiny v2, (T)SRG or Enigma mapping").clicked() {
+            return;
+        }
+        let server_locked = self.server.lock();
+        let Some(server) = server_locked.deref() else { return; };
+        server.dialog_to_open_mapping(self.ui_app.clone());
+    }
+
+    fn compare_button(&mut self, ui: &mut Ui) {
+        if !ui.button("Compare...").on_hover_text("Compare classes, members and code with another build").clicked() {
+            return;
+        }
+        let server_locked = self.server.lock();
+        let Some(server) = server_locked.deref() else { return; };
+        server.dialog_to_compare(self.ui_app.clone());
+    }
+
     fn get_current_tab(&self) -> Option<Tab> {
         let locked_content = self.ui_app.content().lock();
         let Content { opened_tabs, selected } = locked_content.deref();
@@ -130,7 +150,7 @@ impl EguiApp {
         let target_width_for_content = max_width - last_time_remaining;
 
         let edit_path_ui = TextEdit::singleline(file_path)
-            .hint_text("Enter class name to search...")
+            .hint_text("Enter class or member name to search...")
             .desired_width(target_width_for_content).show(ui).response;
 
         let remaining_width = ui.min_rect().width() - target_width_for_content;
@@ -141,7 +161,10 @@ impl EguiApp {
     }
 
     fn popup_file_path_ui(&mut self, ui: &mut Ui, popup_id: Id) {
-        let search_results = self.ui_app.top().lock().search_result.clone();
+        let locked_top = self.ui_app.top().lock();
+        let search_results = locked_top.search_result.clone();
+        let member_results = locked_top.member_search_result.clone();
+        drop(locked_top);
         let style = ui.style();
         let font = TextStyle::Monospace.resolve(&style);
 
@@ -154,9 +177,8 @@ impl EguiApp {
         let highlight_text_format = TextFormat::simple(font, highlight_color);
 
 
-        for result in search_results.items {
-            let path = result.content.to_string();
-            let sections = Self::get_highlight_sections(&path, result.indices);
+        let highlighted_job = |content: &str, indices: BitSet| {
+            let sections = Self::get_highlight_sections(content, indices);
             let mut text_layout_job = LayoutJob::default();
             for (section, highlighted) in sections {
                 if highlighted {
@@ -165,6 +187,11 @@ impl EguiApp {
                     text_layout_job.append(&section, 0.0, dft_text_format.clone())
                 }
             }
+            text_layout_job
+        };
+
+        for result in search_results.items {
+            let text_layout_job = highlighted_job(&result.content, result.indices);
             let selectable_label = ui.selectable_label(false, text_layout_job);
             if selectable_label.clicked() {
                 let message = UIMessage::OpenFile(
@@ -174,6 +201,17 @@ impl EguiApp {
                 Popup::close_id(ui.ctx(), popup_id);
             }
         }
+
+        if member_results.is_empty() { return; }
+        ui.separator();
+        ui.weak("Methods & Fields");
+        for (member, result) in member_results {
+            let text_layout_job = highlighted_job(&result.content, result.indices);
+            if ui.selectable_label(false, text_layout_job).clicked() {
+                self.ui_app.send_message(UIMessage::OpenMember(member));
+                Popup::close_id(ui.ctx(), popup_id);
+            }
+        }
     }
 
     fn get_highlight_sections(path: &str, bits: BitSet) -> Vec<(String, bool)> {
@@ -2,10 +2,21 @@ use crate::impls::server::{ProgressMessage, ServerMessage};
 use crate::server::OpenFileError;
 use crate::{Accessor, ExportableSource};
 use futures::stream::{FuturesUnordered, StreamExt};
+use java_asm::android::{
+    annotate_resource_ids, extract_dex_files, is_oat_bytes, is_vdex_bytes, AndroidManifest,
+    ResourceTable, XmlDocument,
+};
 use java_asm::dex::{ClassDef, DexFile, DexFileAccessor};
+use java_asm::hierarchy::{ClassHierarchy, HierarchyClass};
+use java_asm::kotlin::KotlinMetadata;
+use java_asm::mapping::TreeRemapper;
+use java_asm::node::element::ClassNode;
+use java_asm::remap::{remap_smali, Remapper};
 use java_asm::smali::{SmaliNode, SmaliToken, stb};
-use java_asm::{DescriptorRef, StrRef};
+use java_asm::xref::{MemberRef, Xref, XrefIndex, XrefTarget};
+use java_asm::{AsmResult, DescriptorRef, StrRef};
 use log::{error, warn};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::future::Future;
 use std::io::{Cursor, Read};
@@ -16,6 +27,63 @@ use zip::ZipArchive;
 pub struct DexAccessor {
     pub map: HashMap<DescriptorRef, ClassPosition>,
     pub dex_sources: HashMap<StrRef, Arc<DexFileAccessor>>,
+    pub hierarchy: ClassHierarchy,
+    /// Original names of obfuscated classes and members, all names exposed by this accessor
+    /// are original names when it is set. See [Accessor::set_mapping].
+    pub mapping: Option<Arc<TreeRemapper>>,
+    /// Decoded resource files like the `AndroidManifest.xml` of APKs, by their source name.
+    /// They are opened like classes, see [Accessor::read_resources].
+    pub resources: HashMap<StrRef, ResourceFile>,
+}
+
+pub enum ResourceFile {
+    Xml(XmlDocument),
+    /// A `resources.arsc`, it's shown as one file per resource type and resolves the resource
+    /// ids in smali and XML.
+    Table(ResourceTable),
+}
+
+impl ResourceFile {
+    /// `table` is the resource table of the same APK.
+    fn to_smali(&self, source: &StrRef, table: Option<&ResourceTable>) -> SmaliNode {
+        let mut children = vec![stb().push(SmaliToken::SourceInfo(source.clone())).s()];
+        let lines = match self {
+            ResourceFile::Xml(document) => {
+                if document.root.name.as_ref() == "manifest" {
+                    let summary = AndroidManifest::from_document(document).to_string();
+                    children.extend(summary.lines().map(|line| stb().other(format!("<!-- {line} -->").into()).s()));
+                }
+                document.to_xml_with(table.unwrap_or(&ResourceTable::default()))
+            }
+            ResourceFile::Table(table) => {
+                table.packages.iter().map(|package| format!("# package 0x{:02x} {}\n", package.id, package.name)).collect()
+            }
+        };
+        children.extend(lines.lines().map(|line| stb().other(line.into()).s()));
+        SmaliNode { children, ..Default::default() }
+    }
+}
+
+/// The entries of one resource type, e.g. `string`, of all packages of a table.
+fn resource_type_smali(source: &StrRef, table: &ResourceTable, type_name: &str) -> SmaliNode {
+    let mut children = vec![stb().push(SmaliToken::SourceInfo(source.clone())).s()];
+    let resource_types = table.packages.iter()
+        .flat_map(|package| &package.types)
+        .filter(|resource_type| resource_type.name.as_ref() == type_name);
+    for resource_type in resource_types {
+        let description = table.describe_type(resource_type);
+        children.extend(description.lines().map(|line| stb().other(line.into()).s()));
+    }
+    SmaliNode { children, ..Default::default() }
+}
+
+/// The source of the resource table in the same directory as `source`,
+/// e.g. `app.apk!resources.arsc` for `app.apk!AndroidManifest.xml`.
+fn sibling_table_source(source: &str) -> String {
+    match source.rsplit_once('!') {
+        Some((archive, _)) => format!("{archive}!{RESOURCES_ENTRY}"),
+        None => RESOURCES_ENTRY.to_string(),
+    }
 }
 
 type ClassPosition = (Arc<DexFileAccessor>, ClassDef);
@@ -215,6 +283,8 @@ where
         yield_step().await;
     }
     make_source_names_unique(&mut dex_files);
+    let (dex_files, resource_files): (Vec<_>, Vec<_>) = dex_files.into_iter()
+        .partition(|(_, bytes)| is_dex_bytes(bytes));
 
     // reports
     if dex_files.is_empty() {
@@ -228,7 +298,25 @@ where
     ).await;
     yield_step().await;
 
-    read_dex_sources(dex_files, sender, yield_step).await
+    let mut accessor = read_dex_sources(dex_files, sender, yield_step).await?;
+    accessor.resources = read_resources(resource_files);
+    Ok(accessor)
+}
+
+fn read_resources(resource_files: Vec<(String, Vec<u8>)>) -> HashMap<StrRef, ResourceFile> {
+    let mut resources = HashMap::with_capacity(resource_files.len());
+    for (source, bytes) in resource_files {
+        let resource = if ResourceTable::is_resource_table(&bytes) {
+            ResourceTable::from_bytes(&bytes).map(ResourceFile::Table)
+        } else {
+            XmlDocument::from_bytes(&bytes).map(ResourceFile::Xml)
+        };
+        match resource {
+            Ok(resource) => { resources.insert(source.into(), resource); }
+            Err(err) => warn!("Error when decoding {source}: {err:?}"),
+        }
+    }
+    resources
 }
 
 const MAX_ARCHIVE_DEPTH: usize = 16;
@@ -297,6 +385,15 @@ where
                 bytes,
             )]);
         }
+        if is_dex_container_bytes(&bytes) {
+            if report_current_archive && progress.add_entry() {
+                yield_step.collect_yield().await;
+            }
+            let source = source_prefix.unwrap_or(input_name);
+            return embedded_dex_files(source, &bytes).map_err(|err| OpenFileError::Custom(format!(
+                "unsupported input: {input_name}: {err:?}"
+            )));
+        }
         if !is_zip_bytes(&bytes) {
             return Err(OpenFileError::Custom(format!(
                 "unsupported input: {input_name}"
@@ -324,7 +421,10 @@ where
             if capacity < 4 { continue; }
             let mut header = [0; 4];
             entry.read_exact(&mut header).map_err(OpenFileError::Io)?;
-            if !is_dex_bytes(&header) && !is_zip_bytes(&header) { continue; }
+            let is_resource = (entry_name == MANIFEST_ENTRY && XmlDocument::is_binary_xml(&header))
+                || (entry_name == RESOURCES_ENTRY && ResourceTable::is_resource_table(&header));
+            let is_container = is_dex_container_bytes(&header);
+            if !is_dex_bytes(&header) && !is_zip_bytes(&header) && !is_container && !is_resource { continue; }
 
             // read entry start
             let mut entry_bytes = Vec::with_capacity(capacity);
@@ -334,8 +434,14 @@ where
                 Some(prefix) => format!("{prefix}!{entry_name}"),
                 None => entry_name.clone(),
             };
-            if is_dex_bytes(&entry_bytes) {
+            if is_dex_bytes(&entry_bytes) || is_resource {
                 dex_files.push((entry_source, entry_bytes));
+            } else if is_container {
+                // other ELF files like native libraries can have no extension
+                match embedded_dex_files(&entry_source, &entry_bytes) {
+                    Ok(embedded) => dex_files.extend(embedded),
+                    Err(err) => warn!("Skipped {entry_source}: {err:?}"),
+                }
             } else if is_zip_bytes(&entry_bytes) {
                 nested_entry = Some((entry_name, entry_bytes, entry_source));
             }
@@ -361,7 +467,7 @@ where
 }
 
 fn count_valid_entries(input_name: &str, bytes: &[u8]) -> Result<usize, OpenFileError> {
-    if is_dex_bytes(bytes) {
+    if is_dex_bytes(bytes) || is_dex_container_bytes(bytes) {
         return Ok(1);
     }
     if !is_zip_bytes(bytes) {
@@ -400,12 +506,42 @@ fn is_zip_bytes(bytes: &[u8]) -> bool {
     matches!(bytes.get(..4), Some(b"PK\x03\x04" | b"PK\x05\x06" | b"PK\x07\x08"))
 }
 
+/// VDEX files and OAT files (`.odex`, `.oat`) of on-device dumps which embed dex files.
+fn is_dex_container_bytes(bytes: &[u8]) -> bool {
+    is_vdex_bytes(bytes) || is_oat_bytes(bytes)
+}
+
+/// The dex files of a VDEX or an OAT file named like `base.vdex!classes2.dex`. Compact dex
+/// files can't be read yet and are skipped.
+fn embedded_dex_files(source: &str, bytes: &[u8]) -> AsmResult<Vec<(String, Vec<u8>)>> {
+    let embedded = extract_dex_files(bytes)?;
+    if embedded.is_empty() {
+        warn!("{source} contains no dex files, they may be in the APK it is compiled from");
+    }
+    let mut dex_files = Vec::with_capacity(embedded.len());
+    for (index, dex) in embedded.into_iter().enumerate() {
+        let name = format!("{source}!{}", dex.name(index));
+        if dex.compact {
+            warn!("Skipped {name}: compact dex files are not supported");
+            continue;
+        }
+        dex_files.push((name, dex.bytes));
+    }
+    Ok(dex_files)
+}
+
+/// The binary XML manifest in the root of every APK.
+const MANIFEST_ENTRY: &str = "AndroidManifest.xml";
+/// The compiled resource table in the root of every APK.
+const RESOURCES_ENTRY: &str = "resources.arsc";
+
 fn is_possible_input_entry(name: &str) -> bool {
+    if name == MANIFEST_ENTRY || name == RESOURCES_ENTRY { return true; }
     let Some((_, extension)) = name.rsplit_once('.') else {
         // Keep magic-based detection for entries without an extension.
         return true;
     };
-    ["dex", "apk", "apks", "xapk", "aab", "zip", "jar"]
+    ["dex", "apk", "apks", "xapk", "aab", "zip", "jar", "vdex", "odex", "oat"]
         .iter()
         .any(|candidate| extension.eq_ignore_ascii_case(candidate))
 }
@@ -487,7 +623,78 @@ fn build_accessor(indexed_dexes: impl IntoIterator<Item=IndexedDex>) -> DexAcces
         }
     }
     map.shrink_to_fit();
-    DexAccessor { map, dex_sources }
+    let hierarchy = build_hierarchy(&map, None);
+    DexAccessor { map, dex_sources, hierarchy, mapping: None, resources: HashMap::new() }
+}
+
+fn build_hierarchy(
+    map: &HashMap<DescriptorRef, ClassPosition>, mapping: Option<&TreeRemapper>,
+) -> ClassHierarchy {
+    let mut hierarchy = ClassHierarchy::new();
+    for (class_name, (accessor, class_def)) in map {
+        let class = match HierarchyClass::from_class_def(accessor, class_def) {
+            Ok(class) => class,
+            Err(err) => {
+                warn!("Error when indexing hierarchy of {class_name}: {err:?}");
+                continue;
+            }
+        };
+        let class = match mapping {
+            None => class,
+            Some(mapping) => HierarchyClass {
+                name: map_type(mapping, &class.name),
+                access: class.access,
+                super_name: class.super_name.map(|name| map_type(mapping, &name)),
+                interfaces: class.interfaces.iter().map(|name| map_type(mapping, name)).collect(),
+            },
+        };
+        hierarchy.add_class(class);
+    }
+    hierarchy
+}
+
+fn map_type(mapping: &TreeRemapper, name: &StrRef) -> StrRef {
+    mapping.map_type(name).unwrap_or_else(|_| name.clone())
+}
+
+fn map_member(mapping: &TreeRemapper, member: &MemberRef) -> MemberRef {
+    let name = if member.is_method() {
+        mapping.map_method_name(&member.owner, &member.name, &member.desc)
+    } else {
+        mapping.map_field_name(&member.owner, &member.name, &member.desc)
+    };
+    MemberRef {
+        owner: map_type(mapping, &member.owner),
+        name: name.unwrap_or_else(|| member.name.clone()),
+        desc: mapping.map_desc(&member.desc).unwrap_or_else(|_| member.desc.clone()),
+    }
+}
+
+impl DexAccessor {
+    /// The key in [DexAccessor::map] of a class key which may use the original name.
+    fn obfuscated_key<'a>(&self, class_key: &'a str) -> Cow<'a, str> {
+        let Some(mapping) = &self.mapping else { return Cow::Borrowed(class_key); };
+        let internal_name = class_key.strip_prefix('L').and_then(|k| k.strip_suffix(';')).unwrap_or(class_key);
+        match mapping.unmap_class(internal_name) {
+            Some(obfuscated) => Cow::Owned(format!("L{obfuscated};")),
+            None => Cow::Borrowed(class_key),
+        }
+    }
+
+    fn resource_table(&self, source: &str) -> Option<&ResourceTable> {
+        match self.resources.get(source)? {
+            ResourceFile::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// The name like `@string/app_name` of a resource id in any of the loaded resource tables.
+    fn resource_name(&self, id: u32) -> Option<String> {
+        self.resources.values().find_map(|resource| match resource {
+            ResourceFile::Table(table) => table.reference_name(id),
+            _ => None,
+        })
+    }
 }
 
 pub(crate) fn resolve_dex(
@@ -529,15 +736,48 @@ async fn send_loaded(
 
 impl Accessor for DexAccessor {
     fn read_classes(&self) -> Vec<StrRef> {
-        self.map.keys().cloned().collect()
+        match &self.mapping {
+            None => self.map.keys().cloned().collect(),
+            Some(mapping) => self.map.keys()
+                .map(|key| mapping.map_desc(key).unwrap_or_else(|_| key.clone()))
+                .collect(),
+        }
     }
 
     fn exist_class(&self, class_key: &str) -> bool {
-        self.map.contains_key(class_key)
+        self.map.contains_key(&*self.obfuscated_key(class_key))
+    }
+
+    fn read_resources(&self) -> Vec<StrRef> {
+        let mut keys = Vec::with_capacity(self.resources.len());
+        for (source, resource) in &self.resources {
+            let ResourceFile::Table(table) = resource else {
+                keys.push(source.clone());
+                continue;
+            };
+            // one file per type, so the tree shows the table as a directory
+            let mut type_names: Vec<&str> = table.packages.iter()
+                .flat_map(|package| &package.types)
+                .map(|resource_type| resource_type.name.as_ref())
+                .collect();
+            type_names.sort_unstable();
+            type_names.dedup();
+            keys.extend(type_names.into_iter().map(|type_name| format!("{source}/{type_name}").into()));
+        }
+        keys
     }
 
     fn read_content(&self, class_key: &str) -> Option<SmaliNode> {
-        let class_position = self.map.get(class_key);
+        if let Some((source, resource)) = self.resources.get_key_value(class_key) {
+            let table = self.resource_table(&sibling_table_source(source));
+            return Some(resource.to_smali(source, table));
+        }
+        if let Some((source, type_name)) = class_key.rsplit_once('/')
+            && let Some(table) = self.resource_table(source) {
+            return Some(resource_type_smali(&source.into(), table, type_name));
+        }
+        let obfuscated_key = self.obfuscated_key(class_key);
+        let class_position = self.map.get(&*obfuscated_key);
         if let Some((accessor, class_def)) = class_position {
             let dex_file_name = accessor.file_name.clone();
             let smali_node = accessor.get_class_smali(*class_def).ok();
@@ -546,8 +786,20 @@ impl Accessor for DexAccessor {
                 return None;
             };
             let mut smali_node = smali_node;
+            if let Some(mapping) = &self.mapping {
+                let owner = obfuscated_key.strip_prefix('L').and_then(|k| k.strip_suffix(';')).unwrap_or(&obfuscated_key);
+                remap_smali(&mut smali_node, owner, mapping.as_ref());
+            }
+            if self.resources.values().any(|resource| matches!(resource, ResourceFile::Table(_))) {
+                annotate_resource_ids(&mut smali_node, &|id| self.resource_name(id));
+            }
             let source_tag_smali = stb().push(SmaliToken::SourceInfo(dex_file_name)).s();
             smali_node.children.insert(0, source_tag_smali);
+            match KotlinMetadata::from_dex_class(accessor, class_def) {
+                Ok(Some(metadata)) => smali_node.children.insert(1, metadata.to_smali()),
+                Ok(None) => {}
+                Err(err) => warn!("Failed to read kotlin metadata of {class_key}: {err:?}"),
+            }
             Some(smali_node)
         } else {
             warn!("No class content found for: {}", class_key);
@@ -555,6 +807,74 @@ impl Accessor for DexAccessor {
         }
     }
 
+    fn read_members(&self) -> Vec<MemberRef> {
+        let mut members = Vec::new();
+        for (class_key, (accessor, class_def)) in &self.map {
+            if class_def.class_data_off == 0 { continue; }
+            let class_data = match accessor.get_class_element(class_def.class_data_off) {
+                Ok(class_data) => class_data,
+                Err(err) => {
+                    warn!("Error when reading members of {class_key}: {err:?}");
+                    continue;
+                }
+            };
+            let class_key: &str = class_key;
+            let owner = class_key.strip_prefix('L').and_then(|k| k.strip_suffix(';')).unwrap_or(class_key);
+            for field in class_data.static_fields.iter().chain(&class_data.instance_fields) {
+                members.push(MemberRef::new(owner, &field.name, &field.descriptor));
+            }
+            for method in class_data.direct_methods.iter().chain(&class_data.virtual_methods) {
+                let desc = format!("({}){}", method.parameters.join(""), method.return_type);
+                members.push(MemberRef::new(owner, &method.name, &desc));
+            }
+        }
+        if let Some(mapping) = &self.mapping {
+            members = members.iter().map(|member| map_member(mapping, member)).collect();
+        }
+        members
+    }
+
+    fn hierarchy(&self) -> &ClassHierarchy {
+        &self.hierarchy
+    }
+
+    fn build_xref(&self) -> XrefIndex {
+        let mut xref = XrefIndex::new();
+        for (class_name, (accessor, class_def)) in &self.map {
+            if let Err(err) = xref.add_class_def(accessor, class_def) {
+                warn!("Error when indexing references of {class_name}: {err:?}");
+            }
+        }
+        let Some(mapping) = &self.mapping else { return xref; };
+        let mut mapped = XrefIndex::new();
+        for Xref { from, offset, kind, target } in xref.xrefs() {
+            let target = match target {
+                XrefTarget::Method(member) => XrefTarget::Method(map_member(mapping, member)),
+                XrefTarget::Field(member) => XrefTarget::Field(map_member(mapping, member)),
+                XrefTarget::Class(name) => XrefTarget::Class(map_type(mapping, name)),
+                XrefTarget::String(value) => XrefTarget::String(value.clone()),
+            };
+            mapped.add(Xref { from: map_member(mapping, from), offset: *offset, kind: *kind, target });
+        }
+        mapped
+    }
+
+    fn set_mapping(&mut self, mapping: Option<Arc<TreeRemapper>>) {
+        self.hierarchy = build_hierarchy(&self.map, mapping.as_deref());
+        self.mapping = mapping;
+    }
+
+    fn class_nodes(&self) -> Vec<ClassNode> {
+        let mut nodes = Vec::with_capacity(self.map.len());
+        for (class_name, (accessor, class_def)) in &self.map {
+            match accessor.get_class_node(*class_def) {
+                Ok(node) => nodes.push(node),
+                Err(err) => warn!("Error when converting {class_name} to a class node: {err:?}"),
+            }
+        }
+        nodes
+    }
+
     // Source keys are DEX names, optionally containing `!`-separated nested archive paths.
     fn peek_source(&self, source_key: &str) -> Option<ExportableSource> {
         let dex_source = self.dex_sources.get(source_key);
@@ -1,4 +1,5 @@
 use bit_set::BitSet;
+use java_asm::xref::MemberRef;
 use java_asm::StrRef;
 use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
@@ -15,6 +16,7 @@ pub struct FuzzyMatchModel {
     pattern: Pattern,
     // length is the same as input
     inc_infos: IncrementalInfos,
+    normalize: fn(&str) -> StrRef,
 }
 
 // if change `a` to `ab`, just search things from (previous matched items + remaining items).
@@ -33,11 +35,45 @@ fn normalize_search_input(input: &str) -> StrRef {
         .into()
 }
 
+// Member keys look like `Lfoo/Bar;->run()V`, so `Bar.run` is split into two atoms,
+// and each of them matches anywhere in the key.
+fn normalize_member_input(input: &str) -> StrRef {
+    input
+        .chars()
+        .map(|ch| if ch == '.' || ch == '/' || ch.is_whitespace() { ' ' } else { ch })
+        .collect::<String>()
+        .trim()
+        .into()
+}
+
+/// The text matched by member searches, e.g. `Lfoo/Bar;->run()V` or `Lfoo/Bar;->name:I`.
+pub fn member_search_key(member: &MemberRef) -> StrRef {
+    let MemberRef { owner, name, desc } = member;
+    if member.is_method() {
+        format!("L{owner};->{name}{desc}").into()
+    } else {
+        format!("L{owner};->{name}:{desc}").into()
+    }
+}
+
 impl FuzzyMatchModel {
     pub fn new(
         input: StrRef, items: &[StrRef], top_n: usize,
     ) -> Self {
-        let input = normalize_search_input(&input);
+        Self::new_with_normalize(input, items, top_n, normalize_search_input)
+    }
+
+    /// Model for items built by [member_search_key].
+    pub fn new_for_members(
+        input: StrRef, items: &[StrRef], top_n: usize,
+    ) -> Self {
+        Self::new_with_normalize(input, items, top_n, normalize_member_input)
+    }
+
+    fn new_with_normalize(
+        input: StrRef, items: &[StrRef], top_n: usize, normalize: fn(&str) -> StrRef,
+    ) -> Self {
+        let input = normalize(&input);
         let config = Config::DEFAULT.match_paths();
         let matcher = Matcher::new(config);
         let pattern = Pattern::parse(&input, CaseMatching::Ignore, Normalization::Never);
@@ -50,11 +86,12 @@ impl FuzzyMatchModel {
             matcher,
             pattern,
             inc_infos: inc_info,
+            normalize,
         }
     }
 
     pub fn search_with_new_input(&mut self, new_input: StrRef) -> SearchResult {
-        let new_input = normalize_search_input(&new_input);
+        let new_input = (self.normalize)(&new_input);
         let old_input = self.input.clone();
         let old_len = old_input.len();
         let new_len = new_input.len();
@@ -223,7 +260,8 @@ pub struct SearchResult {
 
 #[cfg(test)]
 mod tests {
-    use crate::impls::fuzzy::{FuzzyMatchModel, SearchResult, SearchResultItem};
+    use crate::impls::fuzzy::{member_search_key, FuzzyMatchModel, SearchResult, SearchResultItem};
+    use java_asm::xref::MemberRef;
     use bit_set::BitSet;
     use java_asm::{vec_str_ref, StrRef};
     use rand::prelude::SliceRandom;
@@ -336,6 +374,26 @@ mod tests {
         assert_eq!(result.items[0].content, "core/models/SafeListAdapter".into());
     }
 
+    #[test]
+    fn test_member_search() {
+        let members = [
+            MemberRef::new("foo/Bar", "onCreate", "(Landroid/os/Bundle;)V"),
+            MemberRef::new("foo/Baz", "onCreate", "(Landroid/os/Bundle;)V"),
+            MemberRef::new("foo/Bar", "TAG", "Ljava/lang/String;"),
+        ];
+        let items: Vec<StrRef> = members.iter().map(member_search_key).collect();
+        assert_eq!(items[2].as_ref(), "Lfoo/Bar;->TAG:Ljava/lang/String;");
+        let mut model = FuzzyMatchModel::new_for_members("".into(), &items, 10);
+
+        let result = model.search_with_new_input("onCreate".into());
+        assert_eq!(result.items.len(), 2);
+        // the exact class comes first, other classes may still match fuzzily.
+        let result = model.search_with_new_input("Bar.onCreate".into());
+        assert_eq!(result.items[0].index, 0);
+        let result = model.search_with_new_input("foo/Bar TAG".into());
+        assert_eq!(result.items[0].index, 2);
+    }
+
     #[test]
     fn test_huge_input() {
         let sample_size = 100_000;
@@ -88,8 +88,9 @@ impl AsmServer {
         (*top_mut).loading_state = current_loading_state.clone();
     }
 
-    fn render_to_app(&self, app: AppContainer) {
-        let classes = self.read_classes();
+    pub(crate) fn render_to_app(&self, app: AppContainer) {
+        let mut classes = self.read_classes();
+        classes.extend(self.read_resources());
         let start = Instant::now();
         let dir_info = DirInfo::from_classes(&classes);
         info!("resolve dir info cost: {:?}", start.elapsed());
@@ -170,6 +171,45 @@ mod tests {
         assert!(accessor.peek_source("second.dex").is_some());
     }
 
+    #[test]
+    fn mapping_shows_original_class_names() {
+        use java_asm::mapping::MappingTree;
+        use java_asm::smali::SmaliToken;
+
+        let (sender, _receiver) = mpsc::channel(16);
+        let accessor = Arc::new(Mutex::new(None));
+        let bytes = include_bytes!("../../../asm/tests/res/dex/classes14.dex").to_vec();
+        futures::executor::block_on(AsmServer::read_files(
+            vec![("classes14.dex".to_owned(), bytes)], sender, accessor.clone(),
+        )).unwrap();
+
+        let mut accessor = accessor.lock();
+        let Some(AccessorEnum::Dex(accessor)) = accessor.as_mut() else {
+            panic!("single DEX was not loaded");
+        };
+        let obfuscated_key = accessor.read_classes().into_iter()
+            .find(|key| !key.rsplit('/').next().unwrap().contains('$'))
+            .unwrap();
+        let obfuscated_name = obfuscated_key[1..obfuscated_key.len() - 1].replace('/', ".");
+        let mapping = MappingTree::parse(&format!("demo.Original -> {obfuscated_name}:\n"))
+            .and_then(|tree| tree.deobfuscator())
+            .unwrap();
+        accessor.set_mapping(Some(Arc::new(mapping)));
+
+        let classes = accessor.read_classes();
+        assert!(classes.iter().any(|key| &**key == "Ldemo/Original;"));
+        assert!(!classes.contains(&obfuscated_key));
+        assert!(accessor.exist_class("Ldemo/Original;"));
+        let content = accessor.read_content("Ldemo/Original;").unwrap();
+        let mut tokens = content.content.iter()
+            .chain(content.children.iter().flat_map(|child| child.content.iter()));
+        assert!(tokens.any(|token| matches!(token, SmaliToken::Descriptor(desc) if &**desc == "Ldemo/Original;")));
+
+        accessor.set_mapping(None);
+        assert!(accessor.read_classes().contains(&obfuscated_key));
+        assert!(!accessor.exist_class("Ldemo/Original;"));
+    }
+
     #[cfg(not(target_family = "wasm"))]
     #[test]
     fn read_single_apk_prefixes_entry_names() {
@@ -197,6 +237,114 @@ mod tests {
         assert!(accessor.peek_source("classes14.dex").is_none());
     }
 
+    #[test]
+    fn read_vdex_extracts_dex_files() {
+        let u32s = |values: &[u32]| values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+        let dex = include_bytes!("../../../asm/tests/res/dex/classes14.dex");
+        // a compact dex header, which is skipped
+        let mut compact = b"cdex001\0".to_vec();
+        compact.resize(32, 0);
+        compact.extend(u32s(&[0x88, 0x88, 0x12345678]));
+        compact.resize(0x88, 0);
+        let mut dex_section = dex.to_vec();
+        dex_section.extend(&compact);
+        dex_section.extend(dex);
+        // VDEX 027 with only the dex section
+        let mut vdex = b"vdex027\0".to_vec();
+        vdex.extend(u32s(&[1, 1, 24, dex_section.len() as u32]));
+        vdex.extend(dex_section);
+        let (sender, _receiver) = mpsc::channel(16);
+        let accessor = Arc::new(Mutex::new(None));
+
+        futures::executor::block_on(AsmServer::read_files(
+            vec![("base.vdex".to_owned(), vdex)], sender, accessor.clone(),
+        )).unwrap();
+
+        let accessor = accessor.lock();
+        let Some(AccessorEnum::Dex(accessor)) = accessor.as_ref() else {
+            panic!("VDEX was not loaded");
+        };
+        let mut sources: Vec<_> = accessor.dex_sources.keys().map(|source| source.to_string()).collect();
+        sources.sort();
+        assert_eq!(sources, ["base.vdex!classes.dex", "base.vdex!classes3.dex"]);
+        assert!(!accessor.read_classes().is_empty());
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn read_apk_decodes_resources() {
+        use std::io::{Cursor, Write};
+        use zip::write::SimpleFileOptions;
+        use zip::ZipWriter;
+
+        fn chunk(chunk_type: u16, header: &[u32], body: &[u8]) -> Vec<u8> {
+            let header_size = 8 + header.len() * 4;
+            let mut bytes = chunk_type.to_le_bytes().to_vec();
+            bytes.extend_from_slice(&(header_size as u16).to_le_bytes());
+            bytes.extend_from_slice(&((header_size + body.len()) as u32).to_le_bytes());
+            bytes.extend(header.iter().flat_map(|value| value.to_le_bytes()));
+            bytes.extend_from_slice(body);
+            bytes
+        }
+        // <manifest package="com.example"/> with utf-8 strings
+        let strings: &[u8] = b"\x08\x08manifest\0\x07\x07package\0\x0b\x0bcom.example\0\0\0";
+        let mut pool_body: Vec<u8> = [0u32, 11, 21].iter().flat_map(|value| value.to_le_bytes()).collect();
+        pool_body.extend_from_slice(strings);
+        let mut body = chunk(0x0001, &[3, 0, 0x100, 40, 0], &pool_body);
+        let mut element: Vec<u8> = [u32::MAX, 0].iter().flat_map(|value| value.to_le_bytes()).collect();
+        element.extend([20u16, 20, 1, 0, 0, 0].iter().flat_map(|value| value.to_le_bytes()));
+        element.extend([u32::MAX, 1, 2, 0x0300_0008, 2].iter().flat_map(|value| value.to_le_bytes()));
+        body.extend(chunk(0x0102, &[1, u32::MAX], &element));
+        body.extend(chunk(0x0103, &[1, u32::MAX, u32::MAX, 0], &[]));
+        let manifest = chunk(0x0003, &[], &body);
+        // package 0x7f with string/app_name = "Example"
+        let utf8_pool = |data: &[u8]| {
+            let mut body = 0u32.to_le_bytes().to_vec();
+            body.extend_from_slice(data);
+            chunk(0x0001, &[1, 0, 0x100, 32, 0], &body)
+        };
+        let type_strings = utf8_pool(b"\x06\x06string\0\0\0\0");
+        let mut package_header = vec![0x7f];
+        package_header.extend([0; 64]);
+        package_header.extend([288, 1, 288 + type_strings.len() as u32, 1, 0]);
+        let mut package_body = type_strings;
+        package_body.extend(utf8_pool(b"\x08\x08app_name\0\0"));
+        let mut type_header = vec![1, 1, 88, 64];
+        type_header.extend([0; 15]);
+        let entry = [0, 8, 0, 0x0300_0008, 0];
+        package_body.extend(chunk(0x0201, &type_header, &entry.iter().flat_map(|value: &u32| value.to_le_bytes()).collect::<Vec<_>>()));
+        let mut table_body = utf8_pool(b"\x07\x07Example\0\0\0");
+        table_body.extend(chunk(0x0200, &package_header, &package_body));
+        let table = chunk(0x0002, &[1], &table_body);
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("AndroidManifest.xml", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&manifest).unwrap();
+        writer.start_file("resources.arsc", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&table).unwrap();
+        writer.start_file("classes14.dex", SimpleFileOptions::default()).unwrap();
+        writer.write_all(include_bytes!("../../../asm/tests/res/dex/classes14.dex")).unwrap();
+        let apk = writer.finish().unwrap().into_inner();
+        let (sender, _receiver) = mpsc::channel(16);
+        let accessor = Arc::new(Mutex::new(None));
+
+        futures::executor::block_on(AsmServer::read_files(
+            vec![("sample.apk".to_owned(), apk)], sender, accessor.clone(),
+        )).unwrap();
+
+        let accessor = accessor.lock();
+        let Some(accessor) = accessor.as_ref() else { panic!("APK was not loaded") };
+        let mut resources = accessor.read_resources();
+        resources.sort();
+        assert_eq!(resources, vec!["sample.apk!AndroidManifest.xml".into(), "sample.apk!resources.arsc/string".into()]);
+        let content = accessor.read_content("sample.apk!AndroidManifest.xml").unwrap().render(0);
+        assert!(content.contains("<!-- package com.example -->"));
+        assert!(content.contains("<manifest package=\"com.example\"/>"));
+        let strings = accessor.read_content("sample.apk!resources.arsc/string").unwrap().render(0);
+        assert!(strings.contains("0x7f010000 @string/app_name"), "{strings}");
+        assert!(strings.contains("[default] \"Example\""), "{strings}");
+    }
+
     #[cfg(not(target_family = "wasm"))]
     #[test]
     fn read_apks_nested_apk() {
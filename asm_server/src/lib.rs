@@ -1,7 +1,11 @@
 use crate::impls::apk_load::DexAccessor;
 use crate::impls::fuzzy::FuzzyMatchModel;
 use enum_dispatch::enum_dispatch;
+use java_asm::hierarchy::ClassHierarchy;
+use java_asm::mapping::TreeRemapper;
+use java_asm::node::element::ClassNode;
 use java_asm::smali::SmaliNode;
+use java_asm::xref::{MemberRef, XrefIndex};
 use java_asm::{DescriptorRef, StrRef};
 use parking_lot::Mutex;
 use std::sync::Arc;
@@ -11,7 +15,7 @@ pub mod server;
 pub(crate) mod impls;
 pub(crate) mod targets;
 pub mod fuzzy {
-    pub use crate::impls::fuzzy::{FuzzyMatchModel, SearchResult, SearchResultItem};
+    pub use crate::impls::fuzzy::{member_search_key, FuzzyMatchModel, SearchResult, SearchResultItem};
 }
 pub use targets::{Duration, Instant, SystemTime};
 pub mod ui;
@@ -25,6 +29,9 @@ pub struct AsmServer {
     pub accessor: AccessorMut,
     classes: ArcVarOpt<Vec<StrRef>>,
     fuzzy: ArcVarOpt<FuzzyMatchModel>,
+    members: ArcVarOpt<Vec<MemberRef>>,
+    member_fuzzy: ArcVarOpt<FuzzyMatchModel>,
+    xref: ArcVarOpt<XrefIndex>,
 }
 
 pub type ArcVarOpt<T> = Arc<Mutex<Option<T>>>;
@@ -55,9 +62,31 @@ pub trait Accessor {
     /// return true if the class exists.
     /// the format of class_key is [DescriptorRef]
     fn exist_class(&self, class_key: &str) -> bool;
+
+    /// keys of decoded resource files like `app.apk!AndroidManifest.xml`, which are shown in
+    /// the file tree and opened by [Accessor::read_content] like classes.
+    fn read_resources(&self) -> Vec<StrRef>;
+
     fn read_content(&self, class_key: &str) -> Option<SmaliNode>;
 
+    /// methods and fields declared in all classes.
+    fn read_members(&self) -> Vec<MemberRef>;
+
+    /// super types and sub types of all classes in this accessor.
+    fn hierarchy(&self) -> &ClassHierarchy;
+
+    /// references between all classes in this accessor, it's expensive so callers should cache it.
+    fn build_xref(&self) -> XrefIndex;
+
     fn peek_source(&self, source_key: &str) -> Option<ExportableSource>;
+
+    /// all classes converted to class nodes with the names in the input, for comparing
+    /// with another input. It's expensive so callers should only call it when needed.
+    fn class_nodes(&self) -> Vec<ClassNode>;
+
+    /// Shows original names of an obfuscated input with `mapping`, class keys, members,
+    /// contents and references are all based on original names after that.
+    fn set_mapping(&mut self, mapping: Option<Arc<TreeRemapper>>);
 }
 
 pub struct ExportableSource {
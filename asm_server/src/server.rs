@@ -1,15 +1,21 @@
 use crate::impls::fuzzy::FuzzyMatchModel;
 use crate::impls::server::{FileOpenContext, ServerMessage};
-use crate::targets::{schedule_task, Instant};
+use crate::targets::{schedule_task, yield_step, Instant};
 use crate::rw_access::{ReadAccess, ReadError, WriteAccess};
-use crate::ui::{AppContainer, Content, DirInfo, Left, SmaliLine, Tab, Top};
+use crate::ui::search::search_lines;
+use crate::ui::{AppContainer, Comparison, Content, DirInfo, GlobalSearch, Left, SmaliLine, Tab, ToastKind, Top, Usages};
 use crate::{Accessor, AccessorEnum, ArcVarOpt, AsmServer, ExportableSource, LoadingState, ServerMut};
+use java_asm::diff::ClassSetDiff;
+use java_asm::mapping::{MappingTree, TreeRemapper};
 use java_asm::smali::SmaliNode;
+use crate::impls::fuzzy::member_search_key;
+use java_asm::xref::{MemberRef, Xref, XrefIndex, XrefTarget};
 use java_asm::{AsmErr, StrRef};
 use log::{error, info};
 use std::fmt::{Display, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use zip::result::ZipError;
 
 impl AsmServer {
@@ -24,6 +30,9 @@ impl AsmServer {
             accessor: Default::default(),
             classes: Default::default(),
             fuzzy: Default::default(),
+            members: Default::default(),
+            member_fuzzy: Default::default(),
+            xref: Default::default(),
         }
     }
 
@@ -53,6 +62,39 @@ impl AsmServer {
         &self.fuzzy
     }
 
+    pub fn get_members(&self) -> &ArcVarOpt<Vec<MemberRef>> {
+        let mut current = self.members.lock();
+        if current.is_some() { return &self.members; }
+        let accessor_locked = self.accessor.lock();
+        let Some(accessor) = accessor_locked.deref() else { return &self.members; };
+        current.replace(accessor.read_members());
+        &self.members
+    }
+
+    fn get_or_create_member_fuzzy(&self, input: StrRef) -> &ArcVarOpt<FuzzyMatchModel> {
+        let mut current = self.member_fuzzy.lock();
+        if current.is_some() { return &self.member_fuzzy; }
+        let load_start = Instant::now();
+        let members_locked = self.get_members().lock();
+        let Some(members) = members_locked.deref() else { return &self.member_fuzzy; };
+        let keys: Vec<StrRef> = members.iter().map(member_search_key).collect();
+        current.replace(FuzzyMatchModel::new_for_members(input, &keys, 30));
+        info!("{} members indexed in {:?}", keys.len(), load_start.elapsed());
+        &self.member_fuzzy
+    }
+
+    fn get_or_create_xref(&self) -> &ArcVarOpt<XrefIndex> {
+        let mut current = self.xref.lock();
+        if current.is_some() { return &self.xref; }
+        let accessor_locked = self.accessor.lock();
+        let Some(accessor) = accessor_locked.deref() else { return &self.xref; };
+        let start = Instant::now();
+        let xref = accessor.build_xref();
+        info!("{} references indexed in {:?}", xref.len(), start.elapsed());
+        current.replace(xref);
+        &self.xref
+    }
+
     pub fn smart_open(server: ServerMut, read_access: ReadAccess, render_target: AppContainer) {
         Self::smart_open_many(server, vec![read_access], render_target);
     }
@@ -172,6 +214,7 @@ impl AsmServer {
             exported_content,
             find: Default::default(),
             scroll_offset: 0.0,
+            reveal_line: None,
         };
         let current = content.opened_tabs.len();
         content.opened_tabs.push(current_tab);
@@ -180,6 +223,14 @@ impl AsmServer {
         top.file_path = file_key.to_string();
     }
 
+    pub fn reveal_line(&self, file_key: &str, line: usize, render_target: &AppContainer) {
+        let mut content = render_target.content().lock();
+        let tab = content.opened_tabs.iter_mut().find(|tab| *tab.file_key == *file_key);
+        if let Some(tab) = tab {
+            tab.reveal_line = Some(line);
+        }
+    }
+
     // switch left side file tree to correct place.
     fn switch_file_tree(&self, left: &mut Left, file_key: &str) {
         let root_node = &mut left.root_node;
@@ -197,14 +248,93 @@ impl AsmServer {
         }
     }
 
+    pub fn show_usages(&self, target: XrefTarget, render_target: &AppContainer) {
+        let results = self.find_usages(&target);
+        *render_target.usages().lock() = Usages { target: Some(target), results, reveal: true };
+    }
+
+    /// Starts searching all classes with the query in [AppContainer::global_search] in background,
+    /// a running search is cancelled.
+    pub fn start_global_search(&self, render_target: &AppContainer) {
+        let mut classes = self.get_classes().lock().clone().unwrap_or_default();
+        classes.sort();
+        let (query, case_sensitive, scope, generation) = {
+            let mut search = render_target.global_search().lock();
+            search.generation += 1;
+            search.results.clear();
+            search.truncated = false;
+            search.searched = 0;
+            search.total = classes.len();
+            search.in_progress = !search.query.is_empty();
+            if !search.in_progress { return; }
+            (search.query.clone(), search.case_sensitive, search.scope, search.generation)
+        };
+        let accessor = self.accessor.clone();
+        let render_target = render_target.clone();
+        schedule_task(async move {
+            const CLASSES_PER_STEP: usize = 32;
+            let start = Instant::now();
+            for chunk in classes.chunks(CLASSES_PER_STEP) {
+                let mut matches = Vec::new();
+                {
+                    let accessor_locked = accessor.lock();
+                    let Some(accessor) = accessor_locked.deref() else { return; };
+                    for class_key in chunk {
+                        let Some(smali) = accessor.read_content(class_key) else { continue; };
+                        let lines = SmaliLine::from_node(&smali);
+                        matches.extend(search_lines(class_key, &lines, &query, case_sensitive, scope));
+                    }
+                }
+                let truncated = {
+                    let mut search = render_target.global_search().lock();
+                    if search.generation != generation { return; }
+                    search.searched += chunk.len();
+                    let remaining = GlobalSearch::MAX_RESULTS - search.results.len();
+                    if matches.len() > remaining {
+                        matches.truncate(remaining);
+                        search.truncated = true;
+                    }
+                    search.results.extend(matches);
+                    search.truncated
+                };
+                if truncated { break; }
+                yield_step().await;
+            }
+            let mut search = render_target.global_search().lock();
+            if search.generation != generation { return; }
+            search.in_progress = false;
+            info!("searched {} classes for `{query}` in {:?}", search.searched, start.elapsed());
+        });
+    }
+
     pub fn search(&self, top: &mut Top) {
         let query = &top.file_path;
         let query: StrRef = query.as_str().into();
         if query.len() > 255 { return; }
         let mut fuzzy_locked = self.get_or_create_fuzzy(query.clone()).lock();
         let Some(fuzzy) = fuzzy_locked.deref_mut() else { return; };
-        let search_result = fuzzy.search_with_new_input(query);
+        let search_result = fuzzy.search_with_new_input(query.clone());
         top.search_result = search_result;
+        drop(fuzzy_locked);
+
+        let mut member_fuzzy_locked = self.get_or_create_member_fuzzy(query.clone()).lock();
+        let Some(member_fuzzy) = member_fuzzy_locked.deref_mut() else { return; };
+        let members_locked = self.members.lock();
+        let Some(members) = members_locked.deref() else { return; };
+        top.member_search_result = member_fuzzy.search_with_new_input(query).items.into_iter()
+            .filter_map(|item| Some((members.get(item.index)?.clone(), item)))
+            .collect();
+    }
+
+    /// Opens the class of `member` and scrolls to its declaration.
+    pub fn open_member(&self, member: &MemberRef, render_target: &AppContainer) {
+        let class_key = format!("L{};", member.owner);
+        self.switch_or_open(&class_key, render_target);
+        let mut content = render_target.content().lock();
+        let tab = content.opened_tabs.iter_mut().find(|tab| *tab.file_key == *class_key);
+        let Some(tab) = tab else { return; };
+        tab.reveal_line = tab.rendered_lines.iter()
+            .position(|line| line.declares(&member.name, &member.desc));
     }
 }
 
@@ -216,7 +346,7 @@ impl AsmServer {
             let dialog = rfd::AsyncFileDialog::new()
                 .add_filter(
                     "Android packages / DEX",
-                    &["apk", "apks", "xapk", "aab", "zip", "dex"],
+                    &["apk", "apks", "xapk", "aab", "zip", "dex", "vdex", "odex", "oat"],
                 );
             let read_accesses = ReadAccess::new_multiple(dialog).await;
             let Some(read_accesses) = read_accesses else { return; };
@@ -224,6 +354,117 @@ impl AsmServer {
         });
     }
 
+    /// Picks a mapping file, e.g. a ProGuard / R8 `mapping.txt` or a Tiny file, and shows
+    /// the names of its last namespace in the opened file with it.
+    pub fn dialog_to_open_mapping(&self, render_target: AppContainer) {
+        let server = self.clone();
+        schedule_task(async move {
+            let dialog = rfd::AsyncFileDialog::new()
+                .add_filter("Mapping", &["txt", "map", "tiny", "srg", "tsrg", "mapping", "mappings"]);
+            let Some(read_access) = ReadAccess::new(dialog).await else { return; };
+            let file_name = read_access.name();
+            let content = match read_access.read().await {
+                Ok(content) => content,
+                Err(error) => {
+                    render_target.push_toast(ToastKind::Error, format!("Failed to read `{file_name}`: {error}"));
+                    return;
+                }
+            };
+            let mapping = MappingTree::parse(&String::from_utf8_lossy(&content))
+                .and_then(|tree| Ok((tree.classes.len(), tree.deobfuscator()?)));
+            match mapping {
+                Ok((class_count, mapping)) => {
+                    let message = format!("{class_count} classes mapped by `{file_name}`");
+                    server.set_mapping(Some(Arc::new(mapping)), &render_target);
+                    render_target.push_toast(ToastKind::Success, message);
+                }
+                Err(error) => {
+                    let message = format!("Failed to load mapping `{file_name}`: {}", OpenFileError::ResolveError(error));
+                    error!("{message}");
+                    render_target.push_toast(ToastKind::Error, message);
+                }
+            }
+        });
+    }
+
+    /// Picks other inputs, e.g. another build of the opened app, and compares their classes
+    /// with the opened ones in background. The result is kept in [AppContainer::comparison].
+    pub fn dialog_to_compare(&self, render_target: AppContainer) {
+        let accessor = self.accessor.clone();
+        schedule_task(async move {
+            let dialog = rfd::AsyncFileDialog::new()
+                .add_filter(
+                    "Android packages / DEX",
+                    &["apk", "apks", "xapk", "aab", "zip", "dex", "vdex", "odex", "oat"],
+                );
+            let Some(read_accesses) = ReadAccess::new_multiple(dialog).await else { return; };
+            let Some(first_access) = read_accesses.first() else { return; };
+            let file_name = if read_accesses.len() == 1 {
+                first_access.name()
+            } else {
+                format!("{} files", read_accesses.len())
+            };
+            *render_target.comparison().lock() = Comparison {
+                file_name: file_name.clone(), in_progress: true, reveal: true, ..Default::default()
+            };
+            let result = Self::compare_with(accessor, read_accesses, &render_target).await;
+            render_target.top().lock().loading_state.in_loading = false;
+            let mut comparison = render_target.comparison().lock();
+            comparison.in_progress = false;
+            match result {
+                Ok(diff) => {
+                    let message = format!(
+                        "Compared with `{file_name}`: {} added, {} removed, {} changed classes",
+                        diff.added.len(), diff.removed.len(), diff.changed.len(),
+                    );
+                    comparison.diff = Some(Arc::new(diff));
+                    render_target.push_toast(ToastKind::Success, message);
+                }
+                Err(error) => {
+                    let message = format!("Failed to compare with `{file_name}`: {error}");
+                    error!("{message}");
+                    render_target.push_toast(ToastKind::Error, message);
+                }
+            }
+        });
+    }
+
+    async fn compare_with(
+        accessor: ArcVarOpt<AccessorEnum>, read_accesses: Vec<ReadAccess>, render_target: &AppContainer,
+    ) -> Result<ClassSetDiff, OpenFileError> {
+        let mut inputs = Vec::with_capacity(read_accesses.len());
+        for read_access in read_accesses {
+            let file_name = read_access.name();
+            let content = read_access.read().await.map_err(OpenFileError::ReadError)?;
+            inputs.push((file_name, content.to_vec()));
+        }
+        // the loading progress of the other input is shown in the top bar.
+        let (sender, mut receiver) = mpsc::channel::<ServerMessage>(50);
+        let progress_target = render_target.clone();
+        schedule_task(async move {
+            while let Some(message) = receiver.recv().await {
+                let ServerMessage::Progress(progress) = message else { continue; };
+                let mut top = progress_target.top().lock();
+                top.loading_state.loading_progress = progress.progress;
+                top.loading_state.in_loading = progress.in_loading;
+                top.loading_state.loading_message = progress.message;
+            }
+        });
+        let other = crate::targets::read_dex_inputs(inputs, sender).await?;
+        yield_step().await;
+        let old_nodes = {
+            let accessor_locked = accessor.lock();
+            let Some(accessor) = accessor_locked.deref() else {
+                return Err(OpenFileError::Custom("no input is opened".to_owned()));
+            };
+            accessor.class_nodes()
+        };
+        yield_step().await;
+        let new_nodes = other.class_nodes();
+        yield_step().await;
+        ClassSetDiff::new(&old_nodes, &new_nodes).map_err(OpenFileError::ResolveError)
+    }
+
     pub fn dialog_to_save_file(&self, source_key: &str) {
         let accessor_locked = self.accessor.lock();
         let Some(accessor) = accessor_locked.deref() else { return; };
@@ -291,6 +532,14 @@ impl AsmServer {
         }
     }
 
+    /// see also: [Accessor::read_resources]
+    pub fn read_resources(&self) -> Vec<StrRef> {
+        match self.accessor.lock().deref() {
+            None => Vec::new(),
+            Some(accessor) => accessor.read_resources(),
+        }
+    }
+
     /// see also: [AccessorEnum::exist_class]
     pub fn find_class(&self, class_key: &str) -> bool {
         let accessor_locked = self.accessor.lock();
@@ -309,6 +558,36 @@ impl AsmServer {
             Some(accessor) => accessor.read_content(class_key),
         }
     }
+
+    /// Applies or removes a mapping of original names, caches based on names are rebuilt,
+    /// opened tabs are closed and the file tree is rendered again.
+    pub fn set_mapping(&self, mapping: Option<Arc<TreeRemapper>>, render_target: &AppContainer) {
+        {
+            let mut accessor_locked = self.accessor.lock();
+            let Some(accessor) = accessor_locked.deref_mut() else { return; };
+            accessor.set_mapping(mapping);
+        }
+        *self.classes.lock() = None;
+        *self.fuzzy.lock() = None;
+        *self.members.lock() = None;
+        *self.member_fuzzy.lock() = None;
+        *self.xref.lock() = None;
+        *render_target.content().lock() = Content::default();
+        *render_target.usages().lock() = Usages::default();
+        self.render_to_app(render_target.clone());
+    }
+
+    /// All references to `target` from the code of loaded classes.
+    /// The index is built at the first call.
+    pub fn find_usages(&self, target: &XrefTarget) -> Vec<Xref> {
+        let xref_locked = self.get_or_create_xref().lock();
+        let Some(xref) = xref_locked.deref() else { return Vec::new(); };
+        let usages = match target {
+            XrefTarget::Class(name) => xref.class_users(name),
+            _ => xref.usages(target),
+        };
+        usages.into_iter().cloned().collect()
+    }
 }
 
 
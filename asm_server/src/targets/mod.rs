@@ -6,9 +6,9 @@ mod wasm;
 mod native;
 
 #[cfg(target_family = "wasm")]
-pub(crate) use wasm::{file_handle_path, read_dex_inputs, reveal_parent, schedule_task, spawn_process_dex};
+pub(crate) use wasm::{file_handle_path, read_dex_inputs, reveal_parent, schedule_task, spawn_process_dex, yield_step};
 #[cfg(not(target_family = "wasm"))]
-pub(crate) use native::{file_handle_path, read_dex_inputs, reveal_parent, schedule_task, spawn_process_dex};
+pub(crate) use native::{file_handle_path, read_dex_inputs, reveal_parent, schedule_task, spawn_process_dex, yield_step};
 
 #[cfg(target_family = "wasm")]
 pub use wasm::{Instant, SystemTime};
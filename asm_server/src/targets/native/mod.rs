@@ -1,6 +1,6 @@
 mod runtime;
 
-pub(crate) use runtime::{file_handle_path, reveal_parent, schedule_task};
+pub(crate) use runtime::{file_handle_path, reveal_parent, schedule_task, yield_step};
 pub use runtime::{Instant, SystemTime};
 
 use crate::impls::apk_load::{
@@ -16,7 +16,7 @@ pub(crate) fn file_handle_path(file_handle: &rfd::FileHandle) -> String {
 
 pub(crate) fn reveal_parent(_path: &Path) {}
 
-pub(super) async fn yield_to_browser() {
+pub(crate) async fn yield_to_browser() {
     let promise = Promise::new(&mut |resolve, _reject| {
         let window = web_sys::window().expect("No window");
         window
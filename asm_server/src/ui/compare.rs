@@ -0,0 +1,162 @@
+use java_asm::diff::{ClassChange, ClassDiff, ClassSetDiff, DiffKind, MemberChange, MemberDiff};
+use java_asm::{ConstContainer, JavaClassAccessFlags, JavaFieldAccessFlags, JavaMethodAccessFlags, StrRef};
+use std::sync::Arc;
+
+/// State of the comparison between the opened input and another input.
+/// The comparison runs in background, [Comparison::diff] is set when it is done.
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    /// name of the other input.
+    pub file_name: String,
+    pub in_progress: bool,
+    pub diff: Option<Arc<ClassSetDiff>>,
+    /// index of the class in [ClassSetDiff::changed] which is shown side by side.
+    pub selected: Option<usize>,
+    // true if the comparison is not shown yet.
+    pub reveal: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffRowKind {
+    /// the declaration of a field or method, or the class itself.
+    Header,
+    Same,
+    Changed,
+}
+
+/// A row of the side-by-side view, the old side is [None] for added lines and the new
+/// side is [None] for removed lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffRow {
+    pub kind: DiffRowKind,
+    pub old: Option<StrRef>,
+    pub new: Option<StrRef>,
+}
+
+impl DiffRow {
+    fn new(kind: DiffRowKind, old: Option<String>, new: Option<String>) -> DiffRow {
+        DiffRow { kind, old: old.map(StrRef::from), new: new.map(StrRef::from) }
+    }
+}
+
+impl Comparison {
+    /// Rows of the selected class, empty if no class is selected.
+    pub fn selected_rows(&self) -> Vec<DiffRow> {
+        let class = self.diff.as_ref()
+            .zip(self.selected)
+            .and_then(|(diff, selected)| diff.changed.get(selected));
+        class.map(side_by_side).unwrap_or_default()
+    }
+}
+
+fn flag_names(flags: u16, const_name: fn(u16) -> Option<&'static str>) -> String {
+    let names: Vec<_> = (0..u16::BITS)
+        .map(|bit| 1u16 << bit)
+        .filter(|flag| flags & flag != 0)
+        .filter_map(const_name)
+        .map(|name| name.strip_prefix("acc_").unwrap_or(name))
+        .collect();
+    format!("access [{}]", names.join(" "))
+}
+
+fn optional(label: &str, value: &Option<StrRef>) -> String {
+    format!("{label} {}", value.as_deref().unwrap_or("-"))
+}
+
+/// Class level changes, then fields and methods, each member starts with a [DiffRowKind::Header].
+pub fn side_by_side(class: &ClassDiff) -> Vec<DiffRow> {
+    let mut rows = vec![DiffRow::new(
+        DiffRowKind::Header, Some(format!(".class {}", class.name)), Some(format!(".class {}", class.name)),
+    )];
+    for change in &class.changes {
+        let (old, new) = match change {
+            ClassChange::Version { old, new } => {
+                (format!("version {}.{}", old.0, old.1), format!("version {}.{}", new.0, new.1))
+            }
+            ClassChange::Access { old, new } => (
+                flag_names(*old, JavaClassAccessFlags::const_name),
+                flag_names(*new, JavaClassAccessFlags::const_name),
+            ),
+            ClassChange::SuperName { old, new } => (optional(".super", old), optional(".super", new)),
+            ClassChange::Interfaces { added, removed } => {
+                rows.extend(removed.iter().map(|name| DiffRow::new(DiffRowKind::Changed, Some(format!(".implements {name}")), None)));
+                rows.extend(added.iter().map(|name| DiffRow::new(DiffRowKind::Changed, None, Some(format!(".implements {name}")))));
+                continue;
+            }
+            ClassChange::Signature { old, new } => (optional(".signature", old), optional(".signature", new)),
+        };
+        rows.push(DiffRow::new(DiffRowKind::Changed, Some(old), Some(new)));
+    }
+    for field in &class.fields {
+        member_rows(&mut rows, ".field", field, JavaFieldAccessFlags::const_name);
+    }
+    for method in &class.methods {
+        member_rows(&mut rows, ".method", method, JavaMethodAccessFlags::const_name);
+    }
+    rows
+}
+
+fn member_rows(
+    rows: &mut Vec<DiffRow>, directive: &str, member: &MemberDiff,
+    const_name: fn(u16) -> Option<&'static str>,
+) {
+    let declaration = Some(format!("{directive} {}{}", member.name, member.desc));
+    let (old, new) = match member.kind {
+        DiffKind::Added => (None, declaration),
+        DiffKind::Removed => (declaration, None),
+        DiffKind::Changed => (declaration.clone(), declaration),
+    };
+    rows.push(DiffRow::new(DiffRowKind::Header, old, new));
+    for change in &member.changes {
+        let (old, new) = match change {
+            MemberChange::Access { old, new } => (flag_names(*old, const_name), flag_names(*new, const_name)),
+            MemberChange::Signature { old, new } => (optional(".signature", old), optional(".signature", new)),
+            MemberChange::Value { old, new } => (optional("=", old), optional("=", new)),
+            MemberChange::Exceptions { old, new } => {
+                (format!(".throws {}", old.join(" ")), format!(".throws {}", new.join(" ")))
+            }
+            MemberChange::Code(code) => {
+                rows.extend(code.side_by_side().into_iter().map(|(old, new)| {
+                    let kind = if old == new { DiffRowKind::Same } else { DiffRowKind::Changed };
+                    DiffRow { kind, old, new }
+                }));
+                continue;
+            }
+        };
+        rows.push(DiffRow::new(DiffRowKind::Changed, Some(old), Some(new)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{side_by_side, DiffRow, DiffRowKind};
+    use java_asm::diff::{ClassDiff, CodeDiff, DiffKind, MemberChange, MemberDiff};
+    use java_asm::StrRef;
+
+    fn lines(values: &[&str]) -> Vec<StrRef> {
+        values.iter().map(|value| StrRef::from(*value)).collect()
+    }
+
+    #[test]
+    fn side_by_side_pairs_changed_lines() {
+        let code = CodeDiff::new(&lines(&["iconst_0", "ireturn"]), &lines(&["iconst_1", "ireturn"]));
+        let method = MemberDiff {
+            name: "get".into(), desc: "()I".into(), kind: DiffKind::Changed, access: 0x0001,
+            changes: vec![MemberChange::Access { old: 0x0001, new: 0x0011 }, MemberChange::Code(code)],
+        };
+        let added = MemberDiff {
+            name: "set".into(), desc: "(I)V".into(), kind: DiffKind::Added, access: 0x0001, changes: vec![],
+        };
+        let class = ClassDiff { name: "A".into(), changes: vec![], fields: vec![], methods: vec![method, added] };
+        let rows = side_by_side(&class);
+        let row = |kind, old: Option<&str>, new: Option<&str>| DiffRow { kind, old: old.map(StrRef::from), new: new.map(StrRef::from) };
+        assert_eq!(rows, vec![
+            row(DiffRowKind::Header, Some(".class A"), Some(".class A")),
+            row(DiffRowKind::Header, Some(".method get()I"), Some(".method get()I")),
+            row(DiffRowKind::Changed, Some("access [public]"), Some("access [public final]")),
+            row(DiffRowKind::Changed, Some("iconst_0"), Some("iconst_1")),
+            row(DiffRowKind::Same, Some("ireturn"), Some("ireturn")),
+            row(DiffRowKind::Header, None, Some(".method set(I)V")),
+        ]);
+    }
+}
@@ -71,7 +71,7 @@ impl FindState {
 
 }
 
-fn find_line_matches(
+pub(crate) fn find_line_matches(
     line: &str, query: &str, case_sensitive: bool,
 ) -> Vec<(usize, usize)> {
     if case_sensitive {
@@ -2,13 +2,18 @@ pub mod log;
 pub mod msg;
 pub mod font;
 pub mod find;
+pub mod search;
+pub mod compare;
 
 pub use find::{FindMatch, FindState};
-use crate::impls::fuzzy::SearchResult;
+pub use search::{GlobalMatch, GlobalSearch, SearchScope};
+pub use compare::{Comparison, DiffRow, DiffRowKind};
+use crate::impls::fuzzy::{SearchResult, SearchResultItem};
 use crate::ui::log::LogHolder;
 use crate::ui::AbsFile::{Dir, File};
 use crate::{AsmServer, Instant, LoadingState};
 use java_asm::smali::{SmaliNode, SmaliToken};
+use java_asm::xref::{MemberRef, Xref, XrefTarget};
 use java_asm::StrRef;
 use ::log::Level;
 use parking_lot::Mutex;
@@ -27,12 +32,22 @@ pub struct App {
     pub content: Arc<Mutex<Content>>,
     pub messages: Arc<Mutex<Vec<UIMessage>>>,
     pub toasts: Arc<Mutex<Vec<Toast>>>,
+    pub usages: Arc<Mutex<Usages>>,
+    pub global_search: Arc<Mutex<GlobalSearch>>,
+    pub comparison: Arc<Mutex<Comparison>>,
 }
 
 #[derive(Clone, Debug)]
 pub enum UIMessage {
     OpenFile(OpenFileMessage),
+    // open the file and scroll to the line.
+    OpenFileAt(OpenFileMessage, usize),
     CloseDir(StrRef),
+    FindUsages(XrefTarget),
+    // open the owner class and scroll to the declaration.
+    OpenMember(MemberRef),
+    // search with the query in [App::global_search].
+    GlobalSearch,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -53,6 +68,15 @@ pub struct OpenFileMessage {
     pub path: StrRef,
 }
 
+/// The result of the latest "find usages" query.
+#[derive(Default, Clone, Debug)]
+pub struct Usages {
+    pub target: Option<XrefTarget>,
+    pub results: Vec<Xref>,
+    // true if the results are not shown yet.
+    pub reveal: bool,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct AppContainer(Arc<App>);
 
@@ -71,6 +95,12 @@ impl AppContainer {
 
     pub fn toasts(&self) -> &Arc<Mutex<Vec<Toast>>> { &self.0.toasts }
 
+    pub fn usages(&self) -> &Arc<Mutex<Usages>> { &self.0.usages }
+
+    pub fn global_search(&self) -> &Arc<Mutex<GlobalSearch>> { &self.0.global_search }
+
+    pub fn comparison(&self) -> &Arc<Mutex<Comparison>> { &self.0.comparison }
+
     pub fn push_toast(&self, kind: ToastKind, message: impl Into<String>) {
         let mut toasts = self.0.toasts.lock();
         const MAX_TOASTS: usize = 200;
@@ -94,9 +124,22 @@ impl AppContainer {
                 UIMessage::OpenFile(message) => {
                     server.switch_or_open(&message.path, self);
                 }
+                UIMessage::OpenFileAt(message, line) => {
+                    server.switch_or_open(&message.path, self);
+                    server.reveal_line(&message.path, line, self);
+                }
                 UIMessage::CloseDir(path) => {
                     server.close_dir(&path, self);
                 }
+                UIMessage::FindUsages(target) => {
+                    server.show_usages(target, self);
+                }
+                UIMessage::OpenMember(member) => {
+                    server.open_member(&member, self);
+                }
+                UIMessage::GlobalSearch => {
+                    server.start_global_search(self);
+                }
             }
         }
     }
@@ -107,6 +150,8 @@ pub struct Top {
     pub loading_state: LoadingState,
     pub file_path: String,
     pub search_result: SearchResult,
+    // matched methods and fields, the indices in items are for [crate::fuzzy::member_search_key].
+    pub member_search_result: Vec<(MemberRef, SearchResultItem)>,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -279,6 +324,8 @@ pub struct Tab {
     pub exported_content: Arc<str>,
     pub find: FindState,
     pub scroll_offset: f32,
+    // the line to scroll to when this tab is rendered next time.
+    pub reveal_line: Option<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -321,6 +368,22 @@ impl SmaliLine {
             })
             .collect()
     }
+
+    /// Returns true if this line declares the field or method, e.g. `.method public run()V`.
+    pub fn declares(&self, name: &str, desc: &str) -> bool {
+        let mut tokens = self.tokens.iter()
+            .map(|token| &token.token)
+            .filter(|token| !matches!(token, SmaliToken::Raw(_)));
+        let mut previous_is_descriptor = false;
+        while let Some(token) = tokens.next() {
+            if let SmaliToken::MemberName(member_name) = token
+                && !previous_is_descriptor && **member_name == *name {
+                return matches!(tokens.next(), Some(SmaliToken::Descriptor(d)) if **d == *desc);
+            }
+            previous_is_descriptor = matches!(token, SmaliToken::Descriptor(_));
+        }
+        false
+    }
 }
 
 fn display_token_text(token: &SmaliToken) -> String {
@@ -0,0 +1,149 @@
+use crate::ui::find::find_line_matches;
+use crate::ui::SmaliLine;
+use java_asm::smali::SmaliToken;
+use java_asm::StrRef;
+
+/// Which part of the rendered smali is searched by [GlobalSearch].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    /// the whole text of every line.
+    #[default]
+    All,
+    /// string constants and other literals.
+    Literals,
+    /// names of methods and fields, both declarations and references.
+    Members,
+    /// type and method descriptors.
+    Descriptors,
+    /// lines with an opcode, including all of their operands.
+    Instructions,
+}
+
+impl SearchScope {
+    pub const ALL: [SearchScope; 5] = [
+        SearchScope::All, SearchScope::Literals, SearchScope::Members,
+        SearchScope::Descriptors, SearchScope::Instructions,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::All => "All",
+            SearchScope::Literals => "Strings / Literals",
+            SearchScope::Members => "Members",
+            SearchScope::Descriptors => "Descriptors",
+            SearchScope::Instructions => "Instructions",
+        }
+    }
+
+    fn token_matches(self, token: &SmaliToken) -> bool {
+        match self {
+            SearchScope::Literals => matches!(token, SmaliToken::Literal(_)),
+            SearchScope::Members => matches!(token, SmaliToken::MemberName(_)),
+            SearchScope::Descriptors => matches!(token, SmaliToken::Descriptor(_)),
+            SearchScope::All | SearchScope::Instructions => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlobalMatch {
+    pub class_key: StrRef,
+    pub line: usize,
+    // the text of the whole line.
+    pub text: String,
+    pub start_byte: usize,
+    pub end_byte: usize,
+}
+
+/// State of the search across all loaded classes.
+/// The search runs in background, results are appended while classes are searched.
+#[derive(Clone, Debug, Default)]
+pub struct GlobalSearch {
+    pub query: String,
+    pub case_sensitive: bool,
+    pub scope: SearchScope,
+    pub in_progress: bool,
+    // count of classes searched, and count of all classes.
+    pub searched: usize,
+    pub total: usize,
+    pub results: Vec<GlobalMatch>,
+    // true if the search stopped after [GlobalSearch::MAX_RESULTS] results.
+    pub truncated: bool,
+    // increased by each new search, an outdated search stops when it sees a different value.
+    pub(crate) generation: u64,
+}
+
+impl GlobalSearch {
+    pub const MAX_RESULTS: usize = 5000;
+
+    /// 0.0 ~ 1.0
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 { return 1.0; }
+        self.searched as f32 / self.total as f32
+    }
+}
+
+pub(crate) fn search_lines(
+    class_key: &StrRef, lines: &[SmaliLine], query: &str, case_sensitive: bool, scope: SearchScope,
+) -> Vec<GlobalMatch> {
+    let mut result = Vec::new();
+    if query.is_empty() { return result; }
+    for (line_index, line) in lines.iter().enumerate() {
+        let ranges: Vec<(usize, usize)> = match scope {
+            SearchScope::All => find_line_matches(&line.text, query, case_sensitive),
+            SearchScope::Instructions => {
+                let is_insn = line.tokens.iter().any(|token| matches!(token.token, SmaliToken::Op(_)));
+                if !is_insn { continue; }
+                find_line_matches(&line.text, query, case_sensitive)
+            }
+            _ => line.tokens.iter()
+                .filter(|token| scope.token_matches(&token.token))
+                .flat_map(|token| {
+                    find_line_matches(&token.text, query, case_sensitive).into_iter()
+                        .map(|(start, end)| (token.start_byte + start, token.start_byte + end))
+                })
+                .collect(),
+        };
+        result.extend(ranges.into_iter().map(|(start_byte, end_byte)| GlobalMatch {
+            class_key: class_key.clone(),
+            line: line_index,
+            text: line.text.clone(),
+            start_byte,
+            end_byte,
+        }));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search_lines, SearchScope};
+    use crate::ui::SmaliLine;
+    use java_asm::smali::{stb, SmaliNode};
+    use java_asm::StrRef;
+
+    fn lines() -> Vec<SmaliLine> {
+        let mut node = stb().raw(".method").mn("run".into()).d("()V".into()).s();
+        node.add_child(stb().op("const-string").v(0u16).l("run fast".into()).s());
+        node.add_child(stb().op("invoke-static").d("La/B;".into()).mn("run".into()).d("()V".into()).s());
+        SmaliLine::from_node(&SmaliNode { end_tag: Some(".end method"), ..node })
+    }
+
+    #[test]
+    fn scopes_limit_searched_tokens() {
+        let key = StrRef::from("La/B;");
+        let lines = lines();
+        let search = |scope| search_lines(&key, &lines, "run", false, scope);
+
+        assert_eq!(search(SearchScope::All).len(), 3);
+        let members = search(SearchScope::Members);
+        assert_eq!(members.iter().map(|m| m.line).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(search(SearchScope::Literals).len(), 1);
+        assert_eq!(search(SearchScope::Instructions).len(), 2);
+        assert!(search(SearchScope::Descriptors).is_empty());
+
+        let literal = &search(SearchScope::Literals)[0];
+        assert_eq!(&literal.text[literal.start_byte..literal.end_byte], "run");
+        assert_eq!(literal.line, 1);
+    }
+}